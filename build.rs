@@ -0,0 +1,46 @@
+//! Embeds build provenance for `GET /api/version`.
+//!
+//! - `ZEROCLAW_GIT_COMMIT`: short commit hash. Taken from the environment when
+//!   set (e.g. Docker builds, where `.git` is excluded), else from `git`,
+//!   else "unknown".
+//! - `ZEROCLAW_BUILD_TIMESTAMP`: Unix seconds. Honours `SOURCE_DATE_EPOCH`
+//!   for reproducible builds.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ZEROCLAW_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs/heads");
+
+    let commit = std::env::var("ZEROCLAW_GIT_COMMIT")
+        .ok()
+        .filter(|c| !c.trim().is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ZEROCLAW_GIT_COMMIT={commit}");
+
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs())
+        });
+    println!("cargo:rustc-env=ZEROCLAW_BUILD_TIMESTAMP={timestamp}");
+}
@@ -1,6 +1,5 @@
 use anyhow::{bail, Context as _, Result};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 use zeroclaw::config::zeroclaw_home;
@@ -224,17 +223,23 @@ fn open_registry() -> Result<Registry> {
 
 fn run_start(name: &str) -> Result<()> {
     let registry = open_registry()?;
-    lifecycle::start_instance(&registry, name).map_err(|e| anyhow::anyhow!("{e}"))
+    let result = lifecycle::start_instance(&registry, name).map_err(|e| anyhow::anyhow!("{e}"));
+    lifecycle::webhooks::flush();
+    result
 }
 
 fn run_stop(name: &str) -> Result<()> {
     let registry = open_registry()?;
-    lifecycle::stop_instance(&registry, name).map_err(|e| anyhow::anyhow!("{e}"))
+    let result = lifecycle::stop_instance(&registry, name).map_err(|e| anyhow::anyhow!("{e}"));
+    lifecycle::webhooks::flush();
+    result
 }
 
 fn run_restart(name: &str) -> Result<()> {
     let registry = open_registry()?;
-    lifecycle::restart_instance(&registry, name).map_err(|e| anyhow::anyhow!("{e}"))
+    let result = lifecycle::restart_instance(&registry, name).map_err(|e| anyhow::anyhow!("{e}"));
+    lifecycle::webhooks::flush();
+    result
 }
 
 fn run_status(name: Option<&str>) -> Result<()> {
@@ -258,6 +263,10 @@ async fn run_server() -> Result<()> {
     let _migration_lock = migrate::acquire_migration_lock(&cp)
         .context("Cannot start server: migration lock held (migration in progress?)")?;
 
+    // Fail fast on a bad instance port range rather than on the first create
+    let port_range = cp::server::PortRange::from_env()
+        .map_err(|e| anyhow::anyhow!("Invalid instance port range: {e}"))?;
+
     let registry = Registry::open(&registry_path(&cp))?;
 
     // Run reconciliation (lock already held)
@@ -271,16 +280,27 @@ async fn run_server() -> Result<()> {
     // List instances
     let instances = registry.list_instances()?;
     println!("ZeroClaw Control Plane");
-    println!("Instances: {}", instances.len());
+    println!("Instances: {} (ports {port_range})", instances.len());
     for inst in &instances {
         println!(
             "  {} (id: {}, port: {}, status: {})",
             inst.name, inst.id, inst.port, inst.status
         );
     }
+
+    let mut state = cp::server::CpState::new(registry_path(&cp));
+    state.port_range = port_range;
+    state.allow_secret_export = std::env::var(cp::server::ALLOW_SECRET_EXPORT_ENV)
+        .is_ok_and(|v| v.eq_ignore_ascii_case("true") || v == "1");
+    state.auto_authorize = cp::messaging::AutoAuthorize::from_env();
+    state.log_limits = cp::server::LogLimits::from_env();
+    state.ttl_policy = cp::messaging::TtlPolicy::from_env();
+    state.masking = std::sync::Arc::new(cp::masking::SecretMasking::from_env(&cp)?);
+    // Sends check routing rules in memory; this first load saves a reload
+    state.routing_rules.load(&registry)?;
     drop(registry); // close DB connection; per-request connections from here
 
-    let db_path = Arc::new(registry_path(&cp));
+    let db_path = state.db_path.clone();
 
     // Run startup reconciliation (supervisor)
     let db_path_reconcile = db_path.clone();
@@ -316,7 +336,6 @@ async fn run_server() -> Result<()> {
     ));
 
     // Build router
-    let state = cp::server::CpState { db_path };
     let app = cp::server::build_router(state);
 
     println!("Server ready. Press Ctrl+C to stop.");
@@ -11,6 +11,9 @@ use super::traits::{Channel, ChannelMessage};
 pub struct CpRelayChannel {
     instance_name: String,
     cp_url: String,
+    /// Token the CP issued this instance at start, sent as a bearer token
+    /// so sends are attributed to this instance.
+    token: Option<String>,
     client: reqwest::Client,
 }
 
@@ -24,6 +27,7 @@ impl CpRelayChannel {
         Self {
             instance_name,
             cp_url,
+            token: None,
             client,
         }
     }
@@ -34,7 +38,9 @@ impl CpRelayChannel {
         let cp_url = std::env::var("ZEROCLAW_CP_URL")
             .unwrap_or_else(|_| "http://127.0.0.1:18800".to_string());
 
-        Some(Self::new(instance_name, cp_url))
+        let mut relay = Self::new(instance_name, cp_url);
+        relay.token = std::env::var("ZEROCLAW_CP_TOKEN").ok();
+        Some(relay)
     }
 }
 
@@ -57,12 +63,14 @@ impl Channel for CpRelayChannel {
             "idempotency_key": format!("reply:{}", original_msg_id),
         });
 
-        let reply_resp = self
+        let mut request = self
             .client
             .post(format!("{}/api/messages", self.cp_url))
-            .json(&reply_body)
-            .send()
-            .await;
+            .json(&reply_body);
+        if let Some(ref token) = self.token {
+            request = request.bearer_auth(token);
+        }
+        let reply_resp = request.send().await;
 
         match reply_resp {
             Ok(resp) if resp.status().is_success() => {
@@ -351,13 +351,23 @@ impl Channel for DiscordChannel {
     }
 
     async fn health_check(&self) -> bool {
-        self.client
+        self.check_health().await.is_ok()
+    }
+
+    async fn check_health(&self) -> anyhow::Result<()> {
+        let resp = self
+            .client
             .get("https://discord.com/api/v10/users/@me")
             .header("Authorization", format!("Bot {}", self.bot_token))
             .send()
-            .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+            .await?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        let message = body["message"].as_str().unwrap_or("no message");
+        anyhow::bail!("Discord users/@me failed ({status}): {message}")
     }
 }
 
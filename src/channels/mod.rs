@@ -5,7 +5,9 @@ pub mod email_channel;
 pub mod imessage;
 pub mod irc;
 pub mod matrix;
+pub mod quiet_hours;
 pub mod slack;
+pub mod stt;
 pub mod telegram;
 pub mod telegram_types;
 pub mod traits;
@@ -19,6 +21,7 @@ pub use imessage::IMessageChannel;
 pub use irc::IrcChannel;
 pub use matrix::MatrixChannel;
 pub use slack::SlackChannel;
+pub use stt::SpeechToText;
 pub use telegram::TelegramChannel;
 pub use telegram_types::TelegramToolContext;
 pub use traits::Channel;
@@ -44,6 +47,8 @@ const BOOTSTRAP_MAX_CHARS: usize = 20_000;
 const DEFAULT_CHANNEL_INITIAL_BACKOFF_SECS: u64 = 2;
 const DEFAULT_CHANNEL_MAX_BACKOFF_SECS: u64 = 60;
 const CHANNEL_MESSAGE_TIMEOUT_SECS: u64 = 90;
+/// How often sends held for quiet hours are checked for delivery.
+const QUIET_HOURS_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
 
 fn spawn_supervised_listener(
     ch: Arc<dyn Channel>,
@@ -335,12 +340,34 @@ pub fn handle_command(command: crate::ChannelCommands, config: &Config) -> Resul
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ChannelHealthState {
+pub enum ChannelHealthState {
     Healthy,
     Unhealthy,
     Timeout,
 }
 
+impl ChannelHealthState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Unhealthy => "unhealthy",
+            Self::Timeout => "timeout",
+        }
+    }
+}
+
+/// Outcome of health-checking one configured channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelCheck {
+    /// Key under `channels_config`, e.g. `telegram`.
+    pub channel: &'static str,
+    /// Display name, e.g. `Telegram`.
+    pub label: &'static str,
+    pub state: ChannelHealthState,
+    /// Why the check failed, when the channel could say.
+    pub error: Option<String>,
+}
+
 fn classify_health_result(
     result: &std::result::Result<bool, tokio::time::error::Elapsed>,
 ) -> ChannelHealthState {
@@ -351,22 +378,28 @@ fn classify_health_result(
     }
 }
 
-/// Run health checks for configured channels.
-pub async fn doctor_channels(config: Config) -> Result<()> {
-    let mut channels: Vec<(&'static str, Arc<dyn Channel>)> = Vec::new();
+/// Configured channels that support a health check, as
+/// `(config key, display name, channel)` in config order.
+fn health_checkable_channels(
+    config: &Config,
+) -> Vec<(&'static str, &'static str, Arc<dyn Channel>)> {
+    let mut channels: Vec<(&'static str, &'static str, Arc<dyn Channel>)> = Vec::new();
 
     if let Some(ref tg) = config.channels_config.telegram {
         channels.push((
+            "telegram",
             "Telegram",
-            Arc::new(TelegramChannel::new(
-                tg.bot_token.clone(),
-                tg.allowed_users.clone(),
-            )),
+            Arc::new(
+                TelegramChannel::new(tg.bot_token.clone(), tg.allowed_users.clone())
+                    .with_usernames_case_sensitive(tg.usernames_case_sensitive)
+                    .with_auto_download(tg.auto_download.clone()),
+            ),
         ));
     }
 
     if let Some(ref dc) = config.channels_config.discord {
         channels.push((
+            "discord",
             "Discord",
             Arc::new(DiscordChannel::new(
                 dc.bot_token.clone(),
@@ -378,6 +411,7 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
 
     if let Some(ref sl) = config.channels_config.slack {
         channels.push((
+            "slack",
             "Slack",
             Arc::new(SlackChannel::new(
                 sl.bot_token.clone(),
@@ -389,6 +423,7 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
 
     if let Some(ref im) = config.channels_config.imessage {
         channels.push((
+            "imessage",
             "iMessage",
             Arc::new(IMessageChannel::new(im.allowed_contacts.clone())),
         ));
@@ -396,6 +431,7 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
 
     if let Some(ref mx) = config.channels_config.matrix {
         channels.push((
+            "matrix",
             "Matrix",
             Arc::new(MatrixChannel::new(
                 mx.homeserver.clone(),
@@ -408,6 +444,7 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
 
     if let Some(ref wa) = config.channels_config.whatsapp {
         channels.push((
+            "whatsapp",
             "WhatsApp",
             Arc::new(WhatsAppChannel::new(
                 wa.access_token.clone(),
@@ -419,11 +456,16 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
     }
 
     if let Some(ref email_cfg) = config.channels_config.email {
-        channels.push(("Email", Arc::new(EmailChannel::new(email_cfg.clone()))));
+        channels.push((
+            "email",
+            "Email",
+            Arc::new(EmailChannel::new(email_cfg.clone())),
+        ));
     }
 
     if let Some(ref irc) = config.channels_config.irc {
         channels.push((
+            "irc",
             "IRC",
             Arc::new(IrcChannel::new(
                 irc.server.clone(),
@@ -440,7 +482,53 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
         ));
     }
 
-    if channels.is_empty() {
+    channels
+}
+
+/// Health-check every configured channel concurrently, each bounded by
+/// `timeout`, so one slow channel doesn't hold up the rest. Results are in
+/// config order; a check that panics counts as unhealthy.
+pub async fn check_channels(config: &Config, timeout: Duration) -> Vec<ChannelCheck> {
+    let channels = health_checkable_channels(config);
+    let mut checks: Vec<ChannelCheck> = channels
+        .iter()
+        .map(|&(channel, label, _)| ChannelCheck {
+            channel,
+            label,
+            state: ChannelHealthState::Unhealthy,
+            error: None,
+        })
+        .collect();
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (idx, (_, _, channel)) in channels.into_iter().enumerate() {
+        tasks.spawn(async move {
+            let result = tokio::time::timeout(timeout, channel.check_health()).await;
+            let error = match &result {
+                Ok(Err(e)) => Some(format!("{e:#}")),
+                _ => None,
+            };
+            let state = classify_health_result(&result.map(|r| r.is_ok()));
+            (idx, state, error)
+        });
+    }
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((idx, state, error)) => {
+                checks[idx].state = state;
+                checks[idx].error = error;
+            }
+            Err(e) => tracing::warn!("Channel health check task failed: {e}"),
+        }
+    }
+    checks
+}
+
+/// Run health checks for configured channels.
+pub async fn doctor_channels(config: Config) -> Result<()> {
+    let checks = check_channels(&config, Duration::from_secs(10)).await;
+
+    if checks.is_empty() {
         println!("No real-time channels configured. Run `zeroclaw onboard` first.");
         return Ok(());
     }
@@ -452,11 +540,9 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
     let mut unhealthy = 0_u32;
     let mut timeout = 0_u32;
 
-    for (name, channel) in channels {
-        let result = tokio::time::timeout(Duration::from_secs(10), channel.health_check()).await;
-        let state = classify_health_result(&result);
-
-        match state {
+    for check in checks {
+        let name = check.label;
+        match check.state {
             ChannelHealthState::Healthy => {
                 healthy += 1;
                 println!("  ✅ {name:<9} healthy");
@@ -481,6 +567,25 @@ pub async fn doctor_channels(config: Config) -> Result<()> {
     Ok(())
 }
 
+/// Reply to `msg` on the channel it came from. With a Telegram placeholder
+/// (chat message id) the reply is edited into it (see
+/// [`TelegramChannel::finish_placeholder`]).
+async fn send_reply(
+    channels: &[Arc<dyn Channel>],
+    placeholder: Option<&(Arc<TelegramChannel>, i64)>,
+    msg: &traits::ChannelMessage,
+    text: &str,
+) -> Result<()> {
+    if let Some((tg, message_id)) = placeholder {
+        return tg.finish_placeholder(&msg.sender, *message_id, text).await;
+    }
+    // Find the channel that sent this message and reply
+    match channels.iter().find(|ch| ch.name() == msg.channel) {
+        Some(ch) => ch.send(text, msg).await,
+        None => Ok(()),
+    }
+}
+
 /// Start all configured channels and route messages to the agent
 #[allow(clippy::too_many_lines)]
 pub async fn start_channels(config: Config) -> Result<()> {
@@ -540,15 +645,23 @@ pub async fn start_channels(config: Config) -> Result<()> {
     let flow_defs: Arc<std::collections::HashMap<String, crate::flows::types::FlowDefinition>> =
         if flows_enabled {
             let flows_dir = config.workspace_dir.join("flows");
-            match crate::flows::load_flows(&flows_dir) {
+            match crate::flows::load_flows_detailed(&flows_dir) {
                 Ok(defs) => {
                     if !defs.is_empty() {
                         println!("  📋 Flows:    {} loaded", defs.len());
                     }
                     Arc::new(defs)
                 }
-                Err(e) => {
-                    anyhow::bail!("Flow validation failed (flows_enabled=true): {e}");
+                Err(errors) => {
+                    for e in &errors {
+                        tracing::error!("Flow load error: {e}");
+                    }
+                    let lines: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                    anyhow::bail!(
+                        "Flow validation failed (flows_enabled=true): {} error(s):\n  {}",
+                        errors.len(),
+                        lines.join("\n  ")
+                    );
                 }
             }
         } else {
@@ -617,7 +730,10 @@ pub async fn start_channels(config: Config) -> Result<()> {
         if let Some(ref tg) = config.channels_config.telegram {
             if !tg.allowed_users.contains(&"*".to_string()) {
                 for admin_id in &approval_policy.admin_users {
-                    if !tg.allowed_users.contains(admin_id) {
+                    let allowed = tg.allowed_users.iter().any(|u| {
+                        telegram::identity_matches(u, admin_id, tg.usernames_case_sensitive)
+                    });
+                    if !allowed {
                         anyhow::bail!(
                             "approval_policy.admin_users contains '{}' which is not in \
                              channels_config.telegram.allowed_users",
@@ -637,11 +753,27 @@ pub async fn start_channels(config: Config) -> Result<()> {
             None
         };
 
+    // Quiet hours: one gate for every human-facing send, never the CP relay
+    let quiet_hours_gate: Option<Arc<quiet_hours::QuietHoursGate>> =
+        match config.channels_config.quiet_hours {
+            Some(ref qh) => Some(Arc::new(quiet_hours::QuietHoursGate::new(
+                quiet_hours::QuietHours::from_config(qh)?,
+                observer.clone(),
+                quiet_hours::deferred_state_path(&config.workspace_dir),
+            ))),
+            None => None,
+        };
+
     let telegram_channel_arc: Option<Arc<TelegramChannel>> =
         if let Some(ref tg) = config.channels_config.telegram {
-            let mut ch = TelegramChannel::new(tg.bot_token.clone(), tg.allowed_users.clone());
-            if let Some(ref endpoint) = tg.stt_endpoint {
-                ch = ch.with_stt(endpoint.clone());
+            let mut ch = TelegramChannel::new(tg.bot_token.clone(), tg.allowed_users.clone())
+                .with_usernames_case_sensitive(tg.usernames_case_sensitive)
+                .with_auto_download(tg.auto_download.clone())
+                .with_placeholder_replies(tg.placeholder_replies)
+                .with_markdown_v2(tg.markdown_v2)
+                .with_offset_path(telegram::offset_state_path(&config.workspace_dir));
+            if let Some(stt) = stt::create_stt(&config.stt, tg.stt_endpoint.as_deref())? {
+                ch = ch.with_stt(stt);
             }
             ch = ch.with_observer(observer.clone());
             if let Some(ref db) = flow_db {
@@ -650,6 +782,9 @@ pub async fn start_channels(config: Config) -> Result<()> {
             if let Some(ref reg) = approval_registry {
                 ch = ch.with_approval_registry(reg.clone());
             }
+            if let Some(ref gate) = quiet_hours_gate {
+                ch = ch.with_quiet_hours(gate.clone());
+            }
             Some(Arc::new(ch))
         } else {
             None
@@ -861,6 +996,40 @@ pub async fn start_channels(config: Config) -> Result<()> {
         }
     }
 
+    // Telegram checks the quiet-hours gate on every API send itself; the
+    // other human-facing channels only ever `send`, so wrapping them is enough
+    if let Some(ref gate) = quiet_hours_gate {
+        let unwrapped = channels.clone();
+        channels = channels
+            .into_iter()
+            .map(|ch| -> Arc<dyn Channel> {
+                if ch.name() == "cp" || ch.name() == "telegram" {
+                    ch
+                } else {
+                    Arc::new(quiet_hours::QuietHoursChannel::new(ch, gate.clone()))
+                }
+            })
+            .collect();
+
+        // Deliver held sends once the window ends, including ones held
+        // before a restart
+        let gate = gate.clone();
+        let telegram = telegram_channel_arc.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(QUIET_HOURS_FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+                quiet_hours::deliver_due(
+                    &gate,
+                    &unwrapped,
+                    telegram.as_deref(),
+                    chrono::Utc::now(),
+                )
+                .await;
+            }
+        });
+    }
+
     if channels.is_empty() {
         println!("No channels configured. Run `zeroclaw onboard` to set up channels.");
         return Ok(());
@@ -981,6 +1150,14 @@ pub async fn start_channels(config: Config) -> Result<()> {
                     std::borrow::Cow::Owned(m)
                 };
 
+                // Flow-level deadlines end the run regardless of step state
+                for (chat_id, flow_name, step_id) in timeout_store.check_deadlines(&merged_defs) {
+                    tracing::info!(
+                        "Flow '{flow_name}' exceeded its deadline at step '{step_id}' for chat {chat_id}"
+                    );
+                    timeout_store.complete_flow(&chat_id, "deadline_exceeded");
+                }
+
                 let timed_out = timeout_store.check_timeouts(&merged_defs);
                 for (chat_id, flow_name, step_id) in timed_out {
                     tracing::info!("Flow '{flow_name}' step '{step_id}' timed out for chat {chat_id}");
@@ -998,23 +1175,50 @@ pub async fn start_channels(config: Config) -> Result<()> {
                                 .map(|t| t.target.clone());
 
                             if let Some(target_id) = timeout_target {
-                                if let Some(target_step) = flow_def.steps.get(&target_id) {
+                                // No answer to branch on: conditions see an empty context
+                                let target_step = flow_def.steps.get(&target_id).and_then(|step| {
+                                    crate::flows::execute::resolve_branch(
+                                        flow_def,
+                                        step,
+                                        &HashMap::new(),
+                                    )
+                                    .map_err(|e| tracing::warn!("Timeout branch failed: {e}"))
+                                    .ok()
+                                });
+                                if let Some(target_step) = target_step {
                                     let anchor = timeout_store
                                         .get_flow_info(&chat_id)
                                         .and_then(|(_, _, a)| a);
+                                    let vars = timeout_store.flow_vars(&chat_id);
                                     if let Some(ref tg) = timeout_tg {
-                                        match crate::flows::execute::execute_step(
-                                            tg, &chat_id, target_step, anchor,
+                                        match crate::flows::execute::with_step_timeout(
+                                            &target_step.id,
+                                            crate::flows::execute::STEP_EXECUTION_TIMEOUT_SECS,
+                                            crate::flows::execute::execute_step(
+                                                tg,
+                                                &chat_id,
+                                                target_step,
+                                                anchor,
+                                                &vars,
+                                            ),
                                         )
                                         .await
                                         {
                                             Ok(_) => {
                                                 if target_step.is_terminal() {
-                                                    timeout_store.complete_flow(&chat_id, "timed_out");
+                                                    // A delay ending is the flow going as planned
+                                                    let status = if step.kind
+                                                        == crate::flows::types::StepKind::Delay
+                                                    {
+                                                        "completed"
+                                                    } else {
+                                                        "timed_out"
+                                                    };
+                                                    timeout_store.complete_flow(&chat_id, status);
                                                 } else {
                                                     let _ = timeout_store.advance(
                                                         &chat_id,
-                                                        &target_id,
+                                                        &target_step.id,
                                                         anchor,
                                                     );
                                                 }
@@ -1125,18 +1329,44 @@ pub async fn start_channels(config: Config) -> Result<()> {
 
                         if let Some(transition) = matched_transition {
                             let target_step_id = transition.target.clone();
-                            if let Some(target_step) = flow_def.steps.get(&target_step_id) {
-                                // Execute the target step
+                            if let Some(requested_step) = flow_def.steps.get(&target_step_id) {
+                                // Branch conditions and step text see this answer
+                                // alongside the flow's inputs and earlier answers
+                                let _ = flow_store.record_answer(
+                                    &chat_id,
+                                    &current_step_id,
+                                    &callback_data,
+                                );
+                                let flow_ctx = flow_store.flow_vars(&chat_id);
+                                // Execute the target step (bounded; failures route via _error)
                                 if let Some(ref tg_arc) = telegram_channel_arc {
-                                    match crate::flows::execute::execute_step(
-                                        tg_arc,
-                                        &chat_id,
-                                        target_step,
-                                        anchor_msg_id,
+                                    match crate::flows::execute::run_step_routed(
+                                        flow_def,
+                                        requested_step,
+                                        &flow_ctx,
+                                        |step| {
+                                            crate::flows::execute::execute_step(
+                                                tg_arc,
+                                                &chat_id,
+                                                step,
+                                                anchor_msg_id,
+                                                &flow_ctx,
+                                            )
+                                        },
                                     )
                                     .await
                                     {
-                                        Ok(result) => {
+                                        Ok(routed) => {
+                                            if routed.timed_out {
+                                                flow_store.record_step_timeout(
+                                                    &chat_id,
+                                                    &flow_name,
+                                                    &requested_step.id,
+                                                );
+                                            }
+                                            let target_step = routed.step;
+                                            let target_step_id = target_step.id.clone();
+                                            let result = routed.result;
                                             // Store poll_id mapping if present
                                             if let Some(ref poll_id) = result.poll_id {
                                                 if let Some(ref db) = flow_db {
@@ -1167,7 +1397,16 @@ pub async fn start_channels(config: Config) -> Result<()> {
                                             tracing::warn!(
                                                 "Flow step execution failed: {e}; completing flow"
                                             );
-                                            flow_store.complete_flow(&chat_id, "completed");
+                                            if crate::flows::execute::is_step_timeout(&e) {
+                                                flow_store.record_step_timeout(
+                                                    &chat_id,
+                                                    &flow_name,
+                                                    &requested_step.id,
+                                                );
+                                                flow_store.complete_flow(&chat_id, "step_timeout");
+                                            } else {
+                                                flow_store.complete_flow(&chat_id, "completed");
+                                            }
                                             // Fall through to agent_turn
                                         }
                                     }
@@ -1244,6 +1483,20 @@ pub async fn start_channels(config: Config) -> Result<()> {
         println!("  ⏳ Processing message...");
         let started_at = Instant::now();
 
+        // Placeholder the reply will be edited into (telegram `placeholder_replies`)
+        let placeholder = match telegram_channel_arc {
+            Some(ref tg) if msg.channel == "telegram" && tg.placeholder_replies() => {
+                match tg.send_placeholder(&msg.sender).await {
+                    Ok(message_id) => message_id.map(|id| (tg.clone(), id)),
+                    Err(e) => {
+                        tracing::warn!("Telegram placeholder send failed: {e}");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
         let llm_result = tokio::time::timeout(
             Duration::from_secs(CHANNEL_MESSAGE_TIMEOUT_SECS),
             crate::agent::agent_turn(
@@ -1267,14 +1520,8 @@ pub async fn start_channels(config: Config) -> Result<()> {
                     started_at.elapsed().as_millis(),
                     truncate_with_ellipsis(&response, 80)
                 );
-                // Find the channel that sent this message and reply
-                for ch in &channels {
-                    if ch.name() == msg.channel {
-                        if let Err(e) = ch.send(&response, &msg).await {
-                            eprintln!("  ❌ Failed to reply on {}: {e}", ch.name());
-                        }
-                        break;
-                    }
+                if let Err(e) = send_reply(&channels, placeholder.as_ref(), &msg, &response).await {
+                    eprintln!("  ❌ Failed to reply on {}: {e}", msg.channel);
                 }
 
                 // Auto-save assistant response
@@ -1294,12 +1541,8 @@ pub async fn start_channels(config: Config) -> Result<()> {
                     "  ❌ LLM error after {}ms: {e}",
                     started_at.elapsed().as_millis()
                 );
-                for ch in &channels {
-                    if ch.name() == msg.channel {
-                        let _ = ch.send(&format!("⚠️ Error: {e}"), &msg).await;
-                        break;
-                    }
-                }
+                let error_msg = format!("⚠️ Error: {e}");
+                let _ = send_reply(&channels, placeholder.as_ref(), &msg, &error_msg).await;
             }
             Err(_) => {
                 let timeout_msg = format!(
@@ -1311,17 +1554,13 @@ pub async fn start_channels(config: Config) -> Result<()> {
                     timeout_msg,
                     started_at.elapsed().as_millis()
                 );
-                for ch in &channels {
-                    if ch.name() == msg.channel {
-                        let _ = ch
-                            .send(
-                                "⚠️ Request timed out while waiting for the model. Please try again.",
-                                &msg,
-                            )
-                            .await;
-                        break;
-                    }
-                }
+                let _ = send_reply(
+                    &channels,
+                    placeholder.as_ref(),
+                    &msg,
+                    "⚠️ Request timed out while waiting for the model. Please try again.",
+                )
+                .await;
             }
         }
     }
@@ -1733,6 +1972,26 @@ mod tests {
         assert_eq!(state, ChannelHealthState::Timeout);
     }
 
+    #[tokio::test]
+    async fn check_channels_follows_config_order() {
+        let mut config = Config::default();
+        assert!(check_channels(&config, Duration::from_millis(1))
+            .await
+            .is_empty());
+
+        config.channels_config.imessage = Some(crate::config::schema::IMessageConfig {
+            allowed_contacts: vec![],
+        });
+        let checks = check_channels(&config, Duration::from_secs(5)).await;
+        assert_eq!(checks.len(), 1);
+        assert_eq!(checks[0].channel, "imessage");
+        assert_eq!(checks[0].label, "iMessage");
+        if !cfg!(target_os = "macos") {
+            assert_eq!(checks[0].state, ChannelHealthState::Unhealthy);
+            assert!(checks[0].error.is_some());
+        }
+    }
+
     struct AlwaysFailChannel {
         name: &'static str,
         calls: Arc<AtomicUsize>,
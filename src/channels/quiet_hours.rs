@@ -0,0 +1,523 @@
+use async_trait::async_trait;
+use base64::Engine as _;
+use chrono::{DateTime, FixedOffset, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use super::telegram::TelegramChannel;
+use super::traits::{Channel, ChannelMessage};
+use crate::config::QuietHoursConfig;
+use crate::observability::{Observer, ObserverEvent};
+
+/// File under the workspace `state` dir holding sends held for quiet hours.
+const DEFERRED_FILE: &str = "quiet_hours_deferred.json";
+
+/// How urgently a send has to go out. `High` sends ignore quiet hours.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Priority {
+    #[default]
+    Normal,
+    High,
+}
+
+tokio::task_local! {
+    static SEND_PRIORITY: Priority;
+}
+
+/// Run `fut` with every channel send it makes marked `priority`.
+pub async fn with_priority<F: Future>(priority: Priority, fut: F) -> F::Output {
+    SEND_PRIORITY.scope(priority, fut).await
+}
+
+/// Priority of sends made by the current task (`Normal` outside
+/// [`with_priority`]).
+pub fn current_priority() -> Priority {
+    SEND_PRIORITY.try_with(|p| *p).unwrap_or_default()
+}
+
+/// A parsed, validated quiet-hours window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    offset: FixedOffset,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    pub fn from_config(config: &QuietHoursConfig) -> anyhow::Result<Self> {
+        let offset = parse_offset(&config.utc_offset)?;
+        let start = parse_hhmm(&config.start)
+            .ok_or_else(|| anyhow::anyhow!("quiet_hours.start '{}' is not HH:MM", config.start))?;
+        let end = parse_hhmm(&config.end)
+            .ok_or_else(|| anyhow::anyhow!("quiet_hours.end '{}' is not HH:MM", config.end))?;
+        if start == end {
+            anyhow::bail!("quiet_hours.start and quiet_hours.end must differ");
+        }
+        Ok(Self { offset, start, end })
+    }
+
+    /// Whether `now` falls inside the window.
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let local = now.with_timezone(&self.offset).time();
+        if self.start < self.end {
+            self.start <= local && local < self.end
+        } else {
+            local >= self.start || local < self.end
+        }
+    }
+
+    /// The next time the window ends after `now`.
+    pub fn window_end(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let local = now.with_timezone(&self.offset);
+        let mut end = local.date_naive().and_time(self.end);
+        if end <= local.naive_local() {
+            end += chrono::Duration::days(1);
+        }
+        self.offset
+            .from_local_datetime(&end)
+            .single()
+            .map_or(now, |end| end.with_timezone(&Utc))
+    }
+}
+
+/// "UTC"/"Z" or a fixed "+HH:MM"/"-HH:MM" offset.
+fn parse_offset(tz: &str) -> anyhow::Result<FixedOffset> {
+    if tz.eq_ignore_ascii_case("utc") || tz == "Z" {
+        return Ok(FixedOffset::east_opt(0).expect("zero offset is valid"));
+    }
+    let invalid = || anyhow::anyhow!("quiet_hours.utc_offset '{tz}' is not UTC or +HH:MM/-HH:MM");
+    let (sign, rest) = match tz.split_at_checked(1) {
+        Some(("+", rest)) => (1, rest),
+        Some(("-", rest)) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let time = parse_hhmm(rest).ok_or_else(invalid)?;
+    let secs = i32::try_from(time.signed_duration_since(NaiveTime::MIN).num_seconds())
+        .map_err(|_| invalid())?;
+    FixedOffset::east_opt(sign * secs).ok_or_else(invalid)
+}
+
+fn parse_hhmm(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M").ok()
+}
+
+/// A send held during quiet hours, in the form it is replayed from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeferredSend {
+    /// `Channel::send` of `text` to `recipient` on `channel`.
+    Text {
+        channel: String,
+        recipient: String,
+        text: String,
+    },
+    /// A Telegram Bot API call with a JSON body.
+    TelegramJson {
+        chat_id: String,
+        method: String,
+        body: serde_json::Value,
+    },
+    /// A Telegram Bot API upload; `data` is base64.
+    TelegramFile {
+        chat_id: String,
+        method: String,
+        field: String,
+        file_name: String,
+        data: String,
+        caption: Option<String>,
+    },
+}
+
+impl DeferredSend {
+    pub fn telegram_file(
+        chat_id: &str,
+        method: &str,
+        field: &str,
+        file_bytes: &[u8],
+        file_name: &str,
+        caption: Option<&str>,
+    ) -> Self {
+        Self::TelegramFile {
+            chat_id: chat_id.to_string(),
+            method: method.to_string(),
+            field: field.to_string(),
+            file_name: file_name.to_string(),
+            data: base64::engine::general_purpose::STANDARD.encode(file_bytes),
+            caption: caption.map(str::to_string),
+        }
+    }
+}
+
+/// Where the channels started for `workspace_dir` keep held sends.
+pub fn deferred_state_path(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join("state").join(DEFERRED_FILE)
+}
+
+/// The single quiet-hours check every human-facing send goes through. Held
+/// sends are written to a state file, so a restart during the window does
+/// not lose them; [`deliver_due`] sends them once the window ends.
+pub struct QuietHoursGate {
+    hours: QuietHours,
+    observer: Arc<dyn Observer>,
+    path: PathBuf,
+    /// Serializes read-modify-write of the state file.
+    lock: Mutex<()>,
+}
+
+impl QuietHoursGate {
+    pub fn new(hours: QuietHours, observer: Arc<dyn Observer>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            hours,
+            observer,
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// When a send of the current task's priority made at `now` has to be
+    /// held, the time the window ends.
+    pub fn hold_until(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (current_priority() != Priority::High && self.hours.contains(now))
+            .then(|| self.hours.window_end(now))
+    }
+
+    /// Store `send` (made on `channel`) until the window ending at `until`
+    /// is over, and record a `quiet_hours_deferred` event.
+    pub fn defer(
+        &self,
+        channel: &str,
+        send: DeferredSend,
+        until: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        {
+            let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut held = self.load()?;
+            held.push(send);
+            self.save(&held)?;
+        }
+        self.observer
+            .record_event(&ObserverEvent::QuietHoursDeferred {
+                channel: channel.to_string(),
+                until: until.to_rfc3339(),
+            });
+        Ok(())
+    }
+
+    /// Number of sends currently held.
+    pub fn deferred_count(&self) -> usize {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        self.load().map_or(0, |held| held.len())
+    }
+
+    /// Every held send, in the order they were made, once `now` is outside
+    /// the window. Returns nothing while it is still open. The sends stay
+    /// held until [`Self::remove_delivered`] is called for each.
+    pub fn due(&self, now: DateTime<Utc>) -> anyhow::Result<Vec<DeferredSend>> {
+        if self.hours.contains(now) {
+            return Ok(Vec::new());
+        }
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        self.load()
+    }
+
+    /// Drop `send` from the held sends once it has gone out.
+    pub fn remove_delivered(&self, send: &DeferredSend) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut held = self.load()?;
+        if let Some(pos) = held.iter().position(|h| h == send) {
+            held.remove(pos);
+            self.save(&held)?;
+        }
+        Ok(())
+    }
+
+    fn load(&self) -> anyhow::Result<Vec<DeferredSend>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(raw) => Ok(serde_json::from_str(&raw)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Written to a temp file and renamed over the state file, so a crash
+    /// mid-write cannot leave it truncated.
+    fn save(&self, held: &[DeferredSend]) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(held)?)?;
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Send everything `gate` held, if the window is over at `now`: text sends on the
+/// (unwrapped) channel they were made on, Telegram API calls through
+/// `telegram`. Each send is released from the gate once delivered; failed
+/// ones stay held and are retried on the next call.
+pub async fn deliver_due(
+    gate: &QuietHoursGate,
+    channels: &[Arc<dyn Channel>],
+    telegram: Option<&TelegramChannel>,
+    now: DateTime<Utc>,
+) {
+    let held = match gate.due(now) {
+        Ok(held) => held,
+        Err(e) => {
+            tracing::warn!("Failed to read held quiet-hours sends: {e:#}");
+            return;
+        }
+    };
+    for send in held {
+        let result = match &send {
+            DeferredSend::Text {
+                channel,
+                recipient,
+                text,
+            } => match channels.iter().find(|ch| ch.name() == channel) {
+                Some(ch) => ch.send(text, &recipient_message(channel, recipient)).await,
+                None => Err(anyhow::anyhow!("channel '{channel}' is not running")),
+            },
+            DeferredSend::TelegramJson { .. } | DeferredSend::TelegramFile { .. } => match telegram
+            {
+                Some(tg) => tg.replay_deferred(&send).await,
+                None => Err(anyhow::anyhow!("telegram channel is not running")),
+            },
+        };
+        match result {
+            Ok(()) => {
+                if let Err(e) = gate.remove_delivered(&send) {
+                    tracing::warn!("Failed to release delivered quiet-hours send: {e:#}");
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to deliver message held for quiet hours, keeping it: {e:#}");
+            }
+        }
+    }
+}
+
+/// The `reply_to` a held text send is replayed with: human-facing channels
+/// address a reply by its `sender`.
+fn recipient_message(channel: &str, recipient: &str) -> ChannelMessage {
+    ChannelMessage {
+        id: String::new(),
+        sender: recipient.to_string(),
+        content: String::new(),
+        channel: channel.to_string(),
+        timestamp: 0,
+        metadata: std::collections::HashMap::new(),
+    }
+}
+
+/// Wraps a human-facing channel whose only outbound path is `send`, so
+/// sends during quiet hours are held by the gate. Telegram, which also
+/// sends keyboards, polls and files, checks the gate itself.
+pub struct QuietHoursChannel {
+    inner: Arc<dyn Channel>,
+    gate: Arc<QuietHoursGate>,
+}
+
+impl QuietHoursChannel {
+    pub fn new(inner: Arc<dyn Channel>, gate: Arc<QuietHoursGate>) -> Self {
+        Self { inner, gate }
+    }
+
+    async fn send_at(
+        &self,
+        message: &str,
+        reply_to: &ChannelMessage,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        let Some(until) = self.gate.hold_until(now) else {
+            return self.inner.send(message, reply_to).await;
+        };
+        self.gate.defer(
+            self.inner.name(),
+            DeferredSend::Text {
+                channel: self.inner.name().to_string(),
+                recipient: reply_to.sender.clone(),
+                text: message.to_string(),
+            },
+            until,
+        )
+    }
+}
+
+#[async_trait]
+impl Channel for QuietHoursChannel {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn send(&self, message: &str, reply_to: &ChannelMessage) -> anyhow::Result<()> {
+        self.send_at(message, reply_to, Utc::now()).await
+    }
+
+    async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
+        self.inner.listen(tx).await
+    }
+
+    async fn health_check(&self) -> bool {
+        self.inner.health_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::observability::NoopObserver;
+
+    fn hours(utc_offset: &str, start: &str, end: &str) -> anyhow::Result<QuietHours> {
+        QuietHours::from_config(&QuietHoursConfig {
+            utc_offset: utc_offset.into(),
+            start: start.into(),
+            end: end.into(),
+        })
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn overnight_window_respects_offset() {
+        let qh = hours("+02:00", "22:00", "07:00").unwrap();
+        // 01:00 UTC is 03:00 local
+        assert!(qh.contains(at("2026-03-01T01:00:00Z")));
+        // 06:00 UTC is 08:00 local
+        assert!(!qh.contains(at("2026-03-01T06:00:00Z")));
+        assert!(qh.contains(at("2026-03-01T20:00:00Z")));
+        assert_eq!(
+            qh.window_end(at("2026-03-01T20:00:00Z")),
+            at("2026-03-02T05:00:00Z")
+        );
+        assert_eq!(
+            qh.window_end(at("2026-03-01T01:00:00Z")),
+            at("2026-03-01T05:00:00Z")
+        );
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        assert!(hours("Europe/Berlin", "22:00", "07:00").is_err());
+        assert!(hours("UTC", "25:00", "07:00").is_err());
+        assert!(hours("UTC", "07:00", "07:00").is_err());
+        assert!(hours("-05:30", "13:00", "14:00").is_ok());
+    }
+
+    #[derive(Default)]
+    struct RecordingChannel {
+        sent: Mutex<Vec<String>>,
+        offline: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl Channel for RecordingChannel {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn send(&self, message: &str, reply_to: &ChannelMessage) -> anyhow::Result<()> {
+            if self.offline.load(std::sync::atomic::Ordering::SeqCst) {
+                anyhow::bail!("network unreachable");
+            }
+            self.sent
+                .lock()
+                .unwrap()
+                .push(format!("{}: {message}", reply_to.sender));
+            Ok(())
+        }
+
+        async fn listen(
+            &self,
+            _tx: tokio::sync::mpsc::Sender<ChannelMessage>,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn reply_to() -> ChannelMessage {
+        recipient_message("recording", "alice")
+    }
+
+    #[tokio::test]
+    async fn held_sends_persist_until_the_window_ends_unless_high_priority() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = deferred_state_path(tmp.path());
+        let gate = || {
+            Arc::new(QuietHoursGate::new(
+                hours("UTC", "22:00", "07:00").unwrap(),
+                Arc::new(NoopObserver),
+                &path,
+            ))
+        };
+        let inner = Arc::new(RecordingChannel::default());
+        let ch = QuietHoursChannel::new(inner.clone(), gate());
+
+        let night = at("2026-03-01T03:00:00Z");
+        ch.send_at("first", &reply_to(), night).await.unwrap();
+        with_priority(Priority::High, ch.send_at("urgent", &reply_to(), night))
+            .await
+            .unwrap();
+        ch.send_at("second", &reply_to(), night).await.unwrap();
+        assert_eq!(*inner.sent.lock().unwrap(), ["alice: urgent"]);
+
+        // A restarted process picks the held sends up from the state file
+        let restarted = gate();
+        assert_eq!(restarted.deferred_count(), 2);
+        let channels: Vec<Arc<dyn Channel>> = vec![inner.clone()];
+        deliver_due(&restarted, &channels, None, night).await;
+        assert_eq!(restarted.deferred_count(), 2);
+        deliver_due(&restarted, &channels, None, at("2026-03-01T07:00:00Z")).await;
+        assert_eq!(
+            *inner.sent.lock().unwrap(),
+            ["alice: urgent", "alice: first", "alice: second"]
+        );
+        assert_eq!(restarted.deferred_count(), 0);
+
+        // Outside the window sends go straight through
+        ch.send_at("day", &reply_to(), at("2026-03-01T12:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(inner.sent.lock().unwrap().len(), 4);
+        assert_eq!(restarted.deferred_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_stay_held_for_the_next_flush() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let gate = Arc::new(QuietHoursGate::new(
+            hours("UTC", "22:00", "07:00").unwrap(),
+            Arc::new(NoopObserver),
+            deferred_state_path(tmp.path()),
+        ));
+        let inner = Arc::new(RecordingChannel::default());
+        let ch = QuietHoursChannel::new(inner.clone(), gate.clone());
+        let night = at("2026-03-01T03:00:00Z");
+        ch.send_at("first", &reply_to(), night).await.unwrap();
+        ch.send_at("second", &reply_to(), night).await.unwrap();
+
+        let morning = at("2026-03-01T07:00:00Z");
+        let channels: Vec<Arc<dyn Channel>> = vec![inner.clone()];
+        inner
+            .offline
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        deliver_due(&gate, &channels, None, morning).await;
+        assert!(inner.sent.lock().unwrap().is_empty());
+        assert_eq!(gate.deferred_count(), 2);
+
+        inner
+            .offline
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        deliver_due(&gate, &channels, None, morning).await;
+        assert_eq!(
+            *inner.sent.lock().unwrap(),
+            ["alice: first", "alice: second"]
+        );
+        assert_eq!(gate.deferred_count(), 0);
+    }
+}
@@ -181,13 +181,27 @@ impl Channel for SlackChannel {
     }
 
     async fn health_check(&self) -> bool {
-        self.client
+        self.check_health().await.is_ok()
+    }
+
+    async fn check_health(&self) -> anyhow::Result<()> {
+        let resp = self
+            .client
             .get("https://slack.com/api/auth.test")
             .bearer_auth(&self.bot_token)
             .send()
-            .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            anyhow::bail!("Slack auth.test failed ({status})");
+        }
+        // Slack reports a bad token as 200 with `ok: false`
+        let body: serde_json::Value = resp.json().await?;
+        if body["ok"].as_bool() != Some(true) {
+            let error = body["error"].as_str().unwrap_or("unknown error");
+            anyhow::bail!("Slack auth.test failed: {error}");
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::telegram_types::{SpeechClient, TranscriptResult};
+use crate::config::SttConfig;
+
+/// Speech-to-text backend — implement to plug in local Whisper, another
+/// cloud provider, or a test double.
+#[async_trait]
+pub trait SpeechToText: Send + Sync {
+    /// Backend name for logs/diagnostics
+    fn name(&self) -> &str;
+
+    /// Transcribe raw audio bytes; `format` is the file extension (e.g. "ogg")
+    async fn transcribe(&self, audio: Vec<u8>, format: &str) -> Result<TranscriptResult>;
+}
+
+/// The external HTTP transcription service is the default backend.
+#[async_trait]
+impl SpeechToText for SpeechClient {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    async fn transcribe(&self, audio: Vec<u8>, format: &str) -> Result<TranscriptResult> {
+        SpeechClient::transcribe(self, audio, format).await
+    }
+}
+
+/// Build the configured STT backend.
+///
+/// `fallback_endpoint` is the legacy `channels_config.telegram.stt_endpoint`,
+/// used by the `http` backend when `[stt].endpoint` is unset. Returns `None`
+/// when STT is disabled or no endpoint is configured.
+pub fn create_stt(
+    config: &SttConfig,
+    fallback_endpoint: Option<&str>,
+) -> Result<Option<Arc<dyn SpeechToText>>> {
+    match config.backend.as_str() {
+        "none" => Ok(None),
+        "http" => Ok(config
+            .endpoint
+            .as_deref()
+            .or(fallback_endpoint)
+            .map(|endpoint| {
+                Arc::new(SpeechClient::new(endpoint.to_string())) as Arc<dyn SpeechToText>
+            })),
+        other => anyhow::bail!("Unknown STT backend '{other}' (expected \"http\" or \"none\")"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockStt;
+
+    #[async_trait]
+    impl SpeechToText for MockStt {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        async fn transcribe(&self, audio: Vec<u8>, format: &str) -> Result<TranscriptResult> {
+            Ok(TranscriptResult {
+                text: format!("{} bytes of {format}", audio.len()),
+                language: "en".into(),
+                duration_ms: None,
+                confidence: 1.0,
+                processing_time_ms: None,
+            })
+        }
+    }
+
+    fn stt_config(backend: &str, endpoint: Option<&str>) -> SttConfig {
+        SttConfig {
+            backend: backend.into(),
+            endpoint: endpoint.map(String::from),
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_backend_usable_as_trait_object() {
+        let stt: Arc<dyn SpeechToText> = Arc::new(MockStt);
+        let result = stt.transcribe(vec![0; 4], "ogg").await.unwrap();
+        assert_eq!(result.text, "4 bytes of ogg");
+        assert_eq!(stt.name(), "mock");
+    }
+
+    #[test]
+    fn http_backend_prefers_stt_endpoint() {
+        let stt = create_stt(
+            &stt_config("http", Some("http://stt:9000")),
+            Some("http://legacy:9000"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(stt.name(), "http");
+    }
+
+    #[test]
+    fn http_backend_falls_back_to_legacy_endpoint() {
+        assert!(
+            create_stt(&SttConfig::default(), Some("http://legacy:9000"))
+                .unwrap()
+                .is_some()
+        );
+        assert!(create_stt(&SttConfig::default(), None).unwrap().is_none());
+    }
+
+    #[test]
+    fn none_backend_disables_stt() {
+        assert!(
+            create_stt(&stt_config("none", Some("http://stt:9000")), None)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn unknown_backend_errors() {
+        let err = create_stt(&stt_config("whisper-cpp", None), None)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Unknown STT backend"));
+    }
+}
@@ -1,19 +1,54 @@
+use super::quiet_hours::{DeferredSend, QuietHoursGate};
+use super::stt::SpeechToText;
 use super::telegram_types::{
-    InlineButton, SpeechClient, ACCEPTED_AUDIO_TYPES, MAX_VOICE_BYTES, STT_CONCURRENCY,
+    InlineButton, ReplyKeyboardOptions, ACCEPTED_AUDIO_TYPES, MAX_VOICE_BYTES, STT_CONCURRENCY,
     STT_TIMEOUT_SECS,
 };
 use super::traits::{Channel, ChannelMessage};
+use crate::config::TelegramAutoDownloadConfig;
 use crate::observability::traits::{Observer, ObserverEvent, ObserverMetric};
 use async_trait::async_trait;
 use reqwest::multipart::{Form, Part};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 /// Maximum number of update_ids to track for dedup (bounded FIFO).
 const MAX_SEEN_UPDATES: usize = 10_000;
 
+/// Delay after the first failed `getUpdates` poll; doubles per consecutive
+/// failure up to `POLL_BACKOFF_MAX_SECS`.
+const POLL_BACKOFF_BASE_SECS: u64 = 5;
+const POLL_BACKOFF_MAX_SECS: u64 = 60;
+
+/// Default outbound limits, after Telegram's bot FAQ: about one message per
+/// second to a chat and 30 per second overall.
+const DEFAULT_PER_CHAT_PER_SEC: f64 = 1.0;
+const DEFAULT_GLOBAL_PER_SEC: f64 = 30.0;
+
+/// Longest 429 `retry_after` waited out before the one retry.
+const MAX_RETRY_AFTER_SECS: u64 = 60;
+
+/// Per-chat buckets kept before idle (refilled) ones are dropped.
+const MAX_CHAT_BUCKETS: usize = 1024;
+
+/// Public Bot API server; see [`TelegramChannel::with_api_base`].
+const DEFAULT_API_BASE: &str = "https://api.telegram.org";
+
+/// File under the workspace `state` dir holding the last handled `update_id`.
+const OFFSET_FILE: &str = "telegram_offset";
+
+/// Subdirectory of the system temp dir that auto-downloaded media lands in.
+const AUTO_DOWNLOAD_DIR: &str = "zeroclaw-telegram";
+
+/// Auto-downloaded files older than this are deleted.
+const AUTO_DOWNLOAD_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Max concurrent auto-downloads.
+const AUTO_DOWNLOAD_CONCURRENCY: usize = 4;
+
 /// Bounded seen-set for Telegram update_id dedup.
 struct SeenUpdates {
     set: HashSet<i64>,
@@ -43,6 +78,352 @@ impl SeenUpdates {
     }
 }
 
+/// Un-jittered delay after `consecutive_failures` failed polls in a row:
+/// 5s, 10s, 20s, 40s, then 60s.
+fn poll_backoff_base(consecutive_failures: u32) -> std::time::Duration {
+    let secs = POLL_BACKOFF_BASE_SECS
+        .checked_shl(consecutive_failures.saturating_sub(1))
+        .unwrap_or(u64::MAX)
+        .min(POLL_BACKOFF_MAX_SECS);
+    std::time::Duration::from_secs(secs)
+}
+
+/// `poll_backoff_base` plus up to 1s of jitter, so several bots sharing an
+/// outage don't retry in lockstep.
+fn poll_backoff(consecutive_failures: u32) -> std::time::Duration {
+    let jitter_ms = u64::from(Uuid::new_v4().as_bytes()[0]) * 1000 / 256;
+    poll_backoff_base(consecutive_failures) + std::time::Duration::from_millis(jitter_ms)
+}
+
+/// Token bucket holding up to one second of tokens (and at least one), so a
+/// short burst goes straight out and sustained traffic is paced to `rate`.
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn capacity(rate: f64) -> f64 {
+        rate.max(1.0)
+    }
+
+    fn full(rate: f64, now: Instant) -> Self {
+        Self {
+            tokens: Self::capacity(rate),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, rate: f64, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(Self::capacity(rate));
+        self.updated = now;
+    }
+
+    /// Time until a whole token is available.
+    fn wait(&self, rate: f64) -> Duration {
+        if self.tokens >= 1.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((1.0 - self.tokens) / rate)
+        }
+    }
+}
+
+struct SendBuckets {
+    global: TokenBucket,
+    chats: HashMap<String, TokenBucket>,
+}
+
+/// Outbound rate limit: a send needs a token from its chat's bucket and from
+/// the global one. A non-positive rate turns that bucket off.
+struct SendRateLimiter {
+    per_chat_per_sec: f64,
+    global_per_sec: f64,
+    buckets: Mutex<SendBuckets>,
+}
+
+impl SendRateLimiter {
+    fn new(per_chat_per_sec: f64, global_per_sec: f64) -> Self {
+        Self {
+            per_chat_per_sec,
+            global_per_sec,
+            buckets: Mutex::new(SendBuckets {
+                global: TokenBucket::full(global_per_sec, Instant::now()),
+                chats: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Wait until a send to `chat_id` is allowed, and count it.
+    async fn acquire(&self, chat_id: &str) {
+        loop {
+            let wait = self.try_acquire(chat_id, Instant::now());
+            if wait.is_zero() {
+                return;
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Take a token from both buckets if both have one and return zero;
+    /// otherwise take nothing and return how long to wait before retrying.
+    fn try_acquire(&self, chat_id: &str, now: Instant) -> Duration {
+        let per_chat = self.per_chat_per_sec;
+        let global_rate = self.global_per_sec;
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        let SendBuckets { global, chats } = &mut *buckets;
+
+        if chats.len() >= MAX_CHAT_BUCKETS && !chats.contains_key(chat_id) {
+            chats.retain(|_, bucket| {
+                bucket.refill(per_chat, now);
+                bucket.tokens < TokenBucket::capacity(per_chat)
+            });
+        }
+        let chat = chats
+            .entry(chat_id.to_string())
+            .or_insert_with(|| TokenBucket::full(per_chat, now));
+
+        let mut wait = Duration::ZERO;
+        if per_chat > 0.0 {
+            chat.refill(per_chat, now);
+            wait = wait.max(chat.wait(per_chat));
+        }
+        if global_rate > 0.0 {
+            global.refill(global_rate, now);
+            wait = wait.max(global.wait(global_rate));
+        }
+        if wait.is_zero() {
+            chat.tokens -= 1.0;
+            global.tokens -= 1.0;
+        }
+        wait
+    }
+}
+
+/// How long a 429 body's `parameters.retry_after` asks us to wait (1s if it
+/// is missing), capped at `MAX_RETRY_AFTER_SECS`.
+fn retry_after(body: &str) -> Duration {
+    let secs = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["parameters"]["retry_after"].as_u64())
+        .unwrap_or(1);
+    Duration::from_secs(secs.min(MAX_RETRY_AFTER_SECS))
+}
+
+/// Where the channel started for `workspace_dir` keeps its update offset.
+pub fn offset_state_path(workspace_dir: &Path) -> PathBuf {
+    workspace_dir.join("state").join(OFFSET_FILE)
+}
+
+/// The `update_id` saved by [`save_last_update_id`], if the file exists and
+/// holds one.
+fn load_last_update_id(path: &Path) -> Option<i64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Record `update_id` as the last one handled. Written to a temp file and
+/// renamed over `path`, so a crash mid-write cannot leave it truncated.
+fn save_last_update_id(path: &Path, update_id: i64) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, format!("{update_id}\n"))?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Size cap for auto-downloading `msg_type`, or `None` when auto-download
+/// is off or does not cover that kind of message.
+fn auto_download_cap(config: &TelegramAutoDownloadConfig, msg_type: &str) -> Option<u64> {
+    if !config.enabled {
+        return None;
+    }
+    match msg_type {
+        "photo" => Some(config.max_photo_bytes),
+        "document" => Some(config.max_document_bytes),
+        "voice" => Some(config.max_voice_bytes),
+        _ => None,
+    }
+}
+
+/// Delete files in `dir` last modified more than `max_age` ago. Returns how
+/// many were removed; a missing `dir` counts as empty.
+fn prune_downloads(dir: &Path, max_age: Duration) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if expired && std::fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// What a spawned auto-download needs from the channel.
+struct MediaDownloader {
+    client: reqwest::Client,
+    api_base: String,
+    bot_token: String,
+    attach_base64: bool,
+    observer: Option<Arc<dyn Observer>>,
+}
+
+impl MediaDownloader {
+    /// Download an inbound photo, document, or voice note and attach it to
+    /// `metadata`, as `local_path` (a temp file) or `file_base64`.
+    ///
+    /// Files over `max_bytes` are skipped with a `tg.guard_reject` event;
+    /// download failures are logged. Either way the message keeps its
+    /// `file_id` and is delivered. Writing a file first prunes ones older
+    /// than `AUTO_DOWNLOAD_MAX_AGE`.
+    async fn attach(
+        &self,
+        metadata: &mut HashMap<String, serde_json::Value>,
+        msg_type: &str,
+        file_id: &str,
+        file_size: u64,
+        max_bytes: u64,
+        chat_id: &str,
+    ) {
+        if file_size > max_bytes {
+            if let Some(ref obs) = self.observer {
+                obs.record_event(&ObserverEvent::TelegramEvent {
+                    direction: "inbound".to_string(),
+                    event_type: "tg.guard_reject".to_string(),
+                    status: "rejected".to_string(),
+                    chat_id: chat_id.to_string(),
+                    correlation_id: Uuid::new_v4().to_string(),
+                    duration: None,
+                    metadata: Some(build_telegram_metadata(&[
+                        ("reason", serde_json::json!("file_too_large")),
+                        ("chat_id", serde_json::json!(chat_id)),
+                        ("msg_type", serde_json::json!(msg_type)),
+                        ("file_size", serde_json::json!(file_size)),
+                        ("max_bytes", serde_json::json!(max_bytes)),
+                    ])),
+                });
+            }
+            metadata.insert(
+                "download_skipped".into(),
+                serde_json::json!("file_too_large"),
+            );
+            return;
+        }
+
+        let downloaded = fetch_file_limited(
+            &self.client,
+            &self.api_base,
+            &self.bot_token,
+            file_id,
+            max_bytes,
+        )
+        .await;
+        let (bytes, tg_path) = match downloaded {
+            Ok(downloaded) => downloaded,
+            Err(e) => {
+                tracing::warn!("Telegram auto-download of {msg_type} failed: {e}");
+                metadata.insert("download_error".into(), serde_json::json!(e.to_string()));
+                return;
+            }
+        };
+        metadata.insert("file_size".into(), serde_json::json!(bytes.len()));
+
+        if self.attach_base64 {
+            use base64::Engine;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(&bytes);
+            metadata.insert("file_base64".into(), serde_json::json!(encoded));
+            return;
+        }
+
+        let dir = std::env::temp_dir().join(AUTO_DOWNLOAD_DIR);
+        let mut file_name = Uuid::new_v4().to_string();
+        if let Some(ext) = Path::new(&tg_path).extension().and_then(|e| e.to_str()) {
+            file_name = format!("{file_name}.{ext}");
+        }
+        let local_path = dir.join(file_name);
+        let prune_dir = dir.clone();
+        let _ =
+            tokio::task::spawn_blocking(move || prune_downloads(&prune_dir, AUTO_DOWNLOAD_MAX_AGE))
+                .await;
+        let written = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            tokio::fs::write(&local_path, &bytes).await
+        };
+        match written.await {
+            Ok(()) => {
+                metadata.insert(
+                    "local_path".into(),
+                    serde_json::json!(local_path.display().to_string()),
+                );
+            }
+            Err(e) => {
+                tracing::warn!("Telegram auto-download: writing {msg_type} failed: {e}");
+                metadata.insert("download_error".into(), serde_json::json!(e.to_string()));
+            }
+        }
+    }
+}
+
+/// [`TelegramChannel::download_file_limited`] without the channel, for
+/// spawned tasks.
+async fn fetch_file_limited(
+    client: &reqwest::Client,
+    api_base: &str,
+    bot_token: &str,
+    file_id: &str,
+    max_bytes: u64,
+) -> anyhow::Result<(Vec<u8>, String)> {
+    // Step 1: getFile to get file_path
+    let body = serde_json::json!({ "file_id": file_id });
+    let resp = client
+        .post(format!("{api_base}/bot{bot_token}/getFile"))
+        .json(&body)
+        .send()
+        .await?;
+
+    if !resp.status().is_success() {
+        let err = resp.text().await?;
+        anyhow::bail!("Telegram getFile failed: {err}");
+    }
+
+    let data: serde_json::Value = resp.json().await?;
+    let file_path = data["result"]["file_path"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Telegram getFile: missing file_path"))?
+        .to_string();
+    if let Some(size) = data["result"]["file_size"].as_u64() {
+        if size > max_bytes {
+            anyhow::bail!("Telegram file is {size} bytes, over the {max_bytes} byte limit");
+        }
+    }
+
+    // Step 2: Download the file
+    let download_url = format!("{api_base}/file/bot{bot_token}/{file_path}");
+    let file_resp = client.get(&download_url).send().await?;
+
+    if !file_resp.status().is_success() {
+        anyhow::bail!("Telegram file download failed: {}", file_resp.status());
+    }
+
+    let bytes = file_resp.bytes().await?.to_vec();
+    if bytes.len() as u64 > max_bytes {
+        anyhow::bail!(
+            "Telegram file is {} bytes, over the {max_bytes} byte limit",
+            bytes.len()
+        );
+    }
+    Ok((bytes, file_path))
+}
+
 /// Build allowlist-based metadata JSON for Telegram events.
 /// Only includes explicitly listed fields -- never raw message content.
 pub fn build_telegram_metadata(fields: &[(&str, serde_json::Value)]) -> String {
@@ -53,31 +434,291 @@ pub fn build_telegram_metadata(fields: &[(&str, serde_json::Value)]) -> String {
     serde_json::Value::Object(map).to_string()
 }
 
+/// Sender identity for an inbound update's `ChannelMessage.metadata`:
+/// `from_user_id`, `from_username` and `chat_type` (private/group/supergroup).
+/// `sender` stays the chat id so replies route to the chat; in groups this
+/// is what tells the agent which user spoke.
+fn sender_metadata(
+    from: &serde_json::Value,
+    chat: &serde_json::Value,
+) -> HashMap<String, serde_json::Value> {
+    let mut metadata = HashMap::new();
+    if let Some(id) = from["id"].as_i64() {
+        metadata.insert("from_user_id".into(), serde_json::json!(id.to_string()));
+    }
+    if let Some(username) = from["username"].as_str() {
+        metadata.insert("from_username".into(), serde_json::json!(username));
+    }
+    if let Some(chat_type) = chat["type"].as_str() {
+        metadata.insert("chat_type".into(), serde_json::json!(chat_type));
+    }
+    metadata
+}
+
+/// Longest message Telegram accepts, in UTF-16 code units.
+const TELEGRAM_MAX_MESSAGE_LENGTH: usize = 4096;
+
+/// Opening or closing line of a Markdown code block.
+const CODE_FENCE: &str = "```";
+
+/// Whether allowlist entry `entry` names `identity`: exact when
+/// `case_sensitive`, otherwise ASCII case-insensitive.
+pub(crate) fn identity_matches(entry: &str, identity: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        entry == identity
+    } else {
+        entry.eq_ignore_ascii_case(identity)
+    }
+}
+
+/// Message length as Telegram counts it (UTF-16 code units).
+fn telegram_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Upper bound on the length of `text` once [`escape_markdown_v2`] has
+/// escaped it: every reserved character counted as escaped, even inside
+/// code where most are left alone.
+fn markdown_v2_len(text: &str) -> usize {
+    telegram_len(text) + text.matches(MARKDOWN_V2_RESERVED).count()
+}
+
+/// Split `text` into chunks Telegram will accept. Breaks fall between
+/// paragraphs where possible, then between lines; a line that alone is over
+/// the limit is hard-split. A code block split across chunks is closed at
+/// the end of one and reopened (with its language tag) at the start of the
+/// next, so each chunk renders on its own.
+pub fn split_telegram_message(text: &str) -> Vec<String> {
+    split_message_by(text, telegram_len)
+}
+
+/// Like [`split_telegram_message`], but each chunk still fits once it has
+/// been through [`escape_markdown_v2`].
+pub fn split_markdown_v2_message(text: &str) -> Vec<String> {
+    split_message_by(text, markdown_v2_len)
+}
+
+/// Split `text` into chunks whose `len` is within the message limit.
+fn split_message_by(text: &str, len: fn(&str) -> usize) -> Vec<String> {
+    if len(text) <= TELEGRAM_MAX_MESSAGE_LENGTH {
+        return vec![text.to_string()];
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for block in message_blocks(text) {
+        if len(&current) + len(&block) <= TELEGRAM_MAX_MESSAGE_LENGTH {
+            current.push_str(&block);
+            continue;
+        }
+        chunks.push(std::mem::take(&mut current));
+        if len(&block) <= TELEGRAM_MAX_MESSAGE_LENGTH {
+            current = block;
+            continue;
+        }
+        let mut pieces = split_block(&block, len);
+        current = pieces.pop().unwrap_or_default();
+        chunks.extend(pieces);
+    }
+    chunks.push(current);
+
+    // Blank lines at a split would only pad the neighbouring chunks
+    chunks
+        .iter()
+        .map(|chunk| chunk.trim_start_matches('\n').trim_end())
+        .filter(|chunk| !chunk.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `text` cut into paragraphs (each with its trailing blank line) and whole
+/// code blocks, which are kept together where they fit.
+fn message_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut block = String::new();
+    let mut inside_code = false;
+    for line in text.split_inclusive('\n') {
+        let is_fence = line.trim_start().starts_with(CODE_FENCE);
+        if is_fence && !inside_code && !block.is_empty() {
+            blocks.push(std::mem::take(&mut block));
+        }
+        block.push_str(line);
+        if is_fence {
+            inside_code = !inside_code;
+        }
+        if !inside_code && (is_fence || line.trim().is_empty()) {
+            blocks.push(std::mem::take(&mut block));
+        }
+    }
+    if !block.is_empty() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Split one block that is over the limit at line boundaries, closing and
+/// reopening any code block that a split falls inside.
+fn split_block(block: &str, len: fn(&str) -> usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    // Opening line of the code block the next line belongs to, if any
+    let mut open_fence: Option<String> = None;
+    for line in block.split_inclusive('\n') {
+        // Room left once a split inside a code block adds "\n```" here and
+        // the fence line at the start of the next piece
+        let reserve = open_fence
+            .as_ref()
+            .map_or(0, |fence| len(fence) + 1 + len(CODE_FENCE) + 1);
+        let budget = TELEGRAM_MAX_MESSAGE_LENGTH.saturating_sub(reserve).max(1);
+        for part in hard_split(line, budget, len) {
+            if !current.is_empty() && len(&current) + len(part) > budget {
+                if open_fence.is_some() {
+                    if !current.ends_with('\n') {
+                        current.push('\n');
+                    }
+                    current.push_str(CODE_FENCE);
+                }
+                pieces.push(std::mem::take(&mut current));
+                if let Some(fence) = &open_fence {
+                    current.push_str(fence);
+                    current.push('\n');
+                }
+            }
+            current.push_str(part);
+        }
+        if line.trim_start().starts_with(CODE_FENCE) {
+            open_fence = match open_fence {
+                Some(_) => None,
+                None => Some(line.trim_end().to_string()),
+            };
+        }
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// `line` in pieces whose `len` is at most `max`, cut on char boundaries.
+fn hard_split(line: &str, max: usize, len: fn(&str) -> usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut part_len = 0;
+    for (i, c) in line.char_indices() {
+        let c_len = len(c.encode_utf8(&mut [0; 4]));
+        if part_len + c_len > max && i > start {
+            parts.push(&line[start..i]);
+            start = i;
+            part_len = 0;
+        }
+        part_len += c_len;
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+/// Characters `MarkdownV2` reserves outside code; each must be
+/// backslash-escaped to appear literally.
+const MARKDOWN_V2_RESERVED: &[char] = &[
+    '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!', '\\',
+];
+
+/// Escape `text` for `parse_mode: MarkdownV2` so it renders literally, except
+/// for code: closed triple-backtick blocks and backtick spans are kept as
+/// code, with only the backslashes and backticks inside them escaped, as
+/// Telegram requires.
+pub fn escape_markdown_v2(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 8);
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let fence = if rest.starts_with(CODE_FENCE) {
+            Some(CODE_FENCE)
+        } else if c == '`' {
+            Some("`")
+        } else {
+            None
+        };
+        let code_len = fence.and_then(|fence| {
+            rest[fence.len()..]
+                .find(fence)
+                .filter(|&len| len > 0)
+                .map(|len| (fence, len))
+        });
+        if let Some((fence, len)) = code_len {
+            out.push_str(fence);
+            for c in rest[fence.len()..fence.len() + len].chars() {
+                if c == '\\' || c == '`' {
+                    out.push('\\');
+                }
+                out.push(c);
+            }
+            out.push_str(fence);
+            rest = &rest[2 * fence.len() + len..];
+            continue;
+        }
+
+        if MARKDOWN_V2_RESERVED.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Text of the placeholder sent when `placeholder_replies` is on.
+pub const PLACEHOLDER_TEXT: &str = "⏳ working...";
+
+/// Whether an `editMessageText` error body is Telegram refusing a no-op edit
+/// ("message is not modified"), which happens when the new text and markup
+/// equal the current ones.
+fn is_message_not_modified(err_body: &str) -> bool {
+    err_body.contains("message is not modified")
+}
+
 /// Telegram channel -- long-polls the Bot API for updates
 pub struct TelegramChannel {
+    api_base: String,
     bot_token: String,
     allowed_users: Vec<String>,
+    usernames_case_sensitive: bool,
     client: reqwest::Client,
-    speech: Option<Arc<SpeechClient>>,
+    speech: Option<Arc<dyn SpeechToText>>,
     stt_semaphore: Arc<tokio::sync::Semaphore>,
+    download_semaphore: Arc<tokio::sync::Semaphore>,
     observer: Option<Arc<dyn Observer>>,
     seen_update_ids: Arc<Mutex<SeenUpdates>>,
     flow_db: Option<Arc<crate::flows::db::FlowDb>>,
     approval_registry: Option<Arc<crate::security::approval::ApprovalRegistry>>,
+    auto_download: TelegramAutoDownloadConfig,
+    placeholder_replies: bool,
+    markdown_v2: bool,
+    rate_limiter: SendRateLimiter,
+    offset_path: Option<PathBuf>,
+    quiet_hours: Option<Arc<QuietHoursGate>>,
 }
 
 impl TelegramChannel {
     pub fn new(bot_token: String, allowed_users: Vec<String>) -> Self {
         Self {
+            api_base: DEFAULT_API_BASE.to_string(),
             bot_token,
             allowed_users,
+            usernames_case_sensitive: false,
             client: reqwest::Client::new(),
             speech: None,
             stt_semaphore: Arc::new(tokio::sync::Semaphore::new(STT_CONCURRENCY)),
+            download_semaphore: Arc::new(tokio::sync::Semaphore::new(AUTO_DOWNLOAD_CONCURRENCY)),
             observer: None,
             seen_update_ids: Arc::new(Mutex::new(SeenUpdates::new())),
             flow_db: None,
             approval_registry: None,
+            auto_download: TelegramAutoDownloadConfig::default(),
+            placeholder_replies: false,
+            markdown_v2: false,
+            rate_limiter: SendRateLimiter::new(DEFAULT_PER_CHAT_PER_SEC, DEFAULT_GLOBAL_PER_SEC),
+            offset_path: None,
+            quiet_hours: None,
         }
     }
 
@@ -90,9 +731,16 @@ impl TelegramChannel {
         self
     }
 
-    /// Attach an STT endpoint for voice transcription
-    pub fn with_stt(mut self, stt_endpoint: String) -> Self {
-        self.speech = Some(Arc::new(SpeechClient::new(stt_endpoint)));
+    /// Match allowlisted usernames case-sensitively (default: case-insensitive,
+    /// as Telegram treats usernames).
+    pub fn with_usernames_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.usernames_case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Attach a speech-to-text backend for voice transcription
+    pub fn with_stt(mut self, stt: Arc<dyn SpeechToText>) -> Self {
+        self.speech = Some(stt);
         self
     }
 
@@ -102,12 +750,191 @@ impl TelegramChannel {
         self
     }
 
+    /// Talk to the Bot API at `base` (e.g. a self-hosted `telegram-bot-api`
+    /// server) instead of `https://api.telegram.org`.
+    pub fn with_api_base(mut self, base: impl Into<String>) -> Self {
+        self.api_base = base.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Save the last handled `update_id` to `path` (normally
+    /// [`offset_state_path`]) after each batch, and resume polling after it
+    /// on startup instead of refetching from the start.
+    pub fn with_offset_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.offset_path = Some(path.into());
+        self
+    }
+
+    /// The `getUpdates` offset to start polling from. The offset file is the
+    /// only record when one is configured; a value left in the FlowDb
+    /// `telegram_offset` key by older builds is copied into it once.
+    fn initial_offset(&self) -> i64 {
+        let db_offset = || {
+            self.flow_db
+                .as_ref()
+                .and_then(|db| db.get_kv("telegram_offset").ok().flatten())
+                .and_then(|v| v.parse::<i64>().ok())
+        };
+        let Some(path) = self.offset_path.as_deref() else {
+            return db_offset().unwrap_or(0);
+        };
+        if let Some(uid) = load_last_update_id(path) {
+            return uid + 1;
+        }
+        match db_offset() {
+            Some(offset) if offset > 0 => {
+                if let Err(e) = save_last_update_id(path, offset - 1) {
+                    tracing::warn!(
+                        "Failed to migrate telegram offset to {}: {e}",
+                        path.display()
+                    );
+                }
+                offset
+            }
+            _ => 0,
+        }
+    }
+
+    /// Record `offset` where [`Self::initial_offset`] will look for it.
+    /// Returns whether it was saved.
+    fn persist_offset(&self, offset: i64) -> bool {
+        if let Some(ref path) = self.offset_path {
+            return match save_last_update_id(path, offset - 1) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("Failed to write telegram offset to {}: {e}", path.display());
+                    false
+                }
+            };
+        }
+        match self.flow_db {
+            Some(ref db) => match db.set_kv("telegram_offset", &offset.to_string()) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("Failed to persist telegram offset: {e}");
+                    false
+                }
+            },
+            None => false,
+        }
+    }
+
     /// Attach an observer for Telegram event instrumentation
     pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
         self.observer = Some(observer);
         self
     }
 
+    /// Download inbound media as it arrives (see `TelegramAutoDownloadConfig`).
+    pub fn with_auto_download(mut self, config: TelegramAutoDownloadConfig) -> Self {
+        self.auto_download = config;
+        self
+    }
+
+    /// Answer with a [`PLACEHOLDER_TEXT`] message right away and edit it into
+    /// the final reply, instead of sending the reply as a new message.
+    pub fn with_placeholder_replies(mut self, enabled: bool) -> Self {
+        self.placeholder_replies = enabled;
+        self
+    }
+
+    pub fn placeholder_replies(&self) -> bool {
+        self.placeholder_replies
+    }
+
+    /// Cap outbound sends (messages, edits, media, polls) per chat and
+    /// overall, in messages per second. Defaults to 1 per chat and 30 overall;
+    /// a non-positive rate removes that limit.
+    pub fn with_rate_limit(mut self, per_chat_per_sec: f64, global_per_sec: f64) -> Self {
+        self.rate_limiter = SendRateLimiter::new(per_chat_per_sec, global_per_sec);
+        self
+    }
+
+    /// Send replies as `MarkdownV2` with [`escape_markdown_v2`] applied,
+    /// instead of legacy `Markdown` as written by the model.
+    pub fn with_markdown_v2(mut self, enabled: bool) -> Self {
+        self.markdown_v2 = enabled;
+        self
+    }
+
+    /// Hold new messages (`send*` API calls) made during quiet hours in
+    /// `gate` instead of sending them. Edits, callback answers and chat
+    /// actions are not held.
+    pub fn with_quiet_hours(mut self, gate: Arc<QuietHoursGate>) -> Self {
+        self.quiet_hours = Some(gate);
+        self
+    }
+
+    /// If `method` posts a new message during quiet hours, hold `send` in
+    /// the gate and return the stand-in response the caller gets instead.
+    fn hold_for_quiet_hours(
+        &self,
+        method: &str,
+        send: impl FnOnce() -> DeferredSend,
+    ) -> Option<reqwest::Response> {
+        let gate = self.quiet_hours.as_ref()?;
+        if !method.starts_with("send") {
+            return None;
+        }
+        let until = gate.hold_until(chrono::Utc::now())?;
+        if let Err(e) = gate.defer(self.name(), send(), until) {
+            tracing::warn!("Failed to hold Telegram {method} for quiet hours, sending: {e:#}");
+            return None;
+        }
+        // There is no message yet, so no real message_id to hand back
+        let body = serde_json::json!({
+            "ok": true,
+            "result": { "message_id": 0 },
+            "deferred_until": until.to_rfc3339(),
+        });
+        Some(reqwest::Response::from(axum::http::Response::new(
+            body.to_string(),
+        )))
+    }
+
+    /// Send a request held by [`with_quiet_hours`](Self::with_quiet_hours)
+    /// now, bypassing the gate.
+    pub async fn replay_deferred(&self, send: &DeferredSend) -> anyhow::Result<()> {
+        let resp = match send {
+            DeferredSend::TelegramJson {
+                chat_id,
+                method,
+                body,
+            } => {
+                self.send_limited(chat_id, || {
+                    self.client.post(self.api_url(method)).json(body)
+                })
+                .await?
+            }
+            DeferredSend::TelegramFile {
+                chat_id,
+                method,
+                field,
+                file_name,
+                data,
+                caption,
+            } => {
+                use base64::Engine;
+                let file_bytes = base64::engine::general_purpose::STANDARD.decode(data)?;
+                self.send_file_now(
+                    chat_id,
+                    method,
+                    field,
+                    &file_bytes,
+                    file_name,
+                    caption.as_deref(),
+                )
+                .await?
+            }
+            DeferredSend::Text { .. } => anyhow::bail!("not a Telegram API call"),
+        };
+        if !resp.status().is_success() {
+            let err = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Telegram replay of held message failed: {err}");
+        }
+        Ok(())
+    }
+
     /// Record a Telegram event on the observer (if present).
     fn record_tg_event(
         &self,
@@ -132,11 +959,110 @@ impl TelegramChannel {
     }
 
     pub fn api_url(&self, method: &str) -> String {
-        format!("https://api.telegram.org/bot{}/{method}", self.bot_token)
+        format!("{}/bot{}/{method}", self.api_base, self.bot_token)
+    }
+
+    /// Send the request `build` makes once the rate limiter allows a send to
+    /// `chat_id`. On a 429 the request is rebuilt and sent once more after
+    /// the `retry_after` Telegram asked for; that response is returned as is.
+    async fn send_limited<F>(&self, chat_id: &str, build: F) -> reqwest::Result<reqwest::Response>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.rate_limiter.acquire(chat_id).await;
+        let resp = build().send().await?;
+        if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp);
+        }
+
+        let wait = retry_after(&resp.text().await.unwrap_or_default());
+        tracing::warn!(
+            chat_id,
+            retry_after_secs = wait.as_secs(),
+            "Telegram rate limited the send; retrying once"
+        );
+        tokio::time::sleep(wait).await;
+        self.rate_limiter.acquire(chat_id).await;
+        build().send().await
+    }
+
+    /// POST `body` to API `method` through [`send_limited`](Self::send_limited),
+    /// unless quiet hours hold it.
+    async fn post_json(
+        &self,
+        chat_id: &str,
+        method: &str,
+        body: &serde_json::Value,
+    ) -> reqwest::Result<reqwest::Response> {
+        if let Some(held) = self.hold_for_quiet_hours(method, || DeferredSend::TelegramJson {
+            chat_id: chat_id.to_string(),
+            method: method.to_string(),
+            body: body.clone(),
+        }) {
+            return Ok(held);
+        }
+        self.send_limited(chat_id, || {
+            self.client.post(self.api_url(method)).json(body)
+        })
+        .await
+    }
+
+    /// Upload `file_bytes` as the `field` of API `method`, unless quiet
+    /// hours hold it.
+    async fn post_file(
+        &self,
+        chat_id: &str,
+        method: &str,
+        field: &str,
+        file_bytes: &[u8],
+        file_name: &str,
+        caption: Option<&str>,
+    ) -> reqwest::Result<reqwest::Response> {
+        if let Some(held) = self.hold_for_quiet_hours(method, || {
+            DeferredSend::telegram_file(chat_id, method, field, file_bytes, file_name, caption)
+        }) {
+            return Ok(held);
+        }
+        self.send_file_now(chat_id, method, field, file_bytes, file_name, caption)
+            .await
+    }
+
+    /// Upload `file_bytes` as the `field` of API `method` through
+    /// [`send_limited`](Self::send_limited). Multipart bodies cannot be
+    /// cloned, so each attempt builds its own form.
+    async fn send_file_now(
+        &self,
+        chat_id: &str,
+        method: &str,
+        field: &str,
+        file_bytes: &[u8],
+        file_name: &str,
+        caption: Option<&str>,
+    ) -> reqwest::Result<reqwest::Response> {
+        let form = || {
+            let part = Part::bytes(file_bytes.to_vec()).file_name(file_name.to_string());
+            let form = Form::new()
+                .text("chat_id", chat_id.to_string())
+                .part(field.to_string(), part);
+            match caption {
+                Some(cap) => form.text("caption", cap.to_string()),
+                None => form,
+            }
+        };
+        self.send_limited(chat_id, || {
+            self.client.post(self.api_url(method)).multipart(form())
+        })
+        .await
     }
 
-    pub fn is_user_allowed(&self, username: &str) -> bool {
-        self.allowed_users.iter().any(|u| u == "*" || u == username)
+    /// Whether `identity` (a username or numeric user ID) is allowlisted.
+    /// Usernames compare ASCII case-insensitively unless
+    /// `usernames_case_sensitive` is set; numeric IDs are unaffected by case
+    /// folding and so always match exactly.
+    pub fn is_user_allowed(&self, identity: &str) -> bool {
+        self.allowed_users
+            .iter()
+            .any(|u| u == "*" || identity_matches(u, identity, self.usernames_case_sensitive))
     }
 
     pub fn is_any_user_allowed<'a, I>(&self, identities: I) -> bool
@@ -167,21 +1093,15 @@ impl TelegramChannel {
             .unwrap_or("file");
 
         let file_bytes = tokio::fs::read(file_path).await?;
-        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
-
-        let mut form = Form::new()
-            .text("chat_id", chat_id.to_string())
-            .part("document", part);
-
-        if let Some(cap) = caption {
-            form = form.text("caption", cap.to_string());
-        }
-
         let resp = self
-            .client
-            .post(self.api_url("sendDocument"))
-            .multipart(form)
-            .send()
+            .post_file(
+                chat_id,
+                "sendDocument",
+                "document",
+                &file_bytes,
+                file_name,
+                caption,
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -201,21 +1121,15 @@ impl TelegramChannel {
         file_name: &str,
         caption: Option<&str>,
     ) -> anyhow::Result<()> {
-        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
-
-        let mut form = Form::new()
-            .text("chat_id", chat_id.to_string())
-            .part("document", part);
-
-        if let Some(cap) = caption {
-            form = form.text("caption", cap.to_string());
-        }
-
         let resp = self
-            .client
-            .post(self.api_url("sendDocument"))
-            .multipart(form)
-            .send()
+            .post_file(
+                chat_id,
+                "sendDocument",
+                "document",
+                &file_bytes,
+                file_name,
+                caption,
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -240,21 +1154,15 @@ impl TelegramChannel {
             .unwrap_or("photo.jpg");
 
         let file_bytes = tokio::fs::read(file_path).await?;
-        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
-
-        let mut form = Form::new()
-            .text("chat_id", chat_id.to_string())
-            .part("photo", part);
-
-        if let Some(cap) = caption {
-            form = form.text("caption", cap.to_string());
-        }
-
         let resp = self
-            .client
-            .post(self.api_url("sendPhoto"))
-            .multipart(form)
-            .send()
+            .post_file(
+                chat_id,
+                "sendPhoto",
+                "photo",
+                &file_bytes,
+                file_name,
+                caption,
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -274,21 +1182,15 @@ impl TelegramChannel {
         file_name: &str,
         caption: Option<&str>,
     ) -> anyhow::Result<()> {
-        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
-
-        let mut form = Form::new()
-            .text("chat_id", chat_id.to_string())
-            .part("photo", part);
-
-        if let Some(cap) = caption {
-            form = form.text("caption", cap.to_string());
-        }
-
         let resp = self
-            .client
-            .post(self.api_url("sendPhoto"))
-            .multipart(form)
-            .send()
+            .post_file(
+                chat_id,
+                "sendPhoto",
+                "photo",
+                &file_bytes,
+                file_name,
+                caption,
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -313,21 +1215,15 @@ impl TelegramChannel {
             .unwrap_or("video.mp4");
 
         let file_bytes = tokio::fs::read(file_path).await?;
-        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
-
-        let mut form = Form::new()
-            .text("chat_id", chat_id.to_string())
-            .part("video", part);
-
-        if let Some(cap) = caption {
-            form = form.text("caption", cap.to_string());
-        }
-
         let resp = self
-            .client
-            .post(self.api_url("sendVideo"))
-            .multipart(form)
-            .send()
+            .post_file(
+                chat_id,
+                "sendVideo",
+                "video",
+                &file_bytes,
+                file_name,
+                caption,
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -352,21 +1248,15 @@ impl TelegramChannel {
             .unwrap_or("audio.mp3");
 
         let file_bytes = tokio::fs::read(file_path).await?;
-        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
-
-        let mut form = Form::new()
-            .text("chat_id", chat_id.to_string())
-            .part("audio", part);
-
-        if let Some(cap) = caption {
-            form = form.text("caption", cap.to_string());
-        }
-
         let resp = self
-            .client
-            .post(self.api_url("sendAudio"))
-            .multipart(form)
-            .send()
+            .post_file(
+                chat_id,
+                "sendAudio",
+                "audio",
+                &file_bytes,
+                file_name,
+                caption,
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -391,21 +1281,15 @@ impl TelegramChannel {
             .unwrap_or("voice.ogg");
 
         let file_bytes = tokio::fs::read(file_path).await?;
-        let part = Part::bytes(file_bytes).file_name(file_name.to_string());
-
-        let mut form = Form::new()
-            .text("chat_id", chat_id.to_string())
-            .part("voice", part);
-
-        if let Some(cap) = caption {
-            form = form.text("caption", cap.to_string());
-        }
-
         let resp = self
-            .client
-            .post(self.api_url("sendVoice"))
-            .multipart(form)
-            .send()
+            .post_file(
+                chat_id,
+                "sendVoice",
+                "voice",
+                &file_bytes,
+                file_name,
+                caption,
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -433,12 +1317,7 @@ impl TelegramChannel {
             body["caption"] = serde_json::Value::String(cap.to_string());
         }
 
-        let resp = self
-            .client
-            .post(self.api_url("sendDocument"))
-            .json(&body)
-            .send()
-            .await?;
+        let resp = self.post_json(chat_id, "sendDocument", &body).await?;
 
         if !resp.status().is_success() {
             let err = resp.text().await?;
@@ -465,12 +1344,7 @@ impl TelegramChannel {
             body["caption"] = serde_json::Value::String(cap.to_string());
         }
 
-        let resp = self
-            .client
-            .post(self.api_url("sendPhoto"))
-            .json(&body)
-            .send()
-            .await?;
+        let resp = self.post_json(chat_id, "sendPhoto", &body).await?;
 
         if !resp.status().is_success() {
             let err = resp.text().await?;
@@ -483,6 +1357,24 @@ impl TelegramChannel {
 
     // ── Rich messaging methods ──────────────────────────────────
 
+    /// Send `text` with legacy `Markdown` formatting and return its
+    /// `message_id`, if Telegram reported one. The response is not checked
+    /// for success, so a rejected message yields `None`.
+    pub async fn send_markdown_message(
+        &self,
+        chat_id: &str,
+        text: &str,
+    ) -> anyhow::Result<Option<i64>> {
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+            "parse_mode": "Markdown",
+        });
+        let resp = self.post_json(chat_id, "sendMessage", &body).await?;
+        let data: serde_json::Value = resp.json().await?;
+        Ok(data["result"]["message_id"].as_i64())
+    }
+
     /// Send a message with inline keyboard buttons.
     /// `buttons` is a list of button rows: `[[{text, callback_data}, ...], ...]`
     pub async fn send_with_keyboard(
@@ -513,12 +1405,7 @@ impl TelegramChannel {
             }
         });
 
-        let resp = self
-            .client
-            .post(self.api_url("sendMessage"))
-            .json(&body)
-            .send()
-            .await?;
+        let resp = self.post_json(chat_id, "sendMessage", &body).await?;
 
         if !resp.status().is_success() {
             let err = resp.text().await?;
@@ -561,6 +1448,95 @@ impl TelegramChannel {
         })
     }
 
+    /// Send a message with a reply (custom) keyboard that replaces the user's
+    /// system keyboard. `buttons` is a list of rows of button labels; a press
+    /// sends the label back as an ordinary text message.
+    pub async fn send_with_reply_keyboard(
+        &self,
+        chat_id: &str,
+        text: &str,
+        buttons: &[Vec<String>],
+        options: &ReplyKeyboardOptions,
+    ) -> anyhow::Result<i64> {
+        let body = Self::build_reply_keyboard_json(chat_id, text, buttons, options);
+
+        let resp = self.post_json(chat_id, "sendMessage", &body).await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram sendMessage (reply keyboard) failed: {err}");
+        }
+
+        let data: serde_json::Value = resp.json().await?;
+        let msg_id = data["result"]["message_id"]
+            .as_i64()
+            .unwrap_or_default();
+        Ok(msg_id)
+    }
+
+    /// Build the JSON body for `send_with_reply_keyboard` (for testing).
+    pub fn build_reply_keyboard_json(
+        chat_id: &str,
+        text: &str,
+        buttons: &[Vec<String>],
+        options: &ReplyKeyboardOptions,
+    ) -> serde_json::Value {
+        let keyboard: Vec<Vec<serde_json::Value>> = buttons
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|label| serde_json::json!({ "text": label }))
+                    .collect()
+            })
+            .collect();
+
+        let mut reply_markup = serde_json::json!({
+            "keyboard": keyboard,
+            "one_time_keyboard": options.one_time_keyboard,
+            "resize_keyboard": options.resize_keyboard,
+            "is_persistent": options.is_persistent,
+        });
+        if let Some(ref placeholder) = options.input_field_placeholder {
+            reply_markup["input_field_placeholder"] = serde_json::json!(placeholder);
+        }
+
+        serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+            "reply_markup": reply_markup,
+        })
+    }
+
+    /// Send a message that removes the current reply keyboard, restoring the
+    /// user's system keyboard. Telegram requires a message to carry the removal.
+    pub async fn remove_keyboard(&self, chat_id: &str, text: &str) -> anyhow::Result<i64> {
+        let body = Self::build_remove_keyboard_json(chat_id, text);
+
+        let resp = self.post_json(chat_id, "sendMessage", &body).await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram sendMessage (remove keyboard) failed: {err}");
+        }
+
+        let data: serde_json::Value = resp.json().await?;
+        let msg_id = data["result"]["message_id"]
+            .as_i64()
+            .unwrap_or_default();
+        Ok(msg_id)
+    }
+
+    /// Build the JSON body for `remove_keyboard` (for testing).
+    pub fn build_remove_keyboard_json(chat_id: &str, text: &str) -> serde_json::Value {
+        serde_json::json!({
+            "chat_id": chat_id,
+            "text": text,
+            "reply_markup": {
+                "remove_keyboard": true,
+            }
+        })
+    }
+
     /// Edit an existing message's text (and optionally its inline keyboard).
     pub async fn edit_message_text(
         &self,
@@ -594,12 +1570,7 @@ impl TelegramChannel {
             });
         }
 
-        let resp = self
-            .client
-            .post(self.api_url("editMessageText"))
-            .json(&body)
-            .send()
-            .await?;
+        let resp = self.post_json(chat_id, "editMessageText", &body).await?;
 
         if !resp.status().is_success() {
             let err = resp.text().await?;
@@ -609,39 +1580,275 @@ impl TelegramChannel {
         Ok(())
     }
 
-    /// Build the JSON body for `edit_message_text` (for testing).
-    pub fn build_edit_json(
-        chat_id: &str,
-        message_id: i64,
-        text: &str,
-        buttons: Option<&[Vec<InlineButton>]>,
-    ) -> serde_json::Value {
-        let mut body = serde_json::json!({
+    /// Build the JSON body for `edit_message_text` (for testing).
+    pub fn build_edit_json(
+        chat_id: &str,
+        message_id: i64,
+        text: &str,
+        buttons: Option<&[Vec<InlineButton>]>,
+    ) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "chat_id": chat_id,
+            "message_id": message_id,
+            "text": text,
+        });
+
+        if let Some(btns) = buttons {
+            let keyboard: Vec<Vec<serde_json::Value>> = btns
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|b| {
+                            serde_json::json!({
+                                "text": b.text,
+                                "callback_data": b.callback_data,
+                            })
+                        })
+                        .collect()
+                })
+                .collect();
+            body["reply_markup"] = serde_json::json!({
+                "inline_keyboard": keyboard,
+            });
+        }
+
+        body
+    }
+
+    /// Send [`PLACEHOLDER_TEXT`] to `chat_id` and return its `message_id`, for
+    /// [`finish_placeholder`](Self::finish_placeholder) to fill in later.
+    /// Returns `None` during quiet hours: the reply itself is held then, and a
+    /// placeholder delivered with it would only add noise.
+    pub async fn send_placeholder(&self, chat_id: &str) -> anyhow::Result<Option<i64>> {
+        if let Some(ref gate) = self.quiet_hours {
+            if gate.hold_until(chrono::Utc::now()).is_some() {
+                return Ok(None);
+            }
+        }
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": PLACEHOLDER_TEXT,
+        });
+
+        let resp = self.post_json(chat_id, "sendMessage", &body).await?;
+
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram sendMessage (placeholder) failed: {err}");
+        }
+
+        let data: serde_json::Value = resp.json().await?;
+        data["result"]["message_id"]
+            .as_i64()
+            .map(Some)
+            .ok_or_else(|| anyhow::anyhow!("Telegram sendMessage returned no message_id"))
+    }
+
+    /// Replace a placeholder from [`send_placeholder`](Self::send_placeholder)
+    /// with `text`. A reply too long for one message fills the placeholder
+    /// with its first chunk and sends the rest as new messages. If the edit
+    /// fails the placeholder is deleted and the whole reply sent as new
+    /// messages, so it is never left showing [`PLACEHOLDER_TEXT`].
+    pub async fn finish_placeholder(
+        &self,
+        chat_id: &str,
+        message_id: i64,
+        text: &str,
+    ) -> anyhow::Result<()> {
+        let mut chunks = self.split_reply(text).into_iter();
+        let first = chunks.next().unwrap_or_default();
+        if let Err(e) = self.edit_placeholder(chat_id, message_id, &first).await {
+            tracing::warn!("Telegram placeholder edit failed, sending instead: {e}");
+            if let Err(e) = self.delete_message(chat_id, message_id).await {
+                tracing::warn!("Failed to delete Telegram placeholder: {e}");
+            }
+            return self.send_chunks(chat_id, text).await;
+        }
+        for chunk in chunks {
+            self.send_text(chat_id, &chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete message `message_id` from `chat_id`.
+    pub async fn delete_message(&self, chat_id: &str, message_id: i64) -> anyhow::Result<()> {
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "message_id": message_id,
+        });
+        let resp = self.post_json(chat_id, "deleteMessage", &body).await?;
+        if !resp.status().is_success() {
+            let err = resp.text().await?;
+            anyhow::bail!("Telegram deleteMessage failed: {err}");
+        }
+        Ok(())
+    }
+
+    /// Like `send_text` but editing `message_id`. An edit that would leave
+    /// the message unchanged counts as done.
+    async fn edit_placeholder(
+        &self,
+        chat_id: &str,
+        message_id: i64,
+        text: &str,
+    ) -> anyhow::Result<()> {
+        let (parse_mode, formatted) = if self.markdown_v2 {
+            ("MarkdownV2", escape_markdown_v2(text))
+        } else {
+            ("Markdown", text.to_string())
+        };
+        let mut body = Self::build_edit_json(chat_id, message_id, &formatted, None);
+        body["parse_mode"] = serde_json::json!(parse_mode);
+
+        let markdown_resp = self.post_json(chat_id, "editMessageText", &body).await?;
+
+        if markdown_resp.status().is_success() {
+            return Ok(());
+        }
+
+        let markdown_status = markdown_resp.status();
+        let markdown_err = markdown_resp.text().await.unwrap_or_default();
+        if is_message_not_modified(&markdown_err) {
+            return Ok(());
+        }
+        tracing::warn!(
+            status = ?markdown_status,
+            "Telegram editMessageText with {parse_mode} failed; retrying without parse_mode"
+        );
+
+        let plain_body = Self::build_edit_json(chat_id, message_id, text, None);
+        let plain_resp = self
+            .post_json(chat_id, "editMessageText", &plain_body)
+            .await?;
+
+        if !plain_resp.status().is_success() {
+            let plain_status = plain_resp.status();
+            let plain_err = plain_resp.text().await.unwrap_or_default();
+            if is_message_not_modified(&plain_err) {
+                return Ok(());
+            }
+            anyhow::bail!(
+                "Telegram editMessageText failed ({} {}: {}; plain {}: {})",
+                parse_mode,
+                markdown_status,
+                markdown_err,
+                plain_status,
+                plain_err
+            );
+        }
+
+        Ok(())
+    }
+
+    /// `text` split to fit the length limit in the parse mode replies use.
+    fn split_reply(&self, text: &str) -> Vec<String> {
+        if self.markdown_v2 {
+            split_markdown_v2_message(text)
+        } else {
+            split_telegram_message(text)
+        }
+    }
+
+    /// Send `text` as new messages, split to fit the length limit.
+    async fn send_chunks(&self, chat_id: &str, text: &str) -> anyhow::Result<()> {
+        for chunk in self.split_reply(text) {
+            self.send_text(chat_id, &chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// Send one message of at most `TELEGRAM_MAX_MESSAGE_LENGTH`, trying
+    /// Markdown (or `MarkdownV2`, if enabled) first and falling back to plain text.
+    async fn send_text(&self, chat_id: &str, message: &str) -> anyhow::Result<()> {
+        if self.markdown_v2 {
+            return self.send_markdown_v2(chat_id, message).await;
+        }
+
+        let markdown_body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": message,
+            "parse_mode": "Markdown"
+        });
+
+        let markdown_resp = self
+            .post_json(chat_id, "sendMessage", &markdown_body)
+            .await?;
+
+        if markdown_resp.status().is_success() {
+            return Ok(());
+        }
+
+        let markdown_status = markdown_resp.status();
+        let markdown_err = markdown_resp.text().await.unwrap_or_default();
+        tracing::warn!(
+            status = ?markdown_status,
+            "Telegram sendMessage with Markdown failed; retrying without parse_mode"
+        );
+
+        // Retry without parse_mode as a compatibility fallback.
+        let plain_body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": message,
+        });
+        let plain_resp = self.post_json(chat_id, "sendMessage", &plain_body).await?;
+
+        if !plain_resp.status().is_success() {
+            let plain_status = plain_resp.status();
+            let plain_err = plain_resp.text().await.unwrap_or_default();
+            anyhow::bail!(
+                "Telegram sendMessage failed (markdown {}: {}; plain {}: {})",
+                markdown_status,
+                markdown_err,
+                plain_status,
+                plain_err
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Send `message` escaped with [`escape_markdown_v2`] as `MarkdownV2`,
+    /// falling back to plain text if Telegram still rejects it.
+    pub async fn send_markdown_v2(&self, chat_id: &str, message: &str) -> anyhow::Result<()> {
+        let v2_body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": escape_markdown_v2(message),
+            "parse_mode": "MarkdownV2"
+        });
+        let v2_resp = self.post_json(chat_id, "sendMessage", &v2_body).await?;
+
+        if v2_resp.status().is_success() {
+            tracing::debug!(chat_id, "Telegram sendMessage delivered as MarkdownV2");
+            return Ok(());
+        }
+
+        let v2_status = v2_resp.status();
+        let v2_err = v2_resp.text().await.unwrap_or_default();
+        tracing::warn!(
+            status = ?v2_status,
+            "Telegram sendMessage with MarkdownV2 failed; retrying without parse_mode"
+        );
+
+        let plain_body = serde_json::json!({
             "chat_id": chat_id,
-            "message_id": message_id,
-            "text": text,
+            "text": message,
         });
+        let plain_resp = self.post_json(chat_id, "sendMessage", &plain_body).await?;
 
-        if let Some(btns) = buttons {
-            let keyboard: Vec<Vec<serde_json::Value>> = btns
-                .iter()
-                .map(|row| {
-                    row.iter()
-                        .map(|b| {
-                            serde_json::json!({
-                                "text": b.text,
-                                "callback_data": b.callback_data,
-                            })
-                        })
-                        .collect()
-                })
-                .collect();
-            body["reply_markup"] = serde_json::json!({
-                "inline_keyboard": keyboard,
-            });
+        if !plain_resp.status().is_success() {
+            let plain_status = plain_resp.status();
+            let plain_err = plain_resp.text().await.unwrap_or_default();
+            anyhow::bail!(
+                "Telegram sendMessage failed (MarkdownV2 {}: {}; plain {}: {})",
+                v2_status,
+                v2_err,
+                plain_status,
+                plain_err
+            );
         }
 
-        body
+        tracing::info!(chat_id, "Telegram sendMessage delivered as plain text");
+        Ok(())
     }
 
     /// Send a poll to a Telegram chat.
@@ -659,12 +1866,7 @@ impl TelegramChannel {
             "is_anonymous": is_anonymous,
         });
 
-        let resp = self
-            .client
-            .post(self.api_url("sendPoll"))
-            .json(&body)
-            .send()
-            .await?;
+        let resp = self.post_json(chat_id, "sendPoll", &body).await?;
 
         if !resp.status().is_success() {
             let err = resp.text().await?;
@@ -693,12 +1895,7 @@ impl TelegramChannel {
             "is_anonymous": is_anonymous,
         });
 
-        let resp = self
-            .client
-            .post(self.api_url("sendPoll"))
-            .json(&body)
-            .send()
-            .await?;
+        let resp = self.post_json(chat_id, "sendPoll", &body).await?;
 
         if !resp.status().is_success() {
             let err = resp.text().await?;
@@ -764,39 +1961,72 @@ impl TelegramChannel {
     /// Download a file from Telegram by file_id.
     /// Returns `(bytes, file_path_on_telegram)`.
     pub async fn download_file(&self, file_id: &str) -> anyhow::Result<(Vec<u8>, String)> {
-        // Step 1: getFile to get file_path
-        let body = serde_json::json!({ "file_id": file_id });
-        let resp = self
-            .client
-            .post(self.api_url("getFile"))
-            .json(&body)
-            .send()
-            .await?;
-
-        if !resp.status().is_success() {
-            let err = resp.text().await?;
-            anyhow::bail!("Telegram getFile failed: {err}");
-        }
-
-        let data: serde_json::Value = resp.json().await?;
-        let file_path = data["result"]["file_path"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Telegram getFile: missing file_path"))?
-            .to_string();
-
-        // Step 2: Download the file
-        let download_url = format!(
-            "https://api.telegram.org/file/bot{}/{}",
-            self.bot_token, file_path
-        );
-        let file_resp = self.client.get(&download_url).send().await?;
+        self.download_file_limited(file_id, u64::MAX).await
+    }
 
-        if !file_resp.status().is_success() {
-            anyhow::bail!("Telegram file download failed: {}", file_resp.status());
+    /// Like `download_file`, but refuses files larger than `max_bytes`,
+    /// checking the size `getFile` reports before fetching and the actual
+    /// body after.
+    pub async fn download_file_limited(
+        &self,
+        file_id: &str,
+        max_bytes: u64,
+    ) -> anyhow::Result<(Vec<u8>, String)> {
+        fetch_file_limited(
+            &self.client,
+            &self.api_base,
+            &self.bot_token,
+            file_id,
+            max_bytes,
+        )
+        .await
+    }
+
+    /// Deliver `msg`, first auto-downloading its media into its metadata
+    /// (see [`MediaDownloader::attach`]). The download runs in its own task,
+    /// as voice transcription does, so a large or slow file does not hold up
+    /// polling for every chat. Without auto-download for `msg_type`, `msg` is
+    /// sent straight away. Returns `false` once the receiver is gone.
+    async fn send_media_message(
+        &self,
+        tx: &tokio::sync::mpsc::Sender<ChannelMessage>,
+        mut msg: ChannelMessage,
+        msg_type: &str,
+        file_id: &str,
+        file_size: u64,
+    ) -> bool {
+        let Some(max_bytes) = auto_download_cap(&self.auto_download, msg_type) else {
+            return tx.send(msg).await.is_ok();
+        };
+        if file_id.is_empty() {
+            return tx.send(msg).await.is_ok();
         }
-
-        let bytes = file_resp.bytes().await?.to_vec();
-        Ok((bytes, file_path))
+        let downloader = MediaDownloader {
+            client: self.client.clone(),
+            api_base: self.api_base.clone(),
+            bot_token: self.bot_token.clone(),
+            attach_base64: self.auto_download.attach_base64,
+            observer: self.observer.clone(),
+        };
+        let semaphore = self.download_semaphore.clone();
+        let tx = tx.clone();
+        let msg_type = msg_type.to_string();
+        let file_id = file_id.to_string();
+        tokio::spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            downloader
+                .attach(
+                    &mut msg.metadata,
+                    &msg_type,
+                    &file_id,
+                    file_size,
+                    max_bytes,
+                    &msg.sender,
+                )
+                .await;
+            let _ = tx.send(msg).await;
+        });
+        true
     }
 }
 
@@ -807,69 +2037,17 @@ impl Channel for TelegramChannel {
     }
 
     async fn send(&self, message: &str, reply_to: &ChannelMessage) -> anyhow::Result<()> {
-        let chat_id = &reply_to.sender;
-        let markdown_body = serde_json::json!({
-            "chat_id": chat_id,
-            "text": message,
-            "parse_mode": "Markdown"
-        });
-
-        let markdown_resp = self
-            .client
-            .post(self.api_url("sendMessage"))
-            .json(&markdown_body)
-            .send()
-            .await?;
-
-        if markdown_resp.status().is_success() {
-            return Ok(());
-        }
-
-        let markdown_status = markdown_resp.status();
-        let markdown_err = markdown_resp.text().await.unwrap_or_default();
-        tracing::warn!(
-            status = ?markdown_status,
-            "Telegram sendMessage with Markdown failed; retrying without parse_mode"
-        );
-
-        // Retry without parse_mode as a compatibility fallback.
-        let plain_body = serde_json::json!({
-            "chat_id": chat_id,
-            "text": message,
-        });
-        let plain_resp = self
-            .client
-            .post(self.api_url("sendMessage"))
-            .json(&plain_body)
-            .send()
-            .await?;
-
-        if !plain_resp.status().is_success() {
-            let plain_status = plain_resp.status();
-            let plain_err = plain_resp.text().await.unwrap_or_default();
-            anyhow::bail!(
-                "Telegram sendMessage failed (markdown {}: {}; plain {}: {})",
-                markdown_status,
-                markdown_err,
-                plain_status,
-                plain_err
-            );
-        }
-
-        Ok(())
+        self.send_chunks(&reply_to.sender, message).await
     }
 
     async fn listen(&self, tx: tokio::sync::mpsc::Sender<ChannelMessage>) -> anyhow::Result<()> {
         // Load persisted offset (at-least-once semantics)
-        let mut offset: i64 = self
-            .flow_db
-            .as_ref()
-            .and_then(|db| db.get_kv("telegram_offset").ok().flatten())
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(0);
+        let mut offset = self.initial_offset();
+        let mut saved_offset = offset;
 
         tracing::info!("Telegram channel listening for messages (offset={offset})...");
 
+        let mut consecutive_failures: u32 = 0;
         loop {
             let url = self.api_url("getUpdates");
             let body = serde_json::json!({
@@ -878,20 +2056,41 @@ impl Channel for TelegramChannel {
                 "allowed_updates": ["message", "callback_query", "poll_answer"]
             });
 
-            let resp = match self.client.post(&url).json(&body).send().await {
-                Ok(r) => r,
-                Err(e) => {
-                    tracing::warn!("Telegram poll error: {e}");
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
-                    continue;
-                }
+            let polled = match self.client.post(&url).json(&body).send().await {
+                Ok(resp) => resp
+                    .json::<serde_json::Value>()
+                    .await
+                    .map_err(|e| ("parse", e)),
+                Err(e) => Err(("network", e)),
             };
-
-            let data: serde_json::Value = match resp.json().await {
-                Ok(d) => d,
-                Err(e) => {
-                    tracing::warn!("Telegram parse error: {e}");
-                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            let data = match polled {
+                Ok(d) => {
+                    consecutive_failures = 0;
+                    d
+                }
+                Err((kind, e)) => {
+                    consecutive_failures = consecutive_failures.saturating_add(1);
+                    let backoff = poll_backoff(consecutive_failures);
+                    tracing::warn!(
+                        "Telegram poll {kind} error ({consecutive_failures} in a row, retrying in {}ms): {e}",
+                        backoff.as_millis()
+                    );
+                    self.record_tg_event(
+                        "inbound",
+                        "tg.poll_error",
+                        "error",
+                        "",
+                        None,
+                        Some(build_telegram_metadata(&[
+                            ("kind", serde_json::json!(kind)),
+                            (
+                                "consecutive_failures",
+                                serde_json::json!(consecutive_failures),
+                            ),
+                            ("backoff_ms", serde_json::json!(backoff.as_millis() as u64)),
+                        ])),
+                    );
+                    tokio::time::sleep(backoff).await;
                     continue;
                 }
             };
@@ -997,7 +2196,7 @@ impl Channel for TelegramChannel {
                             ])),
                         );
 
-                        let mut metadata = HashMap::new();
+                        let mut metadata = sender_metadata(&cb["from"], &cb["message"]["chat"]);
                         metadata.insert("msg_type".into(), serde_json::json!("callback_query"));
                         metadata.insert("callback_query_id".into(), serde_json::json!(cb_id));
                         metadata.insert("original_message_id".into(), serde_json::json!(original_msg_id));
@@ -1178,7 +2377,7 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                             .to_string();
                         let duration = voice["duration"].as_u64().unwrap_or(0);
 
-                        let mut metadata = HashMap::new();
+                        let mut metadata = sender_metadata(&message["from"], &message["chat"]);
                         metadata.insert("msg_type".into(), serde_json::json!("voice"));
                         metadata.insert("file_id".into(), serde_json::json!(file_id));
                         metadata.insert("duration".into(), serde_json::json!(duration));
@@ -1270,12 +2469,11 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                             let speech = speech.clone();
                             let tx = tx.clone();
                             let semaphore = self.stt_semaphore.clone();
-                            let bot_token = self.bot_token.clone();
                             let client = self.client.clone();
                             let observer = self.observer.clone();
                             let stt_chat_id = chat_id.clone();
-                            let api_base =
-                                format!("https://api.telegram.org/bot{}", bot_token);
+                            let api_base = format!("{}/bot{}", self.api_base, self.bot_token);
+                            let file_base = format!("{}/file/bot{}", self.api_base, self.bot_token);
 
                             tokio::spawn(async move {
                                 // Bounded concurrency
@@ -1376,10 +2574,7 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                                     }
                                 };
 
-                                let download_url = format!(
-                                    "https://api.telegram.org/file/bot{}/{}",
-                                    bot_token, file_path
-                                );
+                                let download_url = format!("{file_base}/{file_path}");
                                 let audio_bytes = match client.get(&download_url).send().await {
                                     Ok(r) => match r.bytes().await {
                                         Ok(b) => b.to_vec(),
@@ -1517,7 +2712,10 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                                 .as_secs(),
                             metadata,
                         };
-                        if tx.send(msg).await.is_err() {
+                        if !self
+                            .send_media_message(&tx, msg, "voice", &file_id, file_size)
+                            .await
+                        {
                             return Ok(());
                         }
                         continue;
@@ -1542,8 +2740,9 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                             .and_then(|p| p["file_id"].as_str())
                             .unwrap_or_default()
                             .to_string();
+                        let file_size = best.and_then(|p| p["file_size"].as_u64()).unwrap_or(0);
 
-                        let mut metadata = HashMap::new();
+                        let mut metadata = sender_metadata(&message["from"], &message["chat"]);
                         metadata.insert("msg_type".into(), serde_json::json!("photo"));
                         metadata.insert("file_id".into(), serde_json::json!(file_id));
                         if let Some(ref uid) = user_id_str {
@@ -1567,7 +2766,10 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                             metadata,
                         };
 
-                        if tx.send(msg).await.is_err() {
+                        if !self
+                            .send_media_message(&tx, msg, "photo", &file_id, file_size)
+                            .await
+                        {
                             return Ok(());
                         }
                         continue;
@@ -1598,8 +2800,9 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                             .as_str()
                             .unwrap_or("application/octet-stream")
                             .to_string();
+                        let file_size = doc["file_size"].as_u64().unwrap_or(0);
 
-                        let mut metadata = HashMap::new();
+                        let mut metadata = sender_metadata(&message["from"], &message["chat"]);
                         metadata.insert("msg_type".into(), serde_json::json!("document"));
                         metadata.insert("file_id".into(), serde_json::json!(file_id));
                         metadata.insert("file_name".into(), serde_json::json!(file_name));
@@ -1622,7 +2825,10 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                             metadata,
                         };
 
-                        if tx.send(msg).await.is_err() {
+                        if !self
+                            .send_media_message(&tx, msg, "document", &file_id, file_size)
+                            .await
+                        {
                             return Ok(());
                         }
                         continue;
@@ -1657,7 +2863,7 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                         .send()
                         .await; // Ignore errors for typing indicator
 
-                    let mut metadata = HashMap::new();
+                    let mut metadata = sender_metadata(&message["from"], &message["chat"]);
                     metadata.insert("msg_type".into(), serde_json::json!("text"));
                     if let Some(ref uid) = user_id_str {
                         metadata.insert("user_id".into(), serde_json::json!(uid));
@@ -1681,28 +2887,39 @@ Allowlist Telegram @username or numeric user ID, then run `zeroclaw onboard --ch
                 }
 
                 // Persist offset after processing the batch (at-least-once)
-                if let Some(ref db) = self.flow_db {
-                    if let Err(e) = db.set_kv("telegram_offset", &offset.to_string()) {
-                        tracing::warn!("Failed to persist telegram offset: {e}");
-                    }
+                if offset != saved_offset && self.persist_offset(offset) {
+                    saved_offset = offset;
                 }
             }
         }
     }
 
     async fn health_check(&self) -> bool {
-        self.client
+        self.check_health().await.is_ok()
+    }
+
+    async fn check_health(&self) -> anyhow::Result<()> {
+        // The URL holds the bot token, so keep it out of the error
+        let resp = self
+            .client
             .get(self.api_url("getMe"))
             .send()
             .await
-            .map(|r| r.status().is_success())
-            .unwrap_or(false)
+            .map_err(|e| anyhow::anyhow!("Telegram getMe failed: {}", e.without_url()))?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        let description = body["description"].as_str().unwrap_or("no description");
+        anyhow::bail!("Telegram getMe failed ({status}): {description}")
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::channels::telegram_types::TranscriptResult;
 
     #[test]
     fn telegram_channel_name() {
@@ -1710,6 +2927,125 @@ mod tests {
         assert_eq!(ch.name(), "telegram");
     }
 
+    #[test]
+    fn poll_backoff_doubles_to_cap() {
+        let secs: Vec<u64> = (1..=7).map(|n| poll_backoff_base(n).as_secs()).collect();
+        assert_eq!(secs, [5, 10, 20, 40, 60, 60, 60]);
+        assert_eq!(poll_backoff_base(u32::MAX).as_secs(), 60);
+
+        let jittered = poll_backoff(1);
+        assert!(jittered >= std::time::Duration::from_secs(5));
+        assert!(jittered < std::time::Duration::from_secs(6));
+    }
+
+    #[test]
+    fn offset_file_round_trips_and_creates_state_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = offset_state_path(tmp.path());
+        assert_eq!(path, tmp.path().join("state").join(OFFSET_FILE));
+        assert_eq!(load_last_update_id(&path), None);
+
+        save_last_update_id(&path, 123_456_789).unwrap();
+        assert_eq!(load_last_update_id(&path), Some(123_456_789));
+        save_last_update_id(&path, 123_456_790).unwrap();
+        assert_eq!(load_last_update_id(&path), Some(123_456_790));
+        assert!(!path.with_extension("tmp").exists());
+
+        std::fs::write(&path, "garbage").unwrap();
+        assert_eq!(load_last_update_id(&path), None);
+
+        let ch = TelegramChannel::new("t".into(), vec![]).with_offset_path(&path);
+        assert_eq!(ch.offset_path.as_deref(), Some(path.as_path()));
+    }
+
+    #[test]
+    fn startup_resumes_after_the_saved_update_id() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = offset_state_path(tmp.path());
+        let ch = TelegramChannel::new("t".into(), vec![]).with_offset_path(&path);
+        assert_eq!(ch.initial_offset(), 0);
+
+        assert!(ch.persist_offset(42));
+        assert_eq!(load_last_update_id(&path), Some(41));
+        let restarted = TelegramChannel::new("t".into(), vec![]).with_offset_path(&path);
+        assert_eq!(restarted.initial_offset(), 42);
+    }
+
+    #[test]
+    fn flow_db_offset_migrates_to_the_offset_file_once() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = offset_state_path(tmp.path());
+        let db = Arc::new(crate::flows::db::FlowDb::open_in_memory().unwrap());
+        db.set_kv("telegram_offset", "100").unwrap();
+        let ch = TelegramChannel::new("t".into(), vec![])
+            .with_flow_db(db.clone())
+            .with_offset_path(&path);
+
+        assert_eq!(ch.initial_offset(), 100);
+        assert_eq!(load_last_update_id(&path), Some(99));
+
+        // From here on the file is the only record
+        assert!(ch.persist_offset(105));
+        assert_eq!(
+            db.get_kv("telegram_offset").unwrap().as_deref(),
+            Some("100")
+        );
+        assert_eq!(ch.initial_offset(), 105);
+    }
+
+    #[test]
+    fn rate_limiter_delays_second_send_to_an_empty_chat_bucket() {
+        let limiter = SendRateLimiter::new(1.0, 30.0);
+        let t0 = Instant::now();
+        assert_eq!(limiter.try_acquire("42", t0), Duration::ZERO);
+        assert_eq!(limiter.try_acquire("42", t0), Duration::from_secs(1));
+        // Other chats have their own bucket
+        assert_eq!(limiter.try_acquire("7", t0), Duration::ZERO);
+
+        let half = t0 + Duration::from_millis(500);
+        assert_eq!(limiter.try_acquire("42", half), Duration::from_millis(500));
+        let t1 = t0 + Duration::from_secs(1);
+        assert_eq!(limiter.try_acquire("42", t1), Duration::ZERO);
+    }
+
+    #[test]
+    fn rate_limiter_global_bucket_spans_chats() {
+        let limiter = SendRateLimiter::new(10.0, 2.0);
+        let t0 = Instant::now();
+        assert_eq!(limiter.try_acquire("1", t0), Duration::ZERO);
+        assert_eq!(limiter.try_acquire("2", t0), Duration::ZERO);
+        assert_eq!(limiter.try_acquire("3", t0), Duration::from_millis(500));
+        // A refused send takes no token from its chat
+        let chat_tokens = limiter.buckets.lock().unwrap().chats["3"].tokens;
+        assert!((chat_tokens - 10.0).abs() < f64::EPSILON);
+
+        let unlimited = SendRateLimiter::new(0.0, 0.0);
+        for _ in 0..100 {
+            assert_eq!(unlimited.try_acquire("1", t0), Duration::ZERO);
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_acquire_waits_for_a_token() {
+        let limiter = SendRateLimiter::new(20.0, 1000.0);
+        let start = Instant::now();
+        for _ in 0..20 {
+            limiter.acquire("42").await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+        limiter.acquire("42").await;
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn retry_after_reads_429_body() {
+        let body = r#"{"ok":false,"error_code":429,"parameters":{"retry_after":7}}"#;
+        assert_eq!(retry_after(body), Duration::from_secs(7));
+        assert_eq!(retry_after("not json"), Duration::from_secs(1));
+        let huge = r#"{"parameters":{"retry_after":3600}}"#;
+        assert_eq!(retry_after(huge), Duration::from_secs(MAX_RETRY_AFTER_SECS));
+    }
+
     #[test]
     fn telegram_api_url() {
         let ch = TelegramChannel::new("123:ABC".into(), vec![]);
@@ -1719,6 +3055,121 @@ mod tests {
         );
     }
 
+    #[test]
+    fn placeholder_replies_default_off_and_no_op_edits_detected() {
+        let ch = TelegramChannel::new("t".into(), vec![]);
+        assert!(!ch.placeholder_replies());
+        assert!(ch.with_placeholder_replies(true).placeholder_replies());
+
+        assert!(is_message_not_modified(
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: message is not modified"}"#
+        ));
+        assert!(!is_message_not_modified(
+            r#"{"ok":false,"error_code":400,"description":"Bad Request: message to edit not found"}"#
+        ));
+    }
+
+    #[test]
+    fn escape_markdown_v2_escapes_reserved_chars() {
+        assert_eq!(
+            escape_markdown_v2("snake_case costs $1.50 (approx.) - done!"),
+            r"snake\_case costs $1\.50 \(approx\.\) \- done\!"
+        );
+        assert_eq!(
+            escape_markdown_v2(r"_*[]()~`>#+-=|{}.!\"),
+            r"\_\*\[\]\(\)\~\`\>\#\+\-\=\|\{\}\.\!\\"
+        );
+        assert_eq!(escape_markdown_v2("héllo 🦀"), "héllo 🦀");
+    }
+
+    #[test]
+    fn escape_markdown_v2_leaves_code_alone() {
+        assert_eq!(
+            escape_markdown_v2("run `cargo build -p my_crate` now."),
+            r"run `cargo build -p my_crate` now\."
+        );
+        assert_eq!(
+            escape_markdown_v2("```rust\nlet s = \"a\\n`b`\";\n```\nok."),
+            "```rust\nlet s = \"a\\\\n\\`b\\`\";\n```\nok\\."
+        );
+        // Unclosed or empty code markers are plain text
+        assert_eq!(escape_markdown_v2("a ` b"), r"a \` b");
+        assert_eq!(escape_markdown_v2("``x"), r"\`\`x");
+    }
+
+    #[test]
+    fn split_telegram_message_keeps_short_text_whole() {
+        assert_eq!(split_telegram_message("hello"), vec!["hello"]);
+        let exact = "a".repeat(TELEGRAM_MAX_MESSAGE_LENGTH);
+        assert_eq!(split_telegram_message(&exact), vec![exact.clone()]);
+    }
+
+    #[test]
+    fn split_telegram_message_breaks_between_paragraphs() {
+        let para = "word ".repeat(300);
+        let text = format!("{para}\n\n{para}\n\n{para}");
+        let chunks = split_telegram_message(&text);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], format!("{para}\n\n{para}").trim_end());
+        assert_eq!(chunks[1], para.trim_end());
+    }
+
+    #[test]
+    fn split_telegram_message_hard_splits_long_lines() {
+        // 2 UTF-16 units each: the limit is 2048 of these, not 4096
+        let text = "🦀".repeat(5000);
+        let chunks = split_telegram_message(&text);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|c| telegram_len(c) <= TELEGRAM_MAX_MESSAGE_LENGTH));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn split_telegram_message_reopens_split_code_blocks() {
+        let code = (0..400)
+            .map(|i| format!("let x{i} = {i};\n"))
+            .collect::<Vec<_>>()
+            .concat();
+        let text = format!("Here you go:\n\n```rust\n{code}```\n\nDone.");
+        let chunks = split_telegram_message(&text);
+        assert!(chunks.len() >= 3);
+        assert_eq!(chunks[0], "Here you go:");
+        let last = chunks.len() - 1;
+        assert!(chunks[last].ends_with("```\n\nDone."));
+
+        // Each code chunk is a complete block, and together they hold every
+        // line of code once, in order
+        let mut body = String::new();
+        for (i, chunk) in chunks.iter().enumerate().skip(1) {
+            assert!(telegram_len(chunk) <= TELEGRAM_MAX_MESSAGE_LENGTH);
+            let suffix = if i == last { "```\n\nDone." } else { "```" };
+            let inner = chunk
+                .strip_prefix("```rust\n")
+                .and_then(|c| c.strip_suffix(suffix))
+                .unwrap();
+            body.push_str(inner);
+        }
+        assert_eq!(body, code);
+    }
+
+    #[test]
+    fn split_markdown_v2_message_fits_after_escaping() {
+        // Every '.' doubles once escaped, so raw-length chunks would overflow
+        let text = "a.".repeat(3000);
+        assert_eq!(split_telegram_message(&text).len(), 2);
+        let chunks = split_markdown_v2_message(&text);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|c| telegram_len(&escape_markdown_v2(c)) <= TELEGRAM_MAX_MESSAGE_LENGTH));
+        assert_eq!(chunks.concat(), text);
+
+        let ch = TelegramChannel::new("t".into(), vec![]).with_markdown_v2(true);
+        assert_eq!(ch.split_reply(&text), chunks);
+    }
+
     #[test]
     fn telegram_user_allowed_wildcard() {
         let ch = TelegramChannel::new("t".into(), vec!["*".into()]);
@@ -1753,13 +3204,31 @@ mod tests {
     }
 
     #[test]
-    fn telegram_user_case_sensitive() {
+    fn telegram_user_case_insensitive_by_default() {
         let ch = TelegramChannel::new("t".into(), vec!["Alice".into()]);
         assert!(ch.is_user_allowed("Alice"));
+        assert!(ch.is_user_allowed("alice"));
+        assert!(ch.is_user_allowed("ALICE"));
+        assert!(!ch.is_user_allowed("alicia"));
+    }
+
+    #[test]
+    fn telegram_user_case_sensitive_opt_out() {
+        let ch = TelegramChannel::new("t".into(), vec!["Alice".into()])
+            .with_usernames_case_sensitive(true);
+        assert!(ch.is_user_allowed("Alice"));
         assert!(!ch.is_user_allowed("alice"));
         assert!(!ch.is_user_allowed("ALICE"));
     }
 
+    #[test]
+    fn telegram_numeric_id_matches_exactly_regardless_of_case_mode() {
+        let ch = TelegramChannel::new("t".into(), vec!["123456789".into()]);
+        assert!(ch.is_user_allowed("123456789"));
+        assert!(!ch.is_user_allowed("1234567890"));
+        assert!(!ch.is_user_allowed("12345678"));
+    }
+
     #[test]
     fn telegram_wildcard_with_specific_users() {
         let ch = TelegramChannel::new("t".into(), vec!["alice".into(), "*".into()]);
@@ -2025,4 +3494,191 @@ mod tests {
         // Should not panic
         assert!(result.is_err());
     }
+
+    #[test]
+    fn sender_metadata_identifies_group_speaker() {
+        let message = serde_json::json!({
+            "message_id": 7,
+            "from": {"id": 111, "username": "alice"},
+            "chat": {"id": -100_222, "type": "supergroup", "title": "Team"},
+            "text": "hi",
+        });
+        let metadata = sender_metadata(&message["from"], &message["chat"]);
+        assert_eq!(metadata["from_user_id"], "111");
+        assert_eq!(metadata["from_username"], "alice");
+        assert_eq!(metadata["chat_type"], "supergroup");
+        // The chat id (used as `sender`) is distinct from the speaker
+        assert_eq!(message["chat"]["id"], -100_222);
+        assert!(!metadata.values().any(|v| v == "-100222"));
+
+        let anonymous = sender_metadata(&serde_json::json!({}), &serde_json::json!({}));
+        assert!(anonymous.is_empty());
+    }
+
+    #[test]
+    fn auto_download_cap_is_per_type_and_off_by_default() {
+        let mut config = TelegramAutoDownloadConfig::default();
+        assert_eq!(auto_download_cap(&config, "photo"), None);
+
+        config.enabled = true;
+        config.max_document_bytes = 1024;
+        assert_eq!(auto_download_cap(&config, "document"), Some(1024));
+        assert_eq!(
+            auto_download_cap(&config, "photo"),
+            Some(config.max_photo_bytes)
+        );
+        assert_eq!(
+            auto_download_cap(&config, "voice"),
+            Some(config.max_voice_bytes)
+        );
+        assert_eq!(auto_download_cap(&config, "text"), None);
+    }
+
+    #[test]
+    fn prune_downloads_removes_only_expired_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let old = tmp.path().join("old.jpg");
+        let fresh = tmp.path().join("fresh.jpg");
+        std::fs::write(&old, b"old").unwrap();
+        std::fs::write(&fresh, b"fresh").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() - Duration::from_secs(2 * 24 * 60 * 60))
+            .unwrap();
+
+        assert_eq!(prune_downloads(tmp.path(), AUTO_DOWNLOAD_MAX_AGE), 1);
+        assert!(!old.exists());
+        assert!(fresh.exists());
+        assert_eq!(
+            prune_downloads(&tmp.path().join("missing"), AUTO_DOWNLOAD_MAX_AGE),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn media_without_auto_download_is_sent_inline() {
+        let ch = TelegramChannel::new("t".into(), vec![]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+        let msg = ChannelMessage {
+            id: "m1".into(),
+            sender: "42".into(),
+            content: "[Photo received]".into(),
+            channel: "telegram".into(),
+            timestamp: 0,
+            metadata: HashMap::new(),
+        };
+        assert!(ch.send_media_message(&tx, msg, "photo", "file-1", 10).await);
+        assert_eq!(rx.try_recv().unwrap().id, "m1");
+    }
+
+    /// Records what it was asked to transcribe.
+    #[derive(Default)]
+    struct RecordingStt {
+        calls: Mutex<Vec<(Vec<u8>, String)>>,
+    }
+
+    #[async_trait]
+    impl SpeechToText for RecordingStt {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn transcribe(
+            &self,
+            audio: Vec<u8>,
+            format: &str,
+        ) -> anyhow::Result<TranscriptResult> {
+            self.calls.lock().unwrap().push((audio, format.to_string()));
+            Ok(TranscriptResult {
+                text: "turn on the lights".into(),
+                language: "en".into(),
+                duration_ms: None,
+                confidence: 1.0,
+                processing_time_ms: None,
+            })
+        }
+    }
+
+    /// Bot API stub: one voice update, then empty polls; `getFile` and the
+    /// file download serve a fake voice note.
+    async fn spawn_voice_bot_api() -> String {
+        use axum::routing::{get, post};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let delivered = Arc::new(AtomicBool::new(false));
+        let app = axum::Router::new()
+            .route(
+                "/botTEST/getUpdates",
+                post(move || {
+                    let delivered = delivered.clone();
+                    async move {
+                        if delivered.swap(true, Ordering::SeqCst) {
+                            tokio::time::sleep(Duration::from_millis(200)).await;
+                            return axum::Json(serde_json::json!({ "ok": true, "result": [] }));
+                        }
+                        axum::Json(serde_json::json!({ "ok": true, "result": [{
+                            "update_id": 1,
+                            "message": {
+                                "message_id": 7,
+                                "from": { "id": 42, "username": "alice" },
+                                "chat": { "id": 42, "type": "private" },
+                                "voice": {
+                                    "file_id": "voice-1",
+                                    "file_size": 9,
+                                    "mime_type": "audio/ogg",
+                                    "duration": 2,
+                                },
+                            },
+                        }] }))
+                    }
+                }),
+            )
+            .route(
+                "/botTEST/sendChatAction",
+                post(|| async { axum::Json(serde_json::json!({ "ok": true, "result": true })) }),
+            )
+            .route(
+                "/botTEST/getFile",
+                post(|| async {
+                    axum::Json(serde_json::json!({
+                        "ok": true,
+                        "result": { "file_id": "voice-1", "file_path": "voice/file_1.oga" },
+                    }))
+                }),
+            )
+            .route(
+                "/file/botTEST/voice/file_1.oga",
+                get(|| async { b"OggS-fake".to_vec() }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn voice_update_is_transcribed_with_injected_stt() {
+        let stt = Arc::new(RecordingStt::default());
+        let ch = TelegramChannel::new("TEST".into(), vec!["*".into()])
+            .with_api_base(spawn_voice_bot_api().await)
+            .with_stt(stt.clone());
+        let (tx, mut rx) = tokio::sync::mpsc::channel(4);
+        let listener = tokio::spawn(async move { ch.listen(tx).await });
+
+        let msg = tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("no message within 10s")
+            .expect("listener stopped");
+        listener.abort();
+
+        assert_eq!(msg.content, "turn on the lights");
+        assert_eq!(msg.sender, "42");
+        assert_eq!(msg.channel, "telegram");
+        assert_eq!(msg.metadata["msg_type"], "voice");
+        assert_eq!(msg.metadata["file_id"], "voice-1");
+        let calls = stt.calls.lock().unwrap();
+        assert_eq!(*calls, [(b"OggS-fake".to_vec(), "oga".to_string())]);
+    }
 }
@@ -9,6 +9,23 @@ pub struct InlineButton {
     pub callback_data: String,
 }
 
+/// Display options for a reply (custom) keyboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReplyKeyboardOptions {
+    /// Hide the keyboard once a button has been pressed
+    #[serde(default)]
+    pub one_time_keyboard: bool,
+    /// Shrink the keyboard to fit its buttons instead of the default height
+    #[serde(default)]
+    pub resize_keyboard: bool,
+    /// Keep the keyboard shown when the user hides the system keyboard
+    #[serde(default)]
+    pub is_persistent: bool,
+    /// Placeholder shown in the input field while the keyboard is active
+    #[serde(default)]
+    pub input_field_placeholder: Option<String>,
+}
+
 /// Max voice file size to download for STT (5 MB)
 pub const MAX_VOICE_BYTES: u64 = 5 * 1024 * 1024;
 
@@ -29,4 +29,17 @@ pub trait Channel: Send + Sync {
     async fn health_check(&self) -> bool {
         true
     }
+
+    /// Like [`health_check`](Self::health_check), but saying why the channel
+    /// is unhealthy. Channels that can tell (e.g. a rejected token) override it.
+    async fn check_health(&self) -> anyhow::Result<()> {
+        if self.health_check().await {
+            Ok(())
+        } else {
+            anyhow::bail!(
+                "{} health check failed (check credentials, config and network)",
+                self.name()
+            )
+        }
+    }
 }
@@ -0,0 +1,626 @@
+//! JSON Schema for [`Config`], for client-side validation in editors and the
+//! control-plane SPA.
+//!
+//! Hand-built to mirror the serde shape of the config structs: fields
+//! without a serde default are `required` and `Option<T>` fields accept
+//! `null`. Unknown keys are allowed, as serde ignores them on load. Defaults are filled in from
+//! [`Config::default`] so they cannot drift. Secret fields carry
+//! `"writeOnly": true` and `"x-secret": true`; the control plane masks them
+//! on read.
+
+use serde_json::{json, Map, Value};
+
+use super::Config;
+
+/// JSON Schema (draft 2020-12) describing `config.toml` / the config JSON.
+pub fn json_schema() -> Value {
+    let mut schema = object(
+        &["default_temperature"],
+        vec![
+            ("schema_version", uint()),
+            ("api_key", secret(nullable(string()))),
+            ("default_provider", nullable(string())),
+            ("default_model", nullable(string())),
+            ("default_temperature", number()),
+            ("observability", observability()),
+            ("autonomy", autonomy()),
+            ("approval_policy", approval_policy()),
+            ("runtime", runtime()),
+            ("reliability", reliability()),
+            ("model_routes", array(model_route())),
+            ("heartbeat", heartbeat()),
+            ("channels_config", channels()),
+            ("memory", memory()),
+            ("tunnel", tunnel()),
+            ("gateway", gateway()),
+            ("composio", composio()),
+            ("secrets", object(&[], vec![("encrypt", boolean())])),
+            ("browser", browser()),
+            ("identity", identity()),
+            ("stt", stt()),
+            ("http_credentials", array(http_credential())),
+        ],
+    );
+    if let Ok(defaults) = serde_json::to_value(Config::default()) {
+        apply_defaults(&mut schema, &defaults);
+    }
+    schema["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+    schema["title"] = json!("ZeroClaw config");
+    schema
+}
+
+// ── Sections ─────────────────────────────────────────────────────
+
+fn observability() -> Value {
+    object(
+        &["backend"],
+        vec![
+            ("backend", string()),
+            ("otel_endpoint", nullable(string())),
+            ("otel_service_name", nullable(string())),
+        ],
+    )
+}
+
+fn autonomy() -> Value {
+    object(
+        &[
+            "level",
+            "workspace_only",
+            "allowed_commands",
+            "forbidden_paths",
+            "max_actions_per_hour",
+            "max_cost_per_day_cents",
+        ],
+        vec![
+            (
+                "level",
+                json!({ "enum": ["readonly", "supervised", "full"] }),
+            ),
+            ("workspace_only", boolean()),
+            ("allowed_commands", strings()),
+            ("forbidden_paths", strings()),
+            ("max_actions_per_hour", uint()),
+            ("max_cost_per_day_cents", uint()),
+            ("require_approval_for_medium_risk", boolean()),
+            ("block_high_risk_commands", boolean()),
+        ],
+    )
+}
+
+fn approval_policy() -> Value {
+    object(
+        &[],
+        vec![
+            ("enabled", boolean()),
+            ("timeout_secs", uint()),
+            ("medium_risk_approver", string()),
+            ("high_risk_approver", string()),
+            ("origin_mode", string()),
+            ("admin_users", strings()),
+        ],
+    )
+}
+
+fn runtime() -> Value {
+    let docker = object(
+        &[],
+        vec![
+            ("image", string()),
+            ("network", string()),
+            ("memory_limit_mb", nullable(uint())),
+            ("cpu_limit", nullable(number())),
+            ("read_only_rootfs", boolean()),
+            ("mount_workspace", boolean()),
+            ("allowed_workspace_roots", strings()),
+        ],
+    );
+    object(&[], vec![("kind", string()), ("docker", docker)])
+}
+
+fn reliability() -> Value {
+    object(
+        &[],
+        vec![
+            ("provider_retries", uint()),
+            ("provider_backoff_ms", uint()),
+            ("fallback_providers", strings()),
+            ("channel_initial_backoff_secs", uint()),
+            ("channel_max_backoff_secs", uint()),
+            ("scheduler_poll_secs", uint()),
+            ("scheduler_retries", uint()),
+        ],
+    )
+}
+
+fn model_route() -> Value {
+    object(
+        &["hint", "provider", "model"],
+        vec![
+            ("hint", string()),
+            ("provider", string()),
+            ("model", string()),
+            ("api_key", secret(nullable(string()))),
+        ],
+    )
+}
+
+fn http_credential() -> Value {
+    object(
+        &["url_prefix"],
+        vec![("url_prefix", string()), ("headers", map(secret(string())))],
+    )
+}
+
+fn heartbeat() -> Value {
+    object(
+        &["enabled", "interval_minutes"],
+        vec![("enabled", boolean()), ("interval_minutes", uint())],
+    )
+}
+
+fn channels() -> Value {
+    let flow_policy = object(
+        &[],
+        vec![
+            ("agent_authoring_enabled", boolean()),
+            ("denied_step_kinds", strings()),
+            ("max_steps", uint()),
+            ("max_agent_flows", uint()),
+            ("require_handoff_on_keyboard", boolean()),
+            ("auto_approve", boolean()),
+            ("auto_approve_max_steps", uint()),
+            ("denied_text_patterns", strings()),
+        ],
+    );
+    let auto_download = object(
+        &[],
+        vec![
+            ("enabled", boolean()),
+            ("attach_base64", boolean()),
+            ("max_photo_bytes", uint()),
+            ("max_document_bytes", uint()),
+            ("max_voice_bytes", uint()),
+        ],
+    );
+    let telegram = object(
+        &["bot_token", "allowed_users"],
+        vec![
+            ("bot_token", secret(string())),
+            ("allowed_users", strings()),
+            ("usernames_case_sensitive", boolean()),
+            ("stt_endpoint", nullable(string())),
+            ("flows_enabled", boolean()),
+            ("flow_policy", flow_policy),
+            ("auto_download", auto_download),
+            ("placeholder_replies", boolean()),
+            ("markdown_v2", boolean()),
+        ],
+    );
+    let discord = object(
+        &["bot_token"],
+        vec![
+            ("bot_token", secret(string())),
+            ("guild_id", nullable(string())),
+            ("allowed_users", strings()),
+        ],
+    );
+    let slack = object(
+        &["bot_token"],
+        vec![
+            ("bot_token", secret(string())),
+            ("app_token", secret(nullable(string()))),
+            ("channel_id", nullable(string())),
+            ("allowed_users", strings()),
+        ],
+    );
+    let webhook = object(
+        &["port"],
+        vec![("port", port()), ("secret", secret(nullable(string())))],
+    );
+    let imessage = object(&["allowed_contacts"], vec![("allowed_contacts", strings())]);
+    let matrix = object(
+        &["homeserver", "access_token", "room_id", "allowed_users"],
+        vec![
+            ("homeserver", string()),
+            ("access_token", secret(string())),
+            ("room_id", string()),
+            ("allowed_users", strings()),
+        ],
+    );
+    let whatsapp = object(
+        &["access_token", "phone_number_id", "verify_token"],
+        vec![
+            ("access_token", secret(string())),
+            ("phone_number_id", string()),
+            ("verify_token", secret(string())),
+            ("app_secret", secret(nullable(string()))),
+            ("allowed_numbers", strings()),
+        ],
+    );
+    let email = object(
+        &[
+            "imap_host",
+            "smtp_host",
+            "username",
+            "password",
+            "from_address",
+        ],
+        vec![
+            ("imap_host", string()),
+            ("imap_port", port()),
+            ("imap_folder", string()),
+            ("smtp_host", string()),
+            ("smtp_port", port()),
+            ("smtp_tls", boolean()),
+            ("username", string()),
+            ("password", secret(string())),
+            ("from_address", string()),
+            ("poll_interval_secs", uint()),
+            ("allowed_senders", strings()),
+        ],
+    );
+    let irc = object(
+        &["server", "nickname"],
+        vec![
+            ("server", string()),
+            ("port", port()),
+            ("nickname", string()),
+            ("username", nullable(string())),
+            ("channels", strings()),
+            ("allowed_users", strings()),
+            ("server_password", secret(nullable(string()))),
+            ("nickserv_password", secret(nullable(string()))),
+            ("sasl_password", secret(nullable(string()))),
+            ("verify_tls", nullable(boolean())),
+        ],
+    );
+    let cp_relay = object(&["cp_url"], vec![("cp_url", string())]);
+    let quiet_hours = object(
+        &["start", "end"],
+        vec![
+            ("utc_offset", string()),
+            ("start", string()),
+            ("end", string()),
+        ],
+    );
+    object(
+        &["cli"],
+        vec![
+            ("cli", boolean()),
+            ("telegram", nullable(telegram)),
+            ("discord", nullable(discord)),
+            ("slack", nullable(slack)),
+            ("webhook", nullable(webhook)),
+            ("imessage", nullable(imessage)),
+            ("matrix", nullable(matrix)),
+            ("whatsapp", nullable(whatsapp)),
+            ("email", nullable(email)),
+            ("irc", nullable(irc)),
+            ("cp_relay", nullable(cp_relay)),
+            ("quiet_hours", nullable(quiet_hours)),
+        ],
+    )
+}
+
+fn memory() -> Value {
+    object(
+        &["backend", "auto_save"],
+        vec![
+            ("backend", string()),
+            ("auto_save", boolean()),
+            ("hygiene_enabled", boolean()),
+            ("archive_after_days", uint()),
+            ("purge_after_days", uint()),
+            ("conversation_retention_days", uint()),
+            ("embedding_provider", string()),
+            ("embedding_model", string()),
+            ("embedding_dimensions", uint()),
+            ("vector_weight", number()),
+            ("keyword_weight", number()),
+            ("embedding_cache_size", uint()),
+            ("chunk_max_tokens", uint()),
+        ],
+    )
+}
+
+fn tunnel() -> Value {
+    let cloudflare = object(&["token"], vec![("token", secret(string()))]);
+    let tailscale = object(
+        &[],
+        vec![("funnel", boolean()), ("hostname", nullable(string()))],
+    );
+    let ngrok = object(
+        &["auth_token"],
+        vec![
+            ("auth_token", secret(string())),
+            ("domain", nullable(string())),
+        ],
+    );
+    let custom = object(
+        &["start_command"],
+        vec![
+            ("start_command", string()),
+            ("health_url", nullable(string())),
+            ("url_pattern", nullable(string())),
+        ],
+    );
+    object(
+        &["provider"],
+        vec![
+            ("provider", string()),
+            ("cloudflare", nullable(cloudflare)),
+            ("tailscale", nullable(tailscale)),
+            ("ngrok", nullable(ngrok)),
+            ("custom", nullable(custom)),
+        ],
+    )
+}
+
+fn gateway() -> Value {
+    object(
+        &[],
+        vec![
+            ("port", port()),
+            ("host", string()),
+            ("require_pairing", boolean()),
+            ("allow_public_bind", boolean()),
+            ("paired_tokens", secret(strings())),
+            ("pair_rate_limit_per_minute", uint()),
+            ("webhook_rate_limit_per_minute", uint()),
+            ("idempotency_ttl_secs", uint()),
+        ],
+    )
+}
+
+fn composio() -> Value {
+    object(
+        &[],
+        vec![
+            ("enabled", boolean()),
+            ("api_key", secret(nullable(string()))),
+            ("entity_id", string()),
+        ],
+    )
+}
+
+fn browser() -> Value {
+    object(
+        &[],
+        vec![
+            ("enabled", boolean()),
+            ("allowed_domains", strings()),
+            ("session_name", nullable(string())),
+        ],
+    )
+}
+
+fn identity() -> Value {
+    object(
+        &[],
+        vec![
+            ("format", string()),
+            ("aieos_path", nullable(string())),
+            ("aieos_inline", nullable(string())),
+        ],
+    )
+}
+
+fn stt() -> Value {
+    object(
+        &[],
+        vec![("backend", string()), ("endpoint", nullable(string()))],
+    )
+}
+
+// ── Building blocks ──────────────────────────────────────────────
+
+fn object(required: &[&str], properties: Vec<(&str, Value)>) -> Value {
+    let properties: Map<String, Value> = properties
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect();
+    let mut schema = json!({
+        "type": "object",
+        "properties": properties,
+    });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn number() -> Value {
+    json!({ "type": "number" })
+}
+
+fn boolean() -> Value {
+    json!({ "type": "boolean" })
+}
+
+/// Unsigned integer (`u32`, `u64`, `usize`).
+fn uint() -> Value {
+    json!({ "type": "integer", "minimum": 0 })
+}
+
+fn port() -> Value {
+    json!({ "type": "integer", "minimum": 0, "maximum": 65535 })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+/// Object with arbitrary keys, every value matching `values`.
+fn map(values: Value) -> Value {
+    json!({ "type": "object", "additionalProperties": values })
+}
+
+fn strings() -> Value {
+    array(string())
+}
+
+/// `Option<T>`: also accepts `null`.
+fn nullable(mut schema: Value) -> Value {
+    if let Some(Value::String(ty)) = schema.get("type").cloned() {
+        schema["type"] = json!([ty, "null"]);
+    } else {
+        let mut variants = schema["enum"].as_array().cloned().unwrap_or_default();
+        variants.push(Value::Null);
+        schema["enum"] = Value::Array(variants);
+    }
+    schema
+}
+
+fn secret(mut schema: Value) -> Value {
+    schema["writeOnly"] = json!(true);
+    schema["x-secret"] = json!(true);
+    schema
+}
+
+/// Copy non-null values from the serialized default config into the
+/// matching properties' `default`.
+fn apply_defaults(schema: &mut Value, defaults: &Value) {
+    let (Some(properties), Some(defaults)) = (
+        schema.get_mut("properties").and_then(Value::as_object_mut),
+        defaults.as_object(),
+    ) else {
+        return;
+    };
+    for (name, default) in defaults {
+        let Some(property) = properties.get_mut(name) else {
+            continue;
+        };
+        if default.is_null() {
+            continue;
+        }
+        if default.is_object() {
+            apply_defaults(property, default);
+        } else {
+            property["default"] = default.clone();
+        }
+    }
+}
+
+/// Resolve a dotted config path (`[*]` for array items, `*` for map values)
+/// to its schema.
+#[cfg(test)]
+pub(crate) fn schema_at<'a>(schema: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(schema, |node, segment| {
+        if segment == "*" {
+            return node.get("additionalProperties");
+        }
+        let (name, items) = match segment.strip_suffix("[*]") {
+            Some(name) => (name, true),
+            None => (segment, false),
+        };
+        let node = node.get("properties")?.get(name)?;
+        if items {
+            node.get("items")
+        } else {
+            Some(node)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_parses_and_has_top_level_sections() {
+        let text = serde_json::to_string(&json_schema()).unwrap();
+        let schema: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(schema["type"], "object");
+        for section in ["gateway", "channels_config", "model_routes", "runtime"] {
+            assert!(
+                schema["properties"].get(section).is_some(),
+                "missing section {section}"
+            );
+        }
+        assert_eq!(schema["properties"]["model_routes"]["type"], "array");
+        assert_eq!(schema["required"], json!(["default_temperature"]));
+        assert!(schema.get("additionalProperties").is_none());
+    }
+
+    #[test]
+    fn schema_defaults_come_from_config_default() {
+        let schema = json_schema();
+        assert_eq!(
+            schema_at(&schema, "default_temperature").unwrap()["default"],
+            0.7
+        );
+        assert_eq!(
+            schema_at(&schema, "channels_config.cli").unwrap()["default"],
+            true
+        );
+        assert_eq!(
+            schema_at(&schema, "autonomy.level").unwrap()["default"],
+            "supervised"
+        );
+        // Unset optionals get no default
+        assert!(schema_at(&schema, "api_key")
+            .unwrap()
+            .get("default")
+            .is_none());
+    }
+
+    /// Dotted paths of keys in `value` that `schema` does not describe.
+    fn undescribed_keys(value: &Value, schema: &Value, path: &str, out: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    let child_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    let child_schema = schema
+                        .get("properties")
+                        .and_then(|p| p.get(key))
+                        .or_else(|| schema.get("additionalProperties"));
+                    match child_schema {
+                        Some(child_schema) => {
+                            undescribed_keys(child, child_schema, &child_path, out);
+                        }
+                        None => out.push(child_path),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    undescribed_keys(item, &schema["items"], &format!("{path}[*]"), out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn schema_describes_every_default_config_key() {
+        let defaults = serde_json::to_value(Config::default()).unwrap();
+        let mut missing = Vec::new();
+        undescribed_keys(&defaults, &json_schema(), "", &mut missing);
+        assert!(
+            missing.is_empty(),
+            "Config keys missing from the JSON schema: {missing:?}"
+        );
+    }
+
+    #[test]
+    fn nullable_sections_accept_null() {
+        let schema = json_schema();
+        assert_eq!(
+            schema_at(&schema, "channels_config.telegram").unwrap()["type"],
+            json!(["object", "null"])
+        );
+        assert_eq!(
+            schema_at(&schema, "api_key").unwrap()["type"],
+            json!(["string", "null"])
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Config schema versioning and field migration table.
+//!
+//! Each entry records a config key that was renamed or removed, the schema
+//! version that dropped it, and what to use instead. The control plane runs
+//! incoming configs through [`check_schema`] against [`FIELD_MIGRATIONS`] on
+//! validate/PUT and surfaces the
+//! result as warnings so operators can migrate before the daemon trips on it.
+
+use serde::Serialize;
+
+/// Schema version written by this build. Bump when adding a migration entry.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// A config key that is deprecated as of `since_version`.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldMigration {
+    /// Schema version in which the key stopped being honoured.
+    pub since_version: u32,
+    /// Dotted path of the old key (e.g. `gateway.bind`).
+    pub path: &'static str,
+    /// Dotted path of the replacement key, if there is one.
+    pub replacement: Option<&'static str>,
+    /// Short operator-facing hint.
+    pub note: &'static str,
+}
+
+/// Known renamed/removed fields, oldest first. Empty until a release
+/// renames or removes a key.
+pub const FIELD_MIGRATIONS: &[FieldMigration] = &[];
+
+/// A single schema warning for an incoming config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaWarning {
+    /// Dotted path of the offending key (or `schema_version`).
+    pub field: String,
+    /// Suggested replacement key, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+    pub message: String,
+}
+
+/// Read `schema_version` from a raw TOML table. Missing means 0 (pre-versioning).
+pub fn schema_version_of(raw: &toml::Value) -> u32 {
+    raw.get("schema_version")
+        .and_then(toml::Value::as_integer)
+        .and_then(|v| u32::try_from(v).ok())
+        .unwrap_or(0)
+}
+
+fn lookup<'a>(raw: &'a toml::Value, path: &str) -> Option<&'a toml::Value> {
+    path.split('.').try_fold(raw, |node, key| node.get(key))
+}
+
+/// Compare a raw config against the current build's schema.
+///
+/// Returns one warning per `migrations` key still present (callers pass
+/// [`FIELD_MIGRATIONS`]), plus a version
+/// warning when the config is older or newer than [`CURRENT_SCHEMA_VERSION`].
+/// Never fails: unknown shapes simply produce no field warnings.
+pub fn check_schema(raw: &toml::Value, migrations: &[FieldMigration]) -> Vec<SchemaWarning> {
+    let version = schema_version_of(raw);
+    let mut warnings = Vec::new();
+
+    if version < CURRENT_SCHEMA_VERSION {
+        warnings.push(SchemaWarning {
+            field: "schema_version".into(),
+            replacement: None,
+            message: format!(
+                "Config schema version {version} is older than this build's version \
+                 {CURRENT_SCHEMA_VERSION}; set schema_version = {CURRENT_SCHEMA_VERSION} after migrating"
+            ),
+        });
+    } else if version > CURRENT_SCHEMA_VERSION {
+        warnings.push(SchemaWarning {
+            field: "schema_version".into(),
+            replacement: None,
+            message: format!(
+                "Config schema version {version} is newer than this build's version \
+                 {CURRENT_SCHEMA_VERSION}; some fields may be ignored"
+            ),
+        });
+    }
+
+    for m in migrations {
+        if lookup(raw, m.path).is_some() {
+            warnings.push(SchemaWarning {
+                field: m.path.to_string(),
+                replacement: m.replacement.map(str::to_string),
+                message: format!("Deprecated since schema v{}: {}", m.since_version, m.note),
+            });
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> toml::Value {
+        toml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn current_config_has_no_warnings() {
+        let raw = parse(&format!(
+            "schema_version = {CURRENT_SCHEMA_VERSION}\n[gateway]\nhost = \"127.0.0.1\"\n"
+        ));
+        assert!(check_schema(&raw, FIELD_MIGRATIONS).is_empty());
+    }
+
+    #[test]
+    fn missing_version_warns() {
+        let raw = parse("default_temperature = 0.7\n");
+        let warnings = check_schema(&raw, FIELD_MIGRATIONS);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "schema_version");
+    }
+
+    #[test]
+    fn newer_version_warns() {
+        let raw = parse(&format!(
+            "schema_version = {}\n",
+            CURRENT_SCHEMA_VERSION + 1
+        ));
+        let warnings = check_schema(&raw, FIELD_MIGRATIONS);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("newer"));
+    }
+
+    #[test]
+    fn deprecated_keys_warn_with_replacement() {
+        let migrations = [
+            FieldMigration {
+                since_version: 1,
+                path: "gateway.bind",
+                replacement: Some("gateway.host"),
+                note: "use gateway.host",
+            },
+            FieldMigration {
+                since_version: 1,
+                path: "legacy_mode",
+                replacement: None,
+                note: "removed",
+            },
+        ];
+        let raw = parse(&format!(
+            "schema_version = {CURRENT_SCHEMA_VERSION}\n[gateway]\nbind = \"0.0.0.0\"\n"
+        ));
+        assert_eq!(
+            check_schema(&raw, &migrations),
+            [SchemaWarning {
+                field: "gateway.bind".into(),
+                replacement: Some("gateway.host".into()),
+                message: "Deprecated since schema v1: use gateway.host".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn migration_table_versions_are_in_range() {
+        for m in FIELD_MIGRATIONS {
+            assert!(m.since_version >= 1 && m.since_version <= CURRENT_SCHEMA_VERSION);
+        }
+    }
+}
@@ -1,9 +1,13 @@
+pub mod json_schema;
+pub mod migrations;
 pub mod schema;
+pub mod workspace;
 
 pub use schema::{
     zeroclaw_home, ApprovalPolicyConfig, AutonomyConfig, BrowserConfig, ChannelsConfig,
     ComposioConfig, Config, DiscordConfig, DockerRuntimeConfig, FlowPolicyConfig, GatewayConfig,
     HeartbeatConfig, IMessageConfig, IdentityConfig, MatrixConfig, MemoryConfig, ModelRouteConfig,
-    ObservabilityConfig, ReliabilityConfig, RuntimeConfig, SecretsConfig, SlackConfig,
-    TelegramConfig, TunnelConfig, WebhookConfig,
+    ObservabilityConfig, QuietHoursConfig, ReliabilityConfig, RuntimeConfig, SecretsConfig,
+    SlackConfig, SttConfig, TelegramAutoDownloadConfig, TelegramConfig, TunnelConfig,
+    WebhookConfig,
 };
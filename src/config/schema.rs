@@ -2,6 +2,7 @@ use crate::security::AutonomyLevel;
 use anyhow::{Context, Result};
 use directories::UserDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::{self, File, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -16,6 +17,9 @@ pub struct Config {
     /// Path to config.toml - computed from home, not serialized
     #[serde(skip)]
     pub config_path: PathBuf,
+    /// Config schema version (see `config::migrations`). Missing means pre-versioning (0).
+    #[serde(default)]
+    pub schema_version: u32,
     pub api_key: Option<String>,
     pub default_provider: Option<String>,
     pub default_model: Option<String>,
@@ -66,6 +70,12 @@ pub struct Config {
 
     #[serde(default)]
     pub identity: IdentityConfig,
+
+    #[serde(default)]
+    pub stt: SttConfig,
+
+    #[serde(default)]
+    pub http_credentials: Vec<HttpCredentialConfig>,
 }
 
 // ── Identity (AIEOS / OpenClaw format) ──────────────────────────
@@ -228,6 +238,32 @@ pub struct BrowserConfig {
     pub session_name: Option<String>,
 }
 
+// ── Speech-to-text ───────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SttConfig {
+    /// "http" (external `/transcribe` service) | "none"
+    #[serde(default = "default_stt_backend")]
+    pub backend: String,
+    /// Endpoint for the http backend. Falls back to
+    /// `channels_config.telegram.stt_endpoint` when unset.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+fn default_stt_backend() -> String {
+    "http".into()
+}
+
+impl Default for SttConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_stt_backend(),
+            endpoint: None,
+        }
+    }
+}
+
 // ── Memory ───────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -662,6 +698,56 @@ pub struct ModelRouteConfig {
     pub api_key: Option<String>,
 }
 
+// ── HTTP credentials ─────────────────────────────────────────────
+
+/// Headers attached to outbound HTTP requests whose URL starts with a prefix.
+///
+/// Keeps auth headers for outbound webhooks (the control plane's lifecycle
+/// webhooks) in config, where they are secret-masked, rather than in flow
+/// definitions or the environment.
+///
+/// ```toml
+/// [[http_credentials]]
+/// url_prefix = "https://api.example.com/"
+/// headers = { Authorization = "Bearer sk-..." }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpCredentialConfig {
+    /// URL prefix the headers apply to; matched on a path boundary, so
+    /// `https://api.example.com` does not match `https://api.example.com.evil/`
+    pub url_prefix: String,
+    /// Header name -> value. Every value is treated as a secret.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl HttpCredentialConfig {
+    pub fn matches(&self, url: &str) -> bool {
+        let Some(rest) = url.strip_prefix(self.url_prefix.as_str()) else {
+            return false;
+        };
+        self.url_prefix.ends_with('/') || rest.is_empty() || rest.starts_with(['/', '?', '#'])
+    }
+}
+
+/// Headers from every credential matching `url`, keyed by lowercased name.
+/// Where several match, the longest prefix wins for a given header.
+pub fn http_credential_headers(
+    credentials: &[HttpCredentialConfig],
+    url: &str,
+) -> BTreeMap<String, String> {
+    let mut matching: Vec<&HttpCredentialConfig> =
+        credentials.iter().filter(|c| c.matches(url)).collect();
+    matching.sort_by_key(|c| c.url_prefix.len());
+    let mut headers = BTreeMap::new();
+    for credential in matching {
+        for (name, value) in &credential.headers {
+            headers.insert(name.to_ascii_lowercase(), value.clone());
+        }
+    }
+    headers
+}
+
 // ── Heartbeat ────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -760,6 +846,8 @@ pub struct ChannelsConfig {
     pub email: Option<crate::channels::email_channel::EmailConfig>,
     pub irc: Option<IrcConfig>,
     pub cp_relay: Option<CpRelayConfig>,
+    /// Hold human-facing sends during a daily window (`[channels_config.quiet_hours]`)
+    pub quiet_hours: Option<QuietHoursConfig>,
 }
 
 impl Default for ChannelsConfig {
@@ -776,6 +864,7 @@ impl Default for ChannelsConfig {
             email: None,
             irc: None,
             cp_relay: None,
+            quiet_hours: None,
         }
     }
 }
@@ -785,10 +874,34 @@ pub struct CpRelayConfig {
     pub cp_url: String,
 }
 
+/// Daily window during which outbound channel sends are deferred until the
+/// window ends. Held sends are kept in the workspace `state` dir, so they
+/// survive a restart. Inter-agent relay traffic and approval requests are
+/// never deferred.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHoursConfig {
+    /// Fixed UTC offset the window is expressed in: "UTC", "+02:00", "-05:30".
+    /// Not a named timezone, so it does not follow daylight saving changes.
+    #[serde(default = "default_quiet_hours_utc_offset")]
+    pub utc_offset: String,
+    /// Window start as "HH:MM"
+    pub start: String,
+    /// Window end as "HH:MM"; earlier than `start` means it spans midnight
+    pub end: String,
+}
+
+fn default_quiet_hours_utc_offset() -> String {
+    "UTC".into()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
     pub bot_token: String,
     pub allowed_users: Vec<String>,
+    /// Match `allowed_users` usernames case-sensitively. Off by default:
+    /// Telegram usernames are case-insensitive. Numeric IDs always match exactly.
+    #[serde(default)]
+    pub usernames_case_sensitive: bool,
     /// External STT endpoint for voice transcription (e.g. "http://localhost:9000")
     #[serde(default)]
     pub stt_endpoint: Option<String>,
@@ -798,6 +911,19 @@ pub struct TelegramConfig {
     /// Policy constraints for agent-authored flows (Phase 17)
     #[serde(default)]
     pub flow_policy: FlowPolicyConfig,
+    /// Download inbound media as it arrives instead of leaving only a `file_id`
+    #[serde(default)]
+    pub auto_download: TelegramAutoDownloadConfig,
+    /// Send a "⏳ working..." placeholder as soon as a message arrives and
+    /// edit it into the reply, instead of sending the reply separately
+    #[serde(default)]
+    pub placeholder_replies: bool,
+    /// Send replies as escaped `MarkdownV2` instead of legacy `Markdown`, so a
+    /// stray `_` or `*` no longer costs a reply all of its formatting. Only
+    /// code spans and blocks keep their formatting: `*bold*`, `_italic_` and
+    /// links are escaped and show up as literal text
+    #[serde(default)]
+    pub markdown_v2: bool,
 }
 
 /// Policy constraints for agent-authored flows.
@@ -859,6 +985,56 @@ impl Default for FlowPolicyConfig {
     }
 }
 
+/// Eager download of inbound Telegram media.
+///
+/// Telegram `file_id`s can expire before the agent gets to them; with this
+/// enabled the listener fetches each photo, document, or voice note up front
+/// and attaches it to the message. Files over the per-type cap are skipped
+/// (the message still carries its `file_id`). Temp files are deleted after
+/// a day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramAutoDownloadConfig {
+    /// Master switch (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Attach file contents as base64 instead of a temp-file path (default: false)
+    #[serde(default)]
+    pub attach_base64: bool,
+    /// Largest photo to download, in bytes
+    #[serde(default = "default_auto_download_photo_bytes")]
+    pub max_photo_bytes: u64,
+    /// Largest document to download, in bytes
+    #[serde(default = "default_auto_download_document_bytes")]
+    pub max_document_bytes: u64,
+    /// Largest voice note to download, in bytes
+    #[serde(default = "default_auto_download_voice_bytes")]
+    pub max_voice_bytes: u64,
+}
+
+fn default_auto_download_photo_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+fn default_auto_download_document_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_auto_download_voice_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+
+impl Default for TelegramAutoDownloadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            attach_base64: false,
+            max_photo_bytes: default_auto_download_photo_bytes(),
+            max_document_bytes: default_auto_download_document_bytes(),
+            max_voice_bytes: default_auto_download_voice_bytes(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscordConfig {
     pub bot_token: String,
@@ -971,6 +1147,7 @@ impl Default for Config {
         Self {
             workspace_dir: zeroclaw_dir.join("workspace"),
             config_path: zeroclaw_dir.join("config.toml"),
+            schema_version: super::migrations::CURRENT_SCHEMA_VERSION,
             api_key: None,
             default_provider: Some("openrouter".to_string()),
             default_model: Some("anthropic/claude-sonnet-4-20250514".to_string()),
@@ -990,6 +1167,8 @@ impl Default for Config {
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             identity: IdentityConfig::default(),
+            stt: SttConfig::default(),
+            http_credentials: Vec::new(),
         }
     }
 }
@@ -1006,14 +1185,7 @@ impl Config {
         }
 
         if config_path.exists() {
-            let contents =
-                fs::read_to_string(&config_path).context("Failed to read config file")?;
-            let mut config: Config =
-                toml::from_str(&contents).context("Failed to parse config file")?;
-            // Set computed paths that are skipped during serialization
-            config.config_path = config_path.clone();
-            config.workspace_dir = zeroclaw_dir.join("workspace");
-            Ok(config)
+            Self::load_from_path(&config_path)
         } else {
             let mut config = Config::default();
             config.config_path = config_path.clone();
@@ -1023,6 +1195,21 @@ impl Config {
         }
     }
 
+    /// Load the config at `config_path`, with the workspace directory next to
+    /// it, as `load_or_init` does for the default home.
+    pub fn load_from_path(config_path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(config_path).context("Failed to read config file")?;
+        let mut config: Config =
+            toml::from_str(&contents).context("Failed to parse config file")?;
+        // Set computed paths that are skipped during serialization
+        config.config_path = config_path.to_path_buf();
+        config.workspace_dir = config_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("workspace");
+        Ok(config)
+    }
+
     /// Apply environment variable overrides to config
     pub fn apply_env_overrides(&mut self) {
         // API Key: ZEROCLAW_API_KEY or API_KEY
@@ -1277,6 +1464,7 @@ mod tests {
         let config = Config {
             workspace_dir: PathBuf::from("/tmp/test/workspace"),
             config_path: PathBuf::from("/tmp/test/config.toml"),
+            schema_version: 1,
             api_key: Some("sk-test-key".into()),
             default_provider: Some("openrouter".into()),
             default_model: Some("gpt-4o".into()),
@@ -1311,9 +1499,13 @@ mod tests {
                 telegram: Some(TelegramConfig {
                     bot_token: "123:ABC".into(),
                     allowed_users: vec!["user1".into()],
+                    usernames_case_sensitive: false,
                     stt_endpoint: None,
                     flows_enabled: false,
                     flow_policy: FlowPolicyConfig::default(),
+                    auto_download: TelegramAutoDownloadConfig::default(),
+                    placeholder_replies: false,
+                    markdown_v2: false,
                 }),
                 discord: None,
                 slack: None,
@@ -1324,6 +1516,7 @@ mod tests {
                 email: None,
                 irc: None,
                 cp_relay: None,
+                quiet_hours: None,
             },
             memory: MemoryConfig::default(),
             tunnel: TunnelConfig::default(),
@@ -1332,6 +1525,8 @@ mod tests {
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             identity: IdentityConfig::default(),
+            stt: SttConfig::default(),
+            http_credentials: Vec::new(),
         };
 
         let toml_str = toml::to_string_pretty(&config).unwrap();
@@ -1375,6 +1570,37 @@ default_temperature = 0.7
         assert_eq!(parsed.memory.conversation_retention_days, 30);
     }
 
+    #[test]
+    fn http_credentials_match_on_prefix_boundary() {
+        let parsed: Config = toml::from_str(
+            r#"
+default_temperature = 0.7
+
+[[http_credentials]]
+url_prefix = "https://api.example.com"
+headers = { Authorization = "Bearer outer", "X-Team" = "core" }
+
+[[http_credentials]]
+url_prefix = "https://api.example.com/v2/"
+headers = { authorization = "Bearer inner" }
+"#,
+        )
+        .unwrap();
+        let creds = &parsed.http_credentials;
+
+        let v1 = http_credential_headers(creds, "https://api.example.com/v1/items");
+        assert_eq!(v1["authorization"], "Bearer outer");
+        assert_eq!(v1["x-team"], "core");
+
+        // Longest prefix wins per header
+        let v2 = http_credential_headers(creds, "https://api.example.com/v2/items");
+        assert_eq!(v2["authorization"], "Bearer inner");
+        assert_eq!(v2["x-team"], "core");
+
+        assert!(http_credential_headers(creds, "https://api.example.com.evil/").is_empty());
+        assert!(http_credential_headers(creds, "https://other.example.com/").is_empty());
+    }
+
     #[test]
     fn config_save_and_load_tmpdir() {
         let dir = std::env::temp_dir().join("zeroclaw_test_config");
@@ -1385,6 +1611,7 @@ default_temperature = 0.7
         let config = Config {
             workspace_dir: dir.join("workspace"),
             config_path: config_path.clone(),
+            schema_version: 1,
             api_key: Some("sk-roundtrip".into()),
             default_provider: Some("openrouter".into()),
             default_model: Some("test-model".into()),
@@ -1404,6 +1631,8 @@ default_temperature = 0.7
             secrets: SecretsConfig::default(),
             browser: BrowserConfig::default(),
             identity: IdentityConfig::default(),
+            stt: SttConfig::default(),
+            http_credentials: Vec::new(),
         };
 
         config.save().unwrap();
@@ -1456,9 +1685,13 @@ default_temperature = 0.7
         let tc = TelegramConfig {
             bot_token: "123:XYZ".into(),
             allowed_users: vec!["alice".into(), "bob".into()],
+            usernames_case_sensitive: false,
             stt_endpoint: None,
             flows_enabled: false,
             flow_policy: FlowPolicyConfig::default(),
+            auto_download: TelegramAutoDownloadConfig::default(),
+            placeholder_replies: false,
+            markdown_v2: false,
         };
         let json = serde_json::to_string(&tc).unwrap();
         let parsed: TelegramConfig = serde_json::from_str(&json).unwrap();
@@ -1575,6 +1808,7 @@ default_temperature = 0.7
             email: None,
             irc: None,
             cp_relay: None,
+            quiet_hours: None,
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -1734,6 +1968,7 @@ channel_id = "C123"
             email: None,
             irc: None,
             cp_relay: None,
+            quiet_hours: None,
         };
         let toml_str = toml::to_string_pretty(&c).unwrap();
         let parsed: ChannelsConfig = toml::from_str(&toml_str).unwrap();
@@ -0,0 +1,61 @@
+//! Workspace directory layout shared by onboarding and the control plane.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Subdirectories every instance workspace needs. Create, clone, import and
+/// the onboarding scaffold all build workspaces from this list.
+pub const WORKSPACE_SUBDIRS: &[&str] = &["skills", "memory", "sessions", "state", "cron"];
+
+/// Env var with extra comma-separated workspace subdirectories (e.g.
+/// `attachments`) created alongside [`WORKSPACE_SUBDIRS`].
+pub const EXTRA_WORKSPACE_SUBDIRS_ENV: &str = "ZEROCLAW_CP_EXTRA_WORKSPACE_SUBDIRS";
+
+/// [`WORKSPACE_SUBDIRS`] plus any extras from [`EXTRA_WORKSPACE_SUBDIRS_ENV`].
+/// Extras must be single relative path components; others are ignored.
+pub fn workspace_subdirs() -> Vec<String> {
+    workspace_subdirs_with(&std::env::var(EXTRA_WORKSPACE_SUBDIRS_ENV).unwrap_or_default())
+}
+
+fn workspace_subdirs_with(extra: &str) -> Vec<String> {
+    let mut dirs: Vec<String> = WORKSPACE_SUBDIRS.iter().map(|d| d.to_string()).collect();
+    for name in extra.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let mut components = Path::new(name).components();
+        let single_normal = matches!(
+            (components.next(), components.next()),
+            (Some(std::path::Component::Normal(_)), None)
+        );
+        if !single_normal {
+            tracing::warn!("Ignoring workspace subdir {name:?} in {EXTRA_WORKSPACE_SUBDIRS_ENV}");
+        } else if !dirs.iter().any(|d| d == name) {
+            dirs.push(name.to_string());
+        }
+    }
+    dirs
+}
+
+/// Create every directory from [`workspace_subdirs`] under `workspace_dir`.
+/// Existing directories are left untouched.
+pub fn create_workspace_subdirs(workspace_dir: &Path) -> Result<()> {
+    for subdir in workspace_subdirs() {
+        let path = workspace_dir.join(&subdir);
+        fs::create_dir_all(&path)
+            .with_context(|| format!("Failed to create workspace dir {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_subdirs_appends_valid_extras() {
+        let dirs = workspace_subdirs_with(" attachments, skills,../escape,a/b,,exports ");
+        let mut expected: Vec<String> = WORKSPACE_SUBDIRS.iter().map(|d| d.to_string()).collect();
+        expected.extend(["attachments".to_string(), "exports".to_string()]);
+        assert_eq!(dirs, expected);
+        assert_eq!(workspace_subdirs_with(""), WORKSPACE_SUBDIRS);
+    }
+}
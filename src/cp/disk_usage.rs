@@ -0,0 +1,182 @@
+//! On-disk size of instance directories, for `GET /api/instances/:name/disk`.
+//!
+//! Walks are bounded by depth and by a total entry budget so a runaway
+//! tree (deep nesting, millions of small files) cannot pin a blocking
+//! thread; a walk that hits either bound reports itself as truncated.
+//! Symlinks are counted as entries but never followed.
+
+use std::path::Path;
+
+/// Deepest directory level below the measured path that is descended into.
+pub const MAX_DEPTH: usize = 32;
+/// Entries (files, directories, links) visited per request before giving up.
+pub const MAX_ENTRIES: usize = 200_000;
+
+/// Total size and file count of a tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirUsage {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+impl DirUsage {
+    fn add(&mut self, other: Self) {
+        self.bytes += other.bytes;
+        self.files += other.files;
+    }
+}
+
+/// Measures trees against one shared depth limit and entry budget.
+#[derive(Debug)]
+pub struct UsageWalker {
+    max_depth: usize,
+    remaining: usize,
+    truncated: bool,
+}
+
+impl Default for UsageWalker {
+    fn default() -> Self {
+        Self::new(MAX_DEPTH, MAX_ENTRIES)
+    }
+}
+
+impl UsageWalker {
+    pub fn new(max_depth: usize, max_entries: usize) -> Self {
+        Self {
+            max_depth,
+            remaining: max_entries,
+            truncated: false,
+        }
+    }
+
+    /// Whether any walk so far stopped at the depth limit or entry budget.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Size of `path` and everything under it. A missing or unreadable
+    /// path measures as empty.
+    pub fn measure(&mut self, path: &Path) -> DirUsage {
+        let mut usage = DirUsage::default();
+        self.walk(path, 0, &mut usage);
+        usage
+    }
+
+    fn walk(&mut self, path: &Path, depth: usize, usage: &mut DirUsage) {
+        if self.remaining == 0 {
+            self.truncated = true;
+            return;
+        }
+        self.remaining -= 1;
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            return;
+        };
+        if !meta.is_dir() {
+            usage.bytes += meta.len();
+            usage.files += 1;
+            return;
+        }
+        if depth >= self.max_depth {
+            self.truncated = true;
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(path) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            self.walk(&entry.path(), depth + 1, usage);
+        }
+    }
+
+    /// Usage of each of `subdirs` inside `workspace` (in the given order,
+    /// empty when missing), and of everything else at its top level.
+    pub fn workspace_breakdown(
+        &mut self,
+        workspace: &Path,
+        subdirs: &[String],
+    ) -> (Vec<(String, DirUsage)>, DirUsage) {
+        let mut named: Vec<(String, DirUsage)> = subdirs
+            .iter()
+            .map(|name| (name.clone(), DirUsage::default()))
+            .collect();
+        let mut other = DirUsage::default();
+        let Ok(entries) = std::fs::read_dir(workspace) else {
+            return (named, other);
+        };
+        for entry in entries.flatten() {
+            let usage = self.measure(&entry.path());
+            let name = entry.file_name();
+            match named.iter_mut().find(|(n, _)| name == n.as_str()) {
+                Some((_, total)) => total.add(usage),
+                None => other.add(usage),
+            }
+        }
+        (named, other)
+    }
+}
+
+/// `bytes` in binary units with one decimal, e.g. `512 B`, `1.5 KiB`.
+#[allow(clippy::cast_precision_loss)]
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_bytes_picks_binary_units() {
+        assert_eq!(human_bytes(0), "0 B");
+        assert_eq!(human_bytes(1023), "1023 B");
+        assert_eq!(human_bytes(1536), "1.5 KiB");
+        assert_eq!(human_bytes(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(human_bytes(3 << 30), "3.0 GiB");
+    }
+
+    #[test]
+    fn walker_splits_workspace_and_honours_bounds() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let ws = tmp.path();
+        std::fs::create_dir_all(ws.join("skills/deploy")).unwrap();
+        std::fs::write(ws.join("skills/deploy/SKILL.md"), [0u8; 100]).unwrap();
+        std::fs::write(ws.join("skills/README.md"), [0u8; 20]).unwrap();
+        std::fs::write(ws.join("SOUL.md"), [0u8; 7]).unwrap();
+
+        let subdirs = ["skills".to_string(), "memory".to_string()];
+        let mut walker = UsageWalker::default();
+        let (named, other) = walker.workspace_breakdown(ws, &subdirs);
+        let skills = DirUsage {
+            bytes: 120,
+            files: 2,
+        };
+        assert_eq!(
+            named,
+            [
+                ("skills".into(), skills),
+                ("memory".into(), DirUsage::default()),
+            ]
+        );
+        assert_eq!(other, DirUsage { bytes: 7, files: 1 });
+        assert!(!walker.truncated());
+
+        // skills/deploy is beyond depth 1
+        let mut shallow = UsageWalker::new(1, MAX_ENTRIES);
+        assert_eq!(shallow.measure(&ws.join("skills")).bytes, 20);
+        assert!(shallow.truncated());
+
+        let mut capped = UsageWalker::new(MAX_DEPTH, 2);
+        capped.measure(ws);
+        assert!(capped.truncated());
+    }
+}
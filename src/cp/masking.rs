@@ -1,7 +1,50 @@
+use hmac::{Hmac, Mac};
 use serde_json::Value;
+use sha2::Sha256;
+use std::path::Path;
 
 pub const MASKED: &str = "***MASKED***";
 
+/// Env var selecting the [`RedactionMode`]: `full` (default), `hash` or `length`.
+pub const REDACTION_MODE_ENV: &str = "ZEROCLAW_CP_REDACTION_MODE";
+
+/// How a secret value is replaced by config masking and payload redaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionMode {
+    /// Fixed sentinel (`***MASKED***` / `***REDACTED***`).
+    #[default]
+    Full,
+    /// `hmac:` plus the first 12 hex chars of the value's HMAC-SHA256 under
+    /// a per-install key (see [`REDACTION_KEY_FILE`]), so the same secret
+    /// used in two places can be correlated without disclosure, and guesses
+    /// can't be checked against a placeholder without the key.
+    Hash,
+    /// One `*` per character of the value.
+    Length,
+}
+
+impl RedactionMode {
+    /// Mode from [`REDACTION_MODE_ENV`]; unset or unrecognised means `Full`.
+    pub fn from_env() -> Self {
+        let Ok(raw) = std::env::var(REDACTION_MODE_ENV) else {
+            return Self::Full;
+        };
+        Self::parse(&raw).unwrap_or_else(|| {
+            tracing::warn!("Ignoring {REDACTION_MODE_ENV}={raw:?}: expected full, hash or length");
+            Self::Full
+        })
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "full" => Some(Self::Full),
+            "hash" => Some(Self::Hash),
+            "length" => Some(Self::Length),
+            _ => None,
+        }
+    }
+}
+
 /// All known scalar secret paths (segments from root).
 const SCALAR_SECRET_PATHS: &[&[&str]] = &[
     &["api_key"],
@@ -23,53 +66,167 @@ const SCALAR_SECRET_PATHS: &[&[&str]] = &[
     &["tunnel", "cloudflare", "token"],
 ];
 
+/// File in the control plane directory holding the [`RedactionMode::Hash`] key.
+pub const REDACTION_KEY_FILE: &str = ".redaction_key";
+
+/// Secret detection and redaction settings, built once at startup
+/// (see [`SecretMasking::from_env`]) and shared by every masking call.
+#[derive(Clone, Default)]
+pub struct SecretMasking {
+    pub detector: SecretKeyDetector,
+    pub mode: RedactionMode,
+    /// HMAC key for [`RedactionMode::Hash`]; empty unless that mode is set.
+    hash_key: Vec<u8>,
+}
+
+impl std::fmt::Debug for SecretMasking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretMasking")
+            .field("detector", &self.detector)
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecretMasking {
+    /// Patterns from [`SECRET_KEY_PATTERNS_ENV`] and mode from
+    /// [`REDACTION_MODE_ENV`]. In `Hash` mode the key is read from
+    /// [`REDACTION_KEY_FILE`] in `cp_dir`, which is created on first use.
+    pub fn from_env(cp_dir: &Path) -> anyhow::Result<Self> {
+        let mode = RedactionMode::from_env();
+        let hash_key = if mode == RedactionMode::Hash {
+            crate::security::secrets::load_or_create_key(&cp_dir.join(REDACTION_KEY_FILE))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            detector: SecretKeyDetector::from_env(),
+            mode,
+            hash_key,
+        })
+    }
+
+    /// Replacement for `secret`; `sentinel` is used in `Full` mode.
+    pub fn placeholder(&self, secret: &str, sentinel: &str) -> String {
+        match self.mode {
+            RedactionMode::Full => sentinel.to_string(),
+            RedactionMode::Hash => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(&self.hash_key)
+                    .expect("HMAC accepts any key length");
+                mac.update(secret.as_bytes());
+                let digest = hex::encode(mac.finalize().into_bytes());
+                format!("hmac:{}", &digest[..12])
+            }
+            RedactionMode::Length => "*".repeat(secret.chars().count()),
+        }
+    }
+
+    /// True if `incoming` is what masking `current` would have produced,
+    /// i.e. the client echoed a masked value back unchanged.
+    fn is_masked(&self, incoming: &str, current: Option<&str>) -> bool {
+        incoming == MASKED
+            || current.is_some_and(|c| c != MASKED && self.placeholder(c, MASKED) == incoming)
+    }
+}
+
 /// Replace known secret fields in a serialized config JSON with `"***MASKED***"`.
 ///
 /// Walks enumerated paths corresponding to every secret field in `Config` and
-/// its nested channel/tunnel/composio/model_routes structs. Null or missing
-/// values are left as-is (they are not a leak).
+/// its nested channel/tunnel/composio/model_routes/http_credentials structs.
+/// Null or missing values are left as-is (they are not a leak). Uses the
+/// default [`SecretMasking`]; see [`mask_config_secrets_with`].
 pub fn mask_config_secrets(value: &mut Value) {
-    // Top-level scalar secrets
-    mask_path(value, &["api_key"]);
+    mask_config_secrets_with(value, &SecretMasking::default());
+}
 
-    // Channels
-    mask_path(value, &["channels_config", "telegram", "bot_token"]);
-    mask_path(value, &["channels_config", "discord", "bot_token"]);
-    mask_path(value, &["channels_config", "slack", "bot_token"]);
-    mask_path(value, &["channels_config", "slack", "app_token"]);
-    mask_path(value, &["channels_config", "webhook", "secret"]);
-    mask_path(value, &["channels_config", "matrix", "access_token"]);
-    mask_path(value, &["channels_config", "whatsapp", "access_token"]);
-    mask_path(value, &["channels_config", "whatsapp", "verify_token"]);
-    mask_path(value, &["channels_config", "whatsapp", "app_secret"]);
-    mask_path(value, &["channels_config", "irc", "server_password"]);
-    mask_path(value, &["channels_config", "irc", "nickserv_password"]);
-    mask_path(value, &["channels_config", "irc", "sasl_password"]);
-    mask_path(value, &["channels_config", "email", "password"]);
+/// [`mask_config_secrets`] with explicit settings: also masks any field
+/// matching an operator-configured pattern, using the configured mode.
+pub fn mask_config_secrets_with(value: &mut Value, masking: &SecretMasking) {
+    mask_known_secret_paths(value, masking);
+    mask_custom_secret_keys(value, masking);
+}
 
-    // Gateway paired tokens (array of strings)
-    mask_array_elements(value, &["gateway", "paired_tokens"]);
+/// Mask every string leaf whose key matches an operator-configured pattern.
+/// Built-in patterns are deliberately not applied here: config secrets are
+/// enumerated by path so that masked sentinels round-trip through PUT.
+fn mask_custom_secret_keys(value: &mut Value, masking: &SecretMasking) {
+    match value {
+        Value::Object(map) => {
+            for (key, val) in map.iter_mut() {
+                match val.as_str() {
+                    Some(s) if masking.detector.is_custom_secret_key(key) => {
+                        *val = Value::String(masking.placeholder(s, MASKED));
+                    }
+                    _ => mask_custom_secret_keys(val, masking),
+                }
+            }
+        }
+        Value::Array(arr) => {
+            for item in arr.iter_mut() {
+                mask_custom_secret_keys(item, masking);
+            }
+        }
+        _ => {}
+    }
+}
 
-    // Composio
-    mask_path(value, &["composio", "api_key"]);
+fn mask_known_secret_paths(value: &mut Value, masking: &SecretMasking) {
+    // Top-level, channel, composio and tunnel scalar secrets
+    for segs in SCALAR_SECRET_PATHS {
+        mask_path(value, segs, masking);
+    }
 
-    // Tunnel
-    mask_path(value, &["tunnel", "ngrok", "auth_token"]);
-    mask_path(value, &["tunnel", "cloudflare", "token"]);
+    // Gateway paired tokens (array of strings)
+    mask_array_elements(value, &["gateway", "paired_tokens"], masking);
 
     // Model routes (array of objects, each may have api_key)
     if let Some(routes) = value.pointer_mut("/model_routes") {
         if let Some(arr) = routes.as_array_mut() {
             for route in arr.iter_mut() {
-                mask_path(route, &["api_key"]);
+                mask_path(route, &["api_key"], masking);
+            }
+        }
+    }
+
+    // HTTP credentials (array of objects; every header value is a secret)
+    if let Some(creds) = value
+        .pointer_mut("/http_credentials")
+        .and_then(Value::as_array_mut)
+    {
+        for cred in creds.iter_mut() {
+            if let Some(headers) = cred.get_mut("headers").and_then(Value::as_object_mut) {
+                for header in headers.values_mut() {
+                    if let Some(s) = header.as_str() {
+                        *header = Value::String(masking.placeholder(s, MASKED));
+                    }
+                }
             }
         }
     }
 }
 
-/// Walk a dotted path into a JSON value and replace the leaf with MASKED,
-/// but only if the leaf is a non-null string.
-fn mask_path(value: &mut Value, segments: &[&str]) {
+/// Header values of each `http_credentials` element, as
+/// `(element index, header name, value)`.
+fn http_credential_header_values(value: &Value) -> Vec<(usize, String, &str)> {
+    let Some(creds) = value.get("http_credentials").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    for (i, cred) in creds.iter().enumerate() {
+        if let Some(headers) = cred.get("headers").and_then(Value::as_object) {
+            for (name, header) in headers {
+                if let Some(s) = header.as_str() {
+                    out.push((i, name.clone(), s));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Walk a dotted path into a JSON value and replace the leaf with its
+/// placeholder (MASKED in `Full` mode), but only if the leaf is a non-null string.
+fn mask_path(value: &mut Value, segments: &[&str], masking: &SecretMasking) {
     if segments.is_empty() {
         return;
     }
@@ -84,16 +241,16 @@ fn mask_path(value: &mut Value, segments: &[&str]) {
     }
 
     let leaf_key = segments[segments.len() - 1];
-    if let Some(leaf) = current.get(leaf_key) {
-        if leaf.is_string() {
-            current[leaf_key] = Value::String(MASKED.to_string());
+    if let Some(leaf) = current.get_mut(leaf_key) {
+        if let Some(s) = leaf.as_str() {
+            *leaf = Value::String(masking.placeholder(s, MASKED));
         }
         // null / missing: leave as-is
     }
 }
 
 /// Mask every string element in an array at the given path.
-fn mask_array_elements(value: &mut Value, segments: &[&str]) {
+fn mask_array_elements(value: &mut Value, segments: &[&str], masking: &SecretMasking) {
     if segments.is_empty() {
         return;
     }
@@ -110,8 +267,8 @@ fn mask_array_elements(value: &mut Value, segments: &[&str]) {
     if let Some(arr_val) = current.get_mut(leaf_key) {
         if let Some(arr) = arr_val.as_array_mut() {
             for elem in arr.iter_mut() {
-                if elem.is_string() {
-                    *elem = Value::String(MASKED.to_string());
+                if let Some(s) = elem.as_str() {
+                    *elem = Value::String(masking.placeholder(s, MASKED));
                 }
             }
         }
@@ -206,6 +363,13 @@ pub fn detect_secret_fields(value: &Value) -> Vec<String> {
         }
     }
 
+    // http_credentials[*].headers.*
+    for (i, name, s) in http_credential_header_values(value) {
+        if s != MASKED {
+            paths.push(format!("http_credentials[{i}].headers.{name}"));
+        }
+    }
+
     paths
 }
 
@@ -217,6 +381,17 @@ pub fn detect_secret_fields(value: &Value) -> Vec<String> {
 pub fn preserve_masked_secrets(
     incoming: &mut Value,
     current: &Value,
+) -> Result<Vec<String>, (String, String)> {
+    preserve_masked_secrets_with(incoming, current, &SecretMasking::default())
+}
+
+/// [`preserve_masked_secrets`] with explicit settings: also accepts the
+/// current value's placeholder in the configured mode, and restores fields
+/// masked by operator-configured patterns.
+pub fn preserve_masked_secrets_with(
+    incoming: &mut Value,
+    current: &Value,
+    masking: &SecretMasking,
 ) -> Result<Vec<String>, (String, String)> {
     let mut new_secret_paths = Vec::new();
 
@@ -225,9 +400,10 @@ pub fn preserve_masked_secrets(
         let path_str = dotted(segs);
         if let Some(incoming_val) = get_at_path(incoming, segs) {
             if let Some(s) = incoming_val.as_str() {
-                if s == MASKED {
+                let current_str = get_at_path(current, segs).and_then(Value::as_str);
+                if masking.is_masked(s, current_str) {
                     // Sentinel -- try to preserve from current
-                    match get_at_path(current, segs).and_then(Value::as_str) {
+                    match current_str {
                         Some(current_str) if current_str != MASKED => {
                             set_at_path(incoming, segs, Value::String(current_str.to_string()));
                         }
@@ -251,10 +427,20 @@ pub fn preserve_masked_secrets(
     if let Some(arr) =
         get_at_path(incoming, &["gateway", "paired_tokens"]).and_then(Value::as_array)
     {
-        let all_masked = !arr.is_empty() && arr.iter().all(|e| e.as_str() == Some(MASKED));
+        let current_arr =
+            get_at_path(current, &["gateway", "paired_tokens"]).and_then(Value::as_array);
+        let is_masked_at = |i: usize, s: &str| {
+            let current_elem = current_arr.and_then(|c| c.get(i)).and_then(Value::as_str);
+            masking.is_masked(s, current_elem)
+        };
+        let all_masked = !arr.is_empty()
+            && arr
+                .iter()
+                .enumerate()
+                .all(|(i, e)| e.as_str().is_some_and(|s| is_masked_at(i, s)));
         if all_masked {
             // Copy entire array from current
-            match get_at_path(current, &["gateway", "paired_tokens"]).and_then(Value::as_array) {
+            match current_arr {
                 Some(current_arr) if !current_arr.is_empty() => {
                     set_at_path(
                         incoming,
@@ -273,7 +459,7 @@ pub fn preserve_masked_secrets(
             // Check for individual new secrets
             for (i, elem) in arr.iter().enumerate() {
                 if let Some(s) = elem.as_str() {
-                    if s != MASKED {
+                    if !is_masked_at(i, s) {
                         new_secret_paths.push(format!("gateway.paired_tokens[{i}]"));
                     }
                 }
@@ -288,12 +474,12 @@ pub fn preserve_masked_secrets(
 
         for (i, route) in routes_snapshot.iter().enumerate() {
             if let Some(s) = route.get("api_key").and_then(Value::as_str) {
-                if s == MASKED {
+                let current_key = current_routes
+                    .and_then(|r| r.get(i))
+                    .and_then(|r| r.get("api_key"))
+                    .and_then(Value::as_str);
+                if masking.is_masked(s, current_key) {
                     // Try to preserve from current route at same index
-                    let current_key = current_routes
-                        .and_then(|r| r.get(i))
-                        .and_then(|r| r.get("api_key"))
-                        .and_then(Value::as_str);
                     match current_key {
                         Some(k) if k != MASKED => {
                             if let Some(route_mut) = incoming
@@ -319,9 +505,87 @@ pub fn preserve_masked_secrets(
         }
     }
 
+    // http_credentials[*].headers.* (preserved from the element at the same index)
+    let incoming_headers: Vec<(usize, String, String)> = http_credential_header_values(incoming)
+        .into_iter()
+        .map(|(i, name, s)| (i, name, s.to_string()))
+        .collect();
+    for (i, name, s) in incoming_headers {
+        let path = format!("http_credentials[{i}].headers.{name}");
+        let current_val = current
+            .get("http_credentials")
+            .and_then(|c| c.get(i))
+            .and_then(|c| c.get("headers"))
+            .and_then(|h| h.get(&name))
+            .and_then(Value::as_str);
+        if !masking.is_masked(&s, current_val) {
+            new_secret_paths.push(path);
+            continue;
+        }
+        match current_val {
+            Some(c) if c != MASKED => {
+                incoming["http_credentials"][i]["headers"][&name] = Value::String(c.to_string());
+            }
+            _ => {
+                return Err((
+                    path.clone(),
+                    format!(
+                        "Cannot preserve masked value for '{path}': no existing secret to preserve"
+                    ),
+                ));
+            }
+        }
+    }
+
+    // Fields masked via operator-configured patterns
+    preserve_custom_masked(incoming, current, masking, "")?;
+
     Ok(new_secret_paths)
 }
 
+/// Restore sentinels on fields masked by custom patterns (objects only; the
+/// array-valued secrets above are handled by their enumerated paths).
+fn preserve_custom_masked(
+    incoming: &mut Value,
+    current: &Value,
+    masking: &SecretMasking,
+    prefix: &str,
+) -> Result<(), (String, String)> {
+    let Some(map) = incoming.as_object_mut() else {
+        return Ok(());
+    };
+    for (key, val) in map.iter_mut() {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        let current_val = current.get(key).unwrap_or(&Value::Null);
+        let echoed_mask = val
+            .as_str()
+            .is_some_and(|s| masking.is_masked(s, current_val.as_str()));
+        if echoed_mask && masking.detector.is_custom_secret_key(key) {
+            match current_val.as_str() {
+                Some(c) if c != MASKED => *val = Value::String(c.to_string()),
+                _ => {
+                    return Err((
+                        path.clone(),
+                        format!("Cannot preserve masked value for '{path}': no existing secret to preserve"),
+                    ))
+                }
+            }
+        } else if val.is_object() {
+            preserve_custom_masked(val, current_val, masking, &path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fields that identify an element in an array of objects, tried in order
+/// (`model_routes` is keyed by `hint`, `http_credentials` by `url_prefix`). Arrays keyed this way are diffed by
+/// element identity, so reordering alone is not a change.
+const ARRAY_IDENTITY_KEYS: &[&str] = &["hint", "url_prefix", "id", "name"];
+
 /// Compute a field-by-field diff between two JSON values.
 /// Both inputs should already be masked.
 pub fn diff_json(old: &Value, new: &Value) -> ConfigDiff {
@@ -348,6 +612,61 @@ pub fn diff_json(old: &Value, new: &Value) -> ConfigDiff {
     }
 }
 
+/// Identity key shared by every element of both arrays: each element must be
+/// an object with a unique string value for it.
+fn array_identity_key(old: &[Value], new: &[Value]) -> Option<&'static str> {
+    ARRAY_IDENTITY_KEYS.iter().copied().find(|key| {
+        [old, new].iter().all(|arr| {
+            let mut seen = std::collections::HashSet::new();
+            arr.iter().all(|v| {
+                v.get(key)
+                    .and_then(Value::as_str)
+                    .is_some_and(|id| seen.insert(id))
+            })
+        })
+    })
+}
+
+/// Diff two arrays of objects by `key`: elements are addressed as
+/// `prefix[key=value]`, matched by identity rather than position.
+#[allow(clippy::too_many_arguments)]
+fn diff_keyed_arrays(
+    old: &[Value],
+    new: &[Value],
+    key: &str,
+    prefix: &str,
+    changes: &mut Vec<ConfigChange>,
+    added: &mut Vec<String>,
+    removed: &mut Vec<String>,
+    unchanged: &mut usize,
+) {
+    let id_of = |v: &Value| {
+        v.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let new_by_id: std::collections::HashMap<String, &Value> =
+        new.iter().map(|v| (id_of(v), v)).collect();
+    for old_val in old {
+        let id = id_of(old_val);
+        let path = format!("{prefix}[{key}={id}]");
+        match new_by_id.get(&id) {
+            Some(new_val) => {
+                diff_recursive(old_val, new_val, &path, changes, added, removed, unchanged);
+            }
+            None => removed.push(path),
+        }
+    }
+    let old_ids: std::collections::HashSet<String> = old.iter().map(id_of).collect();
+    for new_val in new {
+        let id = id_of(new_val);
+        if !old_ids.contains(&id) {
+            added.push(format!("{prefix}[{key}={id}]"));
+        }
+    }
+}
+
 fn diff_recursive(
     old: &Value,
     new: &Value,
@@ -388,7 +707,11 @@ fn diff_recursive(
             }
         }
         (Value::Array(old_arr), Value::Array(new_arr)) => {
-            if old_arr.len() == new_arr.len() {
+            if let Some(key) = array_identity_key(old_arr, new_arr) {
+                diff_keyed_arrays(
+                    old_arr, new_arr, key, prefix, changes, added, removed, unchanged,
+                );
+            } else if old_arr.len() == new_arr.len() {
                 for (i, (o, n)) in old_arr.iter().zip(new_arr.iter()).enumerate() {
                     let path = format!("{prefix}[{i}]");
                     diff_recursive(o, n, &path, changes, added, removed, unchanged);
@@ -425,7 +748,7 @@ fn diff_recursive(
 
 const REDACTED: &str = "***REDACTED***";
 
-/// Keys whose string values should be redacted in message payloads.
+/// Built-in key-name patterns treated as secrets (case-insensitive substring).
 const SECRET_KEY_PATTERNS: &[&str] = &[
     "api_key",
     "apikey",
@@ -439,31 +762,92 @@ const SECRET_KEY_PATTERNS: &[&str] = &[
     "credentials",
 ];
 
-/// Scan a JSON payload for known secret patterns and replace string values with `***REDACTED***`.
-/// Checks JSON object keys (case-insensitive) against known secret-like patterns.
+/// Env var with extra comma-separated secret key patterns, e.g. `"otel_endpoint,dsn"`.
+pub const SECRET_KEY_PATTERNS_ENV: &str = "ZEROCLAW_CP_SECRET_KEY_PATTERNS";
+
+/// Secret field detection shared by config masking and payload redaction.
+///
+/// A key is secret if it contains a built-in pattern, is the leaf name of a
+/// known config secret path, or contains an operator-configured pattern.
+#[derive(Debug, Clone, Default)]
+pub struct SecretKeyDetector {
+    extra: Vec<String>,
+}
+
+impl SecretKeyDetector {
+    /// Detector with extra patterns (trimmed, lowercased, empties dropped).
+    pub fn new<I, S>(extra: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            extra: extra
+                .into_iter()
+                .map(|p| p.as_ref().trim().to_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Built-ins plus patterns from [`SECRET_KEY_PATTERNS_ENV`].
+    pub fn from_env() -> Self {
+        std::env::var(SECRET_KEY_PATTERNS_ENV)
+            .map(|v| Self::new(v.split(',')))
+            .unwrap_or_default()
+    }
+
+    pub fn extra_patterns(&self) -> &[String] {
+        &self.extra
+    }
+
+    /// True if `key` looks like a secret field name.
+    pub fn is_secret_key(&self, key: &str) -> bool {
+        let key_lower = key.to_lowercase();
+        SECRET_KEY_PATTERNS
+            .iter()
+            .any(|pattern| key_lower.contains(pattern))
+            || SCALAR_SECRET_PATHS
+                .iter()
+                .filter_map(|segs| segs.last())
+                .any(|leaf| key_lower == *leaf)
+            || self.matches_extra(&key_lower)
+    }
+
+    /// True if `key` matches an operator-configured pattern only.
+    pub fn is_custom_secret_key(&self, key: &str) -> bool {
+        self.matches_extra(&key.to_lowercase())
+    }
+
+    fn matches_extra(&self, key_lower: &str) -> bool {
+        self.extra.iter().any(|p| key_lower.contains(p.as_str()))
+    }
+}
+
+/// Scan a JSON payload for secret-like keys and replace string values with
+/// `***REDACTED***`, using the default [`SecretMasking`].
+/// Uses the same detection as config masking (see [`SecretKeyDetector`]).
 pub fn redact_payload_secrets(value: &mut Value) {
+    redact_payload_secrets_with(value, &SecretMasking::default());
+}
+
+/// [`redact_payload_secrets`] with explicit detector and redaction mode.
+pub fn redact_payload_secrets_with(value: &mut Value, masking: &SecretMasking) {
     match value {
         Value::Object(map) => {
-            let keys: Vec<String> = map.keys().cloned().collect();
-            for key in keys {
-                let key_lower = key.to_lowercase();
-                let is_secret = SECRET_KEY_PATTERNS
-                    .iter()
-                    .any(|pattern| key_lower.contains(pattern));
-                if is_secret {
-                    if let Some(val) = map.get_mut(&key) {
-                        if val.is_string() {
-                            *val = Value::String(REDACTED.to_string());
-                        }
+            for (key, val) in map.iter_mut() {
+                if masking.detector.is_secret_key(key) {
+                    if let Some(s) = val.as_str() {
+                        *val = Value::String(masking.placeholder(s, REDACTED));
                     }
-                } else if let Some(val) = map.get_mut(&key) {
-                    redact_payload_secrets(val);
+                } else {
+                    redact_payload_secrets_with(val, masking);
                 }
             }
         }
         Value::Array(arr) => {
             for item in arr.iter_mut() {
-                redact_payload_secrets(item);
+                redact_payload_secrets_with(item, masking);
             }
         }
         _ => {}
@@ -534,6 +918,7 @@ pub fn collect_key_paths(value: &Value, prefix: &str) -> std::collections::HashS
 /// Used by validate_patch_paths to reject unknown keys in PATCH payloads.
 pub const VALID_CONFIG_PATHS: &[&str] = &[
     // Top-level
+    "schema_version",
     "api_key",
     "default_provider",
     "default_model",
@@ -625,11 +1010,15 @@ pub const VALID_CONFIG_PATHS: &[&str] = &[
     "identity.format",
     "identity.aieos_path",
     "identity.aieos_inline",
+    // Speech-to-text
+    "stt.backend",
+    "stt.endpoint",
     // Channels
     "channels_config.cli",
     // Telegram
     "channels_config.telegram.bot_token",
     "channels_config.telegram.allowed_users",
+    "channels_config.telegram.usernames_case_sensitive",
     "channels_config.telegram.stt_endpoint",
     "channels_config.telegram.flows_enabled",
     "channels_config.telegram.flow_policy.agent_authoring_enabled",
@@ -640,6 +1029,13 @@ pub const VALID_CONFIG_PATHS: &[&str] = &[
     "channels_config.telegram.flow_policy.auto_approve",
     "channels_config.telegram.flow_policy.auto_approve_max_steps",
     "channels_config.telegram.flow_policy.denied_text_patterns",
+    "channels_config.telegram.auto_download.enabled",
+    "channels_config.telegram.auto_download.attach_base64",
+    "channels_config.telegram.auto_download.max_photo_bytes",
+    "channels_config.telegram.auto_download.max_document_bytes",
+    "channels_config.telegram.auto_download.max_voice_bytes",
+    "channels_config.telegram.placeholder_replies",
+    "channels_config.telegram.markdown_v2",
     // Discord
     "channels_config.discord.bot_token",
     "channels_config.discord.guild_id",
@@ -690,13 +1086,23 @@ pub const VALID_CONFIG_PATHS: &[&str] = &[
     "channels_config.email.allowed_senders",
     // CP Relay
     "channels_config.cp_relay.cp_url",
+    // Quiet hours
+    "channels_config.quiet_hours.utc_offset",
+    "channels_config.quiet_hours.start",
+    "channels_config.quiet_hours.end",
     // Model routes (array-of-tables, validated separately)
     "model_routes",
+    // HTTP credentials (array-of-tables, validated separately)
+    "http_credentials",
 ];
 
 /// Valid keys within a model_routes element.
 pub const MODEL_ROUTE_ELEMENT_KEYS: &[&str] = &["hint", "provider", "model", "api_key"];
 
+/// Valid keys within an http_credentials element. Keys under `headers` are
+/// header names and are not checked.
+pub const HTTP_CREDENTIAL_ELEMENT_KEYS: &[&str] = &["url_prefix", "headers"];
+
 /// Paths where null is allowed (Option<T> fields and section-level Option<ChannelConfig>).
 pub const NULLABLE_PATHS: &[&str] = &[
     // Top-level Option<String>
@@ -714,6 +1120,7 @@ pub const NULLABLE_PATHS: &[&str] = &[
     "channels_config.irc",
     "channels_config.imessage",
     "channels_config.cp_relay",
+    "channels_config.quiet_hours",
     // Tunnel sub-configs (Option<TunnelSubConfig>)
     "tunnel.cloudflare",
     "tunnel.tailscale",
@@ -738,6 +1145,7 @@ pub const NULLABLE_PATHS: &[&str] = &[
     "composio.api_key",
     "identity.aieos_path",
     "identity.aieos_inline",
+    "stt.endpoint",
     "browser.session_name",
     "observability.otel_endpoint",
     "observability.otel_service_name",
@@ -767,6 +1175,7 @@ pub const SECRET_PATHS_MANIFEST: &[&str] = &[
     "tunnel.cloudflare.token",
     "gateway.paired_tokens",
     "model_routes[*].api_key",
+    "http_credentials[*].headers.*",
 ];
 
 // ── PATCH validation functions ───────────────────────────────
@@ -800,7 +1209,8 @@ pub fn reject_dotted_keys(patch: &Value, prefix: &str) -> Result<(), String> {
 }
 
 /// Validate that all leaf paths in a patch exist in VALID_CONFIG_PATHS.
-/// For model_routes array elements, checks keys against MODEL_ROUTE_ELEMENT_KEYS.
+/// For model_routes and http_credentials array elements, checks keys against
+/// MODEL_ROUTE_ELEMENT_KEYS / HTTP_CREDENTIAL_ELEMENT_KEYS.
 pub fn validate_patch_paths(patch: &Value) -> Result<(), Vec<String>> {
     let mut invalid = Vec::new();
     validate_paths_recursive(patch, "", &mut invalid);
@@ -837,6 +1247,21 @@ fn validate_paths_recursive(value: &Value, prefix: &str, invalid: &mut Vec<Strin
                 continue;
             }
 
+            if path == "http_credentials" {
+                if let Some(arr) = val.as_array() {
+                    for (i, elem) in arr.iter().enumerate() {
+                        if let Some(elem_obj) = elem.as_object() {
+                            for elem_key in elem_obj.keys() {
+                                if !HTTP_CREDENTIAL_ELEMENT_KEYS.contains(&elem_key.as_str()) {
+                                    invalid.push(format!("http_credentials[{i}].{elem_key}"));
+                                }
+                            }
+                        }
+                    }
+                }
+                continue;
+            }
+
             if val.is_object() {
                 // Check if this intermediate path is a valid section-level nullable
                 // (e.g. channels_config.telegram set to null for removal)
@@ -937,7 +1362,8 @@ pub fn is_secret_path(path: &str) -> bool {
 }
 
 /// Collect all secret paths present in a PATCH payload.
-/// Covers scalar secrets, gateway.paired_tokens, and model_routes[*].api_key.
+/// Covers scalar secrets, gateway.paired_tokens, model_routes[*].api_key, and
+/// http_credentials[*].headers.*.
 pub fn collect_secret_writes(patch: &Value) -> Vec<String> {
     let mut paths = Vec::new();
     collect_secrets_recursive(patch, "", &mut paths);
@@ -951,6 +1377,11 @@ pub fn collect_secret_writes(patch: &Value) -> Vec<String> {
         }
     }
 
+    // http_credentials header values
+    for (i, name, _) in http_credential_header_values(patch) {
+        paths.push(format!("http_credentials[{i}].headers.{name}"));
+    }
+
     paths
 }
 
@@ -1177,6 +1608,7 @@ fn fingerprint_value(value: &str, path_hint: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::json_schema::{json_schema, schema_at};
     use serde_json::json;
 
     #[test]
@@ -1245,6 +1677,27 @@ mod tests {
         assert!(v["model_routes"][1].get("api_key").is_none());
     }
 
+    #[test]
+    fn mask_http_credential_headers() {
+        let mut v = json!({
+            "http_credentials": [
+                { "url_prefix": "https://api.example.com/", "headers": { "Authorization": "Bearer x" } }
+            ]
+        });
+        mask_config_secrets(&mut v);
+        assert_eq!(v["http_credentials"][0]["headers"]["Authorization"], MASKED);
+        assert_eq!(
+            v["http_credentials"][0]["url_prefix"],
+            "https://api.example.com/"
+        );
+        assert_eq!(
+            detect_secret_fields(&json!({
+                "http_credentials": [{ "url_prefix": "a", "headers": { "X-Key": "k" } }]
+            })),
+            vec!["http_credentials[0].headers.X-Key".to_string()]
+        );
+    }
+
     #[test]
     fn mask_tunnel_secrets() {
         let mut v = json!({
@@ -1427,6 +1880,28 @@ mod tests {
         assert_eq!(incoming["model_routes"][0]["api_key"], "route-secret");
     }
 
+    #[test]
+    fn preserve_http_credential_header() {
+        let current = json!({
+            "http_credentials": [{ "url_prefix": "a", "headers": { "X-Key": "real" } }]
+        });
+        let mut incoming = json!({
+            "http_credentials": [{ "url_prefix": "a", "headers": { "X-Key": MASKED, "X-New": "n" } }]
+        });
+        let new_paths = preserve_masked_secrets(&mut incoming, &current).unwrap();
+        assert_eq!(incoming["http_credentials"][0]["headers"]["X-Key"], "real");
+        assert_eq!(
+            new_paths,
+            vec!["http_credentials[0].headers.X-New".to_string()]
+        );
+
+        let mut dangling = json!({
+            "http_credentials": [{ "url_prefix": "a", "headers": { "X-Other": MASKED } }]
+        });
+        let err = preserve_masked_secrets(&mut dangling, &current).unwrap_err();
+        assert_eq!(err.0, "http_credentials[0].headers.X-Other");
+    }
+
     // ── diff_json tests ─────────────────────────────────────────
 
     #[test]
@@ -1468,6 +1943,49 @@ mod tests {
         assert_eq!(diff.unchanged_count, 2);
     }
 
+    #[test]
+    fn diff_reordered_model_routes_is_not_a_change() {
+        let fast = json!({ "hint": "fast", "provider": "groq", "model": "llama-3" });
+        let deep = json!({ "hint": "reasoning", "provider": "anthropic", "model": "opus" });
+        let old = json!({ "model_routes": [fast.clone(), deep.clone()] });
+        let new = json!({ "model_routes": [deep, fast] });
+        let diff = diff_json(&old, &new);
+        assert!(
+            diff.changes.is_empty(),
+            "spurious changes: {:?}",
+            diff.changes
+        );
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.unchanged_count, 6);
+    }
+
+    #[test]
+    fn diff_model_routes_by_hint() {
+        let old = json!({ "model_routes": [
+            { "hint": "fast", "provider": "groq", "model": "llama-3" },
+            { "hint": "code", "provider": "openai", "model": "gpt-4" },
+        ] });
+        let new = json!({ "model_routes": [
+            { "hint": "summarize", "provider": "groq", "model": "llama-3" },
+            { "hint": "fast", "provider": "groq", "model": "llama-4" },
+        ] });
+        let diff = diff_json(&old, &new);
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].path, "model_routes[hint=fast].model");
+        assert_eq!(diff.removed, ["model_routes[hint=code]"]);
+        assert_eq!(diff.added, ["model_routes[hint=summarize]"]);
+    }
+
+    #[test]
+    fn diff_scalar_arrays_stay_positional() {
+        let old = json!({ "allowed_users": ["alice", "bob"] });
+        let new = json!({ "allowed_users": ["bob", "alice"] });
+        let diff = diff_json(&old, &new);
+        assert_eq!(diff.changes.len(), 2);
+        assert_eq!(diff.changes[0].path, "allowed_users[0]");
+    }
+
     // ── PATCH validation tests ──────────────────────────────────
 
     #[test]
@@ -1529,6 +2047,19 @@ mod tests {
         assert!(result.unwrap_err().contains(&"model_routes[0].bogus".to_string()));
     }
 
+    #[test]
+    fn validate_paths_checks_http_credential_keys() {
+        let patch = json!({
+            "http_credentials": [
+                { "url_prefix": "a", "headers": { "Any-Header": "v" }, "bogus": true }
+            ]
+        });
+        let invalid = validate_patch_paths(&patch).unwrap_err();
+        assert_eq!(invalid, vec!["http_credentials[0].bogus".to_string()]);
+        assert!(collect_secret_writes(&patch)
+            .contains(&"http_credentials[0].headers.Any-Header".to_string()));
+    }
+
     #[test]
     fn validate_null_rejects_required_field() {
         let patch = json!({ "default_temperature": null });
@@ -1700,6 +2231,7 @@ mod tests {
         Config {
             workspace_dir: std::path::PathBuf::new(),
             config_path: std::path::PathBuf::new(),
+            schema_version: 1,
             api_key: Some("test-key".into()),
             default_provider: Some("openrouter".into()),
             default_model: Some("test-model".into()),
@@ -1763,6 +2295,7 @@ mod tests {
                 telegram: Some(TelegramConfig {
                     bot_token: "123:ABC".into(),
                     allowed_users: vec!["user1".into()],
+                    usernames_case_sensitive: false,
                     stt_endpoint: Some("http://localhost:9000".into()),
                     flows_enabled: true,
                     flow_policy: FlowPolicyConfig {
@@ -1775,6 +2308,9 @@ mod tests {
                         auto_approve_max_steps: 5,
                         denied_text_patterns: vec!["blocked".into()],
                     },
+                    auto_download: TelegramAutoDownloadConfig::default(),
+                    placeholder_replies: false,
+                    markdown_v2: false,
                 }),
                 discord: Some(DiscordConfig {
                     bot_token: "discord-tok".into(),
@@ -1835,6 +2371,11 @@ mod tests {
                 cp_relay: Some(CpRelayConfig {
                     cp_url: "http://localhost:18800".into(),
                 }),
+                quiet_hours: Some(QuietHoursConfig {
+                    utc_offset: "+02:00".into(),
+                    start: "22:00".into(),
+                    end: "07:00".into(),
+                }),
             },
             memory: MemoryConfig {
                 backend: "sqlite".into(),
@@ -1896,6 +2437,14 @@ mod tests {
                 aieos_path: Some("/path/to/aieos.json".into()),
                 aieos_inline: Some("{\"name\":\"test\"}".into()),
             },
+            stt: SttConfig {
+                backend: "http".into(),
+                endpoint: Some("http://localhost:9000".into()),
+            },
+            http_credentials: vec![HttpCredentialConfig {
+                url_prefix: "https://api.example.com/".into(),
+                headers: [("Authorization".to_string(), "Bearer tok".to_string())].into(),
+            }],
         }
     }
 
@@ -1991,4 +2540,209 @@ mod tests {
         let result = validate_patch_paths(&patch);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn payload_redaction_covers_config_secret_leaf_names() {
+        // "verify_token"/"app_secret" come from config secret paths; "server_password"
+        // too. All must be redacted in payloads without being listed twice.
+        let mut v = json!({
+            "verify_token": "v",
+            "nested": { "server_password": "p", "note": "keep" },
+        });
+        redact_payload_secrets_with(&mut v, &SecretMasking::default());
+        assert_eq!(v["verify_token"], REDACTED);
+        assert_eq!(v["nested"]["server_password"], REDACTED);
+        assert_eq!(v["nested"]["note"], "keep");
+    }
+
+    #[test]
+    fn custom_secret_pattern_masks_config_and_redacts_payload() {
+        let detector = SecretKeyDetector::new([" OTEL_Endpoint ", ""]);
+        assert_eq!(detector.extra_patterns(), ["otel_endpoint"]);
+        let masking = SecretMasking {
+            detector,
+            ..SecretMasking::default()
+        };
+
+        let mut config = json!({
+            "default_temperature": 0.7,
+            "observability": { "backend": "otel", "otel_endpoint": "https://u:p@collector" },
+        });
+        mask_config_secrets_with(&mut config, &masking);
+        assert_eq!(config["observability"]["otel_endpoint"], MASKED);
+        assert_eq!(config["observability"]["backend"], "otel");
+
+        let mut payload = json!({ "report": { "otel_endpoint": "https://u:p@collector" } });
+        redact_payload_secrets_with(&mut payload, &masking);
+        assert_eq!(payload["report"]["otel_endpoint"], REDACTED);
+
+        // Without the custom pattern neither side touches the field
+        let mut config = json!({ "observability": { "otel_endpoint": "x" } });
+        mask_config_secrets_with(&mut config, &SecretMasking::default());
+        assert_eq!(config["observability"]["otel_endpoint"], "x");
+    }
+
+    #[test]
+    fn custom_masked_sentinel_is_preserved() {
+        let masking = SecretMasking {
+            detector: SecretKeyDetector::new(["otel_endpoint"]),
+            ..SecretMasking::default()
+        };
+        let current = json!({ "observability": { "otel_endpoint": "real" } });
+
+        let mut incoming = json!({ "observability": { "otel_endpoint": MASKED } });
+        preserve_custom_masked(&mut incoming, &current, &masking, "").unwrap();
+        assert_eq!(incoming["observability"]["otel_endpoint"], "real");
+
+        let mut dangling = json!({ "observability": { "otel_endpoint": MASKED } });
+        let err = preserve_custom_masked(&mut dangling, &json!({}), &masking, "").unwrap_err();
+        assert_eq!(err.0, "observability.otel_endpoint");
+    }
+
+    // ── Redaction modes ─────────────────────────────────────────
+
+    fn in_mode(mode: RedactionMode) -> SecretMasking {
+        SecretMasking {
+            mode,
+            hash_key: b"test-install-key".to_vec(),
+            ..SecretMasking::default()
+        }
+    }
+
+    #[test]
+    fn redaction_mode_placeholders() {
+        assert_eq!(
+            in_mode(RedactionMode::Full).placeholder("s3cret", MASKED),
+            MASKED
+        );
+        assert_eq!(
+            in_mode(RedactionMode::Length).placeholder("s3cret", MASKED),
+            "******"
+        );
+        let hash = in_mode(RedactionMode::Hash);
+        let hashed = hash.placeholder("s3cret", MASKED);
+        assert!(hashed.starts_with("hmac:"));
+        assert_eq!(hashed.len(), "hmac:".len() + 12);
+        assert_eq!(hashed, hash.placeholder("s3cret", REDACTED));
+        assert_ne!(hashed, hash.placeholder("other", MASKED));
+
+        assert_eq!(RedactionMode::parse(" Hash "), Some(RedactionMode::Hash));
+        assert_eq!(RedactionMode::parse("length"), Some(RedactionMode::Length));
+        assert_eq!(RedactionMode::parse("full"), Some(RedactionMode::Full));
+        assert_eq!(RedactionMode::parse("partial"), None);
+    }
+
+    #[test]
+    fn hash_mode_is_keyed_per_install() {
+        let ours = in_mode(RedactionMode::Hash);
+        let theirs = SecretMasking {
+            hash_key: b"another-install-key".to_vec(),
+            ..ours.clone()
+        };
+        assert_ne!(
+            ours.placeholder("sk-shared", MASKED),
+            theirs.placeholder("sk-shared", MASKED)
+        );
+        // A plain SHA-256 of a guess does not match
+        let unkeyed = hex::encode(<Sha256 as sha2::Digest>::digest(b"sk-shared"));
+        assert_ne!(
+            ours.placeholder("sk-shared", MASKED),
+            format!("hmac:{}", &unkeyed[..12])
+        );
+    }
+
+    #[test]
+    fn hash_mode_correlates_reused_config_secret() {
+        let mut v = json!({
+            "api_key": "sk-shared",
+            "composio": { "api_key": "sk-shared" },
+            "channels_config": { "telegram": { "bot_token": "123:abc" } },
+        });
+        mask_config_secrets_with(&mut v, &in_mode(RedactionMode::Hash));
+        assert_eq!(v["api_key"], v["composio"]["api_key"]);
+        assert_ne!(v["api_key"], v["channels_config"]["telegram"]["bot_token"]);
+        assert!(!contains_raw_secrets(&v, &["sk-shared", "123:abc"]));
+    }
+
+    #[test]
+    fn length_mode_masks_config_and_custom_keys() {
+        let masking = SecretMasking {
+            detector: SecretKeyDetector::new(["dsn"]),
+            ..in_mode(RedactionMode::Length)
+        };
+        let mut v = json!({
+            "api_key": "abcd",
+            "gateway": { "paired_tokens": ["xy", "xyz"] },
+            "observability": { "sentry_dsn": "https://k@s" },
+        });
+        mask_config_secrets_with(&mut v, &masking);
+        assert_eq!(v["api_key"], "****");
+        assert_eq!(v["gateway"]["paired_tokens"], json!(["**", "***"]));
+        assert_eq!(v["observability"]["sentry_dsn"], "***********");
+    }
+
+    #[test]
+    fn hash_mode_placeholders_round_trip_through_preserve() {
+        let current = json!({
+            "api_key": "sk-old",
+            "gateway": { "paired_tokens": ["t1", "t2"] },
+            "model_routes": [{ "hint": "fast", "api_key": "rk-old" }],
+        });
+        let mut incoming = current.clone();
+        let hash = in_mode(RedactionMode::Hash);
+        mask_config_secrets_with(&mut incoming, &hash);
+
+        let new_paths = preserve_masked_secrets_with(&mut incoming, &current, &hash).unwrap();
+        assert!(new_paths.is_empty());
+        assert_eq!(incoming, current);
+
+        // A value that is not the current secret's hash is a new secret
+        let mut changed = json!({ "api_key": "hmac:000000000000" });
+        let new_paths = preserve_masked_secrets_with(&mut changed, &current, &hash).unwrap();
+        assert_eq!(new_paths, ["api_key"]);
+        // The fixed sentinel is still accepted in any mode
+        let mut sentinel = json!({ "api_key": MASKED });
+        preserve_masked_secrets_with(&mut sentinel, &current, &hash).unwrap();
+        assert_eq!(sentinel["api_key"], "sk-old");
+    }
+
+    #[test]
+    fn payload_redaction_modes() {
+        let payload =
+            json!({ "token": "abc123", "items": [{ "password": "abc123" }], "note": "hi" });
+
+        let mut full = payload.clone();
+        redact_payload_secrets_with(&mut full, &in_mode(RedactionMode::Full));
+        assert_eq!(full["token"], REDACTED);
+
+        let mut length = payload.clone();
+        redact_payload_secrets_with(&mut length, &in_mode(RedactionMode::Length));
+        assert_eq!(length["token"], "******");
+        assert_eq!(length["note"], "hi");
+
+        let mut hashed = payload;
+        redact_payload_secrets_with(&mut hashed, &in_mode(RedactionMode::Hash));
+        assert_eq!(hashed["token"], hashed["items"][0]["password"]);
+        assert!(!contains_raw_secrets(&hashed, &["abc123"]));
+    }
+
+    #[test]
+    fn schema_covers_every_valid_config_path() {
+        let schema = json_schema();
+        for path in VALID_CONFIG_PATHS {
+            assert!(schema_at(&schema, path).is_some(), "schema lacks {path}");
+        }
+    }
+
+    #[test]
+    fn schema_marks_every_secret_path() {
+        let schema = json_schema();
+        for path in SECRET_PATHS_MANIFEST {
+            let node = schema_at(&schema, path).unwrap_or_else(|| panic!("schema lacks {path}"));
+            assert_eq!(node["x-secret"], true, "{path} not marked secret");
+            assert_eq!(node["writeOnly"], true, "{path} not writeOnly");
+        }
+        let port = schema_at(&schema, "gateway.port").unwrap();
+        assert!(port.get("x-secret").is_none());
+    }
 }
@@ -0,0 +1,77 @@
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+/// Status changes a slow subscriber may fall behind by before it starts
+/// missing them (it is told how many it skipped).
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// One message status transition, as published to subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MessageStatusEvent {
+    /// What happened: `enqueued`, `leased`, `acknowledged`, `retried` or
+    /// `dead_lettered`. Used as the SSE `event:` type.
+    pub event: &'static str,
+    pub message_id: String,
+    /// The message's status after the transition.
+    pub status: &'static str,
+    pub at: String,
+}
+
+/// In-process fan-out of message status changes. Subscribers only see
+/// transitions published after they subscribe; nothing is replayed.
+pub struct MessageEventBus {
+    tx: broadcast::Sender<MessageStatusEvent>,
+}
+
+impl Default for MessageEventBus {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl MessageEventBus {
+    /// Process-wide bus fed by the messaging handlers and the delivery worker.
+    pub fn global() -> &'static Self {
+        static BUS: OnceLock<MessageEventBus> = OnceLock::new();
+        BUS.get_or_init(Self::default)
+    }
+
+    pub fn publish(&self, event: &'static str, message_id: &str, status: &'static str) {
+        // Fails only when nobody is subscribed, which is fine
+        let _ = self.tx.send(MessageStatusEvent {
+            event,
+            message_id: message_id.to_string(),
+            status,
+            at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<MessageStatusEvent> {
+        self.tx.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_only_see_later_events() {
+        let bus = MessageEventBus::default();
+        bus.publish("enqueued", "m1", "queued");
+
+        let mut rx = bus.subscribe();
+        bus.publish("leased", "m1", "leased");
+        bus.publish("acknowledged", "m1", "acknowledged");
+
+        let first = rx.try_recv().unwrap();
+        assert_eq!((first.event, first.status), ("leased", "leased"));
+        assert_eq!(first.message_id, "m1");
+        assert_eq!(rx.try_recv().unwrap().event, "acknowledged");
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -1,14 +1,25 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use axum::body::{Body, Bytes};
 use axum::extract::{Path as AxumPath, Query, State};
-use axum::http::StatusCode;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 
-use crate::cp::masking::redact_payload_secrets;
-use crate::cp::server::CpState;
-use crate::db::{NewMessage, Registry};
+use crate::cp::masking::{redact_payload_secrets_with, SecretMasking};
+use crate::cp::message_events::MessageEventBus;
+use crate::cp::metrics::{MessagingCounts, MessagingMetrics};
+use crate::cp::routing_cache::RoutingRuleCache;
+use crate::cp::server::{paginated, CpState, ALLOW_SECRET_EXPORT_ENV};
+use crate::cp::transform;
+use crate::cp::workers;
+use crate::db::{
+    BatchEnqueueOutcome, HealthGate, MessageSearchFilters, NackOutcome, NewMessage, Registry,
+};
 use crate::lifecycle;
 
 type ApiResponse = (StatusCode, Json<serde_json::Value>);
@@ -22,7 +33,186 @@ fn err_json(status: StatusCode, message: &str) -> ApiResponse {
 }
 
 const MAX_PAYLOAD_BYTES: usize = 65536; // 64 KiB
-const MAX_HOP_COUNT: i64 = 8;
+const DEFAULT_MAX_HOP_COUNT: i64 = 8;
+/// Highest message priority; requested priorities are clamped to `0..=9`.
+const MAX_PRIORITY: i64 = 9;
+/// `content_type` of a JSON payload; any other value marks a raw payload.
+const JSON_CONTENT_TYPE: &str = "application/json";
+const DEFAULT_TTL_SECS: i64 = 3600;
+const DEFAULT_MAX_TTL_SECS: i64 = 86400;
+
+// ── TTL policy ───────────────────────────────────────────────────
+
+/// Server-side TTL bounds for messages and routing rules.
+///
+/// Read from `ZEROCLAW_CP_DEFAULT_TTL_SECS` / `ZEROCLAW_CP_MAX_TTL_SECS` once
+/// at startup and kept in [`CpState`]; unset or invalid values fall back to
+/// 1 hour / 24 hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtlPolicy {
+    /// TTL applied when the caller omits `ttl_secs`.
+    pub default_ttl_secs: i64,
+    /// Upper bound; larger requests are clamped to this.
+    pub max_ttl_secs: i64,
+}
+
+impl Default for TtlPolicy {
+    fn default() -> Self {
+        Self {
+            default_ttl_secs: DEFAULT_TTL_SECS,
+            max_ttl_secs: DEFAULT_MAX_TTL_SECS,
+        }
+    }
+}
+
+impl TtlPolicy {
+    /// A policy from configured values: `None` or non-positive keeps the
+    /// default, and the default TTL never exceeds the max.
+    pub fn new(default_ttl_secs: Option<i64>, max_ttl_secs: Option<i64>) -> Self {
+        let defaults = Self::default();
+        let max_ttl_secs = max_ttl_secs
+            .filter(|v| *v > 0)
+            .unwrap_or(defaults.max_ttl_secs);
+        let default_ttl_secs = default_ttl_secs
+            .filter(|v| *v > 0)
+            .unwrap_or(defaults.default_ttl_secs)
+            .min(max_ttl_secs);
+        Self {
+            default_ttl_secs,
+            max_ttl_secs,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        fn i64_env(key: &str) -> Option<i64> {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+        }
+        Self::new(
+            i64_env("ZEROCLAW_CP_DEFAULT_TTL_SECS"),
+            i64_env("ZEROCLAW_CP_MAX_TTL_SECS"),
+        )
+    }
+
+    /// Resolve a requested TTL: `None` uses the default, values above the max
+    /// are clamped, and non-positive values are rejected.
+    pub fn effective(&self, requested: Option<i64>) -> Result<i64, String> {
+        match requested {
+            None => Ok(self.default_ttl_secs),
+            Some(ttl) if ttl <= 0 => Err(format!("ttl_secs must be positive (got {ttl})")),
+            Some(ttl) => Ok(ttl.min(self.max_ttl_secs)),
+        }
+    }
+}
+
+/// Messages with `hop_count` at or above this are rejected. Read from
+/// `ZEROCLAW_CP_MAX_HOP_COUNT`; unset or invalid (non-positive) values fall
+/// back to 8.
+pub fn max_hop_count() -> i64 {
+    std::env::var("ZEROCLAW_CP_MAX_HOP_COUNT")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_MAX_HOP_COUNT)
+}
+
+// ── Auto-authorization ───────────────────────────────────────────
+
+/// Senders allowed to create their own routing rules with `ensure_rule`.
+/// Off by default: it bypasses the intent of the routing allowlist, so the
+/// operator opts senders in with `ZEROCLAW_CP_AUTO_AUTHORIZE_SENDERS`
+/// (comma-separated instance names, or `*` for all).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoAuthorize {
+    senders: Vec<String>,
+}
+
+impl AutoAuthorize {
+    pub fn from_env() -> Self {
+        Self::parse(&std::env::var("ZEROCLAW_CP_AUTO_AUTHORIZE_SENDERS").unwrap_or_default())
+    }
+
+    /// Senders from a comma-separated list, as in the env var.
+    pub fn parse(senders: &str) -> Self {
+        let senders = senders
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        Self { senders }
+    }
+
+    pub fn allows(&self, sender: &str) -> bool {
+        self.senders.iter().any(|s| s == "*" || s == sender)
+    }
+}
+
+/// What a send request proves about its caller: the instance token from
+/// `Authorization: Bearer` (issued at instance start), checked against the
+/// registry only when the send needs it (`ensure_rule`).
+#[derive(Debug, Clone, Default)]
+struct SendAuth {
+    token: Option<String>,
+    auto_authorize: AutoAuthorize,
+}
+
+impl SendAuth {
+    fn from_request(state: &CpState, headers: &HeaderMap) -> Self {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|t| t.trim().to_string());
+        Self {
+            token,
+            auto_authorize: state.auto_authorize.clone(),
+        }
+    }
+
+    /// 401 unless the token belongs to an active instance; 403 unless that
+    /// instance is `from` and the operator trusts it with `ensure_rule`.
+    fn authorize_ensure_rule(
+        &self,
+        registry: &Registry,
+        from: &str,
+    ) -> Result<(), (StatusCode, String)> {
+        let token = self.token.as_deref().ok_or_else(|| {
+            (
+                StatusCode::UNAUTHORIZED,
+                "ensure_rule requires the sending instance's token \
+                 (Authorization: Bearer)"
+                    .to_string(),
+            )
+        })?;
+        let caller = registry
+            .instance_name_for_token(token)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+            .ok_or_else(|| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    "Invalid instance token".to_string(),
+                )
+            })?;
+        if caller != from {
+            return Err((
+                StatusCode::FORBIDDEN,
+                format!("Instance token belongs to '{caller}', not '{from}'"),
+            ));
+        }
+        if !self.auto_authorize.allows(from) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                format!(
+                    "ensure_rule is not enabled for sender '{from}' \
+                     (see ZEROCLAW_CP_AUTO_AUTHORIZE_SENDERS)"
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
 
 // ── Routing rules ────────────────────────────────────────────────
 
@@ -30,29 +220,81 @@ const MAX_HOP_COUNT: i64 = 8;
 pub struct CreateRuleBody {
     pub from_instance: String,
     pub to_instance: String,
+    /// `*`, `prefix.*`, an exact type, or `re:<regex>`.
     pub type_pattern: String,
     #[serde(default = "default_max_retries")]
     pub max_retries: i64,
-    #[serde(default = "default_ttl_secs")]
-    pub ttl_secs: i64,
+    /// Omitted means the policy default (see `TtlPolicy`).
+    pub ttl_secs: Option<i64>,
+    /// How long the recipient holds a lease on messages this rule routes.
+    #[serde(default = "default_lease_secs")]
+    pub lease_secs: i64,
     #[serde(default)]
     pub auto_start: bool,
+    /// Reject sends that revisit an instance already on the correlation
+    /// thread (`ROUTE_CYCLE`).
+    #[serde(default)]
+    pub detect_cycles: bool,
+    /// `off` (default), `running` or `http`: how healthy the recipient must
+    /// be before it may lease messages (see `HealthGate`).
+    pub health_gate: Option<String>,
+    /// JSON template the payload is rewritten through at enqueue (see
+    /// `cp::transform`). Omitted means payloads are stored as sent.
+    pub transform: Option<serde_json::Value>,
 }
 
 fn default_max_retries() -> i64 {
     5
 }
-fn default_ttl_secs() -> i64 {
-    3600
+
+fn default_lease_secs() -> i64 {
+    crate::db::DEFAULT_LEASE_SECS
 }
 
 pub async fn handle_create_rule(
     State(state): State<CpState>,
     Json(body): Json<CreateRuleBody>,
 ) -> ApiResponse {
+    let health_gate = match body.health_gate.as_deref() {
+        None => HealthGate::Off,
+        Some(value) => match HealthGate::parse(value) {
+            Some(gate) => gate,
+            None => {
+                return err_json(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Invalid health_gate: '{value}'. Valid values: off, running, http"),
+                )
+            }
+        },
+    };
+
+    if let Err(msg) = crate::db::validate_type_pattern(&body.type_pattern) {
+        return err_json(StatusCode::BAD_REQUEST, &msg);
+    }
+
+    if body.lease_secs <= 0 {
+        return err_json(StatusCode::BAD_REQUEST, "lease_secs must be positive");
+    }
+
+    if let Some(ref template) = body.transform {
+        if let Err(msg) = transform::validate_template(template) {
+            return err_json(
+                StatusCode::BAD_REQUEST,
+                &format!("Invalid transform: {msg}"),
+            );
+        }
+    }
+    let transform = body.transform.as_ref().map(ToString::to_string);
+
     let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
+    let ttl_policy = state.ttl_policy;
     let result =
         tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
+            let ttl_secs = ttl_policy
+                .effective(body.ttl_secs)
+                .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+
             let registry = Registry::open(&db_path)
                 .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
 
@@ -84,16 +326,26 @@ pub async fn handle_create_rule(
                     &body.to_instance,
                     &body.type_pattern,
                     body.max_retries,
-                    body.ttl_secs,
+                    ttl_secs,
+                    body.lease_secs,
                     body.auto_start,
+                    body.detect_cycles,
+                    health_gate,
+                    transform.as_deref(),
                 )
                 .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+            routing_rules.invalidate();
 
             Ok(serde_json::json!({
                 "id": id,
                 "from_instance": body.from_instance,
                 "to_instance": body.to_instance,
                 "type_pattern": body.type_pattern,
+                "ttl_secs": ttl_secs,
+                "lease_secs": body.lease_secs,
+                "detect_cycles": body.detect_cycles,
+                "health_gate": health_gate.as_str(),
+                "transform": body.transform,
             }))
         })
         .await;
@@ -108,6 +360,26 @@ pub async fn handle_create_rule(
     }
 }
 
+fn rule_json(r: &crate::db::RoutingRule) -> serde_json::Value {
+    serde_json::json!({
+        "id": r.id,
+        "from_instance": r.from_instance,
+        "to_instance": r.to_instance,
+        "type_pattern": r.type_pattern,
+        "max_retries": r.max_retries,
+        "ttl_secs": r.ttl_secs,
+        "lease_secs": r.lease_secs,
+        "auto_start": r.auto_start,
+        "detect_cycles": r.detect_cycles,
+        "health_gate": r.health_gate.as_str(),
+        "transform": r
+            .transform
+            .as_deref()
+            .and_then(|t| serde_json::from_str::<serde_json::Value>(t).ok()),
+        "created_at": r.created_at,
+    })
+}
+
 pub async fn handle_list_rules(State(state): State<CpState>) -> ApiResponse {
     let db_path = state.db_path.clone();
     let result = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
@@ -115,21 +387,7 @@ pub async fn handle_list_rules(State(state): State<CpState>) -> ApiResponse {
         let rules = registry
             .list_routing_rules()
             .map_err(|e| format!("{e:#}"))?;
-        let json: Vec<serde_json::Value> = rules
-            .iter()
-            .map(|r| {
-                serde_json::json!({
-                    "id": r.id,
-                    "from_instance": r.from_instance,
-                    "to_instance": r.to_instance,
-                    "type_pattern": r.type_pattern,
-                    "max_retries": r.max_retries,
-                    "ttl_secs": r.ttl_secs,
-                    "auto_start": r.auto_start,
-                    "created_at": r.created_at,
-                })
-            })
-            .collect();
+        let json: Vec<serde_json::Value> = rules.iter().map(rule_json).collect();
         Ok(serde_json::json!(json))
     })
     .await;
@@ -144,11 +402,100 @@ pub async fn handle_list_rules(State(state): State<CpState>) -> ApiResponse {
     }
 }
 
+#[derive(Deserialize)]
+pub struct RoutingCheckQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    #[serde(rename = "type")]
+    pub message_type: Option<String>,
+    /// TTL the sender would request; omitted means the rule's TTL.
+    pub ttl_secs: Option<i64>,
+}
+
+/// Dry-run the send path's instance and routing checks for a hypothetical
+/// message: which rule matches and the `max_retries`/TTL/`auto_start` it would
+/// get. Nothing is enqueued.
+pub async fn handle_routing_check(
+    State(state): State<CpState>,
+    Query(query): Query<RoutingCheckQuery>,
+) -> ApiResponse {
+    let (Some(from), Some(to), Some(message_type)) = (query.from, query.to, query.message_type)
+    else {
+        return err_json(
+            StatusCode::BAD_REQUEST,
+            "from, to and type query parameters are required",
+        );
+    };
+    let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
+    let ttl_policy = state.ttl_policy;
+    let result = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
+        let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
+        let denied = |reason: String, rule: Option<&crate::db::RoutingRule>| {
+            serde_json::json!({
+                "allowed": false,
+                "reason": reason,
+                "rule": rule.map(rule_json),
+                "effective": null,
+            })
+        };
+
+        for name in [&from, &to] {
+            if registry
+                .get_instance_by_name(name)
+                .map_err(|e| format!("{e:#}"))?
+                .is_none()
+            {
+                return Ok(denied(format!("No instance named '{name}'"), None));
+            }
+        }
+        let Some(rule) = routing_rules
+            .check_route_allowed(&registry, &from, &to, &message_type)
+            .map_err(|e| format!("{e:#}"))?
+        else {
+            return Ok(denied(
+                format!("No routing rule allows {from} -> {to} for type '{message_type}'"),
+                None,
+            ));
+        };
+        // Same resolution as send: explicit request, else the rule's TTL
+        let requested_ttl = query.ttl_secs.unwrap_or(rule.ttl_secs);
+        let ttl_secs = match ttl_policy.effective(Some(requested_ttl)) {
+            Ok(ttl) => ttl,
+            Err(msg) => return Ok(denied(msg, Some(&rule))),
+        };
+
+        Ok(serde_json::json!({
+            "allowed": true,
+            "reason": null,
+            "rule": rule_json(&rule),
+            "effective": {
+                "max_retries": rule.max_retries,
+                "ttl_secs": ttl_secs,
+                "lease_secs": rule.lease_secs,
+                "auto_start": rule.auto_start,
+                "health_gate": rule.health_gate.as_str(),
+            },
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err(msg)) => err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
 pub async fn handle_delete_rule(
     State(state): State<CpState>,
     AxumPath(id): AxumPath<String>,
 ) -> ApiResponse {
     let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
     let result =
         tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
             let registry = Registry::open(&db_path)
@@ -157,6 +504,7 @@ pub async fn handle_delete_rule(
                 .delete_routing_rule(&id)
                 .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
             if deleted {
+                routing_rules.invalidate();
                 Ok(serde_json::json!({ "deleted": true, "id": id }))
             } else {
                 Err((
@@ -188,18 +536,81 @@ pub struct SendMessageBody {
     pub payload: serde_json::Value,
     pub correlation_id: Option<String>,
     pub idempotency_key: Option<String>,
+    /// How a key is derived when `idempotency_key` is omitted.
+    #[serde(default)]
+    pub idempotency_mode: IdempotencyMode,
     #[serde(default)]
     pub hop_count: i64,
+    /// Overrides the routing rule's TTL; clamped to the policy max.
+    pub ttl_secs: Option<i64>,
+    /// Higher priorities are leased first; clamped to 0-9, default 0.
+    #[serde(default)]
+    pub priority: i64,
+    /// MIME type of a raw string payload (e.g. base64 data); omit for JSON.
+    pub content_type: Option<String>,
+    /// Parse a string payload as JSON text and store the parsed value;
+    /// invalid JSON is rejected with 400.
+    #[serde(default)]
+    pub payload_is_json: bool,
+    /// Create a minimal routing rule for this from/to/type if none allows
+    /// it. Only honoured when the request carries `from_instance`'s own
+    /// instance token and the sender is trusted by [`AutoAuthorize`].
+    #[serde(default)]
+    pub ensure_rule: bool,
+    /// Message this one forwards; set by the forward endpoint, not clients.
+    #[serde(skip)]
+    pub forwarded_from: Option<String>,
+}
+
+/// Source of a message's idempotency key. An explicit `idempotency_key`
+/// always takes precedence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdempotencyMode {
+    /// Only caller-supplied keys; messages without one never dedup.
+    #[default]
+    Explicit,
+    /// Derive the key from sender, recipient, type and payload, so an
+    /// identical resend within `PAYLOAD_HASH_DEDUP_WINDOW_SECS` is
+    /// deduplicated against the stored message.
+    PayloadHash,
+}
+
+/// How long a payload-derived idempotency key dedups identical messages.
+/// After that the same message (e.g. a daily "ok") is delivered again.
+pub const PAYLOAD_HASH_DEDUP_WINDOW_SECS: i64 = 300;
+
+/// Idempotency key for `IdempotencyMode::PayloadHash`: SHA-256 over the
+/// envelope fields and the payload's JSON (object keys sorted), each
+/// NUL-terminated so adjacent fields cannot run together.
+pub fn payload_idempotency_key(
+    from_instance: &str,
+    to_instance: &str,
+    message_type: &str,
+    payload: &serde_json::Value,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [from_instance, to_instance, message_type] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher.update(payload.to_string().as_bytes());
+    format!("sha256:{}", hex::encode(hasher.finalize()))
 }
 
 pub async fn handle_send_message(
     State(state): State<CpState>,
+    headers: HeaderMap,
     Json(body): Json<SendMessageBody>,
 ) -> ApiResponse {
     let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
+    let auth = SendAuth::from_request(&state, &headers);
+    let ttl_policy = state.ttl_policy;
+    let masking = state.masking.clone();
     let result = tokio::task::spawn_blocking(
         move || -> Result<(StatusCode, serde_json::Value), (StatusCode, String)> {
-            validate_and_enqueue(&db_path, body)
+            validate_and_enqueue(&db_path, &routing_rules, &auth, ttl_policy, &masking, body)
         },
     )
     .await;
@@ -214,198 +625,980 @@ pub async fn handle_send_message(
     }
 }
 
-#[allow(clippy::too_many_lines)]
-fn validate_and_enqueue(
-    db_path: &Path,
-    mut body: SendMessageBody,
-) -> Result<(StatusCode, serde_json::Value), (StatusCode, String)> {
-    let registry = Registry::open(db_path)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
-
-    // 1. Instance existence (D10)
+/// 404 unless `name` is a registered instance.
+fn require_instance(registry: &Registry, name: &str) -> Result<(), (StatusCode, String)> {
     if registry
-        .get_instance_by_name(&body.from_instance)
+        .get_instance_by_name(name)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
         .is_none()
     {
-        return Err((
-            StatusCode::NOT_FOUND,
-            format!("No instance named '{}'", body.from_instance),
-        ));
+        return Err((StatusCode::NOT_FOUND, format!("No instance named '{name}'")));
     }
-    if registry
-        .get_instance_by_name(&body.to_instance)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
-        .is_none()
-    {
-        return Err((
-            StatusCode::NOT_FOUND,
-            format!("No instance named '{}'", body.to_instance),
-        ));
+    Ok(())
+}
+
+/// Check a payload against its declared content type and return the type to
+/// store (`None` for JSON).
+///
+/// Without a `content_type` (or with `application/json`) the payload is JSON.
+/// With `payload_is_json`, a string payload is treated as JSON text: it is
+/// parsed and replaced by the parsed value, so it is stored in canonical form
+/// and malformed input is rejected here rather than by each consumer. Any
+/// other content type marks a raw payload, which must be a string and is
+/// stored verbatim.
+pub fn normalize_payload(
+    payload: &mut serde_json::Value,
+    content_type: Option<&str>,
+    payload_is_json: bool,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let raw_type = content_type
+        .map(str::trim)
+        .filter(|ct| !ct.is_empty() && !ct.eq_ignore_ascii_case(JSON_CONTENT_TYPE));
+    if let Some(ct) = raw_type {
+        if payload_is_json {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("payload_is_json cannot be combined with content_type '{ct}'"),
+            ));
+        }
+        if !payload.is_string() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Payload with content_type '{ct}' must be a string"),
+            ));
+        }
+        return Ok(Some(ct.to_string()));
+    }
+    if payload_is_json {
+        if let serde_json::Value::String(text) = payload {
+            let parsed = serde_json::from_str(text).map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("Payload is not valid JSON: {e}"),
+                )
+            })?;
+            *payload = parsed;
+        }
     }
+    Ok(None)
+}
 
-    // 2. Payload size check
-    let payload_str = body.payload.to_string();
-    if payload_str.len() > MAX_PAYLOAD_BYTES {
+/// Payload size and hop-count limits, checked against the uncompressed payload.
+fn check_envelope(payload: &serde_json::Value, hop_count: i64) -> Result<(), (StatusCode, String)> {
+    let payload_len = payload.to_string().len();
+    if payload_len > MAX_PAYLOAD_BYTES {
         return Err((
             StatusCode::BAD_REQUEST,
             format!(
                 "Payload exceeds maximum size of {} bytes ({} bytes)",
-                MAX_PAYLOAD_BYTES,
-                payload_str.len()
+                MAX_PAYLOAD_BYTES, payload_len
             ),
         ));
     }
-
-    // 3. Hop count check
-    if body.hop_count >= MAX_HOP_COUNT {
+    let max_hops = max_hop_count();
+    if hop_count >= max_hops {
         return Err((
             StatusCode::BAD_REQUEST,
-            format!(
-                "Hop count {} exceeds maximum of {}",
-                body.hop_count, MAX_HOP_COUNT
-            ),
+            format!("Hop count {} exceeds maximum of {}", hop_count, max_hops),
         ));
     }
+    Ok(())
+}
 
-    // 4. Routing allowlist check
-    let rule = registry
-        .check_route_allowed(&body.from_instance, &body.to_instance, &body.message_type)
+/// The routing rule allowing `from -> to` for `message_type`.
+fn resolve_route(
+    registry: &Registry,
+    routing_rules: &RoutingRuleCache,
+    from: &str,
+    to: &str,
+    message_type: &str,
+) -> Result<crate::db::RoutingRule, (StatusCode, String)> {
+    let rule = routing_rules
+        .check_route_allowed(registry, from, to, message_type)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
     let Some(rule) = rule else {
         return Err((
             StatusCode::FORBIDDEN,
+            format!("No routing rule allows {from} -> {to} for type '{message_type}'"),
+        ));
+    };
+    Ok(rule)
+}
+
+/// [`resolve_route`] for `ensure_rule` sends: if no rule allows the route,
+/// create a minimal one with `default_ttl_secs` first (authenticated,
+/// trusted senders only). Also returns whether a rule was created.
+fn resolve_or_create_route(
+    registry: &Registry,
+    routing_rules: &RoutingRuleCache,
+    auth: &SendAuth,
+    from: &str,
+    to: &str,
+    message_type: &str,
+    default_ttl_secs: i64,
+) -> Result<(crate::db::RoutingRule, bool), (StatusCode, String)> {
+    auth.authorize_ensure_rule(registry, from)?;
+    let (rule, created) = registry
+        .ensure_routing_rule(
+            from,
+            to,
+            message_type,
+            default_max_retries(),
+            default_ttl_secs,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    if created {
+        routing_rules.invalidate();
+        tracing::info!(
+            "Auto-created routing rule {} for {from} -> {to} type '{message_type}'",
+            rule.id
+        );
+    }
+    Ok((rule, created))
+}
+
+/// For rules with `detect_cycles`, reject a send whose recipient already
+/// received a message on this correlation thread (A -> B -> A -> B is
+/// rejected at the third hop; the reply B -> A is allowed).
+fn check_route_cycle(
+    registry: &Registry,
+    rule: &crate::db::RoutingRule,
+    correlation_id: Option<&str>,
+    to: &str,
+) -> Result<(), (StatusCode, String)> {
+    let Some(correlation_id) = correlation_id.filter(|_| rule.detect_cycles) else {
+        return Ok(());
+    };
+    let path = registry
+        .correlation_route_path(correlation_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    if path.iter().any(|visited| visited == to) {
+        return Err((
+            StatusCode::CONFLICT,
             format!(
-                "No routing rule allows {} -> {} for type '{}'",
-                body.from_instance, body.to_instance, body.message_type
+                "ROUTE_CYCLE: '{to}' already received a message on correlation thread \
+                 '{correlation_id}' (path: {})",
+                path.join(" -> ")
             ),
         ));
+    }
+    Ok(())
+}
+
+/// Rewrite a JSON payload through `rule`'s transform, if it has one.
+/// Returns the payload as sent when it was transformed. Raw payloads (with
+/// a `content_type`) are opaque and pass through unchanged.
+fn apply_rule_transform(
+    rule: &crate::db::RoutingRule,
+    payload: &mut serde_json::Value,
+    content_type: Option<&str>,
+    from_instance: &str,
+    to_instance: &str,
+    message_type: &str,
+) -> Result<Option<serde_json::Value>, (StatusCode, String)> {
+    let (Some(template), None) = (rule.transform.as_deref(), content_type) else {
+        return Ok(None);
+    };
+    let template: serde_json::Value = serde_json::from_str(template).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Routing rule {} has an unreadable transform: {e}", rule.id),
+        )
+    })?;
+    let input = transform::TransformInput {
+        payload,
+        from_instance,
+        to_instance,
+        message_type,
+    };
+    let transformed = transform::apply_template(&template, &input).map_err(|msg| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Routing rule {} transform failed: {msg}", rule.id),
+        )
+    })?;
+    Ok(Some(std::mem::replace(payload, transformed)))
+}
+
+/// Start a stopped recipient whose routing rule asks for it. Best effort.
+fn auto_start_if_stopped(registry: &Registry, to_instance: &str) {
+    if let Ok(Some(inst)) = registry.get_instance_by_name(to_instance) {
+        let inst_dir = lifecycle::instance_dir_from(&inst);
+        let (live_status, _) =
+            lifecycle::live_status(&inst_dir).unwrap_or(("unknown".into(), None));
+        if live_status == "stopped" || live_status == "dead" {
+            tracing::info!("Auto-starting instance '{to_instance}' for pending message");
+            if let Err(e) = lifecycle::start_instance(registry, to_instance) {
+                tracing::warn!("Auto-start failed for '{to_instance}': {e}");
+            }
+        }
+    }
+}
+
+/// A send that passed validation and is ready to insert.
+struct PreparedSend {
+    msg: NewMessage,
+    meta: SendMeta,
+}
+
+/// What a send records and reports alongside its inserted row.
+struct SendMeta {
+    rule: crate::db::RoutingRule,
+    rule_created: bool,
+    ttl_secs: i64,
+    /// The (redacted) payload before the rule's transform, if it applied.
+    original_payload: Option<serde_json::Value>,
+    forwarded_from: Option<String>,
+}
+
+enum Prepared {
+    Send(Box<PreparedSend>),
+    /// Idempotency hit: the ID of the message already stored under the key.
+    Duplicate(String),
+}
+
+fn validate_and_enqueue(
+    db_path: &Path,
+    routing_rules: &RoutingRuleCache,
+    auth: &SendAuth,
+    ttl_policy: TtlPolicy,
+    masking: &SecretMasking,
+    body: SendMessageBody,
+) -> Result<(StatusCode, serde_json::Value), (StatusCode, String)> {
+    let registry = Registry::open(db_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+
+    // Return existing message ID (not an error)
+    let duplicate = |existing_id: String| {
+        Ok((
+            StatusCode::OK,
+            serde_json::json!({
+                "id": existing_id,
+                "deduplicated": true,
+            }),
+        ))
+    };
+    let PreparedSend { msg, meta } =
+        match prepare_send(&registry, routing_rules, auth, ttl_policy, masking, body)? {
+            Prepared::Send(prepared) => *prepared,
+            Prepared::Duplicate(existing_id) => return duplicate(existing_id),
+        };
+
+    // 8. Enqueue (the idempotency key is checked again in the insert
+    // transaction, in case a concurrent send took it since step 6)
+    let outcome = registry
+        .with_retry(|reg| reg.enqueue_messages_batch(std::slice::from_ref(&msg)))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+        .pop();
+    let msg = match outcome {
+        Some(BatchEnqueueOutcome::Queued(msg)) => *msg,
+        Some(BatchEnqueueOutcome::Deduplicated(existing_id)) => return duplicate(existing_id),
+        None => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Message {} not found after insert", msg.id),
+            ))
+        }
+    };
+    record_send_events(&registry, &meta, &msg)?;
+
+    // 9. Auto-start check
+    if meta.rule.auto_start {
+        auto_start_if_stopped(&registry, &msg.to_instance);
+    }
+
+    Ok((StatusCode::CREATED, sent_json(&meta, &msg)))
+}
+
+/// Steps 1-7 of a send: every check, and the row to insert.
+fn prepare_send(
+    registry: &Registry,
+    routing_rules: &RoutingRuleCache,
+    auth: &SendAuth,
+    ttl_policy: TtlPolicy,
+    masking: &SecretMasking,
+    mut body: SendMessageBody,
+) -> Result<Prepared, (StatusCode, String)> {
+    // 1. Instance existence (D10)
+    require_instance(registry, &body.from_instance)?;
+    require_instance(registry, &body.to_instance)?;
+
+    // 2-3. Payload content type, size (uncompressed; large payloads are
+    // gzipped at rest) and hop count
+    body.content_type = normalize_payload(
+        &mut body.payload,
+        body.content_type.as_deref(),
+        body.payload_is_json,
+    )?;
+    check_envelope(&body.payload, body.hop_count)?;
+
+    // 4. Routing allowlist (creating the rule for trusted `ensure_rule`
+    // senders)
+    let (rule, rule_created) = if body.ensure_rule {
+        resolve_or_create_route(
+            registry,
+            routing_rules,
+            auth,
+            &body.from_instance,
+            &body.to_instance,
+            &body.message_type,
+            ttl_policy.default_ttl_secs,
+        )?
+    } else {
+        let rule = resolve_route(
+            registry,
+            routing_rules,
+            &body.from_instance,
+            &body.to_instance,
+            &body.message_type,
+        )?;
+        (rule, false)
     };
 
-    // 5. Idempotency check
+    // 5. Effective TTL: explicit request, else the rule's TTL; clamped
+    // either way
+    let ttl_secs = ttl_policy
+        .effective(Some(body.ttl_secs.unwrap_or(rule.ttl_secs)))
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+
+    // 6. Idempotency check (hash the payload before redaction, so payloads
+    // differing only in a secret stay distinct)
+    let windowed =
+        body.idempotency_key.is_none() && body.idempotency_mode == IdempotencyMode::PayloadHash;
+    if windowed {
+        body.idempotency_key = Some(payload_idempotency_key(
+            &body.from_instance,
+            &body.to_instance,
+            &body.message_type,
+            &body.payload,
+        ));
+    }
     if let Some(ref key) = body.idempotency_key {
-        if let Some(existing_id) = registry
-            .check_idempotency_key(key)
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+        let existing = if windowed {
+            registry.check_idempotency_key_within(key, PAYLOAD_HASH_DEDUP_WINDOW_SECS)
+        } else {
+            registry.check_idempotency_key(key)
+        };
+        if let Some(existing_id) =
+            existing.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
         {
-            // Return existing message ID (not an error)
-            return Ok((
-                StatusCode::OK,
-                serde_json::json!({
-                    "id": existing_id,
-                    "deduplicated": true,
-                }),
-            ));
+            return Ok(Prepared::Duplicate(existing_id));
         }
     }
 
-    // 6. Secret redaction
-    redact_payload_secrets(&mut body.payload);
+    // 6b. Correlation-thread cycle (after dedup, so a resend of an already
+    // queued message still reports it rather than a cycle)
+    check_route_cycle(
+        registry,
+        &rule,
+        body.correlation_id.as_deref(),
+        &body.to_instance,
+    )?;
 
-    // 7. Enqueue
-    let msg_id = uuid::Uuid::new_v4().to_string();
-    let new_msg = NewMessage {
-        id: msg_id.clone(),
-        from_instance: body.from_instance.clone(),
-        to_instance: body.to_instance.clone(),
-        message_type: body.message_type.clone(),
+    // 6c. Rule payload transform; the result is held to the same size limit
+    let mut original_payload = apply_rule_transform(
+        &rule,
+        &mut body.payload,
+        body.content_type.as_deref(),
+        &body.from_instance,
+        &body.to_instance,
+        &body.message_type,
+    )?;
+    if original_payload.is_some() {
+        check_envelope(&body.payload, body.hop_count)?;
+    }
+
+    // 7. Secret redaction (the pre-transform payload is kept in an event)
+    redact_payload_secrets_with(&mut body.payload, masking);
+    if let Some(ref mut original) = original_payload {
+        redact_payload_secrets_with(original, masking);
+    }
+
+    let msg = NewMessage {
+        id: uuid::Uuid::new_v4().to_string(),
+        from_instance: body.from_instance,
+        to_instance: body.to_instance,
+        message_type: body.message_type,
         payload: body.payload.to_string(),
-        correlation_id: body.correlation_id.clone(),
-        idempotency_key: body.idempotency_key.clone(),
+        correlation_id: body.correlation_id,
+        idempotency_key: body.idempotency_key,
+        idempotency_window_secs: windowed.then_some(PAYLOAD_HASH_DEDUP_WINDOW_SECS),
         hop_count: body.hop_count,
         max_retries: rule.max_retries,
-        ttl_secs: rule.ttl_secs,
+        ttl_secs,
+        content_type: body.content_type,
+        priority: body.priority.clamp(0, MAX_PRIORITY),
     };
+    Ok(Prepared::Send(Box::new(PreparedSend {
+        msg,
+        meta: SendMeta {
+            rule,
+            rule_created,
+            ttl_secs,
+            original_payload,
+            forwarded_from: body.forwarded_from,
+        },
+    })))
+}
 
-    let msg = registry
-        .enqueue_message(&new_msg)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+/// The `created` event of an enqueued send, plus `transformed` when the
+/// rule rewrote its payload and `forwarded` on the message it forwards.
+fn record_send_events(
+    registry: &Registry,
+    meta: &SendMeta,
+    msg: &crate::db::Message,
+) -> Result<(), (StatusCode, String)> {
+    // Record which rule governed retries/TTL; overlapping rules make it ambiguous
+    let mut created_detail = serde_json::json!({ "rule_id": meta.rule.id });
+    if meta.rule_created {
+        created_detail["rule_created"] = serde_json::json!(true);
+    }
+    if let Some(ref original_id) = meta.forwarded_from {
+        created_detail["forwarded_from"] = serde_json::json!(original_id);
+    }
     registry
-        .append_message_event(&msg.id, "created", None)
+        .append_message_event(&msg.id, "created", Some(&created_detail.to_string()))
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
-
-    // 8. Auto-start check
-    if rule.auto_start {
-        if let Ok(Some(inst)) = registry.get_instance_by_name(&body.to_instance) {
-            let inst_dir = lifecycle::instance_dir_from(&inst);
-            let (live_status, _) =
-                lifecycle::live_status(&inst_dir).unwrap_or(("unknown".into(), None));
-            if live_status == "stopped" || live_status == "dead" {
-                tracing::info!(
-                    "Auto-starting instance '{}' for pending message",
-                    body.to_instance
-                );
-                if let Err(e) = lifecycle::start_instance(&registry, &body.to_instance) {
-                    tracing::warn!("Auto-start failed for '{}': {e}", body.to_instance);
-                }
-            }
-        }
+    if let Some(ref original) = meta.original_payload {
+        let detail = serde_json::json!({
+            "rule_id": meta.rule.id,
+            "original_payload": original,
+        });
+        registry
+            .append_message_event(&msg.id, "transformed", Some(&detail.to_string()))
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
     }
-
-    Ok((
-        StatusCode::CREATED,
-        serde_json::json!({
-            "id": msg.id,
-            "status": msg.status,
-            "expires_at": msg.expires_at,
-        }),
-    ))
+    if let Some(ref original_id) = meta.forwarded_from {
+        let detail = serde_json::json!({
+            "message_id": msg.id,
+            "to_instance": msg.to_instance,
+        });
+        registry
+            .append_message_event(original_id, "forwarded", Some(&detail.to_string()))
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    }
+    MessageEventBus::global().publish("enqueued", &msg.id, "queued");
+    Ok(())
 }
 
-// ── Receive message (long-poll) ──────────────────────────────────
-
-#[derive(Deserialize)]
-pub struct ReceiveQuery {
-    #[serde(default = "default_wait")]
-    pub wait: u64,
+/// Response for an enqueued send.
+fn sent_json(meta: &SendMeta, msg: &crate::db::Message) -> serde_json::Value {
+    serde_json::json!({
+        "id": msg.id,
+        "status": msg.status,
+        "ttl_secs": meta.ttl_secs,
+        "expires_at": msg.expires_at,
+        "idempotency_key": msg.idempotency_key,
+        "priority": msg.priority,
+        "rule_id": meta.rule.id,
+        "rule_created": meta.rule_created,
+    })
 }
 
-fn default_wait() -> u64 {
-    30
-}
+// ── Batch send ───────────────────────────────────────────────────
 
-pub async fn handle_receive_message(
+/// Upper bound on messages in one batch send.
+const MAX_BATCH_MESSAGES: usize = 100;
+
+pub async fn handle_send_batch(
     State(state): State<CpState>,
-    AxumPath(name): AxumPath<String>,
-    Query(query): Query<ReceiveQuery>,
+    headers: HeaderMap,
+    Json(bodies): Json<Vec<SendMessageBody>>,
 ) -> ApiResponse {
-    let wait_secs = query.wait.min(60); // Cap at 60s
-    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(wait_secs);
+    let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
+    let auth = SendAuth::from_request(&state, &headers);
+    let ttl_policy = state.ttl_policy;
+    let masking = state.masking.clone();
+    let result =
+        tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
+            validate_and_enqueue_batch(
+                &db_path,
+                &routing_rules,
+                &auth,
+                ttl_policy,
+                &masking,
+                bodies,
+            )
+        })
+        .await;
 
-    loop {
-        let db_path = state.db_path.clone();
-        let instance_name = name.clone();
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err((status, msg))) => err_json(status, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
 
-        let result = tokio::task::spawn_blocking(move || -> Result<Option<serde_json::Value>, String> {
-            let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
-            let msg = registry.lease_pending_message(&instance_name).map_err(|e| format!("{e:#}"))?;
-            match msg {
-                Some(m) => {
-                    let _ = registry.append_message_event(&m.id, "leased", None);
-                    Ok(Some(serde_json::json!({
-                        "id": m.id,
-                        "from_instance": m.from_instance,
-                        "to_instance": m.to_instance,
-                        "message_type": m.message_type,
-                        "payload": serde_json::from_str::<serde_json::Value>(&m.payload).unwrap_or(serde_json::Value::String(m.payload.clone())),
-                        "correlation_id": m.correlation_id,
-                        "hop_count": m.hop_count,
-                        "created_at": m.created_at,
-                    })))
+/// Validate each message as a single send would and enqueue the valid ones
+/// in one transaction. Results are per item (`queued`, `deduplicated` or
+/// `rejected`, by index) so partial failures are visible; only an empty or
+/// oversized batch is rejected as a whole.
+fn validate_and_enqueue_batch(
+    db_path: &Path,
+    routing_rules: &RoutingRuleCache,
+    auth: &SendAuth,
+    ttl_policy: TtlPolicy,
+    masking: &SecretMasking,
+    bodies: Vec<SendMessageBody>,
+) -> Result<serde_json::Value, (StatusCode, String)> {
+    if bodies.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Batch must contain at least one message".into(),
+        ));
+    }
+    if bodies.len() > MAX_BATCH_MESSAGES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Batch is limited to {MAX_BATCH_MESSAGES} messages"),
+        ));
+    }
+    let registry = Registry::open(db_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+
+    // Validate every message before writing anything
+    let mut results = Vec::with_capacity(bodies.len());
+    let mut new_msgs = Vec::new();
+    let mut slots = Vec::new();
+    for (index, body) in bodies.into_iter().enumerate() {
+        match prepare_send(&registry, routing_rules, auth, ttl_policy, masking, body) {
+            Ok(Prepared::Send(prepared)) => {
+                let PreparedSend { msg, meta } = *prepared;
+                new_msgs.push(msg);
+                slots.push((index, meta));
+                results.push(serde_json::Value::Null);
+            }
+            Ok(Prepared::Duplicate(existing_id)) => {
+                results.push(deduplicated_json(index, &existing_id));
+            }
+            Err((status, error)) => results.push(serde_json::json!({
+                "index": index,
+                "status": "rejected",
+                "code": status.as_u16(),
+                "error": error,
+            })),
+        }
+    }
+
+    let outcomes = registry
+        .enqueue_messages_batch(&new_msgs)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    for ((index, meta), outcome) in slots.into_iter().zip(outcomes) {
+        results[index] = match outcome {
+            BatchEnqueueOutcome::Queued(msg) => {
+                record_send_events(&registry, &meta, &msg)?;
+                if meta.rule.auto_start {
+                    auto_start_if_stopped(&registry, &msg.to_instance);
                 }
-                None => Ok(None),
+                let mut item = sent_json(&meta, &msg);
+                item["index"] = serde_json::json!(index);
+                item
+            }
+            BatchEnqueueOutcome::Deduplicated(existing_id) => {
+                deduplicated_json(index, &existing_id)
             }
+        };
+    }
+
+    let count = |status: &str| results.iter().filter(|r| r["status"] == status).count();
+    Ok(serde_json::json!({
+        "queued": count("queued"),
+        "deduplicated": count("deduplicated"),
+        "rejected": count("rejected"),
+        "results": results,
+    }))
+}
+
+fn deduplicated_json(index: usize, existing_id: &str) -> serde_json::Value {
+    serde_json::json!({
+        "index": index,
+        "id": existing_id,
+        "status": "deduplicated",
+    })
+}
+
+// ── Forward message ──────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct ForwardBody {
+    #[serde(alias = "to")]
+    pub to_instance: String,
+    /// Defaults to the original's type.
+    #[serde(rename = "type")]
+    pub message_type: Option<String>,
+    /// Defaults to the original's (already redacted) payload.
+    pub payload: Option<serde_json::Value>,
+    /// Overrides the routing rule's TTL; clamped to the policy max.
+    pub ttl_secs: Option<i64>,
+}
+
+/// Forward a received message: its recipient sends a new message to
+/// `to_instance` on the same correlation thread with `hop_count + 1`. The
+/// new send goes through the full send pipeline (routing rule for the new
+/// hop, hop limit, cycle detection), and the two messages are linked by a
+/// `forwarded` event on the original and `forwarded_from` on the new
+/// message's `created` event.
+pub async fn handle_forward_message(
+    State(state): State<CpState>,
+    AxumPath(id): AxumPath<String>,
+    Json(body): Json<ForwardBody>,
+) -> ApiResponse {
+    let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
+    let ttl_policy = state.ttl_policy;
+    let masking = state.masking.clone();
+    let result = tokio::task::spawn_blocking(
+        move || -> Result<(StatusCode, serde_json::Value), (StatusCode, String)> {
+            let send = forward_send_body(&db_path, &id, body)?;
+            let auth = SendAuth::default();
+            validate_and_enqueue(&db_path, &routing_rules, &auth, ttl_policy, &masking, send)
+        },
+    )
+    .await;
+
+    match result {
+        Ok(Ok((status, value))) => (status, Json(value)),
+        Ok(Err((status, msg))) => err_json(status, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+/// The send request a forward of message `id` amounts to. Only messages the
+/// recipient has actually received (leased or acknowledged) can be
+/// forwarded. A message without a correlation ID starts a thread keyed by
+/// its own ID.
+fn forward_send_body(
+    db_path: &Path,
+    id: &str,
+    body: ForwardBody,
+) -> Result<SendMessageBody, (StatusCode, String)> {
+    let registry = Registry::open(db_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    let original = registry
+        .get_message(id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No message with id '{id}'")))?;
+    if original.status != "leased" && original.status != "acknowledged" {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "Message '{id}' is {}; only received (leased or acknowledged) messages can be forwarded",
+                original.status
+            ),
+        ));
+    }
+
+    let payload = match body.payload {
+        Some(payload) => payload,
+        None => serde_json::from_str(&original.payload).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Stored payload: {e}"),
+            )
+        })?,
+    };
+    Ok(SendMessageBody {
+        from_instance: original.to_instance,
+        to_instance: body.to_instance,
+        message_type: body.message_type.unwrap_or(original.message_type),
+        payload,
+        correlation_id: Some(original.correlation_id.unwrap_or(original.id)),
+        idempotency_key: None,
+        idempotency_mode: IdempotencyMode::Explicit,
+        hop_count: original.hop_count + 1,
+        ttl_secs: body.ttl_secs,
+        priority: original.priority,
+        content_type: original.content_type,
+        payload_is_json: false,
+        ensure_rule: false,
+        forwarded_from: Some(id.to_string()),
+    })
+}
+
+// ── Broadcast message ────────────────────────────────────────────
+
+/// Upper bound on recipients in one broadcast.
+const MAX_BROADCAST_RECIPIENTS: usize = 100;
+
+#[derive(Deserialize)]
+pub struct BroadcastBody {
+    #[serde(alias = "from")]
+    pub from_instance: String,
+    #[serde(alias = "to")]
+    pub to_instances: Vec<String>,
+    #[serde(rename = "type")]
+    pub message_type: String,
+    pub payload: serde_json::Value,
+    /// Shared by every fanned-out message; generated when omitted.
+    pub correlation_id: Option<String>,
+    #[serde(default)]
+    pub hop_count: i64,
+    /// Overrides each routing rule's TTL; clamped to the policy max.
+    pub ttl_secs: Option<i64>,
+    /// See [`SendMessageBody::priority`].
+    #[serde(default)]
+    pub priority: i64,
+    /// See [`SendMessageBody::content_type`].
+    pub content_type: Option<String>,
+    /// See [`SendMessageBody::payload_is_json`].
+    #[serde(default)]
+    pub payload_is_json: bool,
+}
+
+pub async fn handle_broadcast_message(
+    State(state): State<CpState>,
+    Json(body): Json<BroadcastBody>,
+) -> ApiResponse {
+    let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
+    let ttl_policy = state.ttl_policy;
+    let masking = state.masking.clone();
+    let result =
+        tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
+            validate_and_broadcast(&db_path, &routing_rules, ttl_policy, &masking, body)
+        })
+        .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err((status, msg))) => err_json(status, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+/// Validate routing per recipient and enqueue one message per allowed
+/// recipient in a single transaction. Recipients that fail validation are
+/// reported, not fatal; request-level problems (sender, payload, hop count,
+/// recipient list) reject the whole broadcast.
+fn validate_and_broadcast(
+    db_path: &Path,
+    routing_rules: &RoutingRuleCache,
+    ttl_policy: TtlPolicy,
+    masking: &SecretMasking,
+    mut body: BroadcastBody,
+) -> Result<serde_json::Value, (StatusCode, String)> {
+    let registry = Registry::open(db_path)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+
+    if body.to_instances.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "to_instances must name at least one recipient".into(),
+        ));
+    }
+    if body.to_instances.len() > MAX_BROADCAST_RECIPIENTS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Broadcast is limited to {MAX_BROADCAST_RECIPIENTS} recipients"),
+        ));
+    }
+    let mut seen = std::collections::HashSet::new();
+    if let Some(dup) = body
+        .to_instances
+        .iter()
+        .find(|to| !seen.insert(to.as_str()))
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Recipient '{dup}' is listed more than once"),
+        ));
+    }
+    require_instance(&registry, &body.from_instance)?;
+    // Payload problems are the same for every recipient: reject them once
+    let mut payload = body.payload.clone();
+    normalize_payload(
+        &mut payload,
+        body.content_type.as_deref(),
+        body.payload_is_json,
+    )?;
+    check_envelope(&payload, body.hop_count)?;
+
+    let correlation_id = body
+        .correlation_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    // Validate every recipient as a single send would before writing anything
+    let mut results = Vec::with_capacity(body.to_instances.len());
+    // Broadcasts never create routing rules
+    let auth = SendAuth::default();
+    let mut new_msgs = Vec::new();
+    let mut slots = Vec::new();
+    for to in std::mem::take(&mut body.to_instances) {
+        let send = SendMessageBody {
+            from_instance: body.from_instance.clone(),
+            to_instance: to.clone(),
+            message_type: body.message_type.clone(),
+            payload: body.payload.clone(),
+            correlation_id: Some(correlation_id.clone()),
+            idempotency_key: None,
+            idempotency_mode: IdempotencyMode::Explicit,
+            hop_count: body.hop_count,
+            ttl_secs: body.ttl_secs,
+            priority: body.priority,
+            content_type: body.content_type.clone(),
+            payload_is_json: body.payload_is_json,
+            ensure_rule: false,
+            forwarded_from: None,
+        };
+        match prepare_send(&registry, routing_rules, &auth, ttl_policy, masking, send) {
+            Ok(Prepared::Send(prepared)) => {
+                let PreparedSend { msg, meta } = *prepared;
+                new_msgs.push(msg);
+                slots.push((results.len(), meta));
+                results.push(serde_json::Value::Null);
+            }
+            // Broadcasts carry no idempotency key
+            Ok(Prepared::Duplicate(_)) => unreachable!("broadcast send without idempotency key"),
+            Err((status, error)) => results.push(serde_json::json!({
+                "to_instance": to,
+                "status": "rejected",
+                "code": status.as_u16(),
+                "error": error,
+            })),
+        }
+    }
+
+    let outcomes = registry
+        .enqueue_messages_batch(&new_msgs)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+    let mut queued = 0;
+    for ((idx, meta), outcome) in slots.into_iter().zip(outcomes) {
+        let BatchEnqueueOutcome::Queued(msg) = outcome else {
+            continue;
+        };
+        record_send_events(&registry, &meta, &msg)?;
+        results[idx] = serde_json::json!({
+            "to_instance": msg.to_instance,
+            "status": msg.status,
+            "id": msg.id,
+            "expires_at": msg.expires_at,
+        });
+        queued += 1;
+        if meta.rule.auto_start {
+            auto_start_if_stopped(&registry, &msg.to_instance);
+        }
+    }
+
+    Ok(serde_json::json!({
+        "correlation_id": correlation_id,
+        "queued": queued,
+        "rejected": results.len() - queued,
+        "results": results,
+    }))
+}
+
+// ── Receive message (long-poll) ──────────────────────────────────
+
+/// Upper bound on `max=` for a single receive.
+const MAX_RECEIVE_BATCH: usize = 50;
+
+#[derive(Deserialize)]
+pub struct ReceiveQuery {
+    #[serde(default = "default_wait")]
+    pub wait: u64,
+    /// Lease up to this many messages at once (1..=50). With `max=1` the
+    /// response is `{"message": ...}`; otherwise `{"messages": [...]}`.
+    #[serde(default = "default_max")]
+    pub max: usize,
+}
+
+fn default_wait() -> u64 {
+    30
+}
+
+fn default_max() -> usize {
+    1
+}
+
+fn leased_message_json(m: &crate::db::Message) -> serde_json::Value {
+    serde_json::json!({
+        "id": m.id,
+        "from_instance": m.from_instance,
+        "to_instance": m.to_instance,
+        "message_type": m.message_type,
+        "payload": serde_json::from_str::<serde_json::Value>(&m.payload).unwrap_or(serde_json::Value::String(m.payload.clone())),
+        "correlation_id": m.correlation_id,
+        "hop_count": m.hop_count,
+        "content_type": m.content_type,
+        "priority": m.priority,
+        "lease_expires_at": m.lease_expires_at,
+        "created_at": m.created_at,
+    })
+}
+
+pub async fn handle_receive_message(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+    Query(query): Query<ReceiveQuery>,
+) -> ApiResponse {
+    let wait_secs = query.wait.min(60); // Cap at 60s
+    let max = query.max.clamp(1, MAX_RECEIVE_BATCH);
+    let deadline = tokio::time::Instant::now() + tokio::time::Duration::from_secs(wait_secs);
+    // Identifies this receive call in the `leased` events it produces.
+    let poll_id = uuid::Uuid::new_v4().to_string();
+
+    loop {
+        // An unhealthy recipient leases nothing; its messages stay queued.
+        match recipient_ready(state.db_path.clone(), name.clone()).await {
+            Ok(true) => {}
+            Ok(false) if tokio::time::Instant::now() >= deadline => {
+                let mut body = receive_body(max, Vec::new());
+                body["held_reason"] = serde_json::json!("recipient_unhealthy");
+                return ok_json(body);
+            }
+            Ok(false) => {
+                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                continue;
+            }
+            Err(msg) => return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+        }
+        let db_path = state.db_path.clone();
+        let instance_name = name.clone();
+        let poll_id = poll_id.clone();
+        let routing_rules = state.routing_rules.clone();
+
+        let result = tokio::task::spawn_blocking(move || -> Result<Vec<serde_json::Value>, String> {
+            let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
+            let msgs = registry
+                .lease_pending_messages_routed(
+                    &instance_name,
+                    max,
+                    crate::db::DEFAULT_LEASE_SECS,
+                    |from, message_type| {
+                        routing_rules.check_route_allowed(
+                            &registry,
+                            from,
+                            &instance_name,
+                            message_type,
+                        )
+                    },
+                )
+                .map_err(|e| format!("{e:#}"))?;
+            MessagingMetrics::global().record_leased(msgs.len());
+            for msg in &msgs {
+                MessageEventBus::global().publish("leased", &msg.id, "leased");
+            }
+            let _ = registry.record_lease_events(&msgs, &instance_name, &poll_id);
+            Ok(msgs.iter().map(leased_message_json).collect())
         }).await;
 
         match result {
-            Ok(Ok(Some(msg_json))) => {
-                return ok_json(serde_json::json!({ "message": msg_json }));
+            Ok(Ok(msgs)) if !msgs.is_empty() || tokio::time::Instant::now() >= deadline => {
+                return ok_json(receive_body(max, msgs));
             }
-            Ok(Ok(None)) => {
-                // No message available, check if we should keep waiting
-                if tokio::time::Instant::now() >= deadline {
-                    return ok_json(serde_json::json!({ "message": null }));
-                }
+            Ok(Ok(_)) => {
+                // No message available yet, keep waiting
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
             }
             Ok(Err(msg)) => {
@@ -421,22 +1614,107 @@ pub async fn handle_receive_message(
     }
 }
 
+/// With `max=1` the response is `{"message": ...}`; otherwise `{"messages": [...]}`.
+fn receive_body(max: usize, msgs: Vec<serde_json::Value>) -> serde_json::Value {
+    if max == 1 {
+        serde_json::json!({ "message": msgs.into_iter().next() })
+    } else {
+        serde_json::json!({ "messages": msgs })
+    }
+}
+
+/// Timeout for the gateway `/health` probe of `HealthGate::Http`.
+const HEALTH_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Whether `name` passes the strictest health gate of the routing rules
+/// into it. Holding messages back from a crash-looping recipient keeps them
+/// from burning retries until it recovers.
+async fn recipient_ready(db_path: Arc<PathBuf>, name: String) -> Result<bool, String> {
+    let status = tokio::task::spawn_blocking(move || -> Result<_, String> {
+        let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
+        let gate = registry
+            .recipient_health_gate(&name)
+            .map_err(|e| format!("{e:#}"))?;
+        if gate == HealthGate::Off {
+            return Ok(None);
+        }
+        let Some(instance) = registry
+            .get_instance_by_name(&name)
+            .map_err(|e| format!("{e:#}"))?
+        else {
+            return Ok(None);
+        };
+        let running = lifecycle::live_status(&lifecycle::instance_dir_from(&instance))
+            .is_ok_and(|(status, _)| status == "running");
+        Ok(Some((gate, running, instance.port)))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {e}"))??;
+
+    Ok(match status {
+        None => true,
+        Some((_, false, _)) => false,
+        Some((HealthGate::Http, true, port)) => gateway_healthy(port).await,
+        Some(_) => true,
+    })
+}
+
+async fn gateway_healthy(port: u16) -> bool {
+    let Ok(client) = reqwest::Client::builder()
+        .timeout(HEALTH_PROBE_TIMEOUT)
+        .build()
+    else {
+        return false;
+    };
+    client
+        .get(format!("http://127.0.0.1:{port}/health"))
+        .send()
+        .await
+        .is_ok_and(|resp| resp.status().is_success())
+}
+
 // ── Acknowledge message ──────────────────────────────────────────
 
+/// Optional acknowledge body. A missing or empty body is a plain ack.
+#[derive(Deserialize, Default)]
+pub struct AcknowledgeBody {
+    /// Processing outcome, stored (redacted) in the `acknowledged` event.
+    pub result: Option<serde_json::Value>,
+}
+
 pub async fn handle_acknowledge_message(
     State(state): State<CpState>,
     AxumPath(id): AxumPath<String>,
+    body: Bytes,
 ) -> ApiResponse {
+    let mut body: AcknowledgeBody = if body.iter().all(u8::is_ascii_whitespace) {
+        AcknowledgeBody::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(b) => b,
+            Err(e) => {
+                return err_json(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Invalid acknowledge body: {e}"),
+                )
+            }
+        }
+    };
+    if let Some(result) = body.result.as_mut() {
+        redact_payload_secrets_with(result, &state.masking);
+    }
+
     let db_path = state.db_path.clone();
     let result =
         tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
             let registry = Registry::open(&db_path)
                 .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
             let acked = registry
-                .acknowledge_message(&id)
+                .acknowledge_message(&id, body.result.as_ref())
                 .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
             if acked {
-                let _ = registry.append_message_event(&id, "acknowledged", None);
+                MessagingMetrics::global().record_acknowledged();
+                MessageEventBus::global().publish("acknowledged", &id, "acknowledged");
                 Ok(serde_json::json!({ "id": id, "status": "acknowledged" }))
             } else {
                 Err((
@@ -457,6 +1735,702 @@ pub async fn handle_acknowledge_message(
     }
 }
 
+// ── Expedite message ─────────────────────────────────────────────
+
+/// Make a queued message the next one its recipient leases.
+pub async fn handle_expedite_message(
+    State(state): State<CpState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResponse {
+    let db_path = state.db_path.clone();
+    let result =
+        tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
+            let registry = Registry::open(&db_path)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+            let msg = registry
+                .get_message(&id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+                .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No message with id '{id}'")))?;
+            let not_queued = |status: &str| {
+                (
+                    StatusCode::CONFLICT,
+                    format!("Message '{id}' is {status}; only queued messages can be expedited"),
+                )
+            };
+            if msg.status != "queued" {
+                return Err(not_queued(&msg.status));
+            }
+            // Leased or reaped between the lookup and the update
+            let expedited_at = registry
+                .expedite_message(&id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+                .ok_or_else(|| not_queued("no longer queued"))?;
+            Ok(serde_json::json!({
+                "id": id,
+                "status": "queued",
+                "to_instance": msg.to_instance,
+                "expedited_at": expedited_at,
+            }))
+        })
+        .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err((status, msg))) => err_json(status, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+// ── Extend lease ─────────────────────────────────────────────────
+
+/// Longest a single extension may push a lease out.
+const MAX_LEASE_EXTENSION_SECS: i64 = 600;
+
+/// Optional extend-lease body. A missing or empty body extends by the
+/// default lease.
+#[derive(Deserialize, Default)]
+pub struct ExtendLeaseBody {
+    /// Seconds from now the lease should last; capped at 600.
+    pub secs: Option<i64>,
+}
+
+/// Give a consumer more time on a message it still holds a live lease on.
+pub async fn handle_extend_lease(
+    State(state): State<CpState>,
+    AxumPath(id): AxumPath<String>,
+    body: Bytes,
+) -> ApiResponse {
+    let body: ExtendLeaseBody = if body.iter().all(u8::is_ascii_whitespace) {
+        ExtendLeaseBody::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(b) => b,
+            Err(e) => {
+                return err_json(
+                    StatusCode::BAD_REQUEST,
+                    &format!("Invalid extend-lease body: {e}"),
+                )
+            }
+        }
+    };
+    let secs = body.secs.unwrap_or(crate::db::DEFAULT_LEASE_SECS);
+    if secs <= 0 {
+        return err_json(StatusCode::BAD_REQUEST, "secs must be positive");
+    }
+    let secs = secs.min(MAX_LEASE_EXTENSION_SECS);
+
+    let db_path = state.db_path.clone();
+    let result =
+        tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
+            let registry = Registry::open(&db_path)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+            let msg = registry
+                .get_message(&id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+                .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No message with id '{id}'")))?;
+            if msg.status != "leased" {
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!(
+                        "Message '{id}' is {}; only leased messages can have their lease extended",
+                        msg.status
+                    ),
+                ));
+            }
+            // Lease ran out (or the reaper requeued it) since the lookup
+            let lease_expires_at = registry
+                .extend_lease(&id, secs)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+                .ok_or_else(|| {
+                    (
+                        StatusCode::CONFLICT,
+                        format!("Lease on message '{id}' has already expired"),
+                    )
+                })?;
+            Ok(serde_json::json!({
+                "id": id,
+                "status": "leased",
+                "secs": secs,
+                "lease_expires_at": lease_expires_at,
+            }))
+        })
+        .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err((status, msg))) => err_json(status, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+// ── Nack message ─────────────────────────────────────────────────
+
+/// Hand a leased message back without waiting for its lease to expire:
+/// it is requeued with retry backoff, or dead-lettered once retries run out.
+pub async fn handle_nack_message(
+    State(state): State<CpState>,
+    AxumPath(id): AxumPath<String>,
+) -> ApiResponse {
+    let db_path = state.db_path.clone();
+    let result =
+        tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
+            let registry = Registry::open(&db_path)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?;
+            let msg = registry
+                .get_message(&id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+                .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No message with id '{id}'")))?;
+            let not_leased = |status: &str| {
+                (
+                    StatusCode::CONFLICT,
+                    format!("Message '{id}' is {status}; only leased messages can be nacked"),
+                )
+            };
+            if msg.status != "leased" {
+                return Err(not_leased(&msg.status));
+            }
+            // Acknowledged or reaped between the lookup and the update
+            let outcome = registry
+                .nack_message(&id)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+                .ok_or_else(|| not_leased("no longer leased"))?;
+            let metrics = MessagingMetrics::global();
+            Ok(match outcome {
+                NackOutcome::Requeued(next_attempt_at) => {
+                    metrics.record_retried();
+                    MessageEventBus::global().publish("retried", &id, "queued");
+                    serde_json::json!({
+                        "id": id,
+                        "status": "queued",
+                        "next_attempt_at": next_attempt_at,
+                    })
+                }
+                NackOutcome::DeadLettered => {
+                    metrics.record_dead_lettered();
+                    MessageEventBus::global().publish("dead_lettered", &id, "dead_letter");
+                    serde_json::json!({
+                        "id": id,
+                        "status": "dead_letter",
+                        "dead_letter_reason": "nacked past max_retries",
+                    })
+                }
+            })
+        })
+        .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err((status, msg))) => err_json(status, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+// ── Purge messages ───────────────────────────────────────────────
+
+/// Terminal statuses a purge may target; queued and leased messages are
+/// never purged.
+const PURGEABLE_STATUSES: [&str; 3] = ["dead_letter", "acknowledged", "expired"];
+
+#[derive(Deserialize)]
+pub struct PurgeBody {
+    /// `dead_letter`, `acknowledged`, or `expired` (dead-lettered for TTL).
+    pub status: String,
+    /// Only messages last updated at least this long ago are deleted.
+    pub older_than_secs: i64,
+}
+
+/// Delete old terminal messages and their audit events (retention).
+pub async fn handle_purge_messages(
+    State(state): State<CpState>,
+    Json(body): Json<PurgeBody>,
+) -> ApiResponse {
+    if !PURGEABLE_STATUSES.contains(&body.status.as_str()) {
+        return err_json(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "Invalid status: '{}'. Valid values: {}",
+                body.status,
+                PURGEABLE_STATUSES.join(", ")
+            ),
+        );
+    }
+    if body.older_than_secs < 0 {
+        return err_json(
+            StatusCode::BAD_REQUEST,
+            "older_than_secs must not be negative",
+        );
+    }
+    let cutoff = (chrono::Utc::now() - chrono::Duration::seconds(body.older_than_secs))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
+        let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
+        let (messages, events) = registry
+            .purge_messages(&body.status, &cutoff)
+            .map_err(|e| format!("{e:#}"))?;
+        Ok(serde_json::json!({
+            "status": body.status,
+            "cutoff": cutoff,
+            "messages_deleted": messages,
+            "events_deleted": events,
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err(msg)) => err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+// ── Message status stream (SSE) ──────────────────────────────────
+
+/// Heartbeat comment interval, so idle proxies keep the stream open.
+const EVENT_STREAM_HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// Server-sent events for message status changes from the moment the client
+/// connects (no replay). Each event's type is the transition (`enqueued`,
+/// `leased`, `acknowledged`, `retried`, `dead_lettered`) and its data the
+/// JSON [`MessageStatusEvent`](crate::cp::message_events::MessageStatusEvent).
+/// A client too slow to keep up gets a `lagged` event with the number of
+/// changes it missed.
+pub async fn handle_message_events_stream() -> Response {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let rx = MessageEventBus::global().subscribe();
+    let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+        let event = match rx.recv().await {
+            Ok(change) => Event::default().event(change.event).json_data(&change),
+            Err(RecvError::Lagged(skipped)) => Event::default()
+                .event("lagged")
+                .json_data(serde_json::json!({ "skipped": skipped })),
+            Err(RecvError::Closed) => return None,
+        };
+        Some((event, rx))
+    });
+    Sse::new(stream)
+        .keep_alive(
+            KeepAlive::new()
+                .interval(EVENT_STREAM_HEARTBEAT)
+                .text("heartbeat"),
+        )
+        .into_response()
+}
+
+// ── Message stats ────────────────────────────────────────────────
+
+pub async fn handle_message_stats(State(state): State<CpState>) -> ApiResponse {
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
+        let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
+        let status_counts = registry
+            .message_status_counts()
+            .map_err(|e| format!("{e:#}"))?;
+        let reasons = registry
+            .dead_letter_reasons_summary()
+            .map_err(|e| format!("{e:#}"))?;
+
+        let mut by_status = serde_json::Map::new();
+        let mut total = 0i64;
+        for (status, count) in status_counts {
+            total += count;
+            by_status.insert(status, serde_json::json!(count));
+        }
+        let dead_letter_reasons: Vec<serde_json::Value> = reasons
+            .iter()
+            .map(|r| serde_json::json!({ "reason": r.reason, "count": r.count }))
+            .collect();
+
+        Ok(serde_json::json!({
+            "total": total,
+            "by_status": by_status,
+            "dead_letter_reasons": dead_letter_reasons,
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err(msg)) => err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+// ── Message throughput ───────────────────────────────────────────
+
+/// Message state transitions since startup and during the last delivery
+/// worker interval, from the in-process [`MessagingMetrics`].
+pub async fn handle_message_throughput() -> ApiResponse {
+    let metrics = MessagingMetrics::global();
+    let totals = metrics.counts();
+    let uptime_secs = metrics.uptime_secs();
+    let last_interval = metrics.last_interval().map(|interval| {
+        serde_json::json!({
+            "interval_secs": interval.secs,
+            "counts": interval.counts.to_json(),
+        })
+    });
+    ok_json(serde_json::json!({
+        "uptime_secs": uptime_secs.floor() as u64,
+        "totals": totals.to_json(),
+        "per_minute": per_minute(totals, uptime_secs),
+        "last_interval": last_interval,
+    }))
+}
+
+fn per_minute(counts: MessagingCounts, secs: f64) -> serde_json::Value {
+    let mut rates = counts.to_json();
+    if let Some(map) = rates.as_object_mut() {
+        for value in map.values_mut() {
+            let count = value.as_u64().unwrap_or(0) as f64;
+            let rate = if secs > 0.0 { count * 60.0 / secs } else { 0.0 };
+            *value = serde_json::json!((rate * 100.0).round() / 100.0);
+        }
+    }
+    rates
+}
+
+// ── Message export ───────────────────────────────────────────────
+
+/// Header that opts an export out of payload secret redaction.
+const INCLUDE_SECRETS_HEADER: &str = "x-include-secrets";
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    /// Only `jsonl` is supported.
+    pub format: Option<String>,
+    /// Only messages created at or after this time (`%Y-%m-%d %H:%M:%S`, UTC).
+    pub since: Option<String>,
+}
+
+/// Stream every message, with its events inlined, as JSON lines for backup.
+///
+/// Rows are read through [`Registry::iter_messages`] on a blocking task and
+/// handed to the response body through a small channel, so neither side
+/// buffers the table. Payloads are run through secret redaction again
+/// (covering rows stored before the current detector settings) unless
+/// `X-Include-Secrets: true` is sent and allowed (see [`include_secrets`]).
+pub async fn handle_export_messages(
+    State(state): State<CpState>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let format = query.format.as_deref().unwrap_or("jsonl");
+    if format != "jsonl" {
+        return err_json(
+            StatusCode::BAD_REQUEST,
+            &format!("Invalid format: '{format}'. Valid values: jsonl"),
+        )
+        .into_response();
+    }
+    if let Some(ref since) = query.since {
+        if chrono::NaiveDateTime::parse_from_str(since, "%Y-%m-%d %H:%M:%S").is_err() {
+            return err_json(
+                StatusCode::BAD_REQUEST,
+                "since must be formatted as YYYY-MM-DD HH:MM:SS (UTC)",
+            )
+            .into_response();
+        }
+    }
+    let redaction = match include_secrets(&state, &headers, "message export") {
+        Ok(redaction) => redaction,
+        Err(resp) => return resp.into_response(),
+    };
+
+    // Open up front so a broken registry is a 500, not a truncated stream
+    let db_path = state.db_path.clone();
+    let registry = match tokio::task::spawn_blocking(move || Registry::open(&db_path)).await {
+        Ok(Ok(registry)) => registry,
+        Ok(Err(e)) => {
+            return err_json(StatusCode::INTERNAL_SERVER_ERROR, &format!("{e:#}")).into_response()
+        }
+        Err(e) => {
+            return err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Task join error: {e}"),
+            )
+            .into_response()
+        }
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(64);
+    let since = query.since;
+    tokio::task::spawn_blocking(move || {
+        for msg in registry.iter_messages(since.as_deref()) {
+            let line = msg.and_then(|msg| {
+                let events = registry.get_message_events(&msg.id)?;
+                Ok(export_line(&msg, &events, redaction.as_deref()))
+            });
+            let chunk = line.map_err(|e| {
+                tracing::error!("Message export failed: {e:#}");
+                std::io::Error::other(format!("{e:#}"))
+            });
+            let failed = chunk.is_err();
+            // A send error means the client went away
+            if tx.blocking_send(chunk).is_err() || failed {
+                return;
+            }
+        }
+    });
+
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .header(
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"messages.jsonl\"",
+        )
+        .body(Body::from_stream(body))
+        .unwrap()
+}
+
+/// How `endpoint` should redact payloads: with the control plane's
+/// [`SecretMasking`], or not at all (`None`) if the request sent
+/// `X-Include-Secrets: true` (or `1`). Asking for unredacted payloads is
+/// refused with 403 unless the control plane was started with
+/// [`ALLOW_SECRET_EXPORT_ENV`]; every allowed use is logged.
+fn include_secrets(
+    state: &CpState,
+    headers: &HeaderMap,
+    endpoint: &str,
+) -> Result<Option<Arc<SecretMasking>>, ApiResponse> {
+    let wanted = headers
+        .get(INCLUDE_SECRETS_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("true") || v == "1");
+    if !wanted {
+        return Ok(Some(state.masking.clone()));
+    }
+    if !state.allow_secret_export {
+        return Err(err_json(
+            StatusCode::FORBIDDEN,
+            &format!(
+                "{INCLUDE_SECRETS_HEADER} is disabled; set {ALLOW_SECRET_EXPORT_ENV}=true \
+                 on the control plane to allow it"
+            ),
+        ));
+    }
+    tracing::warn!("{endpoint}: returning unredacted payloads ({INCLUDE_SECRETS_HEADER})");
+    Ok(None)
+}
+
+/// The full message row as JSON, payload secrets redacted with `redaction`
+/// unless it is `None`.
+fn message_row_json(
+    msg: &crate::db::Message,
+    redaction: Option<&SecretMasking>,
+) -> serde_json::Value {
+    let mut payload = serde_json::from_str::<serde_json::Value>(&msg.payload)
+        .unwrap_or_else(|_| serde_json::Value::String(msg.payload.clone()));
+    if let Some(masking) = redaction {
+        redact_payload_secrets_with(&mut payload, masking);
+    }
+    serde_json::json!({
+        "id": msg.id,
+        "from_instance": msg.from_instance,
+        "to_instance": msg.to_instance,
+        "message_type": msg.message_type,
+        "payload": payload,
+        "correlation_id": msg.correlation_id,
+        "idempotency_key": msg.idempotency_key,
+        "hop_count": msg.hop_count,
+        "content_type": msg.content_type,
+        "priority": msg.priority,
+        "status": msg.status,
+        "retry_count": msg.retry_count,
+        "max_retries": msg.max_retries,
+        "next_attempt_at": msg.next_attempt_at,
+        "lease_expires_at": msg.lease_expires_at,
+        "expires_at": msg.expires_at,
+        "dead_letter_reason": msg.dead_letter_reason,
+        "created_at": msg.created_at,
+        "updated_at": msg.updated_at,
+    })
+}
+
+/// A message event as JSON, with a JSON `detail` parsed.
+fn event_json(e: &crate::db::MessageEvent) -> serde_json::Value {
+    serde_json::json!({
+        "event_type": e.event_type,
+        "detail": e.detail.as_deref().map(|d| {
+            serde_json::from_str::<serde_json::Value>(d)
+                .unwrap_or_else(|_| serde_json::Value::String(d.to_string()))
+        }),
+        "created_at": e.created_at,
+    })
+}
+
+/// One export line: the full message row plus its events, newline-terminated.
+fn export_line(
+    msg: &crate::db::Message,
+    events: &[crate::db::MessageEvent],
+    redaction: Option<&SecretMasking>,
+) -> Bytes {
+    let mut row = message_row_json(msg, redaction);
+    row["events"] = events.iter().map(event_json).collect();
+    let mut line = row.to_string();
+    line.push('\n');
+    Bytes::from(line)
+}
+
+// ── Correlation chain ────────────────────────────────────────────
+
+/// Every message on a correlation thread, oldest first, each with its
+/// events: the full trace of a multi-hop handoff. The envelope adds
+/// `hop_count` (the highest hop count reached on the thread) and how many
+/// messages ended acknowledged or dead-lettered versus are still pending.
+/// Payloads are redacted as in the export unless `X-Include-Secrets: true`
+/// is sent and allowed.
+pub async fn handle_correlation_chain(
+    State(state): State<CpState>,
+    AxumPath(correlation_id): AxumPath<String>,
+    headers: HeaderMap,
+) -> ApiResponse {
+    let redaction = match include_secrets(&state, &headers, "correlation chain") {
+        Ok(redaction) => redaction,
+        Err(resp) => return resp,
+    };
+    let db_path = state.db_path.clone();
+    let result =
+        tokio::task::spawn_blocking(move || -> Result<serde_json::Value, (StatusCode, String)> {
+            let internal = |e: anyhow::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}"));
+            let registry = Registry::open(&db_path).map_err(internal)?;
+            let chain = registry
+                .correlation_messages(&correlation_id)
+                .map_err(internal)?;
+            if chain.is_empty() {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    format!("No messages with correlation_id '{correlation_id}'"),
+                ));
+            }
+
+            let mut messages = Vec::with_capacity(chain.len());
+            for msg in &chain {
+                let events = registry.get_message_events(&msg.id).map_err(internal)?;
+                let mut row = message_row_json(msg, redaction.as_deref());
+                row["events"] = events.iter().map(event_json).collect();
+                messages.push(row);
+            }
+            let count = |status: &str| chain.iter().filter(|m| m.status == status).count();
+            let acknowledged = count("acknowledged");
+            let dead_letter = count("dead_letter");
+            Ok(serde_json::json!({
+                "correlation_id": correlation_id,
+                "total": chain.len(),
+                "hop_count": chain.iter().map(|m| m.hop_count).max(),
+                "summary": {
+                    "acknowledged": acknowledged,
+                    "dead_letter": dead_letter,
+                    "pending": chain.len() - acknowledged - dead_letter,
+                },
+                "messages": messages,
+            }))
+        })
+        .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err((status, msg))) => err_json(status, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+// ── Message search ───────────────────────────────────────────────
+
+/// Upper bound on `limit=` for a search page.
+const MAX_SEARCH_LIMIT: usize = 200;
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    /// Text to find in message payloads (case-insensitive).
+    pub q: Option<String>,
+    pub status: Option<String>,
+    pub from_instance: Option<String>,
+    pub to_instance: Option<String>,
+    pub message_type: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Messages whose payload contains `q`, newest first, in the shared
+/// offset-paginated list shape. Payloads are redacted as in the export
+/// unless `X-Include-Secrets: true` is sent and allowed.
+pub async fn handle_search_messages(
+    State(state): State<CpState>,
+    Query(query): Query<SearchQuery>,
+    headers: HeaderMap,
+) -> ApiResponse {
+    let text = query.q.unwrap_or_default();
+    if text.trim().is_empty() {
+        return err_json(StatusCode::BAD_REQUEST, "q is required");
+    }
+    let limit = query.limit.unwrap_or(50).min(MAX_SEARCH_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let redaction = match include_secrets(&state, &headers, "message search") {
+        Ok(redaction) => redaction,
+        Err(resp) => return resp,
+    };
+    let filters = MessageSearchFilters {
+        status: query.status,
+        from_instance: query.from_instance,
+        to_instance: query.to_instance,
+        message_type: query.message_type,
+    };
+
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
+        let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
+        let (messages, total) = registry
+            .search_messages(&text, &filters, limit, offset)
+            .map_err(|e| format!("{e:#}"))?;
+        let messages: Vec<serde_json::Value> = messages
+            .iter()
+            .map(|m| message_row_json(m, redaction.as_deref()))
+            .collect();
+        Ok(paginated(
+            serde_json::json!({ "query": text, "messages": messages }),
+            total,
+            limit,
+            offset,
+        ))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(value)) => ok_json(value),
+        Ok(Err(msg)) => err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
 // ── Delivery worker ──────────────────────────────────────────────
 
 pub async fn run_delivery_worker(
@@ -464,6 +2438,7 @@ pub async fn run_delivery_worker(
     mut shutdown_rx: tokio::sync::watch::Receiver<bool>,
 ) {
     let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
     loop {
         tokio::select! {
@@ -477,27 +2452,52 @@ pub async fn run_delivery_worker(
         }
 
         let db = db_path.clone();
-        let result = tokio::task::spawn_blocking(move || delivery_tick(&db)).await;
+        let backoff =
+            workers::run_iteration(workers::DELIVERY_WORKER, move || delivery_tick(&db)).await;
+        MessagingMetrics::global().roll_interval();
 
-        if let Ok(Err(e)) = result {
-            tracing::error!("Delivery worker tick error: {e:#}");
+        if !backoff.is_zero() {
+            tokio::select! {
+                () = tokio::time::sleep(backoff) => {},
+                _ = shutdown_rx.changed() => {
+                    if *shutdown_rx.borrow() {
+                        tracing::info!("Delivery worker shutting down");
+                        return;
+                    }
+                }
+            }
         }
     }
 }
 
-fn delivery_tick(db_path: &Path) -> anyhow::Result<()> {
+/// One pass of the delivery worker: requeue expired leases, expire messages
+/// past their TTL, and auto-start recipients with pending work. Returns the
+/// number of messages and instances acted on.
+pub fn delivery_tick(db_path: &Path) -> anyhow::Result<usize> {
     let registry = Registry::open(db_path)?;
+    let metrics = MessagingMetrics::global();
+    let events = MessageEventBus::global();
+    let mut processed = 0;
 
     // Process expired leases
     let expired_leases = registry.get_expired_leases()?;
+    processed += expired_leases.len();
     for msg in expired_leases {
-        registry.append_message_event(&msg.id, "lease_expired", None)?;
+        registry.record_lease_outcome(&msg.id, "lease_expired")?;
         if msg.retry_count + 1 >= msg.max_retries {
             registry.dead_letter_message(&msg.id, "max retries exceeded")?;
+            metrics.record_dead_lettered();
+            events.publish("dead_lettered", &msg.id, "dead_letter");
             tracing::info!("Message {} dead-lettered (max retries)", msg.id);
         } else {
-            registry.retry_message(&msg.id)?;
-            registry.append_message_event(&msg.id, "retry_scheduled", None)?;
+            let next_attempt_at = registry.retry_message(&msg.id)?;
+            metrics.record_retried();
+            events.publish("retried", &msg.id, "queued");
+            let detail = serde_json::json!({
+                "attempt": msg.retry_count + 2,
+                "next_attempt_at": next_attempt_at,
+            });
+            registry.append_message_event(&msg.id, "retry_scheduled", Some(&detail.to_string()))?;
             tracing::debug!(
                 "Message {} retried (attempt {})",
                 msg.id,
@@ -509,12 +2509,17 @@ fn delivery_tick(db_path: &Path) -> anyhow::Result<()> {
     // Process TTL-expired messages
     let ttl_expired = registry.get_ttl_expired_messages()?;
     for msg in ttl_expired {
-        registry.dead_letter_message(&msg.id, "TTL expired")?;
-        tracing::info!("Message {} dead-lettered (TTL expired)", msg.id);
+        if registry.expire_message(&msg.id)? {
+            processed += 1;
+            metrics.record_ttl_expired();
+            events.publish("dead_lettered", &msg.id, "dead_letter");
+            tracing::info!("Message {} dead-lettered (TTL expired)", msg.id);
+        }
     }
 
     // Process auto-starts
     let autostart_needed = registry.get_instances_needing_autostart()?;
+    processed += autostart_needed.len();
     for (_msg, instance_name) in autostart_needed {
         tracing::info!("Auto-starting instance '{instance_name}' for pending messages");
         if let Err(e) = lifecycle::start_instance(&registry, &instance_name) {
@@ -522,5 +2527,5 @@ fn delivery_tick(db_path: &Path) -> anyhow::Result<()> {
         }
     }
 
-    Ok(())
+    Ok(processed)
 }
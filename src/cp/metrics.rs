@@ -0,0 +1,335 @@
+use crate::db::Registry;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::Instant;
+
+/// Message state transitions counted by [`MessagingMetrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessagingCounts {
+    /// Messages handed to a recipient by a receive call.
+    pub leased: u64,
+    pub acknowledged: u64,
+    /// Expired leases put back in the queue for another attempt.
+    pub retried: u64,
+    /// Expired leases that ran out of retries.
+    pub dead_lettered: u64,
+    /// Queued messages dead-lettered because their TTL passed.
+    pub ttl_expired: u64,
+}
+
+impl MessagingCounts {
+    fn since(self, earlier: Self) -> Self {
+        Self {
+            leased: self.leased.saturating_sub(earlier.leased),
+            acknowledged: self.acknowledged.saturating_sub(earlier.acknowledged),
+            retried: self.retried.saturating_sub(earlier.retried),
+            dead_lettered: self.dead_lettered.saturating_sub(earlier.dead_lettered),
+            ttl_expired: self.ttl_expired.saturating_sub(earlier.ttl_expired),
+        }
+    }
+
+    /// `(metric suffix, help, value)` for each counter, in a stable order.
+    fn fields(self) -> [(&'static str, &'static str, u64); 5] {
+        [
+            ("leased", "Messages leased to recipients.", self.leased),
+            (
+                "acknowledged",
+                "Leased messages acknowledged by recipients.",
+                self.acknowledged,
+            ),
+            (
+                "retried",
+                "Expired leases requeued for another attempt.",
+                self.retried,
+            ),
+            (
+                "dead_lettered",
+                "Expired leases dead-lettered after exhausting retries.",
+                self.dead_lettered,
+            ),
+            (
+                "ttl_expired",
+                "Queued messages dead-lettered because their TTL passed.",
+                self.ttl_expired,
+            ),
+        ]
+    }
+
+    pub fn to_json(self) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        for (name, _, value) in self.fields() {
+            map.insert(name.to_string(), value.into());
+        }
+        serde_json::Value::Object(map)
+    }
+}
+
+/// Transitions during one completed delivery-worker interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntervalCounts {
+    pub counts: MessagingCounts,
+    pub secs: f64,
+}
+
+#[derive(Debug)]
+struct IntervalState {
+    started: Instant,
+    baseline: MessagingCounts,
+    last: Option<IntervalCounts>,
+}
+
+/// Throughput counters for the messaging pipeline, incremented by the
+/// delivery worker and by the handlers that change message state.
+///
+/// Counts are since process start; unlike `message_stats`, which is a
+/// point-in-time view of the queue, they give rates of change.
+#[derive(Debug)]
+pub struct MessagingMetrics {
+    leased: AtomicU64,
+    acknowledged: AtomicU64,
+    retried: AtomicU64,
+    dead_lettered: AtomicU64,
+    ttl_expired: AtomicU64,
+    started: Instant,
+    interval: Mutex<IntervalState>,
+}
+
+impl Default for MessagingMetrics {
+    fn default() -> Self {
+        let now = Instant::now();
+        Self {
+            leased: AtomicU64::new(0),
+            acknowledged: AtomicU64::new(0),
+            retried: AtomicU64::new(0),
+            dead_lettered: AtomicU64::new(0),
+            ttl_expired: AtomicU64::new(0),
+            started: now,
+            interval: Mutex::new(IntervalState {
+                started: now,
+                baseline: MessagingCounts::default(),
+                last: None,
+            }),
+        }
+    }
+}
+
+impl MessagingMetrics {
+    /// Process-wide counters read by `/metrics` and the throughput API.
+    pub fn global() -> &'static Self {
+        static METRICS: OnceLock<MessagingMetrics> = OnceLock::new();
+        METRICS.get_or_init(Self::default)
+    }
+
+    pub fn record_leased(&self, count: usize) {
+        self.leased.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_acknowledged(&self) {
+        self.acknowledged.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_retried(&self) {
+        self.retried.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_dead_lettered(&self) {
+        self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_ttl_expired(&self) {
+        self.ttl_expired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn counts(&self) -> MessagingCounts {
+        MessagingCounts {
+            leased: self.leased.load(Ordering::Relaxed),
+            acknowledged: self.acknowledged.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            ttl_expired: self.ttl_expired.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn uptime_secs(&self) -> f64 {
+        self.started.elapsed().as_secs_f64()
+    }
+
+    /// Close the current interval and start the next one. Called by the
+    /// delivery worker once per tick.
+    pub fn roll_interval(&self) {
+        let counts = self.counts();
+        let mut interval = self.interval.lock().unwrap_or_else(PoisonError::into_inner);
+        interval.last = Some(IntervalCounts {
+            counts: counts.since(interval.baseline),
+            secs: interval.started.elapsed().as_secs_f64(),
+        });
+        interval.started = Instant::now();
+        interval.baseline = counts;
+    }
+
+    /// Transitions during the most recently completed interval, if any.
+    pub fn last_interval(&self) -> Option<IntervalCounts> {
+        self.interval
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .last
+    }
+}
+
+/// Everything the Prometheus endpoints report, taken once per scrape: the
+/// process counters and the registry's messages and instances by status.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub counts: MessagingCounts,
+    pub messages: Vec<(String, i64)>,
+    pub instances: Vec<(String, i64)>,
+}
+
+impl MetricsSnapshot {
+    pub fn collect(metrics: &MessagingMetrics, registry: &Registry) -> anyhow::Result<Self> {
+        Ok(Self {
+            counts: metrics.counts(),
+            messages: registry.message_status_counts()?,
+            instances: registry.instance_status_counts()?,
+        })
+    }
+
+    /// The snapshot in the Prometheus text exposition format: counters,
+    /// then gauges.
+    pub fn render_prometheus(&self) -> String {
+        render_counters(self.counts) + &render_state_gauges(&self.messages, &self.instances)
+    }
+}
+
+fn render_counters(counts: MessagingCounts) -> String {
+    let mut out = String::new();
+    for (name, help, value) in counts.fields() {
+        let metric = format!("zeroclaw_cp_messages_{name}_total");
+        let _ = writeln!(out, "# HELP {metric} {help}");
+        let _ = writeln!(out, "# TYPE {metric} counter");
+        let _ = writeln!(out, "{metric} {value}");
+    }
+    out
+}
+
+/// Registry state at scrape time, rendered as Prometheus gauges:
+/// messages and non-archived instances by status, plus the queued and
+/// dead-letter counts on their own for simple alert rules.
+fn render_state_gauges(messages: &[(String, i64)], instances: &[(String, i64)]) -> String {
+    let count_of = |status: &str| {
+        messages
+            .iter()
+            .find(|(s, _)| s == status)
+            .map_or(0, |(_, n)| *n)
+    };
+    let mut out = String::new();
+    write_labeled_gauge(
+        &mut out,
+        "zeroclaw_messages_total",
+        "Messages in the registry, by status.",
+        messages,
+    );
+    write_gauge(
+        &mut out,
+        "zeroclaw_messages_queued",
+        "Messages waiting to be leased.",
+        count_of("queued"),
+    );
+    write_gauge(
+        &mut out,
+        "zeroclaw_dead_letter_total",
+        "Messages in the dead-letter state.",
+        count_of("dead_letter"),
+    );
+    write_labeled_gauge(
+        &mut out,
+        "zeroclaw_instances",
+        "Registered (non-archived) instances, by status.",
+        instances,
+    );
+    out
+}
+
+fn write_gauge(out: &mut String, metric: &str, help: &str, value: i64) {
+    let _ = writeln!(out, "# HELP {metric} {help}");
+    let _ = writeln!(out, "# TYPE {metric} gauge");
+    let _ = writeln!(out, "{metric} {value}");
+}
+
+fn write_labeled_gauge(out: &mut String, metric: &str, help: &str, counts: &[(String, i64)]) {
+    let _ = writeln!(out, "# HELP {metric} {help}");
+    let _ = writeln!(out, "# TYPE {metric} gauge");
+    for (status, value) in counts {
+        let status = status.replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = writeln!(out, "{metric}{{status=\"{status}\"}} {value}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roll_interval_reports_deltas() {
+        let metrics = MessagingMetrics::default();
+        assert_eq!(metrics.last_interval(), None);
+
+        metrics.record_leased(3);
+        metrics.record_acknowledged();
+        metrics.roll_interval();
+        let first = metrics.last_interval().unwrap();
+        assert_eq!(first.counts.leased, 3);
+        assert_eq!(first.counts.acknowledged, 1);
+
+        metrics.record_retried();
+        metrics.roll_interval();
+        let second = metrics.last_interval().unwrap();
+        assert_eq!(
+            second.counts,
+            MessagingCounts {
+                retried: 1,
+                ..MessagingCounts::default()
+            }
+        );
+        assert_eq!(metrics.counts().leased, 3);
+    }
+
+    #[test]
+    fn prometheus_output_has_one_counter_per_transition() {
+        let metrics = MessagingMetrics::default();
+        metrics.record_ttl_expired();
+        let text = render_counters(metrics.counts());
+        assert_eq!(text.matches("# TYPE ").count(), 5);
+        assert!(text.contains("# TYPE zeroclaw_cp_messages_ttl_expired_total counter\n"));
+        assert!(text.contains("\nzeroclaw_cp_messages_ttl_expired_total 1\n"));
+        assert!(text.contains("\nzeroclaw_cp_messages_leased_total 0\n"));
+    }
+
+    #[test]
+    fn snapshot_renders_counters_and_gauges() {
+        let metrics = MessagingMetrics::default();
+        metrics.record_leased(2);
+        let registry = Registry::open_in_memory().unwrap();
+        registry
+            .create_instance("id-1", "agent", 18801, "/c.toml", None, None)
+            .unwrap();
+        let snapshot = MetricsSnapshot::collect(&metrics, &registry).unwrap();
+        let text = snapshot.render_prometheus();
+        assert!(text.contains("\nzeroclaw_cp_messages_leased_total 2\n"));
+        assert!(text.contains("\nzeroclaw_messages_queued 0\n"));
+        assert!(text.contains("\nzeroclaw_instances{status=\"stopped\"} 1\n"));
+    }
+
+    #[test]
+    fn state_gauges_label_by_status() {
+        let messages = vec![("dead_letter".to_string(), 2), ("leased".to_string(), 1)];
+        let instances = vec![("running".to_string(), 3)];
+        let text = render_state_gauges(&messages, &instances);
+        assert!(text.contains("# TYPE zeroclaw_messages_total gauge\n"));
+        assert!(text.contains("\nzeroclaw_messages_total{status=\"leased\"} 1\n"));
+        assert!(text.contains("\nzeroclaw_messages_queued 0\n"));
+        assert!(text.contains("\nzeroclaw_dead_letter_total 2\n"));
+        assert!(text.contains("\nzeroclaw_instances{status=\"running\"} 3\n"));
+    }
+}
@@ -1,4 +1,10 @@
+pub mod disk_usage;
 pub mod masking;
+pub mod message_events;
 pub mod messaging;
+pub mod metrics;
+pub mod routing_cache;
 pub mod server;
 pub mod supervisor;
+pub mod transform;
+pub mod workers;
@@ -0,0 +1,207 @@
+//! In-memory copy of the routing rules, so sends do not query the
+//! `routing_rules` table every time.
+//!
+//! The control plane invalidates the cache whenever it writes rules itself
+//! (the rule handlers, auto-created rules, deleting an instance). Writes made
+//! anywhere else (another process, the CLI, a hand edit) are caught by
+//! [`Registry::routing_rules_version`], a counter that triggers bump on every
+//! rule insert, update or delete. It is checked at most once per
+//! [`VERSION_CHECK_INTERVAL`], so lookups in between never touch the DB.
+
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use crate::db::{Registry, RoutingRule};
+
+/// How often a lookup checks the rules version for out-of-band writes.
+pub const VERSION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Snapshot {
+    rules: Vec<RoutingRule>,
+    /// Rules version the snapshot was read at; `None` once invalidated.
+    version: Option<i64>,
+    /// When `version` was last compared with the registry's.
+    checked_at: Instant,
+}
+
+/// Shared, cheaply cloned cache of every routing rule. Starts empty and
+/// stale; [`load`](Self::load) it at startup to skip the first reload.
+#[derive(Clone)]
+pub struct RoutingRuleCache {
+    inner: Arc<RwLock<Snapshot>>,
+    check_interval: Duration,
+}
+
+impl Default for RoutingRuleCache {
+    fn default() -> Self {
+        Self::with_version_check_interval(VERSION_CHECK_INTERVAL)
+    }
+}
+
+impl RoutingRuleCache {
+    /// A cache that checks for out-of-band writes every `check_interval`
+    /// instead of every [`VERSION_CHECK_INTERVAL`].
+    pub fn with_version_check_interval(check_interval: Duration) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Snapshot {
+                rules: Vec::new(),
+                version: None,
+                checked_at: Instant::now(),
+            })),
+            check_interval,
+        }
+    }
+
+    /// Replace the cached rules with the registry's current ones.
+    pub fn load(&self, registry: &Registry) -> Result<()> {
+        // Version first: a write landing in between only costs a reload
+        let version = registry.routing_rules_version()?;
+        let rules = registry.list_routing_rules()?;
+        *self.inner.write().unwrap_or_else(PoisonError::into_inner) = Snapshot {
+            rules,
+            version: Some(version),
+            checked_at: Instant::now(),
+        };
+        Ok(())
+    }
+
+    /// Mark the cached rules stale; the next lookup reloads them.
+    pub fn invalidate(&self) {
+        self.inner
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .version = None;
+    }
+
+    /// Reload the rules if they were invalidated, or if the version check is
+    /// due and finds an out-of-band write.
+    fn refresh(&self, registry: &Registry) -> Result<()> {
+        let (version, due) = {
+            let snapshot = self.inner.read().unwrap_or_else(PoisonError::into_inner);
+            (
+                snapshot.version,
+                snapshot.checked_at.elapsed() >= self.check_interval,
+            )
+        };
+        match version {
+            None => self.load(registry),
+            Some(_) if !due => Ok(()),
+            Some(version) => {
+                if registry.routing_rules_version()? == version {
+                    self.inner
+                        .write()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .checked_at = Instant::now();
+                    Ok(())
+                } else {
+                    self.load(registry)
+                }
+            }
+        }
+    }
+
+    /// [`Registry::check_route_allowed`], answered from the cache.
+    pub fn check_route_allowed(
+        &self,
+        registry: &Registry,
+        from: &str,
+        to: &str,
+        message_type: &str,
+    ) -> Result<Option<RoutingRule>> {
+        self.refresh(registry)?;
+        Ok(self
+            .inner
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .rules
+            .iter()
+            .find(|rule| rule.allows(from, to, message_type))
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::HealthGate;
+
+    fn add_rule(registry: &Registry, from: &str, to: &str) -> String {
+        registry
+            .create_routing_rule(
+                from,
+                to,
+                "*",
+                5,
+                3600,
+                60,
+                false,
+                false,
+                HealthGate::Off,
+                None,
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn reloads_after_out_of_band_writes() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        let registry = Registry::open(&db_path).unwrap();
+        let cache = RoutingRuleCache::with_version_check_interval(Duration::ZERO);
+        let rule_id = add_rule(&registry, "a", "b");
+        cache.load(&registry).unwrap();
+
+        let found = cache.check_route_allowed(&registry, "a", "b", "task");
+        assert_eq!(found.unwrap().unwrap().id, rule_id);
+
+        // Another connection deletes the rule and adds a new one
+        let other = Registry::open(&db_path).unwrap();
+        assert!(other.delete_routing_rule(&rule_id).unwrap());
+        add_rule(&other, "a", "c");
+
+        let lookup = |to: &str| {
+            cache
+                .check_route_allowed(&registry, "a", to, "task")
+                .unwrap()
+        };
+        assert!(lookup("b").is_none());
+        assert!(lookup("c").is_some());
+        let snapshot = cache.inner.read().unwrap();
+        assert_eq!(snapshot.rules.len(), 1);
+        assert_eq!(
+            snapshot.version,
+            Some(registry.routing_rules_version().unwrap())
+        );
+    }
+
+    #[test]
+    fn lookups_between_version_checks_use_the_cache_alone() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        let registry = Registry::open(&db_path).unwrap();
+        let cache = RoutingRuleCache::with_version_check_interval(Duration::from_secs(3600));
+        let rule_id = add_rule(&registry, "a", "b");
+        cache.load(&registry).unwrap();
+
+        // An out-of-band delete goes unseen until the next version check...
+        let other = Registry::open(&db_path).unwrap();
+        assert!(other.delete_routing_rule(&rule_id).unwrap());
+        let lookup = || {
+            cache
+                .check_route_allowed(&registry, "a", "b", "task")
+                .unwrap()
+        };
+        assert_eq!(lookup().unwrap().id, rule_id);
+        // ...and a miss is answered without asking the registry
+        assert!(cache
+            .check_route_allowed(&registry, "a", "c", "task")
+            .unwrap()
+            .is_none());
+
+        // A write the control plane makes invalidates straight away
+        cache.invalidate();
+        assert!(lookup().is_none());
+    }
+}
@@ -1,10 +1,11 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufRead, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use axum::body::Body;
+use axum::body::{Body, Bytes};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
 use axum::extract::{Path as AxumPath, Query, State};
-use axum::http::{header, HeaderMap, StatusCode};
+use axum::http::{header, HeaderMap, StatusCode, Uri};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{delete, get, post};
 use axum::{Json, Router};
@@ -12,72 +13,231 @@ use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use tokio_util::io::ReaderStream;
 
+use crate::cp::disk_usage::{self, human_bytes, DirUsage, UsageWalker};
 use crate::cp::masking::{
     apply_json_patch_to_toml, collect_key_paths, collect_secret_writes,
-    compute_secret_fingerprints, diff_json, mask_config_secrets, preserve_masked_secrets,
+    compute_secret_fingerprints, diff_json, mask_config_secrets_with, preserve_masked_secrets_with,
     reject_dotted_keys, reject_masked_sentinels, validate_null_targets, validate_patch_paths,
-    SECRET_PATHS_MANIFEST,
+    SecretMasking, SECRET_PATHS_MANIFEST,
 };
 use crate::cp::messaging;
-use crate::db::Registry;
+use crate::cp::metrics::{MessagingMetrics, MetricsSnapshot};
+use crate::cp::routing_cache::RoutingRuleCache;
+use crate::cp::workers::WorkerStatusBoard;
+use crate::db::{ArchiveOutcome, PortAllocError, Registry, SqliteTuning, UnarchiveOutcome};
 use crate::lifecycle;
-use crate::lifecycle::LifecycleError;
-
-/// Maximum bytes to read from the tail of a log file.
-/// Bounds memory usage regardless of total file size.
-const MAX_TAIL_BYTES: u64 = 4 * 1024 * 1024; // 4 MiB
+use crate::lifecycle::log_lines;
+use crate::lifecycle::webhooks::LifecycleEvent;
+use crate::lifecycle::{LifecycleError, ReloadOutcome};
+
+/// Default for `LogLimits::tail_bytes`.
+const DEFAULT_LOG_TAIL_BYTES: u64 = 4 * 1024 * 1024; // 4 MiB
+/// Default for `LogLimits::max_lines`.
+const DEFAULT_MAX_LOG_LINES: usize = 10_000;
+/// Hard ceilings on the configured log limits, so a configuration mistake
+/// cannot make a single logs request allocate without bound.
+const LOG_TAIL_BYTES_CEILING: u64 = 64 * 1024 * 1024; // 64 MiB
+const MAX_LOG_LINES_CEILING: usize = 200_000;
+
+/// Env var overriding `LogLimits::max_lines`.
+pub const MAX_LOG_LINES_ENV: &str = "ZEROCLAW_CP_MAX_LOG_LINES";
+/// Env var overriding `LogLimits::tail_bytes`.
+pub const LOG_TAIL_BYTES_ENV: &str = "ZEROCLAW_CP_LOG_TAIL_BYTES";
+
+/// Env var overriding `PortRange::start`.
+pub const PORT_RANGE_START_ENV: &str = "ZEROCLAW_CP_PORT_RANGE_START";
+/// Env var overriding `PortRange::end`.
+pub const PORT_RANGE_END_ENV: &str = "ZEROCLAW_CP_PORT_RANGE_END";
+
+/// Env var that, set to `true` or `1`, lets callers opt out of payload
+/// secret redaction with `X-Include-Secrets: true`.
+pub const ALLOW_SECRET_EXPORT_ENV: &str = "ZEROCLAW_CP_ALLOW_SECRET_EXPORT";
 
 /// Embedded SPA HTML served at `/` and as a fallback for non-API paths.
 const INDEX_HTML: &str = include_str!("../../static/index.html");
 
-/// Read the last `n` lines from a file without loading the entire file.
-/// Reads at most `MAX_TAIL_BYTES` from the end of the file.
-fn read_last_n_lines(path: &Path, n: usize) -> std::io::Result<Vec<String>> {
-    let mut file = std::fs::File::open(path)?;
-    let file_len = file.metadata()?.len();
+/// Env var naming an HTML file, or a directory of UI files with an
+/// `index.html`, to serve instead of the embedded dashboard.
+pub const UI_PATH_ENV: &str = "ZEROCLAW_CP_UI_PATH";
+/// Env var that turns the dashboard off when set to `off`, `false` or `0`.
+pub const UI_ENABLED_ENV: &str = "ZEROCLAW_CP_UI";
+
+/// Bounds on what the logs endpoint reads and returns.
+///
+/// Read from `ZEROCLAW_CP_MAX_LOG_LINES` / `ZEROCLAW_CP_LOG_TAIL_BYTES` once
+/// at startup and kept in [`CpState`]; unset or invalid values fall back to
+/// 10,000 lines / 4 MiB, and larger values are clamped to 200,000 lines /
+/// 64 MiB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogLimits {
+    /// Most lines a single request may return.
+    pub max_lines: usize,
+    /// Most bytes read from the end of the file (or per page in `head` mode).
+    pub tail_bytes: u64,
+}
 
-    let read_from = file_len.saturating_sub(MAX_TAIL_BYTES);
-    file.seek(SeekFrom::Start(read_from))?;
+impl Default for LogLimits {
+    fn default() -> Self {
+        Self {
+            max_lines: DEFAULT_MAX_LOG_LINES,
+            tail_bytes: DEFAULT_LOG_TAIL_BYTES,
+        }
+    }
+}
 
-    let to_read = (file_len - read_from) as usize;
-    let mut buf = vec![0u8; to_read];
-    file.read_exact(&mut buf)?;
+impl LogLimits {
+    /// Limits from configured values: `None` or zero keeps the default, and
+    /// anything above the ceiling is clamped to it.
+    pub fn new(max_lines: Option<u64>, tail_bytes: Option<u64>) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_lines: max_lines
+                .filter(|v| *v > 0)
+                .map_or(defaults.max_lines, |v| {
+                    usize::try_from(v).unwrap_or(usize::MAX)
+                })
+                .min(MAX_LOG_LINES_CEILING),
+            tail_bytes: tail_bytes
+                .filter(|v| *v > 0)
+                .unwrap_or(defaults.tail_bytes)
+                .min(LOG_TAIL_BYTES_CEILING),
+        }
+    }
 
-    let text = String::from_utf8_lossy(&buf);
-    let all_lines: Vec<&str> = text.lines().collect();
+    pub fn from_env() -> Self {
+        fn u64_env(key: &str) -> Option<u64> {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        }
+        Self::new(u64_env(MAX_LOG_LINES_ENV), u64_env(LOG_TAIL_BYTES_ENV))
+    }
 
-    // If we seeked past the start, the first "line" may be partial -- skip it
-    let skip = if read_from > 0 && !all_lines.is_empty() {
-        1
-    } else {
-        0
+    fn to_json(self) -> serde_json::Value {
+        serde_json::json!({
+            "max_lines": self.max_lines,
+            "tail_bytes": self.tail_bytes,
+        })
+    }
+}
+
+/// Inclusive range new and cloned instances get their gateway port from.
+///
+/// Read from `ZEROCLAW_CP_PORT_RANGE_START` / `ZEROCLAW_CP_PORT_RANGE_END`
+/// (default 18801-18999) once at startup and kept in [`CpState`]. Unlike
+/// the log limits, a bad value is an error rather than a fallback: the
+/// server refuses to start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Default for PortRange {
+    fn default() -> Self {
+        Self {
+            start: 18801,
+            end: 18999,
+        }
+    }
+}
+
+impl std::fmt::Display for PortRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl PortRange {
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(
+            std::env::var(PORT_RANGE_START_ENV).ok().as_deref(),
+            std::env::var(PORT_RANGE_END_ENV).ok().as_deref(),
+        )
+    }
+
+    /// Validate a range from raw env values (`None` = unset, use the
+    /// default): both ends above 1024 and `start <= end`.
+    pub fn parse(start: Option<&str>, end: Option<&str>) -> Result<Self, String> {
+        fn port(key: &str, value: Option<&str>, default: u16) -> Result<u16, String> {
+            let Some(value) = value else {
+                return Ok(default);
+            };
+            let port: u16 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("{key} must be a port number, got '{value}'"))?;
+            if port <= 1024 {
+                return Err(format!("{key} must be above 1024, got {port}"));
+            }
+            Ok(port)
+        }
+        let defaults = Self::default();
+        let range = Self {
+            start: port(PORT_RANGE_START_ENV, start, defaults.start)?,
+            end: port(PORT_RANGE_END_ENV, end, defaults.end)?,
+        };
+        if range.start > range.end {
+            return Err(format!(
+                "{PORT_RANGE_START_ENV} ({}) must not exceed {PORT_RANGE_END_ENV} ({})",
+                range.start, range.end
+            ));
+        }
+        Ok(range)
+    }
+}
+
+/// Parse one daemon log line for `format=json`. Lines written by the
+/// daemon's JSON formatter (`ZEROCLAW_LOG_FORMAT=json`) become
+/// `{timestamp, level, target, message, fields}`; anything else (plain-text
+/// logs, panics, output from child processes) is kept as `{raw}`.
+fn parse_log_line(line: &str) -> serde_json::Value {
+    let Ok(serde_json::Value::Object(mut obj)) = serde_json::from_str(line) else {
+        return serde_json::json!({ "raw": line });
     };
+    let mut fields = match obj.remove("fields") {
+        Some(serde_json::Value::Object(fields)) => fields,
+        _ => serde_json::Map::new(),
+    };
+    let message = fields
+        .remove("message")
+        .or_else(|| obj.remove("message"))
+        .unwrap_or(serde_json::Value::Null);
+    serde_json::json!({
+        "timestamp": obj.remove("timestamp"),
+        "level": obj.remove("level"),
+        "target": obj.remove("target"),
+        "message": message,
+        "fields": fields,
+    })
+}
 
-    let usable = &all_lines[skip..];
-    let start = usable.len().saturating_sub(n);
-    Ok(usable[start..].iter().map(|s| s.to_string()).collect())
+/// `lines` as returned by the logs endpoint: strings, or parsed entries
+/// when `structured`.
+fn log_lines_json(lines: Vec<String>, structured: bool) -> serde_json::Value {
+    if structured {
+        lines.iter().map(|l| parse_log_line(l)).collect()
+    } else {
+        serde_json::json!(lines)
+    }
 }
 
-/// Read a tail window of `MAX_TAIL_BYTES` from a file and paginate within it.
+/// Read a tail window of `tail_bytes` from a file and paginate within it.
 /// Returns (lines, window_lines, has_more, truncated).
 fn read_lines_paginated(
     path: &Path,
     offset: usize,
     count: usize,
+    tail_bytes: u64,
 ) -> std::io::Result<(Vec<String>, usize, bool, bool)> {
     let mut file = std::fs::File::open(path)?;
     let file_len = file.metadata()?.len();
 
-    let read_from = file_len.saturating_sub(MAX_TAIL_BYTES);
+    let read_from = file_len.saturating_sub(tail_bytes);
     let truncated = read_from > 0;
     file.seek(SeekFrom::Start(read_from))?;
 
-    let to_read = (file_len - read_from) as usize;
-    let mut buf = vec![0u8; to_read];
-    file.read_exact(&mut buf)?;
-
-    let text = String::from_utf8_lossy(&buf);
-    let all_lines: Vec<&str> = text.lines().collect();
+    let all_lines = log_lines::read_lines(file.take(file_len - read_from))?;
 
     let skip = if truncated && !all_lines.is_empty() {
         1
@@ -89,36 +249,441 @@ fn read_lines_paginated(
 
     let start = offset.min(window_lines);
     let end = (start + count).min(window_lines);
-    let lines: Vec<String> = usable[start..end].iter().map(|s| s.to_string()).collect();
+    let lines = usable[start..end].to_vec();
     let has_more = end < window_lines;
 
     Ok((lines, window_lines, has_more, truncated))
 }
 
-/// Shared state: just the DB path. Each request opens its own connection.
+/// How far `read_lines_from_start` keeps reading past the returned page to
+/// count the file's lines before giving up and reporting the total unknown.
+const MAX_LINE_COUNT_SCAN_BYTES: u64 = 64 * 1024 * 1024; // 64 MiB
+
+/// Longest line `read_lines_from_start` returns; the rest of a longer line
+/// is skipped, so one huge or unterminated line cannot exhaust memory.
+const MAX_LOG_LINE_BYTES: usize = 1024 * 1024; // 1 MiB
+
+/// Read one line (through its `\n`) into `buf`, keeping at most `cap` bytes
+/// of it. Returns the bytes consumed from `reader`; 0 at end of file.
+fn read_capped_line<R: BufRead>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    cap: usize,
+) -> std::io::Result<u64> {
+    buf.clear();
+    let mut consumed = 0u64;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            return Ok(consumed);
+        }
+        let (line, done) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (&available[..=i], true),
+            None => (available, false),
+        };
+        let keep = line.len().min(cap.saturating_sub(buf.len()));
+        buf.extend_from_slice(&line[..keep]);
+        let n = line.len();
+        reader.consume(n);
+        consumed += n as u64;
+        if done {
+            return Ok(consumed);
+        }
+    }
+}
+
+/// A page of a log read forwards by `read_lines_from_start`.
+struct LogHeadPage {
+    lines: Vec<String>,
+    /// Lines in the whole file; `None` when the scan budget ran out first or
+    /// the read started past byte 0.
+    total_lines: Option<usize>,
+    has_more: bool,
+    /// Byte offset just past the returned lines, where the next page starts.
+    next_byte_offset: u64,
+}
+
+/// Read up to `count` lines from `byte_offset`, after skipping `from_line`
+/// lines. The returned page is bounded by `max_page_bytes` like the tail
+/// window and each line by `MAX_LOG_LINE_BYTES`; the rest of the file is
+/// then scanned for the line count, up to `MAX_LINE_COUNT_SCAN_BYTES`.
+fn read_lines_from_start(
+    path: &Path,
+    byte_offset: u64,
+    from_line: usize,
+    count: usize,
+    max_page_bytes: u64,
+) -> std::io::Result<LogHeadPage> {
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(byte_offset))?;
+    let mut reader = std::io::BufReader::new(file);
+    let mut buf = Vec::new();
+    let mut next_line = |buf: &mut Vec<u8>| -> std::io::Result<Option<u64>> {
+        match read_capped_line(&mut reader, buf, MAX_LOG_LINE_BYTES)? {
+            0 => Ok(None),
+            n => Ok(Some(n)),
+        }
+    };
+    // Line numbers are only absolute when reading from the top
+    let total = |lines: usize| (byte_offset == 0).then_some(lines);
+
+    let mut pos = byte_offset;
+    let mut line_no = 0;
+    while line_no < from_line {
+        let Some(n) = next_line(&mut buf)? else {
+            return Ok(LogHeadPage {
+                lines: Vec::new(),
+                total_lines: total(line_no),
+                has_more: false,
+                next_byte_offset: pos,
+            });
+        };
+        pos += n;
+        line_no += 1;
+    }
+
+    let mut lines = Vec::new();
+    let mut page_bytes = 0u64;
+    while lines.len() < count && page_bytes < max_page_bytes {
+        let Some(n) = next_line(&mut buf)? else {
+            return Ok(LogHeadPage {
+                lines,
+                total_lines: total(line_no),
+                has_more: false,
+                next_byte_offset: pos,
+            });
+        };
+        page_bytes += n;
+        pos += n;
+        line_no += 1;
+        lines.push(log_lines::decode_line(&buf));
+    }
+
+    // Anything left means more lines; count them while the budget lasts.
+    let mut scanned = 0u64;
+    let mut remaining = 0;
+    while scanned < MAX_LINE_COUNT_SCAN_BYTES {
+        match next_line(&mut buf)? {
+            Some(n) => {
+                scanned += n;
+                remaining += 1;
+            }
+            None => {
+                return Ok(LogHeadPage {
+                    lines,
+                    total_lines: total(line_no + remaining),
+                    has_more: remaining > 0,
+                    next_byte_offset: pos,
+                })
+            }
+        }
+    }
+    Ok(LogHeadPage {
+        lines,
+        total_lines: None,
+        has_more: true,
+        next_byte_offset: pos,
+    })
+}
+
+/// How often a log stream checks its file for appended lines.
+const LOG_STREAM_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Follows a log file by path, returning the lines appended since the last
+/// poll. When the file at `path` is replaced (rotation) or truncated below
+/// the read position, reading restarts from the top of the new file. A
+/// trailing line still missing its newline is held until it completes, up
+/// to `MAX_LOG_LINE_BYTES`.
+struct LogFollower {
+    path: PathBuf,
+    pos: u64,
+    file_id: Option<u64>,
+    decoder: log_lines::LineDecoder,
+}
+
+impl LogFollower {
+    /// Follow `path` from its current end (or from the start once it exists).
+    fn at_end(path: PathBuf) -> Self {
+        let meta = std::fs::metadata(&path).ok();
+        Self {
+            pos: meta.as_ref().map_or(0, std::fs::Metadata::len),
+            file_id: meta.as_ref().and_then(log_file_id),
+            path,
+            decoder: log_lines::LineDecoder::with_max_line_bytes(MAX_LOG_LINE_BYTES),
+        }
+    }
+
+    fn poll(&mut self) -> std::io::Result<Vec<String>> {
+        let mut file = match std::fs::File::open(&self.path) {
+            Ok(f) => f,
+            // Between rotation's rename and the daemon reopening the log
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let meta = file.metadata()?;
+        let file_id = log_file_id(&meta);
+        if file_id != self.file_id || meta.len() < self.pos {
+            self.file_id = file_id;
+            self.pos = 0;
+            self.decoder = log_lines::LineDecoder::with_max_line_bytes(MAX_LOG_LINE_BYTES);
+        }
+        if meta.len() == self.pos {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.pos))?;
+        let mut appended = file.take(meta.len() - self.pos);
+        let mut chunk = vec![0u8; log_lines::READ_CHUNK_BYTES];
+        let mut lines = Vec::new();
+        loop {
+            let n = match appended.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            self.pos += n as u64;
+            lines.extend(self.decoder.push(&chunk[..n]));
+        }
+        Ok(lines)
+    }
+}
+
+/// Identity of the file behind a path (its inode), so a rotated-in
+/// replacement is noticed even once it has grown past the old length.
+#[cfg(unix)]
+fn log_file_id(meta: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(meta.ino())
+}
+
+#[cfg(not(unix))]
+fn log_file_id(_meta: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+/// Add the paging fields shared by offset-paginated list responses to
+/// `body`: `total`, `limit`, `offset`, `has_more` (false once `offset +
+/// limit` reaches `total`), and `next_offset` / `prev_offset` (null on the
+/// last / first page).
+pub(crate) fn paginated(
+    mut body: serde_json::Value,
+    total: usize,
+    limit: usize,
+    offset: usize,
+) -> serde_json::Value {
+    let end = offset.saturating_add(limit);
+    let has_more = end < total;
+    body["total"] = serde_json::json!(total);
+    body["limit"] = serde_json::json!(limit);
+    body["offset"] = serde_json::json!(offset);
+    body["has_more"] = serde_json::json!(has_more);
+    body["next_offset"] = serde_json::json!(has_more.then_some(end));
+    body["prev_offset"] = serde_json::json!((offset > 0).then(|| offset.saturating_sub(limit)));
+    body
+}
+
+/// Shared state: the DB path, the routing rule cache and the instance port
+/// range. Each request opens its own connection.
 #[derive(Clone)]
 pub struct CpState {
     pub db_path: Arc<PathBuf>,
+    pub routing_rules: RoutingRuleCache,
+    pub port_range: PortRange,
+    /// Whether `X-Include-Secrets: true` may return unredacted payloads
+    /// (see [`ALLOW_SECRET_EXPORT_ENV`]).
+    pub allow_secret_export: bool,
+    /// Senders that may create their own routing rules with `ensure_rule`.
+    pub auto_authorize: messaging::AutoAuthorize,
+    /// Bounds on the logs endpoints.
+    pub log_limits: LogLimits,
+    /// Default and maximum message TTLs.
+    pub ttl_policy: messaging::TtlPolicy,
+    /// Secret key patterns and redaction mode for config masking and
+    /// payload redaction.
+    pub masking: Arc<SecretMasking>,
+}
+
+impl CpState {
+    /// State for the registry at `db_path`, with an empty routing rule cache,
+    /// the default port range, secret export disabled, no sender trusted
+    /// with `ensure_rule`, the default log limits and TTL policy, and
+    /// built-in secret patterns with full redaction.
+    pub fn new(db_path: impl Into<PathBuf>) -> Self {
+        Self {
+            db_path: Arc::new(db_path.into()),
+            routing_rules: RoutingRuleCache::default(),
+            port_range: PortRange::default(),
+            allow_secret_export: false,
+            auto_authorize: messaging::AutoAuthorize::default(),
+            log_limits: LogLimits::default(),
+            ttl_policy: messaging::TtlPolicy::default(),
+            masking: Arc::new(SecretMasking::default()),
+        }
+    }
+}
+
+/// What the control plane serves at `/` and other non-API paths.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum UiMode {
+    /// The dashboard compiled into the binary.
+    #[default]
+    Embedded,
+    /// An operator-supplied HTML file served for every path, or a directory
+    /// whose files are served by path, with its `index.html` for paths that
+    /// name no file (client-side routes). Re-read on every request; if
+    /// nothing can be read the embedded dashboard is served instead.
+    Path(PathBuf),
+    /// No UI: non-API paths return 404 (API-only deployments).
+    Disabled,
 }
 
-/// Serve the embedded SPA HTML.
-async fn handle_ui() -> Response<Body> {
+impl UiMode {
+    /// Mode from [`UI_ENABLED_ENV`] and [`UI_PATH_ENV`]; disabling wins over
+    /// a path, and neither set means `Embedded`.
+    pub fn from_env() -> Self {
+        let disabled = std::env::var(UI_ENABLED_ENV).is_ok_and(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "off" | "false" | "0"
+            )
+        });
+        if disabled {
+            return Self::Disabled;
+        }
+        match std::env::var(UI_PATH_ENV) {
+            Ok(path) if !path.trim().is_empty() => Self::Path(PathBuf::from(path.trim())),
+            _ => Self::Embedded,
+        }
+    }
+}
+
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+async fn handle_ui(ui: Arc<UiMode>, uri: Uri) -> Response<Body> {
+    let (body, content_type) = match ui.as_ref() {
+        UiMode::Embedded => (Body::from(INDEX_HTML), HTML_CONTENT_TYPE),
+        UiMode::Path(path) => match read_ui_file(path, uri.path()).await {
+            Ok((bytes, content_type)) => (Body::from(bytes), content_type),
+            Err(e) => {
+                tracing::warn!(
+                    "Cannot read UI from {}: {e}; serving embedded UI",
+                    path.display()
+                );
+                (Body::from(INDEX_HTML), HTML_CONTENT_TYPE)
+            }
+        },
+        UiMode::Disabled => {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+                .body(Body::from("Not Found"))
+                .unwrap()
+        }
+    };
     Response::builder()
         .status(StatusCode::OK)
-        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-        .body(Body::from(INDEX_HTML))
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body)
         .unwrap()
 }
 
-/// JSON 404 for unknown API paths. Keeps API errors as JSON, never HTML.
+/// What a [`UiMode::Path`] serves for `request_path`, with its content type.
+async fn read_ui_file(path: &Path, request_path: &str) -> std::io::Result<(Vec<u8>, &'static str)> {
+    if !tokio::fs::metadata(path).await?.is_dir() {
+        return Ok((tokio::fs::read(path).await?, HTML_CONTENT_TYPE));
+    }
+    if let Some(relative) = ui_asset_path(request_path) {
+        let file = path.join(relative);
+        if tokio::fs::metadata(&file).await.is_ok_and(|m| m.is_file()) {
+            let content_type = mime_guess::from_path(&file)
+                .first_raw()
+                .unwrap_or("application/octet-stream");
+            return Ok((tokio::fs::read(&file).await?, content_type));
+        }
+    }
+    Ok((
+        tokio::fs::read(path.join("index.html")).await?,
+        HTML_CONTENT_TYPE,
+    ))
+}
+
+/// `request_path` relative to the UI directory; `None` for `/` or a path
+/// that would climb out of it.
+fn ui_asset_path(request_path: &str) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for segment in request_path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            s if s.contains('\\') => return None,
+            s => relative.push(s),
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
 async fn handle_api_fallback() -> ApiResponse {
     err_json(StatusCode::NOT_FOUND, "Unknown API endpoint")
 }
 
-/// Build the axum router with all CP API routes and embedded UI.
+/// Prometheus scrape endpoint (`/metrics` and `/api/metrics`): the
+/// messaging counters plus gauges of registry state (messages and instances
+/// by status), from one [`MetricsSnapshot`] per scrape.
+async fn handle_metrics(State(state): State<CpState>) -> Response<Body> {
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> anyhow::Result<String> {
+        let registry = Registry::open(&db_path)?;
+        let snapshot = MetricsSnapshot::collect(MessagingMetrics::global(), &registry)?;
+        Ok(snapshot.render_prometheus())
+    })
+    .await;
+
+    let (status, body) = match result {
+        Ok(Ok(text)) => (StatusCode::OK, text),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to collect registry metrics: {e:#}");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to collect registry metrics\n".to_string(),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Task join error: {e}\n"),
+        ),
+    };
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; version=0.0.4")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+/// Build the axum router with all CP API routes and the UI selected by
+/// [`UiMode::from_env`].
 pub fn build_router(state: CpState) -> Router {
+    build_router_with_ui(state, UiMode::from_env())
+}
+
+/// Build the axum router with all CP API routes and the given UI.
+pub fn build_router_with_ui(state: CpState, ui: UiMode) -> Router {
     let api_router = Router::new()
         .route("/health", get(handle_health))
+        .route("/version", get(handle_version))
+        .route("/admin/workers", get(handle_admin_workers))
+        .route("/config/schema", get(handle_config_schema))
+        .route("/config/templates", get(handle_list_templates))
+        .route(
+            "/config/templates/:name",
+            get(handle_get_template)
+                .put(handle_put_template)
+                .delete(handle_delete_template),
+        )
         .route("/instances", get(handle_list_instances).post(handle_create_instance))
         .route("/instances/:name", get(handle_get_instance).delete(handle_delete_instance))
         .route("/instances/:name/archive", post(handle_archive))
@@ -127,14 +692,23 @@ pub fn build_router(state: CpState) -> Router {
         .route("/instances/:name/start", post(handle_start))
         .route("/instances/:name/stop", post(handle_stop))
         .route("/instances/:name/restart", post(handle_restart))
+        .route("/instances/:name/reload", post(handle_reload))
         .route("/instances/:name/logs", get(handle_logs))
         .route("/instances/:name/details", get(handle_details))
+        .route(
+            "/instances/:name/channels/validate",
+            post(handle_validate_channels),
+        )
         .route("/instances/:name/tasks", get(handle_tasks))
         .route("/instances/:name/usage", get(handle_usage))
+        .route("/instances/:name/activity", get(handle_instance_activity))
+        .route("/instances/:name/disk", get(handle_instance_disk))
         .route("/instances/:name/logs/download", get(handle_logs_download))
+        .route("/instances/:name/logs/stream", get(handle_logs_stream))
         .route(
             "/instances/:name/config",
             get(handle_config_get)
+                .head(handle_config_head)
                 .put(handle_config_put)
                 .patch(handle_config_patch),
         )
@@ -143,12 +717,36 @@ pub fn build_router(state: CpState) -> Router {
             post(handle_config_validate),
         )
         .route("/instances/:name/config/diff", post(handle_config_diff))
+        .route("/instances/:name/config/audit", get(handle_config_audit))
         .route(
             "/routing-rules",
             get(messaging::handle_list_rules).post(messaging::handle_create_rule),
         )
         .route("/routing-rules/:id", delete(messaging::handle_delete_rule))
+        .route("/routing/check", get(messaging::handle_routing_check))
         .route("/messages", post(messaging::handle_send_message))
+        .route("/messages/batch", post(messaging::handle_send_batch))
+        .route(
+            "/messages/broadcast",
+            post(messaging::handle_broadcast_message),
+        )
+        .route("/metrics", get(handle_metrics))
+        .route("/messages/stats", get(messaging::handle_message_stats))
+        .route("/messages/export", get(messaging::handle_export_messages))
+        .route("/messages/search", get(messaging::handle_search_messages))
+        .route(
+            "/correlations/:correlation_id",
+            get(messaging::handle_correlation_chain),
+        )
+        .route("/messages/purge", post(messaging::handle_purge_messages))
+        .route(
+            "/messages/events/stream",
+            get(messaging::handle_message_events_stream),
+        )
+        .route(
+            "/messages/throughput",
+            get(messaging::handle_message_throughput),
+        )
         .route(
             "/instances/:name/messages/pending",
             get(messaging::handle_receive_message),
@@ -157,6 +755,19 @@ pub fn build_router(state: CpState) -> Router {
             "/messages/:id/acknowledge",
             post(messaging::handle_acknowledge_message),
         )
+        .route(
+            "/messages/:id/expedite",
+            post(messaging::handle_expedite_message),
+        )
+        .route(
+            "/messages/:id/extend-lease",
+            post(messaging::handle_extend_lease),
+        )
+        .route("/messages/:id/nack", post(messaging::handle_nack_message))
+        .route(
+            "/messages/:id/forward",
+            post(messaging::handle_forward_message),
+        )
         // Setup wizard: workspace scaffold
         .route(
             "/instances/:name/scaffold",
@@ -208,6 +819,7 @@ pub fn build_router(state: CpState) -> Router {
             "/instances/:name/flows/audit",
             get(handle_flow_audit),
         )
+        .route("/flows/validate", post(handle_flows_validate))
         // Phase 15.5: Telegram observability endpoints
         .route(
             "/instances/:name/telegram/events",
@@ -223,10 +835,13 @@ pub fn build_router(state: CpState) -> Router {
         )
         .fallback(handle_api_fallback);
 
+    let ui = Arc::new(ui);
+    let fallback_ui = ui.clone();
     Router::new()
-        .route("/", get(handle_ui))
+        .route("/", get(move |uri: Uri| handle_ui(ui.clone(), uri)))
+        .route("/metrics", get(handle_metrics))
         .nest("/api", api_router)
-        .fallback(handle_ui)
+        .fallback(move |uri: Uri| handle_ui(fallback_ui.clone(), uri))
         .with_state(state)
 }
 
@@ -248,6 +863,19 @@ fn lifecycle_err_to_response(e: LifecycleError) -> ApiResponse {
         LifecycleError::AlreadyRunning(_) => err_json(StatusCode::CONFLICT, &e.to_string()),
         LifecycleError::NotRunning(_) => err_json(StatusCode::CONFLICT, &e.to_string()),
         LifecycleError::LockHeld => err_json(StatusCode::SERVICE_UNAVAILABLE, &e.to_string()),
+        LifecycleError::StartFailed {
+            name,
+            exit_status,
+            log_path,
+            log_tail,
+        } => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "error": format!("Daemon for '{name}' exited immediately ({exit_status})"),
+                "log_path": log_path,
+                "log_tail": log_tail,
+            })),
+        ),
         LifecycleError::Internal(_) => err_json(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
     }
 }
@@ -259,6 +887,50 @@ fn open_registry(db_path: &Path) -> Result<Registry, ApiResponse> {
     })
 }
 
+/// Next free gateway port in `range`; 503 naming the range when it is
+/// exhausted.
+fn allocate_instance_port(registry: &Registry, range: PortRange) -> Result<u16, ApiResponse> {
+    registry
+        .allocate_port(range.start..=range.end, &[])
+        .map_err(|e| match e {
+            PortAllocError::Exhausted { .. } => {
+                err_json(StatusCode::SERVICE_UNAVAILABLE, &e.to_string())
+            }
+            PortAllocError::Internal(e) => {
+                tracing::error!("Port allocation failed: {e:#}");
+                err_json(StatusCode::INTERNAL_SERVER_ERROR, "Failed to allocate port")
+            }
+        })
+}
+
+/// [`open_registry`] for read-heavy list/search endpoints: queries past the
+/// configured timeout are interrupted (see [`query_failed`]).
+fn open_registry_for_reads(db_path: &Path) -> Result<Registry, ApiResponse> {
+    let registry = open_registry(db_path)?;
+    let timeout_ms = SqliteTuning::global().query_timeout_ms;
+    if timeout_ms > 0 {
+        registry.set_query_timeout(Some(std::time::Duration::from_millis(timeout_ms)));
+    }
+    Ok(registry)
+}
+
+/// Error response for a failed query: 504 if it was interrupted by the
+/// query timeout, 500 otherwise.
+fn query_failed(e: &anyhow::Error, what: &str) -> ApiResponse {
+    if crate::db::is_query_timeout(e) {
+        tracing::warn!("{what}: query timed out");
+        return err_json(
+            StatusCode::GATEWAY_TIMEOUT,
+            &format!(
+                "{what}: query exceeded the {} ms timeout; narrow the filters",
+                SqliteTuning::global().query_timeout_ms
+            ),
+        );
+    }
+    tracing::error!("{what}: {e:#}");
+    err_json(StatusCode::INTERNAL_SERVER_ERROR, what)
+}
+
 // ── Phase 13.1: CRUD request bodies ─────────────────────────────
 
 #[derive(Deserialize)]
@@ -267,12 +939,19 @@ struct CreateInstanceBody {
     port: Option<u16>,
     model_provider: Option<String>,
     model_name: Option<String>,
+    /// Stored config template (see `/api/config/templates`) to start from.
+    template: Option<String>,
+    /// Config object merged over the template (or the defaults).
+    overrides: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize)]
 struct CloneInstanceBody {
     new_name: String,
     port: Option<u16>,
+    /// Copy only the source's skills; the clone starts from default config.
+    #[serde(default)]
+    skills_only: bool,
 }
 
 // ── Phase 13.1: helpers ─────────────────────────────────────────
@@ -343,6 +1022,37 @@ fn build_default_config(
     config
 }
 
+/// [`build_default_config`] with each layer (a template, then caller
+/// overrides) merged on top in order. The port, provider and model passed
+/// here win over the layers: the registry owns the port, and explicit
+/// create arguments are more specific than a shared template.
+fn build_layered_config(
+    port: u16,
+    provider: Option<&str>,
+    model: Option<&str>,
+    layers: &[&serde_json::Value],
+) -> Result<crate::config::Config, String> {
+    let base = build_default_config(port, provider, model);
+    if layers.is_empty() {
+        return Ok(base);
+    }
+    let mut toml_text =
+        toml::to_string(&base).map_err(|e| format!("Failed to serialize config: {e}"))?;
+    for layer in layers {
+        toml_text = apply_json_patch_to_toml(&toml_text, layer)?;
+    }
+    let mut config: crate::config::Config =
+        toml::from_str(&toml_text).map_err(|e| format!("Merged config is invalid: {e}"))?;
+    config.gateway.port = port;
+    if let Some(p) = provider {
+        config.default_provider = Some(p.to_string());
+    }
+    if let Some(m) = model {
+        config.default_model = Some(m.to_string());
+    }
+    Ok(config)
+}
+
 // ── Instance serialization ───────────────────────────────────────
 
 fn instance_to_json(
@@ -359,25 +1069,104 @@ fn instance_to_json(
         "config_path": inst.config_path,
         "workspace_dir": inst.workspace_dir,
         "archived_at": inst.archived_at,
+        "started_at": inst.started_at,
+        "uptime_secs": uptime_secs(inst, live_status),
+        "restart_count": inst.restart_count,
     })
 }
 
+/// Registry columns that disagree with what the daemon would resolve from
+/// its config file: an explicit `gateway.port` other than the registry port,
+/// or a registry `workspace_dir` other than `workspace/` beside the config.
+fn config_drift(inst: &crate::db::Instance, raw_toml: &str) -> Vec<serde_json::Value> {
+    let mut drift = Vec::new();
+    if let Some(port) = explicit_gateway_port(raw_toml) {
+        if port != inst.port {
+            drift.push(serde_json::json!({
+                "field": "port",
+                "registry": inst.port,
+                "config": port,
+            }));
+        }
+    }
+    let config_workspace = Path::new(&inst.config_path)
+        .parent()
+        .map(|dir| dir.join("workspace"));
+    if let (Some(registry_ws), Some(config_ws)) = (&inst.workspace_dir, config_workspace) {
+        if Path::new(registry_ws) != config_ws {
+            drift.push(serde_json::json!({
+                "field": "workspace_dir",
+                "registry": registry_ws,
+                "config": config_ws.display().to_string(),
+            }));
+        }
+    }
+    drift
+}
+
+/// Seconds since the last start, only while the daemon is actually running.
+fn uptime_secs(inst: &crate::db::Instance, live_status: &str) -> Option<i64> {
+    if live_status != "running" {
+        return None;
+    }
+    let started =
+        chrono::NaiveDateTime::parse_from_str(inst.started_at.as_deref()?, "%Y-%m-%d %H:%M:%S")
+            .ok()?;
+    Some((chrono::Utc::now().naive_utc() - started).num_seconds().max(0))
+}
+
+/// Age in seconds of the oldest still-queued message, if any.
+fn oldest_queued_age_secs(depth: &crate::db::QueueDepth) -> Option<i64> {
+    secs_since(depth.oldest_queued_at.as_deref()?)
+}
+
+/// Seconds elapsed since a registry timestamp (`%Y-%m-%d %H:%M:%S`, UTC).
+fn secs_since(ts: &str) -> Option<i64> {
+    let then = chrono::NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S").ok()?;
+    Some((chrono::Utc::now().naive_utc() - then).num_seconds().max(0))
+}
+
 // ── Handlers ─────────────────────────────────────────────────────
 
+/// Build identity of this control plane. Clients check this before relying
+/// on version-sensitive behaviour (e.g. config schema migrations).
+async fn handle_version() -> ApiResponse {
+    let build_timestamp = env!("ZEROCLAW_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|ts| ts.to_rfc3339());
+    ok_json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_commit": env!("ZEROCLAW_GIT_COMMIT"),
+        "build_timestamp": build_timestamp,
+        "config_schema_version": crate::config::migrations::CURRENT_SCHEMA_VERSION,
+    }))
+}
+
 async fn handle_health(State(state): State<CpState>) -> impl IntoResponse {
     let db_path = state.db_path.clone();
     let result = tokio::task::spawn_blocking(move || -> Result<serde_json::Value, String> {
         let registry = Registry::open(&db_path).map_err(|e| format!("{e:#}"))?;
         let instances = registry.list_instances().map_err(|e| format!("{e:#}"))?;
+        let names: Vec<&str> = instances.iter().map(|i| i.name.as_str()).collect();
+        let depths = registry.queue_depth_for(&names).map_err(|e| format!("{e:#}"))?;
 
         let mut instance_map = serde_json::Map::new();
         for inst in &instances {
             let inst_dir = lifecycle::instance_dir_from(inst);
             let (status, pid) =
                 lifecycle::live_status(&inst_dir).unwrap_or(("unknown".to_string(), None));
+            let depth = depths.get(&inst.name).cloned().unwrap_or_default();
             instance_map.insert(
                 inst.name.clone(),
-                serde_json::json!({ "status": status, "pid": pid }),
+                serde_json::json!({
+                    "status": status,
+                    "pid": pid,
+                    "queue_depth": depth.depth,
+                    "leasable": depth.leasable,
+                    "oldest_queued_age_secs": oldest_queued_age_secs(&depth),
+                }),
             );
         }
 
@@ -398,6 +1187,36 @@ async fn handle_health(State(state): State<CpState>) -> impl IntoResponse {
     }
 }
 
+/// JSON Schema for instance configs, with secret fields marked, for
+/// client-side validation and redaction hints.
+async fn handle_config_schema() -> ApiResponse {
+    ok_json(crate::config::json_schema::json_schema())
+}
+
+/// Last run, last error and items processed for each background loop, so a
+/// stalled reaper or supervisor is visible. `consecutive_failures > 0` means
+/// the loop is currently failing and backing off.
+async fn handle_admin_workers() -> ApiResponse {
+    let workers: serde_json::Map<String, serde_json::Value> = WorkerStatusBoard::global()
+        .snapshot()
+        .into_iter()
+        .map(|(name, status)| {
+            (
+                name,
+                serde_json::json!({
+                    "last_run_at": status.last_run_at,
+                    "last_success_at": status.last_success_at,
+                    "last_error": status.last_error,
+                    "last_error_at": status.last_error_at,
+                    "items_processed": status.items_processed,
+                    "consecutive_failures": status.consecutive_failures,
+                }),
+            )
+        })
+        .collect();
+    ok_json(serde_json::json!({ "workers": workers }))
+}
+
 #[derive(Deserialize)]
 struct ListInstancesQuery {
     include_archived: Option<bool>,
@@ -424,7 +1243,14 @@ async fn handle_list_instances(
                 let inst_dir = lifecycle::instance_dir_from(inst);
                 lifecycle::live_status(&inst_dir).unwrap_or(("unknown".to_string(), None))
             };
-            list.push(instance_to_json(inst, &status, pid));
+            let mut entry = instance_to_json(inst, &status, pid);
+            // Null when the config file can't be read
+            entry["config_drift"] = std::fs::read_to_string(&inst.config_path)
+                .ok()
+                .map_or(serde_json::Value::Null, |raw| {
+                    serde_json::json!(config_drift(inst, &raw))
+                });
+            list.push(entry);
         }
 
         Ok(serde_json::json!(list))
@@ -561,6 +1387,49 @@ async fn handle_restart(
     }
 }
 
+/// POST /api/instances/:name/reload — ask the daemon to re-read its config
+/// (SIGHUP) without dropping in-flight work. When the daemon cannot reload,
+/// nothing is sent and a restart is recommended instead.
+async fn handle_reload(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+) -> impl IntoResponse {
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let registry = match open_registry(&db_path) {
+            Ok(r) => r,
+            Err(resp) => return resp,
+        };
+        match lifecycle::reload_instance(&registry, &name) {
+            Ok(ReloadOutcome::Signaled { pid }) => ok_json(serde_json::json!({
+                "status": "reload_requested",
+                "name": name,
+                "pid": pid,
+                "acknowledged": true,
+                "restart_recommended": false,
+            })),
+            Ok(ReloadOutcome::Unsupported { pid }) => ok_json(serde_json::json!({
+                "status": "reload_unsupported",
+                "name": name,
+                "pid": pid,
+                "acknowledged": false,
+                "restart_recommended": true,
+                "message": "Daemon does not handle SIGHUP; restart the instance to apply config changes",
+            })),
+            Err(e) => lifecycle_err_to_response(e),
+        }
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
 // ── Phase 13.1: CRUD handlers ───────────────────────────────────
 
 async fn handle_create_instance(
@@ -570,14 +1439,48 @@ async fn handle_create_instance(
     if let Err(msg) = validate_instance_name(&body.name) {
         return err_json(StatusCode::BAD_REQUEST, &msg);
     }
+    if let Some(template) = &body.template {
+        if let Err(msg) = validate_instance_name(template) {
+            return err_json(
+                StatusCode::BAD_REQUEST,
+                &format!("Invalid template name: {msg}"),
+            );
+        }
+    }
+    if let Some(overrides) = &body.overrides {
+        if !overrides.is_object() {
+            return err_json(StatusCode::BAD_REQUEST, "overrides must be a JSON object");
+        }
+        if let Err(msg) = reject_masked_sentinels(overrides) {
+            return err_json(StatusCode::BAD_REQUEST, &msg);
+        }
+    }
 
     let db_path = state.db_path.clone();
+    let port_range = state.port_range;
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
             Ok(r) => r,
             Err(resp) => return resp,
         };
 
+        let template = match body.template.as_deref() {
+            None => None,
+            Some(template) => match read_config_template(&db_path, template) {
+                Ok(Some(value)) => Some(value),
+                Ok(None) => {
+                    return err_json(
+                        StatusCode::NOT_FOUND,
+                        &format!("No config template named '{template}'"),
+                    )
+                }
+                Err(msg) => {
+                    tracing::error!("{msg}");
+                    return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg);
+                }
+            },
+        };
+
         // Check for name conflict
         match registry.get_instance_by_name(&body.name) {
             Ok(Some(_)) => {
@@ -597,47 +1500,41 @@ async fn handle_create_instance(
         let port = if let Some(p) = body.port {
             p
         } else {
-            match registry.allocate_port_with_excludes(18801, 18999, &[]) {
-                Ok(Some(p)) => p,
-                Ok(None) => {
-                    return err_json(
-                        StatusCode::SERVICE_UNAVAILABLE,
-                        "No ports available in range 18801-18999",
-                    )
-                }
-                Err(e) => {
-                    tracing::error!("Port allocation failed: {e:#}");
-                    return err_json(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to allocate port",
-                    );
-                }
+            match allocate_instance_port(&registry, port_range) {
+                Ok(p) => p,
+                Err(resp) => return resp,
             }
         };
 
+        // Build config: defaults, then template, then overrides
+        let layers: Vec<&serde_json::Value> =
+            template.iter().chain(body.overrides.as_ref()).collect();
+        let mut config = match build_layered_config(
+            port,
+            body.model_provider.as_deref(),
+            body.model_name.as_deref(),
+            &layers,
+        ) {
+            Ok(c) => c,
+            Err(msg) => return err_json(StatusCode::BAD_REQUEST, &msg),
+        };
+
         let id = uuid::Uuid::new_v4().to_string();
         let instances_dir = instances_dir_from_db(&db_path);
         let inst_dir = instances_dir.join(&id);
 
         // Create instance directory + workspace subdirs
         let workspace_dir = inst_dir.join("workspace");
-        for subdir in &["skills", "memory", "sessions", "state", "cron"] {
-            if let Err(e) = std::fs::create_dir_all(workspace_dir.join(subdir)) {
-                tracing::error!("Failed to create workspace dir: {e}");
-                let _ = std::fs::remove_dir_all(&inst_dir);
-                return err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to create workspace directories",
-                );
-            }
+        if let Err(e) = crate::config::workspace::create_workspace_subdirs(&workspace_dir) {
+            tracing::error!("{e:#}");
+            let _ = std::fs::remove_dir_all(&inst_dir);
+            return err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create workspace directories",
+            );
         }
 
-        // Build and write config
-        let mut config = build_default_config(
-            port,
-            body.model_provider.as_deref(),
-            body.model_name.as_deref(),
-        );
+        // Write config
         let config_path = inst_dir.join("config.toml");
         config.config_path = config_path.clone();
         config.workspace_dir = workspace_dir.clone();
@@ -697,6 +1594,7 @@ async fn handle_create_instance(
                 "name": body.name,
                 "port": port,
                 "status": "stopped",
+                "template": body.template,
             })),
         )
     })
@@ -711,10 +1609,27 @@ async fn handle_create_instance(
     }
 }
 
+#[derive(Deserialize)]
+struct ArchiveQuery {
+    /// `cancel`: cancel messages still pending for the instance.
+    drain: Option<String>,
+}
+
 async fn handle_archive(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
+    Query(query): Query<ArchiveQuery>,
 ) -> impl IntoResponse {
+    let cancel_pending = match query.drain.as_deref() {
+        None => false,
+        Some("cancel") => true,
+        Some(other) => {
+            return err_json(
+                StatusCode::BAD_REQUEST,
+                &format!("Unknown drain mode '{other}' (expected 'cancel')"),
+            )
+        }
+    };
     let db_path = state.db_path.clone();
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
@@ -725,10 +1640,18 @@ async fn handle_archive(
         let instance = match registry.get_instance_by_name(&name) {
             Ok(Some(inst)) => inst,
             Ok(None) => {
-                return err_json(
-                    StatusCode::NOT_FOUND,
-                    &format!("No active instance named '{name}'"),
-                )
+                // Re-archiving is a no-op so automation can safely retry.
+                return match registry.find_archived_instance_by_name(&name) {
+                    Ok(Some(_)) => already_archived_response(&name),
+                    Ok(None) => err_json(
+                        StatusCode::NOT_FOUND,
+                        &format!("No instance named '{name}'"),
+                    ),
+                    Err(e) => {
+                        tracing::error!("Failed to query archived instance: {e:#}");
+                        err_json(StatusCode::INTERNAL_SERVER_ERROR, "Failed to query instance")
+                    }
+                };
             }
             Err(e) => {
                 tracing::error!("Failed to query instance: {e:#}");
@@ -747,10 +1670,42 @@ async fn handle_archive(
             }
         }
 
-        // Archive
-        match registry.archive_instance(&instance.id) {
-            Ok(true) => ok_json(serde_json::json!({ "status": "archived", "name": name })),
-            Ok(false) => err_json(StatusCode::NOT_FOUND, &format!("Instance '{name}' not found")),
+        // Archive, cancelling pending messages in the same transaction
+        let archived = if cancel_pending {
+            registry
+                .archive_instance_cancelling_messages(&instance.id, &name, "recipient_archived")
+                .map(|(outcome, count)| (outcome, Some(count)))
+        } else {
+            registry
+                .archive_instance(&instance.id)
+                .map(|outcome| (outcome, None))
+        };
+        match archived {
+            Ok((ArchiveOutcome::Archived, cancelled)) => {
+                lifecycle::webhooks::notify(LifecycleEvent::Archive, &instance);
+                let mut response = serde_json::json!({ "status": "archived", "name": name });
+                if let Some(count) = cancelled {
+                    response["cancelled_messages"] = serde_json::json!(count);
+                } else {
+                    let pending = registry
+                        .queue_depth_for(&[name.as_str()])
+                        .ok()
+                        .and_then(|mut depths| depths.remove(&name))
+                        .map_or(0, |d| d.depth);
+                    if pending > 0 {
+                        response["pending_messages"] = serde_json::json!(pending);
+                        response["warnings"] = serde_json::json!([format!(
+                            "{pending} message(s) still pending for '{name}' will expire \
+                             undelivered; archive with ?drain=cancel to cancel them"
+                        )]);
+                    }
+                }
+                ok_json(response)
+            }
+            Ok((ArchiveOutcome::AlreadyArchived, _)) => already_archived_response(&name),
+            Ok((ArchiveOutcome::NotFound, _)) => {
+                err_json(StatusCode::NOT_FOUND, &format!("Instance '{name}' not found"))
+            }
             Err(e) => {
                 tracing::error!("Failed to archive instance: {e:#}");
                 err_json(
@@ -771,6 +1726,22 @@ async fn handle_archive(
     }
 }
 
+fn already_archived_response(name: &str) -> ApiResponse {
+    ok_json(serde_json::json!({
+        "status": "archived",
+        "name": name,
+        "already_archived": true,
+    }))
+}
+
+fn already_active_response(name: &str) -> ApiResponse {
+    ok_json(serde_json::json!({
+        "status": "active",
+        "name": name,
+        "already_active": true,
+    }))
+}
+
 async fn handle_unarchive(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
@@ -782,13 +1753,21 @@ async fn handle_unarchive(
             Err(resp) => return resp,
         };
 
-        // Check no active instance with this name
+        // An active instance with this name is either the one being asked
+        // for (a no-op) or a different one holding the name (a conflict).
         match registry.get_instance_by_name(&name) {
             Ok(Some(_)) => {
-                return err_json(
-                    StatusCode::CONFLICT,
-                    &format!("Active instance named '{name}' already exists"),
-                )
+                return match registry.find_archived_instance_by_name(&name) {
+                    Ok(Some(_)) => err_json(
+                        StatusCode::CONFLICT,
+                        &format!("Active instance named '{name}' already exists"),
+                    ),
+                    Ok(None) => already_active_response(&name),
+                    Err(e) => {
+                        tracing::error!("Failed to query archived instance: {e:#}");
+                        err_json(StatusCode::INTERNAL_SERVER_ERROR, "Failed to query instance")
+                    }
+                };
             }
             Ok(None) => {}
             Err(e) => {
@@ -798,8 +1777,11 @@ async fn handle_unarchive(
         }
 
         match registry.unarchive_instance(&name) {
-            Ok(true) => ok_json(serde_json::json!({ "status": "active", "name": name })),
-            Ok(false) => err_json(
+            Ok(UnarchiveOutcome::Unarchived) => {
+                ok_json(serde_json::json!({ "status": "active", "name": name }))
+            }
+            Ok(UnarchiveOutcome::AlreadyActive) => already_active_response(&name),
+            Ok(UnarchiveOutcome::NotFound) => err_json(
                 StatusCode::NOT_FOUND,
                 &format!("No archived instance named '{name}'"),
             ),
@@ -833,6 +1815,7 @@ async fn handle_clone_instance(
     }
 
     let db_path = state.db_path.clone();
+    let port_range = state.port_range;
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
             Ok(r) => r,
@@ -873,21 +1856,9 @@ async fn handle_clone_instance(
         let port = if let Some(p) = body.port {
             p
         } else {
-            match registry.allocate_port_with_excludes(18801, 18999, &[]) {
-                Ok(Some(p)) => p,
-                Ok(None) => {
-                    return err_json(
-                        StatusCode::SERVICE_UNAVAILABLE,
-                        "No ports available in range 18801-18999",
-                    )
-                }
-                Err(e) => {
-                    tracing::error!("Port allocation failed: {e:#}");
-                    return err_json(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "Failed to allocate port",
-                    );
-                }
+            match allocate_instance_port(&registry, port_range) {
+                Ok(p) => p,
+                Err(resp) => return resp,
             }
         };
 
@@ -895,26 +1866,30 @@ async fn handle_clone_instance(
         let instances_dir = instances_dir_from_db(&db_path);
         let new_inst_dir = instances_dir.join(&new_id);
 
-        // Read and parse source config
-        let source_config_str = match std::fs::read_to_string(&source.config_path) {
-            Ok(s) => s,
-            Err(e) => {
-                tracing::error!("Failed to read source config: {e}");
-                return err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to read source config",
-                );
-            }
-        };
+        // Read and parse source config, unless only skills are cloned
+        let mut config: crate::config::Config = if body.skills_only {
+            build_default_config(port, None, None)
+        } else {
+            let source_config_str = match std::fs::read_to_string(&source.config_path) {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::error!("Failed to read source config: {e}");
+                    return err_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to read source config",
+                    );
+                }
+            };
 
-        let mut config: crate::config::Config = match toml::from_str(&source_config_str) {
-            Ok(c) => c,
-            Err(e) => {
-                tracing::error!("Failed to parse source config: {e}");
-                return err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to parse source config",
-                );
+            match toml::from_str(&source_config_str) {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::error!("Failed to parse source config: {e}");
+                    return err_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to parse source config",
+                    );
+                }
             }
         };
 
@@ -928,30 +1903,41 @@ async fn handle_clone_instance(
         config.workspace_dir = new_workspace.clone();
 
         // Create workspace subdirs
-        for subdir in &["skills", "memory", "sessions", "state", "cron"] {
-            if let Err(e) = std::fs::create_dir_all(new_workspace.join(subdir)) {
-                tracing::error!("Failed to create workspace dir: {e}");
-                let _ = std::fs::remove_dir_all(&new_inst_dir);
-                return err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to create workspace directories",
-                );
-            }
+        if let Err(e) = crate::config::workspace::create_workspace_subdirs(&new_workspace) {
+            tracing::error!("{e:#}");
+            let _ = std::fs::remove_dir_all(&new_inst_dir);
+            return err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to create workspace directories",
+            );
         }
 
-        // Copy skills from source if they exist
+        // Copy skills from source if they exist. A failed copy does not
+        // fail the clone; it is listed in the manifest and the warnings.
+        let mut skills_copied = Vec::new();
+        let mut skills_failed = Vec::new();
+        let mut warnings = Vec::new();
         if let Some(ref src_ws) = source.workspace_dir {
-            let src_skills = PathBuf::from(src_ws).join("skills");
-            let dst_skills = new_workspace.join("skills");
+            let src_ws = PathBuf::from(src_ws);
+            let src_skills = src_ws.join("skills");
             if src_skills.is_dir() {
-                if let Ok(entries) = std::fs::read_dir(&src_skills) {
-                    for entry in entries.flatten() {
-                        let src_path = entry.path();
-                        let dst_path = dst_skills.join(entry.file_name());
-                        if src_path.is_dir() {
-                            let _ = copy_dir_recursive(&src_path, &dst_path);
-                        } else {
-                            let _ = std::fs::copy(&src_path, &dst_path);
+                for (path, result) in copy_dir_recursive(&src_skills, &new_workspace.join("skills"))
+                {
+                    // Relative to the workspace, e.g. "skills/deploy/SKILL.md"
+                    let rel = path
+                        .strip_prefix(&src_ws)
+                        .unwrap_or(&path)
+                        .display()
+                        .to_string();
+                    match result {
+                        Ok(()) => skills_copied.push(rel),
+                        Err(e) => {
+                            tracing::warn!(
+                                "Failed to copy {rel} for clone '{}': {e}",
+                                body.new_name
+                            );
+                            warnings.push(format!("Failed to copy {rel}: {e}"));
+                            skills_failed.push(rel);
                         }
                     }
                 }
@@ -1015,6 +2001,12 @@ async fn handle_clone_instance(
                 "port": port,
                 "cloned_from": name,
                 "status": "stopped",
+                "skills_only": body.skills_only,
+                "manifest": {
+                    "skills_copied": skills_copied,
+                    "skills_failed": skills_failed,
+                },
+                "warnings": warnings,
             })),
         )
     })
@@ -1034,6 +2026,7 @@ async fn handle_delete_instance(
     AxumPath(name): AxumPath<String>,
 ) -> impl IntoResponse {
     let db_path = state.db_path.clone();
+    let routing_rules = state.routing_rules.clone();
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
             Ok(r) => r,
@@ -1042,6 +2035,8 @@ async fn handle_delete_instance(
 
         match registry.delete_archived_instance(&name) {
             Ok(Some(inst)) => {
+                // Its routing rules were deleted with it
+                routing_rules.invalidate();
                 // Remove filesystem artifacts
                 let inst_dir = lifecycle::instance_dir_from(&inst);
                 if inst_dir.exists() {
@@ -1084,20 +2079,42 @@ async fn handle_delete_instance(
     }
 }
 
-/// Recursively copy a directory.
-fn copy_dir_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
-    std::fs::create_dir_all(dst)?;
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
+/// Recursively copy a directory, returning one result per file (keyed by
+/// source path, sorted). A directory that cannot be created or listed is
+/// reported once under its own path and its contents are skipped; other
+/// files are still copied.
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Vec<(PathBuf, std::io::Result<()>)> {
+    let mut results = Vec::new();
+    copy_dir_into(src, dst, &mut results);
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    results
+}
+
+fn copy_dir_into(src: &Path, dst: &Path, results: &mut Vec<(PathBuf, std::io::Result<()>)>) {
+    let entries = match std::fs::create_dir_all(dst).and_then(|()| std::fs::read_dir(src)) {
+        Ok(entries) => entries,
+        Err(e) => {
+            results.push((src.to_path_buf(), Err(e)));
+            return;
+        }
+    };
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                results.push((src.to_path_buf(), Err(e)));
+                continue;
+            }
+        };
         let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
         if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
+            copy_dir_into(&src_path, &dst_path, results);
         } else {
-            std::fs::copy(&src_path, &dst_path)?;
+            let result = std::fs::copy(&src_path, &dst_path).map(|_| ());
+            results.push((src_path, result));
         }
     }
-    Ok(())
 }
 
 // ── Logs (enhanced with pagination modes) ────────────────────────
@@ -1107,12 +2124,17 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
 struct LogsQuery {
     lines: Option<usize>,
     offset: Option<usize>,
+    /// Absolute 0-based line to start at; `head` mode only.
+    from_line: Option<usize>,
+    /// Byte offset to start reading at (a previous page's
+    /// `next_byte_offset`); `head` mode only. `from_line` counts from here.
+    byte_offset: Option<u64>,
     mode: Option<String>,
+    /// `text` (default): lines as strings. `json`: each line parsed into a
+    /// structured entry (see `parse_log_line`).
+    format: Option<String>,
 }
 
-/// Maximum number of log lines returnable.
-const MAX_LOG_LINES: usize = 10_000;
-
 async fn handle_logs(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
@@ -1121,20 +2143,44 @@ async fn handle_logs(
     let mode = query.mode.as_deref().unwrap_or("tail");
 
     // Validate mode
-    if mode != "tail" && mode != "page" {
+    if !["tail", "page", "head"].contains(&mode) {
+        return err_json(
+            StatusCode::BAD_REQUEST,
+            &format!("Invalid mode: '{mode}'. Valid values: tail, page, head"),
+        );
+    }
+    if query.from_line.is_some() && mode != "head" {
         return err_json(
             StatusCode::BAD_REQUEST,
-            &format!("Invalid mode: '{mode}'. Valid values: tail, page"),
+            "from_line is only supported with mode=head",
         );
     }
+    if query.byte_offset.is_some() && mode != "head" {
+        return err_json(
+            StatusCode::BAD_REQUEST,
+            "byte_offset is only supported with mode=head",
+        );
+    }
+    let format = query.format.as_deref().unwrap_or("text");
+    if !["text", "json"].contains(&format) {
+        return err_json(
+            StatusCode::BAD_REQUEST,
+            &format!("Invalid format: '{format}'. Valid values: text, json"),
+        );
+    }
+    let structured = format == "json";
 
     let db_path = state.db_path.clone();
+    let limits = state.log_limits;
     let lines_count = query
         .lines
         .unwrap_or(lifecycle::DEFAULT_LOG_LINES)
-        .min(MAX_LOG_LINES);
+        .min(limits.max_lines);
     let offset = query.offset.unwrap_or(0);
+    let from_line = query.from_line.unwrap_or(0);
+    let byte_offset = query.byte_offset.unwrap_or(0);
     let mode = mode.to_string();
+    let format = format.to_string();
 
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
@@ -1167,19 +2213,49 @@ async fn handle_logs(
                 "lines": [],
                 "name": name,
                 "mode": mode,
+                "format": format,
+                "limits": limits.to_json(),
             }));
         }
 
-        if mode == "page" {
-            match read_lines_paginated(&log_file, offset, lines_count) {
+        if mode == "head" {
+            match read_lines_from_start(
+                &log_file,
+                byte_offset,
+                from_line,
+                lines_count,
+                limits.tail_bytes,
+            ) {
+                Ok(page) => ok_json(serde_json::json!({
+                    "lines": log_lines_json(page.lines, structured),
+                    "name": name,
+                    "mode": "head",
+                    "format": format,
+                    "from_line": from_line,
+                    "byte_offset": byte_offset,
+                    "next_byte_offset": page.next_byte_offset,
+                    "total_lines": page.total_lines,
+                    "total_unknown": page.total_lines.is_none(),
+                    "has_more": page.has_more,
+                    "limits": limits.to_json(),
+                })),
+                Err(e) => {
+                    tracing::error!("Failed to read log file: {e}");
+                    err_json(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read log file")
+                }
+            }
+        } else if mode == "page" {
+            match read_lines_paginated(&log_file, offset, lines_count, limits.tail_bytes) {
                 Ok((lines, window_lines, has_more, truncated)) => ok_json(serde_json::json!({
-                    "lines": lines,
+                    "lines": log_lines_json(lines, structured),
                     "name": name,
                     "mode": "page",
+                    "format": format,
                     "offset": offset,
                     "window_lines": window_lines,
                     "has_more": has_more,
                     "truncated": truncated,
+                    "limits": limits.to_json(),
                 })),
                 Err(e) => {
                     tracing::error!("Failed to read log file: {e}");
@@ -1187,11 +2263,13 @@ async fn handle_logs(
                 }
             }
         } else {
-            match read_last_n_lines(&log_file, lines_count) {
+            match log_lines::read_last_n_lines(&log_file, lines_count, limits.tail_bytes) {
                 Ok(tail) => ok_json(serde_json::json!({
-                    "lines": tail,
+                    "lines": log_lines_json(tail, structured),
                     "name": name,
                     "mode": "tail",
+                    "format": format,
+                    "limits": limits.to_json(),
                 })),
                 Err(e) => {
                     tracing::error!("Failed to read log file: {e}");
@@ -1213,6 +2291,26 @@ async fn handle_logs(
 
 // ── Logs download (streamed) ─────────────────────────────────────
 
+/// Directory of the active instance called `name`, or the error response
+/// for an unknown instance or failed lookup.
+fn instance_dir_by_name(db_path: &Path, name: &str) -> Result<PathBuf, ApiResponse> {
+    let registry = open_registry(db_path)?;
+    match registry.get_instance_by_name(name) {
+        Ok(Some(inst)) => Ok(lifecycle::instance_dir_from(&inst)),
+        Ok(None) => Err(err_json(
+            StatusCode::NOT_FOUND,
+            &format!("No instance named '{name}'"),
+        )),
+        Err(e) => {
+            tracing::error!("Failed to query instance: {e:#}");
+            Err(err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to query instance",
+            ))
+        }
+    }
+}
+
 async fn handle_logs_download(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
@@ -1221,28 +2319,8 @@ async fn handle_logs_download(
 
     let db_path = state.db_path.clone();
 
-    // Look up instance dir in a blocking task
-    let lookup = tokio::task::spawn_blocking(move || -> Result<PathBuf, ApiResponse> {
-        let registry = match open_registry(&db_path) {
-            Ok(r) => r,
-            Err(resp) => return Err(resp),
-        };
-        match registry.get_instance_by_name(&name) {
-            Ok(Some(inst)) => Ok(lifecycle::instance_dir_from(&inst)),
-            Ok(None) => Err(err_json(
-                StatusCode::NOT_FOUND,
-                &format!("No instance named '{name}'"),
-            )),
-            Err(e) => {
-                tracing::error!("Failed to query instance: {e:#}");
-                Err(err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to query instance",
-                ))
-            }
-        }
-    })
-    .await;
+    // Look up instance dir in a blocking task
+    let lookup = tokio::task::spawn_blocking(move || instance_dir_by_name(&db_path, &name)).await;
 
     let inst_dir = match lookup {
         Ok(Ok(p)) => p,
@@ -1333,6 +2411,120 @@ async fn handle_logs_download(
         .unwrap()
 }
 
+// ── Logs stream (WebSocket) ──────────────────────────────────────
+
+#[derive(Deserialize)]
+struct LogsStreamQuery {
+    /// Backlog lines sent before following the file.
+    lines: Option<usize>,
+}
+
+/// Live log tail: sends the last `lines` lines, then each line appended to
+/// the log as a text frame, following the file across rotations.
+async fn handle_logs_stream(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+    Query(query): Query<LogsStreamQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let db_path = state.db_path.clone();
+    let lookup = tokio::task::spawn_blocking(move || instance_dir_by_name(&db_path, &name)).await;
+    let inst_dir = match lookup {
+        Ok(Ok(p)) => p,
+        Ok(Err(resp)) => return resp.into_response(),
+        Err(e) => {
+            return err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Task join error: {e}"),
+            )
+            .into_response()
+        }
+    };
+
+    let limits = state.log_limits;
+    let lines = query
+        .lines
+        .unwrap_or(lifecycle::DEFAULT_LOG_LINES)
+        .min(limits.max_lines);
+    let log_file = lifecycle::log_path(&inst_dir);
+    ws.on_upgrade(move |socket| stream_log(socket, log_file, lines, limits.tail_bytes))
+}
+
+async fn stream_log(mut socket: WebSocket, log_file: PathBuf, lines: usize, tail_bytes: u64) {
+    let start = tokio::task::spawn_blocking(move || {
+        let backlog = if log_file.exists() {
+            log_lines::read_last_n_lines(&log_file, lines, tail_bytes)
+        } else {
+            Ok(Vec::new())
+        };
+        (LogFollower::at_end(log_file), backlog)
+    })
+    .await;
+    let (mut follower, backlog) = match start {
+        Ok((follower, Ok(backlog))) => (follower, backlog),
+        Ok((_, Err(e))) => {
+            tracing::error!("Failed to read log file: {e}");
+            let _ = socket.close().await;
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Log stream task failed: {e}");
+            let _ = socket.close().await;
+            return;
+        }
+    };
+    for line in backlog {
+        if socket.send(Message::Text(line)).await.is_err() {
+            return;
+        }
+    }
+
+    let mut poll = tokio::time::interval(LOG_STREAM_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            incoming = socket.recv() => match incoming {
+                // Pings are answered by the socket; other client frames are ignored
+                Some(Ok(Message::Close(_))) => {
+                    let _ = socket.close().await;
+                    return;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => return,
+            },
+            _ = poll.tick() => {
+                let polled = tokio::task::spawn_blocking(move || {
+                    let appended = follower.poll();
+                    (follower, appended)
+                })
+                .await;
+                let appended = match polled {
+                    Ok((f, appended)) => {
+                        follower = f;
+                        appended
+                    }
+                    Err(e) => {
+                        tracing::error!("Log stream task failed: {e}");
+                        return;
+                    }
+                };
+                let appended = match appended {
+                    Ok(appended) => appended,
+                    Err(e) => {
+                        tracing::error!("Failed to read log file: {e}");
+                        let _ = socket.close().await;
+                        return;
+                    }
+                };
+                for line in appended {
+                    if socket.send(Message::Text(line)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+}
+
 // ── Details endpoint ─────────────────────────────────────────────
 
 async fn handle_details(
@@ -1340,6 +2532,7 @@ async fn handle_details(
     AxumPath(name): AxumPath<String>,
 ) -> impl IntoResponse {
     let db_path = state.db_path.clone();
+    let masking = state.masking.clone();
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
             Ok(r) => r,
@@ -1384,9 +2577,11 @@ async fn handle_details(
 
         // Load and parse config
         let config_path = Path::new(&instance.config_path);
+        let mut drift = None;
         let (config_json, config_error, config_unknown_fields) = if config_path.exists() {
             match std::fs::read_to_string(config_path) {
                 Ok(raw_toml) => {
+                    drift = Some(config_drift(&instance, &raw_toml));
                     // Parse as raw TOML value
                     let raw_value: Result<toml::Value, _> = toml::from_str(&raw_toml);
                     // Parse as typed Config
@@ -1397,7 +2592,7 @@ async fn handle_details(
                         Ok(typed_config) => {
                             let mut config_val =
                                 serde_json::to_value(&typed_config).unwrap_or_default();
-                            mask_config_secrets(&mut config_val);
+                            mask_config_secrets_with(&mut config_val, &masking);
 
                             // Compute unknown fields
                             let unknown_fields = if let Ok(raw_val) = raw_value {
@@ -1525,6 +2720,14 @@ async fn handle_details(
             .unwrap_or_else(|| lifecycle::instance_dir_from(&instance).join("workspace"));
         let scaffolded = ws_path.join("SOUL.md").exists();
 
+        let depth = match registry.queue_depth_for(&[instance.name.as_str()]) {
+            Ok(mut depths) => depths.remove(&instance.name).unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Failed to query queue depth for '{}': {e:#}", instance.name);
+                crate::db::QueueDepth::default()
+            }
+        };
+
         let mut response = serde_json::json!({
             "instance": {
                 "id": instance.id,
@@ -1533,6 +2736,13 @@ async fn handle_details(
                 "status": live_status,
                 "pid": live_pid,
                 "archived_at": instance.archived_at,
+                "started_at": instance.started_at,
+                "uptime_secs": uptime_secs(&instance, &live_status),
+                "restart_count": instance.restart_count,
+                "queue_depth": depth.depth,
+                "leasable": depth.leasable,
+                "oldest_queued_age_secs": oldest_queued_age_secs(&depth),
+                "config_drift": drift,
             },
             "config": config_json,
             "config_error": config_error,
@@ -1568,6 +2778,98 @@ async fn handle_details(
     }
 }
 
+/// Per-channel health check timeout for `channels/validate`.
+const CHANNEL_VALIDATE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Probe each channel configured for an instance (e.g. Telegram `getMe`,
+/// Slack `auth.test`) without starting it, so a wrong token shows up before
+/// start. Checks run concurrently, each bounded by
+/// `CHANNEL_VALIDATE_TIMEOUT`; results are reported per channel.
+async fn handle_validate_channels(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+) -> ApiResponse {
+    use crate::channels::ChannelHealthState;
+
+    let db_path = state.db_path.clone();
+    let instance_name = name.clone();
+    let loaded = tokio::task::spawn_blocking(
+        move || -> Result<crate::config::Config, ApiResponse> {
+            let registry = open_registry(&db_path)?;
+            let instance = match registry.get_instance_by_name(&name) {
+                Ok(Some(inst)) => inst,
+                Ok(None) => {
+                    return Err(err_json(
+                        StatusCode::NOT_FOUND,
+                        &format!("No instance named '{name}'"),
+                    ))
+                }
+                Err(e) => {
+                    tracing::error!("Failed to query instance: {e:#}");
+                    return Err(err_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to query instance",
+                    ));
+                }
+            };
+            crate::config::Config::load_from_path(Path::new(&instance.config_path))
+                .map_err(|e| err_json(StatusCode::UNPROCESSABLE_ENTITY, &format!("{e:#}")))
+        },
+    )
+    .await;
+
+    let config = match loaded {
+        Ok(Ok(config)) => config,
+        Ok(Err(resp)) => return resp,
+        Err(e) => {
+            return err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("Task join error: {e}"),
+            )
+        }
+    };
+
+    let checks = crate::channels::check_channels(&config, CHANNEL_VALIDATE_TIMEOUT).await;
+    let channels: Vec<serde_json::Value> = checks
+        .iter()
+        .map(|check| {
+            let error = match check.state {
+                ChannelHealthState::Healthy => None,
+                ChannelHealthState::Unhealthy => Some(
+                    check
+                        .error
+                        .clone()
+                        .unwrap_or_else(|| format!("{} health check failed", check.label)),
+                ),
+                ChannelHealthState::Timeout => Some(format!(
+                    "{} health check timed out after {}s",
+                    check.label,
+                    CHANNEL_VALIDATE_TIMEOUT.as_secs()
+                )),
+            };
+            serde_json::json!({
+                "channel": check.channel,
+                "ok": error.is_none(),
+                "status": check.state.as_str(),
+                "error": error,
+            })
+        })
+        .collect();
+
+    // Configured but not probeable without running the instance
+    let mut skipped = Vec::new();
+    if config.channels_config.webhook.is_some() {
+        skipped.push("webhook");
+    }
+
+    ok_json(serde_json::json!({
+        "instance_name": instance_name,
+        "all_ok": checks.iter().all(|c| c.state == ChannelHealthState::Healthy),
+        "channels": channels,
+        "skipped": skipped,
+    }))
+}
+
 // ── Scaffold endpoint ────────────────────────────────────────────
 
 #[derive(Deserialize)]
@@ -1665,7 +2967,7 @@ async fn handle_scaffold(
             communication_style: comm_style,
         };
 
-        match crate::onboard::scaffold_workspace_cp(&ws_path, &ctx) {
+        match crate::onboard::wizard::scaffold_workspace_cp(&ws_path, &ctx) {
             Ok((created, skipped)) => ok_json(serde_json::json!({
                 "files_created": created,
                 "files_skipped": skipped,
@@ -1698,8 +3000,28 @@ struct TasksQuery {
     limit: Option<usize>,
     offset: Option<usize>,
     status: Option<String>,
+    event_type: Option<String>,
+    channel: Option<String>,
     after: Option<String>,
     before: Option<String>,
+    /// Also include events of archived instances that held this name.
+    include_archived: Option<bool>,
+}
+
+/// Channels that record agent events.
+const EVENT_CHANNELS: &[&str] = &[
+    "cli", "telegram", "discord", "slack", "webhook", "imessage", "matrix", "whatsapp", "email",
+    "irc", "cp",
+];
+
+/// An event type (`tg.inbound.text`) or prefix pattern (`tg.inbound.*`).
+fn is_valid_event_type_filter(event_type: &str) -> bool {
+    let name = event_type.strip_suffix(".*").unwrap_or(event_type);
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
 }
 
 async fn handle_tasks(
@@ -1730,14 +3052,39 @@ async fn handle_tasks(
             );
         }
     }
+    if let Some(ref event_type) = query.event_type {
+        if !is_valid_event_type_filter(event_type) {
+            return err_json(
+                StatusCode::BAD_REQUEST,
+                &format!(
+                    "Invalid event_type: '{event_type}'. Use a name like 'tg.inbound.text' \
+                     or a prefix like 'tg.inbound.*'"
+                ),
+            );
+        }
+    }
+    if let Some(ref channel) = query.channel {
+        if !EVENT_CHANNELS.contains(&channel.as_str()) {
+            return err_json(
+                StatusCode::BAD_REQUEST,
+                &format!(
+                    "Invalid channel: '{channel}'. Valid values: {}",
+                    EVENT_CHANNELS.join(", ")
+                ),
+            );
+        }
+    }
 
     let db_path = state.db_path.clone();
     let status_filter = query.status.clone();
+    let event_type = query.event_type.clone();
+    let channel = query.channel.clone();
     let after = query.after.clone();
     let before = query.before.clone();
+    let include_archived = query.include_archived.unwrap_or(false);
 
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
-        let registry = match open_registry(&db_path) {
+        let registry = match open_registry_for_reads(&db_path) {
             Ok(r) => r,
             Err(resp) => return resp,
         };
@@ -1759,10 +3106,24 @@ async fn handle_tasks(
             }
         };
 
+        let (ids, archived) = match history_instance_ids(&registry, &instance, include_archived) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::error!("Failed to query archived instances: {e:#}");
+                return err_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to query instance",
+                );
+            }
+        };
+        let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+
         match registry.list_agent_events(
-            &instance.id,
+            &id_refs,
             limit,
             offset,
+            event_type.as_deref(),
+            channel.as_deref(),
             status_filter.as_deref(),
             after.as_deref(),
             before.as_deref(),
@@ -1786,25 +3147,22 @@ async fn handle_tasks(
                     })
                     .collect();
 
-                let mut resp = serde_json::json!({
-                    "tasks": tasks,
-                    "total": total,
-                    "data_available": data_available,
-                    "limit": limit,
-                    "offset": offset,
-                });
+                let mut resp = paginated(
+                    serde_json::json!({
+                        "tasks": tasks,
+                        "data_available": data_available,
+                    }),
+                    total,
+                    limit,
+                    offset,
+                );
                 if !data_available {
                     resp["message"] = serde_json::json!("No event data available.");
                 }
+                annotate_archived_history(&mut resp, &name, &archived, include_archived);
                 ok_json(resp)
             }
-            Err(e) => {
-                tracing::error!("Failed to list agent events: {e:#}");
-                err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to list agent events",
-                )
-            }
+            Err(e) => query_failed(&e, "Failed to list agent events"),
         }
     })
     .await;
@@ -1823,6 +3181,47 @@ async fn handle_tasks(
 #[derive(Deserialize)]
 struct UsageQuery {
     window: Option<String>,
+    /// Also include usage of archived instances that held this name.
+    include_archived: Option<bool>,
+}
+
+/// Instance IDs whose history the tasks/usage views cover, plus the IDs of
+/// archived instances that previously held the name. A name freed by
+/// archiving can be reused by a new instance with a new ID, so without
+/// `include_archived` the older history is left out; the caller reports
+/// that in the response instead of showing a silently empty view.
+fn history_instance_ids(
+    registry: &Registry,
+    instance: &crate::db::Instance,
+    include_archived: bool,
+) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+    let archived = registry.archived_instance_ids_by_name(&instance.name)?;
+    let mut ids = vec![instance.id.clone()];
+    if include_archived {
+        ids.extend(archived.iter().cloned());
+    }
+    Ok((ids, archived))
+}
+
+/// Add `archived_instance_ids` (and, when they were not queried, a note
+/// pointing at `include_archived`) to a tasks/usage response.
+fn annotate_archived_history(
+    resp: &mut serde_json::Value,
+    name: &str,
+    archived: &[String],
+    include_archived: bool,
+) {
+    if archived.is_empty() {
+        return;
+    }
+    resp["archived_instance_ids"] = serde_json::json!(archived);
+    if !include_archived {
+        resp["note"] = serde_json::json!(format!(
+            "{} archived instance(s) previously named '{name}' have separate history; \
+             pass include_archived=true to include it",
+            archived.len()
+        ));
+    }
 }
 
 async fn handle_usage(
@@ -1840,6 +3239,95 @@ async fn handle_usage(
 
     let db_path = state.db_path.clone();
     let window = window.to_string();
+    let include_archived = query.include_archived.unwrap_or(false);
+
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let registry = match open_registry_for_reads(&db_path) {
+            Ok(r) => r,
+            Err(resp) => return resp,
+        };
+
+        let instance = match registry.get_instance_by_name(&name) {
+            Ok(Some(inst)) => inst,
+            Ok(None) => {
+                return err_json(
+                    StatusCode::NOT_FOUND,
+                    &format!("No instance named '{name}'"),
+                )
+            }
+            Err(e) => {
+                tracing::error!("Failed to query instance: {e:#}");
+                return err_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to query instance",
+                );
+            }
+        };
+
+        // Compute window start from now
+        let now = chrono::Utc::now();
+        let duration = match window.as_str() {
+            "1h" => chrono::Duration::hours(1),
+            "24h" => chrono::Duration::hours(24),
+            "7d" => chrono::Duration::days(7),
+            "30d" => chrono::Duration::days(30),
+            _ => unreachable!(),
+        };
+        let window_start = (now - duration).format("%Y-%m-%d %H:%M:%S").to_string();
+        let window_end = now.format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let (ids, archived) = match history_instance_ids(&registry, &instance, include_archived) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::error!("Failed to query archived instances: {e:#}");
+                return err_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to query instance",
+                );
+            }
+        };
+        let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+
+        match registry.get_agent_usage(&id_refs, Some(&window_start), Some(&window_end)) {
+            Ok(summary) => {
+                let data_available = summary.request_count > 0;
+                let mut resp = serde_json::json!({
+                    "instance_name": name,
+                    "window": window,
+                    "data_available": data_available,
+                    "usage": {
+                        "input_tokens": summary.input_tokens,
+                        "output_tokens": summary.output_tokens,
+                        "total_tokens": summary.total_tokens,
+                        "request_count": summary.request_count,
+                        "unknown_count": summary.unknown_count,
+                    },
+                });
+                annotate_archived_history(&mut resp, &name, &archived, include_archived);
+                ok_json(resp)
+            }
+            Err(e) => query_failed(&e, "Failed to query usage"),
+        }
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+/// Most recent activity for one instance across agent events and messages
+/// in either direction. All timestamps are null for an instance that has
+/// never done anything.
+async fn handle_instance_activity(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+) -> ApiResponse {
+    let db_path = state.db_path.clone();
 
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
@@ -1864,39 +3352,112 @@ async fn handle_usage(
             }
         };
 
-        // Compute window start from now
-        let now = chrono::Utc::now();
-        let duration = match window.as_str() {
-            "1h" => chrono::Duration::hours(1),
-            "24h" => chrono::Duration::hours(24),
-            "7d" => chrono::Duration::days(7),
-            "30d" => chrono::Duration::days(30),
-            _ => unreachable!(),
-        };
-        let window_start = (now - duration).format("%Y-%m-%d %H:%M:%S").to_string();
-        let window_end = now.format("%Y-%m-%d %H:%M:%S").to_string();
+        match registry.instance_activity(&instance.id, &instance.name) {
+            Ok(activity) => {
+                let last = activity.last_activity_at().map(str::to_string);
+                ok_json(serde_json::json!({
+                    "instance_name": instance.name,
+                    "idle_secs": last.as_deref().and_then(secs_since),
+                    "last_activity_at": last,
+                    "sources": {
+                        "agent_event": activity.last_event_at,
+                        "inbound_message": activity.last_inbound_message_at,
+                        "outbound_message": activity.last_outbound_message_at,
+                    },
+                }))
+            }
+            Err(e) => {
+                tracing::error!("Failed to query activity: {e:#}");
+                err_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to query activity",
+                )
+            }
+        }
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+/// GET /api/instances/:name/disk
+///
+/// Bytes used by the instance workspace, broken down by workspace subdir
+/// (`other` covers the rest of its top level), plus the instance logs.
+/// The walk is bounded (see `cp::disk_usage`); `truncated` is true when it
+/// stopped early and the figures are an undercount.
+async fn handle_instance_disk(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+) -> ApiResponse {
+    let db_path = state.db_path.clone();
+
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let registry = match open_registry_for_reads(&db_path) {
+            Ok(r) => r,
+            Err(resp) => return resp,
+        };
+
+        let instance = match registry.get_instance_by_name(&name) {
+            Ok(Some(inst)) => inst,
+            Ok(None) => {
+                return err_json(
+                    StatusCode::NOT_FOUND,
+                    &format!("No instance named '{name}'"),
+                )
+            }
+            Err(e) => return query_failed(&e, "Failed to query instance"),
+        };
+        drop(registry);
+
+        let inst_dir = lifecycle::instance_dir_from(&instance);
+        let workspace = instance
+            .workspace_dir
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| inst_dir.join("workspace"));
+        let log_path = lifecycle::log_path(&inst_dir);
+        let log_dir = log_path.parent().unwrap_or(&inst_dir);
 
-        match registry.get_agent_usage(&instance.id, Some(&window_start), Some(&window_end)) {
-            Ok(summary) => {
-                let data_available = summary.request_count > 0;
-                ok_json(serde_json::json!({
-                    "instance_name": name,
-                    "window": window,
-                    "data_available": data_available,
-                    "usage": {
-                        "input_tokens": summary.input_tokens,
-                        "output_tokens": summary.output_tokens,
-                        "total_tokens": summary.total_tokens,
-                        "request_count": summary.request_count,
-                        "unknown_count": summary.unknown_count,
-                    },
-                }))
-            }
-            Err(e) => {
-                tracing::error!("Failed to query usage: {e:#}");
-                err_json(StatusCode::INTERNAL_SERVER_ERROR, "Failed to query usage")
-            }
+        let mut walker = UsageWalker::default();
+        let (subdirs, other) =
+            walker.workspace_breakdown(&workspace, &crate::config::workspace::workspace_subdirs());
+        let logs = walker.measure(log_dir);
+
+        let entry = |usage: DirUsage| {
+            serde_json::json!({
+                "bytes": usage.bytes,
+                "human": human_bytes(usage.bytes),
+                "files": usage.files,
+            })
+        };
+        let mut breakdown = serde_json::Map::new();
+        let mut total_bytes = other.bytes + logs.bytes;
+        for (subdir, usage) in subdirs {
+            total_bytes += usage.bytes;
+            breakdown.insert(subdir, entry(usage));
         }
+        breakdown.insert("other".into(), entry(other));
+        breakdown.insert("logs".into(), entry(logs));
+
+        ok_json(serde_json::json!({
+            "name": instance.name,
+            "workspace_dir": workspace.display().to_string(),
+            "total_bytes": total_bytes,
+            "total_human": human_bytes(total_bytes),
+            "breakdown": breakdown,
+            "truncated": walker.truncated(),
+            "limits": {
+                "max_depth": disk_usage::MAX_DEPTH,
+                "max_entries": disk_usage::MAX_ENTRIES,
+            },
+        }))
     })
     .await;
 
@@ -1912,20 +3473,140 @@ async fn handle_usage(
 // ── Config API ──────────────────────────────────────────────────
 
 #[derive(Deserialize)]
-struct ConfigPutBody {
+struct ConfigPatchBody {
+    patch: serde_json::Value,
+    #[serde(default)]
+    etag: String,
+}
+
+/// Config body for validate / diff / PUT, normalized to a TOML string.
+///
+/// Accepted forms:
+/// - `Content-Type: application/toml` with the raw TOML as the body
+/// - JSON `{"config": "<toml>", "etag": "..."}` (the original wrapped form)
+/// - JSON config object directly, e.g. `{"default_temperature": 0.7, ...}`
+///
+/// For the raw TOML and unwrapped JSON forms the `ETag` comes from `If-Match`.
+struct ConfigPayload {
     config: String, // TOML string
-    etag: String,   // SHA-256 hex of previous file bytes
+    etag: String,   // SHA-256 hex of previous file bytes (PUT only)
 }
 
-#[derive(Deserialize)]
-struct ConfigBody {
-    config: String, // TOML string (used by validate + diff)
+fn is_toml_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .is_some_and(|v| v == "application/toml" || v == "text/toml")
 }
 
-#[derive(Deserialize)]
-struct ConfigPatchBody {
-    patch: serde_json::Value,
-    etag: String,
+fn if_match_etag(headers: &HeaderMap) -> String {
+    headers
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().trim_start_matches("W/").trim_matches('"').to_string())
+        .unwrap_or_default()
+}
+
+/// Drop JSON nulls (TOML has no null; absent means default).
+fn strip_json_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, v| !v.is_null());
+            map.values_mut().for_each(strip_json_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_json_nulls),
+        _ => {}
+    }
+}
+
+fn parse_config_payload(headers: &HeaderMap, body: &[u8]) -> Result<ConfigPayload, ApiResponse> {
+    if is_toml_content_type(headers) {
+        let config = String::from_utf8(body.to_vec())
+            .map_err(|_| err_json(StatusCode::BAD_REQUEST, "TOML body is not valid UTF-8"))?;
+        return Ok(ConfigPayload {
+            config,
+            etag: if_match_etag(headers),
+        });
+    }
+
+    let value: serde_json::Value = serde_json::from_slice(body)
+        .map_err(|e| err_json(StatusCode::BAD_REQUEST, &format!("Invalid JSON body: {e}")))?;
+    let serde_json::Value::Object(map) = value else {
+        return Err(err_json(
+            StatusCode::BAD_REQUEST,
+            "Body must be a JSON object (or TOML with Content-Type: application/toml)",
+        ));
+    };
+
+    // Wrapped form: {"config": "<toml>", "etag": "..."}
+    if let Some(serde_json::Value::String(config)) = map.get("config") {
+        let etag = match map.get("etag").and_then(|v| v.as_str()) {
+            Some(etag) => etag.to_string(),
+            None => if_match_etag(headers),
+        };
+        return Ok(ConfigPayload {
+            config: config.clone(),
+            etag,
+        });
+    }
+
+    // Unwrapped form: the object is the config itself
+    let mut config_json = serde_json::Value::Object(map);
+    strip_json_nulls(&mut config_json);
+    let config = toml::Value::try_from(&config_json)
+        .and_then(|v| toml::to_string(&v))
+        .map_err(|e| err_json(StatusCode::BAD_REQUEST, &format!("Invalid config: {e}")))?;
+    Ok(ConfigPayload {
+        config,
+        etag: if_match_etag(headers),
+    })
+}
+
+fn is_merge_patch_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase())
+        .is_some_and(|v| v == "application/merge-patch+json")
+}
+
+/// Config PATCH body.
+///
+/// Accepted forms:
+/// - `Content-Type: application/merge-patch+json` with an RFC 7386 merge
+///   patch as the body and the `ETag` in `If-Match`
+/// - JSON `{"patch": {...}, "etag": "..."}` (the original wrapped form;
+///   `If-Match` is used when `etag` is omitted)
+fn parse_patch_payload(headers: &HeaderMap, body: &[u8]) -> Result<ConfigPatchBody, ApiResponse> {
+    if is_merge_patch_content_type(headers) {
+        let patch = serde_json::from_slice(body).map_err(|e| {
+            err_json(
+                StatusCode::BAD_REQUEST,
+                &format!("Invalid merge patch: {e}"),
+            )
+        })?;
+        return Ok(ConfigPatchBody {
+            patch,
+            etag: if_match_etag(headers),
+        });
+    }
+
+    let mut wrapped: ConfigPatchBody = serde_json::from_slice(body).map_err(|e| {
+        err_json(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "Invalid JSON body: {e} (send {{\"patch\", \"etag\"}} or a merge patch \
+                 with Content-Type: application/merge-patch+json)"
+            ),
+        )
+    })?;
+    if wrapped.etag.is_empty() {
+        wrapped.etag = if_match_etag(headers);
+    }
+    Ok(wrapped)
 }
 
 fn compute_config_etag(raw_bytes: &[u8]) -> String {
@@ -1935,9 +3616,10 @@ fn compute_config_etag(raw_bytes: &[u8]) -> String {
 /// Produce masked TOML and masked JSON from a typed Config.
 fn masked_config_outputs(
     config: &crate::config::schema::Config,
+    masking: &SecretMasking,
 ) -> Result<(String, serde_json::Value), String> {
     let mut config_json = serde_json::to_value(config).map_err(|e| format!("{e}"))?;
-    mask_config_secrets(&mut config_json);
+    mask_config_secrets_with(&mut config_json, masking);
     // Deserialize masked JSON back to Config, then serialize to pretty TOML
     let masked_config: crate::config::schema::Config =
         serde_json::from_value(config_json.clone()).map_err(|e| format!("{e}"))?;
@@ -1950,47 +3632,20 @@ fn masked_config_outputs(
 async fn handle_config_get(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
-) -> impl IntoResponse {
+    headers: HeaderMap,
+) -> Response {
     let db_path = state.db_path.clone();
-    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
-        let registry = match open_registry(&db_path) {
-            Ok(r) => r,
-            Err(resp) => return resp,
-        };
-
-        let instance = match registry.get_instance_by_name(&name) {
-            Ok(Some(inst)) => inst,
-            Ok(None) => {
-                return err_json(
-                    StatusCode::NOT_FOUND,
-                    &format!("No instance named '{name}'"),
-                )
-            }
-            Err(e) => {
-                tracing::error!("Failed to query instance: {e:#}");
-                return err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to query instance",
-                );
-            }
-        };
-
-        let config_path = Path::new(&instance.config_path);
-        let raw_bytes = match std::fs::read(config_path) {
+    let masking = state.masking.clone();
+    let result = tokio::task::spawn_blocking(move || -> Response {
+        let raw_bytes = match read_instance_config_bytes(&db_path, &name) {
             Ok(b) => b,
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                return err_json(StatusCode::NOT_FOUND, "Config file not found")
-            }
-            Err(e) => {
-                tracing::error!("Failed to read config: {e}");
-                return err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to read config file",
-                );
-            }
+            Err(resp) => return resp.into_response(),
         };
 
         let etag = compute_config_etag(&raw_bytes);
+        if if_none_match_hits(&headers, &etag) {
+            return with_etag(StatusCode::NOT_MODIFIED.into_response(), &etag);
+        }
 
         let raw_str = match String::from_utf8(raw_bytes) {
             Ok(s) => s,
@@ -1999,6 +3654,7 @@ async fn handle_config_get(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Config file is not valid UTF-8",
                 )
+                .into_response()
             }
         };
 
@@ -2009,25 +3665,27 @@ async fn handle_config_get(
                     StatusCode::INTERNAL_SERVER_ERROR,
                     &format!("Config parse error: {e}"),
                 )
+                .into_response()
             }
         };
 
-        let (masked_toml, masked_json) = match masked_config_outputs(&config) {
+        let (masked_toml, masked_json) = match masked_config_outputs(&config, &masking) {
             Ok(v) => v,
-            Err(msg) => return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+            Err(msg) => return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg).into_response(),
         };
 
         let secret_fingerprints = compute_secret_fingerprints(&config);
         let secret_paths: Vec<&str> = SECRET_PATHS_MANIFEST.to_vec();
 
-        ok_json(serde_json::json!({
+        let resp = ok_json(serde_json::json!({
             "name": name,
             "config_toml": masked_toml,
             "config_masked": masked_json,
             "secret_fingerprints": secret_fingerprints,
             "secret_paths": secret_paths,
             "etag": etag,
-        }))
+        }));
+        with_etag(resp.into_response(), &etag)
     })
     .await;
 
@@ -2036,8 +3694,97 @@ async fn handle_config_get(
         Err(e) => err_json(
             StatusCode::INTERNAL_SERVER_ERROR,
             &format!("Task join error: {e}"),
-        ),
+        )
+        .into_response(),
+    }
+}
+
+// ── HEAD /api/instances/:name/config ────────────────────────────
+
+/// Cheap change detection: `ETag` header only, no parsing or masking.
+async fn handle_config_head(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+    headers: HeaderMap,
+) -> Response {
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> Response {
+        let raw_bytes = match read_instance_config_bytes(&db_path, &name) {
+            Ok(b) => b,
+            Err((status, _)) => return status.into_response(),
+        };
+        let etag = compute_config_etag(&raw_bytes);
+        let status = if if_none_match_hits(&headers, &etag) {
+            StatusCode::NOT_MODIFIED
+        } else {
+            StatusCode::OK
+        };
+        with_etag(status.into_response(), &etag)
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => {
+            tracing::error!("Task join error: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Look up an instance by name and read its raw config file.
+fn read_instance_config_bytes(db_path: &Path, name: &str) -> Result<Vec<u8>, ApiResponse> {
+    let registry = open_registry(db_path)?;
+
+    let instance = match registry.get_instance_by_name(name) {
+        Ok(Some(inst)) => inst,
+        Ok(None) => {
+            return Err(err_json(
+                StatusCode::NOT_FOUND,
+                &format!("No instance named '{name}'"),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Failed to query instance: {e:#}");
+            return Err(err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to query instance",
+            ));
+        }
+    };
+
+    match std::fs::read(Path::new(&instance.config_path)) {
+        Ok(b) => Ok(b),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(err_json(StatusCode::NOT_FOUND, "Config file not found"))
+        }
+        Err(e) => {
+            tracing::error!("Failed to read config: {e}");
+            Err(err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read config file",
+            ))
+        }
+    }
+}
+
+/// Attach a strong `ETag` header (quoted config hash) to a response.
+fn with_etag(mut resp: Response, etag: &str) -> Response {
+    if let Ok(value) = header::HeaderValue::from_str(&format!("\"{etag}\"")) {
+        resp.headers_mut().insert(header::ETAG, value);
     }
+    resp
+}
+
+/// True if `If-None-Match` lists `etag` (quoted, weak, or bare) or `*`.
+fn if_none_match_hits(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/").trim_matches('"'))
+        .any(|tag| tag == "*" || tag == etag)
 }
 
 // ── PUT /api/instances/:name/config ─────────────────────────────
@@ -2046,13 +3793,19 @@ async fn handle_config_put(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
     headers: HeaderMap,
-    Json(body): Json<ConfigPutBody>,
+    body: Bytes,
 ) -> impl IntoResponse {
+    let body = match parse_config_payload(&headers, &body) {
+        Ok(b) => b,
+        Err(resp) => return resp,
+    };
     let db_path = state.db_path.clone();
+    let masking = state.masking.clone();
     let allow_secret_write = headers
         .get("x-allow-secret-write")
         .and_then(|v| v.to_str().ok())
         .map_or(false, |v| v.eq_ignore_ascii_case("true"));
+    let actor = request_actor(&headers);
 
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
@@ -2116,6 +3869,7 @@ async fn handle_config_put(
             Ok(c) => c,
             Err(e) => return err_json(StatusCode::BAD_REQUEST, &format!("Invalid config: {e}")),
         };
+        let schema_warnings = config_schema_warnings(&body.config);
 
         // Parse current config for sentinel preservation
         let current_str = String::from_utf8_lossy(&current_bytes);
@@ -2133,7 +3887,7 @@ async fn handle_config_put(
         let current_json = serde_json::to_value(&current_config).unwrap_or_default();
 
         // Preserve masked sentinels
-        match preserve_masked_secrets(&mut incoming_json, &current_json) {
+        match preserve_masked_secrets_with(&mut incoming_json, &current_json, &masking) {
             Err((_path, msg)) => {
                 return err_json(StatusCode::BAD_REQUEST, &msg);
             }
@@ -2205,6 +3959,35 @@ async fn handle_config_put(
             );
         }
 
+        // Keep the registry port in sync with an explicit gateway.port
+        // (checked under the lock). An omitted port defaults in the schema,
+        // but the daemon is launched with --port from the registry anyway.
+        let requested_port = explicit_gateway_port(&body.config);
+        let port_changed = requested_port.is_some_and(|p| p != instance.port);
+        if let Some(new_port) = requested_port.filter(|_| port_changed) {
+            match registry.reassign_port(&instance.id, new_port) {
+                Ok(None) => {}
+                Ok(Some(holder)) => {
+                    return (
+                        StatusCode::CONFLICT,
+                        Json(serde_json::json!({
+                            "error": format!(
+                                "gateway.port {new_port} is already used by instance '{holder}'"
+                            ),
+                            "conflicting_instance": holder,
+                        })),
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("Failed to reassign port: {e:#}");
+                    return err_json(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to update instance port",
+                    );
+                }
+            }
+        }
+
         // Set skip fields
         final_config.config_path = PathBuf::from(&config_path_str);
         final_config.workspace_dir = inst_dir.join("workspace");
@@ -2212,6 +3995,11 @@ async fn handle_config_put(
         // Atomic write
         if let Err(e) = final_config.save() {
             tracing::error!("Failed to save config: {e:#}");
+            if port_changed {
+                if let Err(e) = registry.reassign_port(&instance.id, instance.port) {
+                    tracing::error!("Failed to restore instance port: {e:#}");
+                }
+            }
             return err_json(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 &format!("Failed to save config: {e}"),
@@ -2230,6 +4018,13 @@ async fn handle_config_put(
             }
         };
         let new_etag = compute_config_etag(&new_bytes);
+        record_config_write(
+            &registry,
+            &instance.id,
+            (&recheck_etag, &recheck_bytes),
+            (&new_etag, &new_bytes),
+            &actor,
+        );
 
         // Check if instance is running
         let (live_status, _live_pid) =
@@ -2241,6 +4036,7 @@ async fn handle_config_put(
             "name": name,
             "etag": new_etag,
             "restart_recommended": restart_recommended,
+            "warnings": schema_warnings,
         }))
     })
     .await;
@@ -2260,13 +4056,18 @@ async fn handle_config_patch(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
     headers: HeaderMap,
-    Json(body): Json<ConfigPatchBody>,
+    body: Bytes,
 ) -> impl IntoResponse {
+    let body = match parse_patch_payload(&headers, &body) {
+        Ok(b) => b,
+        Err(resp) => return resp,
+    };
     let db_path = state.db_path.clone();
     let allow_secret_write = headers
         .get("x-allow-secret-write")
         .and_then(|v| v.to_str().ok())
         .map_or(false, |v| v.eq_ignore_ascii_case("true"));
+    let actor = request_actor(&headers);
 
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
@@ -2473,6 +4274,13 @@ async fn handle_config_patch(
             }
         };
         let new_etag = compute_config_etag(&new_bytes);
+        record_config_write(
+            &registry,
+            &instance.id,
+            (&recheck_etag, recheck_str.as_bytes()),
+            (&new_etag, &new_bytes),
+            &actor,
+        );
 
         // Check if instance is running
         let (live_status, _live_pid) =
@@ -2497,6 +4305,60 @@ async fn handle_config_patch(
     }
 }
 
+/// Header naming who made a config write, recorded in the audit trail.
+const ACTOR_HEADER: &str = "x-zeroclaw-actor";
+
+/// Actor for audit entries: the `X-ZeroClaw-Actor` header, or `operator`
+/// (the name flow approvals record) when absent.
+fn request_actor(headers: &HeaderMap) -> String {
+    headers
+        .get(ACTOR_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .unwrap_or("operator")
+        .to_string()
+}
+
+/// Dotted config paths that differ between two config files, via the same
+/// `diff_json` the diff endpoint uses. Only paths are kept, so secrets are
+/// never copied into the audit table.
+fn config_changed_paths(old: &[u8], new: &[u8]) -> Vec<String> {
+    let as_json = |bytes: &[u8]| {
+        toml::from_str::<crate::config::schema::Config>(&String::from_utf8_lossy(bytes))
+            .ok()
+            .and_then(|c| serde_json::to_value(c).ok())
+            .unwrap_or_default()
+    };
+    let diff = diff_json(&as_json(old), &as_json(new));
+    let mut paths: Vec<String> = diff
+        .changes
+        .into_iter()
+        .map(|c| c.path)
+        .chain(diff.added)
+        .chain(diff.removed)
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Append a `config_audit` row for a completed write. The file is already
+/// saved, so a failure here is logged rather than failing the request.
+fn record_config_write(
+    registry: &Registry,
+    instance_id: &str,
+    (old_etag, old_bytes): (&str, &[u8]),
+    (new_etag, new_bytes): (&str, &[u8]),
+    actor: &str,
+) {
+    let changed_paths = config_changed_paths(old_bytes, new_bytes);
+    if let Err(e) =
+        registry.insert_config_audit(instance_id, old_etag, new_etag, &changed_paths, actor)
+    {
+        tracing::error!("Failed to record config audit entry: {e:#}");
+    }
+}
+
 /// Atomic write: write to temp file, fsync, rename over target.
 fn atomic_write_config(path: &Path, content: &str) -> anyhow::Result<()> {
     use std::fs::{self, File, OpenOptions};
@@ -2531,19 +4393,92 @@ fn atomic_write_config(path: &Path, content: &str) -> anyhow::Result<()> {
         if had_existing && backup_path.exists() {
             let _ = fs::copy(&backup_path, path);
         }
-        anyhow::bail!("Atomic rename failed: {e}");
-    }
-
-    // Directory fsync
-    if let Ok(dir) = File::open(parent) {
-        let _ = dir.sync_all();
-    }
+        anyhow::bail!("Atomic rename failed: {e}");
+    }
+
+    // Directory fsync
+    if let Ok(dir) = File::open(parent) {
+        let _ = dir.sync_all();
+    }
+
+    if had_existing {
+        let _ = fs::remove_file(&backup_path);
+    }
+
+    Ok(())
+}
+
+// ── GET /api/instances/:name/config/audit ─────────────────────
+
+#[derive(Deserialize)]
+struct ConfigAuditQuery {
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+/// Successful config writes for an instance, newest first.
+async fn handle_config_audit(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+    Query(params): Query<ConfigAuditQuery>,
+) -> ApiResponse {
+    let db_path = state.db_path.clone();
+    let limit = params.limit.unwrap_or(50).min(200);
+    let offset = params.offset.unwrap_or(0);
+
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let registry = match open_registry_for_reads(&db_path) {
+            Ok(r) => r,
+            Err(resp) => return resp,
+        };
+
+        let instance = match registry.get_instance_by_name(&name) {
+            Ok(Some(inst)) => inst,
+            Ok(None) => {
+                return err_json(
+                    StatusCode::NOT_FOUND,
+                    &format!("No instance named '{name}'"),
+                )
+            }
+            Err(e) => return query_failed(&e, "Failed to query instance"),
+        };
+
+        match registry.list_config_audit(&instance.id, limit, offset) {
+            Ok((rows, total)) => {
+                let entries: Vec<serde_json::Value> = rows
+                    .iter()
+                    .map(|r| {
+                        let changed_paths: serde_json::Value =
+                            serde_json::from_str(&r.changed_paths).unwrap_or_default();
+                        serde_json::json!({
+                            "id": r.id,
+                            "old_etag": r.old_etag,
+                            "new_etag": r.new_etag,
+                            "changed_paths": changed_paths,
+                            "actor": r.actor,
+                            "created_at": r.created_at,
+                        })
+                    })
+                    .collect();
+                ok_json(paginated(
+                    serde_json::json!({ "name": name, "entries": entries }),
+                    total,
+                    limit,
+                    offset,
+                ))
+            }
+            Err(e) => query_failed(&e, "Failed to list config audit"),
+        }
+    })
+    .await;
 
-    if had_existing {
-        let _ = fs::remove_file(&backup_path);
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
     }
-
-    Ok(())
 }
 
 // ── POST /api/instances/:name/config/validate ───────────────────
@@ -2551,8 +4486,13 @@ fn atomic_write_config(path: &Path, content: &str) -> anyhow::Result<()> {
 async fn handle_config_validate(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
-    Json(body): Json<ConfigBody>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> impl IntoResponse {
+    let body = match parse_config_payload(&headers, &body) {
+        Ok(b) => b,
+        Err(resp) => return resp,
+    };
     let db_path = state.db_path.clone();
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
@@ -2579,7 +4519,10 @@ async fn handle_config_validate(
         };
 
         match toml::from_str::<crate::config::schema::Config>(&body.config) {
-            Ok(_) => ok_json(serde_json::json!({ "valid": true })),
+            Ok(_) => ok_json(serde_json::json!({
+                "valid": true,
+                "warnings": config_schema_warnings(&body.config),
+            })),
             Err(e) => (
                 StatusCode::BAD_REQUEST,
                 Json(serde_json::json!({
@@ -2600,14 +4543,39 @@ async fn handle_config_validate(
     }
 }
 
+/// `gateway.port` if the raw config TOML sets it explicitly.
+fn explicit_gateway_port(raw: &str) -> Option<u16> {
+    toml::from_str::<toml::Value>(raw)
+        .ok()?
+        .get("gateway")?
+        .get("port")?
+        .as_integer()
+        .and_then(|p| u16::try_from(p).ok())
+}
+
+/// Schema-version and deprecated-field warnings for a raw config TOML.
+/// Advisory only: callers have already checked that it parses as `Config`.
+fn config_schema_warnings(raw: &str) -> Vec<crate::config::migrations::SchemaWarning> {
+    use crate::config::migrations::{check_schema, FIELD_MIGRATIONS};
+    toml::from_str::<toml::Value>(raw)
+        .map(|v| check_schema(&v, FIELD_MIGRATIONS))
+        .unwrap_or_default()
+}
+
 // ── POST /api/instances/:name/config/diff ───────────────────────
 
 async fn handle_config_diff(
     State(state): State<CpState>,
     AxumPath(name): AxumPath<String>,
-    Json(body): Json<ConfigBody>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> impl IntoResponse {
+    let body = match parse_config_payload(&headers, &body) {
+        Ok(b) => b,
+        Err(resp) => return resp,
+    };
     let db_path = state.db_path.clone();
+    let masking = state.masking.clone();
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
         let registry = match open_registry(&db_path) {
             Ok(r) => r,
@@ -2670,10 +4638,10 @@ async fn handle_config_diff(
 
         // Mask both sides
         let mut current_json = serde_json::to_value(&current_config).unwrap_or_default();
-        mask_config_secrets(&mut current_json);
+        mask_config_secrets_with(&mut current_json, &masking);
 
         let mut proposed_json = serde_json::to_value(&proposed_config).unwrap_or_default();
-        mask_config_secrets(&mut proposed_json);
+        mask_config_secrets_with(&mut proposed_json, &masking);
 
         // Diff
         let diff = diff_json(&current_json, &proposed_json);
@@ -2707,6 +4675,261 @@ async fn handle_config_diff(
     }
 }
 
+// ── Config templates ────────────────────────────────────────────
+
+/// Derive the config templates dir from db_path (sibling of `instances`).
+fn templates_dir_from_db(db_path: &Path) -> PathBuf {
+    db_path.parent().unwrap_or(Path::new(".")).join("templates")
+}
+
+fn template_path(db_path: &Path, name: &str) -> PathBuf {
+    templates_dir_from_db(db_path).join(format!("{name}.toml"))
+}
+
+/// A stored template as JSON, or `None` if there is no such template.
+fn read_config_template(db_path: &Path, name: &str) -> Result<Option<serde_json::Value>, String> {
+    let raw = match std::fs::read_to_string(template_path(db_path, name)) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read config template '{name}': {e}")),
+    };
+    let value: toml::Value = toml::from_str(&raw)
+        .map_err(|e| format!("Config template '{name}' is not valid TOML: {e}"))?;
+    serde_json::to_value(value)
+        .map(Some)
+        .map_err(|e| format!("Config template '{name}' is not representable as JSON: {e}"))
+}
+
+fn template_to_toml(template: &serde_json::Value) -> Result<String, String> {
+    toml::Value::try_from(template)
+        .and_then(|v| toml::to_string_pretty(&v))
+        .map_err(|e| format!("Failed to serialize template: {e}"))
+}
+
+fn template_name_error(name: &str) -> Option<ApiResponse> {
+    validate_instance_name(name).err().map(|msg| {
+        err_json(
+            StatusCode::BAD_REQUEST,
+            &format!("Invalid template name: {msg}"),
+        )
+    })
+}
+
+async fn handle_list_templates(State(state): State<CpState>) -> impl IntoResponse {
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let entries = match std::fs::read_dir(templates_dir_from_db(&db_path)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return ok_json(serde_json::json!({ "templates": [] }))
+            }
+            Err(e) => {
+                tracing::error!("Failed to list config templates: {e}");
+                return err_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to list config templates",
+                );
+            }
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "toml" {
+                    return None;
+                }
+                path.file_stem()?.to_str().map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        ok_json(serde_json::json!({ "templates": names }))
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+async fn handle_get_template(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+) -> impl IntoResponse {
+    if let Some(resp) = template_name_error(&name) {
+        return resp;
+    }
+    let db_path = state.db_path.clone();
+    let masking = state.masking.clone();
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let mut template = match read_config_template(&db_path, &name) {
+            Ok(Some(value)) => value,
+            Ok(None) => {
+                return err_json(
+                    StatusCode::NOT_FOUND,
+                    &format!("No config template named '{name}'"),
+                )
+            }
+            Err(msg) => {
+                tracing::error!("{msg}");
+                return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg);
+            }
+        };
+        mask_config_secrets_with(&mut template, &masking);
+        let masked_toml = match template_to_toml(&template) {
+            Ok(t) => t,
+            Err(msg) => return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+        };
+        ok_json(serde_json::json!({
+            "name": name,
+            "config_toml": masked_toml,
+            "config_masked": template,
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+/// Create or replace a template. Accepts the same body forms as the
+/// instance config PUT (no ETag needed); masked secrets are preserved from
+/// the template being replaced.
+async fn handle_put_template(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Some(resp) = template_name_error(&name) {
+        return resp;
+    }
+    let body = match parse_config_payload(&headers, &body) {
+        Ok(b) => b,
+        Err(resp) => return resp,
+    };
+    let db_path = state.db_path.clone();
+    let masking = state.masking.clone();
+    let allow_secret_write = headers
+        .get("x-allow-secret-write")
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| v.eq_ignore_ascii_case("true"));
+
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let parsed = toml::from_str::<toml::Value>(&body.config)
+            .map_err(|e| e.to_string())
+            .and_then(|v| serde_json::to_value(v).map_err(|e| e.to_string()));
+        let mut incoming = match parsed {
+            Ok(v) => v,
+            Err(e) => return err_json(StatusCode::BAD_REQUEST, &format!("Invalid template: {e}")),
+        };
+
+        let current = match read_config_template(&db_path, &name) {
+            Ok(current) => current,
+            Err(msg) => {
+                tracing::error!("{msg}");
+                return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg);
+            }
+        };
+        let created = current.is_none();
+        let current = current.unwrap_or_else(|| serde_json::json!({}));
+
+        match preserve_masked_secrets_with(&mut incoming, &current, &masking) {
+            Err((_path, msg)) => return err_json(StatusCode::BAD_REQUEST, &msg),
+            Ok(new_secret_paths) => {
+                if !new_secret_paths.is_empty() && !allow_secret_write {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({
+                            "error": "Template contains secret fields blocked by default",
+                            "blocked_fields": new_secret_paths,
+                            "hint": "Set header X-Allow-Secret-Write: true to allow",
+                        })),
+                    );
+                }
+            }
+        }
+
+        // A template must produce a valid config when layered on the defaults
+        if let Err(msg) = build_layered_config(0, None, None, &[&incoming]) {
+            return err_json(StatusCode::BAD_REQUEST, &format!("Invalid template: {msg}"));
+        }
+
+        let toml_str = match template_to_toml(&incoming) {
+            Ok(t) => t,
+            Err(msg) => return err_json(StatusCode::INTERNAL_SERVER_ERROR, &msg),
+        };
+        let path = template_path(&db_path, &name);
+        let written = std::fs::create_dir_all(templates_dir_from_db(&db_path))
+            .and_then(|()| write_config_atomic(&path, toml_str.as_bytes()));
+        if let Err(e) = written {
+            tracing::error!("Failed to write config template '{name}': {e}");
+            return err_json(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to write config template",
+            );
+        }
+
+        ok_json(serde_json::json!({
+            "status": "saved",
+            "name": name,
+            "created": created,
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
+/// Delete a template. Instances created from it keep their own copy.
+async fn handle_delete_template(
+    State(state): State<CpState>,
+    AxumPath(name): AxumPath<String>,
+) -> impl IntoResponse {
+    if let Some(resp) = template_name_error(&name) {
+        return resp;
+    }
+    let db_path = state.db_path.clone();
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        match std::fs::remove_file(template_path(&db_path, &name)) {
+            Ok(()) => ok_json(serde_json::json!({ "status": "deleted", "name": name })),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => err_json(
+                StatusCode::NOT_FOUND,
+                &format!("No config template named '{name}'"),
+            ),
+            Err(e) => {
+                tracing::error!("Failed to delete config template '{name}': {e}");
+                err_json(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to delete config template",
+                )
+            }
+        }
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
 // ── Telegram observability endpoints (Phase 15.5) ───────────────
 
 #[derive(Deserialize)]
@@ -2750,7 +4973,7 @@ async fn handle_telegram_events(
     let before = query.before.clone();
 
     let result = tokio::task::spawn_blocking(move || -> ApiResponse {
-        let registry = match open_registry(&db_path) {
+        let registry = match open_registry_for_reads(&db_path) {
             Ok(r) => r,
             Err(resp) => return resp,
         };
@@ -2802,20 +5025,14 @@ async fn handle_telegram_events(
                     })
                     .collect();
 
-                ok_json(serde_json::json!({
-                    "events": event_list,
-                    "total": total,
-                    "limit": limit,
-                    "offset": offset,
-                }))
-            }
-            Err(e) => {
-                tracing::error!("Failed to list telegram events: {e:#}");
-                err_json(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "Failed to list telegram events",
-                )
+                ok_json(paginated(
+                    serde_json::json!({ "events": event_list }),
+                    total,
+                    limit,
+                    offset,
+                ))
             }
+            Err(e) => query_failed(&e, "Failed to list telegram events"),
         }
     })
     .await;
@@ -3123,12 +5340,12 @@ async fn handle_flows_history(
         };
 
         if !state_db_path.exists() {
-            return ok_json(serde_json::json!({
-                "history": [],
-                "total": 0,
-                "limit": limit,
-                "offset": offset,
-            }));
+            return ok_json(paginated(
+                serde_json::json!({ "history": [] }),
+                0,
+                limit,
+                offset,
+            ));
         }
 
         let flow_db = match crate::flows::db::FlowDb::open_read_only(&state_db_path) {
@@ -3162,12 +5379,12 @@ async fn handle_flows_history(
                         })
                     })
                     .collect();
-                ok_json(serde_json::json!({
-                    "history": history,
-                    "total": total,
-                    "limit": limit,
-                    "offset": offset,
-                }))
+                ok_json(paginated(
+                    serde_json::json!({ "history": history }),
+                    total,
+                    limit,
+                    offset,
+                ))
             }
             Err(e) => {
                 tracing::error!("Failed to list flow history: {e:#}");
@@ -3353,6 +5570,96 @@ async fn handle_flow_replay(
     }
 }
 
+#[derive(Deserialize)]
+struct FlowValidateBody {
+    /// File name -> TOML contents.
+    #[serde(default)]
+    files: Option<std::collections::BTreeMap<String, String>>,
+    /// Flows directory on the control-plane host.
+    #[serde(default)]
+    path: Option<String>,
+}
+
+/// POST /api/flows/validate
+///
+/// Dry-run validation of a whole flows directory, given either as uploaded
+/// `files` or a `path`. Runs the same checks as loading flows into an
+/// instance and reports errors per file; nothing is written or loaded.
+async fn handle_flows_validate(Json(body): Json<FlowValidateBody>) -> ApiResponse {
+    let result = tokio::task::spawn_blocking(move || -> ApiResponse {
+        let (base, files): (PathBuf, Vec<(String, String)>) = match (body.files, body.path) {
+            (Some(files), None) => (PathBuf::new(), files.into_iter().collect()),
+            (None, Some(path)) => {
+                let dir = PathBuf::from(&path);
+                if !dir.is_dir() {
+                    return err_json(
+                        StatusCode::NOT_FOUND,
+                        &format!("No flows directory at '{path}'"),
+                    );
+                }
+                match crate::flows::read_flow_files(&dir) {
+                    Ok(files) => (dir, files),
+                    Err(e) => {
+                        return err_json(
+                            StatusCode::UNPROCESSABLE_ENTITY,
+                            &format!("Failed to read flows directory: {e:#}"),
+                        )
+                    }
+                }
+            }
+            _ => {
+                return err_json(
+                    StatusCode::BAD_REQUEST,
+                    "Provide exactly one of 'files' or 'path'",
+                )
+            }
+        };
+
+        let (definitions, outcomes) = crate::flows::validate_flow_files(&base, &files);
+        let mut flows: Vec<&String> = definitions.keys().collect();
+        flows.sort();
+        let mut error_count = 0;
+        let files: Vec<serde_json::Value> = files
+            .iter()
+            .zip(&outcomes)
+            .map(|((file, _), outcome)| match outcome {
+                Ok(name) => serde_json::json!({
+                    "file": file,
+                    "flow_name": name,
+                    "valid": true,
+                    "errors": [],
+                }),
+                Err(errors) => {
+                    error_count += errors.len();
+                    let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+                    serde_json::json!({
+                        "file": file,
+                        "flow_name": errors[0].flow_name,
+                        "valid": false,
+                        "errors": messages,
+                    })
+                }
+            })
+            .collect();
+
+        ok_json(serde_json::json!({
+            "valid": error_count == 0,
+            "error_count": error_count,
+            "flows": flows,
+            "files": files,
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(resp) => resp,
+        Err(e) => err_json(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Task join error: {e}"),
+        ),
+    }
+}
+
 // ── Phase 17.5: Flow Version Management API ─────────────────────
 
 #[derive(Deserialize)]
@@ -3494,12 +5801,12 @@ async fn handle_flow_versions_list(
         };
 
         if !state_db_path.exists() {
-            return ok_json(serde_json::json!({
-                "versions": [],
-                "total": 0,
-                "limit": limit,
-                "offset": offset,
-            }));
+            return ok_json(paginated(
+                serde_json::json!({ "versions": [] }),
+                0,
+                limit,
+                offset,
+            ));
         }
 
         let flow_db = match crate::flows::db::FlowDb::open_read_only(&state_db_path) {
@@ -3534,12 +5841,12 @@ async fn handle_flow_versions_list(
                         })
                     })
                     .collect();
-                ok_json(serde_json::json!({
-                    "versions": versions,
-                    "total": total,
-                    "limit": limit,
-                    "offset": offset,
-                }))
+                ok_json(paginated(
+                    serde_json::json!({ "versions": versions }),
+                    total,
+                    limit,
+                    offset,
+                ))
             }
             Err(e) => {
                 tracing::error!("Failed to list flow versions: {e:#}");
@@ -3985,12 +6292,12 @@ async fn handle_flow_audit(
         };
 
         if !state_db_path.exists() {
-            return ok_json(serde_json::json!({
-                "entries": [],
-                "total": 0,
-                "limit": limit,
-                "offset": offset,
-            }));
+            return ok_json(paginated(
+                serde_json::json!({ "entries": [] }),
+                0,
+                limit,
+                offset,
+            ));
         }
 
         let flow_db = match crate::flows::db::FlowDb::open_read_only(&state_db_path) {
@@ -4017,12 +6324,12 @@ async fn handle_flow_audit(
                         })
                     })
                     .collect();
-                ok_json(serde_json::json!({
-                    "entries": entries,
-                    "total": total,
-                    "limit": limit,
-                    "offset": offset,
-                }))
+                ok_json(paginated(
+                    serde_json::json!({ "entries": entries }),
+                    total,
+                    limit,
+                    offset,
+                ))
             }
             Err(e) => {
                 tracing::error!("Failed to list flow audit log: {e:#}");
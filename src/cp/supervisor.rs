@@ -4,8 +4,10 @@ use std::sync::Arc;
 
 use tokio::sync::watch;
 
+use crate::cp::workers;
 use crate::db::Registry;
 use crate::lifecycle;
+use crate::lifecycle::webhooks::LifecycleEvent;
 
 /// Interval between supervisor ticks.
 const SUPERVISOR_INTERVAL_SECS: u64 = 15;
@@ -81,11 +83,12 @@ fn reconcile_instance(registry: &Registry, instance: &crate::db::Instance, inst_
                 "Supervisor: instance '{}' DB=running but process is dead. Marking stopped.",
                 instance.name
             );
-            if let Err(e) = registry.update_status(&instance.id, "stopped") {
-                tracing::warn!(
+            match registry.update_status(&instance.id, "stopped") {
+                Ok(()) => lifecycle::webhooks::notify(LifecycleEvent::Crash, instance),
+                Err(e) => tracing::warn!(
                     "Supervisor: failed to update status for '{}': {e:#}",
                     instance.name
-                );
+                ),
             }
             // Clear DB PID cache (best-effort)
             if let Err(e) = registry.update_pid(&instance.id, None) {
@@ -153,14 +156,26 @@ pub async fn run_supervisor(db_path: Arc<PathBuf>, mut shutdown: watch::Receiver
     // First tick fires immediately; skip it since startup_reconcile already ran.
     interval.tick().await;
 
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
     loop {
         tokio::select! {
             _ = interval.tick() => {
                 let db_path = db_path.clone();
-                // Run check in spawn_blocking since it does sync I/O
-                let _ = tokio::task::spawn_blocking(move || {
-                    check_all_instances(&db_path);
+                // Run check on the blocking pool since it does sync I/O;
+                // failures are recorded and retried after a backoff.
+                let backoff = workers::run_iteration(workers::SUPERVISOR_WORKER, move || {
+                    check_all_instances(&db_path)
                 }).await;
+                if !backoff.is_zero() {
+                    tokio::select! {
+                        () = tokio::time::sleep(backoff) => {}
+                        _ = shutdown.changed() => {
+                            tracing::info!("Supervisor shutting down");
+                            return;
+                        }
+                    }
+                }
             }
             _ = shutdown.changed() => {
                 tracing::info!("Supervisor shutting down");
@@ -170,23 +185,13 @@ pub async fn run_supervisor(db_path: Arc<PathBuf>, mut shutdown: watch::Receiver
     }
 }
 
-/// Single tick: open registry, scan all instances, reconcile.
-pub fn check_all_instances(db_path: &Path) {
-    let registry = match Registry::open(db_path) {
-        Ok(r) => r,
-        Err(e) => {
-            tracing::error!("Supervisor: failed to open registry: {e:#}");
-            return;
-        }
-    };
-
-    let instances = match registry.list_instances() {
-        Ok(i) => i,
-        Err(e) => {
-            tracing::error!("Supervisor: failed to list instances: {e:#}");
-            return;
-        }
-    };
+/// Single tick: open registry, scan all instances, reconcile. Returns the
+/// number of instances checked. Per-instance problems are logged and
+/// skipped; only registry failures are returned.
+pub fn check_all_instances(db_path: &Path) -> anyhow::Result<usize> {
+    let registry = Registry::open(db_path)?;
+    let instances = registry.list_instances()?;
+    let mut checked = 0;
 
     for instance in &instances {
         let inst_dir = lifecycle::instance_dir_from(instance);
@@ -211,5 +216,8 @@ pub fn check_all_instances(db_path: &Path) {
         };
 
         reconcile_instance(&registry, instance, &inst_dir);
+        checked += 1;
     }
+
+    Ok(checked)
 }
@@ -0,0 +1,225 @@
+//! Payload transforms for routing rules.
+//!
+//! A transform is a JSON template applied to a message's payload at enqueue
+//! time. Strings in the template may reference the message with `{{...}}`:
+//!
+//! - `{{payload}}` / `{{payload.a.b}}` / `{{payload.items.0}}`: the payload,
+//!   or a field inside it (numeric segments index arrays)
+//! - `{{from_instance}}`, `{{to_instance}}`, `{{type}}`: the envelope
+//!
+//! The delimiters are those of flow step text ([`crate::flows::template`]),
+//! but the grammar differs, so this module has its own parser:
+//!
+//! - a placeholder is a dotted path, not a bare variable name;
+//! - there is no `\{{` escape: every `{{` opens a placeholder;
+//! - a `{{` with no closing `}}`, or a path with an unknown root, is an
+//!   error when the rule is created, where flow text leaves it as written.
+//!
+//! A string that is exactly one placeholder is replaced by the referenced
+//! value with its JSON type intact; placeholders embedded in a longer string
+//! are interpolated as text (strings verbatim, other values as JSON).
+//! Object keys are not interpolated. For example, with the template
+//! `{"event": "{{type}}", "data": "{{payload}}"}` the payload `{"x": 1}` sent
+//! as `task` becomes `{"event": "task", "data": {"x": 1}}`.
+
+use serde_json::Value;
+
+/// Top-level names a placeholder may start with.
+const ROOTS: &[&str] = &["payload", "from_instance", "to_instance", "type"];
+
+/// The message a transform is applied to.
+pub struct TransformInput<'a> {
+    pub payload: &'a Value,
+    pub from_instance: &'a str,
+    pub to_instance: &'a str,
+    pub message_type: &'a str,
+}
+
+/// Check every placeholder in `template` is well-formed and starts with a
+/// known root. Called at rule creation so bad templates never reach a send.
+pub fn validate_template(template: &Value) -> Result<(), String> {
+    visit_strings(template, &mut |s| {
+        for path in placeholders(s)? {
+            let root = path.split('.').next().unwrap_or_default();
+            if !ROOTS.contains(&root) {
+                return Err(format!(
+                    "Unknown placeholder '{{{{{path}}}}}': must start with one of {}",
+                    ROOTS.join(", ")
+                ));
+            }
+            if root != "payload" && path != root {
+                return Err(format!(
+                    "Placeholder '{{{{{path}}}}}': '{root}' has no fields"
+                ));
+            }
+            if path.split('.').any(str::is_empty) {
+                return Err(format!(
+                    "Placeholder '{{{{{path}}}}}' has an empty path segment"
+                ));
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Apply `template` to `input`. Fails when a placeholder references a
+/// payload field that does not exist.
+pub fn apply_template(template: &Value, input: &TransformInput<'_>) -> Result<Value, String> {
+    match template {
+        Value::String(s) => render_string(s, input),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| apply_template(item, input))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), apply_template(v, input)?)))
+            .collect::<Result<_, String>>()
+            .map(Value::Object),
+        other => Ok(other.clone()),
+    }
+}
+
+fn render_string(s: &str, input: &TransformInput<'_>) -> Result<Value, String> {
+    let paths = placeholders(s)?;
+    if paths.is_empty() {
+        return Ok(Value::String(s.to_string()));
+    }
+    if s.starts_with("{{") && s.find("}}") == Some(s.len() - 2) {
+        return resolve(paths[0], input);
+    }
+
+    // `placeholders` succeeded, so every `{{` has a closing `}}`
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    for path in paths {
+        let start = rest.find("{{").unwrap_or_default();
+        let end = start + rest[start..].find("}}").unwrap_or_default();
+        out.push_str(&rest[..start]);
+        match resolve(path, input)? {
+            Value::String(text) => out.push_str(&text),
+            value => out.push_str(&value.to_string()),
+        }
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(Value::String(out))
+}
+
+fn resolve(path: &str, input: &TransformInput<'_>) -> Result<Value, String> {
+    let mut segments = path.split('.');
+    match segments.next().unwrap_or_default() {
+        "from_instance" => Ok(Value::String(input.from_instance.to_string())),
+        "to_instance" => Ok(Value::String(input.to_instance.to_string())),
+        "type" => Ok(Value::String(input.message_type.to_string())),
+        "payload" => {
+            let mut value = input.payload;
+            for segment in segments {
+                value = match value {
+                    Value::Object(map) => map.get(segment),
+                    Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+                    _ => None,
+                }
+                .ok_or_else(|| format!("Transform references missing field '{{{{{path}}}}}'"))?;
+            }
+            Ok(value.clone())
+        }
+        _ => Err(format!("Unknown placeholder '{{{{{path}}}}}'")),
+    }
+}
+
+/// The paths of every `{{...}}` in `s`, in order (see the module docs for
+/// how this differs from [`crate::flows::template`]).
+fn placeholders(s: &str) -> Result<Vec<&str>, String> {
+    let mut paths = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| format!("Unterminated placeholder in '{s}'"))?;
+        paths.push(after[..end].trim());
+        rest = &after[end + 2..];
+    }
+    Ok(paths)
+}
+
+fn visit_strings(
+    value: &Value,
+    f: &mut impl FnMut(&str) -> Result<(), String>,
+) -> Result<(), String> {
+    match value {
+        Value::String(s) => f(s),
+        Value::Array(items) => items.iter().try_for_each(|item| visit_strings(item, f)),
+        Value::Object(map) => map.values().try_for_each(|v| visit_strings(v, f)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn input(payload: &Value) -> TransformInput<'_> {
+        TransformInput {
+            payload,
+            from_instance: "agent-a",
+            to_instance: "agent-b",
+            message_type: "task",
+        }
+    }
+
+    #[test]
+    fn whole_placeholders_keep_type_and_embedded_ones_interpolate() {
+        let payload = json!({"user": {"name": "ada", "id": 7}, "tags": ["x", "y"]});
+        let template = json!({
+            "source": "{{from_instance}}",
+            "user": "{{payload.user}}",
+            "first_tag": "{{payload.tags.0}}",
+            "summary": "{{type}} for {{payload.user.name}} (#{{payload.user.id}})",
+            "fixed": [1, true, null],
+        });
+        assert!(validate_template(&template).is_ok());
+        assert_eq!(
+            apply_template(&template, &input(&payload)).unwrap(),
+            json!({
+                "source": "agent-a",
+                "user": {"name": "ada", "id": 7},
+                "first_tag": "x",
+                "summary": "task for ada (#7)",
+                "fixed": [1, true, null],
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        for template in [
+            json!({"a": "{{payload"}),
+            json!({"a": "{{secrets.key}}"}),
+            json!({"a": "{{type.name}}"}),
+            json!(["{{payload..x}}"]),
+        ] {
+            assert!(validate_template(&template).is_err(), "{template}");
+        }
+    }
+
+    #[test]
+    fn missing_payload_field_fails_the_transform() {
+        let err = apply_template(&json!("{{payload.nope}}"), &input(&json!({}))).unwrap_err();
+        assert!(err.contains("payload.nope"));
+    }
+
+    #[test]
+    fn backslash_does_not_escape_a_placeholder() {
+        // Unlike flow step text, `\{{` still opens a placeholder
+        let template = json!(r"\{{type}}");
+        assert_eq!(
+            apply_template(&template, &input(&json!({}))).unwrap(),
+            json!(r"\task")
+        );
+        assert!(validate_template(&json!(r"\{{secrets}}")).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::Duration;
+
+/// Name under which the delivery worker (lease reaper, TTL expiry, auto-start)
+/// reports.
+pub const DELIVERY_WORKER: &str = "delivery";
+/// Name under which the supervisor loop reports.
+pub const SUPERVISOR_WORKER: &str = "supervisor";
+
+/// Upper bound on the extra delay after consecutive failed iterations.
+const MAX_FAILURE_BACKOFF_SECS: u64 = 60;
+
+/// Health of one background loop, as of its most recent iteration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkerStatus {
+    /// When the last iteration finished (UTC, `%Y-%m-%d %H:%M:%S`).
+    pub last_run_at: Option<String>,
+    pub last_success_at: Option<String>,
+    /// Error (or panic message) of the most recent failed iteration. Kept
+    /// after later successes so an intermittent fault stays visible.
+    pub last_error: Option<String>,
+    pub last_error_at: Option<String>,
+    /// Items handled across all successful iterations since startup.
+    pub items_processed: u64,
+    /// Failed iterations since the last success.
+    pub consecutive_failures: u32,
+}
+
+/// Status of every background loop, shared between the loops and the API.
+///
+/// Held in memory rather than in the registry: the failures it exists to
+/// surface include the registry itself being unreachable.
+#[derive(Debug, Default)]
+pub struct WorkerStatusBoard {
+    workers: Mutex<BTreeMap<String, WorkerStatus>>,
+}
+
+impl WorkerStatusBoard {
+    /// Process-wide board written by the loops spawned in `zeroclaw_cp`.
+    pub fn global() -> &'static Self {
+        static BOARD: OnceLock<WorkerStatusBoard> = OnceLock::new();
+        BOARD.get_or_init(Self::default)
+    }
+
+    pub fn record_success(&self, worker: &str, items: usize) {
+        let now = now_str();
+        let mut workers = self.workers.lock().unwrap_or_else(PoisonError::into_inner);
+        let status = workers.entry(worker.to_string()).or_default();
+        status.last_run_at = Some(now.clone());
+        status.last_success_at = Some(now);
+        status.items_processed += items as u64;
+        status.consecutive_failures = 0;
+    }
+
+    /// Record a failed iteration. Returns the consecutive failure count.
+    pub fn record_failure(&self, worker: &str, error: &str) -> u32 {
+        let now = now_str();
+        let mut workers = self.workers.lock().unwrap_or_else(PoisonError::into_inner);
+        let status = workers.entry(worker.to_string()).or_default();
+        status.last_run_at = Some(now.clone());
+        status.last_error = Some(error.to_string());
+        status.last_error_at = Some(now);
+        status.consecutive_failures = status.consecutive_failures.saturating_add(1);
+        status.consecutive_failures
+    }
+
+    /// All workers that have reported at least once, by name.
+    pub fn snapshot(&self) -> BTreeMap<String, WorkerStatus> {
+        self.workers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Extra delay before the next iteration after `consecutive_failures`
+/// failures in a row: 1s, 2s, 4s, ... capped at 60s. Zero after a success.
+pub fn failure_backoff(consecutive_failures: u32) -> Duration {
+    if consecutive_failures == 0 {
+        return Duration::ZERO;
+    }
+    let secs = 1u64
+        .checked_shl(consecutive_failures - 1)
+        .unwrap_or(u64::MAX)
+        .min(MAX_FAILURE_BACKOFF_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Run one loop iteration on the blocking pool and record its outcome on
+/// the global board. An error or panic is recorded instead of ending the
+/// loop; the returned backoff is how long the caller should wait on top of
+/// its normal interval (zero on success).
+pub async fn run_iteration<F>(worker: &'static str, iteration: F) -> Duration
+where
+    F: FnOnce() -> anyhow::Result<usize> + Send + 'static,
+{
+    let board = WorkerStatusBoard::global();
+    let error = match tokio::task::spawn_blocking(iteration).await {
+        Ok(Ok(items)) => {
+            board.record_success(worker, items);
+            return Duration::ZERO;
+        }
+        Ok(Err(e)) => format!("{e:#}"),
+        Err(e) if e.is_panic() => {
+            let panic = e.into_panic();
+            let msg = panic
+                .downcast_ref::<&str>()
+                .map(|s| (*s).to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic payload".into());
+            format!("panicked: {msg}")
+        }
+        Err(e) => format!("task failed: {e}"),
+    };
+    let failures = board.record_failure(worker, &error);
+    let backoff = failure_backoff(failures);
+    tracing::error!(
+        "Worker '{worker}' iteration failed ({failures} in a row, backing off {}s): {error}",
+        backoff.as_secs()
+    );
+    backoff
+}
+
+fn now_str() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
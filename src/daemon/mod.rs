@@ -289,9 +289,13 @@ mod tests {
         config.channels_config.telegram = Some(crate::config::TelegramConfig {
             bot_token: "token".into(),
             allowed_users: vec![],
+            usernames_case_sensitive: false,
             stt_endpoint: None,
             flows_enabled: false,
             flow_policy: crate::config::FlowPolicyConfig::default(),
+            auto_download: crate::config::TelegramAutoDownloadConfig::default(),
+            placeholder_replies: false,
+            markdown_v2: false,
         });
         assert!(has_supervised_channels(&config));
     }
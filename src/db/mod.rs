@@ -1,5 +1,7 @@
 use anyhow::{Context, Result};
 use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 use std::path::Path;
 
 // ── Messaging structs (Phase 10.1) ──────────────────────────────
@@ -14,9 +16,58 @@ pub struct RoutingRule {
     pub max_retries: i64,
     pub ttl_secs: i64,
     pub auto_start: bool,
+    /// Reject sends that would revisit an instance already on the message's
+    /// correlation thread (see `Registry::correlation_route_path`).
+    pub detect_cycles: bool,
+    /// Recipient health required before it may lease messages.
+    pub health_gate: HealthGate,
+    /// JSON template applied to payloads at enqueue (see `cp::transform`).
+    pub transform: Option<String>,
+    /// How long a recipient holds a lease on messages this rule routes.
+    pub lease_secs: i64,
     pub created_at: String,
 }
 
+impl RoutingRule {
+    /// Whether this rule allows `from -> to` for `message_type`.
+    pub fn allows(&self, from: &str, to: &str, message_type: &str) -> bool {
+        self.from_instance == from
+            && self.to_instance == to
+            && type_pattern_matches(&self.type_pattern, message_type)
+    }
+}
+
+/// How healthy a recipient must be before `messages/pending` leases to it.
+///
+/// Set per routing rule; a recipient is held to the strictest gate among
+/// the rules that route to it (see [`Registry::recipient_health_gate`]).
+/// Ordered from least to most strict.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthGate {
+    #[default]
+    Off,
+    /// The daemon process must be running.
+    Running,
+    /// Running, and its gateway `/health` endpoint must respond.
+    Http,
+}
+
+impl HealthGate {
+    pub const ALL: [Self; 3] = [Self::Off, Self::Running, Self::Http];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Running => "running",
+            Self::Http => "http",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|gate| gate.as_str() == value)
+    }
+}
+
 /// A queued inter-agent message.
 #[derive(Debug, Clone)]
 pub struct Message {
@@ -36,6 +87,80 @@ pub struct Message {
     pub expires_at: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Why the message was dead-lettered (None unless status is `dead_letter`).
+    pub dead_letter_reason: Option<String>,
+    /// MIME type of a raw (non-JSON) payload; `None` means the payload is JSON.
+    pub content_type: Option<String>,
+    /// 0-9; higher priorities are leased first, FIFO within a priority.
+    pub priority: i64,
+}
+
+/// Dead-letter count for a single reason (see `dead_letter_reasons_summary`).
+#[derive(Debug, Clone)]
+pub struct DeadLetterReasonCount {
+    pub reason: String,
+    pub count: i64,
+}
+
+/// Lease duration for messages handed to a receiver.
+pub const DEFAULT_LEASE_SECS: i64 = 90;
+
+/// Payloads larger than this are stored gzip-compressed (when that is smaller),
+/// with `payload_encoding = 'gzip'`. Reads decompress transparently.
+pub const PAYLOAD_COMPRESSION_THRESHOLD_BYTES: usize = 4096;
+
+/// Pending-message backlog for one recipient (see `queue_depth_for`).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueDepth {
+    /// Messages still awaiting acknowledgement (queued + leased).
+    pub depth: i64,
+    /// Queued messages a receive call could lease right now: not waiting
+    /// out a retry backoff and not past their TTL (see `count_leasable`).
+    pub leasable: i64,
+    /// `created_at` of the oldest still-queued message, if any.
+    pub oldest_queued_at: Option<String>,
+}
+
+/// Most recent recorded activity for one instance (see `instance_activity`).
+/// Timestamps are UTC, `%Y-%m-%d %H:%M:%S`; `None` when nothing is recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstanceActivity {
+    pub last_event_at: Option<String>,
+    /// Newest message addressed to the instance.
+    pub last_inbound_message_at: Option<String>,
+    /// Newest message sent by the instance.
+    pub last_outbound_message_at: Option<String>,
+}
+
+impl InstanceActivity {
+    /// Latest of the three sources.
+    pub fn last_activity_at(&self) -> Option<&str> {
+        [
+            &self.last_event_at,
+            &self.last_inbound_message_at,
+            &self.last_outbound_message_at,
+        ]
+        .into_iter()
+        .filter_map(Option::as_deref)
+        .max()
+    }
+}
+
+/// Result for one message of [`Registry::enqueue_messages_batch`].
+#[derive(Debug, Clone)]
+pub enum BatchEnqueueOutcome {
+    Queued(Box<Message>),
+    /// Its idempotency key was already used; carries the existing message's ID.
+    Deduplicated(String),
+}
+
+/// What [`Registry::nack_message`] did with a negatively acknowledged message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NackOutcome {
+    /// Back in the queue; carries the backed-off `next_attempt_at`.
+    Requeued(String),
+    /// Retries were exhausted, so it was dead-lettered instead.
+    DeadLettered,
 }
 
 /// Parameters for creating a new message.
@@ -47,9 +172,26 @@ pub struct NewMessage {
     pub payload: String,
     pub correlation_id: Option<String>,
     pub idempotency_key: Option<String>,
+    /// When set, only a message created in the last this-many seconds holds
+    /// `idempotency_key`; an older holder gives it up when this message is
+    /// enqueued. `None` holds the key for as long as the holder exists.
+    pub idempotency_window_secs: Option<i64>,
     pub hop_count: i64,
     pub max_retries: i64,
     pub ttl_secs: i64,
+    /// See [`Message::content_type`].
+    pub content_type: Option<String>,
+    /// See [`Message::priority`].
+    pub priority: i64,
+}
+
+/// Optional filters for [`Registry::search_messages`]; `None` matches any.
+#[derive(Debug, Clone, Default)]
+pub struct MessageSearchFilters {
+    pub status: Option<String>,
+    pub from_instance: Option<String>,
+    pub to_instance: Option<String>,
+    pub message_type: Option<String>,
 }
 
 /// An append-only audit event for a message.
@@ -62,6 +204,127 @@ pub struct MessageEvent {
     pub created_at: String,
 }
 
+/// Rows fetched per query by [`MessageIter`].
+const MESSAGE_ITER_BATCH: usize = 500;
+
+/// Cursor over messages in `(created_at, id)` order, created by
+/// [`Registry::iter_messages`]. Rows are fetched in keyset-paginated batches,
+/// so the table is never loaded all at once.
+pub struct MessageIter<'a> {
+    registry: &'a Registry,
+    since: Option<String>,
+    /// `(created_at, id)` of the last message yielded.
+    after: Option<(String, String)>,
+    batch: std::vec::IntoIter<Message>,
+    exhausted: bool,
+}
+
+impl Iterator for MessageIter<'_> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(msg) = self.batch.next() {
+            self.after = Some((msg.created_at.clone(), msg.id.clone()));
+            return Some(Ok(msg));
+        }
+        if self.exhausted {
+            return None;
+        }
+        match self.registry.message_batch_after(
+            self.since.as_deref(),
+            self.after.as_ref(),
+            MESSAGE_ITER_BATCH,
+        ) {
+            Ok(batch) => {
+                self.exhausted = batch.len() < MESSAGE_ITER_BATCH;
+                self.batch = batch.into_iter();
+                self.next()
+            }
+            Err(e) => {
+                self.exhausted = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// One lease of a message and how it ended, reconstructed from its
+/// `message_events` by [`Registry::get_delivery_attempts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryAttempt {
+    /// 1-based attempt number (`retry_count + 1` when leased).
+    pub attempt: i64,
+    /// Instance that leased the message.
+    pub consumer: Option<String>,
+    /// Receive call that leased it; shared by every message in a batch.
+    pub poll_id: Option<String>,
+    pub lease_secs: Option<i64>,
+    pub leased_at: String,
+    /// Terminating event type (`acknowledged`, `lease_expired`, `cancelled`,
+    /// ...), or `in_flight` while the lease is still held.
+    pub outcome: String,
+    pub ended_at: Option<String>,
+    /// Seconds between lease and outcome.
+    pub held_secs: Option<i64>,
+}
+
+/// Error from [`Registry::allocate_port`].
+#[derive(Debug)]
+pub enum PortAllocError {
+    /// Every port in the range is taken or excluded.
+    Exhausted {
+        range: RangeInclusive<u16>,
+    },
+    Internal(anyhow::Error),
+}
+
+impl std::fmt::Display for PortAllocError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exhausted { range } => write!(
+                f,
+                "No ports available in range {}-{}",
+                range.start(),
+                range.end()
+            ),
+            Self::Internal(e) => write!(f, "{e:#}"),
+        }
+    }
+}
+
+impl std::error::Error for PortAllocError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Internal(e) => Some(e.as_ref()),
+            Self::Exhausted { .. } => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for PortAllocError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+/// Result of [`Registry::archive_instance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveOutcome {
+    Archived,
+    /// The instance was archived before this call; nothing changed.
+    AlreadyArchived,
+    NotFound,
+}
+
+/// Result of [`Registry::unarchive_instance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnarchiveOutcome {
+    Unarchived,
+    /// No archived instance has the name but an active one does.
+    AlreadyActive,
+    NotFound,
+}
+
 /// Telegram health counters for a time window.
 #[derive(Debug, Clone)]
 pub struct TelegramHealthCounters {
@@ -114,6 +377,19 @@ pub struct AgentUsageSummary {
     pub unknown_count: usize,
 }
 
+/// One successful write to an instance's config file.
+#[derive(Debug, Clone)]
+pub struct ConfigAuditEntry {
+    pub id: i64,
+    pub instance_id: String,
+    pub old_etag: String,
+    pub new_etag: String,
+    /// JSON array of dotted config paths that differ between the two versions.
+    pub changed_paths: String,
+    pub actor: String,
+    pub created_at: String,
+}
+
 /// Represents a managed ZeroClaw instance in the CP registry.
 #[derive(Debug, Clone)]
 pub struct Instance {
@@ -127,6 +403,138 @@ pub struct Instance {
     pub migration_run_id: Option<String>,
     /// Best-effort PID cache. The pidfile on disk is authoritative.
     pub pid: Option<u32>,
+    /// When the daemon was last started (UTC, `%Y-%m-%d %H:%M:%S`).
+    pub started_at: Option<String>,
+    /// Number of successful restarts via `lifecycle::restart_instance`.
+    pub restart_count: i64,
+}
+
+// ── SQLite tuning ───────────────────────────────────────────────
+
+pub const DEFAULT_BUSY_TIMEOUT_MS: u64 = 5000;
+pub const DEFAULT_QUERY_TIMEOUT_MS: u64 = 3000;
+/// VM instructions between deadline checks in the query-timeout handler.
+const QUERY_TIMEOUT_CHECK_OPS: i32 = 1000;
+/// First backoff of [`Registry::with_retry`]; doubled after each attempt.
+const BUSY_RETRY_INITIAL_DELAY: std::time::Duration = std::time::Duration::from_millis(10);
+/// How long [`Registry::with_retry`] keeps retrying before giving up.
+const BUSY_RETRY_BUDGET: std::time::Duration = std::time::Duration::from_millis(500);
+const JOURNAL_MODES: &[&str] = &["WAL", "DELETE", "TRUNCATE", "PERSIST", "MEMORY", "OFF"];
+const SYNCHRONOUS_MODES: &[&str] = &["OFF", "NORMAL", "FULL", "EXTRA"];
+
+/// Connection pragmas applied by [`Registry::open`].
+///
+/// Read from `ZEROCLAW_CP_DB_BUSY_TIMEOUT_MS`, `ZEROCLAW_CP_DB_JOURNAL_MODE`,
+/// `ZEROCLAW_CP_DB_SYNCHRONOUS` and `ZEROCLAW_CP_DB_QUERY_TIMEOUT_MS`; unset
+/// or invalid values fall back to the defaults: 5000 ms, `WAL`, `FULL` (the
+/// `SQLite` default), 3000 ms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteTuning {
+    pub busy_timeout_ms: u64,
+    pub journal_mode: String,
+    pub synchronous: String,
+    /// Budget for the queries of one read-heavy request (see
+    /// [`Registry::set_query_timeout`]). Not applied by `open`; 0 disables.
+    pub query_timeout_ms: u64,
+}
+
+impl Default for SqliteTuning {
+    fn default() -> Self {
+        Self {
+            busy_timeout_ms: DEFAULT_BUSY_TIMEOUT_MS,
+            journal_mode: "WAL".into(),
+            synchronous: "FULL".into(),
+            query_timeout_ms: DEFAULT_QUERY_TIMEOUT_MS,
+        }
+    }
+}
+
+impl SqliteTuning {
+    pub fn from_env() -> Self {
+        fn mode_env(key: &str, allowed: &[&str]) -> Option<String> {
+            let raw = std::env::var(key).ok()?;
+            let mode = raw.trim().to_uppercase();
+            if allowed.contains(&mode.as_str()) {
+                Some(mode)
+            } else {
+                tracing::warn!("Ignoring {key}={raw:?}: expected one of {allowed:?}");
+                None
+            }
+        }
+        let defaults = Self::default();
+        Self {
+            busy_timeout_ms: std::env::var("ZEROCLAW_CP_DB_BUSY_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .unwrap_or(defaults.busy_timeout_ms),
+            journal_mode: mode_env("ZEROCLAW_CP_DB_JOURNAL_MODE", JOURNAL_MODES)
+                .unwrap_or(defaults.journal_mode),
+            synchronous: mode_env("ZEROCLAW_CP_DB_SYNCHRONOUS", SYNCHRONOUS_MODES)
+                .unwrap_or(defaults.synchronous),
+            query_timeout_ms: std::env::var("ZEROCLAW_CP_DB_QUERY_TIMEOUT_MS")
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .unwrap_or(defaults.query_timeout_ms),
+        }
+    }
+
+    /// Process-wide tuning, read from the environment once.
+    pub fn global() -> &'static Self {
+        static TUNING: std::sync::OnceLock<SqliteTuning> = std::sync::OnceLock::new();
+        TUNING.get_or_init(Self::from_env)
+    }
+
+    fn apply(&self, conn: &Connection) -> Result<()> {
+        anyhow::ensure!(
+            JOURNAL_MODES.contains(&self.journal_mode.as_str()),
+            "Unsupported SQLite journal_mode: {}",
+            self.journal_mode
+        );
+        anyhow::ensure!(
+            SYNCHRONOUS_MODES.contains(&self.synchronous.as_str()),
+            "Unsupported SQLite synchronous mode: {}",
+            self.synchronous
+        );
+        // Each request opens its own connection; wait for competing writers
+        // instead of failing immediately with SQLITE_BUSY. Set first so the
+        // journal_mode switch below also waits on a busy database.
+        conn.busy_timeout(std::time::Duration::from_millis(self.busy_timeout_ms))
+            .context("Failed to set SQLite busy timeout")?;
+        conn.execute_batch(&format!(
+            "PRAGMA journal_mode={}; PRAGMA synchronous={}; PRAGMA foreign_keys=ON;",
+            self.journal_mode, self.synchronous
+        ))
+        .context("Failed to set SQLite pragmas")?;
+        Ok(())
+    }
+}
+
+/// Whether `err` is a query interrupted by [`Registry::set_query_timeout`].
+pub fn is_query_timeout(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.downcast_ref::<rusqlite::Error>().is_some_and(|e| {
+            e.sqlite_error_code() == Some(rusqlite::ErrorCode::OperationInterrupted)
+        })
+    })
+}
+
+/// Whether `err` is SQLite reporting the database busy or locked by another
+/// connection (`SQLITE_BUSY` / `SQLITE_LOCKED`).
+pub fn is_busy(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.downcast_ref::<rusqlite::Error>().is_some_and(|e| {
+            matches!(
+                e.sqlite_error_code(),
+                Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+            )
+        })
+    })
+}
+
+/// SHA-256 of an instance's control plane token, as stored. Lowercase hex.
+fn hash_instance_token(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
 /// SQLite-backed registry for managing ZeroClaw instances.
@@ -135,18 +543,65 @@ pub struct Registry {
 }
 
 impl Registry {
-    /// Open (or create) the registry database at the given path.
+    /// Open (or create) the registry database at the given path, using
+    /// [`SqliteTuning::global`].
     pub fn open(db_path: &Path) -> Result<Self> {
+        Self::open_with_tuning(db_path, SqliteTuning::global())
+    }
+
+    /// Open (or create) the registry database with explicit connection tuning.
+    pub fn open_with_tuning(db_path: &Path, tuning: &SqliteTuning) -> Result<Self> {
         let conn = Connection::open(db_path)
             .with_context(|| format!("Failed to open registry DB: {}", db_path.display()))?;
-
-        conn.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")
-            .context("Failed to set SQLite pragmas")?;
+        tuning.apply(&conn)?;
 
         Self::init_schema(&conn)?;
         Ok(Self { conn })
     }
 
+    /// Interrupt any query still running `timeout` from now, so one
+    /// pathological scan cannot hold the connection (and a blocking-pool
+    /// thread) indefinitely. The deadline covers every later query on this
+    /// registry; interrupted queries fail with an error for which
+    /// [`is_query_timeout`] is true. `None` removes the limit.
+    pub fn set_query_timeout(&self, timeout: Option<std::time::Duration>) {
+        match timeout {
+            Some(timeout) => {
+                let deadline = std::time::Instant::now() + timeout;
+                self.conn.progress_handler(
+                    QUERY_TIMEOUT_CHECK_OPS,
+                    Some(move || std::time::Instant::now() >= deadline),
+                );
+            }
+            None => self
+                .conn
+                .progress_handler(QUERY_TIMEOUT_CHECK_OPS, None::<fn() -> bool>),
+        }
+    }
+
+    /// Run `op`, retrying with exponential backoff (10 ms, doubling, for up
+    /// to ~500 ms) while it fails because the database is busy or locked.
+    ///
+    /// The busy timeout already waits out ordinary lock contention; this
+    /// covers the cases where SQLite returns `SQLITE_BUSY` without waiting,
+    /// such as a transaction that read a snapshot another connection has
+    /// since written past. `op` must be safe to re-run after a failure: a
+    /// single statement or a transaction that rolls back on error.
+    pub fn with_retry<T>(&self, mut op: impl FnMut(&Self) -> Result<T>) -> Result<T> {
+        let started = std::time::Instant::now();
+        let mut delay = BUSY_RETRY_INITIAL_DELAY;
+        loop {
+            match op(self) {
+                Err(e) if is_busy(&e) && started.elapsed() + delay <= BUSY_RETRY_BUDGET => {
+                    tracing::debug!("Registry busy, retrying in {delay:?}: {e:#}");
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// Open an in-memory registry (for testing).
     #[cfg(test)]
     pub fn open_in_memory() -> Result<Self> {
@@ -198,6 +653,25 @@ impl Registry {
             conn.execute_batch("ALTER TABLE instances ADD COLUMN pid INTEGER;")?;
         }
 
+        // Migration: add uptime/restart tracking columns if missing.
+        let instance_columns: Vec<String> = conn
+            .prepare("PRAGMA table_info(instances)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .collect();
+
+        if !instance_columns.iter().any(|c| c == "started_at") {
+            conn.execute_batch("ALTER TABLE instances ADD COLUMN started_at TEXT;")?;
+        }
+        if !instance_columns.iter().any(|c| c == "restart_count") {
+            conn.execute_batch(
+                "ALTER TABLE instances ADD COLUMN restart_count INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+        if !instance_columns.iter().any(|c| c == "cp_token_hash") {
+            conn.execute_batch("ALTER TABLE instances ADD COLUMN cp_token_hash TEXT;")?;
+        }
+
         // Phase 7.5: unique active-name index (prevents duplicate active names)
         let dupes: Vec<(String, i64)> = conn
             .prepare("SELECT name, COUNT(*) as cnt FROM instances WHERE archived_at IS NULL GROUP BY name HAVING cnt > 1")?
@@ -327,6 +801,8 @@ impl Registry {
                 ON messages(to_instance, status);
             CREATE INDEX IF NOT EXISTS idx_messages_status_lease
                 ON messages(status, lease_expires_at);
+            CREATE INDEX IF NOT EXISTS idx_messages_leasable
+                ON messages(to_instance, status, next_attempt_at, expires_at);
             CREATE INDEX IF NOT EXISTS idx_messages_correlation
                 ON messages(correlation_id) WHERE correlation_id IS NOT NULL;",
         )?;
@@ -344,6 +820,217 @@ impl Registry {
                 ON message_events(message_id);",
         )?;
 
+        // Append-only contract for message_events, enforced for as long as
+        // the parent message exists. Events whose message is gone (rows left
+        // behind by a connection without foreign key enforcement, or a
+        // message purge) may be removed; see
+        // `Registry::purge_events_for_deleted_messages`.
+        conn.execute_batch(
+            "CREATE TRIGGER IF NOT EXISTS prevent_message_events_delete
+                BEFORE DELETE ON message_events
+                WHEN EXISTS (SELECT 1 FROM messages WHERE id = OLD.message_id)
+            BEGIN
+                SELECT RAISE(ABORT, 'message_events is append-only while its message exists');
+            END;",
+        )?;
+
+        // Migration: add dead_letter_reason column if missing (pre-reason DBs lack it).
+        let has_dl_reason_column = conn
+            .prepare("PRAGMA table_info(messages)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "dead_letter_reason");
+
+        if !has_dl_reason_column {
+            conn.execute_batch("ALTER TABLE messages ADD COLUMN dead_letter_reason TEXT;")?;
+        }
+
+        // Migration: payload_encoding (NULL = plain text, 'gzip' = compressed BLOB).
+        let has_payload_encoding_column = conn
+            .prepare("PRAGMA table_info(messages)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "payload_encoding");
+
+        if !has_payload_encoding_column {
+            conn.execute_batch("ALTER TABLE messages ADD COLUMN payload_encoding TEXT;")?;
+        }
+
+        // Migration: content_type (NULL = JSON payload).
+        let has_content_type_column = conn
+            .prepare("PRAGMA table_info(messages)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "content_type");
+
+        if !has_content_type_column {
+            conn.execute_batch("ALTER TABLE messages ADD COLUMN content_type TEXT;")?;
+        }
+
+        // Migration: priority (0 = default; higher is leased first).
+        let has_priority_column = conn
+            .prepare("PRAGMA table_info(messages)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "priority");
+
+        if !has_priority_column {
+            conn.execute_batch(
+                "ALTER TABLE messages ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+
+        // Migration: expedited_at (set by `Registry::expedite_message`; within
+        // a priority, expedited messages lease first, latest expedite first).
+        let has_expedited_at_column = conn
+            .prepare("PRAGMA table_info(messages)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "expedited_at");
+
+        if !has_expedited_at_column {
+            conn.execute_batch("ALTER TABLE messages ADD COLUMN expedited_at TEXT;")?;
+        }
+
+        // Migration: per-rule opt-in cycle detection.
+        let has_detect_cycles_column = conn
+            .prepare("PRAGMA table_info(routing_rules)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "detect_cycles");
+
+        if !has_detect_cycles_column {
+            conn.execute_batch(
+                "ALTER TABLE routing_rules ADD COLUMN detect_cycles INTEGER NOT NULL DEFAULT 0;",
+            )?;
+        }
+
+        // Migration: per-rule recipient health gate.
+        let has_health_gate_column = conn
+            .prepare("PRAGMA table_info(routing_rules)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "health_gate");
+
+        if !has_health_gate_column {
+            conn.execute_batch(
+                "ALTER TABLE routing_rules ADD COLUMN health_gate TEXT NOT NULL DEFAULT 'off';",
+            )?;
+        }
+
+        // Migration: optional per-rule payload transform (JSON template).
+        let has_transform_column = conn
+            .prepare("PRAGMA table_info(routing_rules)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "transform");
+
+        if !has_transform_column {
+            conn.execute_batch("ALTER TABLE routing_rules ADD COLUMN transform TEXT;")?;
+        }
+
+        // Migration: per-rule lease duration.
+        let has_lease_secs_column = conn
+            .prepare("PRAGMA table_info(routing_rules)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "lease_secs");
+
+        if !has_lease_secs_column {
+            conn.execute_batch(&format!(
+                "ALTER TABLE routing_rules ADD COLUMN lease_secs INTEGER NOT NULL DEFAULT {DEFAULT_LEASE_SECS};"
+            ))?;
+        }
+
+        // Routing rules version: bumped on every rule write, so in-memory
+        // copies of the rules notice changes made by other connections.
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS routing_rules_version (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                version INTEGER NOT NULL
+            );
+            INSERT OR IGNORE INTO routing_rules_version (id, version) VALUES (1, 0);
+            CREATE TRIGGER IF NOT EXISTS routing_rules_bump_insert AFTER INSERT ON routing_rules
+            BEGIN
+                UPDATE routing_rules_version SET version = version + 1;
+            END;
+            CREATE TRIGGER IF NOT EXISTS routing_rules_bump_update AFTER UPDATE ON routing_rules
+            BEGIN
+                UPDATE routing_rules_version SET version = version + 1;
+            END;
+            CREATE TRIGGER IF NOT EXISTS routing_rules_bump_delete AFTER DELETE ON routing_rules
+            BEGIN
+                UPDATE routing_rules_version SET version = version + 1;
+            END;",
+        )?;
+
+        // Full-text index over message payloads, keyed by the message rowid.
+        // Skipped when SQLite was built without FTS5; search then falls back
+        // to LIKE. Plain payloads are indexed by trigger, gzip payloads by
+        // `insert_message` (SQL cannot decompress them).
+        let has_fts_table = conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+
+        if !has_fts_table
+            && conn
+                .execute_batch(
+                    "CREATE VIRTUAL TABLE messages_fts USING fts5(payload, tokenize = 'trigram');",
+                )
+                .is_ok()
+        {
+            conn.execute_batch(
+                "CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages
+                     WHEN new.payload_encoding IS NULL
+                 BEGIN
+                     INSERT INTO messages_fts (rowid, payload) VALUES (new.rowid, new.payload);
+                 END;
+                 CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages
+                 BEGIN
+                     DELETE FROM messages_fts WHERE rowid = old.rowid;
+                 END;
+                 INSERT INTO messages_fts (rowid, payload)
+                     SELECT rowid, payload FROM messages WHERE payload_encoding IS NULL;",
+            )?;
+
+            let mut stmt = conn.prepare(
+                "SELECT rowid, payload, payload_encoding FROM messages
+                 WHERE payload_encoding IS NOT NULL",
+            )?;
+            let compressed = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, decode_payload(row, 1, 2)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (rowid, payload) in compressed {
+                conn.execute(
+                    "INSERT INTO messages_fts (rowid, payload) VALUES (?1, ?2)",
+                    params![rowid, payload],
+                )?;
+            }
+        }
+
+        // Config write audit trail
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS config_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                instance_id TEXT NOT NULL,
+                old_etag TEXT NOT NULL,
+                new_etag TEXT NOT NULL,
+                changed_paths TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                FOREIGN KEY (instance_id) REFERENCES instances(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_config_audit_instance
+                ON config_audit(instance_id, id DESC);",
+        )?;
+
         Ok(())
     }
 
@@ -369,7 +1056,7 @@ impl Registry {
     pub fn get_instance(&self, id: &str) -> Result<Option<Instance>> {
         self.conn
             .query_row(
-                "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid
+                "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid, started_at, restart_count
                  FROM instances WHERE id = ?1",
                 params![id],
                 |row| {
@@ -383,6 +1070,8 @@ impl Registry {
                         archived_at: row.get(6)?,
                         migration_run_id: row.get(7)?,
                         pid: row.get::<_, Option<i64>>(8)?.map(|p| p as u32),
+                        started_at: row.get(9)?,
+                        restart_count: row.get(10)?,
                     })
                 },
             )
@@ -394,7 +1083,7 @@ impl Registry {
     pub fn get_instance_by_name(&self, name: &str) -> Result<Option<Instance>> {
         self.conn
             .query_row(
-                "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid
+                "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid, started_at, restart_count
                  FROM instances WHERE name = ?1 AND archived_at IS NULL",
                 params![name],
                 |row| {
@@ -408,6 +1097,8 @@ impl Registry {
                         archived_at: row.get(6)?,
                         migration_run_id: row.get(7)?,
                         pid: row.get::<_, Option<i64>>(8)?.map(|p| p as u32),
+                        started_at: row.get(9)?,
+                        restart_count: row.get(10)?,
                     })
                 },
             )
@@ -415,11 +1106,26 @@ impl Registry {
             .context("Failed to query instance by name")
     }
 
+    /// IDs of archived instances that were named `name`, oldest archive
+    /// first. A name freed by archiving can be reused by a new instance with
+    /// a new ID, so history recorded under the old ID is not found by ID.
+    pub fn archived_instance_ids_by_name(&self, name: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM instances WHERE name = ?1 AND archived_at IS NOT NULL
+             ORDER BY archived_at, id",
+        )?;
+        let ids = stmt
+            .query_map(params![name], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .context("Failed to query archived instance ids")?;
+        Ok(ids)
+    }
+
     /// Find an archived instance by name.
     pub fn find_archived_instance_by_name(&self, name: &str) -> Result<Option<Instance>> {
         self.conn
             .query_row(
-                "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid
+                "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid, started_at, restart_count
                  FROM instances WHERE name = ?1 AND archived_at IS NOT NULL",
                 params![name],
                 |row| {
@@ -433,6 +1139,8 @@ impl Registry {
                         archived_at: row.get(6)?,
                         migration_run_id: row.get(7)?,
                         pid: row.get::<_, Option<i64>>(8)?.map(|p| p as u32),
+                        started_at: row.get(9)?,
+                        restart_count: row.get(10)?,
                     })
                 },
             )
@@ -456,23 +1164,45 @@ impl Registry {
     // ── Instance CRUD (Phase 13.1) ──────────────────────────────
 
     /// Archive (soft-delete) an instance by ID. Sets archived_at, clears status/pid.
-    pub fn archive_instance(&self, id: &str) -> Result<bool> {
+    /// Archiving an already-archived instance leaves it untouched.
+    pub fn archive_instance(&self, id: &str) -> Result<ArchiveOutcome> {
         let rows = self.conn.execute(
             "UPDATE instances SET archived_at = datetime('now'), status = 'stopped', pid = NULL
              WHERE id = ?1 AND archived_at IS NULL",
             params![id],
         )?;
-        Ok(rows > 0)
+        if rows > 0 {
+            return Ok(ArchiveOutcome::Archived);
+        }
+        let exists = self
+            .conn
+            .query_row("SELECT 1 FROM instances WHERE id = ?1", params![id], |_| {
+                Ok(())
+            })
+            .optional()
+            .context("Failed to query instance for archive")?;
+        Ok(if exists.is_some() {
+            ArchiveOutcome::AlreadyArchived
+        } else {
+            ArchiveOutcome::NotFound
+        })
     }
 
     /// Unarchive a previously archived instance by name.
     /// Caller must check active name/port uniqueness first.
-    pub fn unarchive_instance(&self, name: &str) -> Result<bool> {
+    pub fn unarchive_instance(&self, name: &str) -> Result<UnarchiveOutcome> {
         let rows = self.conn.execute(
             "UPDATE instances SET archived_at = NULL WHERE name = ?1 AND archived_at IS NOT NULL",
             params![name],
         )?;
-        Ok(rows > 0)
+        if rows > 0 {
+            return Ok(UnarchiveOutcome::Unarchived);
+        }
+        Ok(if self.get_instance_by_name(name)?.is_some() {
+            UnarchiveOutcome::AlreadyActive
+        } else {
+            UnarchiveOutcome::NotFound
+        })
     }
 
     /// Hard-delete an archived instance row and mutable related data.
@@ -515,29 +1245,44 @@ impl Registry {
         Ok(inst)
     }
 
-    /// Allocate the next available port in [start, end], skipping ports already
+    /// Allocate the next available port in `range`, skipping ports already
     /// in the DB and any in the excludes list. Linear scan, deterministic.
-    /// Returns None if no port is available.
-    pub fn allocate_port_with_excludes(
+    pub fn allocate_port(
         &self,
-        start: u16,
-        end: u16,
+        range: RangeInclusive<u16>,
         excludes: &[u16],
-    ) -> Result<Option<u16>> {
+    ) -> std::result::Result<u16, PortAllocError> {
+        let used = self.active_ports()?;
+        range
+            .clone()
+            .find(|port| !used.contains(port) && !excludes.contains(port))
+            .ok_or(PortAllocError::Exhausted { range })
+    }
+
+    /// Ports held by non-archived instances.
+    fn active_ports(&self) -> Result<std::collections::HashSet<u16>> {
         let mut stmt = self
             .conn
             .prepare("SELECT port FROM instances WHERE archived_at IS NULL")?;
-        let used: std::collections::HashSet<u16> = stmt
+        let used = stmt
             .query_map([], |row| Ok(row.get::<_, i64>(0)? as u16))?
             .filter_map(|r| r.ok())
             .collect();
+        Ok(used)
+    }
 
-        for port in start..=end {
-            if !used.contains(&port) && !excludes.contains(&port) {
-                return Ok(Some(port));
-            }
+    /// [`Self::allocate_port`] over [start, end], with exhaustion as `None`.
+    pub fn allocate_port_with_excludes(
+        &self,
+        start: u16,
+        end: u16,
+        excludes: &[u16],
+    ) -> Result<Option<u16>> {
+        match self.allocate_port(start..=end, excludes) {
+            Ok(port) => Ok(Some(port)),
+            Err(PortAllocError::Exhausted { .. }) => Ok(None),
+            Err(PortAllocError::Internal(e)) => Err(e),
         }
-        Ok(None)
     }
 
     /// Update the status of an instance by ID.
@@ -555,6 +1300,37 @@ impl Registry {
         Ok(())
     }
 
+    /// Move an instance to a different gateway port.
+    ///
+    /// Returns the name of the active instance already holding `port` (and
+    /// changes nothing), or `None` once the port has been reassigned. The
+    /// unique active-port index backstops races between the check and update.
+    pub fn reassign_port(&self, id: &str, port: u16) -> Result<Option<String>> {
+        let holder: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT name FROM instances
+                 WHERE port = ?1 AND archived_at IS NULL AND id != ?2",
+                params![i64::from(port), id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if holder.is_some() {
+            return Ok(holder);
+        }
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE instances SET port = ?1 WHERE id = ?2",
+                params![i64::from(port), id],
+            )
+            .context("Failed to update instance port")?;
+        if rows == 0 {
+            anyhow::bail!("No instance with id '{id}'");
+        }
+        Ok(None)
+    }
+
     /// Update the cached PID for an instance (best-effort cache; pidfile is authoritative).
     pub fn update_pid(&self, id: &str, pid: Option<u32>) -> Result<()> {
         let rows = self
@@ -570,6 +1346,67 @@ impl Registry {
         Ok(())
     }
 
+    /// Issue a new control plane token for instance `id`, replacing any
+    /// previous one. Only its SHA-256 hash is stored; the token itself is
+    /// returned for the instance's environment.
+    pub fn issue_instance_token(&self, id: &str) -> Result<String> {
+        let token = format!("zcp_{}", uuid::Uuid::new_v4().as_simple());
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE instances SET cp_token_hash = ?1 WHERE id = ?2",
+                params![hash_instance_token(&token), id],
+            )
+            .context("Failed to store instance token")?;
+        if rows == 0 {
+            anyhow::bail!("No instance with id '{id}'");
+        }
+        Ok(token)
+    }
+
+    /// Name of the active instance holding control plane token `token`.
+    pub fn instance_name_for_token(&self, token: &str) -> Result<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT name FROM instances WHERE cp_token_hash = ?1 AND archived_at IS NULL",
+                params![hash_instance_token(token)],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to query instance by token")
+    }
+
+    /// Record that an instance's daemon was just started (resets uptime).
+    pub fn record_started(&self, id: &str) -> Result<()> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE instances SET started_at = ?1 WHERE id = ?2",
+                params![now, id],
+            )
+            .context("Failed to record instance start time")?;
+        if rows == 0 {
+            anyhow::bail!("No instance with id '{id}'");
+        }
+        Ok(())
+    }
+
+    /// Increment an instance's restart counter.
+    pub fn increment_restart_count(&self, id: &str) -> Result<()> {
+        let rows = self
+            .conn
+            .execute(
+                "UPDATE instances SET restart_count = restart_count + 1 WHERE id = ?1",
+                params![id],
+            )
+            .context("Failed to increment restart count")?;
+        if rows == 0 {
+            anyhow::bail!("No instance with id '{id}'");
+        }
+        Ok(())
+    }
+
     /// Borrow the underlying connection (for rollback operations).
     pub fn conn(&self) -> &Connection {
         &self.conn
@@ -598,22 +1435,39 @@ impl Registry {
         Ok(())
     }
 
-    /// List agent events for an instance with pagination and filtering.
-    /// Returns (events, total_count).
+    /// List agent events for one or more instance IDs (e.g. an instance and
+    /// the archived instances that held its name before) with pagination and
+    /// filtering. Returns (events, total_count).
+    ///
+    /// `event_type` matches exactly, or by prefix when it ends in `.*`
+    /// (`tg.inbound.*` matches `tg.inbound.text`).
+    #[allow(clippy::too_many_arguments)]
     pub fn list_agent_events(
         &self,
-        instance_id: &str,
+        instance_ids: &[&str],
         limit: usize,
         offset: usize,
+        event_type: Option<&str>,
+        channel: Option<&str>,
         status_filter: Option<&str>,
         after: Option<&str>,
         before: Option<&str>,
     ) -> Result<(Vec<AgentEvent>, usize)> {
-        let mut where_clauses = vec!["instance_id = ?1".to_string()];
-        let mut param_idx = 2u32;
-        let mut bind_values: Vec<Box<dyn rusqlite::types::ToSql>> =
-            vec![Box::new(instance_id.to_string())];
+        let (id_clause, mut bind_values) = instance_id_in_clause(instance_ids);
+        let mut where_clauses = vec![id_clause];
+        let mut param_idx = bind_values.len() + 1;
 
+        if let Some(et) = event_type {
+            let op = if et.ends_with(".*") { "GLOB" } else { "=" };
+            where_clauses.push(format!("event_type {op} ?{param_idx}"));
+            bind_values.push(Box::new(et.to_string()));
+            param_idx += 1;
+        }
+        if let Some(ch) = channel {
+            where_clauses.push(format!("channel = ?{param_idx}"));
+            bind_values.push(Box::new(ch.to_string()));
+            param_idx += 1;
+        }
         if let Some(status) = status_filter {
             where_clauses.push(format!("status = ?{param_idx}"));
             bind_values.push(Box::new(status.to_string()));
@@ -956,17 +1810,16 @@ impl Registry {
         Ok(())
     }
 
-    /// Get aggregated usage for an instance within a time window.
+    /// Get aggregated usage for one or more instance IDs within a time window.
     pub fn get_agent_usage(
         &self,
-        instance_id: &str,
+        instance_ids: &[&str],
         window_start: Option<&str>,
         window_end: Option<&str>,
     ) -> Result<AgentUsageSummary> {
-        let mut where_clauses = vec!["instance_id = ?1".to_string()];
-        let mut bind_values: Vec<Box<dyn rusqlite::types::ToSql>> =
-            vec![Box::new(instance_id.to_string())];
-        let mut param_idx = 2u32;
+        let (id_clause, mut bind_values) = instance_id_in_clause(instance_ids);
+        let mut where_clauses = vec![id_clause];
+        let mut param_idx = bind_values.len() + 1;
 
         if let Some(start) = window_start {
             where_clauses.push(format!("created_at >= ?{param_idx}"));
@@ -1004,9 +1857,67 @@ impl Registry {
             .context("Failed to query agent usage")
     }
 
+    // ── Config audit ────────────────────────────────────────────
+
+    /// Record a successful config write. `changed_paths` are dotted paths.
+    pub fn insert_config_audit(
+        &self,
+        instance_id: &str,
+        old_etag: &str,
+        new_etag: &str,
+        changed_paths: &[String],
+        actor: &str,
+    ) -> Result<i64> {
+        let paths = serde_json::to_string(changed_paths)?;
+        self.conn
+            .execute(
+                "INSERT INTO config_audit (instance_id, old_etag, new_etag, changed_paths, actor)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![instance_id, old_etag, new_etag, paths, actor],
+            )
+            .context("Failed to insert config audit entry")?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Config writes for an instance, newest first. Returns (entries, total_count).
+    pub fn list_config_audit(
+        &self,
+        instance_id: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<ConfigAuditEntry>, usize)> {
+        let total: usize = self.conn.query_row(
+            "SELECT COUNT(*) FROM config_audit WHERE instance_id = ?1",
+            params![instance_id],
+            |row| row.get::<_, i64>(0).map(|v| v as usize),
+        )?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, instance_id, old_etag, new_etag, changed_paths, actor, created_at
+             FROM config_audit WHERE instance_id = ?1
+             ORDER BY id DESC LIMIT ?2 OFFSET ?3",
+        )?;
+        let rows = stmt.query_map(params![instance_id, limit as i64, offset as i64], |row| {
+            Ok(ConfigAuditEntry {
+                id: row.get(0)?,
+                instance_id: row.get(1)?,
+                old_etag: row.get(2)?,
+                new_etag: row.get(3)?,
+                changed_paths: row.get(4)?,
+                actor: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })?;
+        let entries = rows
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to list config audit entries")?;
+        Ok((entries, total))
+    }
+
     // ── Messaging (Phase 10.1) ─────────────────────────────────
 
     /// Create a routing rule. Returns the generated rule ID.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_routing_rule(
         &self,
         from: &str,
@@ -1014,13 +1925,17 @@ impl Registry {
         type_pattern: &str,
         max_retries: i64,
         ttl_secs: i64,
+        lease_secs: i64,
         auto_start: bool,
+        detect_cycles: bool,
+        health_gate: HealthGate,
+        transform: Option<&str>,
     ) -> Result<String> {
         let id = uuid::Uuid::new_v4().to_string();
         self.conn.execute(
-            "INSERT INTO routing_rules (id, from_instance, to_instance, type_pattern, max_retries, ttl_secs, auto_start)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![id, from, to, type_pattern, max_retries, ttl_secs, auto_start as i64],
+            "INSERT INTO routing_rules (id, from_instance, to_instance, type_pattern, max_retries, ttl_secs, auto_start, detect_cycles, health_gate, transform, lease_secs)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![id, from, to, type_pattern, max_retries, ttl_secs, auto_start as i64, detect_cycles as i64, health_gate.as_str(), transform, lease_secs],
         ).context("Failed to create routing rule")?;
         Ok(id)
     }
@@ -1028,7 +1943,7 @@ impl Registry {
     /// List all routing rules.
     pub fn list_routing_rules(&self) -> Result<Vec<RoutingRule>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, from_instance, to_instance, type_pattern, max_retries, ttl_secs, auto_start, created_at
+            "SELECT id, from_instance, to_instance, type_pattern, max_retries, ttl_secs, auto_start, created_at, detect_cycles, health_gate, transform, lease_secs
              FROM routing_rules ORDER BY created_at",
         )?;
         let rows = stmt.query_map([], |row| {
@@ -1040,6 +1955,10 @@ impl Registry {
                 max_retries: row.get(4)?,
                 ttl_secs: row.get(5)?,
                 auto_start: row.get::<_, i64>(6)? != 0,
+                detect_cycles: row.get::<_, i64>(8)? != 0,
+                health_gate: HealthGate::parse(&row.get::<_, String>(9)?).unwrap_or_default(),
+                transform: row.get(10)?,
+                lease_secs: row.get(11)?,
                 created_at: row.get(7)?,
             })
         })?;
@@ -1059,6 +1978,31 @@ impl Registry {
         Ok(rows > 0)
     }
 
+    /// Counter bumped on every routing rule insert, update or delete, from
+    /// any connection (see `cp::routing_cache`).
+    pub fn routing_rules_version(&self) -> Result<i64> {
+        self.conn
+            .query_row(
+                "SELECT version FROM routing_rules_version WHERE id = 1",
+                [],
+                |row| row.get(0),
+            )
+            .context("Failed to read routing rules version")
+    }
+
+    /// Strictest health gate among the routing rules into `to_instance`.
+    pub fn recipient_health_gate(&self, to_instance: &str) -> Result<HealthGate> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT DISTINCT health_gate FROM routing_rules WHERE to_instance = ?1")?;
+        let gates = stmt.query_map(params![to_instance], |row| row.get::<_, String>(0))?;
+        let mut strictest = HealthGate::Off;
+        for gate in gates {
+            strictest = strictest.max(HealthGate::parse(&gate?).unwrap_or_default());
+        }
+        Ok(strictest)
+    }
+
     /// Check if a route is allowed. Matches exact from/to and prefix match on type.
     /// Returns the matching rule if found.
     pub fn check_route_allowed(
@@ -1070,7 +2014,7 @@ impl Registry {
         // Fetch all rules matching from/to, then check type_pattern in Rust
         // (prefix match: "task.*" matches "task.handoff", "*" matches everything)
         let mut stmt = self.conn.prepare(
-            "SELECT id, from_instance, to_instance, type_pattern, max_retries, ttl_secs, auto_start, created_at
+            "SELECT id, from_instance, to_instance, type_pattern, max_retries, ttl_secs, auto_start, created_at, detect_cycles, health_gate, transform, lease_secs
              FROM routing_rules WHERE from_instance = ?1 AND to_instance = ?2",
         )?;
         let rows = stmt.query_map(params![from, to], |row| {
@@ -1082,6 +2026,10 @@ impl Registry {
                 max_retries: row.get(4)?,
                 ttl_secs: row.get(5)?,
                 auto_start: row.get::<_, i64>(6)? != 0,
+                detect_cycles: row.get::<_, i64>(8)? != 0,
+                health_gate: HealthGate::parse(&row.get::<_, String>(9)?).unwrap_or_default(),
+                transform: row.get(10)?,
+                lease_secs: row.get(11)?,
                 created_at: row.get(7)?,
             })
         })?;
@@ -1095,6 +2043,83 @@ impl Registry {
         Ok(None)
     }
 
+    /// The rule allowing `from -> to` for `message_type`, creating a minimal
+    /// one (exact type, no auto-start, cycle detection or health gate) when
+    /// none exists. Check and insert share one write transaction, so
+    /// concurrent callers cannot both create a rule. Returns the rule and
+    /// whether it was created.
+    pub fn ensure_routing_rule(
+        &self,
+        from: &str,
+        to: &str,
+        message_type: &str,
+        max_retries: i64,
+        ttl_secs: i64,
+    ) -> Result<(RoutingRule, bool)> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = (|| -> Result<(RoutingRule, bool)> {
+            if let Some(rule) = self.check_route_allowed(from, to, message_type)? {
+                return Ok((rule, false));
+            }
+            self.create_routing_rule(
+                from,
+                to,
+                message_type,
+                max_retries,
+                ttl_secs,
+                DEFAULT_LEASE_SECS,
+                false,
+                false,
+                HealthGate::Off,
+                None,
+            )?;
+            let rule = self
+                .check_route_allowed(from, to, message_type)?
+                .ok_or_else(|| anyhow::anyhow!("Routing rule not found after insert"))?;
+            Ok((rule, true))
+        })();
+        match result {
+            Ok(v) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(v)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Instances a correlation thread has been delivered to so far, in send
+    /// order without repeats: the recipients of its messages.
+    pub fn correlation_route_path(&self, correlation_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT to_instance FROM messages WHERE correlation_id = ?1
+             ORDER BY created_at, rowid",
+        )?;
+        let rows = stmt.query_map(params![correlation_id], |row| row.get::<_, String>(0))?;
+        let mut path: Vec<String> = Vec::new();
+        for row in rows {
+            let instance = row?;
+            if !path.contains(&instance) {
+                path.push(instance);
+            }
+        }
+        Ok(path)
+    }
+
+    /// Every message on a correlation thread, in send order.
+    pub fn correlation_messages(&self, correlation_id: &str) -> Result<Vec<Message>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at, dead_letter_reason, payload_encoding, content_type, priority
+             FROM messages WHERE correlation_id = ?1
+             ORDER BY created_at, rowid",
+        )?;
+        let rows = stmt.query_map(params![correlation_id], Self::row_to_message)?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to query correlation thread")
+    }
+
     /// Check if an idempotency key already exists. Returns the existing message ID if so.
     pub fn check_idempotency_key(&self, key: &str) -> Result<Option<String>> {
         self.conn
@@ -1107,22 +2132,131 @@ impl Registry {
             .context("Failed to check idempotency key")
     }
 
-    /// Enqueue a new message. Returns the created Message.
+    /// Like [`Self::check_idempotency_key`], but only a message created in
+    /// the last `window_secs` counts as holding the key.
+    pub fn check_idempotency_key_within(
+        &self,
+        key: &str,
+        window_secs: i64,
+    ) -> Result<Option<String>> {
+        let cutoff = (chrono::Utc::now() - chrono::Duration::seconds(window_secs))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        self.conn
+            .query_row(
+                "SELECT id FROM messages WHERE idempotency_key = ?1 AND created_at >= ?2",
+                params![key, cutoff],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to check idempotency key")
+    }
+
+    /// Take `key` from the message holding it (one outside its dedup window)
+    /// so a new message can use it, recording an `idempotency_key_released`
+    /// event on the old holder. Called inside the enqueue transaction.
+    fn release_idempotency_key(&self, key: &str) -> Result<()> {
+        let released: Vec<String> = self
+            .conn
+            .prepare(
+                "UPDATE messages SET idempotency_key = NULL
+                 WHERE idempotency_key = ?1
+                 RETURNING id",
+            )?
+            .query_map(params![key], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()
+            .context("Failed to release idempotency key")?;
+        for id in &released {
+            self.append_message_event(id, "idempotency_key_released", Some(key))?;
+        }
+        Ok(())
+    }
+
+    /// Enqueue a new message, retrying while the database is busy (see
+    /// [`Self::with_retry`]). Returns the created Message.
     pub fn enqueue_message(&self, msg: &NewMessage) -> Result<Message> {
+        // A one-message transaction, so a retry never re-runs half an insert
+        // (the row and its full-text index entry)
+        let outcome = self
+            .with_retry(|reg| reg.enqueue_messages_batch(std::slice::from_ref(msg)))?
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("Message {} not found after insert", msg.id))?;
+        match outcome {
+            BatchEnqueueOutcome::Queued(message) => Ok(*message),
+            BatchEnqueueOutcome::Deduplicated(existing_id) => anyhow::bail!(
+                "Idempotency key of message {} is already used by message {existing_id}",
+                msg.id
+            ),
+        }
+    }
+
+    /// Enqueue several messages in one transaction: either all are inserted
+    /// or none are. A message whose idempotency key is already taken (by a
+    /// stored message or an earlier one in the batch) is skipped and
+    /// reported instead of failing the batch. Returns one outcome per
+    /// message, in input order.
+    pub fn enqueue_messages_batch(&self, msgs: &[NewMessage]) -> Result<Vec<BatchEnqueueOutcome>> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = (|| -> Result<Vec<Option<String>>> {
+            let mut existing_ids = Vec::with_capacity(msgs.len());
+            for msg in msgs {
+                let existing = match (&msg.idempotency_key, msg.idempotency_window_secs) {
+                    (Some(key), Some(window_secs)) => {
+                        let existing = self.check_idempotency_key_within(key, window_secs)?;
+                        if existing.is_none() {
+                            self.release_idempotency_key(key)?;
+                        }
+                        existing
+                    }
+                    (Some(key), None) => self.check_idempotency_key(key)?,
+                    (None, _) => None,
+                };
+                if existing.is_none() {
+                    self.insert_message(msg)?;
+                }
+                existing_ids.push(existing);
+            }
+            Ok(existing_ids)
+        })();
+        let existing_ids = match result {
+            Ok(ids) => {
+                self.conn.execute_batch("COMMIT")?;
+                ids
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                return Err(e);
+            }
+        };
+
+        msgs.iter()
+            .zip(existing_ids)
+            .map(|(msg, existing)| match existing {
+                Some(id) => Ok(BatchEnqueueOutcome::Deduplicated(id)),
+                None => self
+                    .get_message(&msg.id)?
+                    .map(|message| BatchEnqueueOutcome::Queued(Box::new(message)))
+                    .ok_or_else(|| anyhow::anyhow!("Message {} not found after insert", msg.id)),
+            })
+            .collect()
+    }
+
+    fn insert_message(&self, msg: &NewMessage) -> Result<()> {
         let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let expires_at = (chrono::Utc::now() + chrono::Duration::seconds(msg.ttl_secs))
             .format("%Y-%m-%d %H:%M:%S")
             .to_string();
+        let (payload, payload_encoding) = encode_payload(&msg.payload)?;
 
         self.conn.execute(
-            "INSERT INTO messages (id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, expires_at, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'queued', 0, ?9, ?10, ?11, ?12)",
+            "INSERT INTO messages (id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, expires_at, created_at, updated_at, payload_encoding, content_type, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'queued', 0, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
             params![
                 msg.id,
                 msg.from_instance,
                 msg.to_instance,
                 msg.message_type,
-                msg.payload,
+                payload,
                 msg.correlation_id,
                 msg.idempotency_key,
                 msg.hop_count,
@@ -1130,19 +2264,118 @@ impl Registry {
                 expires_at,
                 now,
                 now,
+                payload_encoding,
+                msg.content_type,
+                msg.priority,
             ],
         ).context("Failed to enqueue message")?;
 
-        // Fetch back the full row
-        self.get_message(&msg.id)?
-            .ok_or_else(|| anyhow::anyhow!("Message {} not found after insert", msg.id))
+        if payload_encoding.is_some() && self.has_message_fts()? {
+            self.conn
+                .execute(
+                    "INSERT INTO messages_fts (rowid, payload) VALUES (last_insert_rowid(), ?1)",
+                    params![msg.payload],
+                )
+                .context("Failed to index message payload")?;
+        }
+        Ok(())
+    }
+
+    /// Whether the `messages_fts` index exists (SQLite has FTS5).
+    fn has_message_fts(&self) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'",
+                [],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    /// Messages whose payload contains `query` (case-insensitive), narrowed
+    /// by `filters`, newest first. Returns one page and the total match count.
+    ///
+    /// Uses the `messages_fts` trigram index when present, which needs at
+    /// least three characters to match; shorter queries, and databases
+    /// without FTS5, fall back to `LIKE`, which only sees uncompressed
+    /// payloads.
+    pub fn search_messages(
+        &self,
+        query: &str,
+        filters: &MessageSearchFilters,
+        limit: usize,
+        offset: usize,
+    ) -> Result<(Vec<Message>, usize)> {
+        let (matcher, pattern) = if query.chars().count() >= 3 && self.has_message_fts()? {
+            (
+                "rowid IN (SELECT rowid FROM messages_fts WHERE messages_fts MATCH ?1)",
+                format!("\"{}\"", query.replace('"', "\"\"")),
+            )
+        } else {
+            let escaped = query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            (
+                "payload_encoding IS NULL AND payload LIKE ?1 ESCAPE '\\'",
+                format!("%{escaped}%"),
+            )
+        };
+        let filter = format!(
+            "{matcher}
+               AND (?2 IS NULL OR status = ?2)
+               AND (?3 IS NULL OR from_instance = ?3)
+               AND (?4 IS NULL OR to_instance = ?4)
+               AND (?5 IS NULL OR message_type = ?5)"
+        );
+        let args = params![
+            pattern,
+            filters.status,
+            filters.from_instance,
+            filters.to_instance,
+            filters.message_type,
+        ];
+
+        let total: usize = self
+            .conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM messages WHERE {filter}"),
+                args,
+                |row| row.get::<_, i64>(0).map(|v| v as usize),
+            )
+            .context("Failed to count message search results")?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at, dead_letter_reason, payload_encoding, content_type, priority
+             FROM messages WHERE {filter}
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?6 OFFSET ?7"
+        ))?;
+        let rows = stmt.query_map(
+            params![
+                pattern,
+                filters.status,
+                filters.from_instance,
+                filters.to_instance,
+                filters.message_type,
+                limit as i64,
+                offset as i64,
+            ],
+            Self::row_to_message,
+        )?;
+        let messages = rows
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to search messages")?;
+        Ok((messages, total))
     }
 
     /// Get a message by ID.
     pub fn get_message(&self, id: &str) -> Result<Option<Message>> {
         self.conn
             .query_row(
-                "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at
+                "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at, dead_letter_reason, payload_encoding, content_type, priority
                  FROM messages WHERE id = ?1",
                 params![id],
                 Self::row_to_message,
@@ -1151,57 +2384,283 @@ impl Registry {
             .context("Failed to query message")
     }
 
+    /// Every message created at or after `since` (all when `None`), oldest
+    /// first, fetched lazily in batches.
+    pub fn iter_messages(&self, since: Option<&str>) -> MessageIter<'_> {
+        MessageIter {
+            registry: self,
+            since: since.map(str::to_string),
+            after: None,
+            batch: Vec::new().into_iter(),
+            exhausted: false,
+        }
+    }
+
+    fn message_batch_after(
+        &self,
+        since: Option<&str>,
+        after: Option<&(String, String)>,
+        limit: usize,
+    ) -> Result<Vec<Message>> {
+        let (after_created, after_id) = after.map_or((None, None), |(c, i)| (Some(c), Some(i)));
+        let mut stmt = self.conn.prepare(
+            "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at, dead_letter_reason, payload_encoding, content_type, priority
+             FROM messages
+             WHERE (?1 IS NULL OR created_at >= ?1)
+               AND (?2 IS NULL OR created_at > ?2 OR (created_at = ?2 AND id > ?3))
+             ORDER BY created_at, id
+             LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            params![since, after_created, after_id, limit as i64],
+            Self::row_to_message,
+        )?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to page messages")
+    }
+
     /// Atomically lease the oldest queued message for an instance.
-    /// Sets status to 'leased' and lease_expires_at to now + 90s.
+    /// Sets status to 'leased' and lease_expires_at per its routing rule's
+    /// `lease_secs` (90s when no rule matches).
     pub fn lease_pending_message(&self, to_instance: &str) -> Result<Option<Message>> {
-        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-        let lease_expires = (chrono::Utc::now() + chrono::Duration::seconds(90))
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string();
+        Ok(self
+            .lease_pending_messages(to_instance, 1, DEFAULT_LEASE_SECS)?
+            .pop())
+    }
 
-        // Find oldest queued message where next_attempt_at has passed (or is null)
-        let msg_id: Option<String> = self
-            .conn
-            .query_row(
-                "SELECT id FROM messages
-                 WHERE to_instance = ?1 AND status = 'queued'
-                 AND (next_attempt_at IS NULL OR next_attempt_at <= ?2)
-                 ORDER BY created_at ASC LIMIT 1",
-                params![to_instance, now],
-                |row| row.get(0),
-            )
-            .optional()
-            .context("Failed to query pending message")?;
+    /// Atomically lease up to `max` queued messages for an instance:
+    /// highest priority first, then expedited messages (latest expedite
+    /// first), then oldest first, and returned in that order. Each lease lasts the `lease_secs` of the routing rule
+    /// that currently allows the message, or `default_lease_secs` when none
+    /// does (e.g. the rule was deleted after enqueue).
+    pub fn lease_pending_messages(
+        &self,
+        to_instance: &str,
+        max: usize,
+        default_lease_secs: i64,
+    ) -> Result<Vec<Message>> {
+        self.lease_pending_messages_routed(to_instance, max, default_lease_secs, |from, ty| {
+            self.check_route_allowed(from, to_instance, ty)
+        })
+    }
+
+    /// [`Self::lease_pending_messages`], looking up the allowing rule with
+    /// `route(from, message_type)` instead of scanning `routing_rules`, so
+    /// callers holding a rule cache can answer from it.
+    pub fn lease_pending_messages_routed(
+        &self,
+        to_instance: &str,
+        max: usize,
+        default_lease_secs: i64,
+        route: impl FnMut(&str, &str) -> Result<Option<RoutingRule>>,
+    ) -> Result<Vec<Message>> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = self.claim_pending_messages(to_instance, max, default_lease_secs, route);
+        match result {
+            Ok(leased) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(leased)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
 
-        let msg_id = match msg_id {
-            Some(id) => id,
-            None => return Ok(None),
+    /// Body of [`Self::lease_pending_messages_routed`]; runs inside its
+    /// transaction.
+    fn claim_pending_messages(
+        &self,
+        to_instance: &str,
+        max: usize,
+        default_lease_secs: i64,
+        mut route: impl FnMut(&str, &str) -> Result<Option<RoutingRule>>,
+    ) -> Result<Vec<Message>> {
+        let now = chrono::Utc::now();
+        let lease_expires_at = |secs: i64| {
+            (now + chrono::Duration::seconds(secs))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
         };
+        let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
 
-        // Transition to leased
-        self.conn.execute(
-            "UPDATE messages SET status = 'leased', lease_expires_at = ?1, updated_at = ?2 WHERE id = ?3",
-            params![lease_expires, now, msg_id],
+        // Claim the next queued messages where next_attempt_at has passed (or is null)
+        // in a single statement, so concurrent leasers (or the reaper) can never
+        // both select the same row before either marks it leased.
+        let mut stmt = self.conn.prepare(
+            "UPDATE messages SET status = 'leased', lease_expires_at = ?1, updated_at = ?2
+             WHERE status = 'queued' AND id IN (
+                 SELECT id FROM messages
+                 WHERE to_instance = ?3 AND status = 'queued'
+                 AND (next_attempt_at IS NULL OR next_attempt_at <= ?2)
+                 ORDER BY priority DESC, expedited_at IS NULL, expedited_at DESC,
+                          created_at ASC, rowid ASC
+                 LIMIT ?4
+             )
+             RETURNING id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at, dead_letter_reason, payload_encoding, content_type, priority, rowid, expedited_at",
         )?;
+        let limit = i64::try_from(max).unwrap_or(i64::MAX);
+        let default_expires_at = lease_expires_at(default_lease_secs);
+        let mut leased = stmt
+            .query_map(
+                params![default_expires_at, now_str, to_instance, limit],
+                |row| {
+                    Ok((
+                        Self::row_to_message(row)?,
+                        (row.get::<_, i64>(20)?, row.get::<_, Option<String>>(21)?),
+                    ))
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to lease pending messages")?;
+
+        // Rules match on type patterns evaluated here, not in SQL, so apply
+        // each rule's lease duration per message (one lookup per sender/type)
+        let mut lease_secs_by_route: HashMap<(String, String), i64> = HashMap::new();
+        for (msg, _) in &mut leased {
+            let key = (msg.from_instance.clone(), msg.message_type.clone());
+            let lease_secs = if let Some(&secs) = lease_secs_by_route.get(&key) {
+                secs
+            } else {
+                let secs = route(&msg.from_instance, &msg.message_type)?
+                    .map_or(default_lease_secs, |rule| rule.lease_secs);
+                lease_secs_by_route.insert(key, secs);
+                secs
+            };
+            if lease_secs != default_lease_secs {
+                let expires_at = lease_expires_at(lease_secs);
+                self.conn.execute(
+                    "UPDATE messages SET lease_expires_at = ?1 WHERE id = ?2",
+                    params![expires_at, msg.id],
+                )?;
+                msg.lease_expires_at = Some(expires_at);
+            }
+        }
 
-        self.get_message(&msg_id)
+        // RETURNING order is unspecified
+        leased.sort_by_key(|(msg, (rowid, expedited_at))| {
+            (
+                std::cmp::Reverse(msg.priority),
+                expedited_at.is_none(),
+                std::cmp::Reverse(expedited_at.clone()),
+                msg.created_at.clone(),
+                *rowid,
+            )
+        });
+        Ok(leased.into_iter().map(|(msg, _)| msg).collect())
     }
 
-    /// Acknowledge a message (mark as acknowledged).
-    pub fn acknowledge_message(&self, id: &str) -> Result<bool> {
+    /// Acknowledge a leased message and record the `acknowledged` event.
+    /// `result` (the consumer's processing outcome, already redacted) is
+    /// stored in the event detail for the producer to read back.
+    pub fn acknowledge_message(
+        &self,
+        id: &str,
+        result: Option<&serde_json::Value>,
+    ) -> Result<bool> {
         let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let rows = self.conn.execute(
             "UPDATE messages SET status = 'acknowledged', updated_at = ?1 WHERE id = ?2 AND status = 'leased'",
             params![now, id],
         )?;
-        Ok(rows > 0)
+        if rows == 0 {
+            return Ok(false);
+        }
+        self.record_lease_outcome_with(id, "acknowledged", result)?;
+        Ok(true)
+    }
+
+    /// Push a live lease's expiry out to now + `secs` and append a
+    /// `lease_extended` event with the new expiry. Returns the new
+    /// `lease_expires_at`, or `None` if the message is not leased or its
+    /// lease has already expired.
+    pub fn extend_lease(&self, id: &str, secs: i64) -> Result<Option<String>> {
+        let now = chrono::Utc::now();
+        let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
+        let lease_expires = (now + chrono::Duration::seconds(secs))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = (|| -> Result<Option<String>> {
+            let rows = self.conn.execute(
+                "UPDATE messages SET lease_expires_at = ?1, updated_at = ?2
+                 WHERE id = ?3 AND status = 'leased' AND lease_expires_at >= ?2",
+                params![lease_expires, now_str, id],
+            )?;
+            if rows == 0 {
+                return Ok(None);
+            }
+            let detail = serde_json::json!({
+                "secs": secs,
+                "lease_expires_at": lease_expires,
+            });
+            self.append_message_event(id, "lease_extended", Some(&detail.to_string()))?;
+            Ok(Some(lease_expires))
+        })();
+        match result {
+            Ok(extended) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(extended)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Negatively acknowledge a leased message: end the lease with a
+    /// `nacked` event and requeue it with the same backoff as
+    /// [`Self::retry_message`], or dead-letter it ("nacked past max_retries")
+    /// once retries are exhausted, on the same limit the lease reaper uses.
+    /// Returns `None` if the message is not leased.
+    pub fn nack_message(&self, id: &str) -> Result<Option<NackOutcome>> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = (|| -> Result<Option<NackOutcome>> {
+            let counts: Option<(i64, i64)> = self
+                .conn
+                .query_row(
+                    "SELECT retry_count, max_retries FROM messages WHERE id = ?1 AND status = 'leased'",
+                    params![id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            let Some((retry_count, max_retries)) = counts else {
+                return Ok(None);
+            };
+            self.record_lease_outcome(id, "nacked")?;
+            if retry_count + 1 >= max_retries {
+                self.dead_letter_message(id, "nacked past max_retries")?;
+                return Ok(Some(NackOutcome::DeadLettered));
+            }
+            // Not `retry_message`: its busy retries would sleep inside this
+            // transaction
+            let next_attempt_at = self.schedule_retry(id)?;
+            let detail = serde_json::json!({
+                "attempt": retry_count + 2,
+                "next_attempt_at": next_attempt_at,
+            });
+            self.append_message_event(id, "retry_scheduled", Some(&detail.to_string()))?;
+            Ok(Some(NackOutcome::Requeued(next_attempt_at)))
+        })();
+        match result {
+            Ok(outcome) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(outcome)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
     }
 
     /// Get messages with expired leases (leased + lease_expires_at < now).
     pub fn get_expired_leases(&self) -> Result<Vec<Message>> {
         let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let mut stmt = self.conn.prepare(
-            "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at
+            "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at, dead_letter_reason, payload_encoding, content_type, priority
              FROM messages WHERE status = 'leased' AND lease_expires_at < ?1",
         )?;
         let rows = stmt.query_map(params![now], Self::row_to_message)?;
@@ -1216,7 +2675,7 @@ impl Registry {
     pub fn get_ttl_expired_messages(&self) -> Result<Vec<Message>> {
         let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
         let mut stmt = self.conn.prepare(
-            "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at
+            "SELECT id, from_instance, to_instance, message_type, payload, correlation_id, idempotency_key, hop_count, status, retry_count, max_retries, next_attempt_at, lease_expires_at, expires_at, created_at, updated_at, dead_letter_reason, payload_encoding, content_type, priority
              FROM messages WHERE status IN ('queued', 'leased') AND expires_at < ?1",
         )?;
         let rows = stmt.query_map(params![now], Self::row_to_message)?;
@@ -1228,7 +2687,13 @@ impl Registry {
     }
 
     /// Retry a message: increment retry_count, set backoff, return to queued.
-    pub fn retry_message(&self, id: &str) -> Result<()> {
+    /// Returns the scheduled `next_attempt_at`. Retried while the database
+    /// is busy (see [`Self::with_retry`]).
+    pub fn retry_message(&self, id: &str) -> Result<String> {
+        self.with_retry(|reg| reg.schedule_retry(id))
+    }
+
+    fn schedule_retry(&self, id: &str) -> Result<String> {
         let now = chrono::Utc::now();
         let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
 
@@ -1253,82 +2718,591 @@ impl Registry {
             "UPDATE messages SET status = 'queued', retry_count = ?1, next_attempt_at = ?2, lease_expires_at = NULL, updated_at = ?3 WHERE id = ?4",
             params![new_retry, next_attempt, now_str, id],
         )?;
-        Ok(())
+        Ok(next_attempt)
     }
 
     /// Move a message to dead_letter status.
+    ///
+    /// Also records a `message_dead_lettered` agent event on the recipient
+    /// instance, carrying the message's correlation ID, so failures line up
+    /// with instance activity. Skipped when the recipient no longer resolves
+    /// (archived or deleted).
     pub fn dead_letter_message(&self, id: &str, reason: &str) -> Result<()> {
         let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
-        self.conn.execute(
-            "UPDATE messages SET status = 'dead_letter', updated_at = ?1 WHERE id = ?2",
-            params![now, id],
-        )?;
+        let updated: Option<(String, String, String, Option<String>)> = self
+            .conn
+            .query_row(
+                "UPDATE messages SET status = 'dead_letter', dead_letter_reason = ?1, updated_at = ?2 WHERE id = ?3
+                 RETURNING from_instance, to_instance, message_type, correlation_id",
+                params![reason, now, id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
         self.append_message_event(id, "dead_lettered", Some(reason))?;
-        Ok(())
+
+        let Some((from_instance, to_instance, message_type, correlation_id)) = updated else {
+            return Ok(());
+        };
+        let Some(recipient) = self.get_instance_by_name(&to_instance)? else {
+            return Ok(());
+        };
+        let metadata = serde_json::json!({
+            "message_id": id,
+            "from_instance": from_instance,
+            "message_type": message_type,
+            "reason": reason,
+        });
+        self.insert_agent_event(&AgentEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            instance_id: recipient.id,
+            event_type: "message_dead_lettered".to_string(),
+            channel: None,
+            summary: Some(format!(
+                "Message {id} ({message_type}) from {from_instance} dead-lettered: {reason}"
+            )),
+            status: "failed".to_string(),
+            duration_ms: None,
+            correlation_id,
+            metadata: Some(metadata.to_string()),
+            created_at: now,
+        })
     }
 
-    /// Append an audit event for a message.
-    pub fn append_message_event(
-        &self,
-        message_id: &str,
-        event_type: &str,
-        detail: Option<&str>,
-    ) -> Result<()> {
-        self.conn
-            .execute(
-                "INSERT INTO message_events (message_id, event_type, detail) VALUES (?1, ?2, ?3)",
-                params![message_id, event_type, detail],
-            )
-            .context("Failed to insert message event")?;
-        Ok(())
+    /// Dead-letter a message whose TTL elapsed before it was acknowledged.
+    ///
+    /// Unlike `dead_letter_message`, records reason `ttl_expired` and a distinct
+    /// `ttl_expired` event so the audit trail separates expiry from retry
+    /// exhaustion. Status change and event are written in one transaction.
+    /// Returns false if the message was no longer queued/leased.
+    pub fn expire_message(&self, id: &str) -> Result<bool> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = (|| -> Result<bool> {
+            let rows = self.conn.execute(
+                "UPDATE messages SET status = 'dead_letter', dead_letter_reason = 'ttl_expired', updated_at = ?1
+                 WHERE id = ?2 AND status IN ('queued', 'leased')",
+                params![now, id],
+            )?;
+            if rows > 0 {
+                let expires_at: String = self.conn.query_row(
+                    "SELECT expires_at FROM messages WHERE id = ?1",
+                    params![id],
+                    |row| row.get(0),
+                )?;
+                self.append_message_event(
+                    id,
+                    "ttl_expired",
+                    Some(&format!("expired at {expires_at}")),
+                )?;
+            }
+            Ok(rows > 0)
+        })();
+        match result {
+            Ok(expired) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(expired)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
     }
 
-    /// Get queued messages where recipient is stopped and routing rule has auto_start.
-    pub fn get_instances_needing_autostart(&self) -> Result<Vec<(Message, String)>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT m.id, m.from_instance, m.to_instance, m.message_type, m.payload, m.correlation_id, m.idempotency_key, m.hop_count, m.status, m.retry_count, m.max_retries, m.next_attempt_at, m.lease_expires_at, m.expires_at, m.created_at, m.updated_at, r.to_instance
-             FROM messages m
-             JOIN routing_rules r ON m.from_instance = r.from_instance AND m.to_instance = r.to_instance AND r.auto_start = 1
-             JOIN instances i ON i.name = m.to_instance AND i.archived_at IS NULL AND i.status = 'stopped'
-             WHERE m.status = 'queued'
-             GROUP BY m.to_instance",
-        )?;
-        let rows = stmt.query_map([], |row| {
-            let msg = Message {
-                id: row.get(0)?,
-                from_instance: row.get(1)?,
-                to_instance: row.get(2)?,
-                message_type: row.get(3)?,
-                payload: row.get(4)?,
-                correlation_id: row.get(5)?,
-                idempotency_key: row.get(6)?,
-                hop_count: row.get(7)?,
-                status: row.get(8)?,
-                retry_count: row.get(9)?,
-                max_retries: row.get(10)?,
-                next_attempt_at: row.get(11)?,
-                lease_expires_at: row.get(12)?,
-                expires_at: row.get(13)?,
-                created_at: row.get(14)?,
-                updated_at: row.get(15)?,
-            };
-            let instance_name: String = row.get(16)?;
-            Ok((msg, instance_name))
-        })?;
-        let mut results = Vec::new();
-        for row in rows {
-            results.push(row?);
+    /// Cancel every queued/leased message addressed to `to_instance`.
+    ///
+    /// Sets status `cancelled`, records `reason` (in `dead_letter_reason`, the
+    /// message's terminal-reason column) and appends a `cancelled` event per
+    /// message, all in one transaction. Returns the number cancelled.
+    pub fn cancel_messages_for_instance(&self, to_instance: &str, reason: &str) -> Result<usize> {
+        self.conn.execute_batch("BEGIN")?;
+        let result = self.cancel_pending_messages(to_instance, reason);
+        match result {
+            Ok(count) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(count)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
         }
-        Ok(results)
     }
 
-    fn row_to_message(row: &rusqlite::Row<'_>) -> rusqlite::Result<Message> {
-        Ok(Message {
+    /// Archive instance `id` and cancel the queued/leased messages addressed
+    /// to `name` in one transaction, so a failure leaves neither change
+    /// applied. Messages are only cancelled when this call archives the
+    /// instance. Returns the archive outcome and the number cancelled.
+    pub fn archive_instance_cancelling_messages(
+        &self,
+        id: &str,
+        name: &str,
+        reason: &str,
+    ) -> Result<(ArchiveOutcome, usize)> {
+        self.conn.execute_batch("BEGIN")?;
+        let result = (|| -> Result<(ArchiveOutcome, usize)> {
+            let outcome = self.archive_instance(id)?;
+            let cancelled = match outcome {
+                ArchiveOutcome::Archived => self.cancel_pending_messages(name, reason)?,
+                _ => 0,
+            };
+            Ok((outcome, cancelled))
+        })();
+        match result {
+            Ok(done) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(done)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Cancel pending messages for `to_instance`; caller owns the transaction.
+    fn cancel_pending_messages(&self, to_instance: &str, reason: &str) -> Result<usize> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let ids: Vec<String> = self
+            .conn
+            .prepare(
+                "UPDATE messages SET status = 'cancelled', dead_letter_reason = ?1,
+                        lease_expires_at = NULL, updated_at = ?2
+                 WHERE to_instance = ?3 AND status IN ('queued', 'leased')
+                 RETURNING id",
+            )?
+            .query_map(params![reason, now, to_instance], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        for id in &ids {
+            self.append_message_event(id, "cancelled", Some(reason))?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Move a queued message to the front of its recipient's queue.
+    ///
+    /// The message's priority is raised to the highest among its recipient's
+    /// queued messages, `expedited_at` is set (the lease order puts it ahead
+    /// of the rest of that priority) and any retry backoff (`next_attempt_at`)
+    /// is cleared, making it the next to lease. `created_at` is left alone.
+    /// Appends an `expedited` event. Returns `expedited_at`, or `None` if the
+    /// message is not queued.
+    pub fn expedite_message(&self, id: &str) -> Result<Option<String>> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = (|| -> Result<Option<String>> {
+            let priority: Option<i64> = self
+                .conn
+                .query_row(
+                    "UPDATE messages SET next_attempt_at = NULL, updated_at = ?1, expedited_at = ?1,
+                            priority = (
+                                SELECT MAX(q.priority) FROM messages q
+                                WHERE q.to_instance = messages.to_instance AND q.status = 'queued'
+                            )
+                     WHERE id = ?2 AND status = 'queued'
+                     RETURNING priority",
+                    params![now, id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(priority) = priority else {
+                return Ok(None);
+            };
+            self.append_message_event(id, "expedited", Some(&format!("priority {priority}")))?;
+            Ok(Some(now.clone()))
+        })();
+        match result {
+            Ok(expedited_at) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(expedited_at)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// Delete audit events whose message no longer exists. Events of live
+    /// messages are untouched (the delete trigger would abort the statement
+    /// otherwise). Delete the message rows with foreign keys deferred and
+    /// call this in the same transaction. Returns the number of events
+    /// removed.
+    pub fn purge_events_for_deleted_messages(&self) -> Result<usize> {
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM message_events
+                 WHERE NOT EXISTS (SELECT 1 FROM messages WHERE messages.id = message_events.message_id)",
+                [],
+            )
+            .context("Failed to purge orphaned message events")?;
+        Ok(removed)
+    }
+
+    /// Delete terminal messages last updated before `cutoff`
+    /// (`%Y-%m-%d %H:%M:%S`) along with their audit events, in one
+    /// transaction. `status` is `dead_letter`, `acknowledged`, or `expired`
+    /// (dead-lettered for `ttl_expired`); queued and leased messages are never
+    /// purged. Returns the number of messages and events deleted; events
+    /// orphaned earlier by other deletes are left alone and not counted.
+    pub fn purge_messages(&self, status: &str, cutoff: &str) -> Result<(usize, usize)> {
+        let filter = match status {
+            "dead_letter" => "status = 'dead_letter'",
+            "acknowledged" => "status = 'acknowledged'",
+            "expired" => "status = 'dead_letter' AND dead_letter_reason = 'ttl_expired'",
+            other => anyhow::bail!("Cannot purge messages with status '{other}'"),
+        };
+        self.conn
+            .execute_batch("BEGIN; PRAGMA defer_foreign_keys = ON;")?;
+        let result = (|| -> Result<(usize, usize)> {
+            let ids = self
+                .conn
+                .prepare(&format!(
+                    "SELECT id FROM messages WHERE {filter} AND updated_at < ?1"
+                ))?
+                .query_map(params![cutoff], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let mut messages = 0;
+            let mut events = 0;
+            for id in &ids {
+                messages += self
+                    .conn
+                    .execute("DELETE FROM messages WHERE id = ?1", params![id])?;
+                events += self.conn.execute(
+                    "DELETE FROM message_events WHERE message_id = ?1",
+                    params![id],
+                )?;
+            }
+            Ok((messages, events))
+        })();
+        match result {
+            Ok(counts) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(counts)
+            }
+            Err(e) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    /// All audit events for a message, oldest first.
+    pub fn get_message_events(&self, message_id: &str) -> Result<Vec<MessageEvent>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, message_id, event_type, detail, created_at
+             FROM message_events WHERE message_id = ?1 ORDER BY id ASC",
+        )?;
+        let rows = stmt.query_map(params![message_id], |row| {
+            Ok(MessageEvent {
+                id: row.get(0)?,
+                message_id: row.get(1)?,
+                event_type: row.get(2)?,
+                detail: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        let mut events = Vec::new();
+        for row in rows {
+            events.push(row?);
+        }
+        Ok(events)
+    }
+
+    /// Append a `leased` event for each message in a receive batch, with
+    /// structured detail: attempt, consumer, poll id, batch size and lease.
+    pub fn record_lease_events(
+        &self,
+        msgs: &[Message],
+        consumer: &str,
+        poll_id: &str,
+    ) -> Result<()> {
+        for msg in msgs {
+            let lease_secs = msg
+                .lease_expires_at
+                .as_deref()
+                .and_then(|expires| secs_between(&msg.updated_at, expires));
+            let detail = serde_json::json!({
+                "attempt": msg.retry_count + 1,
+                "consumer": consumer,
+                "poll_id": poll_id,
+                "batch_size": msgs.len(),
+                "lease_secs": lease_secs,
+                "lease_expires_at": msg.lease_expires_at,
+            });
+            self.append_message_event(&msg.id, "leased", Some(&detail.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Append an event that ends the current lease (`acknowledged`,
+    /// `lease_expired`, `nacked`), recording the attempt number and how long it was held.
+    pub fn record_lease_outcome(&self, message_id: &str, event_type: &str) -> Result<()> {
+        self.record_lease_outcome_with(message_id, event_type, None)
+    }
+
+    fn record_lease_outcome_with(
+        &self,
+        message_id: &str,
+        event_type: &str,
+        result: Option<&serde_json::Value>,
+    ) -> Result<()> {
+        let retry_count: i64 = self
+            .conn
+            .query_row(
+                "SELECT retry_count FROM messages WHERE id = ?1",
+                params![message_id],
+                |row| row.get(0),
+            )
+            .context("Failed to get retry count")?;
+        let leased_at: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT created_at FROM message_events
+                 WHERE message_id = ?1 AND event_type = 'leased'
+                 ORDER BY id DESC LIMIT 1",
+                params![message_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let mut detail = serde_json::json!({
+            "attempt": retry_count + 1,
+            "held_secs": leased_at.and_then(|at| secs_between(&at, &now)),
+        });
+        if let Some(result) = result {
+            detail["result"] = result.clone();
+        }
+        self.append_message_event(message_id, event_type, Some(&detail.to_string()))
+    }
+
+    /// Delivery timeline for a message: one entry per lease, oldest first.
+    ///
+    /// Built from `leased` events and the event that ended each lease.
+    /// Events written before details were structured still yield attempts,
+    /// with the fields they lack left as `None`.
+    pub fn get_delivery_attempts(&self, message_id: &str) -> Result<Vec<DeliveryAttempt>> {
+        let mut attempts: Vec<DeliveryAttempt> = Vec::new();
+        for event in self.get_message_events(message_id)? {
+            let detail: serde_json::Value = event
+                .detail
+                .as_deref()
+                .and_then(|d| serde_json::from_str(d).ok())
+                .unwrap_or_default();
+            match event.event_type.as_str() {
+                "leased" => {
+                    let attempt = detail["attempt"]
+                        .as_i64()
+                        .unwrap_or_else(|| attempts.last().map_or(1, |a| a.attempt + 1));
+                    attempts.push(DeliveryAttempt {
+                        attempt,
+                        consumer: detail["consumer"].as_str().map(str::to_string),
+                        poll_id: detail["poll_id"].as_str().map(str::to_string),
+                        lease_secs: detail["lease_secs"].as_i64(),
+                        leased_at: event.created_at,
+                        outcome: "in_flight".to_string(),
+                        ended_at: None,
+                        held_secs: None,
+                    });
+                }
+                "acknowledged" | "lease_expired" | "nacked" | "cancelled" | "dead_lettered"
+                | "ttl_expired" => {
+                    if let Some(open) = attempts.last_mut().filter(|a| a.ended_at.is_none()) {
+                        open.held_secs = detail["held_secs"]
+                            .as_i64()
+                            .or_else(|| secs_between(&open.leased_at, &event.created_at));
+                        open.outcome = event.event_type;
+                        open.ended_at = Some(event.created_at);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(attempts)
+    }
+
+    /// Count dead-lettered messages grouped by reason, most frequent first.
+    /// Messages dead-lettered before reasons were persisted report as "unknown".
+    pub fn dead_letter_reasons_summary(&self) -> Result<Vec<DeadLetterReasonCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(dead_letter_reason, 'unknown') AS reason, COUNT(*) AS cnt
+             FROM messages WHERE status = 'dead_letter'
+             GROUP BY reason ORDER BY cnt DESC, reason ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(DeadLetterReasonCount {
+                reason: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+        let mut summary = Vec::new();
+        for row in rows {
+            summary.push(row?);
+        }
+        Ok(summary)
+    }
+
+    /// Queue depth and oldest queued message per recipient, in one grouped query.
+    /// Instances with nothing pending are absent from the map.
+    pub fn queue_depth_for(&self, instance_names: &[&str]) -> Result<HashMap<String, QueueDepth>> {
+        if instance_names.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders: Vec<String> = (1..=instance_names.len())
+            .map(|i| format!("?{i}"))
+            .collect();
+        let sql = format!(
+            "SELECT to_instance, COUNT(*),
+                    MIN(CASE WHEN status = 'queued' THEN created_at END),
+                    COUNT(CASE WHEN status = 'queued'
+                                AND (next_attempt_at IS NULL OR next_attempt_at <= ?{now})
+                                AND expires_at > ?{now} THEN 1 END)
+             FROM messages
+             WHERE status IN ('queued', 'leased') AND to_instance IN ({})
+             GROUP BY to_instance",
+            placeholders.join(", "),
+            now = instance_names.len() + 1
+        );
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            rusqlite::params_from_iter(instance_names.iter().copied().chain([now.as_str()])),
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    QueueDepth {
+                        depth: row.get(1)?,
+                        oldest_queued_at: row.get(2)?,
+                        leasable: row.get(3)?,
+                    },
+                ))
+            },
+        )?;
+        let mut depths = HashMap::new();
+        for row in rows {
+            let (name, depth) = row?;
+            depths.insert(name, depth);
+        }
+        Ok(depths)
+    }
+
+    /// Queued messages for `to_instance` that a receive call could lease
+    /// right now. Unlike the raw queued count this leaves out messages
+    /// waiting out a retry backoff and ones past their TTL that the reaper
+    /// has not dead-lettered yet.
+    pub fn count_leasable(&self, to_instance: &str) -> Result<i64> {
+        let now = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM messages
+             WHERE to_instance = ?1 AND status = 'queued'
+             AND (next_attempt_at IS NULL OR next_attempt_at <= ?2)
+             AND expires_at > ?2",
+            params![to_instance, now],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Newest agent event and inbound/outbound message for an instance.
+    /// Events are keyed by instance ID, messages by instance name.
+    pub fn instance_activity(
+        &self,
+        instance_id: &str,
+        instance_name: &str,
+    ) -> Result<InstanceActivity> {
+        self.conn
+            .query_row(
+                "SELECT (SELECT MAX(created_at) FROM agent_events WHERE instance_id = ?1),
+                        (SELECT MAX(created_at) FROM messages WHERE to_instance = ?2),
+                        (SELECT MAX(created_at) FROM messages WHERE from_instance = ?2)",
+                params![instance_id, instance_name],
+                |row| {
+                    Ok(InstanceActivity {
+                        last_event_at: row.get(0)?,
+                        last_inbound_message_at: row.get(1)?,
+                        last_outbound_message_at: row.get(2)?,
+                    })
+                },
+            )
+            .context("Failed to query instance activity")
+    }
+
+    /// Count messages grouped by status.
+    pub fn message_status_counts(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT status, COUNT(*) FROM messages GROUP BY status ORDER BY status")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut counts = Vec::new();
+        for row in rows {
+            counts.push(row?);
+        }
+        Ok(counts)
+    }
+
+    /// Count non-archived instances grouped by status.
+    pub fn instance_status_counts(&self) -> Result<Vec<(String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT status, COUNT(*) FROM instances WHERE archived_at IS NULL
+             GROUP BY status ORDER BY status",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut counts = Vec::new();
+        for row in rows {
+            counts.push(row?);
+        }
+        Ok(counts)
+    }
+
+    /// Append an audit event for a message.
+    pub fn append_message_event(
+        &self,
+        message_id: &str,
+        event_type: &str,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT INTO message_events (message_id, event_type, detail) VALUES (?1, ?2, ?3)",
+                params![message_id, event_type, detail],
+            )
+            .context("Failed to insert message event")?;
+        Ok(())
+    }
+
+    /// Get queued messages where recipient is stopped and routing rule has auto_start.
+    pub fn get_instances_needing_autostart(&self) -> Result<Vec<(Message, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT m.id, m.from_instance, m.to_instance, m.message_type, m.payload, m.correlation_id, m.idempotency_key, m.hop_count, m.status, m.retry_count, m.max_retries, m.next_attempt_at, m.lease_expires_at, m.expires_at, m.created_at, m.updated_at, m.dead_letter_reason, m.payload_encoding, m.content_type, m.priority, r.to_instance
+             FROM messages m
+             JOIN routing_rules r ON m.from_instance = r.from_instance AND m.to_instance = r.to_instance AND r.auto_start = 1
+             JOIN instances i ON i.name = m.to_instance AND i.archived_at IS NULL AND i.status = 'stopped'
+             WHERE m.status = 'queued'
+             GROUP BY m.to_instance",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let msg = Self::row_to_message(row)?;
+            let instance_name: String = row.get(20)?;
+            Ok((msg, instance_name))
+        })?;
+        let mut results = Vec::new();
+        for row in rows {
+            results.push(row?);
+        }
+        Ok(results)
+    }
+
+    /// Map a message row selected with the standard column list, ending in
+    /// `payload_encoding` (column 17), `content_type` (column 18) and
+    /// `priority` (column 19).
+    fn row_to_message(row: &rusqlite::Row<'_>) -> rusqlite::Result<Message> {
+        Ok(Message {
             id: row.get(0)?,
             from_instance: row.get(1)?,
             to_instance: row.get(2)?,
             message_type: row.get(3)?,
-            payload: row.get(4)?,
+            payload: decode_payload(row, 4, 17)?,
             correlation_id: row.get(5)?,
             idempotency_key: row.get(6)?,
             hop_count: row.get(7)?,
@@ -1340,6 +3314,9 @@ impl Registry {
             expires_at: row.get(13)?,
             created_at: row.get(14)?,
             updated_at: row.get(15)?,
+            dead_letter_reason: row.get(16)?,
+            content_type: row.get(18)?,
+            priority: row.get(19)?,
         })
     }
 
@@ -1351,10 +3328,10 @@ impl Registry {
     /// List instances, optionally including archived ones.
     pub fn list_instances_filtered(&self, include_archived: bool) -> Result<Vec<Instance>> {
         let sql = if include_archived {
-            "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid
+            "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid, started_at, restart_count
              FROM instances ORDER BY archived_at IS NOT NULL, name"
         } else {
-            "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid
+            "SELECT id, name, status, port, config_path, workspace_dir, archived_at, migration_run_id, pid, started_at, restart_count
              FROM instances WHERE archived_at IS NULL ORDER BY name"
         };
         let mut stmt = self.conn.prepare(sql)?;
@@ -1369,6 +3346,8 @@ impl Registry {
                 archived_at: row.get(6)?,
                 migration_run_id: row.get(7)?,
                 pid: row.get::<_, Option<i64>>(8)?.map(|p| p as u32),
+                started_at: row.get(9)?,
+                restart_count: row.get(10)?,
             })
         })?;
         let mut instances = Vec::new();
@@ -1379,18 +3358,124 @@ impl Registry {
     }
 }
 
+/// Prefix marking a routing-rule type pattern as a regular expression.
+pub const REGEX_TYPE_PATTERN_PREFIX: &str = "re:";
+
+/// Check a routing-rule type pattern before it is stored: a `re:` pattern
+/// must compile. Glob and exact patterns are always valid.
+pub fn validate_type_pattern(pattern: &str) -> std::result::Result<(), String> {
+    match pattern.strip_prefix(REGEX_TYPE_PATTERN_PREFIX) {
+        Some(source) => regex::Regex::new(source)
+            .map(|_| ())
+            .map_err(|e| format!("Invalid type_pattern regex: {e}")),
+        None => Ok(()),
+    }
+}
+
 /// Check if a type pattern matches a message type.
-/// "*" matches everything. "task.*" matches "task.handoff". Exact match otherwise.
+/// "*" matches everything. "task.*" matches "task.handoff". "re:<regex>"
+/// matches when the regex finds a match (anchor it for a full match).
+/// Exact match otherwise.
 fn type_pattern_matches(pattern: &str, message_type: &str) -> bool {
     if pattern == "*" {
         return true;
     }
+    if let Some(source) = pattern.strip_prefix(REGEX_TYPE_PATTERN_PREFIX) {
+        return cached_type_regex(source).is_some_and(|re| re.is_match(message_type));
+    }
     if let Some(prefix) = pattern.strip_suffix(".*") {
         return message_type == prefix || message_type.starts_with(&format!("{prefix}."));
     }
     pattern == message_type
 }
 
+/// Compiled `re:` type patterns, keyed by source, so `check_route_allowed`
+/// does not recompile a rule's regex on every send. A source that fails to
+/// compile (only possible for rules stored before validation) is cached as
+/// `None` and never matches.
+fn cached_type_regex(source: &str) -> Option<regex::Regex> {
+    use std::sync::{Mutex, OnceLock, PoisonError};
+
+    static CACHE: OnceLock<Mutex<HashMap<String, Option<regex::Regex>>>> = OnceLock::new();
+    let mut cache = CACHE
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    cache
+        .entry(source.to_string())
+        .or_insert_with(|| regex::Regex::new(source).ok())
+        .clone()
+}
+
+/// `instance_id IN (?1, ..., ?n)` over `ids`, with the ids as its bind values.
+fn instance_id_in_clause(ids: &[&str]) -> (String, Vec<Box<dyn rusqlite::types::ToSql>>) {
+    let placeholders: Vec<String> = (1..=ids.len()).map(|i| format!("?{i}")).collect();
+    let values = ids
+        .iter()
+        .map(|id| Box::new((*id).to_string()) as Box<dyn rusqlite::types::ToSql>)
+        .collect();
+    (
+        format!("instance_id IN ({})", placeholders.join(", ")),
+        values,
+    )
+}
+
+/// Gzip `payload` when it exceeds [`PAYLOAD_COMPRESSION_THRESHOLD_BYTES`]
+/// and compression actually shrinks it. Returns the stored value and its
+/// `payload_encoding`.
+fn encode_payload(payload: &str) -> Result<(rusqlite::types::Value, Option<&'static str>)> {
+    use std::io::Write;
+
+    if payload.len() > PAYLOAD_COMPRESSION_THRESHOLD_BYTES {
+        let mut encoder =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(payload.as_bytes())?;
+        let compressed = encoder.finish().context("Failed to compress payload")?;
+        if compressed.len() < payload.len() {
+            return Ok((rusqlite::types::Value::Blob(compressed), Some("gzip")));
+        }
+    }
+    Ok((rusqlite::types::Value::Text(payload.to_string()), None))
+}
+
+/// Read a payload column, decompressing it when the encoding column says so.
+fn decode_payload(
+    row: &rusqlite::Row<'_>,
+    payload_idx: usize,
+    encoding_idx: usize,
+) -> rusqlite::Result<String> {
+    use std::io::Read;
+
+    match row.get::<_, Option<String>>(encoding_idx)?.as_deref() {
+        None => row.get(payload_idx),
+        Some("gzip") => {
+            let compressed: Vec<u8> = row.get(payload_idx)?;
+            let mut payload = String::new();
+            flate2::read::GzDecoder::new(compressed.as_slice())
+                .read_to_string(&mut payload)
+                .map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(
+                        payload_idx,
+                        rusqlite::types::Type::Blob,
+                        Box::new(e),
+                    )
+                })?;
+            Ok(payload)
+        }
+        Some(other) => Err(rusqlite::Error::FromSqlConversionFailure(
+            payload_idx,
+            rusqlite::types::Type::Text,
+            format!("unknown payload_encoding '{other}'").into(),
+        )),
+    }
+}
+
+/// Whole seconds from `start` to `end` (both `%Y-%m-%d %H:%M:%S`).
+fn secs_between(start: &str, end: &str) -> Option<i64> {
+    let parse = |ts: &str| chrono::NaiveDateTime::parse_from_str(ts, "%Y-%m-%d %H:%M:%S").ok();
+    Some((parse(end)? - parse(start)?).num_seconds())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1414,6 +3499,20 @@ mod tests {
         assert!(!type_pattern_matches("task.handoff", "task.other"));
     }
 
+    #[test]
+    fn type_pattern_regex() {
+        let pattern = r"re:^task\.priority\.(high|low)$";
+        assert!(validate_type_pattern(pattern).is_ok());
+        assert!(type_pattern_matches(pattern, "task.priority.high"));
+        assert!(type_pattern_matches(pattern, "task.priority.low"));
+        assert!(!type_pattern_matches(pattern, "task.priority.medium"));
+        assert!(!type_pattern_matches(pattern, "task.priorityXhigh"));
+
+        assert!(validate_type_pattern("re:task.(").is_err());
+        assert!(!type_pattern_matches("re:task.(", "task.("));
+        assert!(validate_type_pattern("task.*").is_ok());
+    }
+
     #[test]
     fn create_and_get_instance() {
         let reg = Registry::open_in_memory().unwrap();
@@ -1454,6 +3553,39 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    fn archive_and_unarchive_are_idempotent() {
+        let reg = Registry::open_in_memory().unwrap();
+        reg.create_instance("id-1", "agent", 18801, "/tmp/c.toml", None, None)
+            .unwrap();
+
+        assert_eq!(
+            reg.archive_instance("id-1").unwrap(),
+            ArchiveOutcome::Archived
+        );
+        assert_eq!(
+            reg.archive_instance("id-1").unwrap(),
+            ArchiveOutcome::AlreadyArchived
+        );
+        assert_eq!(
+            reg.archive_instance("missing").unwrap(),
+            ArchiveOutcome::NotFound
+        );
+
+        assert_eq!(
+            reg.unarchive_instance("agent").unwrap(),
+            UnarchiveOutcome::Unarchived
+        );
+        assert_eq!(
+            reg.unarchive_instance("agent").unwrap(),
+            UnarchiveOutcome::AlreadyActive
+        );
+        assert_eq!(
+            reg.unarchive_instance("missing").unwrap(),
+            UnarchiveOutcome::NotFound
+        );
+    }
+
     #[test]
     fn delete_instance_if_migration_scoped() {
         let reg = Registry::open_in_memory().unwrap();
@@ -1494,6 +3626,21 @@ mod tests {
         assert!(port.is_none());
     }
 
+    #[test]
+    fn allocate_port_reports_exhausted_range() {
+        let reg = Registry::open_in_memory().unwrap();
+        reg.create_instance("id-1", "a1", 18801, "/c.toml", None, None)
+            .unwrap();
+
+        assert_eq!(reg.allocate_port(18801..=18803, &[18802]).unwrap(), 18803);
+        let err = reg.allocate_port(18801..=18802, &[18802]).unwrap_err();
+        assert!(
+            matches!(&err, PortAllocError::Exhausted { range } if *range == (18801..=18802)),
+            "{err:?}"
+        );
+        assert_eq!(err.to_string(), "No ports available in range 18801-18802");
+    }
+
     #[test]
     fn schema_migration_adds_migration_run_id_column() {
         // Simulate a pre-phase5 DB: create table WITHOUT migration_run_id,
@@ -1606,9 +3753,1209 @@ mod tests {
     }
 
     #[test]
-    fn update_pid_errors_on_missing_instance() {
+    fn start_time_and_restart_count_roundtrip() {
         let reg = Registry::open_in_memory().unwrap();
-        let err = reg.update_pid("nonexistent", Some(123)).unwrap_err();
-        assert!(err.to_string().contains("No instance"));
+        reg.create_instance("id-1", "agent", 18801, "/c.toml", None, None)
+            .unwrap();
+
+        let inst = reg.get_instance("id-1").unwrap().unwrap();
+        assert!(inst.started_at.is_none());
+        assert_eq!(inst.restart_count, 0);
+
+        reg.record_started("id-1").unwrap();
+        reg.increment_restart_count("id-1").unwrap();
+        reg.increment_restart_count("id-1").unwrap();
+
+        let inst = reg.get_instance_by_name("agent").unwrap().unwrap();
+        assert!(inst.started_at.is_some());
+        assert_eq!(inst.restart_count, 2);
+
+        assert!(reg.record_started("nonexistent").is_err());
+        assert!(reg.increment_restart_count("nonexistent").is_err());
+    }
+
+    fn enqueue_test_message(reg: &Registry, id: &str) {
+        reg.enqueue_message(&NewMessage {
+            id: id.to_string(),
+            from_instance: "a".to_string(),
+            to_instance: "b".to_string(),
+            message_type: "task".to_string(),
+            payload: "{}".to_string(),
+            correlation_id: None,
+            idempotency_key: None,
+            idempotency_window_secs: None,
+            hop_count: 0,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn idempotency_key_within_window_is_released_on_enqueue() {
+        let reg = Registry::open_in_memory().unwrap();
+        let msg = |id: &str| NewMessage {
+            id: id.to_string(),
+            from_instance: "a".to_string(),
+            to_instance: "b".to_string(),
+            message_type: "status".to_string(),
+            payload: r#"{"status":"ok"}"#.to_string(),
+            correlation_id: None,
+            idempotency_key: Some("sha256:daily-ok".to_string()),
+            idempotency_window_secs: Some(300),
+            hop_count: 0,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        };
+        reg.enqueue_message(&msg("m1")).unwrap();
+        assert_eq!(
+            reg.check_idempotency_key_within("sha256:daily-ok", 300)
+                .unwrap()
+                .as_deref(),
+            Some("m1")
+        );
+
+        // Within the window a second send is deduplicated
+        let outcome = reg.enqueue_messages_batch(&[msg("dup")]).unwrap();
+        assert!(matches!(&outcome[0], BatchEnqueueOutcome::Deduplicated(id) if id == "m1"));
+
+        // A day later the key is free again; checking leaves m1 untouched
+        reg.conn
+            .execute(
+                "UPDATE messages SET created_at = datetime('now', '-1 day') WHERE id = 'm1'",
+                [],
+            )
+            .unwrap();
+        assert_eq!(
+            reg.check_idempotency_key("sha256:daily-ok")
+                .unwrap()
+                .as_deref(),
+            Some("m1")
+        );
+        assert_eq!(
+            reg.check_idempotency_key_within("sha256:daily-ok", 300)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            reg.check_idempotency_key("sha256:daily-ok")
+                .unwrap()
+                .as_deref(),
+            Some("m1")
+        );
+
+        // Enqueueing the next message moves the key over and records it on m1
+        reg.enqueue_message(&msg("m2")).unwrap();
+        assert_eq!(
+            reg.check_idempotency_key("sha256:daily-ok")
+                .unwrap()
+                .as_deref(),
+            Some("m2")
+        );
+        let events = reg.get_message_events("m1").unwrap();
+        let released = events.last().unwrap();
+        assert_eq!(released.event_type, "idempotency_key_released");
+        assert_eq!(released.detail.as_deref(), Some("sha256:daily-ok"));
+    }
+
+    #[test]
+    fn enqueue_messages_batch_is_all_or_nothing() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let reg = Registry::open(&tmp.path().join("registry.db")).unwrap();
+        let msg = |id: &str, to: &str| NewMessage {
+            id: id.to_string(),
+            from_instance: "a".to_string(),
+            to_instance: to.to_string(),
+            message_type: "task".to_string(),
+            payload: "{}".to_string(),
+            correlation_id: Some("fan-out".to_string()),
+            idempotency_key: None,
+            idempotency_window_secs: None,
+            hop_count: 0,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        };
+
+        let queued = reg
+            .enqueue_messages_batch(&[msg("m1", "b"), msg("m2", "c")])
+            .unwrap()
+            .into_iter()
+            .map(|outcome| match outcome {
+                BatchEnqueueOutcome::Queued(m) => *m,
+                BatchEnqueueOutcome::Deduplicated(id) => panic!("deduplicated against {id}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            queued
+                .iter()
+                .map(|m| m.to_instance.as_str())
+                .collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+        assert!(queued
+            .iter()
+            .all(|m| m.status == "queued" && m.correlation_id.as_deref() == Some("fan-out")));
+
+        // Duplicate id fails the second insert; the first is rolled back.
+        assert!(reg
+            .enqueue_messages_batch(&[msg("m3", "b"), msg("m1", "c")])
+            .is_err());
+        assert!(reg.get_message("m3").unwrap().is_none());
+    }
+
+    #[test]
+    fn concurrent_leases_never_double_deliver() {
+        const MESSAGES: usize = 200;
+        const WORKERS: usize = 8;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        {
+            let reg = Registry::open(&db_path).unwrap();
+            for i in 0..MESSAGES {
+                enqueue_test_message(&reg, &format!("m{i}"));
+            }
+        }
+
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(WORKERS));
+        let handles: Vec<_> = (0..WORKERS)
+            .map(|_| {
+                let db_path = db_path.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    let reg = Registry::open(&db_path).unwrap();
+                    barrier.wait();
+                    let mut leased = Vec::new();
+                    while let Some(msg) = reg.lease_pending_message("b").unwrap() {
+                        assert_eq!(msg.status, "leased");
+                        leased.push(msg.id);
+                    }
+                    leased
+                })
+            })
+            .collect();
+
+        let mut all: Vec<String> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        assert_eq!(all.len(), MESSAGES, "every message leased exactly once");
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), MESSAGES, "no message leased twice");
+    }
+
+    #[test]
+    fn batch_lease_takes_oldest_up_to_max() {
+        let reg = Registry::open_in_memory().unwrap();
+        for (i, id) in ["m1", "m2", "m3"].into_iter().enumerate() {
+            enqueue_test_message(&reg, id);
+            reg.conn
+                .execute(
+                    "UPDATE messages SET created_at = ?1 WHERE id = ?2",
+                    params![format!("2024-01-01 00:00:0{i}"), id],
+                )
+                .unwrap();
+        }
+
+        let batch = reg.lease_pending_messages("b", 2, 30).unwrap();
+        let ids: Vec<&str> = batch.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["m1", "m2"]);
+        assert!(batch.iter().all(|m| m.status == "leased"));
+
+        let rest = reg.lease_pending_messages("b", 50, 30).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].id, "m3");
+        assert!(reg.lease_pending_messages("b", 50, 30).unwrap().is_empty());
+    }
+
+    #[test]
+    fn higher_priority_leases_first_then_fifo() {
+        let reg = Registry::open_in_memory().unwrap();
+        for (id, priority) in [("bulk1", 0), ("urgent1", 9), ("bulk2", 0), ("urgent2", 9)] {
+            enqueue_test_message(&reg, id);
+            reg.conn
+                .execute(
+                    "UPDATE messages SET priority = ?1 WHERE id = ?2",
+                    params![priority, id],
+                )
+                .unwrap();
+        }
+
+        // Expedite lifts a message to the top priority as well
+        reg.expedite_message("bulk2").unwrap().unwrap();
+        assert_eq!(reg.get_message("bulk2").unwrap().unwrap().priority, 9);
+
+        let leased = reg.lease_pending_messages("b", 4, 30).unwrap();
+        let ids: Vec<&str> = leased.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["bulk2", "urgent1", "urgent2", "bulk1"]);
+    }
+
+    #[test]
+    fn lease_duration_follows_routing_rule() {
+        let reg = Registry::open_in_memory().unwrap();
+        let gate = HealthGate::Off;
+        reg.create_routing_rule("a", "b", "task", 5, 3600, 600, false, false, gate, None)
+            .unwrap();
+        enqueue_test_message(&reg, "ruled");
+        enqueue_test_message(&reg, "orphan");
+        reg.conn
+            .execute(
+                "UPDATE messages SET message_type = 'other' WHERE id = 'orphan'",
+                [],
+            )
+            .unwrap();
+
+        let leased = reg.lease_pending_messages("b", 2, 30).unwrap();
+        reg.record_lease_events(&leased, "b", "poll-1").unwrap();
+        for msg in &leased {
+            let stored = reg.get_message(&msg.id).unwrap().unwrap();
+            assert_eq!(stored.lease_expires_at, msg.lease_expires_at);
+            let lease_secs =
+                secs_between(&msg.updated_at, msg.lease_expires_at.as_deref().unwrap());
+            // No rule matches `other`, so it falls back to the default
+            let expected = if msg.id == "ruled" { 600 } else { 30 };
+            assert_eq!(lease_secs, Some(expected));
+            let attempts = reg.get_delivery_attempts(&msg.id).unwrap();
+            assert_eq!(attempts[0].lease_secs, Some(expected));
+        }
+    }
+
+    #[test]
+    fn concurrent_batch_leases_never_overlap() {
+        const MESSAGES: usize = 200;
+        const WORKERS: usize = 8;
+
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        {
+            let reg = Registry::open(&db_path).unwrap();
+            for i in 0..MESSAGES {
+                enqueue_test_message(&reg, &format!("m{i}"));
+            }
+        }
+
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(WORKERS));
+        let handles: Vec<_> = (0..WORKERS)
+            .map(|w| {
+                let db_path = db_path.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    let reg = Registry::open(&db_path).unwrap();
+                    barrier.wait();
+                    let mut leased = Vec::new();
+                    loop {
+                        let batch = reg.lease_pending_messages("b", 1 + w % 7, 90).unwrap();
+                        if batch.is_empty() {
+                            break;
+                        }
+                        leased.extend(batch.into_iter().map(|m| m.id));
+                    }
+                    leased
+                })
+            })
+            .collect();
+
+        let mut all: Vec<String> = handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        assert_eq!(all.len(), MESSAGES, "every message leased exactly once");
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), MESSAGES, "no message leased twice");
+    }
+
+    #[test]
+    fn cancel_messages_for_instance_only_touches_pending() {
+        let reg = Registry::open_in_memory().unwrap();
+        for id in ["m1", "m2", "m3"] {
+            enqueue_test_message(&reg, id);
+        }
+        reg.lease_pending_message("b").unwrap();
+        reg.dead_letter_message("m3", "max_retries").unwrap();
+
+        assert_eq!(
+            reg.cancel_messages_for_instance("b", "recipient_archived")
+                .unwrap(),
+            2
+        );
+        for id in ["m1", "m2"] {
+            let msg = reg.get_message(id).unwrap().unwrap();
+            assert_eq!(msg.status, "cancelled");
+            assert_eq!(
+                msg.dead_letter_reason.as_deref(),
+                Some("recipient_archived")
+            );
+            let events = reg.get_message_events(id).unwrap();
+            assert_eq!(events.last().unwrap().event_type, "cancelled");
+        }
+        assert_eq!(
+            reg.get_message("m3").unwrap().unwrap().status,
+            "dead_letter"
+        );
+        assert_eq!(
+            reg.cancel_messages_for_instance("b", "recipient_archived")
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn instance_tokens_resolve_until_reissued_or_archived() {
+        let reg = Registry::open_in_memory().unwrap();
+        reg.create_instance("id-a", "a", 18801, "/tmp/a.toml", None, None)
+            .unwrap();
+        assert!(reg.issue_instance_token("missing").is_err());
+
+        let old = reg.issue_instance_token("id-a").unwrap();
+        let new = reg.issue_instance_token("id-a").unwrap();
+        assert_ne!(old, new);
+        assert_eq!(reg.instance_name_for_token(&old).unwrap(), None);
+        assert_eq!(
+            reg.instance_name_for_token(&new).unwrap().as_deref(),
+            Some("a")
+        );
+
+        reg.archive_instance("id-a").unwrap();
+        assert_eq!(reg.instance_name_for_token(&new).unwrap(), None);
+    }
+
+    #[test]
+    fn archive_instance_cancelling_messages_cancels_only_on_archive() {
+        let reg = Registry::open_in_memory().unwrap();
+        reg.create_instance("id-b", "b", 18801, "/tmp/b.toml", None, None)
+            .unwrap();
+        enqueue_test_message(&reg, "m1");
+
+        let (outcome, cancelled) = reg
+            .archive_instance_cancelling_messages("id-b", "b", "recipient_archived")
+            .unwrap();
+        assert_eq!(outcome, ArchiveOutcome::Archived);
+        assert_eq!(cancelled, 1);
+        assert_eq!(reg.get_message("m1").unwrap().unwrap().status, "cancelled");
+
+        // Re-archiving leaves newer messages alone
+        enqueue_test_message(&reg, "m2");
+        let (outcome, cancelled) = reg
+            .archive_instance_cancelling_messages("id-b", "b", "recipient_archived")
+            .unwrap();
+        assert_eq!(outcome, ArchiveOutcome::AlreadyArchived);
+        assert_eq!(cancelled, 0);
+        assert_eq!(reg.get_message("m2").unwrap().unwrap().status, "queued");
+    }
+
+    #[test]
+    fn large_payloads_are_stored_compressed_and_read_back_verbatim() {
+        let reg = Registry::open_in_memory().unwrap();
+        let big = serde_json::json!({ "text": "all work and no play ".repeat(1000) }).to_string();
+        assert!(big.len() > PAYLOAD_COMPRESSION_THRESHOLD_BYTES);
+        for (id, payload) in [("big", big.as_str()), ("small", "{\"n\":1}")] {
+            reg.enqueue_message(&NewMessage {
+                id: id.to_string(),
+                from_instance: "a".to_string(),
+                to_instance: "b".to_string(),
+                message_type: "task".to_string(),
+                payload: payload.to_string(),
+                correlation_id: None,
+                idempotency_key: None,
+                idempotency_window_secs: None,
+                hop_count: 0,
+                max_retries: 5,
+                ttl_secs: 3600,
+                content_type: None,
+                priority: 0,
+            })
+            .unwrap();
+        }
+
+        let (encoding, stored_len): (Option<String>, i64) = reg
+            .conn
+            .query_row(
+                "SELECT payload_encoding, length(payload) FROM messages WHERE id = 'big'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        assert!(stored_len < i64::try_from(big.len()).unwrap() / 10);
+        let encoding: Option<String> = reg
+            .conn
+            .query_row(
+                "SELECT payload_encoding FROM messages WHERE id = 'small'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(encoding, None);
+
+        assert_eq!(reg.get_message("big").unwrap().unwrap().payload, big);
+        let leased = reg.lease_pending_messages("b", 2, 90).unwrap();
+        assert_eq!(leased[0].payload, big);
+        assert_eq!(leased[1].payload, "{\"n\":1}");
+    }
+
+    #[test]
+    fn search_messages_matches_payload_text_with_filters() {
+        let reg = Registry::open_in_memory().unwrap();
+        let big = serde_json::json!({ "log": "deploy failed on host-7 ".repeat(500) }).to_string();
+        let messages = [
+            ("m1", "b", r#"{"note":"Deploy finished"}"#.to_string()),
+            ("m2", "c", r#"{"note":"deploy started"}"#.to_string()),
+            ("m3", "b", r#"{"note":"unrelated 100%"}"#.to_string()),
+            ("m4", "b", big),
+        ];
+        for (id, to, payload) in messages {
+            reg.enqueue_message(&NewMessage {
+                id: id.to_string(),
+                from_instance: "a".to_string(),
+                to_instance: to.to_string(),
+                message_type: "task".to_string(),
+                payload,
+                correlation_id: None,
+                idempotency_key: None,
+                idempotency_window_secs: None,
+                hop_count: 0,
+                max_retries: 5,
+                ttl_secs: 3600,
+                content_type: None,
+                priority: 0,
+            })
+            .unwrap();
+        }
+        let ids = |query: &str, filters: &MessageSearchFilters| {
+            let (found, total) = reg.search_messages(query, filters, 10, 0).unwrap();
+            let mut ids: Vec<String> = found.into_iter().map(|m| m.id).collect();
+            ids.sort();
+            (ids, total)
+        };
+        let any = MessageSearchFilters::default();
+
+        // Case-insensitive, and compressed payloads are searchable too
+        assert_eq!(
+            ids("DEPLOY", &any),
+            (vec!["m1".into(), "m2".into(), "m4".into()], 3)
+        );
+        assert_eq!(ids("host-7", &any), (vec!["m4".into()], 1));
+        let to_b = MessageSearchFilters {
+            to_instance: Some("b".into()),
+            ..Default::default()
+        };
+        assert_eq!(ids("deploy", &to_b).1, 2);
+        // LIKE wildcards in short queries are literal
+        assert_eq!(ids("0%", &any), (vec!["m3".into()], 1));
+        assert_eq!(ids("nothing like this", &any).1, 0);
+
+        let (page, total) = reg.search_messages("deploy", &any, 1, 1).unwrap();
+        assert_eq!((page.len(), total), (1, 3));
+
+        // Purged messages drop out of the index
+        reg.conn
+            .execute(
+                "UPDATE messages SET status = 'acknowledged', updated_at = '2020-01-01 00:00:00'
+                 WHERE id = 'm4'",
+                [],
+            )
+            .unwrap();
+        reg.purge_messages("acknowledged", "2021-01-01 00:00:00")
+            .unwrap();
+        assert_eq!(ids("host-7", &any).1, 0);
+    }
+
+    #[test]
+    fn expedite_message_jumps_the_queue() {
+        let reg = Registry::open_in_memory().unwrap();
+        for id in ["m1", "m2", "m3"] {
+            enqueue_test_message(&reg, id);
+        }
+        // A pending retry backoff does not hold an expedited message back
+        reg.conn
+            .execute(
+                "UPDATE messages SET next_attempt_at = '2999-01-01 00:00:00' WHERE id = 'm3'",
+                [],
+            )
+            .unwrap();
+
+        let before = reg.get_message("m3").unwrap().unwrap().created_at;
+        reg.expedite_message("m3").unwrap().unwrap();
+        // The insertion time is part of the audit record and stays put
+        assert_eq!(reg.get_message("m3").unwrap().unwrap().created_at, before);
+        let leased = reg.lease_pending_messages("b", 3, 90).unwrap();
+        let ids: Vec<&str> = leased.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["m3", "m1", "m2"]);
+        let events = reg.get_message_events("m3").unwrap();
+        assert_eq!(events.last().unwrap().event_type, "expedited");
+
+        // Leased (or unknown) messages are not expedited
+        assert_eq!(reg.expedite_message("m1").unwrap(), None);
+        assert_eq!(reg.expedite_message("nope").unwrap(), None);
+    }
+
+    #[test]
+    fn extend_lease_only_while_lease_is_live() {
+        let reg = Registry::open_in_memory().unwrap();
+        enqueue_test_message(&reg, "m1");
+        enqueue_test_message(&reg, "m2");
+
+        // Queued (or unknown) messages have no lease to extend
+        assert_eq!(reg.extend_lease("m1", 300).unwrap(), None);
+        assert_eq!(reg.extend_lease("nope", 300).unwrap(), None);
+
+        reg.lease_pending_messages("b", 2, 30).unwrap();
+        let expires_at = reg.extend_lease("m1", 300).unwrap().unwrap();
+        let msg = reg.get_message("m1").unwrap().unwrap();
+        assert_eq!(msg.lease_expires_at.as_deref(), Some(expires_at.as_str()));
+        assert_eq!(secs_between(&msg.updated_at, &expires_at), Some(300));
+        let event = reg.get_message_events("m1").unwrap().pop().unwrap();
+        assert_eq!(event.event_type, "lease_extended");
+        assert!(event.detail.unwrap().contains(&expires_at));
+
+        // A lease that already ran out is left for the reaper
+        reg.conn
+            .execute(
+                "UPDATE messages SET lease_expires_at = '2000-01-01 00:00:00' WHERE id = 'm2'",
+                [],
+            )
+            .unwrap();
+        assert_eq!(reg.extend_lease("m2", 300).unwrap(), None);
+    }
+
+    #[test]
+    fn nack_requeues_with_backoff_until_retries_run_out() {
+        let reg = Registry::open_in_memory().unwrap();
+        enqueue_test_message(&reg, "m1");
+        assert_eq!(reg.nack_message("m1").unwrap(), None);
+
+        let leased = reg.lease_pending_messages("b", 1, 90).unwrap();
+        reg.record_lease_events(&leased, "b", "poll-1").unwrap();
+        let Some(NackOutcome::Requeued(next_attempt_at)) = reg.nack_message("m1").unwrap() else {
+            panic!("expected requeue");
+        };
+        let msg = reg.get_message("m1").unwrap().unwrap();
+        assert_eq!(msg.status, "queued");
+        assert_eq!(msg.retry_count, 1);
+        assert_eq!(msg.lease_expires_at, None);
+        assert_eq!(
+            msg.next_attempt_at.as_deref(),
+            Some(next_attempt_at.as_str())
+        );
+        let attempts = reg.get_delivery_attempts("m1").unwrap();
+        assert_eq!(attempts[0].outcome, "nacked");
+
+        // On the last allowed attempt a nack dead-letters instead
+        reg.conn
+            .execute(
+                "UPDATE messages SET status = 'leased', retry_count = 4 WHERE id = 'm1'",
+                [],
+            )
+            .unwrap();
+        assert_eq!(
+            reg.nack_message("m1").unwrap(),
+            Some(NackOutcome::DeadLettered)
+        );
+        let msg = reg.get_message("m1").unwrap().unwrap();
+        assert_eq!(msg.status, "dead_letter");
+        assert_eq!(
+            msg.dead_letter_reason.as_deref(),
+            Some("nacked past max_retries")
+        );
+    }
+
+    #[test]
+    fn message_events_deletable_only_once_message_is_gone() {
+        let reg = Registry::open_in_memory().unwrap();
+        for id in ["m1", "m2"] {
+            enqueue_test_message(&reg, id);
+            reg.append_message_event(id, "created", None).unwrap();
+        }
+
+        // Ad-hoc deletes of a live message's events stay blocked
+        let err = reg
+            .conn
+            .execute("DELETE FROM message_events WHERE message_id = 'm1'", [])
+            .unwrap_err();
+        assert!(err.to_string().contains("append-only"), "{err}");
+        assert_eq!(reg.purge_events_for_deleted_messages().unwrap(), 0);
+
+        // Remove m1 the way a purge would: foreign keys deferred, then the
+        // orphaned events go in the same transaction
+        let m2_events = reg.get_message_events("m2").unwrap().len();
+        reg.conn
+            .execute_batch(
+                "BEGIN; PRAGMA defer_foreign_keys = ON;
+                 DELETE FROM messages WHERE id = 'm1';",
+            )
+            .unwrap();
+        assert!(reg.purge_events_for_deleted_messages().unwrap() > 0);
+        reg.conn.execute_batch("COMMIT").unwrap();
+
+        assert!(reg.get_message_events("m1").unwrap().is_empty());
+        assert_eq!(reg.get_message_events("m2").unwrap().len(), m2_events);
+    }
+
+    #[test]
+    fn purge_messages_removes_old_terminal_messages_and_events() {
+        let reg = Registry::open_in_memory().unwrap();
+        for id in ["acked", "failed", "expired", "queued", "recent"] {
+            enqueue_test_message(&reg, id);
+            reg.append_message_event(id, "created", None).unwrap();
+        }
+        reg.lease_pending_messages("b", 1, 90).unwrap(); // leases `acked`
+        reg.acknowledge_message("acked", None).unwrap();
+        reg.dead_letter_message("failed", "max retries exceeded")
+            .unwrap();
+        reg.expire_message("expired").unwrap();
+        reg.dead_letter_message("recent", "max retries exceeded")
+            .unwrap();
+        reg.conn
+            .execute(
+                "UPDATE messages SET updated_at = '2000-01-01 00:00:00' WHERE id != 'recent'",
+                [],
+            )
+            .unwrap();
+        let cutoff = "2020-01-01 00:00:00";
+        // An event orphaned outside the purge is not counted as its work
+        enqueue_test_message(&reg, "stray");
+        reg.append_message_event("stray", "created", None).unwrap();
+        reg.conn
+            .execute_batch(
+                "PRAGMA foreign_keys = OFF;
+                 DELETE FROM messages WHERE id = 'stray';
+                 PRAGMA foreign_keys = ON;",
+            )
+            .unwrap();
+        let expired_events = reg.get_message_events("expired").unwrap().len();
+
+        assert!(reg.purge_messages("queued", cutoff).is_err());
+        let (messages, events) = reg.purge_messages("expired", cutoff).unwrap();
+        assert_eq!(messages, 1);
+        assert_eq!(events, expired_events);
+        assert!(reg.get_message("expired").unwrap().is_none());
+        assert!(reg.get_message_events("expired").unwrap().is_empty());
+
+        // `dead_letter` covers every dead-lettered message, but only old ones
+        assert_eq!(reg.purge_messages("dead_letter", cutoff).unwrap().0, 1);
+        assert!(reg.get_message("failed").unwrap().is_none());
+        assert!(reg.get_message("recent").unwrap().is_some());
+
+        assert_eq!(reg.purge_messages("acknowledged", cutoff).unwrap().0, 1);
+        assert!(reg.get_message("queued").unwrap().is_some());
+        assert!(!reg.get_message_events("queued").unwrap().is_empty());
+    }
+
+    #[test]
+    fn dead_letter_records_agent_event_on_recipient() {
+        let reg = Registry::open_in_memory().unwrap();
+        reg.create_instance("id-b", "b", 18801, "/tmp/b.toml", None, None)
+            .unwrap();
+        enqueue_test_message(&reg, "m1");
+        enqueue_test_message(&reg, "m2");
+        reg.conn
+            .execute_batch(
+                "UPDATE messages SET correlation_id = 'thread-1' WHERE id = 'm1';
+                 UPDATE messages SET to_instance = 'gone' WHERE id = 'm2';",
+            )
+            .unwrap();
+
+        reg.dead_letter_message("m1", "max retries exceeded")
+            .unwrap();
+        // An unresolvable recipient still dead-letters, without an agent event
+        reg.dead_letter_message("m2", "max retries exceeded")
+            .unwrap();
+        assert_eq!(
+            reg.get_message("m2").unwrap().unwrap().status,
+            "dead_letter"
+        );
+
+        let (events, total) = reg
+            .list_agent_events(&["id-b"], 10, 0, None, None, None, None, None)
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(events[0].event_type, "message_dead_lettered");
+        assert_eq!(events[0].status, "failed");
+        assert_eq!(events[0].correlation_id.as_deref(), Some("thread-1"));
+        assert!(events[0]
+            .summary
+            .as_deref()
+            .unwrap()
+            .contains("max retries exceeded"));
+    }
+
+    #[test]
+    fn expire_message_records_ttl_expired_event() {
+        let reg = Registry::open_in_memory().unwrap();
+        enqueue_test_message(&reg, "m1");
+        enqueue_test_message(&reg, "m2");
+        reg.acknowledge_message("m2", None).unwrap();
+
+        assert!(reg.expire_message("m1").unwrap());
+        let msg = reg.get_message("m1").unwrap().unwrap();
+        assert_eq!(msg.status, "dead_letter");
+        assert_eq!(msg.dead_letter_reason.as_deref(), Some("ttl_expired"));
+
+        let events: Vec<String> = reg
+            .get_message_events("m1")
+            .unwrap()
+            .into_iter()
+            .map(|e| e.event_type)
+            .collect();
+        assert!(events.contains(&"ttl_expired".to_string()));
+        assert!(!events.contains(&"dead_lettered".to_string()));
+
+        // Already expired: no-op, no duplicate event
+        assert!(!reg.expire_message("m1").unwrap());
+        assert_eq!(reg.get_message_events("m1").unwrap().len(), events.len());
+
+        // m2 is still queued (acknowledge only applies to leased), so it expires
+        assert!(reg.expire_message("m2").unwrap());
+    }
+
+    #[test]
+    fn delivery_attempts_reconstruct_timeline() {
+        let reg = Registry::open_in_memory().unwrap();
+        enqueue_test_message(&reg, "m1");
+
+        // Attempt 1: leased, lease expires, retry scheduled
+        let batch = reg.lease_pending_messages("b", 5, 30).unwrap();
+        reg.record_lease_events(&batch, "b", "poll-1").unwrap();
+        reg.record_lease_outcome("m1", "lease_expired").unwrap();
+        reg.retry_message("m1").unwrap();
+        reg.conn
+            .execute("UPDATE messages SET next_attempt_at = NULL", [])
+            .unwrap();
+
+        // Attempt 2: leased, held ~5s, acknowledged
+        let batch = reg.lease_pending_messages("b", 5, 30).unwrap();
+        reg.record_lease_events(&batch, "b", "poll-2").unwrap();
+        let five_secs_ago = (chrono::Utc::now() - chrono::Duration::seconds(5))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        reg.conn
+            .execute(
+                "UPDATE message_events SET created_at = ?1
+                 WHERE id = (SELECT MAX(id) FROM message_events WHERE event_type = 'leased')",
+                params![five_secs_ago],
+            )
+            .unwrap();
+        assert!(reg.acknowledge_message("m1", None).unwrap());
+
+        let attempts = reg.get_delivery_attempts("m1").unwrap();
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(attempts[0].attempt, 1);
+        assert_eq!(attempts[0].poll_id.as_deref(), Some("poll-1"));
+        assert_eq!(attempts[0].consumer.as_deref(), Some("b"));
+        assert_eq!(attempts[0].lease_secs, Some(30));
+        assert_eq!(attempts[0].outcome, "lease_expired");
+        assert_eq!(attempts[1].attempt, 2);
+        assert_eq!(attempts[1].poll_id.as_deref(), Some("poll-2"));
+        assert_eq!(attempts[1].outcome, "acknowledged");
+        assert!(attempts[1].held_secs.unwrap() >= 5);
+
+        // Unstructured (legacy) events still yield an in-flight attempt
+        enqueue_test_message(&reg, "m2");
+        reg.append_message_event("m2", "leased", None).unwrap();
+        let legacy = reg.get_delivery_attempts("m2").unwrap();
+        assert_eq!(legacy.len(), 1);
+        assert_eq!(legacy[0].attempt, 1);
+        assert_eq!(legacy[0].consumer, None);
+        assert_eq!(legacy[0].outcome, "in_flight");
+    }
+
+    #[test]
+    fn queue_depth_counts_pending_per_recipient() {
+        let reg = Registry::open_in_memory().unwrap();
+        for id in ["m1", "m2", "m3"] {
+            enqueue_test_message(&reg, id);
+        }
+        reg.conn
+            .execute(
+                "UPDATE messages SET created_at = '2020-01-01 00:00:00' WHERE id = 'm2'",
+                [],
+            )
+            .unwrap();
+        reg.lease_pending_message("b").unwrap(); // leases m2 (oldest)
+        reg.dead_letter_message("m3", "test").unwrap();
+
+        let depths = reg.queue_depth_for(&["b", "idle"]).unwrap();
+        let b = &depths["b"];
+        assert_eq!(b.depth, 2); // m1 queued + m2 leased
+        assert_ne!(b.oldest_queued_at.as_deref(), Some("2020-01-01 00:00:00"));
+        assert!(b.oldest_queued_at.is_some());
+        assert!(!depths.contains_key("idle"));
+        assert!(reg.queue_depth_for(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn count_leasable_excludes_backoff_and_expired() {
+        let reg = Registry::open_in_memory().unwrap();
+        for id in ["m1", "m2", "m3", "m4"] {
+            enqueue_test_message(&reg, id);
+        }
+        reg.conn
+            .execute(
+                "UPDATE messages SET next_attempt_at = '2999-01-01 00:00:00' WHERE id = 'm2'",
+                [],
+            )
+            .unwrap();
+        reg.conn
+            .execute(
+                "UPDATE messages SET expires_at = '2000-01-01 00:00:00' WHERE id = 'm3'",
+                [],
+            )
+            .unwrap();
+        reg.lease_pending_message("b").unwrap();
+
+        // m1 leased, m4 ready, m2 in backoff, m3 past its TTL
+        assert_eq!(reg.count_leasable("b").unwrap(), 1);
+        assert_eq!(reg.count_leasable("idle").unwrap(), 0);
+        let depth = &reg.queue_depth_for(&["b"]).unwrap()["b"];
+        assert_eq!(depth.depth, 4);
+        assert_eq!(depth.leasable, 1);
+    }
+
+    #[test]
+    fn iter_messages_pages_through_every_row() {
+        let reg = Registry::open_in_memory().unwrap();
+        let total = MESSAGE_ITER_BATCH + 1;
+        for i in 0..total {
+            enqueue_test_message(&reg, &format!("m{i:04}"));
+        }
+        reg.conn
+            .execute(
+                "UPDATE messages SET created_at = '2020-01-01 00:00:00' WHERE id < 'm0100'",
+                [],
+            )
+            .unwrap();
+
+        let ids: Vec<String> = reg.iter_messages(None).map(|m| m.unwrap().id).collect();
+        assert_eq!(ids.len(), total);
+        // Backdated rows come first, ties broken by id
+        assert_eq!(ids[0], "m0000");
+        assert_eq!(ids[99], "m0099");
+        let unique: std::collections::HashSet<_> = ids.iter().collect();
+        assert_eq!(unique.len(), total);
+
+        let recent = reg.iter_messages(Some("2021-01-01 00:00:00")).count();
+        assert_eq!(recent, total - 100);
+    }
+
+    #[test]
+    fn correlation_route_path_lists_recipients_once() {
+        let reg = Registry::open_in_memory().unwrap();
+        for (id, from, to) in [("m1", "a", "b"), ("m2", "b", "a"), ("m3", "a", "b")] {
+            reg.enqueue_message(&NewMessage {
+                id: id.to_string(),
+                from_instance: from.to_string(),
+                to_instance: to.to_string(),
+                message_type: "task".to_string(),
+                payload: "{}".to_string(),
+                correlation_id: Some("thread".to_string()),
+                idempotency_key: None,
+                idempotency_window_secs: None,
+                hop_count: 0,
+                max_retries: 5,
+                ttl_secs: 3600,
+                content_type: None,
+                priority: 0,
+            })
+            .unwrap();
+        }
+        enqueue_test_message(&reg, "other");
+
+        assert_eq!(reg.correlation_route_path("thread").unwrap(), ["b", "a"]);
+        assert!(reg.correlation_route_path("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn recipient_health_gate_takes_strictest_rule() {
+        let reg = Registry::open_in_memory().unwrap();
+        assert_eq!(reg.recipient_health_gate("b").unwrap(), HealthGate::Off);
+
+        for (from, to, gate) in [
+            ("a", "b", HealthGate::Running),
+            ("c", "b", HealthGate::Http),
+            ("a", "c", HealthGate::Off),
+        ] {
+            reg.create_routing_rule(from, to, "*", 5, 3600, 90, false, false, gate, None)
+                .unwrap();
+        }
+
+        assert_eq!(reg.recipient_health_gate("b").unwrap(), HealthGate::Http);
+        assert_eq!(reg.recipient_health_gate("c").unwrap(), HealthGate::Off);
+        let rules = reg.list_routing_rules().unwrap();
+        let a_to_b = rules
+            .iter()
+            .find(|r| r.from_instance == "a" && r.to_instance == "b");
+        assert_eq!(a_to_b.unwrap().health_gate, HealthGate::Running);
+        assert_eq!(HealthGate::parse("http"), Some(HealthGate::Http));
+        assert_eq!(HealthGate::parse("bogus"), None);
+    }
+
+    #[test]
+    fn instance_activity_takes_newest_per_source() {
+        let reg = Registry::open_in_memory().unwrap();
+        reg.create_instance("id-b", "b", 18801, "/tmp/b.toml", None, None)
+            .unwrap();
+        let idle = reg.instance_activity("id-b", "b").unwrap();
+        assert_eq!(idle, InstanceActivity::default());
+        assert_eq!(idle.last_activity_at(), None);
+
+        // a -> b, so b has inbound traffic only
+        for id in ["m1", "m2"] {
+            enqueue_test_message(&reg, id);
+        }
+        reg.conn
+            .execute(
+                "UPDATE messages SET created_at = '2020-01-01 00:00:00' WHERE id = 'm1';",
+                [],
+            )
+            .unwrap();
+        reg.conn
+            .execute(
+                "UPDATE messages SET created_at = '2020-01-02 00:00:00' WHERE id = 'm2';",
+                [],
+            )
+            .unwrap();
+        reg.insert_agent_event(&AgentEvent {
+            id: "e1".into(),
+            instance_id: "id-b".into(),
+            event_type: "task".into(),
+            channel: None,
+            summary: None,
+            status: "completed".into(),
+            duration_ms: None,
+            correlation_id: None,
+            metadata: None,
+            created_at: "2020-01-03 00:00:00".into(),
+        })
+        .unwrap();
+
+        let b = reg.instance_activity("id-b", "b").unwrap();
+        assert_eq!(b.last_event_at.as_deref(), Some("2020-01-03 00:00:00"));
+        assert_eq!(
+            b.last_inbound_message_at.as_deref(),
+            Some("2020-01-02 00:00:00")
+        );
+        assert_eq!(b.last_outbound_message_at, None);
+        assert_eq!(b.last_activity_at(), Some("2020-01-03 00:00:00"));
+
+        let a = reg.instance_activity("id-a", "a").unwrap();
+        assert_eq!(
+            a.last_outbound_message_at.as_deref(),
+            Some("2020-01-02 00:00:00")
+        );
+        assert_eq!(a.last_activity_at(), Some("2020-01-02 00:00:00"));
+    }
+
+    #[test]
+    fn dead_letter_reasons_summary_groups_by_reason() {
+        let reg = Registry::open_in_memory().unwrap();
+        for id in ["m1", "m2", "m3", "m4"] {
+            enqueue_test_message(&reg, id);
+        }
+        reg.dead_letter_message("m1", "max retries exceeded").unwrap();
+        reg.dead_letter_message("m2", "max retries exceeded").unwrap();
+        reg.dead_letter_message("m3", "TTL expired").unwrap();
+
+        let dl = reg.get_message("m1").unwrap().unwrap();
+        assert_eq!(dl.dead_letter_reason.as_deref(), Some("max retries exceeded"));
+        assert!(reg.get_message("m4").unwrap().unwrap().dead_letter_reason.is_none());
+
+        let summary = reg.dead_letter_reasons_summary().unwrap();
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].reason, "max retries exceeded");
+        assert_eq!(summary[0].count, 2);
+        assert_eq!(summary[1].reason, "TTL expired");
+        assert_eq!(summary[1].count, 1);
+    }
+
+    #[test]
+    fn reassign_port_rejects_active_conflicts() {
+        let reg = Registry::open_in_memory().unwrap();
+        reg.create_instance("id-1", "one", 18801, "/tmp/1.toml", None, None)
+            .unwrap();
+        reg.create_instance("id-2", "two", 18802, "/tmp/2.toml", None, None)
+            .unwrap();
+
+        assert_eq!(
+            reg.reassign_port("id-1", 18802).unwrap().as_deref(),
+            Some("two")
+        );
+        assert_eq!(reg.get_instance("id-1").unwrap().unwrap().port, 18801);
+
+        // Same port is a no-op, a free port moves the instance
+        assert!(reg.reassign_port("id-1", 18801).unwrap().is_none());
+        assert!(reg.reassign_port("id-1", 18803).unwrap().is_none());
+        assert_eq!(reg.get_instance("id-1").unwrap().unwrap().port, 18803);
+
+        assert!(reg.reassign_port("missing", 18900).is_err());
+    }
+
+    #[test]
+    fn update_pid_errors_on_missing_instance() {
+        let reg = Registry::open_in_memory().unwrap();
+        let err = reg.update_pid("nonexistent", Some(123)).unwrap_err();
+        assert!(err.to_string().contains("No instance"));
+    }
+
+    /// Hold a write lock on `db_path` for `hold`, then commit.
+    fn hold_write_lock(db_path: &Path, hold: std::time::Duration) -> std::thread::JoinHandle<()> {
+        let blocker = Connection::open(db_path).unwrap();
+        blocker.execute_batch("BEGIN IMMEDIATE").unwrap();
+        std::thread::spawn(move || {
+            std::thread::sleep(hold);
+            blocker.execute_batch("COMMIT").unwrap();
+        })
+    }
+
+    #[test]
+    fn busy_timeout_waits_for_concurrent_writer() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        let reg = Registry::open_with_tuning(&db_path, &SqliteTuning::default()).unwrap();
+
+        let hold = std::time::Duration::from_millis(300);
+        let blocker = hold_write_lock(&db_path, hold);
+        let started = std::time::Instant::now();
+        enqueue_test_message(&reg, "m1");
+        assert!(
+            started.elapsed() >= hold / 2,
+            "write should wait for the lock"
+        );
+        blocker.join().unwrap();
+        assert!(reg.get_message("m1").unwrap().is_some());
+    }
+
+    #[test]
+    fn zero_busy_timeout_fails_fast_under_contention() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        let tuning = SqliteTuning {
+            busy_timeout_ms: 0,
+            ..SqliteTuning::default()
+        };
+        let reg = Registry::open_with_tuning(&db_path, &tuning).unwrap();
+
+        // Held past the busy retries, which then give up
+        let blocker = hold_write_lock(&db_path, std::time::Duration::from_millis(1500));
+        let err = reg
+            .enqueue_message(&NewMessage {
+                id: "m1".to_string(),
+                from_instance: "a".to_string(),
+                to_instance: "b".to_string(),
+                message_type: "task".to_string(),
+                payload: "{}".to_string(),
+                correlation_id: None,
+                idempotency_key: None,
+                idempotency_window_secs: None,
+                hop_count: 0,
+                max_retries: 5,
+                ttl_secs: 3600,
+                content_type: None,
+                priority: 0,
+            })
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("locked"), "{err:#}");
+        blocker.join().unwrap();
+    }
+
+    #[test]
+    fn with_retry_rides_out_a_short_lock() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        let tuning = SqliteTuning {
+            busy_timeout_ms: 0,
+            ..SqliteTuning::default()
+        };
+        let reg = Registry::open_with_tuning(&db_path, &tuning).unwrap();
+
+        let blocker = hold_write_lock(&db_path, std::time::Duration::from_millis(100));
+        enqueue_test_message(&reg, "m1");
+        blocker.join().unwrap();
+        assert!(reg.get_message("m1").unwrap().is_some());
+    }
+
+    #[test]
+    fn concurrent_enqueues_all_succeed() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        drop(Registry::open(&db_path).unwrap());
+
+        let threads: Vec<_> = (0..8)
+            .map(|t| {
+                let db_path = db_path.clone();
+                std::thread::spawn(move || {
+                    let reg = Registry::open(&db_path).unwrap();
+                    for i in 0..25 {
+                        enqueue_test_message(&reg, &format!("t{t}-m{i}"));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().expect("enqueue thread failed");
+        }
+
+        let reg = Registry::open(&db_path).unwrap();
+        assert_eq!(reg.iter_messages(None).count(), 8 * 25);
+    }
+
+    #[test]
+    fn tuning_applies_configured_pragmas() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let db_path = tmp.path().join("registry.db");
+        let tuning = SqliteTuning {
+            busy_timeout_ms: 1234,
+            journal_mode: "DELETE".into(),
+            synchronous: "NORMAL".into(),
+            query_timeout_ms: 0,
+        };
+        let reg = Registry::open_with_tuning(&db_path, &tuning).unwrap();
+        let pragma = |name: &str| -> i64 {
+            reg.conn
+                .query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(pragma("busy_timeout"), 1234);
+        assert_eq!(pragma("synchronous"), 1);
+        let mode: String = reg
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "delete");
+
+        let bad = SqliteTuning {
+            journal_mode: "WAL; DROP TABLE instances".into(),
+            ..SqliteTuning::default()
+        };
+        assert!(Registry::open_with_tuning(&db_path, &bad).is_err());
+    }
+
+    #[test]
+    fn query_timeout_interrupts_runaway_scan() {
+        let reg = Registry::open_in_memory().unwrap();
+        let runaway = "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) \
+                       SELECT COUNT(*) FROM n";
+
+        reg.set_query_timeout(Some(std::time::Duration::from_millis(50)));
+        let started = std::time::Instant::now();
+        let err = anyhow::Error::from(
+            reg.conn
+                .query_row(runaway, [], |row| row.get::<_, i64>(0))
+                .unwrap_err(),
+        )
+        .context("scan");
+        assert!(is_query_timeout(&err), "{err:#}");
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+
+        // Once lifted, ordinary queries run normally again
+        reg.set_query_timeout(None);
+        assert!(reg.list_routing_rules().unwrap().is_empty());
     }
 }
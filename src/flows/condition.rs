@@ -0,0 +1,129 @@
+//! Conditions for `branch` steps.
+//!
+//! A condition tests one variable of the flow's runtime context:
+//!
+//! - `name` -- the variable is set
+//! - `!name` -- the variable is not set
+//! - `name == value` / `name != value` -- the variable is (not) set to
+//!   `value`, written bare or in `"double"` or `'single'` quotes
+//!
+//! An unset variable is never equal to anything.
+
+use std::collections::HashMap;
+
+use super::template::is_var_name;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Present(String),
+    Absent(String),
+    Eq(String, String),
+    Ne(String, String),
+}
+
+impl Condition {
+    /// Evaluate against the flow's runtime context.
+    pub fn eval(&self, ctx: &HashMap<String, String>) -> bool {
+        match self {
+            Self::Present(name) => ctx.contains_key(name),
+            Self::Absent(name) => !ctx.contains_key(name),
+            Self::Eq(name, value) => ctx.get(name) == Some(value),
+            Self::Ne(name, value) => ctx.get(name) != Some(value),
+        }
+    }
+}
+
+/// Parse a branch condition.
+pub fn parse_condition(src: &str) -> Result<Condition, String> {
+    let src = src.trim();
+    if src.is_empty() {
+        return Err("condition is empty".into());
+    }
+
+    // The first operator splits; anything after it belongs to the value
+    let op = ["==", "!="]
+        .into_iter()
+        .filter_map(|op| src.find(op).map(|at| (at, op)))
+        .min();
+    if let Some((at, op)) = op {
+        let name = var_name(&src[..at])?;
+        let value = literal(&src[at + op.len()..])?;
+        return Ok(if op == "==" {
+            Condition::Eq(name, value)
+        } else {
+            Condition::Ne(name, value)
+        });
+    }
+
+    match src.strip_prefix('!') {
+        Some(name) => Ok(Condition::Absent(var_name(name)?)),
+        None => Ok(Condition::Present(var_name(src)?)),
+    }
+}
+
+fn var_name(src: &str) -> Result<String, String> {
+    let name = src.trim();
+    if is_var_name(name) {
+        Ok(name.to_string())
+    } else {
+        Err(format!("'{name}' is not a variable name"))
+    }
+}
+
+fn literal(src: &str) -> Result<String, String> {
+    let src = src.trim();
+    for quote in ['"', '\''] {
+        if let Some(rest) = src.strip_prefix(quote) {
+            return rest
+                .strip_suffix(quote)
+                .map(String::from)
+                .ok_or_else(|| format!("unterminated quote in {src}"));
+        }
+    }
+    if src.is_empty() {
+        Err("missing value to compare with".into())
+    } else if src.contains(char::is_whitespace) {
+        Err(format!("value '{src}' must be quoted"))
+    } else {
+        Ok(src.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_presence_and_comparisons() {
+        let ok = |src: &str| parse_condition(src).unwrap();
+        assert_eq!(ok(" ticket "), Condition::Present("ticket".into()));
+        assert_eq!(ok("!ticket"), Condition::Absent("ticket".into()));
+        assert_eq!(ok("ask == yes"), Condition::Eq("ask".into(), "yes".into()));
+        assert_eq!(
+            ok(r#"ask != "not now""#),
+            Condition::Ne("ask".into(), "not now".into())
+        );
+        assert_eq!(
+            ok("ask == 'a != b'"),
+            Condition::Eq("ask".into(), "a != b".into())
+        );
+
+        for bad in ["", "two words", "== yes", "ask ==", "ask == 'open"] {
+            assert!(parse_condition(bad).is_err(), "{bad:?} should not parse");
+        }
+        // Values with spaces must be quoted
+        assert!(parse_condition("ask == not now").is_err());
+    }
+
+    #[test]
+    fn evaluates_against_context() {
+        let ctx = HashMap::from([("ask".to_string(), "yes".to_string())]);
+        let holds = |src: &str| parse_condition(src).unwrap().eval(&ctx);
+        assert!(holds("ask"));
+        assert!(!holds("!ask"));
+        assert!(holds("ask == yes"));
+        assert!(!holds("ask != yes"));
+        assert!(!holds("missing == yes"));
+        assert!(holds("missing != yes"));
+    }
+}
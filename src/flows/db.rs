@@ -13,6 +13,8 @@ pub struct FlowInstanceRow {
     pub step_entered_at: String,
     pub anchor_message_id: Option<i64>,
     pub status: String,
+    /// JSON object of the flow's variables: start inputs and answers so far.
+    pub vars: String,
 }
 
 /// Row type for flow history.
@@ -96,7 +98,8 @@ impl FlowDb {
                  step_entered_at  TEXT NOT NULL,
                  anchor_message_id INTEGER,
                  status           TEXT NOT NULL DEFAULT 'active',
-                 created_at       TEXT NOT NULL DEFAULT (datetime('now'))
+                 created_at       TEXT NOT NULL DEFAULT (datetime('now')),
+                 vars             TEXT NOT NULL DEFAULT '{}'
              );
              CREATE INDEX IF NOT EXISTS idx_flow_instances_status
                  ON flow_instances(status);
@@ -159,6 +162,18 @@ impl FlowDb {
                  BEFORE DELETE ON flow_audit_log
                  BEGIN SELECT RAISE(ABORT, 'flow_audit_log is append-only'); END;",
         )?;
+
+        // Migration: flow variables, added after flow_instances shipped
+        let has_vars: bool = guard
+            .prepare("PRAGMA table_info(flow_instances)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(Result::ok)
+            .any(|col| col == "vars");
+        if !has_vars {
+            guard.execute_batch(
+                "ALTER TABLE flow_instances ADD COLUMN vars TEXT NOT NULL DEFAULT '{}';",
+            )?;
+        }
         Ok(())
     }
 
@@ -170,8 +185,8 @@ impl FlowDb {
         guard.execute(
             "INSERT OR REPLACE INTO flow_instances
                 (chat_id, flow_name, current_step, started_at, step_entered_at,
-                 anchor_message_id, status)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                 anchor_message_id, status, vars)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
             params![
                 row.chat_id,
                 row.flow_name,
@@ -180,6 +195,7 @@ impl FlowDb {
                 row.step_entered_at,
                 row.anchor_message_id,
                 row.status,
+                row.vars,
             ],
         )?;
         Ok(())
@@ -190,7 +206,7 @@ impl FlowDb {
         let guard = self.conn.lock().unwrap_or_else(|e| e.into_inner());
         let mut stmt = guard.prepare(
             "SELECT chat_id, flow_name, current_step, started_at, step_entered_at,
-                    anchor_message_id, status
+                    anchor_message_id, status, vars
              FROM flow_instances WHERE chat_id = ?1",
         )?;
         let mut rows = stmt.query_map(params![chat_id], |row| {
@@ -202,6 +218,7 @@ impl FlowDb {
                 step_entered_at: row.get(4)?,
                 anchor_message_id: row.get(5)?,
                 status: row.get(6)?,
+                vars: row.get(7)?,
             })
         })?;
         match rows.next() {
@@ -216,7 +233,7 @@ impl FlowDb {
         let guard = self.conn.lock().unwrap_or_else(|e| e.into_inner());
         let mut stmt = guard.prepare(
             "SELECT chat_id, flow_name, current_step, started_at, step_entered_at,
-                    anchor_message_id, status
+                    anchor_message_id, status, vars
              FROM flow_instances WHERE status = 'active'",
         )?;
         let rows = stmt.query_map([], |row| {
@@ -228,6 +245,7 @@ impl FlowDb {
                 step_entered_at: row.get(4)?,
                 anchor_message_id: row.get(5)?,
                 status: row.get(6)?,
+                vars: row.get(7)?,
             })
         })?;
         let mut result = Vec::new();
@@ -255,6 +273,16 @@ impl FlowDb {
         Ok(changed > 0)
     }
 
+    /// Replace the variables of an active flow. Returns true if a row was updated.
+    pub fn update_vars(&self, chat_id: &str, vars: &str) -> anyhow::Result<bool> {
+        let guard = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let changed = guard.execute(
+            "UPDATE flow_instances SET vars = ?2 WHERE chat_id = ?1 AND status = 'active'",
+            params![chat_id, vars],
+        )?;
+        Ok(changed > 0)
+    }
+
     /// Complete a flow: move from flow_instances to flow_history.
     /// Returns the removed row (if any).
     pub fn complete_flow(
@@ -268,7 +296,7 @@ impl FlowDb {
         let row = {
             let mut stmt = guard.prepare(
                 "SELECT chat_id, flow_name, current_step, started_at, step_entered_at,
-                        anchor_message_id, status
+                        anchor_message_id, status, vars
                  FROM flow_instances WHERE chat_id = ?1",
             )?;
             let mut rows = stmt.query_map(params![chat_id], |row| {
@@ -280,6 +308,7 @@ impl FlowDb {
                     step_entered_at: row.get(4)?,
                     anchor_message_id: row.get(5)?,
                     status: row.get(6)?,
+                    vars: row.get(7)?,
                 })
             })?;
             match rows.next() {
@@ -826,6 +855,7 @@ mod tests {
             step_entered_at: "2026-01-01T00:00:00Z".into(),
             anchor_message_id: Some(42),
             status: "active".into(),
+            vars: "{}".into(),
         };
         db.upsert_active(&row).unwrap();
         let got = db.get_active("chat1").unwrap().unwrap();
@@ -844,6 +874,7 @@ mod tests {
             step_entered_at: "2026-01-01T00:00:00Z".into(),
             anchor_message_id: None,
             status: "active".into(),
+            vars: "{}".into(),
         };
         db.upsert_active(&row).unwrap();
         let updated = db
@@ -866,6 +897,7 @@ mod tests {
             step_entered_at: "2026-01-01T00:00:00Z".into(),
             anchor_message_id: Some(10),
             status: "active".into(),
+            vars: "{}".into(),
         };
         db.upsert_active(&row).unwrap();
         let completed = db.complete_flow("chat1", "completed").unwrap();
@@ -893,6 +925,7 @@ mod tests {
                 step_entered_at: "2026-01-01T00:00:00Z".into(),
                 anchor_message_id: None,
                 status: "active".into(),
+                vars: "{}".into(),
             };
             db.upsert_active(&row).unwrap();
         }
@@ -913,6 +946,7 @@ mod tests {
                 step_entered_at: "2026-01-01T00:00:00Z".into(),
                 anchor_message_id: None,
                 status: "active".into(),
+                vars: "{}".into(),
             };
             db.upsert_active(&row).unwrap();
             db.complete_flow(&row.chat_id, status).unwrap();
@@ -1,4 +1,9 @@
-use super::types::{ButtonDef, Step, StepKind};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+
+use super::template::MissingVar;
+use super::types::{ButtonDef, FlowDefinition, Step, StepKind};
 use crate::channels::telegram::TelegramChannel;
 use crate::channels::telegram_types::InlineButton;
 
@@ -10,14 +15,20 @@ pub struct StepExecuteResult {
     pub poll_id: Option<String>,
 }
 
-/// Execute a flow step against the Telegram API.
+/// Execute a flow step against the Telegram API, with `{{var}}`
+/// placeholders in its text filled from the flow's `vars`. A placeholder
+/// with no value fails the step before anything is sent.
 /// Returns a `StepExecuteResult` with the message_id and optional poll_id.
 pub async fn execute_step(
     channel: &TelegramChannel,
     chat_id: &str,
     step: &Step,
     anchor_message_id: Option<i64>,
+    vars: &HashMap<String, String>,
 ) -> anyhow::Result<StepExecuteResult> {
+    let text = step
+        .render_with(vars, MissingVar::Error)
+        .map_err(|e| anyhow::anyhow!("step '{}': {e}", step.id))?;
     match step.kind {
         StepKind::Keyboard => {
             let buttons = step
@@ -29,9 +40,7 @@ pub async fn execute_step(
                         .collect::<Vec<Vec<InlineButton>>>()
                 })
                 .unwrap_or_default();
-            let msg_id = channel
-                .send_with_keyboard(chat_id, &step.text, &buttons)
-                .await?;
+            let msg_id = channel.send_with_keyboard(chat_id, &text, &buttons).await?;
             Ok(StepExecuteResult {
                 anchor_message_id: Some(msg_id),
                 poll_id: None,
@@ -44,7 +53,7 @@ pub async fn execute_step(
                 .cloned()
                 .unwrap_or_default();
             let (msg_id, poll_id) = channel
-                .send_poll_with_id(chat_id, &step.text, &options, step.poll_anonymous)
+                .send_poll_with_id(chat_id, &text, &options, step.poll_anonymous)
                 .await?;
             Ok(StepExecuteResult {
                 anchor_message_id: Some(msg_id),
@@ -52,15 +61,7 @@ pub async fn execute_step(
             })
         }
         StepKind::Message => {
-            let url = channel.api_url("sendMessage");
-            let body = serde_json::json!({
-                "chat_id": chat_id,
-                "text": &step.text,
-                "parse_mode": "Markdown",
-            });
-            let resp = channel.http_client().post(&url).json(&body).send().await?;
-            let data: serde_json::Value = resp.json().await?;
-            let msg_id = data["result"]["message_id"].as_i64();
+            let msg_id = channel.send_markdown_message(chat_id, &text).await?;
             Ok(StepExecuteResult {
                 anchor_message_id: msg_id,
                 poll_id: None,
@@ -74,7 +75,7 @@ pub async fn execute_step(
                         .collect()
                 });
                 channel
-                    .edit_message_text(chat_id, anchor_id, &step.text, buttons.as_deref())
+                    .edit_message_text(chat_id, anchor_id, &text, buttons.as_deref())
                     .await?;
                 Ok(StepExecuteResult {
                     anchor_message_id: Some(anchor_id),
@@ -82,22 +83,151 @@ pub async fn execute_step(
                 })
             } else {
                 tracing::warn!("edit step '{}' has no anchor message_id, sending new message", step.id);
-                let url = channel.api_url("sendMessage");
-                let body = serde_json::json!({
-                    "chat_id": chat_id,
-                    "text": &step.text,
-                    "parse_mode": "Markdown",
-                });
-                let resp = channel.http_client().post(&url).json(&body).send().await?;
-                let data: serde_json::Value = resp.json().await?;
-                let msg_id = data["result"]["message_id"].as_i64();
+                let msg_id = channel.send_markdown_message(chat_id, &text).await?;
                 Ok(StepExecuteResult {
                     anchor_message_id: msg_id,
                     poll_id: None,
                 })
             }
         }
+        // Branches are normally resolved by `resolve_branch` before
+        // execution; delays wait on the step timer. Neither sends anything.
+        StepKind::Branch | StepKind::Delay => Ok(StepExecuteResult {
+            anchor_message_id,
+            poll_id: None,
+        }),
+    }
+}
+
+/// Follow `branch` steps from `step`, evaluating each condition against the
+/// flow's runtime context `ctx`, to the first step that is not a branch.
+pub fn resolve_branch<'a>(
+    flow: &'a FlowDefinition,
+    mut step: &'a Step,
+    ctx: &HashMap<String, String>,
+) -> anyhow::Result<&'a Step> {
+    // More hops than steps means the branches loop among themselves
+    for _ in 0..=flow.steps.len() {
+        let Some(branch) = &step.branch else {
+            return Ok(step);
+        };
+        let target = if branch.condition.eval(ctx) {
+            &branch.then
+        } else {
+            &branch.otherwise
+        };
+        step = flow.steps.get(target).ok_or_else(|| {
+            anyhow::anyhow!("branch step '{}': target '{target}' not found", step.id)
+        })?;
     }
+    anyhow::bail!(
+        "flow '{}': branch steps loop without reaching a step to run",
+        flow.name
+    )
+}
+
+/// How long executing a step -- its Telegram calls -- may take. This is
+/// separate from the step's `timeout_secs`, which is how long the flow waits
+/// for the user to answer once the step has run.
+pub const STEP_EXECUTION_TIMEOUT_SECS: u64 = 30;
+
+/// A step did not finish executing within [`STEP_EXECUTION_TIMEOUT_SECS`].
+#[derive(Debug, Clone)]
+pub struct StepTimeout {
+    pub step_id: String,
+    pub timeout_secs: u64,
+}
+
+impl std::fmt::Display for StepTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "step '{}' timed out after {}s",
+            self.step_id, self.timeout_secs
+        )
+    }
+}
+
+impl std::error::Error for StepTimeout {}
+
+/// Whether an execution error is a [`StepTimeout`].
+pub fn is_step_timeout(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<StepTimeout>().is_some()
+}
+
+/// Bound a step execution by `timeout_secs` (0 = unbounded).
+pub async fn with_step_timeout<T, F>(step_id: &str, timeout_secs: u64, fut: F) -> anyhow::Result<T>
+where
+    F: Future<Output = anyhow::Result<T>>,
+{
+    if timeout_secs == 0 {
+        return fut.await;
+    }
+    match tokio::time::timeout(Duration::from_secs(timeout_secs), fut).await {
+        Ok(result) => result,
+        Err(_) => Err(StepTimeout {
+            step_id: step_id.to_string(),
+            timeout_secs,
+        }
+        .into()),
+    }
+}
+
+/// A step run through [`run_step_routed`].
+#[derive(Debug)]
+pub struct RoutedStep<'a> {
+    /// Step the flow landed on: the requested step, or its `_error` target.
+    pub step: &'a Step,
+    pub result: StepExecuteResult,
+    /// The requested step timed out (and was routed to `_error`).
+    pub timed_out: bool,
+}
+
+/// Execute `step` bounded by [`STEP_EXECUTION_TIMEOUT_SECS`]. If it fails
+/// (including by timing out) and declares an `_error` transition, execute
+/// that target instead. Errors are returned only when there is no `_error` route or the
+/// error step itself fails.
+///
+/// `branch` steps are resolved against `ctx` first, so the step executed
+/// (and returned) is never a branch.
+pub async fn run_step_routed<'a, F, Fut>(
+    flow: &'a FlowDefinition,
+    step: &'a Step,
+    ctx: &HashMap<String, String>,
+    mut exec: F,
+) -> anyhow::Result<RoutedStep<'a>>
+where
+    F: FnMut(&'a Step) -> Fut,
+    Fut: Future<Output = anyhow::Result<StepExecuteResult>>,
+{
+    let step = resolve_branch(flow, step, ctx)?;
+    let err = match with_step_timeout(&step.id, STEP_EXECUTION_TIMEOUT_SECS, exec(step)).await {
+        Ok(result) => {
+            return Ok(RoutedStep {
+                step,
+                result,
+                timed_out: false,
+            })
+        }
+        Err(e) => e,
+    };
+
+    let Some(target) = step.error_target().and_then(|id| flow.steps.get(id)) else {
+        return Err(err);
+    };
+    let target = resolve_branch(flow, target, ctx)?;
+    tracing::warn!(
+        "flow '{}': step '{}' failed ({err}); routing to '{}'",
+        flow.name,
+        step.id,
+        target.id
+    );
+    let result = with_step_timeout(&target.id, STEP_EXECUTION_TIMEOUT_SECS, exec(target)).await?;
+    Ok(RoutedStep {
+        step: target,
+        result,
+        timed_out: is_step_timeout(&err),
+    })
 }
 
 fn button_def_to_inline(b: &ButtonDef) -> InlineButton {
@@ -122,6 +252,143 @@ mod tests {
         assert_eq!(inline.callback_data, "ok_data");
     }
 
+    fn message_step(id: &str, timeout_secs: Option<u64>, on_error: Option<&str>) -> Step {
+        Step {
+            id: id.into(),
+            kind: StepKind::Message,
+            text: id.into(),
+            buttons: None,
+            poll_options: None,
+            poll_anonymous: true,
+            timeout_secs,
+            agent_handoff: false,
+            transitions: on_error
+                .map(|target| {
+                    vec![crate::flows::types::TransitionDef {
+                        on: crate::flows::types::ON_ERROR.into(),
+                        target: target.into(),
+                    }]
+                })
+                .unwrap_or_default(),
+            branch: None,
+        }
+    }
+
+    fn flow_with(steps: Vec<Step>) -> FlowDefinition {
+        FlowDefinition {
+            name: "slow".into(),
+            description: None,
+            start_step: steps[0].id.clone(),
+            default_timeout_secs: 0,
+            deadline_secs: None,
+            inputs: Vec::new(),
+            steps: steps.into_iter().map(|s| (s.id.clone(), s)).collect(),
+        }
+    }
+
+    /// Sleeps forever on the "hang" step, succeeds instantly otherwise.
+    async fn fake_exec(step: &Step) -> anyhow::Result<StepExecuteResult> {
+        if step.id == "hang" {
+            std::future::pending::<()>().await;
+        }
+        Ok(StepExecuteResult {
+            anchor_message_id: Some(7),
+            poll_id: None,
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_step_times_out_and_routes_to_error_step() {
+        // The wait-for-answer timeout does not bound execution
+        let flow = flow_with(vec![
+            message_step("hang", Some(3600), Some("oops")),
+            message_step("oops", None, None),
+        ]);
+        let routed = run_step_routed(&flow, &flow.steps["hang"], &HashMap::new(), fake_exec)
+            .await
+            .unwrap();
+        assert_eq!(routed.step.id, "oops");
+        assert!(routed.timed_out);
+        assert_eq!(routed.result.anchor_message_id, Some(7));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_step_without_error_route_returns_step_timeout() {
+        let flow = flow_with(vec![message_step("hang", None, None)]);
+        let err = run_step_routed(&flow, &flow.steps["hang"], &HashMap::new(), fake_exec)
+            .await
+            .unwrap_err();
+        assert!(is_step_timeout(&err));
+        assert_eq!(err.to_string(), "step 'hang' timed out after 30s");
+    }
+
+    #[tokio::test]
+    async fn fast_step_is_not_rerouted() {
+        let flow = flow_with(vec![
+            message_step("ok", Some(5), Some("oops")),
+            message_step("oops", None, None),
+        ]);
+        let routed = run_step_routed(&flow, &flow.steps["ok"], &HashMap::new(), fake_exec)
+            .await
+            .unwrap();
+        assert_eq!(routed.step.id, "ok");
+        assert!(!routed.timed_out);
+    }
+
+    #[tokio::test]
+    async fn execute_step_renders_text_from_flow_vars() {
+        use crate::channels::quiet_hours::{DeferredSend, QuietHours, QuietHoursGate};
+        use std::sync::Arc;
+
+        // A quiet-hours window around now holds every send, so the request
+        // lands in the gate's state file instead of going to Telegram
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("deferred.json");
+        let now = chrono::Utc::now();
+        let config = crate::config::QuietHoursConfig {
+            utc_offset: "UTC".into(),
+            start: (now - chrono::Duration::hours(1))
+                .format("%H:%M")
+                .to_string(),
+            end: (now + chrono::Duration::hours(1))
+                .format("%H:%M")
+                .to_string(),
+        };
+        let gate = Arc::new(QuietHoursGate::new(
+            QuietHours::from_config(&config).unwrap(),
+            Arc::new(crate::observability::NoopObserver),
+            &path,
+        ));
+        let channel = TelegramChannel::new("t".into(), vec![]).with_quiet_hours(gate.clone());
+
+        let mut step = message_step("report", None, None);
+        step.text = "Ticket {{ticket}}: you said {{ask}}".into();
+        let vars = HashMap::from([
+            ("ticket".to_string(), "T-1".to_string()),
+            ("ask".to_string(), "yes".to_string()),
+        ]);
+        execute_step(&channel, "42", &step, None, &vars)
+            .await
+            .unwrap();
+
+        let held: Vec<DeferredSend> =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let DeferredSend::TelegramJson { body, .. } = &held[0] else {
+            panic!("expected a held sendMessage, got {:?}", held[0]);
+        };
+        assert_eq!(body["text"], "Ticket T-1: you said yes");
+
+        // A placeholder with no value fails the step without sending
+        let err = execute_step(&channel, "42", &step, None, &HashMap::new())
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "step 'report': no value for variable 'ticket'"
+        );
+        assert_eq!(gate.deferred_count(), 1);
+    }
+
     #[test]
     fn step_execute_result_fields() {
         let result = StepExecuteResult {
@@ -1,31 +1,46 @@
+pub mod condition;
 pub mod db;
 pub mod execute;
 pub mod policy;
+pub mod run;
 pub mod state;
+pub mod template;
 pub mod types;
 pub mod validate;
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use types::FlowDefinition;
 
-/// Load and validate all flow TOML files from the given directory.
-/// Returns a map of flow_name -> FlowDefinition.
-///
-/// If the directory does not exist, returns an empty map (not an error).
-/// If any flow file fails to parse or validate, returns an error.
-pub fn load_flows(flows_dir: &Path) -> anyhow::Result<HashMap<String, FlowDefinition>> {
+/// One problem found while validating or loading flow files (see
+/// [`validate_flow_files`] and [`load_flows_detailed`]).
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FlowLoadError {
+    /// Offending flow file, or the directory itself if it could not be read.
+    pub path: PathBuf,
+    /// Flow name declared in the file, if it parsed far enough to have one.
+    pub flow_name: Option<String>,
+    pub message: String,
+}
+
+impl std::fmt::Display for FlowLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.message, self.path.display())
+    }
+}
+
+/// Read every `*.toml` file in `flows_dir` as `(file name, contents)`,
+/// sorted by file name. A missing directory yields no files.
+pub fn read_flow_files(flows_dir: &Path) -> anyhow::Result<Vec<(String, String)>> {
     if !flows_dir.exists() {
         tracing::debug!("flows directory does not exist: {}", flows_dir.display());
-        return Ok(HashMap::new());
+        return Ok(Vec::new());
     }
 
-    let mut definitions = HashMap::new();
-    let mut all_errors = Vec::new();
-
     let entries = std::fs::read_dir(flows_dir)
         .map_err(|e| anyhow::anyhow!("failed to read flows directory: {e}"))?;
 
+    let mut files = Vec::new();
     for entry in entries {
         let entry = entry?;
         let path = entry.path();
@@ -36,41 +51,108 @@ pub fn load_flows(flows_dir: &Path) -> anyhow::Result<HashMap<String, FlowDefini
 
         let content = std::fs::read_to_string(&path)
             .map_err(|e| anyhow::anyhow!("failed to read {}: {e}", path.display()))?;
+        files.push((entry.file_name().to_string_lossy().into_owned(), content));
+    }
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(files)
+}
 
-        let toml_def: types::FlowDefinitionToml = toml::from_str(&content)
-            .map_err(|e| anyhow::anyhow!("failed to parse {}: {e}", path.display()))?;
+/// Parse and validate a set of flow files without loading them anywhere.
+///
+/// Returns the definitions that validated plus one outcome per file, in
+/// input order: the flow name it declares, or its errors (with paths under
+/// `base`). A flow whose name was already taken by an earlier file is
+/// reported as a duplicate and left out of the definitions.
+pub fn validate_flow_files(
+    base: &Path,
+    files: &[(String, String)],
+) -> (
+    HashMap<String, FlowDefinition>,
+    Vec<Result<String, Vec<FlowLoadError>>>,
+) {
+    let mut definitions = HashMap::new();
+    let mut outcomes = Vec::with_capacity(files.len());
 
-        match validate::build_flow_definition(&toml_def) {
-            Ok(def) => {
-                if definitions.contains_key(&def.name) {
-                    all_errors.push(format!(
-                        "duplicate flow name '{}' in {}",
-                        def.name,
-                        path.display()
-                    ));
-                } else {
-                    tracing::info!("loaded flow '{}' from {}", def.name, path.display());
-                    definitions.insert(def.name.clone(), def);
-                }
+    for (file, content) in files {
+        let error = |flow_name: Option<&str>, message: String| FlowLoadError {
+            path: base.join(file),
+            flow_name: flow_name.map(str::to_string),
+            message,
+        };
+
+        let toml_def: types::FlowDefinitionToml = match toml::from_str(content) {
+            Ok(def) => def,
+            Err(e) => {
+                outcomes.push(Err(vec![error(None, format!("failed to parse: {e}"))]));
+                continue;
             }
-            Err(errors) => {
-                for e in errors {
-                    all_errors.push(format!("{} ({})", e, path.display()));
-                }
+        };
+        let name = toml_def.flow.name.clone();
+
+        let outcome = match validate::build_flow_definition(&toml_def) {
+            Ok(def) if definitions.contains_key(&def.name) => Err(vec![error(
+                Some(&name),
+                format!("duplicate flow name '{}'", def.name),
+            )]),
+            Ok(def) => {
+                definitions.insert(def.name.clone(), def);
+                Ok(name)
             }
-        }
+            Err(errors) => Err(errors
+                .iter()
+                .map(|e| error(Some(&name), e.to_string()))
+                .collect()),
+        };
+        outcomes.push(outcome);
     }
 
+    (definitions, outcomes)
+}
+
+/// Load and validate all flow TOML files from the given directory.
+/// Returns a map of flow_name -> FlowDefinition.
+///
+/// If the directory does not exist, returns an empty map (not an error).
+/// If any flow file fails to parse or validate, returns every problem found.
+pub fn load_flows_detailed(
+    flows_dir: &Path,
+) -> Result<HashMap<String, FlowDefinition>, Vec<FlowLoadError>> {
+    let files = read_flow_files(flows_dir).map_err(|e| {
+        vec![FlowLoadError {
+            path: flows_dir.to_path_buf(),
+            flow_name: None,
+            message: format!("{e:#}"),
+        }]
+    })?;
+    let (definitions, outcomes) = validate_flow_files(flows_dir, &files);
+
+    let all_errors: Vec<FlowLoadError> = outcomes
+        .iter()
+        .filter_map(|outcome| outcome.as_ref().err())
+        .flatten()
+        .cloned()
+        .collect();
     if !all_errors.is_empty() {
-        anyhow::bail!(
-            "flow validation errors:\n  {}",
-            all_errors.join("\n  ")
-        );
+        return Err(all_errors);
     }
 
+    for ((file, _), outcome) in files.iter().zip(&outcomes) {
+        if let Ok(name) = outcome {
+            let path = flows_dir.join(file);
+            tracing::info!("loaded flow '{name}' from {}", path.display());
+        }
+    }
     Ok(definitions)
 }
 
+/// [`load_flows_detailed`] with the errors joined into a single message.
+pub fn load_flows(flows_dir: &Path) -> anyhow::Result<HashMap<String, FlowDefinition>> {
+    load_flows_detailed(flows_dir).map_err(|errors| {
+        let lines: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        anyhow::anyhow!("flow validation errors:\n  {}", lines.join("\n  "))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +234,65 @@ text = "Hi"
         let err = result.unwrap_err().to_string();
         assert!(err.contains("duplicate flow name"));
     }
+
+    #[test]
+    fn validate_flow_files_reports_per_file() {
+        let good = r#"
+[flow]
+name = "same"
+start = "s1"
+
+[[steps]]
+id = "s1"
+kind = "message"
+text = "Hi"
+"#;
+        let files = vec![
+            ("a.toml".to_string(), good.to_string()),
+            ("b.toml".to_string(), good.to_string()),
+            ("c.toml".to_string(), "not toml [".to_string()),
+            (
+                "d.toml".to_string(),
+                "[flow]\nname = \"bad\"\nstart = \"missing\"\n".to_string(),
+            ),
+        ];
+        let (definitions, outcomes) = validate_flow_files(Path::new(""), &files);
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(outcomes[0].as_ref().unwrap(), "same");
+        let dup = outcomes[1].as_ref().unwrap_err();
+        assert_eq!(dup[0].message, "duplicate flow name 'same'");
+        assert_eq!(dup[0].path, Path::new("b.toml"));
+        let junk = outcomes[2].as_ref().unwrap_err();
+        assert!(junk[0].flow_name.is_none());
+        assert!(junk[0].message.starts_with("failed to parse"));
+        let bad = outcomes[3].as_ref().unwrap_err();
+        assert_eq!(bad[0].flow_name.as_deref(), Some("bad"));
+    }
+
+    #[test]
+    fn load_flows_detailed_carries_path_and_flow_name() {
+        let tmp = TempDir::new().unwrap();
+        let flows_dir = tmp.path().join("flows");
+        std::fs::create_dir(&flows_dir).unwrap();
+        std::fs::write(
+            flows_dir.join("bad.toml"),
+            "[flow]\nname = \"bad\"\nstart = \"nonexistent\"\n",
+        )
+        .unwrap();
+        std::fs::write(flows_dir.join("junk.toml"), "not toml [").unwrap();
+
+        let errors = load_flows_detailed(&flows_dir).unwrap_err();
+        let bad: Vec<_> = errors
+            .iter()
+            .filter(|e| e.path == flows_dir.join("bad.toml"))
+            .collect();
+        assert!(!bad.is_empty());
+        assert!(bad.iter().all(|e| e.flow_name.as_deref() == Some("bad")));
+        let junk = errors
+            .iter()
+            .find(|e| e.path == flows_dir.join("junk.toml"))
+            .unwrap();
+        assert!(junk.flow_name.is_none());
+        assert!(junk.to_string().ends_with("junk.toml)"));
+    }
 }
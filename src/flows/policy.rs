@@ -145,6 +145,8 @@ fn step_kind_to_string(kind: &StepKind) -> String {
         StepKind::Poll => "poll".into(),
         StepKind::Message => "message".into(),
         StepKind::Edit => "edit".into(),
+        StepKind::Branch => "branch".into(),
+        StepKind::Delay => "delay".into(),
     }
 }
 
@@ -173,6 +175,8 @@ mod tests {
                 description: None,
                 start: "s1".into(),
                 default_timeout_secs: 60,
+                deadline_secs: None,
+                inputs: Vec::new(),
             },
             steps: vec![StepToml {
                 id: "s1".into(),
@@ -184,6 +188,10 @@ mod tests {
                 timeout_secs: None,
                 agent_handoff: false,
                 transitions: vec![],
+                condition: None,
+                then: None,
+                otherwise: None,
+                duration_secs: None,
             }],
         }
     }
@@ -228,6 +236,10 @@ mod tests {
                 timeout_secs: None,
                 agent_handoff: false,
                 transitions: vec![],
+                condition: None,
+                then: None,
+                otherwise: None,
+                duration_secs: None,
             });
         }
         let policy = make_policy();
@@ -310,6 +322,10 @@ mod tests {
                 timeout_secs: None,
                 agent_handoff: false,
                 transitions: vec![],
+                condition: None,
+                then: None,
+                otherwise: None,
+                duration_secs: None,
             });
         }
         let mut policy = make_policy();
@@ -328,6 +344,8 @@ mod tests {
                 description: None,
                 start_step: "s1".into(),
                 default_timeout_secs: 60,
+                deadline_secs: None,
+                inputs: Vec::new(),
                 steps: HashMap::new(),
             },
         );
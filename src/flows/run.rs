@@ -0,0 +1,455 @@
+//! Programmatic flow runner: drive a flow definition to completion without
+//! a live chat, feeding scripted user events in place of callbacks.
+
+use std::collections::{BTreeMap, HashMap};
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use super::execute::{execute_step, is_step_timeout, run_step_routed, StepExecuteResult};
+use super::types::{FlowDefinition, Step, StepKind, ON_TIMEOUT};
+use crate::channels::telegram::TelegramChannel;
+
+/// Performs a step's side effect (sending a message, keyboard or poll,
+/// editing the anchor). Swap in a fake to run flows without Telegram.
+/// `vars` fills the step text's placeholders.
+#[async_trait]
+pub trait StepHooks: Send + Sync {
+    async fn execute(
+        &self,
+        step: &Step,
+        anchor_message_id: Option<i64>,
+        vars: &HashMap<String, String>,
+    ) -> anyhow::Result<StepExecuteResult>;
+}
+
+/// Executes steps against a Telegram chat, like the channel loop does.
+pub struct TelegramHooks<'a> {
+    pub channel: &'a TelegramChannel,
+    pub chat_id: &'a str,
+}
+
+#[async_trait]
+impl StepHooks for TelegramHooks<'_> {
+    async fn execute(
+        &self,
+        step: &Step,
+        anchor_message_id: Option<i64>,
+        vars: &HashMap<String, String>,
+    ) -> anyhow::Result<StepExecuteResult> {
+        execute_step(self.channel, self.chat_id, step, anchor_message_id, vars).await
+    }
+}
+
+/// Executes nothing; every step succeeds without an anchor message.
+pub struct NoopHooks;
+
+#[async_trait]
+impl StepHooks for NoopHooks {
+    async fn execute(
+        &self,
+        _step: &Step,
+        _anchor_message_id: Option<i64>,
+        _vars: &HashMap<String, String>,
+    ) -> anyhow::Result<StepExecuteResult> {
+        Ok(StepExecuteResult {
+            anchor_message_id: None,
+            poll_id: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowRunStatus {
+    /// Reached a terminal step.
+    Completed,
+    /// Input ran out at a step that is waiting for the user.
+    AwaitingInput,
+    /// An input event matched none of the current step's transitions.
+    NoTransition,
+    /// A step timed out with no `_error` route.
+    StepTimeout,
+    /// A step failed with no `_error` route.
+    Failed,
+    /// The flow's `deadline_secs` elapsed.
+    DeadlineExceeded,
+}
+
+/// One executed step.
+#[derive(Debug, Clone, Serialize)]
+pub struct StepRun {
+    /// Step that was executed (an `_error` target if the requested step failed).
+    pub step_id: String,
+    /// Step that was requested, when it differs from `step_id`.
+    pub routed_from: Option<String>,
+    pub timed_out: bool,
+    pub anchor_message_id: Option<i64>,
+    pub poll_id: Option<String>,
+    /// Input event that moved the flow off this step.
+    pub event: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlowOutcome {
+    pub flow_name: String,
+    /// Step the flow stopped on.
+    pub final_step: String,
+    pub status: FlowRunStatus,
+    /// Event chosen at each step that consumed one, keyed by step id.
+    pub outputs: BTreeMap<String, String>,
+    pub steps: Vec<StepRun>,
+    /// Input events left over when the run stopped.
+    pub remaining_input: Vec<String>,
+    /// Error text for `failed`, `step_timeout` and `no_transition`.
+    pub error: Option<String>,
+}
+
+/// Parse runner input: a JSON array of event strings (callback data or
+/// poll options, in the order the user would send them), or `null`.
+fn parse_input(input: serde_json::Value) -> anyhow::Result<Vec<String>> {
+    match input {
+        serde_json::Value::Null => Ok(Vec::new()),
+        serde_json::Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                serde_json::Value::String(event) => Ok(event),
+                other => anyhow::bail!("flow input events must be strings, got {other}"),
+            })
+            .collect(),
+        other => anyhow::bail!("flow input must be an array of events, got {other}"),
+    }
+}
+
+/// Run `def` from its start step, executing each step through `hooks` and
+/// answering waiting steps with the events in `input` in order.
+///
+/// Transitions resolve as in the channel loop: an exact `on` match, else
+/// `_any`. Step timeouts and `_error` routing follow [`run_step_routed`].
+/// Branch conditions and step text see the events chosen so far, keyed by
+/// step id.
+/// `delay` steps are not waited out: the run follows their `_timeout`
+/// transition straight away.
+/// Only malformed input or a missing start step is an `Err`; everything
+/// else is reported in [`FlowOutcome::status`].
+pub async fn run_flow(
+    def: &FlowDefinition,
+    input: serde_json::Value,
+    hooks: &dyn StepHooks,
+) -> anyhow::Result<FlowOutcome> {
+    let mut events = parse_input(input)?.into_iter();
+    let mut current = def.steps.get(&def.start_step).ok_or_else(|| {
+        anyhow::anyhow!(
+            "flow '{}': start step '{}' not found",
+            def.name,
+            def.start_step
+        )
+    })?;
+    let deadline = def
+        .deadline_secs
+        .map(|secs| tokio::time::Instant::now() + std::time::Duration::from_secs(secs));
+
+    let mut outcome = FlowOutcome {
+        flow_name: def.name.clone(),
+        final_step: current.id.clone(),
+        status: FlowRunStatus::Completed,
+        outputs: BTreeMap::new(),
+        steps: Vec::new(),
+        remaining_input: Vec::new(),
+        error: None,
+    };
+    let mut anchor_message_id = None;
+    let mut context = HashMap::new();
+
+    loop {
+        outcome.final_step.clone_from(&current.id);
+        if deadline.is_some_and(|d| tokio::time::Instant::now() >= d) {
+            outcome.status = FlowRunStatus::DeadlineExceeded;
+            break;
+        }
+
+        let routed = run_step_routed(def, current, &context, |step| {
+            hooks.execute(step, anchor_message_id, &context)
+        })
+        .await;
+        let routed = match routed {
+            Ok(routed) => routed,
+            Err(e) => {
+                outcome.status = if is_step_timeout(&e) {
+                    FlowRunStatus::StepTimeout
+                } else {
+                    FlowRunStatus::Failed
+                };
+                outcome.error = Some(format!("{e:#}"));
+                break;
+            }
+        };
+
+        let step = routed.step;
+        outcome.final_step.clone_from(&step.id);
+        anchor_message_id = routed.result.anchor_message_id.or(anchor_message_id);
+        outcome.steps.push(StepRun {
+            step_id: step.id.clone(),
+            routed_from: (step.id != current.id).then(|| current.id.clone()),
+            timed_out: routed.timed_out,
+            anchor_message_id: routed.result.anchor_message_id,
+            poll_id: routed.result.poll_id,
+            event: None,
+        });
+
+        if step.kind == StepKind::Delay {
+            let next = step
+                .transitions
+                .iter()
+                .find(|t| t.on == ON_TIMEOUT)
+                .and_then(|t| def.steps.get(&t.target));
+            if let Some(next) = next {
+                current = next;
+                continue;
+            }
+        }
+        if step.is_terminal() {
+            outcome.status = FlowRunStatus::Completed;
+            break;
+        }
+        let Some(event) = events.next() else {
+            outcome.status = FlowRunStatus::AwaitingInput;
+            break;
+        };
+        let target = step
+            .transitions
+            .iter()
+            .find(|t| t.on == event || t.on == "_any")
+            .and_then(|t| def.steps.get(&t.target));
+        let Some(target) = target else {
+            outcome.status = FlowRunStatus::NoTransition;
+            outcome.error = Some(format!("no transition for '{event}' at step '{}'", step.id));
+            outcome.remaining_input.push(event);
+            break;
+        };
+        if let Some(run) = outcome.steps.last_mut() {
+            run.event = Some(event.clone());
+        }
+        context.insert(step.id.clone(), event.clone());
+        outcome.outputs.insert(step.id.clone(), event);
+        current = target;
+    }
+
+    outcome.remaining_input.extend(events);
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flows::types::FlowDefinitionToml;
+    use crate::flows::validate::build_flow_definition;
+    use std::sync::Mutex;
+
+    const GREET: &str = r#"
+[flow]
+name = "greet"
+start = "hello"
+
+[[steps]]
+id = "hello"
+kind = "keyboard"
+text = "Hello there! Want the tour?"
+buttons = [[{ text = "Yes", callback_data = "yes" }, { text = "No", callback_data = "no" }]]
+transitions = [
+    { on = "yes", target = "tour" },
+    { on = "no", target = "bye" },
+]
+
+[[steps]]
+id = "tour"
+kind = "edit"
+text = "Here's the tour."
+transitions = [{ on = "_any", target = "bye" }]
+
+[[steps]]
+id = "bye"
+kind = "message"
+text = "Bye!"
+"#;
+
+    fn greet() -> FlowDefinition {
+        let toml_def: FlowDefinitionToml = toml::from_str(GREET).unwrap();
+        build_flow_definition(&toml_def).unwrap()
+    }
+
+    /// Records executed steps; fails the step named in `fail`.
+    #[derive(Default)]
+    struct FakeHooks {
+        executed: Mutex<Vec<(String, Option<i64>)>>,
+        fail: Option<&'static str>,
+    }
+
+    #[async_trait]
+    impl StepHooks for FakeHooks {
+        async fn execute(
+            &self,
+            step: &Step,
+            anchor_message_id: Option<i64>,
+            _vars: &HashMap<String, String>,
+        ) -> anyhow::Result<StepExecuteResult> {
+            let mut executed = self.executed.lock().unwrap();
+            executed.push((step.id.clone(), anchor_message_id));
+            if self.fail == Some(step.id.as_str()) {
+                anyhow::bail!("send failed");
+            }
+            Ok(StepExecuteResult {
+                anchor_message_id: Some(100 + i64::try_from(executed.len()).unwrap()),
+                poll_id: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn greet_runs_end_to_end() {
+        let hooks = FakeHooks::default();
+        let outcome = run_flow(&greet(), serde_json::json!(["yes", "ok"]), &hooks)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.status, FlowRunStatus::Completed);
+        assert_eq!(outcome.final_step, "bye");
+        assert_eq!(outcome.outputs["hello"], "yes");
+        assert_eq!(outcome.outputs["tour"], "ok");
+        assert!(outcome.remaining_input.is_empty());
+        let ids: Vec<&str> = outcome.steps.iter().map(|s| s.step_id.as_str()).collect();
+        assert_eq!(ids, ["hello", "tour", "bye"]);
+        assert_eq!(outcome.steps[0].event.as_deref(), Some("yes"));
+        assert_eq!(outcome.steps[2].event, None);
+
+        // The edit step sees the keyboard's anchor message
+        let executed = hooks.executed.lock().unwrap();
+        assert_eq!(executed[1], ("tour".to_string(), Some(101)));
+    }
+
+    #[tokio::test]
+    async fn greet_stops_awaiting_input_or_on_unknown_event() {
+        let outcome = run_flow(&greet(), serde_json::Value::Null, &NoopHooks)
+            .await
+            .unwrap();
+        assert_eq!(outcome.status, FlowRunStatus::AwaitingInput);
+        assert_eq!(outcome.final_step, "hello");
+
+        let outcome = run_flow(&greet(), serde_json::json!(["maybe", "yes"]), &NoopHooks)
+            .await
+            .unwrap();
+        assert_eq!(outcome.status, FlowRunStatus::NoTransition);
+        assert_eq!(outcome.remaining_input, ["maybe", "yes"]);
+    }
+
+    #[tokio::test]
+    async fn failing_step_reports_failed() {
+        let hooks = FakeHooks {
+            fail: Some("tour"),
+            ..FakeHooks::default()
+        };
+        let outcome = run_flow(&greet(), serde_json::json!(["yes"]), &hooks)
+            .await
+            .unwrap();
+        assert_eq!(outcome.status, FlowRunStatus::Failed);
+        assert_eq!(outcome.final_step, "tour");
+        assert!(outcome.error.unwrap().contains("send failed"));
+    }
+
+    #[tokio::test]
+    async fn branch_follows_earlier_answer() {
+        let toml_def: FlowDefinitionToml = toml::from_str(
+            r#"
+[flow]
+name = "tour"
+start = "ask"
+
+[[steps]]
+id = "ask"
+kind = "keyboard"
+text = "Want the tour?"
+buttons = [[{ text = "Yes", callback_data = "yes" }, { text = "No", callback_data = "no" }]]
+transitions = [{ on = "_any", target = "check" }]
+
+[[steps]]
+id = "check"
+kind = "branch"
+condition = "ask == yes"
+then = "tour"
+else = "bye"
+
+[[steps]]
+id = "tour"
+kind = "message"
+text = "Here's the tour."
+
+[[steps]]
+id = "bye"
+kind = "message"
+text = "Bye!"
+"#,
+        )
+        .unwrap();
+        let def = build_flow_definition(&toml_def).unwrap();
+
+        for (answer, landed) in [("yes", "tour"), ("no", "bye")] {
+            let outcome = run_flow(&def, serde_json::json!([answer]), &NoopHooks)
+                .await
+                .unwrap();
+            assert_eq!(outcome.status, FlowRunStatus::Completed);
+            assert_eq!(outcome.final_step, landed);
+            assert_eq!(outcome.steps[1].step_id, landed);
+            assert_eq!(outcome.steps[1].routed_from.as_deref(), Some("check"));
+        }
+    }
+
+    #[tokio::test]
+    async fn delay_moves_on_without_input() {
+        let toml_def: FlowDefinitionToml = toml::from_str(
+            r#"
+[flow]
+name = "onboarding"
+start = "welcome"
+
+[[steps]]
+id = "welcome"
+kind = "message"
+text = "Welcome!"
+transitions = [{ on = "_any", target = "wait" }]
+
+[[steps]]
+id = "wait"
+kind = "delay"
+duration_secs = 86400
+transitions = [{ on = "_timeout", target = "followup" }]
+
+[[steps]]
+id = "followup"
+kind = "message"
+text = "How is it going?"
+"#,
+        )
+        .unwrap();
+        let def = build_flow_definition(&toml_def).unwrap();
+
+        let outcome = run_flow(&def, serde_json::json!(["ok"]), &NoopHooks)
+            .await
+            .unwrap();
+        assert_eq!(outcome.status, FlowRunStatus::Completed);
+        let ids: Vec<&str> = outcome.steps.iter().map(|s| s.step_id.as_str()).collect();
+        assert_eq!(ids, ["welcome", "wait", "followup"]);
+        assert!(outcome.remaining_input.is_empty());
+    }
+
+    #[tokio::test]
+    async fn malformed_input_is_an_error() {
+        assert!(
+            run_flow(&greet(), serde_json::json!({"yes": 1}), &NoopHooks)
+                .await
+                .is_err()
+        );
+        assert!(run_flow(&greet(), serde_json::json!([1]), &NoopHooks)
+            .await
+            .is_err());
+    }
+}
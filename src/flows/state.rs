@@ -12,6 +12,9 @@ pub struct FlowInstance {
     pub step_entered_at: DateTime<Utc>,
     pub anchor_message_id: Option<i64>,
     pub chat_id: String,
+    /// Values for step text placeholders: the inputs the flow was started
+    /// with, plus each answered step's answer keyed by step id.
+    pub vars: HashMap<String, String>,
 }
 
 /// Error from flow operations.
@@ -114,6 +117,24 @@ impl FlowStore {
         flow_name: &str,
         start_step: &str,
         anchor_message_id: Option<i64>,
+    ) {
+        self.start_flow_with_vars(
+            chat_id,
+            flow_name,
+            start_step,
+            anchor_message_id,
+            HashMap::new(),
+        );
+    }
+
+    /// Like [`Self::start_flow`], seeding the flow's variables (its inputs).
+    pub fn start_flow_with_vars(
+        &self,
+        chat_id: &str,
+        flow_name: &str,
+        start_step: &str,
+        anchor_message_id: Option<i64>,
+        vars: HashMap<String, String>,
     ) {
         let now = Utc::now();
         let instance = FlowInstance {
@@ -123,6 +144,7 @@ impl FlowStore {
             step_entered_at: now,
             anchor_message_id,
             chat_id: chat_id.to_string(),
+            vars,
         };
 
         // Persist first
@@ -198,6 +220,39 @@ impl FlowStore {
         Ok(())
     }
 
+    /// Record the answer given at `step_id`, making it available to later
+    /// steps' text as `{{step_id}}`.
+    pub fn record_answer(
+        &self,
+        chat_id: &str,
+        step_id: &str,
+        answer: &str,
+    ) -> Result<(), FlowError> {
+        let mut guard = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let instance = guard
+            .get_mut(chat_id)
+            .ok_or_else(|| FlowError::NoActiveFlow(chat_id.to_string()))?;
+        instance
+            .vars
+            .insert(step_id.to_string(), answer.to_string());
+
+        // Persist
+        if let Some(ref db) = self.db {
+            let vars = serde_json::to_string(&instance.vars).unwrap_or_else(|_| "{}".into());
+            if let Err(e) = db.update_vars(chat_id, &vars) {
+                tracing::warn!("Failed to persist flow answer: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The variables of the active flow for a chat (empty if none).
+    pub fn flow_vars(&self, chat_id: &str) -> HashMap<String, String> {
+        self.with_flow(chat_id, |inst| inst.vars.clone())
+            .unwrap_or_default()
+    }
+
     /// Complete (remove) the active flow for a chat with the given terminal status.
     pub fn complete_flow(&self, chat_id: &str, status: &str) -> Option<FlowInstance> {
         let removed = self
@@ -247,6 +302,50 @@ impl FlowStore {
         timed_out
     }
 
+    /// Active flows that have run longer than their flow's `deadline_secs`.
+    /// Returns `(chat_id, flow_name, current_step)` like `check_timeouts`.
+    pub fn check_deadlines(
+        &self,
+        flow_defs: &HashMap<String, super::types::FlowDefinition>,
+    ) -> Vec<(String, String, String)> {
+        let guard = self
+            .active
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let now = Utc::now();
+
+        guard
+            .iter()
+            .filter(|(_, instance)| {
+                flow_defs
+                    .get(&instance.flow_name)
+                    .and_then(|def| def.deadline_secs)
+                    .is_some_and(|deadline| {
+                        let elapsed = (now - instance.started_at).num_seconds();
+                        u64::try_from(elapsed).unwrap_or(0) >= deadline
+                    })
+            })
+            .map(|(chat_id, instance)| {
+                (
+                    chat_id.clone(),
+                    instance.flow_name.clone(),
+                    instance.current_step.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Persist a `step_timeout` event for a flow step (audit log, best-effort).
+    pub fn record_step_timeout(&self, chat_id: &str, flow_name: &str, step_id: &str) {
+        if let Some(ref db) = self.db {
+            let detail = format!("chat {chat_id}: step '{step_id}' timed out");
+            if let Err(e) = db.log_audit(flow_name, None, "step_timeout", "executor", Some(&detail))
+            {
+                tracing::warn!("Failed to record step timeout: {e}");
+            }
+        }
+    }
+
     /// Get the flow name and current step for a chat (snapshot).
     pub fn get_flow_info(&self, chat_id: &str) -> Option<(String, String, Option<i64>)> {
         self.active
@@ -278,6 +377,7 @@ fn row_to_instance(row: &FlowInstanceRow) -> FlowInstance {
             .unwrap_or_else(|_| Utc::now()),
         anchor_message_id: row.anchor_message_id,
         chat_id: row.chat_id.clone(),
+        vars: serde_json::from_str(&row.vars).unwrap_or_default(),
     }
 }
 
@@ -291,6 +391,7 @@ fn instance_to_row(inst: &FlowInstance) -> FlowInstanceRow {
         step_entered_at: inst.step_entered_at.to_rfc3339(),
         anchor_message_id: inst.anchor_message_id,
         status: "active".into(),
+        vars: serde_json::to_string(&inst.vars).unwrap_or_else(|_| "{}".into()),
     }
 }
 
@@ -316,6 +417,7 @@ mod tests {
                     TransitionDef { on: "yes".into(), target: "done".into() },
                     TransitionDef { on: "_timeout".into(), target: "timeout".into() },
                 ],
+                branch: None,
             },
         );
         steps.insert(
@@ -330,6 +432,7 @@ mod tests {
                 timeout_secs: None,
                 agent_handoff: false,
                 transitions: vec![],
+                branch: None,
             },
         );
         steps.insert(
@@ -344,6 +447,7 @@ mod tests {
                 timeout_secs: None,
                 agent_handoff: false,
                 transitions: vec![],
+                branch: None,
             },
         );
         FlowDefinition {
@@ -351,6 +455,8 @@ mod tests {
             description: None,
             start_step: "ask".into(),
             default_timeout_secs: 120,
+            deadline_secs: None,
+            inputs: Vec::new(),
             steps,
         }
     }
@@ -376,6 +482,20 @@ mod tests {
         assert_eq!(info.2, Some(200));
     }
 
+    #[test]
+    fn vars_survive_refresh_from_db() {
+        let db = Arc::new(FlowDb::open_in_memory().unwrap());
+        let store = FlowStore::with_db(db);
+        let inputs = HashMap::from([("ticket".to_string(), "T-1".to_string())]);
+        store.start_flow_with_vars("chat1", "test_flow", "ask", None, inputs);
+        store.record_answer("chat1", "ask", "yes").unwrap();
+
+        store.refresh_from_db().unwrap();
+        let vars = store.flow_vars("chat1");
+        assert_eq!(vars["ticket"], "T-1");
+        assert_eq!(vars["ask"], "yes");
+    }
+
     #[test]
     fn complete_flow_removes() {
         let store = FlowStore::new();
@@ -412,4 +532,25 @@ mod tests {
         assert_eq!(timed_out[0].0, "chat1");
         assert_eq!(timed_out[0].2, "ask");
     }
+
+    #[test]
+    fn deadline_detection() {
+        let store = FlowStore::new();
+        store.start_flow("chat1", "test_flow", "ask", None);
+        store.start_flow("chat2", "test_flow", "ask", None);
+        {
+            let mut guard = store.active.lock().unwrap();
+            let inst = guard.get_mut("chat1").unwrap();
+            inst.started_at = Utc::now() - chrono::Duration::seconds(120);
+        }
+
+        let mut defs = HashMap::new();
+        defs.insert("test_flow".into(), make_flow_def());
+        assert!(store.check_deadlines(&defs).is_empty(), "no deadline set");
+
+        defs.get_mut("test_flow").unwrap().deadline_secs = Some(60);
+        let expired = store.check_deadlines(&defs);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].0, "chat1");
+    }
 }
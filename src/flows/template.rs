@@ -0,0 +1,136 @@
+//! `{{var}}` placeholders in flow step text.
+//!
+//! A placeholder is a variable name (letters, digits, `_`) between `{{` and
+//! `}}`, optionally padded with spaces. `\{{` is a literal `{{`, and `{{`
+//! around anything that is not a variable name is left as written.
+
+use std::collections::HashMap;
+
+/// How rendering treats a placeholder with no value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingVar {
+    /// Leave the placeholder in the text as written.
+    #[default]
+    Keep,
+    /// Fail, naming the variable.
+    Error,
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    /// A placeholder: the variable name and the text it was written as.
+    Var {
+        name: &'a str,
+        raw: &'a str,
+    },
+}
+
+/// Whether `name` can be used as a placeholder variable.
+pub fn is_var_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse(text: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("{{") {
+        if rest[..open].ends_with('\\') {
+            parts.push(Part::Text(&rest[..open - 1]));
+            parts.push(Part::Text("{{"));
+            rest = &rest[open + 2..];
+            continue;
+        }
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            break;
+        };
+        let name = after[..close].trim();
+        if !is_var_name(name) {
+            parts.push(Part::Text(&rest[..open + 2]));
+            rest = after;
+            continue;
+        }
+        parts.push(Part::Text(&rest[..open]));
+        parts.push(Part::Var {
+            name,
+            raw: &rest[open..open + close + 4],
+        });
+        rest = &after[close + 2..];
+    }
+    parts.push(Part::Text(rest));
+    parts
+}
+
+/// Variable names referenced by `text`, in order of appearance.
+pub fn template_vars(text: &str) -> Vec<&str> {
+    parse(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Var { name, .. } => Some(name),
+            Part::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Substitute `vars` into `text`; see [`MissingVar`] for unknown names.
+pub fn render(
+    text: &str,
+    vars: &HashMap<String, String>,
+    missing: MissingVar,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    for part in parse(text) {
+        match part {
+            Part::Text(s) => out.push_str(s),
+            Part::Var { name, raw } => match vars.get(name) {
+                Some(value) => out.push_str(value),
+                None if missing == MissingVar::Keep => out.push_str(raw),
+                None => return Err(format!("no value for variable '{name}'")),
+            },
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<String, String> {
+        HashMap::from([
+            ("instance".to_string(), "alpha".to_string()),
+            ("correlation_id".to_string(), "c-42".to_string()),
+        ])
+    }
+
+    #[test]
+    fn substitutes_known_vars() {
+        let text = "Hi from {{instance}} ({{ correlation_id }})";
+        assert_eq!(
+            render(text, &vars(), MissingVar::Error).unwrap(),
+            "Hi from alpha (c-42)"
+        );
+        assert_eq!(template_vars(text), ["instance", "correlation_id"]);
+    }
+
+    #[test]
+    fn missing_vars_kept_or_rejected() {
+        let text = "{{instance}} waits on {{ ticket }}";
+        assert_eq!(
+            render(text, &vars(), MissingVar::Keep).unwrap(),
+            "alpha waits on {{ ticket }}"
+        );
+        let err = render(text, &vars(), MissingVar::Error).unwrap_err();
+        assert!(err.contains("'ticket'"), "{err}");
+    }
+
+    #[test]
+    fn escaped_and_malformed_braces_are_literal() {
+        let text = r"\{{instance}} is {{instance}}, {{not a var}} and {{unclosed";
+        assert_eq!(
+            render(text, &vars(), MissingVar::Error).unwrap(),
+            "{{instance}} is alpha, {{not a var}} and {{unclosed"
+        );
+        assert_eq!(template_vars(text), ["instance"]);
+    }
+}
@@ -1,3 +1,5 @@
+use super::condition::Condition;
+use super::template::{self, MissingVar};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -18,6 +20,13 @@ pub struct FlowMeta {
     pub start: String,
     #[serde(default)]
     pub default_timeout_secs: u64,
+    /// Overall wall-clock budget for one run of the flow, from start.
+    #[serde(default)]
+    pub deadline_secs: Option<u64>,
+    /// Variables supplied when the flow runs; step text may reference them
+    /// as `{{name}}`, and an earlier step's answer as `{{step_id}}`.
+    #[serde(default)]
+    pub inputs: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +47,29 @@ pub struct StepToml {
     pub agent_handoff: bool,
     #[serde(default)]
     pub transitions: Vec<TransitionDef>,
+    /// `branch` steps: condition checked against the flow's runtime context.
+    #[serde(default)]
+    pub condition: Option<String>,
+    /// `branch` steps: step to jump to when the condition holds.
+    #[serde(default)]
+    pub then: Option<String>,
+    /// `branch` steps: step to jump to otherwise.
+    #[serde(default, rename = "else")]
+    pub otherwise: Option<String>,
+    /// `delay` steps: how long to wait, 1..=[`MAX_DELAY_SECS`].
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+}
+
+impl StepToml {
+    /// Every step this one can move to: transition targets, then branch targets.
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.transitions
+            .iter()
+            .map(|t| t.target.as_str())
+            .chain(self.then.as_deref())
+            .chain(self.otherwise.as_deref())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -47,6 +79,14 @@ pub enum StepKind {
     Poll,
     Message,
     Edit,
+    /// Sends nothing; jumps to `then` or `else` depending on `condition`.
+    Branch,
+    /// Sends nothing; waits `duration_secs`, then follows the `_timeout`
+    /// transition. The wait is the flow store's step timer (the validated
+    /// step's `timeout_secs`), not a sleeping task, so the daemon is not
+    /// blocked. It survives a restart only when the store is backed by the
+    /// flow database; an in-memory store forgets the flow.
+    Delay,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +101,16 @@ pub struct TransitionDef {
     pub target: String,
 }
 
+/// Transition event taken when a step fails to execute (including timeout).
+pub const ON_ERROR: &str = "_error";
+
+/// Transition event taken when a waiting step's timeout (or a `delay`
+/// step's duration) elapses.
+pub const ON_TIMEOUT: &str = "_timeout";
+
+/// Longest wait a `delay` step may declare (one day).
+pub const MAX_DELAY_SECS: u64 = 86_400;
+
 // ── Validated runtime types ─────────────────────────────────────
 
 #[derive(Debug, Clone)]
@@ -69,6 +119,8 @@ pub struct FlowDefinition {
     pub description: Option<String>,
     pub start_step: String,
     pub default_timeout_secs: u64,
+    pub deadline_secs: Option<u64>,
+    pub inputs: Vec<String>,
     pub steps: HashMap<String, Step>,
 }
 
@@ -83,12 +135,23 @@ pub struct Step {
     pub timeout_secs: Option<u64>,
     pub agent_handoff: bool,
     pub transitions: Vec<TransitionDef>,
+    /// Set for `branch` steps only.
+    pub branch: Option<Branch>,
+}
+
+/// Where a `branch` step jumps.
+#[derive(Debug, Clone)]
+pub struct Branch {
+    pub condition: Condition,
+    pub then: String,
+    pub otherwise: String,
 }
 
 impl Step {
-    /// A terminal step has no transitions -- the flow completes here.
+    /// A terminal step has no transitions and is not a branch -- the flow
+    /// completes here.
     pub fn is_terminal(&self) -> bool {
-        self.transitions.is_empty()
+        self.transitions.is_empty() && self.branch.is_none()
     }
 
     /// The effective timeout for this step: per-step override, or the flow default.
@@ -96,6 +159,30 @@ impl Step {
     pub fn effective_timeout(&self, flow_default: u64) -> u64 {
         self.timeout_secs.unwrap_or(flow_default)
     }
+
+    /// Step text with `{{var}}` placeholders filled from `vars`. Placeholders
+    /// with no value are left as written.
+    pub fn render(&self, vars: &HashMap<String, String>) -> String {
+        // Keeping missing placeholders never fails
+        template::render(&self.text, vars, MissingVar::Keep).unwrap_or_else(|_| self.text.clone())
+    }
+
+    /// Like [`Self::render`], choosing how a missing variable is handled.
+    pub fn render_with(
+        &self,
+        vars: &HashMap<String, String>,
+        missing: MissingVar,
+    ) -> Result<String, String> {
+        template::render(&self.text, vars, missing)
+    }
+
+    /// Target of this step's `_error` transition, if it declares one.
+    pub fn error_target(&self) -> Option<&str> {
+        self.transitions
+            .iter()
+            .find(|t| t.on == ON_ERROR)
+            .map(|t| t.target.as_str())
+    }
 }
 
 // ── DB row types for flow versioning ────────────────────────────
@@ -149,6 +236,14 @@ mod tests {
         let toml_str = r#"kind = "edit""#;
         let w: W = toml::from_str(toml_str).unwrap();
         assert_eq!(w.kind, StepKind::Edit);
+
+        let toml_str = r#"kind = "branch""#;
+        let w: W = toml::from_str(toml_str).unwrap();
+        assert_eq!(w.kind, StepKind::Branch);
+
+        let toml_str = r#"kind = "delay""#;
+        let w: W = toml::from_str(toml_str).unwrap();
+        assert_eq!(w.kind, StepKind::Delay);
     }
 
     #[test]
@@ -163,6 +258,7 @@ mod tests {
             timeout_secs: None,
             agent_handoff: false,
             transitions: vec![],
+            branch: None,
         };
         assert!(step.is_terminal());
     }
@@ -182,6 +278,7 @@ mod tests {
                 on: "yes".into(),
                 target: "done".into(),
             }],
+            branch: None,
         };
         assert_eq!(step.effective_timeout(120), 30);
         // Without override, uses flow default
@@ -1,3 +1,5 @@
+use super::condition::parse_condition;
+use super::template::{is_var_name, template_vars};
 use super::types::*;
 use std::collections::{HashMap, HashSet, VecDeque};
 
@@ -42,8 +44,34 @@ pub fn build_flow_definition(toml: &FlowDefinitionToml) -> Result<FlowDefinition
         });
     }
 
+    if toml.flow.deadline_secs == Some(0) {
+        errors.push(FlowValidationError {
+            flow_name: name.clone(),
+            message: "deadline_secs must be positive".into(),
+        });
+    }
+
+    for input in &toml.flow.inputs {
+        if !is_var_name(input) {
+            errors.push(FlowValidationError {
+                flow_name: name.clone(),
+                message: format!("input '{input}' must be letters, digits or '_'"),
+            });
+        }
+    }
+
+    // Parsed `branch` steps, keyed by step id
+    let mut branches = HashMap::new();
+
     // Validate each step
     for step in &toml.steps {
+        if step.timeout_secs == Some(0) {
+            errors.push(FlowValidationError {
+                flow_name: name.clone(),
+                message: format!("step '{}': timeout_secs must be positive", step.id),
+            });
+        }
+
         // Kind/field validation
         match step.kind {
             StepKind::Keyboard => {
@@ -79,6 +107,142 @@ pub fn build_flow_definition(toml: &FlowDefinitionToml) -> Result<FlowDefinition
                     });
                 }
             }
+            StepKind::Branch => {
+                if !step.transitions.is_empty() {
+                    errors.push(FlowValidationError {
+                        flow_name: name.clone(),
+                        message: format!(
+                            "step '{}': branch step moves via then/else, not transitions",
+                            step.id
+                        ),
+                    });
+                }
+                let condition = match step.condition.as_deref().map(parse_condition) {
+                    Some(Ok(condition)) => Some(condition),
+                    Some(Err(e)) => {
+                        errors.push(FlowValidationError {
+                            flow_name: name.clone(),
+                            message: format!("step '{}': invalid condition: {e}", step.id),
+                        });
+                        None
+                    }
+                    None => {
+                        errors.push(FlowValidationError {
+                            flow_name: name.clone(),
+                            message: format!(
+                                "step '{}': branch step requires a condition",
+                                step.id
+                            ),
+                        });
+                        None
+                    }
+                };
+                for (label, target) in [("then", &step.then), ("else", &step.otherwise)] {
+                    match target {
+                        Some(target) if !step_ids.contains(target.as_str()) => {
+                            errors.push(FlowValidationError {
+                                flow_name: name.clone(),
+                                message: format!(
+                                    "step '{}': {label} target '{target}' does not exist",
+                                    step.id
+                                ),
+                            });
+                        }
+                        Some(_) => {}
+                        None => {
+                            errors.push(FlowValidationError {
+                                flow_name: name.clone(),
+                                message: format!(
+                                    "step '{}': branch step requires '{label}'",
+                                    step.id
+                                ),
+                            });
+                        }
+                    }
+                }
+                if let (Some(condition), Some(then), Some(otherwise)) =
+                    (condition, &step.then, &step.otherwise)
+                {
+                    branches.insert(
+                        step.id.as_str(),
+                        Branch {
+                            condition,
+                            then: then.clone(),
+                            otherwise: otherwise.clone(),
+                        },
+                    );
+                }
+            }
+            StepKind::Delay => {
+                match step.duration_secs {
+                    Some(secs) if (1..=MAX_DELAY_SECS).contains(&secs) => {}
+                    Some(secs) => errors.push(FlowValidationError {
+                        flow_name: name.clone(),
+                        message: format!(
+                            "step '{}': duration_secs must be between 1 and {MAX_DELAY_SECS} (found {secs})",
+                            step.id
+                        ),
+                    }),
+                    None => errors.push(FlowValidationError {
+                        flow_name: name.clone(),
+                        message: format!("step '{}': delay step requires duration_secs", step.id),
+                    }),
+                }
+                if step.timeout_secs.is_some() {
+                    errors.push(FlowValidationError {
+                        flow_name: name.clone(),
+                        message: format!(
+                            "step '{}': delay step waits duration_secs, not timeout_secs",
+                            step.id
+                        ),
+                    });
+                }
+                if !step.transitions.iter().any(|t| t.on == ON_TIMEOUT) {
+                    errors.push(FlowValidationError {
+                        flow_name: name.clone(),
+                        message: format!(
+                            "step '{}': delay step requires a '{ON_TIMEOUT}' transition to continue",
+                            step.id
+                        ),
+                    });
+                }
+            }
+        }
+
+        if step.kind != StepKind::Delay && step.duration_secs.is_some() {
+            errors.push(FlowValidationError {
+                flow_name: name.clone(),
+                message: format!(
+                    "step '{}': duration_secs applies only to delay steps",
+                    step.id
+                ),
+            });
+        }
+
+        if step.kind != StepKind::Branch
+            && (step.condition.is_some() || step.then.is_some() || step.otherwise.is_some())
+        {
+            errors.push(FlowValidationError {
+                flow_name: name.clone(),
+                message: format!(
+                    "step '{}': condition/then/else apply only to branch steps",
+                    step.id
+                ),
+            });
+        }
+
+        // Placeholders name the flow's declared inputs or a step whose
+        // answer is filled in at run time
+        for var in template_vars(step.text.as_deref().unwrap_or_default()) {
+            if !toml.flow.inputs.iter().any(|input| input == var) && !step_ids.contains(var) {
+                errors.push(FlowValidationError {
+                    flow_name: name.clone(),
+                    message: format!(
+                        "step '{}': text references undeclared input '{var}'",
+                        step.id
+                    ),
+                });
+            }
         }
 
         // Validate transition targets exist
@@ -112,22 +276,27 @@ pub fn build_flow_definition(toml: &FlowDefinitionToml) -> Result<FlowDefinition
         }
     }
 
-    // Warning: unreachable steps from start
+    // Unreachable steps from start are dead config: reject them.
+    // Transitions (including `_error` and `_timeout`) and branch targets
+    // are the only edges.
     if step_ids.contains(toml.flow.start.as_str()) {
         let reachable = find_reachable_steps(&toml.steps, &toml.flow.start);
         for step in &toml.steps {
             if !reachable.contains(step.id.as_str()) {
-                tracing::warn!(
-                    "flow '{}': step '{}' is unreachable from start",
-                    name, step.id
-                );
+                errors.push(FlowValidationError {
+                    flow_name: name.clone(),
+                    message: format!("step '{}' is unreachable from start", step.id),
+                });
             }
         }
     }
 
-    // Info: cycle detection (cycles are valid, just log for awareness)
-    if has_cycles(&toml.steps, &toml.flow.start) {
-        tracing::info!("flow '{}': contains cycles (valid for retry loops)", name);
+    // A cycle can keep a flow going forever: reject it
+    for cycle in find_cycles(&toml.steps, &toml.flow.start) {
+        errors.push(FlowValidationError {
+            flow_name: name.clone(),
+            message: format!("steps form a cycle: {}", cycle.join(" -> ")),
+        });
     }
 
     if !errors.is_empty() {
@@ -146,9 +315,15 @@ pub fn build_flow_definition(toml: &FlowDefinitionToml) -> Result<FlowDefinition
                 buttons: s.buttons.clone(),
                 poll_options: s.poll_options.clone(),
                 poll_anonymous: s.poll_anonymous.unwrap_or(true),
-                timeout_secs: s.timeout_secs,
+                // A delay is a step that always times out after its duration
+                timeout_secs: if s.kind == StepKind::Delay {
+                    s.duration_secs
+                } else {
+                    s.timeout_secs
+                },
                 agent_handoff: s.agent_handoff,
                 transitions: s.transitions.clone(),
+                branch: branches.remove(s.id.as_str()),
             },
         );
     }
@@ -158,6 +333,8 @@ pub fn build_flow_definition(toml: &FlowDefinitionToml) -> Result<FlowDefinition
         description: toml.flow.description.clone(),
         start_step: toml.flow.start.clone(),
         default_timeout_secs: toml.flow.default_timeout_secs,
+        deadline_secs: toml.flow.deadline_secs,
+        inputs: toml.flow.inputs.clone(),
         steps,
     })
 }
@@ -174,9 +351,7 @@ fn find_reachable_steps<'a>(steps: &'a [StepToml], start: &'a str) -> HashSet<&'
         }
         for step in steps {
             if step.id == current {
-                for tr in &step.transitions {
-                    queue.push_back(&tr.target);
-                }
+                queue.extend(step.targets());
                 break;
             }
         }
@@ -185,44 +360,48 @@ fn find_reachable_steps<'a>(steps: &'a [StepToml], start: &'a str) -> HashSet<&'
     reachable
 }
 
-/// Check if there are any cycles reachable from start.
-fn has_cycles(steps: &[StepToml], start: &str) -> bool {
-    let mut visited = HashSet::new();
-    let mut stack = HashSet::new();
+/// DFS from start for cycles. Each cycle is the step ids along it, ending
+/// with the first one again (`a -> b -> a`).
+fn find_cycles<'a>(steps: &'a [StepToml], start: &'a str) -> Vec<Vec<&'a str>> {
     let step_map: HashMap<&str, &StepToml> = steps.iter().map(|s| (s.id.as_str(), s)).collect();
-    dfs_cycle(&step_map, start, &mut visited, &mut stack)
+    let (mut visited, mut path, mut cycles) = (HashSet::new(), Vec::new(), Vec::new());
+    dfs_cycles(&step_map, start, &mut visited, &mut path, &mut cycles);
+    cycles
 }
 
-fn dfs_cycle<'a>(
+fn dfs_cycles<'a>(
     map: &HashMap<&str, &'a StepToml>,
     node: &'a str,
     visited: &mut HashSet<&'a str>,
-    stack: &mut HashSet<&'a str>,
-) -> bool {
-    if stack.contains(node) {
-        return true;
+    path: &mut Vec<&'a str>,
+    cycles: &mut Vec<Vec<&'a str>>,
+) {
+    if let Some(at) = path.iter().position(|&id| id == node) {
+        let mut cycle = path[at..].to_vec();
+        cycle.push(node);
+        // Two transitions to the same step close the same cycle
+        if !cycles.contains(&cycle) {
+            cycles.push(cycle);
+        }
+        return;
     }
-    if visited.contains(node) {
-        return false;
+    if !visited.insert(node) {
+        return;
     }
-    visited.insert(node);
-    stack.insert(node);
 
+    path.push(node);
     if let Some(step) = map.get(node) {
-        for tr in &step.transitions {
-            if dfs_cycle(map, &tr.target, visited, stack) {
-                return true;
-            }
+        for target in step.targets() {
+            dfs_cycles(map, target, visited, path, cycles);
         }
     }
-
-    stack.remove(node);
-    false
+    path.pop();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::flows::template::MissingVar;
 
     fn minimal_flow() -> FlowDefinitionToml {
         toml::from_str(
@@ -355,10 +534,187 @@ poll_options = ["only_one"]
         assert!(errs.iter().any(|e| e.message.contains("at least 2 options")));
     }
 
+    #[test]
+    fn rejects_zero_timeouts() {
+        let toml: FlowDefinitionToml = toml::from_str(
+            r#"
+[flow]
+name = "slow"
+start = "s1"
+deadline_secs = 0
+
+[[steps]]
+id = "s1"
+kind = "message"
+text = "Hi"
+timeout_secs = 0
+"#,
+        )
+        .unwrap();
+        let errs = build_flow_definition(&toml).unwrap_err();
+        assert!(errs
+            .iter()
+            .any(|e| e.message == "deadline_secs must be positive"));
+        assert!(errs
+            .iter()
+            .any(|e| e.message == "step 's1': timeout_secs must be positive"));
+    }
+
+    #[test]
+    fn deadline_carried_into_definition() {
+        let toml: FlowDefinitionToml = toml::from_str(
+            r#"
+[flow]
+name = "bounded"
+start = "s1"
+deadline_secs = 600
+
+[[steps]]
+id = "s1"
+kind = "message"
+text = "Hi"
+timeout_secs = 5
+"#,
+        )
+        .unwrap();
+        let def = build_flow_definition(&toml).unwrap();
+        assert_eq!(def.deadline_secs, Some(600));
+        assert_eq!(def.steps["s1"].timeout_secs, Some(5));
+    }
+
+    #[test]
+    fn text_placeholders_must_be_declared_inputs() {
+        let toml: FlowDefinitionToml = toml::from_str(
+            r#"
+[flow]
+name = "greet"
+start = "s1"
+inputs = ["instance"]
+
+[[steps]]
+id = "s1"
+kind = "message"
+text = "Hi from {{instance}}, re {{ticket}}; \\{{escaped}} is fine"
+"#,
+        )
+        .unwrap();
+        let errs = build_flow_definition(&toml).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(
+            errs[0].message,
+            "step 's1': text references undeclared input 'ticket'"
+        );
+
+        let mut toml = toml;
+        toml.flow.inputs.push("ticket".into());
+        let def = build_flow_definition(&toml).unwrap();
+        let vars = HashMap::from([("instance".to_string(), "alpha".to_string())]);
+        assert_eq!(
+            def.steps["s1"].render(&vars),
+            "Hi from alpha, re {{ticket}}; {{escaped}} is fine"
+        );
+        assert!(def.steps["s1"]
+            .render_with(&vars, MissingVar::Error)
+            .is_err());
+    }
+
+    #[test]
+    fn branch_steps_need_condition_and_existing_targets() {
+        let toml: FlowDefinitionToml = toml::from_str(
+            r#"
+[flow]
+name = "triage"
+start = "check"
+
+[[steps]]
+id = "check"
+kind = "branch"
+condition = 'priority == "high"'
+then = "page"
+else = "queue"
+
+[[steps]]
+id = "page"
+kind = "message"
+text = "Paging on-call"
+
+[[steps]]
+id = "queue"
+kind = "message"
+text = "Queued"
+"#,
+        )
+        .unwrap();
+        // Steps reached only through a branch count as reachable
+        let def = build_flow_definition(&toml).unwrap();
+        let branch = def.steps["check"].branch.as_ref().unwrap();
+        assert_eq!(branch.then, "page");
+        assert_eq!(branch.otherwise, "queue");
+        assert!(!def.steps["check"].is_terminal());
+
+        let mut bad = toml.clone();
+        bad.steps[0].condition = Some("priority ==".into());
+        bad.steps[0].otherwise = Some("nowhere".into());
+        bad.steps[2].then = Some("page".into());
+        let errs: Vec<String> = build_flow_definition(&bad)
+            .unwrap_err()
+            .into_iter()
+            .map(|e| e.message)
+            .collect();
+        let has = |msg: &str| errs.iter().any(|e| e.starts_with(msg));
+        assert!(has("step 'check': invalid condition"));
+        assert!(has("step 'check': else target 'nowhere' does not exist"));
+        assert!(has("step 'queue' is unreachable from start"));
+        assert!(has("step 'queue': condition/then/else apply only to branch steps"));
+    }
+
+    #[test]
+    fn delay_duration_is_bounded_and_becomes_step_timer() {
+        let flow = |duration: &str| {
+            format!(
+                r#"
+[flow]
+name = "onboarding"
+start = "wait"
+
+[[steps]]
+id = "wait"
+kind = "delay"
+{duration}
+transitions = [{{ on = "_timeout", target = "followup" }}]
+
+[[steps]]
+id = "followup"
+kind = "message"
+text = "How is it going?"
+"#
+            )
+        };
+        let build = |duration: &str| {
+            let toml: FlowDefinitionToml = toml::from_str(&flow(duration))?;
+            Ok::<_, toml::de::Error>(build_flow_definition(&toml))
+        };
+
+        let def = build("duration_secs = 3600").unwrap().unwrap();
+        let wait = &def.steps["wait"];
+        assert_eq!(wait.effective_timeout(0), 3600);
+        assert!(!wait.is_terminal());
+
+        for (duration, expected) in [
+            ("duration_secs = 0", "between 1 and 86400 (found 0)"),
+            ("duration_secs = 86401", "between 1 and 86400 (found 86401)"),
+            ("", "delay step requires duration_secs"),
+        ] {
+            let errs = build(duration).unwrap().unwrap_err();
+            assert!(errs[0].message.ends_with(expected), "{:?}", errs[0].message);
+        }
+        // Negative durations do not parse
+        assert!(build("duration_secs = -5").is_err());
+    }
+
     #[test]
     fn reachability_detects_unreachable() {
-        // This test verifies the reachability helper; unreachable steps
-        // produce warnings but don't block validation.
+        // Reachability helper only; see `island_step_is_rejected` for validation.
         let steps = vec![
             StepToml {
                 id: "s1".into(),
@@ -370,6 +726,10 @@ poll_options = ["only_one"]
                 timeout_secs: None,
                 agent_handoff: false,
                 transitions: vec![],
+                condition: None,
+                then: None,
+                otherwise: None,
+                duration_secs: None,
             },
             StepToml {
                 id: "orphan".into(),
@@ -381,6 +741,10 @@ poll_options = ["only_one"]
                 timeout_secs: None,
                 agent_handoff: false,
                 transitions: vec![],
+                condition: None,
+                then: None,
+                otherwise: None,
+                duration_secs: None,
             },
         ];
         let reachable = find_reachable_steps(&steps, "s1");
@@ -389,7 +753,38 @@ poll_options = ["only_one"]
     }
 
     #[test]
-    fn cycle_detection() {
+    fn island_step_is_rejected() {
+        let toml: FlowDefinitionToml = toml::from_str(
+            r#"
+[flow]
+name = "island"
+start = "s1"
+
+[[steps]]
+id = "s1"
+kind = "message"
+text = "Hi"
+transitions = [{ on = "_error", target = "oops" }]
+
+[[steps]]
+id = "oops"
+kind = "message"
+text = "Something went wrong"
+
+[[steps]]
+id = "island"
+kind = "message"
+text = "Nobody gets here"
+"#,
+        )
+        .unwrap();
+        let errs = build_flow_definition(&toml).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].message, "step 'island' is unreachable from start");
+    }
+
+    #[test]
+    fn find_cycles_reports_path() {
         let steps = vec![
             StepToml {
                 id: "a".into(),
@@ -404,6 +799,10 @@ poll_options = ["only_one"]
                     on: "next".into(),
                     target: "b".into(),
                 }],
+                condition: None,
+                then: None,
+                otherwise: None,
+                duration_secs: None,
             },
             StepToml {
                 id: "b".into(),
@@ -418,8 +817,94 @@ poll_options = ["only_one"]
                     on: "back".into(),
                     target: "a".into(),
                 }],
+                condition: None,
+                then: None,
+                otherwise: None,
+                duration_secs: None,
             },
         ];
-        assert!(has_cycles(&steps, "a"));
+        assert_eq!(find_cycles(&steps, "a"), [["a", "b", "a"]]);
+    }
+
+    fn cycle_errors(steps: &str) -> Vec<String> {
+        let toml: FlowDefinitionToml =
+            toml::from_str(&format!("[flow]\nname = \"loop\"\nstart = \"a\"\n{steps}")).unwrap();
+        match build_flow_definition(&toml) {
+            Ok(_) => Vec::new(),
+            Err(errs) => errs.into_iter().map(|e| e.message).collect(),
+        }
+    }
+
+    #[test]
+    fn self_loop_is_rejected() {
+        let errs = cycle_errors(
+            r#"
+[[steps]]
+id = "a"
+kind = "message"
+text = "Again?"
+transitions = [{ on = "_error", target = "a" }]
+"#,
+        );
+        assert_eq!(errs, ["steps form a cycle: a -> a"]);
+    }
+
+    #[test]
+    fn two_step_cycle_is_rejected() {
+        let errs = cycle_errors(
+            r#"
+[[steps]]
+id = "a"
+kind = "keyboard"
+text = "Go?"
+buttons = [[{ text = "Go", callback_data = "go" }]]
+transitions = [{ on = "go", target = "b" }]
+
+[[steps]]
+id = "b"
+kind = "branch"
+condition = "a == go"
+then = "a"
+else = "end"
+
+[[steps]]
+id = "end"
+kind = "message"
+text = "Done"
+"#,
+        );
+        assert_eq!(errs, ["steps form a cycle: a -> b -> a"]);
+    }
+
+    #[test]
+    fn diamond_without_cycles_is_accepted() {
+        let errs = cycle_errors(
+            r#"
+[[steps]]
+id = "a"
+kind = "branch"
+condition = "ticket"
+then = "b"
+else = "c"
+
+[[steps]]
+id = "b"
+kind = "message"
+text = "B"
+transitions = [{ on = "_any", target = "d" }]
+
+[[steps]]
+id = "c"
+kind = "message"
+text = "C"
+transitions = [{ on = "_any", target = "d" }]
+
+[[steps]]
+id = "d"
+kind = "message"
+text = "Done"
+"#,
+        );
+        assert!(errs.is_empty(), "{errs:?}");
     }
 }
@@ -742,9 +742,13 @@ mod tests {
         config.channels_config.telegram = Some(TelegramConfig {
             bot_token: "123:ABC".into(),
             allowed_users: vec!["user".into()],
+            usernames_case_sensitive: false,
             stt_endpoint: None,
             flows_enabled: false,
             flow_policy: crate::config::FlowPolicyConfig::default(),
+            auto_download: crate::config::TelegramAutoDownloadConfig::default(),
+            placeholder_replies: false,
+            markdown_v2: false,
         });
         let entries = all_integrations();
         let tg = entries.iter().find(|e| e.name == "Telegram").unwrap();
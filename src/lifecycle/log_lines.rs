@@ -0,0 +1,173 @@
+//! Line decoding for daemon logs.
+//!
+//! Daemon logs are not guaranteed to be UTF-8 (child process output, a
+//! crash mid-write). Every reader that turns log bytes into lines goes
+//! through here so they agree: lines split on `\n` (a trailing `\r` is
+//! dropped), and invalid UTF-8 becomes U+FFFD. Bytes are only decoded once a
+//! whole line is available, so a multi-byte character or a line split
+//! across two reads comes out intact and exactly once.
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes requested per read by [`read_lines`].
+pub const READ_CHUNK_BYTES: usize = 64 * 1024;
+
+/// One log line without its terminator, invalid UTF-8 replaced.
+pub fn decode_line(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Incremental splitter for log bytes arriving in arbitrary chunks.
+#[derive(Debug)]
+pub struct LineDecoder {
+    /// Bytes of the current line not yet terminated by `\n`.
+    pending: Vec<u8>,
+    /// Longest line kept; the rest of a longer line is dropped.
+    max_line_bytes: usize,
+}
+
+impl Default for LineDecoder {
+    fn default() -> Self {
+        Self::with_max_line_bytes(usize::MAX)
+    }
+}
+
+impl LineDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A decoder that truncates lines to `max_line_bytes`, so a huge or
+    /// unterminated line cannot grow the pending buffer without bound.
+    pub fn with_max_line_bytes(max_line_bytes: usize) -> Self {
+        Self {
+            pending: Vec::new(),
+            max_line_bytes,
+        }
+    }
+
+    /// Feed the next chunk; returns the lines it completes.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        for (i, _) in chunk.iter().enumerate().filter(|(_, &b)| b == b'\n') {
+            let line = &chunk[start..i];
+            if self.pending.is_empty() && line.len() <= self.max_line_bytes {
+                lines.push(decode_line(line));
+            } else {
+                self.keep(line);
+                lines.push(decode_line(&self.pending));
+                self.pending.clear();
+            }
+            start = i + 1;
+        }
+        self.keep(&chunk[start..]);
+        lines
+    }
+
+    /// Add `bytes` to the pending line, up to `max_line_bytes`.
+    fn keep(&mut self, bytes: &[u8]) {
+        let room = self.max_line_bytes.saturating_sub(self.pending.len());
+        self.pending
+            .extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    /// The final line if the input did not end with a newline.
+    pub fn finish(self) -> Option<String> {
+        (!self.pending.is_empty()).then(|| decode_line(&self.pending))
+    }
+}
+
+/// Every line `reader` yields, read in [`READ_CHUNK_BYTES`] chunks.
+pub fn read_lines<R: Read>(mut reader: R) -> std::io::Result<Vec<String>> {
+    let mut decoder = LineDecoder::new();
+    let mut chunk = vec![0u8; READ_CHUNK_BYTES];
+    let mut lines = Vec::new();
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        lines.extend(decoder.push(&chunk[..n]));
+    }
+    lines.extend(decoder.finish());
+    Ok(lines)
+}
+
+/// Read the last `n` lines from a file without loading the entire file.
+/// Reads at most `tail_bytes` from the end of the file.
+pub fn read_last_n_lines(path: &Path, n: usize, tail_bytes: u64) -> std::io::Result<Vec<String>> {
+    let mut file = std::fs::File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    let read_from = file_len.saturating_sub(tail_bytes);
+    file.seek(SeekFrom::Start(read_from))?;
+
+    let all_lines = read_lines(file.take(file_len - read_from))?;
+
+    // If we seeked past the start, the first "line" may be partial -- skip it
+    let skip = if read_from > 0 && !all_lines.is_empty() {
+        1
+    } else {
+        0
+    };
+
+    let usable = &all_lines[skip..];
+    let start = usable.len().saturating_sub(n);
+    Ok(usable[start..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_split_across_chunks_decode_once() {
+        let text = "first\r\nsecond é line\nthird";
+        let bytes = text.as_bytes();
+        let split = text.find('é').unwrap() + 1; // inside the two-byte 'é'
+
+        let mut decoder = LineDecoder::new();
+        let mut lines = decoder.push(&bytes[..split]);
+        assert_eq!(lines, ["first"]);
+        lines.extend(decoder.push(&bytes[split..]));
+        lines.extend(decoder.finish());
+        assert_eq!(lines, ["first", "second é line", "third"]);
+    }
+
+    #[test]
+    fn invalid_bytes_become_replacement_chars() {
+        let mut decoder = LineDecoder::new();
+        let lines = decoder.push(b"ok\nbad \xff\xfe byte\n");
+        assert_eq!(lines, ["ok", "bad \u{fffd}\u{fffd} byte"]);
+        assert_eq!(decoder.finish(), None);
+    }
+
+    #[test]
+    fn long_lines_are_truncated_across_chunks() {
+        let mut decoder = LineDecoder::with_max_line_bytes(4);
+        assert!(decoder.push(b"abc").is_empty());
+        assert_eq!(
+            decoder.push(b"defgh\nlonger line\nok\nxyz"),
+            ["abcd", "long", "ok"]
+        );
+        assert_eq!(decoder.push(b"xyzxyz"), Vec::<String>::new());
+        assert_eq!(decoder.finish().as_deref(), Some("xyzx"));
+    }
+
+    #[test]
+    fn read_lines_handles_char_straddling_reads() {
+        let mut bytes = vec![b'a'; READ_CHUNK_BYTES - 1];
+        bytes.extend_from_slice("é\n".as_bytes()); // 'é' straddles the first read
+        bytes.extend_from_slice(b"\xffz\n");
+        let lines = read_lines(bytes.as_slice()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("aé"));
+        assert_eq!(lines[1], "\u{fffd}z");
+    }
+}
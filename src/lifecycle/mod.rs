@@ -8,6 +8,11 @@ use std::process::Command;
 
 use crate::db::{Instance, Registry};
 
+pub mod log_lines;
+pub mod webhooks;
+
+use webhooks::LifecycleEvent;
+
 // ── Typed lifecycle errors ─────────────────────────────────────
 
 /// Typed error for lifecycle operations, enabling HTTP handlers to
@@ -22,6 +27,13 @@ pub enum LifecycleError {
     NotRunning(String),
     /// Lifecycle lock is held by another operation.
     LockHeld,
+    /// Daemon exited right after spawn; carries the tail of its log output.
+    StartFailed {
+        name: String,
+        exit_status: String,
+        log_path: PathBuf,
+        log_tail: Vec<String>,
+    },
     /// Any other error.
     Internal(anyhow::Error),
 }
@@ -33,6 +45,25 @@ impl std::fmt::Display for LifecycleError {
             Self::AlreadyRunning(name) => write!(f, "Instance '{name}' is already running"),
             Self::NotRunning(name) => write!(f, "Instance '{name}' is not running"),
             Self::LockHeld => write!(f, "Lifecycle lock held (concurrent operation in progress)"),
+            Self::StartFailed {
+                name,
+                exit_status,
+                log_path,
+                log_tail,
+            } => {
+                write!(
+                    f,
+                    "Daemon for '{name}' exited immediately ({exit_status}). Check logs at {}",
+                    log_path.display()
+                )?;
+                if !log_tail.is_empty() {
+                    write!(f, "\n--- last {} log lines ---", log_tail.len())?;
+                    for line in log_tail {
+                        write!(f, "\n{line}")?;
+                    }
+                }
+                Ok(())
+            }
             Self::Internal(e) => write!(f, "{e:#}"),
         }
     }
@@ -71,6 +102,12 @@ const LOG_FILE_ROTATED: &str = "daemon.log.1";
 /// Default number of log lines to show.
 pub const DEFAULT_LOG_LINES: usize = 50;
 
+/// Log lines attached to a `StartFailed` error.
+const START_FAILURE_LOG_LINES: usize = 20;
+
+/// How far back from the end of the log `StartFailed` looks for those lines.
+const START_FAILURE_LOG_TAIL_BYTES: u64 = 64 * 1024;
+
 /// Timeout in seconds waiting for graceful shutdown.
 const SHUTDOWN_TIMEOUT_SECS: u64 = 10;
 
@@ -215,6 +252,26 @@ pub fn verify_pid_ownership(pid: u32, expected_instance_dir: &Path) -> Result<bo
     }
 }
 
+/// Whether the process has a handler installed for `signal`, per the
+/// `SigCgt` mask in `/proc/<pid>/status`. Unreadable status counts as not
+/// caught, so callers never send a signal whose default action is fatal.
+pub fn catches_signal(pid: u32, signal: libc::c_int) -> bool {
+    fs::read_to_string(format!("/proc/{pid}/status"))
+        .is_ok_and(|status| sigcgt_has(&status, signal))
+}
+
+/// Test `signal` against the `SigCgt:` hex mask (bit `signal - 1`).
+fn sigcgt_has(status: &str, signal: libc::c_int) -> bool {
+    let Some(mask) = status
+        .lines()
+        .find_map(|line| line.strip_prefix("SigCgt:"))
+        .and_then(|hex| u64::from_str_radix(hex.trim(), 16).ok())
+    else {
+        return false;
+    };
+    (1..=64).contains(&signal) && mask & (1u64 << (signal - 1)) != 0
+}
+
 // ── Binary resolution ──────────────────────────────────────────
 
 /// Resolve the `zeroclaw` binary path.
@@ -377,7 +434,10 @@ fn start_inner(
     fs::create_dir_all(&logs_dir).context("Failed to create log directory")?;
     rotate_logs(inst_dir)?;
 
-    // Spawn daemon
+    // Spawn daemon, with a fresh token it authenticates to the CP with
+    let cp_token = registry
+        .issue_instance_token(&instance.id)
+        .map_err(LifecycleError::Internal)?;
     let bin = zeroclaw_bin()?;
     let log_file = fs::OpenOptions::new()
         .create(true)
@@ -394,6 +454,7 @@ fn start_inner(
         .arg(instance.port.to_string())
         .env("ZEROCLAW_HOME", inst_dir.to_string_lossy().as_ref())
         .env("ZEROCLAW_INSTANCE_NAME", &instance.name)
+        .env("ZEROCLAW_CP_TOKEN", &cp_token)
         .env(
             "ZEROCLAW_CP_URL",
             std::env::var("ZEROCLAW_CP_URL").unwrap_or_else(|_| {
@@ -415,14 +476,21 @@ fn start_inner(
     std::thread::sleep(std::time::Duration::from_millis(POST_SPAWN_CHECK_MS));
     match child.try_wait() {
         Ok(Some(exit_status)) => {
-            // Child already exited -- startup failed
-            let log_hint = log_path(inst_dir);
-            return Err(LifecycleError::Internal(anyhow::anyhow!(
-                "Daemon for '{}' exited immediately ({}). Check logs at {}",
-                instance.name,
-                exit_status,
-                log_hint.display()
-            )));
+            // Child already exited -- startup failed. Logs were rotated before
+            // spawn, so the current log holds only this run's output.
+            let log_file = log_path(inst_dir);
+            let log_tail = log_lines::read_last_n_lines(
+                &log_file,
+                START_FAILURE_LOG_LINES,
+                START_FAILURE_LOG_TAIL_BYTES,
+            )
+            .unwrap_or_default();
+            return Err(LifecycleError::StartFailed {
+                name: instance.name.clone(),
+                exit_status: exit_status.to_string(),
+                log_path: log_file,
+                log_tail,
+            });
         }
         Ok(None) => {
             // Still running -- good
@@ -458,6 +526,11 @@ fn start_inner(
         tracing::warn!("Failed to cache PID in DB (non-fatal): {e:#}");
     }
 
+    // Best-effort: reset uptime for this run
+    if let Err(e) = registry.record_started(&instance.id) {
+        tracing::warn!("Failed to record start time in DB (non-fatal): {e:#}");
+    }
+
     println!(
         "Started instance '{}' (PID {pid}, port {})",
         instance.name, instance.port
@@ -484,7 +557,9 @@ pub fn start_instance(registry: &Registry, name: &str) -> Result<(), LifecycleEr
 
     let inst_dir = instance_dir_from(&instance);
     let _lock = require_lifecycle_lock(&inst_dir)?;
-    start_inner(registry, &instance, &inst_dir)
+    start_inner(registry, &instance, &inst_dir)?;
+    webhooks::notify(LifecycleEvent::Start, &instance);
+    Ok(())
 }
 
 /// Stop a running instance by name.
@@ -496,7 +571,9 @@ pub fn stop_instance(registry: &Registry, name: &str) -> Result<(), LifecycleErr
 
     let inst_dir = instance_dir_from(&instance);
     let _lock = require_lifecycle_lock(&inst_dir)?;
-    stop_inner(registry, &instance, &inst_dir)
+    stop_inner(registry, &instance, &inst_dir)?;
+    webhooks::notify(LifecycleEvent::Stop, &instance);
+    Ok(())
 }
 
 /// Restart an instance (stop if running, then start). Holds a single lifecycle
@@ -521,7 +598,59 @@ pub fn restart_instance(registry: &Registry, name: &str) -> Result<(), Lifecycle
         }
     }
 
-    start_inner(registry, &instance, &inst_dir)
+    start_inner(registry, &instance, &inst_dir)?;
+
+    // Best-effort: count only restarts that actually brought the daemon back up
+    if let Err(e) = registry.increment_restart_count(&instance.id) {
+        tracing::warn!("Failed to increment restart count (non-fatal): {e:#}");
+    }
+    webhooks::notify(LifecycleEvent::Restart, &instance);
+    Ok(())
+}
+
+/// Result of [`reload_instance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadOutcome {
+    /// SIGHUP delivered to a daemon that handles it.
+    Signaled { pid: u32 },
+    /// The daemon has no SIGHUP handler (the default action would kill it),
+    /// so nothing was sent; a restart is needed to pick up config changes.
+    Unsupported { pid: u32 },
+}
+
+/// Ask a running instance to reload its config by sending SIGHUP. The PID
+/// file is the source of truth; the signal is only sent when the daemon is
+/// verified to own the PID and to handle SIGHUP.
+pub fn reload_instance(registry: &Registry, name: &str) -> Result<ReloadOutcome, LifecycleError> {
+    let instance = registry
+        .get_instance_by_name(name)
+        .map_err(LifecycleError::Internal)?
+        .ok_or_else(|| LifecycleError::NotFound(name.to_string()))?;
+
+    let inst_dir = instance_dir_from(&instance);
+    let _lock = require_lifecycle_lock(&inst_dir)?;
+
+    let pid = read_pid(&inst_dir)?
+        .filter(|pid| is_pid_alive(*pid))
+        .ok_or_else(|| LifecycleError::NotRunning(instance.name.clone()))?;
+    if !verify_pid_ownership(pid, &inst_dir)? {
+        return Err(LifecycleError::Internal(anyhow::anyhow!(
+            "PID {pid} is alive but does NOT belong to instance '{}'. Refusing to send signal.",
+            instance.name
+        )));
+    }
+    if !catches_signal(pid, libc::SIGHUP) {
+        return Ok(ReloadOutcome::Unsupported { pid });
+    }
+
+    let ret = unsafe { libc::kill(pid as libc::pid_t, libc::SIGHUP) };
+    if ret != 0 {
+        let err = std::io::Error::last_os_error();
+        return Err(LifecycleError::Internal(anyhow::anyhow!(
+            "Failed to send SIGHUP to PID {pid}: {err}"
+        )));
+    }
+    Ok(ReloadOutcome::Signaled { pid })
 }
 
 /// Determine live status of an instance from its PID file.
@@ -684,6 +813,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sigcgt_mask_selects_signal_bit() {
+        // SIGHUP (1) and SIGTERM (15) caught
+        let status = "Name:\tzeroclaw\nSigIgn:\t0000000000000000\nSigCgt:\t0000000000004001\n";
+        assert!(sigcgt_has(status, libc::SIGHUP));
+        assert!(sigcgt_has(status, libc::SIGTERM));
+        assert!(!sigcgt_has(status, libc::SIGINT));
+        assert!(!sigcgt_has("Name:\tzeroclaw\n", libc::SIGHUP));
+    }
+
     #[test]
     fn ownership_wrong_dir_returns_false() {
         let pid = std::process::id();
@@ -765,6 +904,8 @@ mod tests {
             archived_at: None,
             migration_run_id: None,
             pid: None,
+            started_at: None,
+            restart_count: 0,
         };
         let dir = instance_dir_from(&inst);
         assert_eq!(
@@ -0,0 +1,325 @@
+//! Outbound lifecycle webhooks: POST a JSON event to operator-configured URLs
+//! whenever an instance is started, stopped, restarted, archived, or found
+//! dead by the supervisor.
+//!
+//! Configured from the environment:
+//! - `ZEROCLAW_CP_LIFECYCLE_WEBHOOK_URLS`: comma-separated target URLs
+//! - `ZEROCLAW_CP_LIFECYCLE_WEBHOOK_SECRET`: optional HMAC-SHA256 key; when
+//!   set, each request carries `X-ZeroClaw-Signature: sha256=<hex>` over the
+//!   raw body (same scheme as `X-Hub-Signature-256` on the `WhatsApp` webhook)
+//! - `ZEROCLAW_CP_LIFECYCLE_WEBHOOK_CREDENTIALS`: optional path to a TOML file
+//!   of `[[http_credentials]]` tables; each request carries the headers of
+//!   every entry whose prefix matches the target URL, for receivers that
+//!   need auth beyond the signature
+//!
+//! Delivery is best-effort and runs off the caller's thread: a failed or slow
+//! receiver never fails or delays the lifecycle operation itself.
+
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::config::schema::{http_credential_headers, HttpCredentialConfig};
+use crate::db::Instance;
+
+/// Per-request timeout for a webhook POST.
+const REQUEST_TIMEOUT_SECS: u64 = 5;
+
+/// Attempts per URL (the first try plus one retry).
+const MAX_ATTEMPTS: u32 = 2;
+
+/// Pause before the retry.
+const RETRY_DELAY_MS: u64 = 1000;
+
+/// Header carrying the HMAC signature of the body.
+pub const SIGNATURE_HEADER: &str = "X-ZeroClaw-Signature";
+
+/// Header carrying the event name, so receivers can route without parsing.
+pub const EVENT_HEADER: &str = "X-ZeroClaw-Event";
+
+/// Delivery threads not yet joined by [`flush`].
+static PENDING: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+
+/// Instance state change reported to lifecycle webhooks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    Start,
+    Stop,
+    Restart,
+    Archive,
+    /// DB said running but the process was gone; detected by the supervisor.
+    Crash,
+}
+
+impl LifecycleEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+            Self::Archive => "archive",
+            Self::Crash => "crash",
+        }
+    }
+}
+
+/// Where to send lifecycle events and how to sign them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebhookTargets {
+    pub urls: Vec<String>,
+    pub secret: Option<String>,
+    /// Auth headers for matching URLs.
+    pub credentials: Vec<HttpCredentialConfig>,
+}
+
+impl WebhookTargets {
+    pub fn from_env() -> Self {
+        let urls = std::env::var("ZEROCLAW_CP_LIFECYCLE_WEBHOOK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(str::to_string)
+            .collect();
+        let secret = std::env::var("ZEROCLAW_CP_LIFECYCLE_WEBHOOK_SECRET")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        let credentials = std::env::var("ZEROCLAW_CP_LIFECYCLE_WEBHOOK_CREDENTIALS")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(|path| read_credentials(Path::new(path.trim())))
+            .unwrap_or_default();
+        Self {
+            urls,
+            secret,
+            credentials,
+        }
+    }
+}
+
+/// The `[[http_credentials]]` in the TOML file at `path`; none (with a
+/// warning) if it cannot be read or parsed.
+fn read_credentials(path: &Path) -> Vec<HttpCredentialConfig> {
+    #[derive(Deserialize)]
+    struct Credentials {
+        #[serde(default)]
+        http_credentials: Vec<HttpCredentialConfig>,
+    }
+
+    let parsed = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| toml::from_str::<Credentials>(&raw).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(c) => c.http_credentials,
+        Err(e) => {
+            tracing::warn!(
+                "Lifecycle webhook: ignoring credentials file {}: {e}",
+                path.display()
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// JSON body for one event.
+pub fn event_payload(event: LifecycleEvent, instance: &Instance) -> serde_json::Value {
+    serde_json::json!({
+        "event": event.as_str(),
+        "instance": instance.name,
+        "instance_id": instance.id,
+        "port": instance.port,
+        "timestamp": chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    })
+}
+
+/// `sha256=<hex>` HMAC-SHA256 of `body` keyed with `secret`.
+pub fn signature_header(secret: &str, body: &[u8]) -> String {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Fire `event` for `instance` at every configured URL. Returns immediately;
+/// failures are logged. No-op when no URLs are configured.
+pub fn notify(event: LifecycleEvent, instance: &Instance) {
+    let targets = WebhookTargets::from_env();
+    if targets.urls.is_empty() {
+        return;
+    }
+    let body = event_payload(event, instance).to_string();
+    let name = instance.name.clone();
+
+    let handle = std::thread::spawn(move || deliver(&targets, event, &name, &body));
+    let mut pending = PENDING.lock().unwrap_or_else(PoisonError::into_inner);
+    pending.retain(|h| !h.is_finished());
+    pending.push(handle);
+}
+
+/// Wait for in-flight deliveries. Short-lived callers (the CLI) call this
+/// before exiting so their events are not dropped with the process.
+pub fn flush() {
+    let handles = std::mem::take(&mut *PENDING.lock().unwrap_or_else(PoisonError::into_inner));
+    for handle in handles {
+        let _ = handle.join();
+    }
+}
+
+fn deliver(targets: &WebhookTargets, event: LifecycleEvent, name: &str, body: &str) {
+    let client = match reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("Lifecycle webhook: failed to build HTTP client: {e}");
+            return;
+        }
+    };
+    let signature = targets
+        .secret
+        .as_deref()
+        .map(|secret| signature_header(secret, body.as_bytes()));
+
+    for url in &targets.urls {
+        let credential_headers = http_credential_headers(&targets.credentials, url);
+        for attempt in 1..=MAX_ATTEMPTS {
+            let mut request = client.post(url);
+            for (header, value) in &credential_headers {
+                request = request.header(header, value);
+            }
+            let mut request = request
+                .header("Content-Type", "application/json")
+                .header(EVENT_HEADER, event.as_str())
+                .body(body.to_string());
+            if let Some(ref sig) = signature {
+                request = request.header(SIGNATURE_HEADER, sig);
+            }
+            let error = match request.send() {
+                Ok(resp) if resp.status().is_success() => break,
+                Ok(resp) => format!("HTTP {}", resp.status()),
+                Err(e) => e.to_string(),
+            };
+            if attempt < MAX_ATTEMPTS {
+                std::thread::sleep(Duration::from_millis(RETRY_DELAY_MS));
+            } else {
+                tracing::warn!(
+                    "Lifecycle webhook '{}' for '{name}' to {url} failed after {MAX_ATTEMPTS} attempts: {error}",
+                    event.as_str()
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_verifies_with_gateway_scheme() {
+        let body = br#"{"event":"start"}"#;
+        let header = signature_header("s3cret", body);
+        assert!(header.starts_with("sha256="));
+        assert!(crate::gateway::verify_whatsapp_signature(
+            "s3cret", body, &header
+        ));
+        assert!(!crate::gateway::verify_whatsapp_signature(
+            "other", body, &header
+        ));
+    }
+
+    #[test]
+    fn deliver_sends_matching_credential_headers() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks/lifecycle", listener.local_addr().unwrap());
+        let receiver = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                headers.push(line.trim().to_ascii_lowercase());
+            }
+            let mut stream = stream;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            headers
+        });
+
+        let prefix = url.trim_end_matches("/lifecycle").to_string();
+        let targets = WebhookTargets {
+            urls: vec![url],
+            secret: None,
+            credentials: vec![
+                HttpCredentialConfig {
+                    url_prefix: prefix,
+                    headers: [("Authorization".to_string(), "Bearer hook-token".to_string())]
+                        .into(),
+                },
+                HttpCredentialConfig {
+                    url_prefix: "https://elsewhere.example.com".into(),
+                    headers: [("X-Api-Key".to_string(), "nope".to_string())].into(),
+                },
+            ],
+        };
+        deliver(&targets, LifecycleEvent::Start, "alpha", "{}");
+
+        let headers = receiver.join().unwrap();
+        assert!(headers.contains(&"authorization: bearer hook-token".to_string()));
+        assert!(headers.contains(&"x-zeroclaw-event: start".to_string()));
+        assert!(!headers.iter().any(|h| h.starts_with("x-api-key")));
+    }
+
+    #[test]
+    fn credentials_file_is_read_and_bad_files_are_ignored() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("webhook_credentials.toml");
+        std::fs::write(
+            &path,
+            r#"
+[[http_credentials]]
+url_prefix = "https://hooks.example.com"
+headers = { Authorization = "Bearer hook-token" }
+"#,
+        )
+        .unwrap();
+        let creds = read_credentials(&path);
+        assert_eq!(creds.len(), 1);
+        let headers = http_credential_headers(&creds, "https://hooks.example.com/lifecycle");
+        assert_eq!(headers["authorization"], "Bearer hook-token");
+
+        std::fs::write(&path, "http_credentials = 3").unwrap();
+        assert!(read_credentials(&path).is_empty());
+        assert!(read_credentials(&dir.path().join("missing.toml")).is_empty());
+    }
+
+    #[test]
+    fn event_names_are_stable() {
+        let names: Vec<_> = [
+            LifecycleEvent::Start,
+            LifecycleEvent::Stop,
+            LifecycleEvent::Restart,
+            LifecycleEvent::Archive,
+            LifecycleEvent::Crash,
+        ]
+        .into_iter()
+        .map(LifecycleEvent::as_str)
+        .collect();
+        assert_eq!(names, ["start", "stop", "restart", "archive", "crash"]);
+    }
+}
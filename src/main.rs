@@ -266,12 +266,24 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
-    // Initialize logging
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .finish();
-
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+    // Initialize logging. ZEROCLAW_LOG_FORMAT=json writes one JSON object per
+    // line, which the control plane's logs endpoint can parse (format=json).
+    let json_logs = std::env::var("ZEROCLAW_LOG_FORMAT")
+        .is_ok_and(|format| format.trim().eq_ignore_ascii_case("json"));
+    if json_logs {
+        let subscriber = FmtSubscriber::builder()
+            .with_max_level(Level::INFO)
+            .json()
+            .finish();
+        tracing::subscriber::set_global_default(subscriber)
+            .expect("setting default subscriber failed");
+    } else {
+        let subscriber = FmtSubscriber::builder()
+            .with_max_level(Level::INFO)
+            .finish();
+        tracing::subscriber::set_global_default(subscriber)
+            .expect("setting default subscriber failed");
+    }
 
     // Onboard runs quick setup by default, or the interactive wizard with --interactive
     if let Commands::Onboard {
@@ -850,6 +850,14 @@ fn commit_one(s: &StagedInstance, registry: &Registry, run_id: &str) -> Result<(
     // Write .migration-created marker (fsync file + dir)
     write_marker_file(&s.instance_dir)?;
 
+    // Fill in any workspace subdirs an existing OpenClaw workspace lacks. Only
+    // missing (empty) dirs are created, so rollback can leave them in place.
+    if let Some(ws) = s.workspace_dir.as_deref().map(Path::new) {
+        if ws.is_dir() {
+            crate::config::workspace::create_workspace_subdirs(ws)?;
+        }
+    }
+
     // Write config.toml atomically with 0600 perms
     let config_path = s.instance_dir.join("config.toml");
     write_file_atomic_mode(&config_path, s.config_toml.as_bytes(), 0o600)?;
@@ -37,6 +37,9 @@ impl Observer for LogObserver {
             ObserverEvent::HeartbeatTick => {
                 info!("heartbeat.tick");
             }
+            ObserverEvent::QuietHoursDeferred { channel, until } => {
+                info!(channel = %channel, until = %until, "quiet_hours_deferred");
+            }
             ObserverEvent::Error { component, message } => {
                 info!(component = %component, error = %message, "error");
             }
@@ -273,6 +273,15 @@ impl Observer for OtelObserver {
             ObserverEvent::HeartbeatTick => {
                 self.heartbeat_ticks.add(1, &[]);
             }
+            ObserverEvent::QuietHoursDeferred { channel, .. } => {
+                self.channel_messages.add(
+                    1,
+                    &[
+                        KeyValue::new("channel", channel.clone()),
+                        KeyValue::new("direction", "quiet_hours_deferred"),
+                    ],
+                );
+            }
             ObserverEvent::Error { component, message } => {
                 // Create an error span for visibility in trace backends
                 let mut span = tracer.build(
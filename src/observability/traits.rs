@@ -21,6 +21,11 @@ pub enum ObserverEvent {
         direction: String,
     },
     HeartbeatTick,
+    /// An outbound channel send held until quiet hours end
+    QuietHoursDeferred {
+        channel: String,
+        until: String,
+    },
     Error {
         component: String,
         message: String,
@@ -2,5 +2,5 @@ pub mod wizard;
 
 pub use wizard::{
     personality_sliders_to_comm_style, run_channels_repair_wizard, run_quick_setup, run_wizard,
-    scaffold_workspace_cp, ProjectContext,
+    ProjectContext,
 };
@@ -84,6 +84,7 @@ pub fn run_wizard() -> Result<Config> {
     let config = Config {
         workspace_dir: workspace_dir.clone(),
         config_path: config_path.clone(),
+        schema_version: crate::config::migrations::CURRENT_SCHEMA_VERSION,
         api_key: if api_key.is_empty() {
             None
         } else {
@@ -107,6 +108,8 @@ pub fn run_wizard() -> Result<Config> {
         secrets: secrets_config,
         browser: BrowserConfig::default(),
         identity: crate::config::IdentityConfig::default(),
+        stt: crate::config::SttConfig::default(),
+        http_credentials: Vec::new(),
     };
 
     println!(
@@ -277,6 +280,7 @@ pub fn run_quick_setup(
     let config = Config {
         workspace_dir: workspace_dir.clone(),
         config_path: config_path.clone(),
+        schema_version: crate::config::migrations::CURRENT_SCHEMA_VERSION,
         api_key: api_key.map(String::from),
         default_provider: Some(provider_name.clone()),
         default_model: Some(model.clone()),
@@ -296,6 +300,8 @@ pub fn run_quick_setup(
         secrets: SecretsConfig::default(),
         browser: BrowserConfig::default(),
         identity: crate::config::IdentityConfig::default(),
+        stt: crate::config::SttConfig::default(),
+        http_credentials: Vec::new(),
     };
 
     config.save()?;
@@ -1121,6 +1127,7 @@ fn setup_channels() -> Result<ChannelsConfig> {
         email: None,
         irc: None,
         cp_relay: None,
+        quiet_hours: None,
     };
 
     loop {
@@ -1282,9 +1289,13 @@ fn setup_channels() -> Result<ChannelsConfig> {
                 config.telegram = Some(TelegramConfig {
                     bot_token: token,
                     allowed_users,
+                    usernames_case_sensitive: false,
                     stt_endpoint: None,
                     flows_enabled: false,
                     flow_policy: crate::config::FlowPolicyConfig::default(),
+                    auto_download: crate::config::TelegramAutoDownloadConfig::default(),
+                    placeholder_replies: false,
+                    markdown_v2: false,
                 });
             }
             1 => {
@@ -2303,8 +2314,8 @@ fn scaffold_workspace(workspace_dir: &Path, ctx: &ProjectContext) -> Result<()>
     ];
 
     // Create subdirectories
-    let subdirs = ["sessions", "memory", "state", "cron", "skills"];
-    for dir in &subdirs {
+    let subdirs = crate::config::workspace::WORKSPACE_SUBDIRS;
+    for dir in subdirs {
         fs::create_dir_all(workspace_dir.join(dir))?;
     }
 
@@ -2336,7 +2347,7 @@ fn scaffold_workspace(workspace_dir: &Path, ctx: &ProjectContext) -> Result<()>
         "  {}",
         style(format!("  {}/", workspace_dir.display())).dim()
     );
-    for dir in &subdirs {
+    for dir in subdirs {
         println!("  {}", style(format!("  ├── {dir}/")).dim());
     }
     for (i, (filename, _)) in files.iter().enumerate() {
@@ -2578,11 +2589,8 @@ pub fn scaffold_workspace_cp(
         ("MEMORY.md", memory.to_string()),
     ];
 
-    // Create subdirectories
-    let subdirs = ["sessions", "memory", "state", "cron", "skills"];
-    for dir in &subdirs {
-        fs::create_dir_all(workspace_dir.join(dir))?;
-    }
+    // Create subdirectories (including the CP's configured extras)
+    crate::config::workspace::create_workspace_subdirs(workspace_dir)?;
 
     let mut created = 0usize;
     let mut skipped = 0usize;
@@ -240,7 +240,14 @@ impl ApprovalGate {
                 )
             };
 
-            if let Err(e) = self.channel.send_with_keyboard(chat_id, &text, &buttons).await {
+            // The request times out in `timeout_secs`, so holding it for
+            // quiet hours would only deny it unseen
+            let sent = crate::channels::quiet_hours::with_priority(
+                crate::channels::quiet_hours::Priority::High,
+                self.channel.send_with_keyboard(chat_id, &text, &buttons),
+            )
+            .await;
+            if let Err(e) = sent {
                 tracing::warn!("Failed to send approval request to chat {chat_id}: {e}");
             }
         }
@@ -64,6 +64,15 @@ impl Tool for TelegramComposeFlowTool {
                     "type": "integer",
                     "description": "Default timeout in seconds for steps without explicit timeout (0 = no timeout)"
                 },
+                "deadline_secs": {
+                    "type": "integer",
+                    "description": "Optional overall time limit in seconds for one run of the flow"
+                },
+                "inputs": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Variables supplied when the flow runs; step text may reference them as {{name}}, and an earlier step's answer as {{step_id}}"
+                },
                 "steps": {
                     "type": "array",
                     "description": "Array of step definitions",
@@ -71,7 +80,7 @@ impl Tool for TelegramComposeFlowTool {
                         "type": "object",
                         "properties": {
                             "id": { "type": "string" },
-                            "kind": { "type": "string", "enum": ["message", "keyboard", "poll", "edit"] },
+                            "kind": { "type": "string", "enum": ["message", "keyboard", "poll", "edit", "branch", "delay"] },
                             "text": { "type": "string" },
                             "buttons": {
                                 "type": "array",
@@ -90,6 +99,10 @@ impl Tool for TelegramComposeFlowTool {
                             "poll_anonymous": { "type": "boolean" },
                             "timeout_secs": { "type": "integer" },
                             "agent_handoff": { "type": "boolean" },
+                            "condition": { "type": "string", "description": "Branch steps: e.g. `answer == yes`, `ticket`, `!ticket`" },
+                            "then": { "type": "string", "description": "Branch steps: target when the condition holds" },
+                            "else": { "type": "string", "description": "Branch steps: target otherwise" },
+                            "duration_secs": { "type": "integer", "description": "Delay steps: seconds to wait (1-86400) before following the _timeout transition" },
                             "transitions": {
                                 "type": "array",
                                 "items": {
@@ -176,6 +189,17 @@ impl Tool for TelegramComposeFlowTool {
             .get("default_timeout_secs")
             .and_then(|v| v.as_u64())
             .unwrap_or(0);
+        let deadline_secs = args.get("deadline_secs").and_then(serde_json::Value::as_u64);
+        let inputs = args
+            .get("inputs")
+            .and_then(serde_json::Value::as_array)
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|n| n.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         let toml_def = FlowDefinitionToml {
             flow: FlowMeta {
@@ -183,6 +207,8 @@ impl Tool for TelegramComposeFlowTool {
                 description,
                 start: start_step,
                 default_timeout_secs: default_timeout,
+                deadline_secs,
+                inputs,
             },
             steps,
         };
@@ -1,7 +1,7 @@
 use crate::channels::telegram::TelegramChannel;
 use crate::channels::telegram_types::TelegramToolContext;
 use crate::flows::db::FlowDb;
-use crate::flows::execute::execute_step;
+use crate::flows::execute::{execute_step, run_step_routed};
 use crate::flows::state::FlowStore;
 use crate::flows::types::FlowDefinition;
 use crate::tools::traits::{Tool, ToolResult};
@@ -76,6 +76,11 @@ impl Tool for TelegramFlowTool {
                 "chat_id": {
                     "type": "string",
                     "description": "Target chat ID (auto-resolved from context if omitted)"
+                },
+                "inputs": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Values for the flow's declared inputs, filled into {{name}} placeholders in step text"
                 }
             },
             "required": ["flow_name"]
@@ -196,16 +201,68 @@ impl Tool for TelegramFlowTool {
             }
         };
 
-        // Execute the start step
-        match execute_step(&self.channel, &chat_id, start_step, None).await {
-            Ok(result) => {
+        let vars: HashMap<String, String> = match args.get("inputs") {
+            None | Some(serde_json::Value::Null) => HashMap::new(),
+            Some(serde_json::Value::Object(inputs)) => inputs
+                .iter()
+                .map(|(name, value)| {
+                    let value = value
+                        .as_str()
+                        .map_or_else(|| value.to_string(), str::to_string);
+                    (name.clone(), value)
+                })
+                .collect(),
+            Some(_) => {
+                return Ok(ToolResult {
+                    success: false,
+                    output: String::new(),
+                    error: Some("inputs must be an object of input names to values".into()),
+                });
+            }
+        };
+        let missing: Vec<&str> = flow_def
+            .inputs
+            .iter()
+            .filter(|input| !vars.contains_key(*input))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Ok(ToolResult {
+                success: false,
+                output: String::new(),
+                error: Some(format!(
+                    "Flow '{}' needs inputs: {}",
+                    flow_name,
+                    missing.join(", ")
+                )),
+            });
+        }
+
+        // Execute the start step (bounded; failures route via _error)
+        match run_step_routed(flow_def, start_step, &vars, |step| {
+            execute_step(&self.channel, &chat_id, step, None, &vars)
+        })
+        .await
+        {
+            Ok(routed) => {
+                let result = routed.result;
                 // Register the flow in the store
-                self.flow_store.start_flow(
+                self.flow_store.start_flow_with_vars(
                     &chat_id,
                     &flow_name,
-                    &flow_def.start_step,
+                    &routed.step.id,
                     result.anchor_message_id,
+                    vars.clone(),
                 );
+                if routed.timed_out {
+                    self.flow_store
+                        .record_step_timeout(&chat_id, &flow_name, &start_step.id);
+                }
+                // Routed straight to a terminal step (via `_error` or a branch):
+                // nothing to wait for
+                if routed.step.id != start_step.id && routed.step.is_terminal() {
+                    self.flow_store.complete_flow(&chat_id, "completed");
+                }
 
                 // Store poll_id mapping if present
                 if let Some(ref poll_id) = result.poll_id {
@@ -222,7 +279,7 @@ impl Tool for TelegramFlowTool {
                     output: format!(
                         "Flow '{}' started at step '{}', message_id={}",
                         flow_name,
-                        flow_def.start_step,
+                        routed.step.id,
                         result.anchor_message_id.unwrap_or(-1),
                     ),
                     error: None,
@@ -1,10 +1,9 @@
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
-use zeroclaw::db::Registry;
+use zeroclaw::db::{HealthGate, Registry};
 
 /// Helper: create a temp CP dir with a registry and two registered instances.
 fn setup_two_instances() -> (TempDir, PathBuf) {
@@ -56,9 +55,12 @@ fn setup_two_instances() -> (TempDir, PathBuf) {
 
 /// Helper: start an in-process axum server on a random port.
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    start_test_server_with_state(cp::server::CpState::new(db_path)).await
+}
+
+async fn start_test_server_with_state(
+    state: cp::server::CpState,
+) -> (String, tokio::sync::watch::Sender<bool>) {
     let app = cp::server::build_router(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -100,6 +102,8 @@ async fn gate1_send_receive_acknowledge() -> Result<()> {
         .send()
         .await?;
     assert_eq!(rule_resp.status(), 201, "create rule should return 201");
+    let rule_body: serde_json::Value = rule_resp.json().await?;
+    let rule_id = rule_body["id"].as_str().unwrap().to_string();
 
     // Send message from A to B
     let send_resp = client
@@ -117,6 +121,13 @@ async fn gate1_send_receive_acknowledge() -> Result<()> {
     let msg_id = send_body["id"].as_str().unwrap().to_string();
     assert_eq!(send_body["status"].as_str().unwrap(), "queued");
 
+    // The authorizing rule is reported and recorded on the created event
+    assert_eq!(send_body["rule_id"], rule_id.as_str());
+    let events = Registry::open(&db_path)?.get_message_events(&msg_id)?;
+    assert_eq!(events[0].event_type, "created");
+    let created: serde_json::Value = serde_json::from_str(events[0].detail.as_deref().unwrap())?;
+    assert_eq!(created["rule_id"], rule_id.as_str());
+
     // Receive message as B (short poll)
     let recv_resp = client
         .get(format!(
@@ -142,6 +153,15 @@ async fn gate1_send_receive_acknowledge() -> Result<()> {
     let ack_body: serde_json::Value = ack_resp.json().await?;
     assert_eq!(ack_body["status"].as_str().unwrap(), "acknowledged");
 
+    // Delivery timeline: one attempt, leased by B and acknowledged
+    let attempts = Registry::open(&db_path)?.get_delivery_attempts(&msg_id)?;
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(attempts[0].attempt, 1);
+    assert_eq!(attempts[0].consumer.as_deref(), Some("agent-b"));
+    assert!(attempts[0].poll_id.is_some());
+    assert_eq!(attempts[0].outcome, "acknowledged");
+    assert!(attempts[0].held_secs.is_some());
+
     // Verify second receive returns null (message already acked)
     let recv2 = client
         .get(format!(
@@ -221,6 +241,73 @@ async fn gate2_idempotency_dedup() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate2_payload_hash_idempotency_dedup() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "*",
+        }))
+        .send()
+        .await?;
+
+    let send = |payload: serde_json::Value, key: Option<&str>| {
+        let mut body = serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task",
+            "payload": payload,
+            "idempotency_mode": "payload_hash",
+        });
+        if let Some(key) = key {
+            body["idempotency_key"] = key.into();
+        }
+        let request = client
+            .post(format!("{base_url}/api/messages"))
+            .json(&body)
+            .send();
+        async move {
+            let resp = request.await?;
+            let status = resp.status().as_u16();
+            let body: serde_json::Value = resp.json().await?;
+            anyhow::Ok((status, body))
+        }
+    };
+
+    let (status, first) = send(serde_json::json!({"text": "same", "n": 1}), None).await?;
+    assert_eq!(status, 201);
+    let key = first["idempotency_key"].as_str().unwrap();
+    assert!(key.starts_with("sha256:"));
+
+    // Identical resend (object keys in a different order) dedups
+    let (status, dup) = send(serde_json::json!({"n": 1, "text": "same"}), None).await?;
+    assert_eq!(status, 200);
+    assert_eq!(dup["deduplicated"], true);
+    assert_eq!(dup["id"], first["id"]);
+
+    // A differing payload gets its own message
+    let (status, other) = send(serde_json::json!({"text": "different", "n": 1}), None).await?;
+    assert_eq!(status, 201);
+    assert_ne!(other["id"], first["id"]);
+
+    // An explicit key takes precedence over the derived one
+    let (status, explicit) = send(
+        serde_json::json!({"text": "same", "n": 1}),
+        Some("caller-key"),
+    )
+    .await?;
+    assert_eq!(status, 201);
+    assert_eq!(explicit["idempotency_key"], "caller-key");
+
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 3: Hop limit rejection
 // ══════════════════════════════════════════════════════════════════
@@ -299,6 +386,456 @@ async fn gate4_unauthorized_route_rejection() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn routing_check_explains_decision_without_enqueuing() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let check = |query: &'static str| {
+        let client = client.clone();
+        let url = format!("{base_url}/api/routing/check?{query}");
+        async move { client.get(url).send().await }
+    };
+
+    // No rule yet: denied with the same reason send would give
+    let body: serde_json::Value = check("from=agent-a&to=agent-b&type=task.handoff")
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["allowed"], false);
+    assert!(body["reason"].as_str().unwrap().contains("routing rule"));
+    assert!(body["rule"].is_null());
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+            "max_retries": 2,
+            "ttl_secs": 600,
+            "auto_start": true,
+        }))
+        .send()
+        .await?;
+
+    let body: serde_json::Value = check("from=agent-a&to=agent-b&type=task.handoff")
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["allowed"], true);
+    assert_eq!(body["rule"]["type_pattern"], "task.*");
+    assert_eq!(
+        body["effective"],
+        serde_json::json!({
+            "max_retries": 2,
+            "ttl_secs": 600,
+            "lease_secs": 90,
+            "auto_start": true,
+            "health_gate": "off",
+        })
+    );
+
+    // A requested TTL overrides the rule's; unknown instances are explained
+    let body: serde_json::Value = check("from=agent-a&to=agent-b&type=task.x&ttl_secs=60")
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["effective"]["ttl_secs"], 60);
+    let body: serde_json::Value = check("from=agent-a&to=ghost&type=task.x")
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["allowed"], false);
+    assert!(body["reason"].as_str().unwrap().contains("ghost"));
+
+    assert_eq!(check("from=agent-a&to=agent-b").await?.status(), 400);
+    let stats: serde_json::Value = client
+        .get(format!("{base_url}/api/messages/stats"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(stats["total"], 0, "check must not enqueue: {stats}");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn regex_type_pattern_routes_matching_types() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let create_rule = |pattern: &str| {
+        client
+            .post(format!("{base_url}/api/routing-rules"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type_pattern": pattern,
+            }))
+            .send()
+    };
+
+    let resp = create_rule("re:^task\\.(").await?;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = resp.json().await?;
+    assert!(body["error"].as_str().unwrap().contains("regex"));
+
+    let resp = create_rule(r"re:^task\.priority\.(high|low)$").await?;
+    assert_eq!(resp.status(), 201);
+
+    for (message_type, expected) in [
+        ("task.priority.high", 201),
+        ("task.priority.low", 201),
+        ("task.priority.medium", 403),
+    ] {
+        let resp = client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type": message_type,
+                "payload": {},
+            }))
+            .send()
+            .await?;
+        assert_eq!(resp.status(), expected, "{message_type}");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn priority_messages_are_received_first() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+        }))
+        .send()
+        .await?;
+    let mut ids = Vec::new();
+    for (message_type, priority) in [("task.backfill", None), ("task.interrupt", Some(42))] {
+        let mut body = serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": message_type,
+            "payload": {},
+        });
+        if let Some(priority) = priority {
+            body["priority"] = serde_json::json!(priority);
+        }
+        let sent: serde_json::Value = client
+            .post(format!("{base_url}/api/messages"))
+            .json(&body)
+            .send()
+            .await?
+            .json()
+            .await?;
+        ids.push(sent["id"].clone());
+        // Out-of-range priorities are clamped
+        assert_eq!(sent["priority"], priority.map_or(0, |_| 9));
+    }
+
+    let recv: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1&max=2"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(recv["messages"][0]["id"], ids[1]);
+    assert_eq!(recv["messages"][0]["priority"], 9);
+    assert_eq!(recv["messages"][1]["id"], ids[0]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn routing_rule_lease_secs_sets_lease_expiry() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let create_rule = |lease_secs: i64| {
+        client
+            .post(format!("{base_url}/api/routing-rules"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type_pattern": "task.*",
+                "lease_secs": lease_secs,
+            }))
+            .send()
+    };
+
+    assert_eq!(create_rule(0).await?.status(), 400);
+    let rule: serde_json::Value = create_rule(600).await?.json().await?;
+    assert_eq!(rule["lease_secs"], 600);
+
+    client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task.render",
+            "payload": {},
+        }))
+        .send()
+        .await?;
+    let recv: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let lease_expires_at = chrono::NaiveDateTime::parse_from_str(
+        recv["message"]["lease_expires_at"].as_str().unwrap(),
+        "%Y-%m-%d %H:%M:%S",
+    )?;
+    let remaining = lease_expires_at - chrono::Utc::now().naive_utc();
+    assert!(
+        remaining.num_seconds() > 500,
+        "lease too short: {remaining}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn message_events_stream_reports_status_changes() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+        }))
+        .send()
+        .await?;
+    let mut stream = client
+        .get(format!("{base_url}/api/messages/events/stream"))
+        .send()
+        .await?;
+    assert_eq!(stream.status(), 200);
+    assert!(stream.headers()["content-type"]
+        .to_str()?
+        .starts_with("text/event-stream"));
+
+    let sent: serde_json::Value = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task.render",
+            "payload": {},
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let id = sent["id"].as_str().unwrap();
+    let recv: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(recv["message"]["id"], id);
+
+    // Other tests share the process-wide bus, so pick out this message
+    let mut seen = Vec::new();
+    let mut buffer = String::new();
+    while seen.len() < 2 {
+        let chunk = tokio::time::timeout(std::time::Duration::from_secs(5), stream.chunk())
+            .await?
+            .unwrap()
+            .unwrap();
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = buffer.find("\n\n") {
+            let frame: String = buffer.drain(..end + 2).collect();
+            if frame.contains(id) {
+                seen.push(frame);
+            }
+        }
+    }
+    assert!(seen[0].starts_with("event: enqueued\n"), "{}", seen[0]);
+    assert!(seen[0].contains(r#""status":"queued""#));
+    assert!(seen[1].starts_with("event: leased\n"), "{}", seen[1]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn broadcast_fans_out_with_shared_correlation_id() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "config.*",
+        }))
+        .send()
+        .await?;
+
+    // agent-b is routed; ghost does not exist. Neither failure blocks the other.
+    let resp = client
+        .post(format!("{base_url}/api/messages/broadcast"))
+        .json(&serde_json::json!({
+            "from": "agent-a",
+            "to": ["agent-b", "ghost"],
+            "type": "config.reload",
+            "payload": {"reason": "rotated keys"},
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["queued"], 1);
+    assert_eq!(body["rejected"], 1);
+    let correlation_id = body["correlation_id"].as_str().unwrap().to_string();
+    assert_eq!(body["results"][0]["to_instance"], "agent-b");
+    assert_eq!(body["results"][0]["status"], "queued");
+    assert_eq!(body["results"][1]["to_instance"], "ghost");
+    assert_eq!(body["results"][1]["status"], "rejected");
+    assert_eq!(body["results"][1]["code"], 404);
+    // Each fanned-out message is recorded as a single send would be
+    let events = Registry::open(&db_path)?
+        .get_message_events(body["results"][0]["id"].as_str().unwrap())?;
+    assert_eq!(events[0].event_type, "created");
+
+    let recv: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(recv["message"]["id"], body["results"][0]["id"]);
+    assert_eq!(recv["message"]["correlation_id"], correlation_id.as_str());
+
+    // Request-level problems reject the whole broadcast
+    let resp = client
+        .post(format!("{base_url}/api/messages/broadcast"))
+        .json(&serde_json::json!({
+            "from": "agent-a",
+            "to": ["agent-b", "agent-b"],
+            "type": "config.reload",
+            "payload": {},
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn batch_send_reports_each_item() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task",
+        }))
+        .send()
+        .await?;
+    let item = |to: &str, key: &str| {
+        serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": to,
+            "type": "task",
+            "payload": {"key": key},
+            "idempotency_key": key,
+        })
+    };
+
+    // A key already stored, one repeated within the batch, and an unrouted
+    // recipient are reported without blocking the valid items
+    let resp = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&item("agent-b", "k0"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 201);
+    let stored_id = resp.json::<serde_json::Value>().await?["id"].clone();
+
+    let resp = client
+        .post(format!("{base_url}/api/messages/batch"))
+        .json(&serde_json::json!([
+            item("agent-b", "k1"),
+            item("agent-a", "k2"),
+            item("agent-b", "k0"),
+            item("agent-b", "k1"),
+            item("agent-b", "k3"),
+        ]))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["queued"], 2);
+    assert_eq!(body["deduplicated"], 2);
+    assert_eq!(body["rejected"], 1);
+    let results = body["results"].as_array().unwrap();
+    let statuses: Vec<_> = results.iter().map(|r| r["status"].clone()).collect();
+    assert_eq!(
+        statuses,
+        [
+            "queued",
+            "rejected",
+            "deduplicated",
+            "deduplicated",
+            "queued"
+        ]
+    );
+    for (i, r) in results.iter().enumerate() {
+        assert_eq!(r["index"], i);
+    }
+    assert_eq!(results[1]["code"], 403);
+    assert_eq!(results[2]["id"], stored_id);
+    assert_eq!(results[3]["id"], results[0]["id"]);
+
+    let registry = Registry::open(&db_path)?;
+    let queued = results[4]["id"].as_str().unwrap();
+    let events = registry.get_message_events(queued)?;
+    assert_eq!(events[0].event_type, "created");
+
+    // Request-level problems reject the whole batch
+    let resp = client
+        .post(format!("{base_url}/api/messages/batch"))
+        .json(&serde_json::json!([]))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 5: Failed delivery -> dead_letter
 // ══════════════════════════════════════════════════════════════════
@@ -310,7 +847,18 @@ async fn gate5_failed_delivery_dead_letter() -> Result<()> {
     let registry = Registry::open(&db_path)?;
 
     // Create routing rule with max_retries = 1
-    registry.create_routing_rule("agent-a", "agent-b", "*", 1, 3600, false)?;
+    registry.create_routing_rule(
+        "agent-a",
+        "agent-b",
+        "*",
+        1,
+        3600,
+        90,
+        false,
+        false,
+        HealthGate::Off,
+        None,
+    )?;
 
     // Enqueue a message
     let msg_id = uuid::Uuid::new_v4().to_string();
@@ -322,9 +870,12 @@ async fn gate5_failed_delivery_dead_letter() -> Result<()> {
         payload: r#"{"text":"test"}"#.to_string(),
         correlation_id: None,
         idempotency_key: None,
+        idempotency_window_secs: None,
         hop_count: 0,
         max_retries: 1,
         ttl_secs: 3600,
+        content_type: None,
+        priority: 0,
     };
     let msg = registry.enqueue_message(&new_msg)?;
     assert_eq!(msg.status, "queued");
@@ -539,35 +1090,99 @@ async fn routing_rules_crud() -> Result<()> {
     Ok(())
 }
 
-// ══════════════════════════════════════════════════════════════════
-// Instance existence validation (D10)
-// ══════════════════════════════════════════════════════════════════
-
 #[tokio::test]
-async fn instance_existence_validation() -> Result<()> {
+async fn cached_routing_rules_follow_api_and_out_of_band_writes() -> Result<()> {
     let (_tmp, db_path) = setup_two_instances();
-    let (base_url, _shutdown) = start_test_server(db_path).await;
+    // Check for out-of-band writes on every lookup rather than every few seconds
+    let mut state = cp::server::CpState::new(db_path.clone());
+    state.routing_rules =
+        cp::routing_cache::RoutingRuleCache::with_version_check_interval(std::time::Duration::ZERO);
+    let (base_url, _shutdown) = start_test_server_with_state(state).await;
     let client = reqwest::Client::new();
+    let send = || {
+        client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type": "task",
+                "payload": {"text": "hi"},
+            }))
+            .send()
+    };
 
-    // Create rule with nonexistent from_instance
     let resp = client
         .post(format!("{base_url}/api/routing-rules"))
         .json(&serde_json::json!({
-            "from_instance": "nonexistent",
+            "from_instance": "agent-a",
             "to_instance": "agent-b",
             "type_pattern": "*",
         }))
         .send()
         .await?;
-    assert_eq!(resp.status(), 404, "nonexistent from_instance should 404");
+    let rule_id = resp.json::<serde_json::Value>().await?["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(send().await?.status(), 201);
 
-    // Create rule with nonexistent to_instance
-    let resp = client
-        .post(format!("{base_url}/api/routing-rules"))
-        .json(&serde_json::json!({
-            "from_instance": "agent-a",
-            "to_instance": "nonexistent",
-            "type_pattern": "*",
+    // Deleted through the API: the cached rule must not keep allowing sends
+    client
+        .delete(format!("{base_url}/api/routing-rules/{rule_id}"))
+        .send()
+        .await?;
+    assert_eq!(send().await?.status(), 403);
+
+    // Written straight to the registry, bypassing the API
+    let registry = Registry::open(&db_path)?;
+    let rule_id = registry.create_routing_rule(
+        "agent-a",
+        "agent-b",
+        "task",
+        5,
+        3600,
+        60,
+        false,
+        false,
+        zeroclaw::db::HealthGate::Off,
+        None,
+    )?;
+    assert_eq!(send().await?.status(), 201);
+    registry.delete_routing_rule(&rule_id)?;
+    assert_eq!(send().await?.status(), 403);
+
+    Ok(())
+}
+
+// ══════════════════════════════════════════════════════════════════
+// Instance existence validation (D10)
+// ══════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn instance_existence_validation() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    // Create rule with nonexistent from_instance
+    let resp = client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "nonexistent",
+            "to_instance": "agent-b",
+            "type_pattern": "*",
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404, "nonexistent from_instance should 404");
+
+    // Create rule with nonexistent to_instance
+    let resp = client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "nonexistent",
+            "type_pattern": "*",
         }))
         .send()
         .await?;
@@ -622,3 +1237,1486 @@ async fn long_poll_timeout_returns_null() -> Result<()> {
 
     Ok(())
 }
+
+// ══════════════════════════════════════════════════════════════════
+// Batch receive with max=
+// ══════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn receive_batch_with_max() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+        }))
+        .send()
+        .await?;
+    let mut sent = Vec::new();
+    for i in 0..4 {
+        let body: serde_json::Value = client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type": "task.batch",
+                "payload": {"n": i},
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        sent.push(body["id"].as_str().unwrap().to_string());
+    }
+
+    // max=3 leases the three oldest as an array
+    let body: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1&max=3"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert!(body.get("message").is_none());
+    let ids: Vec<&str> = body["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        ids,
+        sent[..3].iter().map(String::as_str).collect::<Vec<_>>()
+    );
+
+    // max=1 (the default) keeps the single-message shape
+    let body: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1&max=1"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["message"]["id"].as_str().unwrap(), sent[3]);
+
+    // Nothing left: batch form returns an empty array
+    let body: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=0&max=10"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["messages"], serde_json::json!([]));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn expedite_moves_message_to_front() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+        }))
+        .send()
+        .await?;
+    let mut sent = Vec::new();
+    for i in 0..3 {
+        let body: serde_json::Value = client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type": "task.triage",
+                "payload": {"n": i},
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        sent.push(body["id"].as_str().unwrap().to_string());
+    }
+
+    let resp = client
+        .post(format!("{base_url}/api/messages/{}/expedite", sent[2]))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+
+    // The expedited message is leased first
+    let body: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["message"]["id"].as_str().unwrap(), sent[2]);
+
+    // Leased messages can't be expedited; unknown ids are 404
+    let resp = client
+        .post(format!("{base_url}/api/messages/{}/expedite", sent[2]))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 409);
+    let resp = client
+        .post(format!("{base_url}/api/messages/no-such-id/expedite"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn extend_lease_pushes_out_expiry() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+        }))
+        .send()
+        .await?;
+    let sent: serde_json::Value = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task.render",
+            "payload": {},
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let id = sent["id"].as_str().unwrap();
+    let extend_url = format!("{base_url}/api/messages/{id}/extend-lease");
+
+    // Still queued: nothing to extend
+    let resp = client.post(&extend_url).send().await?;
+    assert_eq!(resp.status(), 409);
+
+    client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+        ))
+        .send()
+        .await?;
+    let resp = client
+        .post(&extend_url)
+        .json(&serde_json::json!({ "secs": 3600 }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["secs"], 600);
+
+    let events = Registry::open(&db_path)?.get_message_events(id)?;
+    assert_eq!(events.last().unwrap().event_type, "lease_extended");
+
+    let resp = client
+        .post(format!("{base_url}/api/messages/no-such-id/extend-lease"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn nack_requeues_leased_message() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+            "max_retries": 2,
+        }))
+        .send()
+        .await?;
+    let sent: serde_json::Value = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task.sync",
+            "payload": {},
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let id = sent["id"].as_str().unwrap();
+    let nack_url = format!("{base_url}/api/messages/{id}/nack");
+    let receive_url = format!("{base_url}/api/instances/agent-b/messages/pending?wait=5");
+
+    // Only leased messages can be nacked
+    assert_eq!(client.post(&nack_url).send().await?.status(), 409);
+
+    client.get(&receive_url).send().await?;
+    let resp = client.post(&nack_url).send().await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["status"], "queued");
+    assert!(body["next_attempt_at"].is_string());
+
+    // Redelivered after backoff; a second nack exhausts max_retries
+    let recv: serde_json::Value = client.get(&receive_url).send().await?.json().await?;
+    assert_eq!(recv["message"]["id"], id);
+    let body: serde_json::Value = client.post(&nack_url).send().await?.json().await?;
+    assert_eq!(body["status"], "dead_letter");
+
+    let resp = client
+        .post(format!("{base_url}/api/messages/no-such-id/nack"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
+// ══════════════════════════════════════════════════════════════════
+// Message stats: dead-letter reason breakdown
+// ══════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn message_stats_dead_letter_reasons() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let registry = Registry::open(&db_path)?;
+    registry.create_routing_rule(
+        "agent-a",
+        "agent-b",
+        "*",
+        5,
+        3600,
+        90,
+        false,
+        false,
+        HealthGate::Off,
+        None,
+    )?;
+
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        let msg_id = uuid::Uuid::new_v4().to_string();
+        registry.enqueue_message(&zeroclaw::db::NewMessage {
+            id: msg_id.clone(),
+            from_instance: "agent-a".to_string(),
+            to_instance: "agent-b".to_string(),
+            message_type: "task".to_string(),
+            payload: r#"{"text":"test"}"#.to_string(),
+            correlation_id: None,
+            idempotency_key: None,
+            idempotency_window_secs: None,
+            hop_count: 0,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        })?;
+        ids.push(msg_id);
+    }
+    registry.dead_letter_message(&ids[0], "max retries exceeded")?;
+    registry.dead_letter_message(&ids[1], "app.rejected")?;
+    drop(registry);
+
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{base_url}/api/messages/stats"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["total"].as_i64().unwrap(), 3);
+    assert_eq!(body["by_status"]["queued"].as_i64().unwrap(), 1);
+    assert_eq!(body["by_status"]["dead_letter"].as_i64().unwrap(), 2);
+
+    let reasons = body["dead_letter_reasons"].as_array().unwrap();
+    assert_eq!(reasons.len(), 2);
+    assert!(reasons
+        .iter()
+        .any(|r| r["reason"] == "max retries exceeded" && r["count"] == 1));
+    assert!(reasons
+        .iter()
+        .any(|r| r["reason"] == "app.rejected" && r["count"] == 1));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn purge_deletes_old_terminal_messages_only() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let registry = Registry::open(&db_path)?;
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let msg_id = uuid::Uuid::new_v4().to_string();
+        registry.enqueue_message(&zeroclaw::db::NewMessage {
+            id: msg_id.clone(),
+            from_instance: "agent-a".to_string(),
+            to_instance: "agent-b".to_string(),
+            message_type: "task".to_string(),
+            payload: "{}".to_string(),
+            correlation_id: None,
+            idempotency_key: None,
+            idempotency_window_secs: None,
+            hop_count: 0,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        })?;
+        registry.append_message_event(&msg_id, "created", None)?;
+        ids.push(msg_id);
+    }
+    registry.dead_letter_message(&ids[0], "max retries exceeded")?;
+    drop(registry);
+    // Age both messages past the retention window
+    rusqlite::Connection::open(&db_path)?
+        .execute("UPDATE messages SET updated_at = '2000-01-01 00:00:00'", [])?;
+
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+    let purge = |status: &str| {
+        client
+            .post(format!("{base_url}/api/messages/purge"))
+            .json(&serde_json::json!({ "status": status, "older_than_secs": 86400 }))
+            .send()
+    };
+
+    // Queued/leased messages can never be purged
+    assert_eq!(purge("queued").await?.status(), 400);
+
+    let resp = purge("dead_letter").await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["messages_deleted"], 1);
+    assert!(body["events_deleted"].as_u64().unwrap() >= 2);
+
+    let registry = Registry::open(&db_path)?;
+    assert!(registry.get_message(&ids[0])?.is_none());
+    assert!(registry.get_message(&ids[1])?.is_some());
+
+    Ok(())
+}
+
+// ══════════════════════════════════════════════════════════════════
+// Message TTL bounds
+// ══════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn message_ttl_default_clamp_and_zero() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    // Rule without ttl_secs gets the policy default
+    let rule_resp = client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "*",
+        }))
+        .send()
+        .await?;
+    assert_eq!(rule_resp.status(), 201);
+    let rule_body: serde_json::Value = rule_resp.json().await?;
+    assert_eq!(rule_body["ttl_secs"].as_i64().unwrap(), 3600);
+
+    let send = |ttl: Option<i64>| {
+        let mut body = serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task",
+            "payload": {"text": "ttl"},
+        });
+        if let Some(ttl) = ttl {
+            body["ttl_secs"] = serde_json::json!(ttl);
+        }
+        client.post(format!("{base_url}/api/messages")).json(&body).send()
+    };
+
+    // Omitted: falls back to the rule's TTL
+    let resp = send(None).await?;
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["ttl_secs"].as_i64().unwrap(), 3600);
+
+    // Over max: clamped to 24h
+    let resp = send(Some(10_000_000)).await?;
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["ttl_secs"].as_i64().unwrap(), 86400);
+
+    // Zero: rejected rather than expiring instantly
+    let resp = send(Some(0)).await?;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = resp.json().await?;
+    assert!(body["error"].as_str().unwrap().contains("ttl_secs"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn message_ttl_follows_configured_policy() -> Result<()> {
+    // Configured bounds are read once at startup; the default never exceeds
+    // the max, and non-positive values keep the built-in bounds
+    std::env::set_var("ZEROCLAW_CP_DEFAULT_TTL_SECS", "7200");
+    std::env::set_var("ZEROCLAW_CP_MAX_TTL_SECS", "600");
+    let policy = cp::messaging::TtlPolicy::from_env();
+    std::env::remove_var("ZEROCLAW_CP_DEFAULT_TTL_SECS");
+    std::env::remove_var("ZEROCLAW_CP_MAX_TTL_SECS");
+    assert_eq!(policy.default_ttl_secs, 600);
+    assert_eq!(policy.max_ttl_secs, 600);
+    assert_eq!(
+        cp::messaging::TtlPolicy::new(Some(-1), Some(0)),
+        cp::messaging::TtlPolicy::default()
+    );
+
+    let (_tmp, db_path) = setup_two_instances();
+    let mut state = cp::server::CpState::new(db_path);
+    state.ttl_policy = cp::messaging::TtlPolicy::new(Some(120), Some(600));
+    let (base_url, _shutdown) = start_test_server_with_state(state).await;
+    let client = reqwest::Client::new();
+
+    let rule_body: serde_json::Value = client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "*",
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(rule_body["ttl_secs"].as_i64().unwrap(), 120);
+
+    let body: serde_json::Value = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task",
+            "payload": {"text": "ttl"},
+            "ttl_secs": 10_000,
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["ttl_secs"].as_i64().unwrap(), 600);
+
+    Ok(())
+}
+
+// ══════════════════════════════════════════════════════════════════
+// TTL expiry audit trail
+// ══════════════════════════════════════════════════════════════════
+
+#[test]
+fn reaper_expires_message_with_ttl_expired_event() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let registry = Registry::open(&db_path)?;
+    registry.enqueue_message(&zeroclaw::db::NewMessage {
+        id: "expiring".to_string(),
+        from_instance: "agent-a".to_string(),
+        to_instance: "agent-b".to_string(),
+        message_type: "task".to_string(),
+        payload: "{}".to_string(),
+        correlation_id: None,
+        idempotency_key: None,
+        idempotency_window_secs: None,
+        hop_count: 0,
+        max_retries: 5,
+        ttl_secs: 3600,
+        content_type: None,
+        priority: 0,
+    })?;
+    registry.conn().execute(
+        "UPDATE messages SET expires_at = '2000-01-01 00:00:00' WHERE id = 'expiring'",
+        [],
+    )?;
+
+    assert_eq!(cp::messaging::delivery_tick(&db_path)?, 1);
+
+    let msg = registry.get_message("expiring")?.unwrap();
+    assert_eq!(msg.status, "dead_letter");
+    assert_eq!(msg.dead_letter_reason.as_deref(), Some("ttl_expired"));
+    let events = registry.get_message_events("expiring")?;
+    assert!(events.iter().any(|e| e.event_type == "ttl_expired"));
+    Ok(())
+}
+
+// ══════════════════════════════════════════════════════════════════
+// Background worker status
+// ══════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn worker_failures_are_recorded_and_exposed() -> Result<()> {
+    use cp::workers::{failure_backoff, run_iteration};
+    use std::time::Duration;
+
+    // The board is process-wide; use a name no real loop reports under.
+    const WORKER: &str = "test-flaky-worker";
+
+    let backoff = run_iteration(WORKER, || panic!("reaper exploded")).await;
+    assert_eq!(backoff, Duration::from_secs(1));
+    let backoff = run_iteration(WORKER, || anyhow::bail!("database is locked")).await;
+    assert_eq!(backoff, Duration::from_secs(2));
+    assert_eq!(failure_backoff(30), Duration::from_secs(60));
+
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let workers = || {
+        let request = client.get(format!("{base_url}/api/admin/workers")).send();
+        async move {
+            let body: serde_json::Value = request.await?.json().await?;
+            anyhow::Ok(body["workers"][WORKER].clone())
+        }
+    };
+
+    let status = workers().await?;
+    assert_eq!(status["consecutive_failures"], 2);
+    assert_eq!(status["last_error"], "database is locked");
+    assert!(status["last_success_at"].is_null());
+
+    // A later success resets the failure streak but keeps the last error
+    assert_eq!(run_iteration(WORKER, || Ok(3)).await, Duration::ZERO);
+    let status = workers().await?;
+    assert_eq!(status["consecutive_failures"], 0);
+    assert_eq!(status["items_processed"], 3);
+    assert_eq!(status["last_error"], "database is locked");
+    assert!(status["last_success_at"].is_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn throughput_counters_track_leases_and_acks() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    // Counters are process-wide and other tests move them too, so compare
+    // against a baseline with >=.
+    let before = cp::metrics::MessagingMetrics::global().counts();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+        }))
+        .send()
+        .await?;
+    let send_body: serde_json::Value = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task.handoff",
+            "payload": {"text": "count me"},
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let msg_id = send_body["id"].as_str().unwrap().to_string();
+    client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+        ))
+        .send()
+        .await?;
+    let ack = client
+        .post(format!("{base_url}/api/messages/{msg_id}/acknowledge"))
+        .send()
+        .await?;
+    assert_eq!(ack.status(), 200);
+
+    let throughput: serde_json::Value = client
+        .get(format!("{base_url}/api/messages/throughput"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let totals = &throughput["totals"];
+    assert!(totals["leased"].as_u64().unwrap() >= before.leased + 1);
+    assert!(totals["acknowledged"].as_u64().unwrap() >= before.acknowledged + 1);
+    assert!(throughput["per_minute"]["leased"].is_number());
+
+    let resp = client.get(format!("{base_url}/metrics")).send().await?;
+    assert_eq!(resp.status(), 200);
+    let text = resp.text().await?;
+    assert!(text.contains("# TYPE zeroclaw_cp_messages_acknowledged_total counter"));
+    assert!(text.contains("zeroclaw_cp_messages_dead_lettered_total "));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn api_metrics_reports_registry_state_gauges() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task",
+        }))
+        .send()
+        .await?;
+    for n in 0..2 {
+        client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type": "task",
+                "payload": {"n": n},
+            }))
+            .send()
+            .await?;
+    }
+
+    let resp = client.get(format!("{base_url}/api/metrics")).send().await?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "text/plain; version=0.0.4");
+    let text = resp.text().await?;
+    assert!(text.contains("\nzeroclaw_messages_total{status=\"queued\"} 2\n"));
+    assert!(text.contains("\nzeroclaw_messages_queued 2\n"));
+    assert!(text.contains("\nzeroclaw_dead_letter_total 0\n"));
+    assert!(text.contains("\nzeroclaw_instances{status=\"stopped\"} 2\n"));
+    // The process counters from /metrics are included too
+    assert!(text.contains("# TYPE zeroclaw_cp_messages_leased_total counter"));
+
+    // /metrics renders the same snapshot
+    let text = client
+        .get(format!("{base_url}/metrics"))
+        .send()
+        .await?
+        .text()
+        .await?;
+    assert!(text.contains("\nzeroclaw_messages_queued 2\n"));
+    assert!(text.contains("\nzeroclaw_instances{status=\"stopped\"} 2\n"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn route_cycle_rejects_revisit_within_correlation_thread() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    for (from, to) in [("agent-a", "agent-b"), ("agent-b", "agent-a")] {
+        let resp = client
+            .post(format!("{base_url}/api/routing-rules"))
+            .json(&serde_json::json!({
+                "from_instance": from,
+                "to_instance": to,
+                "type_pattern": "*",
+                "detect_cycles": true,
+            }))
+            .send()
+            .await?;
+        assert_eq!(resp.status(), 201);
+    }
+
+    let send = |from: &str, to: &str, correlation_id: &str| {
+        client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": from,
+                "to_instance": to,
+                "type": "task",
+                "payload": {"text": "ping"},
+                "correlation_id": correlation_id,
+            }))
+            .send()
+    };
+
+    // A -> B, then the reply B -> A
+    assert_eq!(send("agent-a", "agent-b", "thread-1").await?.status(), 201);
+    assert_eq!(send("agent-b", "agent-a", "thread-1").await?.status(), 201);
+
+    // Third hop A -> B revisits B
+    let resp = send("agent-a", "agent-b", "thread-1").await?;
+    assert_eq!(resp.status(), 409);
+    let body: serde_json::Value = resp.json().await?;
+    let error = body["error"].as_str().unwrap();
+    assert!(error.starts_with("ROUTE_CYCLE"), "{error}");
+    assert!(error.contains("agent-b -> agent-a"), "{error}");
+
+    // Other threads are unaffected
+    assert_eq!(send("agent-a", "agent-b", "thread-2").await?.status(), 201);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn export_streams_jsonl_with_events_and_redaction() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let registry = Registry::open(&db_path)?;
+    for (id, payload) in [
+        ("exp-1", r#"{"text":"hello","api_key":"sk-live-123"}"#),
+        ("exp-2", r#"{"text":"second"}"#),
+    ] {
+        registry.enqueue_message(&zeroclaw::db::NewMessage {
+            id: id.into(),
+            from_instance: "agent-a".into(),
+            to_instance: "agent-b".into(),
+            message_type: "task".into(),
+            payload: payload.into(),
+            correlation_id: None,
+            idempotency_key: None,
+            idempotency_window_secs: None,
+            hop_count: 0,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        })?;
+        registry.append_message_event(id, "created", None)?;
+    }
+    drop(registry);
+
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let mut state = cp::server::CpState::new(db_path);
+    state.allow_secret_export = true;
+    let (secrets_url, _secrets_shutdown) = start_test_server_with_state(state).await;
+    let client = reqwest::Client::new();
+    let export = |base_url: &str, include_secrets: bool| {
+        let mut request = client.get(format!("{base_url}/api/messages/export?format=jsonl"));
+        if include_secrets {
+            request = request.header("X-Include-Secrets", "true");
+        }
+        async move {
+            let resp = request.send().await?;
+            assert_eq!(resp.status(), 200);
+            let text = resp.text().await?;
+            let lines = text
+                .lines()
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<serde_json::Value>, _>>()?;
+            anyhow::Ok(lines)
+        }
+    };
+
+    let lines = export(&base_url, false).await?;
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["id"], "exp-1");
+    assert_eq!(lines[0]["payload"]["text"], "hello");
+    assert_ne!(lines[0]["payload"]["api_key"], "sk-live-123");
+    assert_eq!(lines[0]["events"][0]["event_type"], "created");
+    assert_eq!(lines[1]["id"], "exp-2");
+
+    // Unredacted payloads only where the control plane allows them
+    let resp = client
+        .get(format!("{base_url}/api/messages/export?format=jsonl"))
+        .header("X-Include-Secrets", "true")
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 403);
+    let lines = export(&secrets_url, true).await?;
+    assert_eq!(lines[0]["payload"]["api_key"], "sk-live-123");
+
+    let resp = client
+        .get(format!("{base_url}/api/messages/export?format=csv"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn correlation_chain_traces_every_hop() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let registry = Registry::open(&db_path)?;
+    for (id, from, to, hop_count, correlation_id) in [
+        ("c-1", "agent-a", "agent-b", 0, "thread-1"),
+        ("c-2", "agent-b", "agent-a", 1, "thread-1"),
+        ("c-3", "agent-a", "agent-b", 2, "thread-1"),
+        ("other", "agent-a", "agent-b", 0, "thread-2"),
+    ] {
+        registry.enqueue_message(&zeroclaw::db::NewMessage {
+            id: id.into(),
+            from_instance: from.into(),
+            to_instance: to.into(),
+            message_type: "task".into(),
+            payload: r#"{"text":"relay","token":"sk-live-123"}"#.into(),
+            correlation_id: Some(correlation_id.into()),
+            idempotency_key: None,
+            idempotency_window_secs: None,
+            hop_count,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        })?;
+        registry.append_message_event(id, "created", None)?;
+    }
+    let leased = registry.lease_pending_messages("agent-b", 1, 90)?;
+    assert_eq!(leased[0].id, "c-1");
+    registry.acknowledge_message("c-1", None)?;
+    registry.dead_letter_message("c-2", "max retries exceeded")?;
+    drop(registry);
+
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{base_url}/api/correlations/thread-1"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["total"], 3);
+    assert_eq!(body["hop_count"], 2);
+    assert_eq!(body["summary"]["acknowledged"], 1);
+    assert_eq!(body["summary"]["dead_letter"], 1);
+    assert_eq!(body["summary"]["pending"], 1);
+    let messages = body["messages"].as_array().unwrap();
+    let ids: Vec<&str> = messages.iter().map(|m| m["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["c-1", "c-2", "c-3"]);
+    assert_eq!(messages[0]["events"][0]["event_type"], "created");
+    assert_ne!(messages[0]["payload"]["token"], "sk-live-123");
+
+    let resp = client
+        .get(format!("{base_url}/api/correlations/missing"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_finds_payload_text_and_redacts_secrets() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let registry = Registry::open(&db_path)?;
+    for (id, message_type, payload) in [
+        (
+            "s-1",
+            "task",
+            r#"{"text":"rotate the key","api_key":"sk-live-123"}"#,
+        ),
+        ("s-2", "note", r#"{"text":"Rotate logs"}"#),
+        ("s-3", "task", r#"{"text":"unrelated"}"#),
+    ] {
+        registry.enqueue_message(&zeroclaw::db::NewMessage {
+            id: id.into(),
+            from_instance: "agent-a".into(),
+            to_instance: "agent-b".into(),
+            message_type: message_type.into(),
+            payload: payload.into(),
+            correlation_id: None,
+            idempotency_key: None,
+            idempotency_window_secs: None,
+            hop_count: 0,
+            max_retries: 5,
+            ttl_secs: 3600,
+            content_type: None,
+            priority: 0,
+        })?;
+    }
+    drop(registry);
+
+    let mut state = cp::server::CpState::new(db_path);
+    state.allow_secret_export = true;
+    let (base_url, _shutdown) = start_test_server_with_state(state).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{base_url}/api/messages/search?q=rotate"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["has_more"], false);
+    let messages = body["messages"].as_array().unwrap();
+    let task = messages.iter().find(|m| m["id"] == "s-1").unwrap();
+    assert_eq!(task["payload"]["text"], "rotate the key");
+    assert_ne!(task["payload"]["api_key"], "sk-live-123");
+
+    let resp = client
+        .get(format!(
+            "{base_url}/api/messages/search?q=rotate&message_type=task&limit=1"
+        ))
+        .header("X-Include-Secrets", "true")
+        .send()
+        .await?;
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["total"], 1);
+    assert_eq!(body["messages"][0]["payload"]["api_key"], "sk-live-123");
+
+    let resp = client
+        .get(format!("{base_url}/api/messages/search"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn acknowledge_result_round_trips_into_event_detail() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+        }))
+        .send()
+        .await?;
+    let send_and_lease = || async {
+        let send_body: serde_json::Value = client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type": "task.lookup",
+                "payload": {"query": "weather"},
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        client
+            .get(format!(
+                "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+            ))
+            .send()
+            .await?;
+        anyhow::Ok(send_body["id"].as_str().unwrap().to_string())
+    };
+    let ack_detail = |msg_id: &str| -> Result<serde_json::Value> {
+        let event = Registry::open(&db_path)?
+            .get_message_events(msg_id)?
+            .into_iter()
+            .find(|e| e.event_type == "acknowledged")
+            .expect("acknowledged event");
+        Ok(serde_json::from_str(event.detail.as_deref().unwrap())?)
+    };
+
+    // Ack with a result: stored in the event detail, secrets redacted
+    let msg_id = send_and_lease().await?;
+    let ack = client
+        .post(format!("{base_url}/api/messages/{msg_id}/acknowledge"))
+        .json(&serde_json::json!({
+            "result": {"forecast": "sunny", "api_key": "sk-live-123"},
+        }))
+        .send()
+        .await?;
+    assert_eq!(ack.status(), 200);
+    let detail = ack_detail(&msg_id)?;
+    assert_eq!(detail["result"]["forecast"], "sunny");
+    assert_ne!(detail["result"]["api_key"], "sk-live-123");
+    assert_eq!(detail["attempt"], 1);
+
+    // Plain ack still works and records no result
+    let msg_id = send_and_lease().await?;
+    let ack = client
+        .post(format!("{base_url}/api/messages/{msg_id}/acknowledge"))
+        .send()
+        .await?;
+    assert_eq!(ack.status(), 200);
+    assert!(ack_detail(&msg_id)?.get("result").is_none());
+
+    // Malformed body is rejected without acknowledging
+    let msg_id = send_and_lease().await?;
+    let ack = client
+        .post(format!("{base_url}/api/messages/{msg_id}/acknowledge"))
+        .body("{not json")
+        .send()
+        .await?;
+    assert_eq!(ack.status(), 400);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn health_gate_holds_messages_for_stopped_recipient() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+            "health_gate": "bogus",
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    let rule: serde_json::Value = client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "task.*",
+            "health_gate": "running",
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(rule["health_gate"], "running");
+    let rule_id = rule["id"].as_str().unwrap().to_string();
+
+    let send_body: serde_json::Value = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task.handoff",
+            "payload": {"text": "wait for me"},
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let msg_id = send_body["id"].as_str().unwrap().to_string();
+
+    // agent-b is not running: nothing is leased and the message stays queued
+    let recv: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=0"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert!(recv["message"].is_null());
+    assert_eq!(recv["held_reason"], "recipient_unhealthy");
+    let msg = Registry::open(&db_path)?.get_message(&msg_id)?.unwrap();
+    assert_eq!(msg.status, "queued");
+    assert_eq!(msg.retry_count, 0);
+
+    // Without the gating rule the message is delivered as usual
+    client
+        .delete(format!("{base_url}/api/routing-rules/{rule_id}"))
+        .send()
+        .await?;
+    let recv: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=0"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(recv["message"]["id"], msg_id.as_str());
+    assert!(recv.get("held_reason").is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn payload_is_json_parses_strings_and_rejects_invalid_json() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "*",
+        }))
+        .send()
+        .await?;
+
+    let send = |payload: serde_json::Value, extra: serde_json::Value| {
+        let mut body = serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task",
+            "payload": payload,
+        });
+        body.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        client
+            .post(format!("{base_url}/api/messages"))
+            .json(&body)
+            .send()
+    };
+
+    // Stringified JSON is stored as the parsed value, in canonical form
+    let resp = send(
+        serde_json::json!("{ \"b\": 2, \"a\": 1 }"),
+        serde_json::json!({"payload_is_json": true}),
+    )
+    .await?;
+    assert_eq!(resp.status(), 201);
+    let id = resp.json::<serde_json::Value>().await?["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let msg = Registry::open(&db_path)?.get_message(&id)?.unwrap();
+    assert_eq!(msg.payload, r#"{"a":1,"b":2}"#);
+    assert_eq!(msg.content_type, None);
+
+    // Malformed JSON text is rejected up front
+    let resp = send(
+        serde_json::json!("{not json"),
+        serde_json::json!({"payload_is_json": true}),
+    )
+    .await?;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = resp.json().await?;
+    assert!(body["error"].as_str().unwrap().contains("not valid JSON"));
+
+    // Without the flag the same string is an ordinary JSON string payload
+    let resp = send(serde_json::json!("{not json"), serde_json::json!({})).await?;
+    assert_eq!(resp.status(), 201);
+
+    // Raw payloads carry their content type and must be strings
+    let resp = send(
+        serde_json::json!({"data": "aGk="}),
+        serde_json::json!({"content_type": "application/octet-stream"}),
+    )
+    .await?;
+    assert_eq!(resp.status(), 400);
+    let resp = send(
+        serde_json::json!("aGk="),
+        serde_json::json!({"content_type": "application/octet-stream", "payload_is_json": true}),
+    )
+    .await?;
+    assert_eq!(resp.status(), 400);
+    let resp = send(
+        serde_json::json!("aGk="),
+        serde_json::json!({"content_type": "application/octet-stream"}),
+    )
+    .await?;
+    assert_eq!(resp.status(), 201);
+
+    let recv: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/agent-b/messages/pending?wait=0&max=10"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let raw = recv["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["content_type"] == "application/octet-stream")
+        .expect("raw message should be delivered with its content type");
+    assert_eq!(raw["payload"], "aGk=");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ensure_rule_creates_route_for_trusted_sender_only() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let registry = Registry::open(&db_path)?;
+    let token = |name: &str| -> Result<String> {
+        let instance = registry.get_instance_by_name(name)?.unwrap();
+        registry.issue_instance_token(&instance.id)
+    };
+    let (a_token, b_token) = (token("agent-a")?, token("agent-b")?);
+    drop(registry);
+
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let mut state = cp::server::CpState::new(db_path.clone());
+    state.auto_authorize = cp::messaging::AutoAuthorize::parse("agent-x, agent-a");
+    let (trusted_url, _trusted_shutdown) = start_test_server_with_state(state).await;
+    let client = reqwest::Client::new();
+
+    let body = serde_json::json!({
+        "from_instance": "agent-a",
+        "to_instance": "agent-b",
+        "type": "handoff",
+        "payload": {"step": 1},
+        "ensure_rule": true,
+    });
+    let send = |base_url: &str, token: Option<&str>| {
+        let mut request = client.post(format!("{base_url}/api/messages")).json(&body);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request.send()
+    };
+
+    // The sender must prove it is from_instance
+    assert_eq!(send(&trusted_url, None).await?.status(), 401);
+    assert_eq!(send(&trusted_url, Some("zcp_bogus")).await?.status(), 401);
+    assert_eq!(send(&trusted_url, Some(&b_token)).await?.status(), 403);
+
+    // Off by default: the flag does not bypass the allowlist
+    let resp = send(&base_url, Some(&a_token)).await?;
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = resp.json().await?;
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("ZEROCLAW_CP_AUTO_AUTHORIZE_SENDERS"));
+    assert!(Registry::open(&db_path)?.list_routing_rules()?.is_empty());
+
+    // Trusted sender: the first send creates the rule, the second reuses it
+    let first: serde_json::Value = send(&trusted_url, Some(&a_token)).await?.json().await?;
+    let second: serde_json::Value = send(&trusted_url, Some(&a_token)).await?.json().await?;
+
+    assert_eq!(first["rule_created"], true);
+    assert_eq!(second["rule_created"], false);
+    assert_eq!(first["rule_id"], second["rule_id"]);
+
+    let registry = Registry::open(&db_path)?;
+    let rules = registry.list_routing_rules()?;
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].from_instance, "agent-a");
+    assert_eq!(rules[0].to_instance, "agent-b");
+    assert_eq!(rules[0].type_pattern, "handoff");
+
+    let id = first["id"].as_str().unwrap();
+    let events = registry.get_message_events(id)?;
+    assert!(events[0]
+        .detail
+        .as_deref()
+        .unwrap()
+        .contains("\"rule_created\":true"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn forward_increments_hops_and_links_messages() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type_pattern": "*",
+        }))
+        .send()
+        .await?;
+
+    let send = |hop_count: i64| {
+        client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type": "task",
+                "payload": {"text": "relay me"},
+                "hop_count": hop_count,
+            }))
+            .send()
+    };
+    let forward = |id: &str| {
+        client
+            .post(format!("{base_url}/api/messages/{id}/forward"))
+            .json(&serde_json::json!({"to_instance": "agent-a"}))
+            .send()
+    };
+    let receive = || {
+        client
+            .get(format!(
+                "{base_url}/api/instances/agent-b/messages/pending?wait=1"
+            ))
+            .send()
+    };
+
+    let original: serde_json::Value = send(0).await?.json().await?;
+    let original_id = original["id"].as_str().unwrap().to_string();
+
+    // Not yet received by agent-b
+    assert_eq!(forward(&original_id).await?.status(), 409);
+    receive().await?;
+
+    // No rule for the new hop agent-b -> agent-a
+    assert_eq!(forward(&original_id).await?.status(), 403);
+
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-b",
+            "to_instance": "agent-a",
+            "type_pattern": "*",
+        }))
+        .send()
+        .await?;
+    let resp = forward(&original_id).await?;
+    assert_eq!(resp.status(), 201);
+    let forwarded_id = resp.json::<serde_json::Value>().await?["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let registry = Registry::open(&db_path)?;
+    let forwarded = registry.get_message(&forwarded_id)?.unwrap();
+    assert_eq!(forwarded.from_instance, "agent-b");
+    assert_eq!(forwarded.to_instance, "agent-a");
+    assert_eq!(forwarded.hop_count, 1);
+    assert_eq!(
+        forwarded.correlation_id.as_deref(),
+        Some(original_id.as_str())
+    );
+    assert_eq!(forwarded.payload, r#"{"text":"relay me"}"#);
+
+    let created: serde_json::Value = serde_json::from_str(
+        registry.get_message_events(&forwarded_id)?[0]
+            .detail
+            .as_deref()
+            .unwrap(),
+    )?;
+    assert_eq!(created["forwarded_from"], original_id.as_str());
+    let link = registry
+        .get_message_events(&original_id)?
+        .into_iter()
+        .find(|e| e.event_type == "forwarded")
+        .unwrap();
+    assert!(link.detail.unwrap().contains(&forwarded_id));
+
+    // The incremented hop count is held to the limit
+    let last_hop: serde_json::Value = send(7).await?.json().await?;
+    receive().await?;
+    let resp = forward(last_hop["id"].as_str().unwrap()).await?;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = resp.json().await?;
+    assert!(body["error"].as_str().unwrap().contains("Hop count 8"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn rule_transform_reshapes_payload_and_keeps_original() -> Result<()> {
+    let (_tmp, db_path) = setup_two_instances();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+    let create_rule = |transform: serde_json::Value| {
+        client
+            .post(format!("{base_url}/api/routing-rules"))
+            .json(&serde_json::json!({
+                "from_instance": "agent-a",
+                "to_instance": "agent-b",
+                "type_pattern": "*",
+                "transform": transform,
+            }))
+            .send()
+    };
+
+    // Templates are validated when the rule is created
+    let resp = create_rule(serde_json::json!({"x": "{{secrets.key}}"})).await?;
+    assert_eq!(resp.status(), 400);
+    let resp = create_rule(serde_json::json!({"x": "{{payload"})).await?;
+    assert_eq!(resp.status(), 400);
+
+    let resp = create_rule(serde_json::json!({
+        "kind": "{{type}}",
+        "from": "{{from_instance}}",
+        "body": "{{payload}}",
+        "title": "Ticket #{{payload.ticket}}",
+    }))
+    .await?;
+    assert_eq!(resp.status(), 201);
+
+    let resp = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task",
+            "payload": {"ticket": 42},
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 201);
+    let id = resp.json::<serde_json::Value>().await?["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let registry = Registry::open(&db_path)?;
+    let stored: serde_json::Value =
+        serde_json::from_str(&registry.get_message(&id)?.unwrap().payload)?;
+    assert_eq!(
+        stored,
+        serde_json::json!({
+            "kind": "task",
+            "from": "agent-a",
+            "body": {"ticket": 42},
+            "title": "Ticket #42",
+        })
+    );
+    let transformed = registry
+        .get_message_events(&id)?
+        .into_iter()
+        .find(|e| e.event_type == "transformed")
+        .unwrap();
+    let detail: serde_json::Value = serde_json::from_str(&transformed.detail.unwrap())?;
+    assert_eq!(
+        detail["original_payload"],
+        serde_json::json!({"ticket": 42})
+    );
+
+    // A payload missing a referenced field is rejected, not sent half-filled
+    let resp = client
+        .post(format!("{base_url}/api/messages"))
+        .json(&serde_json::json!({
+            "from_instance": "agent-a",
+            "to_instance": "agent-b",
+            "type": "task",
+            "payload": {"other": 1},
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = resp.json().await?;
+    assert!(body["error"].as_str().unwrap().contains("payload.ticket"));
+    Ok(())
+}
@@ -1,7 +1,6 @@
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
 use zeroclaw::db::Registry;
@@ -35,7 +34,7 @@ fn setup_with_instance(name: &str, port: u16) -> (TempDir, PathBuf) {
     let id = uuid::Uuid::new_v4().to_string();
     let inst_dir = instances_dir.join(&id);
     let workspace_dir = inst_dir.join("workspace");
-    for subdir in &["skills", "memory", "sessions", "state", "cron"] {
+    for subdir in zeroclaw::config::workspace::WORKSPACE_SUBDIRS {
         fs::create_dir_all(workspace_dir.join(subdir)).unwrap();
     }
 
@@ -65,9 +64,7 @@ host = "127.0.0.1"
 }
 
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    let state = cp::server::CpState::new(db_path);
     let app = cp::server::build_router(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -287,7 +284,7 @@ async fn create_produces_workspace() -> Result<()> {
     let instances_dir = db_path.parent().unwrap().join("instances");
     let workspace_dir = instances_dir.join(id).join("workspace");
 
-    for subdir in &["skills", "memory", "sessions", "state", "cron"] {
+    for subdir in zeroclaw::config::workspace::WORKSPACE_SUBDIRS {
         assert!(
             workspace_dir.join(subdir).is_dir(),
             "workspace/{subdir} should exist"
@@ -297,6 +294,86 @@ async fn create_produces_workspace() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn create_instance_from_template() -> Result<()> {
+    let (_tmp, db_path) = setup_cp();
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+
+    let client = reqwest::Client::new();
+
+    // Store a template
+    let resp = client
+        .put(format!("{base_url}/api/config/templates/fleet-base"))
+        .json(&serde_json::json!({
+            "default_temperature": 0.3,
+            "default_model": "template-model",
+            "gateway": { "port": 1 },
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["created"], true);
+
+    let list: serde_json::Value = client
+        .get(format!("{base_url}/api/config/templates"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(list["templates"], serde_json::json!(["fleet-base"]));
+
+    // Create from it, overriding one field
+    let resp = client
+        .post(format!("{base_url}/api/instances"))
+        .json(&serde_json::json!({
+            "name": "templated",
+            "port": 18860,
+            "template": "fleet-base",
+            "overrides": { "default_model": "override-model" },
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["template"], "fleet-base");
+
+    let id = body["id"].as_str().unwrap();
+    let config_path = db_path
+        .parent()
+        .unwrap()
+        .join("instances")
+        .join(id)
+        .join("config.toml");
+    let config: toml::Value = toml::from_str(&std::fs::read_to_string(config_path)?)?;
+    assert_eq!(config["default_temperature"].as_float(), Some(0.3));
+    assert_eq!(config["default_model"].as_str(), Some("override-model"));
+    // The registry port wins over the template's
+    assert_eq!(config["gateway"]["port"].as_integer(), Some(18860));
+
+    // Unknown template
+    let resp = client
+        .post(format!("{base_url}/api/instances"))
+        .json(&serde_json::json!({ "name": "orphan", "template": "missing" }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    // Delete the template
+    let resp = client
+        .delete(format!("{base_url}/api/config/templates/fleet-base"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let resp = client
+        .get(format!("{base_url}/api/config/templates/fleet-base"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 2: Archive
 // ══════════════════════════════════════════════════════════════════
@@ -332,6 +409,108 @@ async fn archive_instance_basic() -> Result<()> {
     Ok(())
 }
 
+/// Queue two messages from `msg-source` to `name` (instances must exist).
+async fn queue_messages_to(client: &reqwest::Client, base_url: &str, name: &str) -> Vec<String> {
+    client
+        .post(format!("{base_url}/api/routing-rules"))
+        .json(&serde_json::json!({
+            "from_instance": "msg-source",
+            "to_instance": name,
+            "type_pattern": "*"
+        }))
+        .send()
+        .await
+        .unwrap();
+    let mut ids = Vec::new();
+    for i in 0..2 {
+        let body: serde_json::Value = client
+            .post(format!("{base_url}/api/messages"))
+            .json(&serde_json::json!({
+                "from_instance": "msg-source",
+                "to_instance": name,
+                "type": "test",
+                "payload": {"n": i}
+            }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    ids
+}
+
+#[tokio::test]
+async fn archive_warns_or_cancels_pending_messages() -> Result<()> {
+    let (_tmp, db_path) = setup_with_instance("msg-source", 18801);
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    for name in ["keep-queue", "drain-queue"] {
+        client
+            .post(format!("{base_url}/api/instances"))
+            .json(&serde_json::json!({ "name": name }))
+            .send()
+            .await?;
+    }
+    let kept = queue_messages_to(&client, &base_url, "keep-queue").await;
+    let drained = queue_messages_to(&client, &base_url, "drain-queue").await;
+
+    // Default: archive leaves messages alone but warns
+    let body: serde_json::Value = client
+        .post(format!("{base_url}/api/instances/keep-queue/archive"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["status"], "archived");
+    assert_eq!(body["pending_messages"], 2);
+    assert!(body["warnings"][0]
+        .as_str()
+        .unwrap()
+        .contains("drain=cancel"));
+
+    // drain=cancel cancels them with a reason and event
+    let body: serde_json::Value = client
+        .post(format!(
+            "{base_url}/api/instances/drain-queue/archive?drain=cancel"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["cancelled_messages"], 2);
+    assert!(body.get("warnings").is_none());
+
+    let registry = Registry::open(&db_path)?;
+    for id in &kept {
+        assert_eq!(registry.get_message(id)?.unwrap().status, "queued");
+    }
+    for id in &drained {
+        let msg = registry.get_message(id)?.unwrap();
+        assert_eq!(msg.status, "cancelled");
+        assert_eq!(
+            msg.dead_letter_reason.as_deref(),
+            Some("recipient_archived")
+        );
+        let events = registry.get_message_events(id)?;
+        assert!(events.iter().any(|e| e.event_type == "cancelled"));
+    }
+
+    // Unknown drain mode is rejected before anything happens
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/msg-source/archive?drain=bogus"
+        ))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn archive_nonexistent() -> Result<()> {
     let (_tmp, db_path) = setup_cp();
@@ -362,12 +541,15 @@ async fn archive_already_archived() -> Result<()> {
         .await?;
     assert_eq!(resp.status(), 200);
 
-    // Try to archive again (should 404 since it's no longer active)
+    // Archive again: idempotent no-op
     let resp = client
         .post(format!("{base_url}/api/instances/double-archive/archive"))
         .send()
         .await?;
-    assert_eq!(resp.status(), 404);
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["status"], "archived");
+    assert_eq!(body["already_archived"], true);
 
     Ok(())
 }
@@ -409,6 +591,30 @@ async fn unarchive_instance_basic() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn unarchive_already_active() -> Result<()> {
+    let (_tmp, db_path) = setup_with_instance("never-archived", 18801);
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{base_url}/api/instances/never-archived/unarchive"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["status"], "active");
+    assert_eq!(body["already_active"], true);
+
+    let resp = client
+        .post(format!("{base_url}/api/instances/nonexistent/unarchive"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn unarchive_name_conflict() -> Result<()> {
     let (_tmp, db_path) = setup_cp();
@@ -529,6 +735,132 @@ async fn clone_clears_auth() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn clone_reports_skill_manifest_and_skills_only_skips_config() -> Result<()> {
+    let (_tmp, db_path) = setup_with_instance("skilled", 18801);
+    let source = Registry::open(&db_path)?
+        .get_instance_by_name("skilled")?
+        .unwrap();
+    let skills_dir = PathBuf::from(source.workspace_dir.unwrap()).join("skills");
+    fs::create_dir_all(skills_dir.join("deploy"))?;
+    fs::write(skills_dir.join("deploy").join("SKILL.md"), "# Deploy")?;
+    fs::write(skills_dir.join("notes.md"), "notes")?;
+    // A dangling symlink cannot be copied
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(skills_dir.join("gone.md"), skills_dir.join("broken.md"))?;
+    let source_config = fs::read_to_string(&source.config_path)?;
+    fs::write(
+        &source.config_path,
+        format!("default_model = \"source-only\"\n{source_config}"),
+    )?;
+
+    let (base_url, _shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("{base_url}/api/instances/skilled/clone"))
+        .json(&serde_json::json!({ "new_name": "full-clone" }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(
+        body["manifest"]["skills_copied"],
+        serde_json::json!(["skills/deploy/SKILL.md", "skills/notes.md"])
+    );
+    #[cfg(unix)]
+    {
+        assert_eq!(
+            body["manifest"]["skills_failed"],
+            serde_json::json!(["skills/broken.md"])
+        );
+        let warnings = body["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].as_str().unwrap().contains("skills/broken.md"));
+    }
+
+    let resp = client
+        .post(format!("{base_url}/api/instances/skilled/clone"))
+        .json(&serde_json::json!({ "new_name": "skills-clone", "skills_only": true }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 201);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["skills_only"], true);
+    let copied = body["manifest"]["skills_copied"].as_array().unwrap();
+    assert_eq!(copied.len(), 2);
+
+    let clone = Registry::open(&db_path)?
+        .get_instance_by_name("skills-clone")?
+        .unwrap();
+    let clone_skills = PathBuf::from(clone.workspace_dir.unwrap()).join("skills");
+    assert_eq!(
+        fs::read_to_string(clone_skills.join("deploy").join("SKILL.md"))?,
+        "# Deploy"
+    );
+    let clone_config: zeroclaw::Config = toml::from_str(&fs::read_to_string(&clone.config_path)?)?;
+    assert_ne!(clone_config.default_model.as_deref(), Some("source-only"));
+    assert_eq!(clone_config.gateway.port, clone.port);
+
+    Ok(())
+}
+
+#[test]
+fn port_range_parsing_validates_bounds() {
+    use zeroclaw::cp::server::PortRange;
+
+    assert_eq!(PortRange::parse(None, None), Ok(PortRange::default()));
+    let range = PortRange::parse(Some("20000"), Some(" 20010 ")).unwrap();
+    assert_eq!((range.start, range.end), (20000, 20010));
+    assert_eq!(range.to_string(), "20000-20010");
+    assert!(PortRange::parse(Some("20000"), Some("20000")).is_ok());
+
+    let err = PortRange::parse(Some("20010"), Some("20000")).unwrap_err();
+    assert!(err.contains("must not exceed"), "{err}");
+    let err = PortRange::parse(Some("1024"), None).unwrap_err();
+    assert!(err.contains("above 1024"), "{err}");
+    assert!(PortRange::parse(None, Some("70000")).is_err());
+    assert!(PortRange::parse(Some("low"), None).is_err());
+}
+
+#[tokio::test]
+async fn disk_usage_breaks_down_workspace_and_logs() -> Result<()> {
+    let (_tmp, db_path) = setup_with_instance("disk-agent", 18850);
+    let instance = Registry::open(&db_path)?
+        .get_instance_by_name("disk-agent")?
+        .unwrap();
+    let workspace = PathBuf::from(instance.workspace_dir.as_deref().unwrap());
+    fs::write(workspace.join("memory/notes.md"), vec![b'x'; 2048])?;
+    fs::write(workspace.join("SOUL.md"), b"soul")?;
+    let log_path = zeroclaw::lifecycle::log_path(workspace.parent().unwrap());
+    fs::create_dir_all(log_path.parent().unwrap())?;
+    fs::write(&log_path, vec![b'l'; 100])?;
+
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{base_url}/api/instances/disk-agent/disk"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["total_bytes"], 2048 + 4 + 100);
+    assert_eq!(body["breakdown"]["memory"]["bytes"], 2048);
+    assert_eq!(body["breakdown"]["memory"]["human"], "2.0 KiB");
+    assert_eq!(body["breakdown"]["skills"]["bytes"], 0);
+    assert_eq!(body["breakdown"]["other"]["bytes"], 4);
+    assert_eq!(body["breakdown"]["logs"]["bytes"], 100);
+    assert_eq!(body["truncated"], false);
+
+    let resp = client
+        .get(format!("{base_url}/api/instances/nope/disk"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 5: Delete
 // ══════════════════════════════════════════════════════════════════
@@ -891,12 +1223,12 @@ async fn full_crud_lifecycle_flow() -> Result<()> {
     assert_eq!(list.len(), 1);
     assert_eq!(list[0]["name"], "lifecycle-test");
 
-    // 11. Unarchive on active instance returns 409 (active name conflict with itself)
+    // 11. Unarchive on active instance is an idempotent no-op
     let resp = client
         .post(format!("{base_url}/api/instances/lifecycle-test/unarchive"))
         .send()
         .await?;
-    assert_eq!(resp.status(), 409);
+    assert_eq!(resp.status(), 200);
 
     // 12. Archive original, then unarchive
     client
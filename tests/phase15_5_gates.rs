@@ -240,6 +240,7 @@ fn flow_store_timeout_detection() {
             timeout_secs: Some(0), // 0 = no timeout
             agent_handoff: false,
             transitions: vec![],
+            branch: None,
         },
     );
     let mut defs = HashMap::new();
@@ -250,6 +251,8 @@ fn flow_store_timeout_detection() {
             description: None,
             start_step: "ask".into(),
             default_timeout_secs: 0,
+            deadline_secs: None,
+            inputs: Vec::new(),
             steps,
         },
     );
@@ -277,6 +280,7 @@ fn flow_transition_exact_match() {
             TransitionDef { on: "yes".into(), target: "done".into() },
             TransitionDef { on: "no".into(), target: "cancel".into() },
         ],
+        branch: None,
     };
     let matched = step.transitions.iter().find(|t| t.on == "yes");
     assert!(matched.is_some());
@@ -300,6 +304,7 @@ fn flow_transition_any_match() {
         transitions: vec![
             TransitionDef { on: "_any".into(), target: "next".into() },
         ],
+        branch: None,
     };
     let callback_data = "anything_at_all";
     let matched = step
@@ -327,6 +332,7 @@ fn flow_transition_no_match() {
         transitions: vec![
             TransitionDef { on: "yes".into(), target: "done".into() },
         ],
+        branch: None,
     };
     let matched = step.transitions.iter().find(|t| t.on == "unknown_data");
     assert!(matched.is_none());
@@ -347,6 +353,7 @@ fn flow_terminal_step() {
         timeout_secs: None,
         agent_handoff: false,
         transitions: vec![],
+        branch: None,
     };
     assert!(terminal.is_terminal());
 
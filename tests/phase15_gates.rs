@@ -388,3 +388,65 @@ fn config_backward_compat() {
         "stt_endpoint should default to None"
     );
 }
+
+// ── Gate 19: Telegram reply keyboard JSON ───────────────────────
+
+#[test]
+fn telegram_reply_keyboard_json() {
+    use zeroclaw::channels::telegram::TelegramChannel;
+    use zeroclaw::channels::telegram_types::ReplyKeyboardOptions;
+
+    let buttons = vec![
+        vec!["Status".to_string(), "Logs".to_string()],
+        vec!["Help".to_string()],
+    ];
+
+    // Defaults: every option off
+    let json = TelegramChannel::build_reply_keyboard_json(
+        "12345",
+        "Menu:",
+        &buttons,
+        &ReplyKeyboardOptions::default(),
+    );
+    assert_eq!(json["chat_id"], "12345");
+    assert_eq!(json["text"], "Menu:");
+    let markup = &json["reply_markup"];
+    assert!(markup.get("inline_keyboard").is_none());
+    assert_eq!(markup["one_time_keyboard"], false);
+    assert_eq!(markup["resize_keyboard"], false);
+    assert_eq!(markup["is_persistent"], false);
+    assert!(markup.get("input_field_placeholder").is_none());
+    let rows = markup["keyboard"].as_array().unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0], serde_json::json!([{"text": "Status"}, {"text": "Logs"}]));
+    assert_eq!(rows[1], serde_json::json!([{"text": "Help"}]));
+
+    // Options are passed through
+    let options = ReplyKeyboardOptions {
+        one_time_keyboard: true,
+        resize_keyboard: true,
+        is_persistent: true,
+        input_field_placeholder: Some("Pick one".into()),
+    };
+    let json = TelegramChannel::build_reply_keyboard_json("12345", "Menu:", &buttons, &options);
+    let markup = &json["reply_markup"];
+    assert_eq!(markup["one_time_keyboard"], true);
+    assert_eq!(markup["resize_keyboard"], true);
+    assert_eq!(markup["is_persistent"], true);
+    assert_eq!(markup["input_field_placeholder"], "Pick one");
+}
+
+// ── Gate 20: Telegram remove keyboard JSON ──────────────────────
+
+#[test]
+fn telegram_remove_keyboard_json() {
+    use zeroclaw::channels::telegram::TelegramChannel;
+
+    let json = TelegramChannel::build_remove_keyboard_json("12345", "Done.");
+    assert_eq!(json["chat_id"], "12345");
+    assert_eq!(json["text"], "Done.");
+    assert_eq!(
+        json["reply_markup"],
+        serde_json::json!({ "remove_keyboard": true })
+    );
+}
@@ -1,7 +1,6 @@
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
 use zeroclaw::db::Registry;
@@ -44,9 +43,7 @@ fn setup_instance(
 
 /// Helper: start an in-process axum server on a random port.
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    let state = cp::server::CpState::new(db_path);
     let app = cp::server::build_router(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -299,3 +296,62 @@ async fn telegram_health_windows() -> Result<()> {
     let _ = shutdown.send(true);
     Ok(())
 }
+
+// ══════════════════════════════════════════════════════════════════
+// Gate 9: Dry-run flow validation reports per-file errors
+// ══════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn flows_validate_reports_per_file() -> Result<()> {
+    let (tmp, db_path, _id, _inst_dir) =
+        setup_instance("flow-validate", 19007, MINIMAL_CONFIG, false);
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let good = "[flow]\nname = \"greet\"\nstart = \"hello\"\n\n[[steps]]\nid = \"hello\"\nkind = \"message\"\ntext = \"Hi\"\n";
+    let resp = client
+        .post(format!("{base_url}/api/flows/validate"))
+        .json(&serde_json::json!({
+            "files": {
+                "a.toml": good,
+                "b.toml": good,
+                "c.toml": "[flow]\nname = \"broken\"\nstart = \"nowhere\"\n",
+            }
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["valid"], false);
+    assert_eq!(body["flows"], serde_json::json!(["greet"]));
+    let files = body["files"].as_array().unwrap();
+    assert_eq!(files[0]["valid"], true);
+    assert_eq!(files[1]["errors"][0], "duplicate flow name 'greet'");
+    assert_eq!(files[2]["flow_name"], "broken");
+    assert_eq!(files[2]["valid"], false);
+
+    // Same checks against a directory on disk
+    let flows_dir = tmp.path().join("flows");
+    fs::create_dir_all(&flows_dir)?;
+    fs::write(flows_dir.join("greet.toml"), good)?;
+    let resp = client
+        .post(format!("{base_url}/api/flows/validate"))
+        .json(&serde_json::json!({ "path": flows_dir }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["valid"], true);
+    assert_eq!(body["files"][0]["file"], "greet.toml");
+
+    let resp = client
+        .post(format!("{base_url}/api/flows/validate"))
+        .json(&serde_json::json!({}))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
@@ -35,6 +35,7 @@ fn make_flow_def() -> FlowDefinition {
                     target: "timeout_step".into(),
                 },
             ],
+            branch: None,
         },
     );
     steps.insert(
@@ -49,6 +50,7 @@ fn make_flow_def() -> FlowDefinition {
             timeout_secs: None,
             agent_handoff: false,
             transitions: vec![],
+            branch: None,
         },
     );
     steps.insert(
@@ -63,6 +65,7 @@ fn make_flow_def() -> FlowDefinition {
             timeout_secs: None,
             agent_handoff: false,
             transitions: vec![],
+            branch: None,
         },
     );
     FlowDefinition {
@@ -70,6 +73,8 @@ fn make_flow_def() -> FlowDefinition {
         description: None,
         start_step: "ask".into(),
         default_timeout_secs: 120,
+        deadline_secs: None,
+        inputs: Vec::new(),
         steps,
     }
 }
@@ -103,6 +108,7 @@ fn flow_db_upsert_and_get() {
         step_entered_at: "2026-01-01T00:00:00Z".into(),
         anchor_message_id: Some(42),
         status: "active".into(),
+        vars: "{}".into(),
     };
     db.upsert_active(&row).unwrap();
     let got = db.get_active("chat1").unwrap().unwrap();
@@ -123,6 +129,7 @@ fn flow_db_update_step() {
         step_entered_at: "2026-01-01T00:00:00Z".into(),
         anchor_message_id: None,
         status: "active".into(),
+        vars: "{}".into(),
     };
     db.upsert_active(&row).unwrap();
 
@@ -149,6 +156,7 @@ fn flow_db_complete_moves_to_history() {
         step_entered_at: "2026-01-01T00:00:00Z".into(),
         anchor_message_id: Some(10),
         status: "active".into(),
+        vars: "{}".into(),
     };
     db.upsert_active(&row).unwrap();
 
@@ -176,6 +184,7 @@ fn flow_db_list_active() {
             step_entered_at: "2026-01-01T00:00:00Z".into(),
             anchor_message_id: None,
             status: "active".into(),
+            vars: "{}".into(),
         };
         db.upsert_active(&row).unwrap();
     }
@@ -200,6 +209,7 @@ fn flow_db_history_filters() {
             step_entered_at: "2026-01-01T00:00:00Z".into(),
             anchor_message_id: None,
             status: "active".into(),
+            vars: "{}".into(),
         };
         db.upsert_active(&row).unwrap();
         db.complete_flow(&row.chat_id, status).unwrap();
@@ -296,6 +306,7 @@ fn durable_load_from_db() {
         step_entered_at: "2026-01-01T00:00:00+00:00".into(),
         anchor_message_id: Some(10),
         status: "active".into(),
+        vars: "{}".into(),
     })
     .unwrap();
     db.upsert_active(&FlowInstanceRow {
@@ -306,6 +317,7 @@ fn durable_load_from_db() {
         step_entered_at: "2026-01-01T00:00:00+00:00".into(),
         anchor_message_id: None,
         status: "active".into(),
+        vars: "{}".into(),
     })
     .unwrap();
 
@@ -382,6 +394,7 @@ fn expired_during_downtime() {
         step_entered_at: past,
         anchor_message_id: None,
         status: "active".into(),
+        vars: "{}".into(),
     })
     .unwrap();
 
@@ -510,6 +523,7 @@ fn force_complete_sets_status() {
         step_entered_at: "2026-01-01T00:00:00Z".into(),
         anchor_message_id: None,
         status: "active".into(),
+        vars: "{}".into(),
     };
     db.upsert_active(&row).unwrap();
     db.complete_flow("chat1", "force_completed").unwrap();
@@ -531,6 +545,7 @@ fn replay_resets_step() {
         step_entered_at: "2026-01-01T00:00:00Z".into(),
         anchor_message_id: Some(42),
         status: "active".into(),
+        vars: "{}".into(),
     };
     db.upsert_active(&row).unwrap();
 
@@ -561,6 +576,7 @@ fn history_preserves_all_statuses() {
             step_entered_at: "2026-01-01T00:00:00Z".into(),
             anchor_message_id: None,
             status: "active".into(),
+            vars: "{}".into(),
         };
         db.upsert_active(&row).unwrap();
         db.complete_flow(chat, status).unwrap();
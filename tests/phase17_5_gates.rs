@@ -5,7 +5,6 @@
 // and integration tests for approve-deactivates-previous + full lifecycle (2 gates).
 
 use std::path::PathBuf;
-use std::sync::Arc;
 
 use axum::body::Body;
 use axum::http::{Request, StatusCode};
@@ -41,9 +40,7 @@ fn setup_test_env() -> (CpState, PathBuf) {
     let state_db_path = workspace.join("state.db");
     let _flow_db = FlowDb::open(&state_db_path).unwrap();
 
-    let state = CpState {
-        db_path: Arc::new(registry_path),
-    };
+    let state = CpState::new(registry_path);
 
     // Leak the tempdir so it doesn't get cleaned up while the test runs
     std::mem::forget(tmp);
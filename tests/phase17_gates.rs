@@ -22,6 +22,8 @@ fn make_toml_def(name: &str, steps: Vec<StepToml>) -> FlowDefinitionToml {
             description: Some("test flow".into()),
             start: steps.first().map(|s| s.id.clone()).unwrap_or_default(),
             default_timeout_secs: 60,
+            deadline_secs: None,
+            inputs: Vec::new(),
         },
         steps,
     }
@@ -38,6 +40,10 @@ fn message_step(id: &str, text: &str) -> StepToml {
         timeout_secs: None,
         agent_handoff: false,
         transitions: vec![],
+        condition: None,
+        then: None,
+        otherwise: None,
+        duration_secs: None,
     }
 }
 
@@ -58,6 +64,10 @@ fn keyboard_step(id: &str, text: &str, handoff: bool) -> StepToml {
             on: "ok".into(),
             target: "end".into(),
         }],
+        condition: None,
+        then: None,
+        otherwise: None,
+        duration_secs: None,
     }
 }
 
@@ -88,6 +98,7 @@ fn make_flow_def(name: &str) -> FlowDefinition {
             timeout_secs: None,
             agent_handoff: false,
             transitions: vec![],
+            branch: None,
         },
     );
     FlowDefinition {
@@ -95,6 +106,8 @@ fn make_flow_def(name: &str) -> FlowDefinition {
         description: Some("test".into()),
         start_step: "s1".into(),
         default_timeout_secs: 60,
+        deadline_secs: None,
+        inputs: Vec::new(),
         steps,
     }
 }
@@ -1,6 +1,5 @@
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
 use zeroclaw::db::Registry;
@@ -31,9 +30,7 @@ fn setup_instance(name: &str, port: u16, config_toml: &str) -> (TempDir, PathBuf
 
 /// Helper: start an in-process axum server on a random port.
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    let state = cp::server::CpState::new(db_path);
     let app = cp::server::build_router(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -492,6 +489,175 @@ async fn patch_nested_optional_path_validated() {
     assert_eq!(resp.status(), 200);
 }
 
+// ── Gate 9: RFC 7386 merge patch with If-Match ──────────────────
+
+#[tokio::test]
+async fn merge_patch_content_type_applies_nested_fields() {
+    let (_tmp, db_path, _id, dir) = setup_instance("gate9", 19013, &config_with_secrets());
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let url = format!("{base_url}/api/instances/gate9/config");
+    let merge_patch = |body: serde_json::Value, etag: &str| {
+        client
+            .patch(&url)
+            .header("Content-Type", "application/merge-patch+json")
+            .header("If-Match", format!("\"{etag}\""))
+            .body(body.to_string())
+            .send()
+    };
+
+    // Nested fields at two depths; siblings and secrets are left alone
+    let etag = get_etag(&base_url, "gate9").await;
+    let resp = merge_patch(
+        serde_json::json!({
+            "heartbeat": { "interval_minutes": 45 },
+            "channels_config": { "telegram": { "flow_policy": { "max_steps": 20 } } }
+        }),
+        &etag,
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_ne!(body["etag"].as_str().unwrap(), etag);
+
+    let on_disk = fs::read_to_string(dir.join("config.toml")).unwrap();
+    let parsed: toml::Value = toml::from_str(&on_disk).unwrap();
+    assert_eq!(
+        parsed["heartbeat"]["interval_minutes"].as_integer(),
+        Some(45)
+    );
+    assert_eq!(parsed["heartbeat"]["enabled"].as_bool(), Some(false));
+    let telegram = &parsed["channels_config"]["telegram"];
+    assert_eq!(telegram["flow_policy"]["max_steps"].as_integer(), Some(20));
+    assert_eq!(
+        telegram["flow_policy"]["max_agent_flows"].as_integer(),
+        Some(50)
+    );
+    assert_eq!(
+        telegram["bot_token"].as_str(),
+        Some("8256947227:AAEqtest_secret_token")
+    );
+    assert_eq!(parsed["model_routes"].as_array().unwrap().len(), 2);
+
+    // null removes a nested optional key
+    let etag = get_etag(&base_url, "gate9").await;
+    let resp = merge_patch(
+        serde_json::json!({ "browser": { "session_name": "s1" } }),
+        &etag,
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 200);
+    let etag = get_etag(&base_url, "gate9").await;
+    let resp = merge_patch(
+        serde_json::json!({ "browser": { "session_name": null } }),
+        &etag,
+    )
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 200);
+    let on_disk = fs::read_to_string(dir.join("config.toml")).unwrap();
+    assert!(!on_disk.contains("session_name"));
+
+    // The stale ETag is rejected; so is a merge patch without If-Match
+    let resp = merge_patch(serde_json::json!({ "default_temperature": 0.1 }), &etag)
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+    let resp = client
+        .patch(&url)
+        .header("Content-Type", "application/merge-patch+json")
+        .body(r#"{"default_temperature": 0.1}"#)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("ETag"));
+}
+
+// ── Config writes are audited ───────────────────────────────────
+
+#[tokio::test]
+async fn config_writes_record_audit_entries() {
+    let (_tmp, db_path, _id, _dir) = setup_instance("audited", 19014, &config_with_secrets());
+    let (base_url, _shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let url = format!("{base_url}/api/instances/audited/config");
+
+    let first_etag = get_etag(&base_url, "audited").await;
+    let resp = client
+        .patch(&url)
+        .header("X-ZeroClaw-Actor", "alice")
+        .json(&serde_json::json!({
+            "patch": { "default_temperature": 0.9, "heartbeat": { "interval_minutes": 45 } },
+            "etag": first_etag,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let second_etag = resp.json::<serde_json::Value>().await.unwrap()["etag"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let resp = client
+        .patch(&url)
+        .json(&serde_json::json!({
+            "patch": { "default_temperature": 0.4 },
+            "etag": second_etag,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    // A rejected write leaves no entry
+    let resp = client
+        .patch(&url)
+        .json(&serde_json::json!({
+            "patch": { "default_temperature": 0.1 },
+            "etag": first_etag,
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 409);
+
+    let body: serde_json::Value = client
+        .get(format!("{url}/audit"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["total"], 2);
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["actor"], "operator");
+    assert_eq!(entries[0]["old_etag"], second_etag.as_str());
+    assert_eq!(
+        entries[0]["changed_paths"],
+        serde_json::json!(["default_temperature"])
+    );
+    assert_eq!(entries[1]["actor"], "alice");
+    assert_eq!(entries[1]["old_etag"], first_etag.as_str());
+    assert_eq!(entries[1]["new_etag"], second_etag.as_str());
+    assert_eq!(
+        entries[1]["changed_paths"],
+        serde_json::json!(["default_temperature", "heartbeat.interval_minutes"])
+    );
+
+    let resp = client
+        .get(format!("{base_url}/api/instances/nope/config/audit"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
 // ── Bonus: GET includes fingerprints and secret_paths ───────────
 
 #[tokio::test]
@@ -1,6 +1,5 @@
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
 use zeroclaw::db::Registry;
@@ -30,9 +29,7 @@ fn setup_instance(name: &str, port: u16, config_toml: &str) -> (TempDir, PathBuf
 }
 
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    let state = cp::server::CpState::new(db_path);
     let app = cp::server::build_router(state);
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
@@ -343,3 +340,66 @@ fn array_secret_detection() {
     let secrets_d = collect_secret_writes(&patch_d);
     assert!(secrets_d.is_empty(), "non-secret array should not trigger");
 }
+
+// ── UI Gate 9: UI override path and disabled mode ───────────────
+
+async fn get_ui(ui: cp::server::UiMode, uri: &str) -> (u16, String) {
+    use tower::ServiceExt;
+    let tmp = TempDir::new().unwrap();
+    let state = cp::server::CpState::new(tmp.path().join("registry.db"));
+    let app = cp::server::build_router_with_ui(state, ui);
+    let resp = app
+        .oneshot(
+            axum::http::Request::builder()
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = resp.status().as_u16();
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, String::from_utf8_lossy(&body).into_owned())
+}
+
+#[tokio::test]
+async fn ui_override_path_and_disabled_mode() {
+    let tmp = TempDir::new().unwrap();
+    fs::write(tmp.path().join("index.html"), "<html>custom</html>").unwrap();
+
+    // Directory containing index.html, served for / and SPA paths
+    let ui = cp::server::UiMode::Path(tmp.path().to_path_buf());
+    assert_eq!(get_ui(ui.clone(), "/").await, (200, "<html>custom</html>".into()));
+    assert_eq!(get_ui(ui, "/instances/x").await.1, "<html>custom</html>");
+
+    // Files under the directory are served by path; paths naming no file
+    // (or climbing out of it) get index.html
+    fs::create_dir(tmp.path().join("assets")).unwrap();
+    fs::write(tmp.path().join("assets/app.js"), "console.log(1)").unwrap();
+    let ui = cp::server::UiMode::Path(tmp.path().to_path_buf());
+    assert_eq!(
+        get_ui(ui.clone(), "/assets/app.js").await,
+        (200, "console.log(1)".into())
+    );
+    assert_eq!(
+        get_ui(ui.clone(), "/assets/missing.js").await.1,
+        "<html>custom</html>"
+    );
+    assert_eq!(get_ui(ui, "/../index.html").await.1, "<html>custom</html>");
+
+    // Unreadable path falls back to the embedded UI
+    let missing = cp::server::UiMode::Path(tmp.path().join("missing.html"));
+    let (status, body) = get_ui(missing, "/").await;
+    assert_eq!(status, 200);
+    assert_ne!(body, "<html>custom</html>");
+    assert!(!body.is_empty());
+
+    // Disabled: 404 on / and non-API paths, API routes unaffected
+    assert_eq!(get_ui(cp::server::UiMode::Disabled, "/").await.0, 404);
+    assert_eq!(get_ui(cp::server::UiMode::Disabled, "/instances/x").await.0, 404);
+    let (status, body) = get_ui(cp::server::UiMode::Disabled, "/api/nope").await;
+    assert_eq!(status, 404);
+    assert!(body.contains("Unknown API endpoint"));
+}
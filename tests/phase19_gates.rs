@@ -508,9 +508,13 @@ fn gate21_admin_users_must_be_allowed() {
             telegram: Some(zeroclaw::config::TelegramConfig {
                 bot_token: "test:token".into(),
                 allowed_users: vec!["user1".into()], // admin_not_in_allowed is NOT here
+                usernames_case_sensitive: false,
                 stt_endpoint: None,
                 flows_enabled: false,
                 flow_policy: Default::default(),
+                auto_download: Default::default(),
+                placeholder_replies: false,
+                markdown_v2: false,
             }),
             ..zeroclaw::config::ChannelsConfig::default()
         },
@@ -543,9 +547,13 @@ fn gate22_wildcard_allowed_skips_admin_check() {
             telegram: Some(zeroclaw::config::TelegramConfig {
                 bot_token: "test:token".into(),
                 allowed_users: vec!["*".into()], // wildcard
+                usernames_case_sensitive: false,
                 stt_endpoint: None,
                 flows_enabled: false,
                 flow_policy: Default::default(),
+                auto_download: Default::default(),
+                placeholder_replies: false,
+                markdown_v2: false,
             }),
             ..zeroclaw::config::ChannelsConfig::default()
         },
@@ -5,6 +5,9 @@ use tempfile::TempDir;
 use zeroclaw::db::Registry;
 use zeroclaw::lifecycle;
 
+/// Serializes tests that point `ZEROCLAW_BIN` at a fake binary.
+static ZEROCLAW_BIN_ENV: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 /// Helper: create a registry with a registered instance in a temp dir.
 /// Returns (TempDir, Registry, instance_id, instance_dir).
 fn setup_instance(name: &str, port: u16) -> (TempDir, Registry, String, std::path::PathBuf) {
@@ -392,9 +395,11 @@ fn gate13_rollback_kills_child_on_write_pid_failure() {
     // logs/ subdir is still writable, but daemon.pid creation will fail (EACCES).
     fs::set_permissions(&inst_dir, fs::Permissions::from_mode(0o555)).unwrap();
 
+    let env_guard = ZEROCLAW_BIN_ENV.lock().unwrap_or_else(|e| e.into_inner());
     std::env::set_var("ZEROCLAW_BIN", fake_bin.to_str().unwrap());
     let result = lifecycle::start_instance(&registry, "rollback-test");
     std::env::remove_var("ZEROCLAW_BIN");
+    drop(env_guard);
 
     // Restore permissions so TempDir cleanup works
     fs::set_permissions(&inst_dir, fs::Permissions::from_mode(0o755)).unwrap();
@@ -455,6 +460,45 @@ fn gate13_rollback_kills_child_on_write_pid_failure() {
     );
 }
 
+// ── Gate 14: Fast-fail start surfaces daemon output ────────────
+
+#[test]
+fn gate14_start_failure_includes_log_tail() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (tmp, registry, id, inst_dir) = setup_instance("fastfail", 18950);
+
+    // Fake daemon that complains on stderr and exits immediately
+    let fake_bin = tmp.path().join("fake-zeroclaw");
+    fs::write(
+        &fake_bin,
+        "#!/bin/sh\necho 'starting up'\necho 'Error: address already in use (port 18950)' >&2\nexit 3\n",
+    )
+    .unwrap();
+    fs::set_permissions(&fake_bin, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let env_guard = ZEROCLAW_BIN_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    std::env::set_var("ZEROCLAW_BIN", fake_bin.to_str().unwrap());
+    let result = lifecycle::start_instance(&registry, "fastfail");
+    std::env::remove_var("ZEROCLAW_BIN");
+    drop(env_guard);
+
+    match result {
+        Err(lifecycle::LifecycleError::StartFailed { log_tail, .. }) => {
+            assert!(
+                log_tail.iter().any(|l| l.contains("address already in use")),
+                "log tail should include stderr, got: {log_tail:?}"
+            );
+            assert!(log_tail.iter().any(|l| l == "starting up"));
+        }
+        other => panic!("expected StartFailed, got: {other:?}"),
+    }
+
+    // Nothing recorded as running
+    assert!(!inst_dir.join("daemon.pid").exists());
+    assert_eq!(registry.get_instance(&id).unwrap().unwrap().status, "stopped");
+}
+
 // ── Gate 12: Runtime start/status/stop (requires binary) ───────
 
 #[test]
@@ -522,6 +566,7 @@ require_pairing = true
     // Verify DB status
     let inst = registry.get_instance(&id).unwrap().unwrap();
     assert_eq!(inst.status, "running");
+    assert!(inst.started_at.is_some(), "start should record started_at");
 
     // Status should show running
     lifecycle::show_status(&registry, Some("runtime-test")).unwrap();
@@ -1,7 +1,6 @@
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
 use zeroclaw::db::{AgentEvent, AgentUsageRecord, Registry};
@@ -33,9 +32,13 @@ fn setup_instance(name: &str, port: u16, config_toml: &str) -> (TempDir, PathBuf
 
 /// Helper: start an in-process axum server on a random port.
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    start_test_server_with_state(cp::server::CpState::new(db_path)).await
+}
+
+/// Helper: [`start_test_server`] with explicitly configured state.
+async fn start_test_server_with_state(
+    state: cp::server::CpState,
+) -> (String, tokio::sync::watch::Sender<bool>) {
     let app = cp::server::build_router(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -286,6 +289,75 @@ async fn gate1_details_missing_config_reports_error() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate1_config_drift_reported_in_list_and_details() -> Result<()> {
+    // Hand-edited gateway.port no longer matches the registry
+    let (_tmp, db_path, id, inst_dir) =
+        setup_instance("drifted", 18967, "[gateway]\nport = 18968\n");
+    let registry = Registry::open(&db_path)?;
+    registry.create_instance(
+        &uuid::Uuid::new_v4().to_string(),
+        "unreadable",
+        18969,
+        inst_dir.join("missing.toml").to_str().unwrap(),
+        None,
+        None,
+    )?;
+    // Workspace recorded somewhere other than beside the config
+    let moved_dir = inst_dir.join("moved");
+    fs::create_dir_all(&moved_dir)?;
+    let moved_config = moved_dir.join("config.toml");
+    fs::write(&moved_config, "[gateway]\nport = 18970\n")?;
+    registry.create_instance(
+        &uuid::Uuid::new_v4().to_string(),
+        "moved",
+        18970,
+        moved_config.to_str().unwrap(),
+        Some("/elsewhere/workspace"),
+        None,
+    )?;
+    drop(registry);
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .get(format!("{base_url}/api/instances/drifted/details"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    let drift = body["instance"]["config_drift"].as_array().unwrap();
+    assert_eq!(drift.len(), 1, "only the port drifted: {drift:?}");
+    assert_eq!(drift[0]["field"], "port");
+    assert_eq!(drift[0]["registry"], 18967);
+    assert_eq!(drift[0]["config"], 18968);
+
+    let list: Vec<serde_json::Value> = client
+        .get(format!("{base_url}/api/instances"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let entry = |name: &str| list.iter().find(|i| i["name"] == name).unwrap().clone();
+    assert_eq!(entry("drifted")["id"], id);
+    assert_eq!(entry("drifted")["config_drift"][0]["field"], "port");
+    // Unreadable config: drift is unknown, not empty
+    assert!(entry("unreadable")["config_drift"].is_null());
+    let moved = entry("moved");
+    let drift = moved["config_drift"].as_array().unwrap();
+    assert_eq!(drift.len(), 1, "only the workspace drifted: {drift:?}");
+    assert_eq!(drift[0]["field"], "workspace_dir");
+    assert_eq!(drift[0]["registry"], "/elsewhere/workspace");
+    assert_eq!(
+        drift[0]["config"],
+        moved_dir.join("workspace").display().to_string()
+    );
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 2: Tasks with stable ordering
 // ══════════════════════════════════════════════════════════════════
@@ -398,6 +470,218 @@ async fn gate2_tasks_invalid_params() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate2_tasks_invalid_filters() -> Result<()> {
+    let (_tmp, db_path, _id, _inst_dir) =
+        setup_instance("task-bad-filter", 18980, "default_temperature = 0.7\n");
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    // unknown channel, malformed event_type
+    for query in [
+        "channel=carrier-pigeon",
+        "event_type=tg%20inbound",
+        "event_type=.*",
+    ] {
+        let resp = client
+            .get(format!(
+                "{base_url}/api/instances/task-bad-filter/tasks?{query}"
+            ))
+            .send()
+            .await?;
+        assert_eq!(resp.status(), 400, "{query} should be rejected");
+    }
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate2_tasks_filter_by_event_type_and_channel() -> Result<()> {
+    let (_tmp, db_path, id, _inst_dir) =
+        setup_instance("task-filter", 18971, "default_temperature = 0.7\n");
+
+    let registry = Registry::open(&db_path)?;
+    let events = [
+        ("tg.inbound.text", "telegram", "completed"),
+        ("tg.inbound.voice", "telegram", "completed"),
+        ("tg.stt.error", "telegram", "failed"),
+        ("tool_call", "cli", "completed"),
+    ];
+    for (i, (event_type, channel, status)) in events.iter().enumerate() {
+        registry.insert_agent_event(&AgentEvent {
+            id: format!("evt-{i}"),
+            instance_id: id.clone(),
+            event_type: (*event_type).to_string(),
+            channel: Some((*channel).to_string()),
+            summary: None,
+            status: (*status).to_string(),
+            duration_ms: None,
+            correlation_id: None,
+            metadata: None,
+            created_at: format!("2026-01-01 00:00:0{i}"),
+        })?;
+    }
+    drop(registry);
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let event_types = |query: &'static str| {
+        let client = client.clone();
+        let url = format!("{base_url}/api/instances/task-filter/tasks?{query}");
+        async move {
+            let body: serde_json::Value = client.get(url).send().await?.json().await?;
+            let types: Vec<String> = body["tasks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["event_type"].as_str().unwrap().to_string())
+                .collect();
+            assert_eq!(body["total"], types.len());
+            anyhow::Ok(types)
+        }
+    };
+
+    assert_eq!(
+        event_types("channel=telegram&event_type=tg.inbound.*").await?,
+        ["tg.inbound.voice", "tg.inbound.text"]
+    );
+    assert_eq!(
+        event_types("event_type=tg.stt.error&status=failed").await?,
+        ["tg.stt.error"]
+    );
+    assert_eq!(event_types("channel=cli").await?, ["tool_call"]);
+    assert!(event_types("channel=discord").await?.is_empty());
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate2_tasks_pagination_metadata_at_boundaries() -> Result<()> {
+    let (_tmp, db_path, id, _inst_dir) =
+        setup_instance("task-pages", 18977, "default_temperature = 0.7\n");
+
+    let registry = Registry::open(&db_path)?;
+    for i in 0..4 {
+        registry.insert_agent_event(&AgentEvent {
+            id: format!("evt-{i}"),
+            instance_id: id.clone(),
+            event_type: "tool_call".to_string(),
+            channel: Some("cli".to_string()),
+            summary: None,
+            status: "completed".to_string(),
+            duration_ms: None,
+            correlation_id: None,
+            metadata: None,
+            created_at: format!("2026-01-01 00:00:0{i}"),
+        })?;
+    }
+    drop(registry);
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let page = |query: &'static str| {
+        let client = client.clone();
+        let url = format!("{base_url}/api/instances/task-pages/tasks?{query}");
+        async move {
+            let body: serde_json::Value = client.get(url).send().await?.json().await?;
+            anyhow::Ok(body)
+        }
+    };
+
+    let first = page("limit=3").await?;
+    assert_eq!(first["total"], 4);
+    assert_eq!(first["has_more"], true);
+    assert_eq!(first["next_offset"], 3);
+    assert!(first["prev_offset"].is_null());
+
+    let last = page("limit=3&offset=3").await?;
+    assert_eq!(last["tasks"].as_array().unwrap().len(), 1);
+    assert_eq!(last["has_more"], false);
+    assert!(last["next_offset"].is_null());
+    assert_eq!(last["prev_offset"], 0);
+
+    // offset + limit == total is the last page
+    let exact = page("limit=2&offset=2").await?;
+    assert_eq!(exact["has_more"], false);
+    assert!(exact["next_offset"].is_null());
+    assert_eq!(exact["prev_offset"], 0);
+    let all = page("limit=4").await?;
+    assert_eq!(all["has_more"], false);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate2_tasks_and_usage_report_history_of_reused_name() -> Result<()> {
+    let (_tmp, db_path, old_id, _inst_dir) =
+        setup_instance("task-reused", 18973, "default_temperature = 0.7\n");
+
+    // Events recorded by the first instance, which is then archived and its
+    // name taken by a new instance with a new ID
+    let registry = Registry::open(&db_path)?;
+    for i in 1..=2 {
+        registry.insert_agent_event(&AgentEvent {
+            id: format!("old-{i}"),
+            instance_id: old_id.clone(),
+            event_type: "tool_call".to_string(),
+            channel: Some("cli".to_string()),
+            summary: Some(format!("Old event {i}")),
+            status: "completed".to_string(),
+            duration_ms: None,
+            correlation_id: None,
+            metadata: None,
+            created_at: format!("2026-01-01 00:00:0{i}"),
+        })?;
+    }
+    registry.archive_instance(&old_id)?;
+    registry.create_instance("new-id", "task-reused", 18974, "/c.toml", None, None)?;
+    drop(registry);
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let body: serde_json::Value = client
+        .get(format!("{base_url}/api/instances/task-reused/tasks"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["total"], 0);
+    assert_eq!(body["archived_instance_ids"], serde_json::json!([old_id]));
+    assert!(body["note"]
+        .as_str()
+        .unwrap()
+        .contains("include_archived=true"));
+
+    let body: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/task-reused/tasks?include_archived=true"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["tasks"][0]["instance_id"], old_id.as_str());
+    assert!(body.get("note").is_none());
+
+    let body: serde_json::Value = client
+        .get(format!("{base_url}/api/instances/task-reused/usage"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["archived_instance_ids"], serde_json::json!([old_id]));
+    assert!(body["note"].is_string());
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 3: Usage with unknown-data markers
 // ══════════════════════════════════════════════════════════════════
@@ -554,6 +838,68 @@ async fn gate4_logs_tail_mode() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate4_logs_json_format_parses_mixed_lines() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) =
+        setup_instance("log-json", 18975, "default_temperature = 0.7\n");
+
+    let log_dir = inst_dir.join("logs");
+    fs::create_dir_all(&log_dir)?;
+    fs::write(
+        log_dir.join("daemon.log"),
+        concat!(
+            r#"{"timestamp":"2026-01-01T00:00:00Z","level":"INFO","fields":{"message":"gateway up","port":8080},"target":"zeroclaw::gateway"}"#,
+            "\n",
+            "thread 'main' panicked at src/main.rs:1:1\n",
+            r#"{"timestamp":"2026-01-01T00:00:01Z","level":"WARN","fields":{"message":"slow reply"},"target":"zeroclaw::agent"}"#,
+            "\n",
+        ),
+    )?;
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let body: serde_json::Value = client
+        .get(format!(
+            "{base_url}/api/instances/log-json/logs?lines=10&format=json"
+        ))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["format"], "json");
+    let lines = body["lines"].as_array().unwrap();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["level"], "INFO");
+    assert_eq!(lines[0]["message"], "gateway up");
+    assert_eq!(lines[0]["target"], "zeroclaw::gateway");
+    assert_eq!(lines[0]["fields"], serde_json::json!({"port": 8080}));
+    assert_eq!(
+        lines[1],
+        serde_json::json!({"raw": "thread 'main' panicked at src/main.rs:1:1"})
+    );
+    assert_eq!(lines[2]["level"], "WARN");
+
+    // Plain lines stay the default
+    let body: serde_json::Value = client
+        .get(format!("{base_url}/api/instances/log-json/logs?lines=10"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["format"], "text");
+    assert!(body["lines"][0].as_str().unwrap().starts_with('{'));
+
+    let resp = client
+        .get(format!("{base_url}/api/instances/log-json/logs?format=xml"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 #[tokio::test]
 async fn gate4_logs_page_mode() -> Result<()> {
     let (_tmp, db_path, _id, inst_dir) =
@@ -593,6 +939,137 @@ async fn gate4_logs_page_mode() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate4_logs_head_mode() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) =
+        setup_instance("log-head", 18972, "default_temperature = 0.7\n");
+
+    let log_dir = inst_dir.join("logs");
+    fs::create_dir_all(&log_dir)?;
+    let content: String = (1..=20).map(|i| format!("line {i}\r\n")).collect();
+    fs::write(log_dir.join("daemon.log"), content)?;
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let get = |query: &str| {
+        client
+            .get(format!("{base_url}/api/instances/log-head/logs?{query}"))
+            .send()
+    };
+
+    // Start of the file by default
+    let body: serde_json::Value = get("mode=head&lines=3").await?.json().await?;
+    assert_eq!(body["mode"], "head");
+    assert_eq!(body["lines"], serde_json::json!(["line 1", "line 2", "line 3"]));
+    assert_eq!(body["total_lines"], 20);
+    assert_eq!(body["total_unknown"], false);
+    assert_eq!(body["has_more"], true);
+    // Effective limits are reported so clients know the cap
+    assert_eq!(body["limits"]["max_lines"], 10_000);
+    assert_eq!(body["limits"]["tail_bytes"], 4 * 1024 * 1024);
+
+    // Absolute position, running off the end
+    let body: serde_json::Value = get("mode=head&from_line=18&lines=5").await?.json().await?;
+    assert_eq!(body["from_line"], 18);
+    assert_eq!(body["lines"], serde_json::json!(["line 19", "line 20"]));
+    assert_eq!(body["total_lines"], 20);
+    assert_eq!(body["has_more"], false);
+
+    // from_line is rejected outside head mode
+    assert_eq!(get("mode=tail&from_line=3").await?.status(), 400);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate4_logs_configured_limits() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) =
+        setup_instance("log-limits", 18981, "default_temperature = 0.7\n");
+
+    let log_dir = inst_dir.join("logs");
+    fs::create_dir_all(&log_dir)?;
+    let content: String = (1..=20).map(|i| format!("line {i}\n")).collect();
+    fs::write(log_dir.join("daemon.log"), content)?;
+
+    // A configured line cap applies; a tail window past the ceiling is clamped
+    let mut state = cp::server::CpState::new(db_path);
+    state.log_limits = cp::server::LogLimits::new(Some(5), Some(u64::MAX));
+    let (base_url, shutdown) = start_test_server_with_state(state).await;
+
+    let body: serde_json::Value = reqwest::get(format!(
+        "{base_url}/api/instances/log-limits/logs?lines=100"
+    ))
+    .await?
+    .json()
+    .await?;
+    let lines = body["lines"].as_array().unwrap();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[4], "line 20");
+    assert_eq!(body["limits"]["max_lines"], 5);
+    assert_eq!(body["limits"]["tail_bytes"], 64 * 1024 * 1024);
+
+    // Zero keeps the default; the line cap has a ceiling too
+    let limits = cp::server::LogLimits::new(Some(0), Some(1_000_000_000_000));
+    assert_eq!(limits.max_lines, 10_000);
+    assert_eq!(limits.tail_bytes, 64 * 1024 * 1024);
+    assert_eq!(
+        cp::server::LogLimits::new(Some(u64::MAX), None).max_lines,
+        200_000
+    );
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate4_logs_head_seeks_by_byte_offset_and_caps_lines() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) =
+        setup_instance("log-seek", 18979, "default_temperature = 0.7\n");
+
+    let log_dir = inst_dir.join("logs");
+    fs::create_dir_all(&log_dir)?;
+    let mut content: String = (1..=5).map(|i| format!("line {i}\n")).collect();
+    content.push_str(&"x".repeat(3 * 1024 * 1024));
+    content.push_str("\nafter\n");
+    fs::write(log_dir.join("daemon.log"), content)?;
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let get = |query: &str| {
+        client
+            .get(format!("{base_url}/api/instances/log-seek/logs?{query}"))
+            .send()
+    };
+
+    let body: serde_json::Value = get("mode=head&lines=2").await?.json().await?;
+    assert_eq!(body["lines"], serde_json::json!(["line 1", "line 2"]));
+    assert_eq!(body["next_byte_offset"], 14);
+
+    // Continue from the returned offset without re-reading earlier lines
+    let body: serde_json::Value = get("mode=head&lines=2&byte_offset=14")
+        .await?
+        .json()
+        .await?;
+    assert_eq!(body["lines"], serde_json::json!(["line 3", "line 4"]));
+    assert_eq!(body["next_byte_offset"], 28);
+    // Line numbers are relative to the offset, so the total is not known
+    assert_eq!(body["total_unknown"], true);
+
+    // A huge line comes back truncated, and the next line is intact
+    let body: serde_json::Value = get("mode=head&from_line=5&lines=2").await?.json().await?;
+    let lines = body["lines"].as_array().unwrap();
+    assert_eq!(lines[0].as_str().unwrap().len(), 1024 * 1024);
+    assert_eq!(lines[1], "after");
+    assert_eq!(body["total_lines"], 7);
+
+    // byte_offset is rejected outside head mode
+    assert_eq!(get("mode=tail&byte_offset=3").await?.status(), 400);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 #[tokio::test]
 async fn gate4_logs_invalid_mode() -> Result<()> {
     let (_tmp, db_path, _id, _inst_dir) =
@@ -750,6 +1227,55 @@ async fn gate4_logs_download_rotated_only() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate4_logs_stream_follows_appends_and_rotation() -> Result<()> {
+    use futures_util::{Stream, StreamExt};
+    use std::io::Write;
+    use tokio_tungstenite::tungstenite::{Error, Message};
+
+    async fn next_line(ws: &mut (impl Stream<Item = Result<Message, Error>> + Unpin)) -> String {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), ws.next())
+            .await
+            .expect("no log line within 5s");
+        match frame {
+            Some(Ok(Message::Text(line))) => line,
+            other => panic!("unexpected frame: {other:?}"),
+        }
+    }
+
+    let (_tmp, db_path, _id, inst_dir) =
+        setup_instance("log-stream", 18978, "default_temperature = 0.7\n");
+    let log_dir = inst_dir.join("logs");
+    fs::create_dir_all(&log_dir)?;
+    let log_file = log_dir.join("daemon.log");
+    fs::write(&log_file, "line 1\nline 2\nline 3\n")?;
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let ws_url = base_url.replace("http://", "ws://");
+    let (mut ws, _) = tokio_tungstenite::connect_async(format!(
+        "{ws_url}/api/instances/log-stream/logs/stream?lines=2"
+    ))
+    .await?;
+
+    // Backlog first, then appended lines
+    assert_eq!(next_line(&mut ws).await, "line 2");
+    assert_eq!(next_line(&mut ws).await, "line 3");
+    fs::OpenOptions::new()
+        .append(true)
+        .open(&log_file)?
+        .write_all(b"line 4\n")?;
+    assert_eq!(next_line(&mut ws).await, "line 4");
+
+    // Rotation: the log is moved aside and a fresh file takes its place
+    fs::rename(&log_file, log_dir.join("daemon.log.1"))?;
+    fs::write(&log_file, "after rotation\n")?;
+    assert_eq!(next_line(&mut ws).await, "after rotation");
+
+    ws.close(None).await?;
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 5: Config secrets never appear in details response
 // ══════════════════════════════════════════════════════════════════
@@ -876,7 +1402,8 @@ fn agent_events_crud() -> Result<()> {
         })?;
     }
 
-    let (events, total) = registry.list_agent_events("inst-1", 10, 0, None, None, None)?;
+    let (events, total) =
+        registry.list_agent_events(&["inst-1"], 10, 0, None, None, None, None, None)?;
     assert_eq!(total, 3);
     assert_eq!(events.len(), 3);
     // Descending order
@@ -917,7 +1444,7 @@ fn agent_usage_aggregation() -> Result<()> {
         created_at: "2026-01-01 12:01:00".to_string(),
     })?;
 
-    let summary = registry.get_agent_usage("inst-1", None, None)?;
+    let summary = registry.get_agent_usage(&["inst-1"], None, None)?;
     assert_eq!(summary.request_count, 2);
     assert_eq!(summary.unknown_count, 1);
     assert_eq!(summary.input_tokens, Some(100));
@@ -926,3 +1453,94 @@ fn agent_usage_aggregation() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn channels_validate_reports_per_channel() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) = setup_instance(
+        "chan-validate",
+        18973,
+        "default_temperature = 0.7\n\n[channels_config]\ncli = true\n\n[channels_config.webhook]\nport = 18974\n",
+    );
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/chan-validate/channels/validate"
+        ))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["instance_name"], "chan-validate");
+    assert_eq!(body["channels"], serde_json::json!([]));
+    assert_eq!(body["all_ok"], true);
+    assert_eq!(body["skipped"], serde_json::json!(["webhook"]));
+
+    // A config the instance could not load is reported with the parse error
+    fs::write(
+        inst_dir.join("config.toml"),
+        "default_temperature = \"hot\"\n",
+    )?;
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/chan-validate/channels/validate"
+        ))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 422);
+    let body: serde_json::Value = resp.json().await?;
+    let error = body["error"].as_str().unwrap();
+    assert!(error.contains("Failed to parse config file"), "{error}");
+    assert!(error.contains("default_temperature"), "{error}");
+
+    let resp = client
+        .post(format!("{base_url}/api/instances/nope/channels/validate"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate4_logs_decode_invalid_bytes_and_chunk_straddling_lines() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) =
+        setup_instance("log-bytes", 18976, "default_temperature = 0.7\n");
+
+    // A long first line puts the two-byte 'é' across the first read boundary
+    let chunk = zeroclaw::lifecycle::log_lines::READ_CHUNK_BYTES;
+    let mut log = vec![b'x'; chunk - 1];
+    log.extend_from_slice("é end\n".as_bytes());
+    log.extend_from_slice(b"bad \xff byte\n");
+    log.extend_from_slice(b"last\n");
+    let log_dir = inst_dir.join("logs");
+    fs::create_dir_all(&log_dir)?;
+    fs::write(log_dir.join("daemon.log"), &log)?;
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    for mode in ["tail", "page", "head"] {
+        let body: serde_json::Value = client
+            .get(format!(
+                "{base_url}/api/instances/log-bytes/logs?lines=10&mode={mode}"
+            ))
+            .send()
+            .await?
+            .json()
+            .await?;
+        let lines = body["lines"].as_array().unwrap();
+        assert_eq!(lines.len(), 3, "{mode}");
+        let first = lines[0].as_str().unwrap();
+        assert_eq!(first.len(), chunk - 1 + "é end".len(), "{mode}");
+        assert!(first.ends_with("xé end"), "{mode}");
+        assert_eq!(lines[1], "bad \u{fffd} byte", "{mode}");
+        assert_eq!(lines[2], "last", "{mode}");
+    }
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
@@ -1,7 +1,6 @@
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
 use zeroclaw::db::Registry;
@@ -45,12 +44,19 @@ require_pairing = true
     (tmp, db_path, id, inst_dir)
 }
 
+/// Helper: register a second instance in an existing registry (no config on disk).
+fn setup_idle_instance(db_path: &std::path::Path, name: &str, port: u16) {
+    let registry = Registry::open(db_path).unwrap();
+    let id = uuid::Uuid::new_v4().to_string();
+    registry
+        .create_instance(&id, name, port, "/nonexistent/config.toml", None, None)
+        .unwrap();
+}
+
 /// Helper: start an in-process axum server on a random port.
 /// Returns the base URL and a shutdown sender.
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    let state = cp::server::CpState::new(db_path);
     let app = cp::server::build_router(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -87,7 +93,7 @@ fn gate1_crash_detection() -> Result<()> {
     assert!(inst_dir.join("daemon.pid").exists());
 
     // Run supervisor check
-    cp::supervisor::check_all_instances(&db_path);
+    cp::supervisor::check_all_instances(&db_path)?;
 
     // Verify: status corrected to stopped, pidfile removed
     let registry = Registry::open(&db_path)?;
@@ -189,6 +195,80 @@ async fn gate3a_health_endpoint() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate3a_health_reports_queue_backlog() -> Result<()> {
+    let (_tmp, db_path, _id, _inst_dir) = setup_instance("health-test", 18904);
+    setup_idle_instance(&db_path, "health-idle", 18905);
+
+    let registry = Registry::open(&db_path)?;
+    registry.enqueue_message(&zeroclaw::db::NewMessage {
+        id: "health-msg-1".to_string(),
+        from_instance: "health-idle".to_string(),
+        to_instance: "health-test".to_string(),
+        message_type: "task".to_string(),
+        payload: "{}".to_string(),
+        correlation_id: None,
+        idempotency_key: None,
+        idempotency_window_secs: None,
+        hop_count: 0,
+        max_retries: 5,
+        ttl_secs: 3600,
+        content_type: None,
+        priority: 0,
+    })?;
+    drop(registry);
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let resp = client.get(format!("{base_url}/api/health")).send().await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+
+    assert_eq!(body["instances"]["health-test"]["queue_depth"], 1);
+    assert_eq!(body["instances"]["health-test"]["leasable"], 1);
+    assert!(
+        body["instances"]["health-test"]["oldest_queued_age_secs"]
+            .as_i64()
+            .unwrap()
+            >= 0
+    );
+    assert_eq!(body["instances"]["health-idle"]["queue_depth"], 0);
+    assert_eq!(body["instances"]["health-idle"]["leasable"], 0);
+    assert!(body["instances"]["health-idle"]["oldest_queued_age_secs"].is_null());
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+// ── Gate 3a: Version endpoint ──
+
+#[tokio::test]
+async fn gate3a_version_endpoint() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let db_path = tmp.path().join("registry.db");
+    let _registry = Registry::open(&db_path)?;
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let resp = client.get(format!("{base_url}/api/version")).send().await?;
+    assert_eq!(resp.status(), 200);
+
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(!body["git_commit"].as_str().unwrap().is_empty());
+    let ts = body["build_timestamp"].as_str().unwrap();
+    assert!(chrono::DateTime::parse_from_rfc3339(ts).is_ok());
+    assert_eq!(
+        body["config_schema_version"],
+        zeroclaw::config::migrations::CURRENT_SCHEMA_VERSION
+    );
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 // ── Gate 3b: List instances ──
 
 #[tokio::test]
@@ -399,7 +479,7 @@ fn gate5_supervisor_skips_locked_instance() -> Result<()> {
     let _lock = lifecycle::acquire_lifecycle_lock(&inst_dir)?;
 
     // Run supervisor -- should skip this instance because lock is held
-    cp::supervisor::check_all_instances(&db_path);
+    cp::supervisor::check_all_instances(&db_path)?;
 
     // Status should remain "running" (not corrected) because supervisor skipped it
     let registry = Registry::open(&db_path)?;
@@ -443,6 +523,58 @@ async fn gate6_lifecycle_error_http_codes() -> Result<()> {
     Ok(())
 }
 
+// ── Gate 6b: Reload only signals a daemon that handles SIGHUP ──
+
+#[tokio::test]
+async fn gate6b_reload_without_sighup_handler_recommends_restart() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) = setup_instance("reload-test", 18911);
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    let resp = client
+        .post(format!("{base_url}/api/instances/ghost/reload"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    let resp = client
+        .post(format!("{base_url}/api/instances/reload-test/reload"))
+        .send()
+        .await?;
+    assert_eq!(
+        resp.status(),
+        409,
+        "Reloading a stopped instance should be 409"
+    );
+
+    // `sleep` installs no SIGHUP handler, so it must not be signalled
+    let mut child = std::process::Command::new("sleep")
+        .arg("60")
+        .env("ZEROCLAW_HOME", inst_dir.to_str().unwrap())
+        .spawn()?;
+    lifecycle::write_pid(&inst_dir, child.id())?;
+
+    let resp = client
+        .post(format!("{base_url}/api/instances/reload-test/reload"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["status"], "reload_unsupported");
+    assert_eq!(body["acknowledged"], false);
+    assert_eq!(body["restart_recommended"], true);
+    assert!(
+        child.try_wait()?.is_none(),
+        "daemon without a handler should still be running"
+    );
+
+    child.kill()?;
+    let _ = child.wait();
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 // ── Gate: DB PID column migration works ──
 
 #[test]
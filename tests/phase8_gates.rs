@@ -1,7 +1,6 @@
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::Arc;
 use tempfile::TempDir;
 use zeroclaw::cp;
 use zeroclaw::db::Registry;
@@ -33,9 +32,7 @@ fn setup_instance(name: &str, port: u16, config_toml: &str) -> (TempDir, PathBuf
 
 /// Helper: start an in-process axum server on a random port.
 async fn start_test_server(db_path: PathBuf) -> (String, tokio::sync::watch::Sender<bool>) {
-    let state = cp::server::CpState {
-        db_path: Arc::new(db_path),
-    };
+    let state = cp::server::CpState::new(db_path);
     let app = cp::server::build_router(state);
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -326,6 +323,66 @@ async fn gate1_config_get_404_missing_file() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate1_config_head_and_if_none_match() -> Result<()> {
+    let (_tmp, db_path, _id, inst_dir) = setup_instance("cfg-head", 19006, &config_with_secret());
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+    let url = format!("{base_url}/api/instances/cfg-head/config");
+
+    // GET carries the ETag header matching the body's etag
+    let resp = client.get(&url).send().await?;
+    assert_eq!(resp.status(), 200);
+    let header_etag = resp.headers()["etag"].to_str()?.to_string();
+    let body: serde_json::Value = resp.json().await?;
+    let etag = body["etag"].as_str().unwrap();
+    assert_eq!(header_etag, format!("\"{etag}\""));
+
+    // HEAD: same ETag, no body
+    let resp = client.head(&url).send().await?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["etag"].to_str()?, header_etag);
+    assert!(resp.bytes().await?.is_empty());
+
+    // Conditional GET/HEAD with the current ETag -> 304
+    let resp = client
+        .get(&url)
+        .header("If-None-Match", &header_etag)
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 304);
+    let resp = client
+        .head(&url)
+        .header("If-None-Match", &header_etag)
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 304);
+
+    // External change -> ETag moves, conditional GET returns full body
+    fs::write(
+        inst_dir.join("config.toml"),
+        "api_key = \"SECRET_TOP_LEVEL_KEY\"\ndefault_temperature = 0.5\n",
+    )?;
+    let resp = client
+        .get(&url)
+        .header("If-None-Match", &header_etag)
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    assert_ne!(resp.headers()["etag"].to_str()?, header_etag);
+
+    // Unknown instance: HEAD 404
+    let resp = client
+        .head(format!("{base_url}/api/instances/nope/config"))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 404);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 // ══════════════════════════════════════════════════════════════════
 // Gate 2: Concurrent write detected and rejected (ETag mismatch)
 // ══════════════════════════════════════════════════════════════════
@@ -487,6 +544,224 @@ async fn gate3_invalid_toml_reported_on_validate() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn gate3_config_accepts_toml_and_unwrapped_json() -> Result<()> {
+    let (_tmp, db_path, _id, _inst_dir) = setup_instance("cfg-forms", 19025, &config_with_secret());
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    // Raw TOML body
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/cfg-forms/config/validate"
+        ))
+        .header("content-type", "application/toml")
+        .body("default_temperature = 0.7\n[gateway]\nport = 19025\n")
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.json::<serde_json::Value>().await?["valid"], true);
+
+    // Unwrapped JSON config object (nulls are treated as absent)
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/cfg-forms/config/validate"
+        ))
+        .json(&serde_json::json!({
+            "default_temperature": 0.7,
+            "default_model": null,
+            "gateway": { "port": 19025, "host": "127.0.0.1" },
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.json::<serde_json::Value>().await?["valid"], true);
+
+    // Unwrapped JSON with a wrongly-typed field is rejected
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/cfg-forms/config/validate"
+        ))
+        .json(&serde_json::json!({ "default_temperature": "hot" }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+    assert_eq!(resp.json::<serde_json::Value>().await?["valid"], false);
+
+    // Diff accepts raw TOML too
+    let resp = client
+        .post(format!("{base_url}/api/instances/cfg-forms/config/diff"))
+        .header("content-type", "application/toml")
+        .body("api_key = \"***MASKED***\"\ndefault_temperature = 0.2\n")
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+
+    // PUT raw TOML with the ETag in If-Match
+    let etag = client
+        .get(format!("{base_url}/api/instances/cfg-forms/config"))
+        .send()
+        .await?
+        .json::<serde_json::Value>()
+        .await?["etag"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let resp = client
+        .put(format!("{base_url}/api/instances/cfg-forms/config"))
+        .header("content-type", "application/toml; charset=utf-8")
+        .header("if-match", format!("\"{etag}\""))
+        .body("api_key = \"***MASKED***\"\ndefault_temperature = 0.3\n")
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    let new_etag = resp.json::<serde_json::Value>().await?["etag"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    // PUT unwrapped JSON without If-Match is rejected, with it succeeds
+    let unwrapped = serde_json::json!({
+        "api_key": "***MASKED***",
+        "default_temperature": 0.4,
+    });
+    let resp = client
+        .put(format!("{base_url}/api/instances/cfg-forms/config"))
+        .json(&unwrapped)
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 400);
+    let resp = client
+        .put(format!("{base_url}/api/instances/cfg-forms/config"))
+        .header("if-match", new_etag)
+        .json(&unwrapped)
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+
+    let verify: serde_json::Value = client
+        .get(format!("{base_url}/api/instances/cfg-forms/config"))
+        .send()
+        .await?
+        .json()
+        .await?;
+    assert_eq!(verify["config_masked"]["default_temperature"], 0.4);
+    assert_eq!(verify["config_masked"]["api_key"], "***MASKED***");
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate3_put_port_change_checks_conflicts_and_syncs_registry() -> Result<()> {
+    let (_tmp, db_path, id, _inst_dir) = setup_instance("cfg-port", 19026, &config_with_secret());
+    Registry::open(&db_path)?.create_instance(
+        "other-id",
+        "cfg-port-other",
+        19027,
+        "/nonexistent/config.toml",
+        None,
+        None,
+    )?;
+
+    let (base_url, shutdown) = start_test_server(db_path.clone()).await;
+    let client = reqwest::Client::new();
+
+    let get_etag = || async {
+        client
+            .get(format!("{base_url}/api/instances/cfg-port/config"))
+            .send()
+            .await?
+            .json::<serde_json::Value>()
+            .await
+            .map(|v| v["etag"].as_str().unwrap().to_string())
+    };
+
+    // Port held by another instance -> 409, nothing written
+    let etag = get_etag().await?;
+    let resp = client
+        .put(format!("{base_url}/api/instances/cfg-port/config"))
+        .json(&serde_json::json!({
+            "config": "api_key = \"***MASKED***\"\ndefault_temperature = 0.7\n[gateway]\nport = 19027\n",
+            "etag": etag,
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 409);
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["conflicting_instance"], "cfg-port-other");
+    assert_eq!(get_etag().await?, etag, "config must be unchanged");
+    assert_eq!(
+        Registry::open(&db_path)?.get_instance(&id)?.unwrap().port,
+        19026
+    );
+
+    // Free port -> saved and registry updated
+    let resp = client
+        .put(format!("{base_url}/api/instances/cfg-port/config"))
+        .json(&serde_json::json!({
+            "config": "api_key = \"***MASKED***\"\ndefault_temperature = 0.7\n[gateway]\nport = 19028\n",
+            "etag": etag,
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        Registry::open(&db_path)?.get_instance(&id)?.unwrap().port,
+        19028
+    );
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
+#[tokio::test]
+async fn gate3_validate_warns_on_schema_version_drift() -> Result<()> {
+    let (_tmp, db_path, _id, _inst_dir) =
+        setup_instance("cfg-schema", 19024, &config_with_secret());
+
+    let (base_url, shutdown) = start_test_server(db_path).await;
+    let client = reqwest::Client::new();
+
+    // Unversioned config: still valid, but warned.
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/cfg-schema/config/validate"
+        ))
+        .json(&serde_json::json!({
+            "config": "default_temperature = 0.7\n",
+        }))
+        .send()
+        .await?;
+    assert_eq!(resp.status(), 200);
+
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["valid"], true);
+    let warnings = body["warnings"].as_array().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["field"], "schema_version");
+
+    // Current-version config has no warnings.
+    let resp = client
+        .post(format!(
+            "{base_url}/api/instances/cfg-schema/config/validate"
+        ))
+        .json(&serde_json::json!({
+            "config": format!(
+                "schema_version = {}\ndefault_temperature = 0.7\n",
+                zeroclaw::config::migrations::CURRENT_SCHEMA_VERSION
+            ),
+        }))
+        .send()
+        .await?;
+    let body: serde_json::Value = resp.json().await?;
+    assert_eq!(body["warnings"].as_array().unwrap().len(), 0);
+
+    let _ = shutdown.send(true);
+    Ok(())
+}
+
 #[tokio::test]
 async fn gate3_secrets_blocked_without_header() -> Result<()> {
     let (_tmp, db_path, _id, _inst_dir) =